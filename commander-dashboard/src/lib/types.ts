@@ -51,15 +51,33 @@ export interface SovereignControlStatus {
   policy_id: string;
 }
 
+export interface InitiativeRepositoryProgress {
+  repository: string;
+  tasks_total: number;
+  tasks_done: number;
+}
+
+export interface InitiativeStatus {
+  id: string;
+  title: string;
+  status: "READY" | "IN_PROGRESS" | "DONE" | "BLOCKED";
+  tasks_total: number;
+  tasks_done: number;
+  tasks_blocked: number;
+  progress: number;
+  repositories: InitiativeRepositoryProgress[];
+}
+
 export interface GameState {
   system_status: "OPERATIONAL" | "HALTED" | "DEGRADED" | "OUTAGE";
-  daily_budget: DailyBudget;
+  daily_budget?: DailyBudget;
   party: Agent[];
   active_quests: Quest[];
   repositories: Repository[];
   guardrail_log?: GuardrailEntry[];
   knowledge_tree: KnowledgeNode[];
   sovereign_controls?: SovereignControlStatus;
+  initiatives?: InitiativeStatus[];
 }
 
 export interface GraphNode {
@@ -29,7 +29,7 @@ except ImportError:
     from agents.synapse_proto import semantic_engine_pb2, semantic_engine_pb2_grpc, codegraph_pb2, codegraph_pb2_grpc
     from agents.synapse_proto import orchestrator_pb2, orchestrator_pb2_grpc
 
-from llm import LLMService
+from llm import LLMService, process_spend
 from product_manager import ProductManagerAgent
 from architect import ArchitectAgent
 from coder import CoderAgent
@@ -46,6 +46,62 @@ PROV = "http://www.w3.org/ns/prov#"
 RDF = "http://www.w3.org/1999/02/22-rdf-syntax-ns#"
 SKOS = "http://www.w3.org/2004/02/skos/core#"
 
+# swarmd reads the run's outcome from the last line starting with this (docs/orchestrator-result.schema.json)
+RESULT_PREFIX = "SWARM_RESULT "
+PULL_URL = re.compile(r"https://github\.com/[^/\s\"']+/[^/\s\"']+/pull/\d+")
+
+def load_handoffs(path: Optional[str]) -> List[Dict[str, Any]]:
+    """The documents earlier stages handed to this one, oldest dependency first."""
+    if not path:
+        return []
+    try:
+        with open(path) as f:
+            return json.load(f).get("handoffs", [])
+    except (OSError, ValueError, AttributeError) as e:
+        print(f"⚠️ Ignoring unreadable handoff bundle {path}: {e}")
+        return []
+
+
+def load_task_context(path: Optional[str]) -> Dict[str, Any]:
+    """What swarmd knows about the task beyond its title (SWARM_TASK_CONTEXT)."""
+    if not path:
+        return {}
+    try:
+        with open(path) as f:
+            context = json.load(f)
+        return context if isinstance(context, dict) else {}
+    except (OSError, ValueError) as e:
+        print(f"⚠️ Ignoring unreadable task context {path}: {e}")
+        return {}
+
+
+def write_handoff(path: Optional[str], task: str, result: Dict[str, Any]):
+    """Leaves a summary of this run for the next stage (SWARM_HANDOFF_OUT)."""
+    if not path:
+        return
+    stages = [
+        {"task_type": step.get("task_type"), "agent": step.get("agent"), "outcome": step.get("outcome"), "result": step.get("result")}
+        for step in result.get("history", [])
+    ]
+    document = {"task": task, "status": result.get("final_status") or result.get("status"), "stages": stages}
+    with open(path, "w") as f:
+        json.dump(document, f, indent=2, default=str)
+
+
+def result_contract(result: Dict[str, Any]) -> Dict[str, Any]:
+    """The structured result swarmd ingests: status, summary, cost and artifacts."""
+    ok = "success" in (result.get("final_status"), result.get("status"))
+    stages = len(result.get("history", []))
+    summary = result.get("error") or (f"Completed {stages} stages" if ok else "Check logs for details.")
+    pulls = dict.fromkeys(PULL_URL.findall(json.dumps(result, default=str)))
+    return {
+        "status": "success" if ok else "failure",
+        "summary": summary,
+        "cost_usd": round(process_spend(), 6),
+        "artifacts": [{"kind": "pull_request", "uri": url} for url in pulls],
+    }
+
+
 class OrchestratorAgent:
     def __init__(self):
         # Load environment variables
@@ -68,9 +124,15 @@ class OrchestratorAgent:
         self.namespace = "default"
         self.agents = {}
 
+        # Handoffs of the stages before this one (SWARM_HANDOFF_IN, written by swarmd)
+        self.handoffs = load_handoffs(os.getenv("SWARM_HANDOFF_IN"))
+        # Description, labels, earlier failures and budget of the task (written by swarmd)
+        self.task_context = load_task_context(os.getenv("SWARM_TASK_CONTEXT"))
+
         # Services
         self.bridge = TrelloBridge()
-        self.git = GitService()
+        # Checkout of its own for this run (SWARM_WORKDIR, prepared by swarmd)
+        self.git = GitService(os.getenv("SWARM_WORKDIR", "."))
         self.cloud_factory = CloudGatewayFactory()
         self.llm = LLMService()
 
@@ -557,6 +619,10 @@ class OrchestratorAgent:
 
     def run_agent_step(self, agent_name, task, task_type, stack, history):
         context = {"history": history}
+        if self.handoffs:
+            context["handoffs"] = self.handoffs
+        if self.task_context:
+            context["task"] = self.task_context
 
         # Ensure agent exists in memory
         if agent_name not in self.agents and "Coder" in agent_name:
@@ -833,6 +899,8 @@ if __name__ == "__main__":
     agent = OrchestratorAgent()
     try:
         result = agent.run(task_str, stack=args.stack)
-        print(json.dumps(result, indent=2))
+        write_handoff(os.getenv("SWARM_HANDOFF_OUT"), task_str, result)
+        print(json.dumps(result, indent=2, default=str))
+        print(RESULT_PREFIX + json.dumps(result_contract(result)))
     finally:
         agent.close()
@@ -44,6 +44,22 @@ except ImportError:
 PRICE_INPUT_PER_1K = 0.005
 PRICE_OUTPUT_PER_1K = 0.015
 
+# LLM spend of this process, for the result an orchestrator reports to swarmd
+_process_spend = 0.0
+
+
+def process_spend() -> float:
+    return _process_spend
+
+
+def spend_limit() -> Optional[float]:
+    """Spend cap swarmd sets for this run (chores), or None."""
+    value = os.getenv("SWARM_SPEND_LIMIT_USD")
+    try:
+        return float(value) if value else None
+    except ValueError:
+        return None
+
 SWARM = "http://swarm.os/ontology/"
 NIST = "http://nist.gov/caisi/"
 RDF = "http://www.w3.org/1999/02/22-rdf-syntax-ns#"
@@ -267,6 +283,8 @@ class LLMService:
 
         cost = (prompt_tokens / 1000 * PRICE_INPUT_PER_1K) + \
                (completion_tokens / 1000 * PRICE_OUTPUT_PER_1K)
+        global _process_spend
+        _process_spend += cost
 
         today = datetime.now().strftime("%Y-%m-%d")
         event_id = f"{SWARM}event/spend/{uuid.uuid4()}"
@@ -275,6 +293,9 @@ class LLMService:
             {"subject": event_id, "predicate": f"{RDF}type", "object": f"{SWARM}SpendEvent"},
             {"subject": event_id, "predicate": f"{SWARM}date", "object": f'"{today}"'},
             {"subject": event_id, "predicate": f"{SWARM}amount", "object": f'"{cost:.6f}"'}, # High precision
+            {"subject": event_id, "predicate": f"{SWARM}promptTokens", "object": f'"{prompt_tokens}"'},
+            {"subject": event_id, "predicate": f"{SWARM}completionTokens", "object": f'"{completion_tokens}"'},
+            {"subject": event_id, "predicate": f"{SWARM}model", "object": f'"{self.model}"'},
             {"subject": f"{SWARM}Finance", "predicate": f"{SWARM}dailySpent", "object": f'"{self.get_daily_spend() + cost:.6f}"'} # Cache (approx)
         ]
         self._ingest(triples)
@@ -352,6 +373,11 @@ class LLMService:
         """
         Generate a completion using the configured LLM, with Budget Enforcement.
         """
+        limit = spend_limit()
+        if limit is not None and _process_spend >= limit:
+            raise BudgetExceededException(
+                f"Run spend limit reached! Spent: ${_process_spend:.4f}, Limit: ${limit:.4f}"
+            )
         request = orchestrator_pb2.LlmCompletionRequest(
             prompt=prompt,
             model=self.model,
@@ -1,6 +1,7 @@
 import os
 import requests
 from datetime import datetime
+from urllib.parse import quote
 from typing import Dict, Any, Optional
 from lib.contracts import EventType, GatewayEvent
 
@@ -47,3 +48,33 @@ def report_tool(tool_name: str, args: Dict[str, Any], agent_id: str = "agent"):
         details={"agent_id": agent_id, "tool": tool_name, "args": args},
         severity="DEBUG"
     )
+
+def register_artifact(
+    task_id: str,
+    path: str,
+    kind: str = "file",
+    name: Optional[str] = None,
+    content_type: str = "application/octet-stream"
+) -> Optional[Dict[str, Any]]:
+    """Register a file, diff or report produced for a task, so reviewers can fetch it from the gateway.
+
+    Needs an operator token in SWARM_API_TOKEN when the gateway has API_TOKENS set.
+    """
+    headers = {"Content-Type": content_type}
+    token = os.getenv("SWARM_API_TOKEN")
+    if token:
+        headers["Authorization"] = f"Bearer {token}"
+    try:
+        with open(path, "rb") as f:
+            response = requests.post(
+                f"{GATEWAY_URL}/api/v1/tasks/{quote(task_id, safe='')}/artifacts",
+                params={"name": name or os.path.basename(path), "kind": kind},
+                headers=headers,
+                data=f,
+                timeout=30
+            )
+        response.raise_for_status()
+        return response.json()
+    except Exception as e:
+        print(f"⚠️ Could not register artifact {path}: {e}")
+        return None
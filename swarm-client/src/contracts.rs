@@ -0,0 +1,1210 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SystemStatus {
+    Operational,
+    Degraded,
+    Outage,
+    Halted,
+    Throttled,
+    Unknown,
+}
+
+impl SystemStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemStatus::Operational => "OPERATIONAL",
+            SystemStatus::Degraded => "DEGRADED",
+            SystemStatus::Outage => "OUTAGE",
+            SystemStatus::Halted => "HALTED",
+            SystemStatus::Throttled => "THROTTLED",
+            SystemStatus::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum QuestStatus {
+    Requirements,
+    Design,
+    Ready,
+    InProgress,
+    Done,
+    Blocked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CharacterProfileDocument {
+    pub selected_character_id: Option<String>,
+    pub selected_character_loadout: CharacterLoadoutSelection,
+    pub profiles: Vec<CharacterProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DailyBudget {
+    pub max: f64,
+    pub spent: f64,
+    pub unit: String,
+    /// Today's spend per agent, highest first; only in the game state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_agent: Vec<SpendShare>,
+    /// Today's spend per repository (a country on the map), highest first; only in the game state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_country: Vec<SpendShare>,
+    /// Tokens reported with today's spend.
+    #[serde(default)]
+    pub tokens: TokenUsage,
+}
+
+/// Prompt and completion tokens reported with spend, counted apart from its dollars.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TokenUsage {
+    pub prompt: u64,
+    pub completion: u64,
+    /// `DAILY_TOKEN_BUDGET` over the same days; left out when tokens are not capped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> u64 {
+        self.prompt + self.completion
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SpendReport {
+    pub amount: f64,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    /// Repository to charge, an id or IRI; the task's or else the agent's repository without it.
+    #[serde(default)]
+    pub repo_id: Option<String>,
+    /// Tokens behind the amount, for token-metered models.
+    #[serde(default)]
+    pub prompt_tokens: Option<u64>,
+    #[serde(default)]
+    pub completion_tokens: Option<u64>,
+    /// The model called.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SpendAck {
+    pub event_id: String,
+    pub daily_budget: DailyBudget,
+}
+
+/// Query of `/api/v1/budget/report`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct BudgetReportQuery {
+    /// `week` (Monday to Sunday, the default) or `month`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<String>,
+    /// Any `YYYY-MM-DD` day of the period to report; today by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+/// Spend over a week or a month, per agent, repository, model and task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BudgetReport {
+    pub period: String,
+    /// First and last day of the period, `YYYY-MM-DD` in the display timezone.
+    pub from: String,
+    pub to: String,
+    pub spent: f64,
+    /// The current daily budget times the days of the period.
+    pub budget: f64,
+    pub unit: String,
+    /// Highest spend first.
+    pub by_agent: Vec<SpendShare>,
+    /// By the repository charged; highest spend first.
+    pub by_repository: Vec<SpendShare>,
+    /// By the model called, for spend that names one; highest spend first.
+    #[serde(default)]
+    pub by_model: Vec<SpendShare>,
+    /// By the task charged, for spend that names one; highest spend first.
+    #[serde(default)]
+    pub by_task: Vec<SpendShare>,
+    #[serde(default)]
+    pub tokens: TokenUsage,
+    pub burn_rate: BurnRate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SpendShare {
+    /// Agent, repository or task IRI, or model name; left out for spend attributed to none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub spent: f64,
+    pub events: usize,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+/// Spend of the month the report falls in, projected to the month's end at the daily
+/// average so far.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BurnRate {
+    /// `YYYY-MM`.
+    pub month: String,
+    pub days_elapsed: u32,
+    pub days_in_month: u32,
+    pub spent: f64,
+    pub daily_average: f64,
+    pub projected: f64,
+    /// The current daily budget times the days of the month.
+    pub budget: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BudgetUpdateRequest {
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyStats {
+    pub hp: i32,
+    pub mana: i32,
+    pub success_rate: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyMember {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "class")]
+    pub class_name: String,
+    pub level: i32,
+    pub stats: PartyStats,
+    pub current_action: String,
+    pub location: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActiveQuest {
+    pub id: String,
+    pub title: String,
+    pub status: QuestStatus,
+}
+
+/// How much of a repository agents have explored: its directories, two levels deep, that
+/// discovery or a finished run has touched.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Exploration {
+    /// Explored share of the directories, 0 to 100.
+    pub percent: f64,
+    pub directories: usize,
+    pub explored: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RepositoryState {
+    pub id: String,
+    pub name: String,
+    pub swarm: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceHealth {
+    Healthy,
+    Degraded,
+    Halted,
+    UnderAttack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServiceState {
+    pub id: String,
+    pub name: String,
+    pub health: ServiceHealth,
+    #[serde(default = "default_service_hp")]
+    pub hp: i32,
+    #[serde(default)]
+    pub latency_ms: f64,
+    #[serde(default)]
+    pub error_rate: f64,
+}
+
+const fn default_service_hp() -> i32 {
+    100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CountryState {
+    pub id: String,
+    pub name: String,
+    pub services: Vec<ServiceState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KnowledgeNodeCost {
+    pub budget: f64,
+    pub time_hours: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KnowledgeNode {
+    pub id: String,
+    pub domain: String,
+    pub name: String,
+    pub capability: String,
+    pub level: i32,
+    pub prerequisites: Vec<String>,
+    pub cost: KnowledgeNodeCost,
+    pub unlocked: bool,
+    #[serde(default = "default_knowledge_source_type")]
+    pub source_type: String,
+    #[serde(default = "default_knowledge_source_ref")]
+    pub source_ref: String,
+    #[serde(default)]
+    pub documentation: String,
+}
+
+fn default_knowledge_source_type() -> String {
+    "seed".to_string()
+}
+
+fn default_knowledge_source_ref() -> String {
+    "seed://default".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CharacterLoadoutSelection {
+    #[serde(default)]
+    pub primary_weapon: String,
+    #[serde(default)]
+    pub secondary_weapon: String,
+    #[serde(default)]
+    pub armor: String,
+    #[serde(default)]
+    pub hit_points: i32,
+    #[serde(default)]
+    pub mana: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CharacterProfile {
+    #[serde(rename = "agent_id")]
+    pub id: String,
+    pub display_name: String,
+    #[serde(rename = "class")]
+    pub class_name: String,
+    pub level: i32,
+    pub base_success_rate: f64,
+    pub loadout: CharacterLoadoutSelection,
+    pub current_action: String,
+    pub location: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CharacterSelectionRequest {
+    pub character_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CharacterLoadoutSaveRequest {
+    pub character_id: String,
+    pub loadout: CharacterLoadoutSelection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PolicyApprovalStatus {
+    pub approved: bool,
+    pub approved_by: Option<String>,
+    pub policy_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameState {
+    pub system_status: SystemStatus,
+    pub selected_character_id: Option<String>,
+    pub selected_character_loadout: CharacterLoadoutSelection,
+    /// Admin only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_budget: Option<DailyBudget>,
+    pub party: Vec<PartyMember>,
+    pub active_quests: Vec<ActiveQuest>,
+    /// Exploration of each repository, keyed by its IRI; the map's fog of war.
+    #[serde(default)]
+    pub fog_map: std::collections::BTreeMap<String, Exploration>,
+    pub repositories: Vec<RepositoryState>,
+    pub countries: Vec<CountryState>,
+    pub knowledge_tree: Vec<KnowledgeNode>,
+    /// Operators and admins only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sovereign_controls: Option<PolicyApprovalStatus>,
+    #[serde(default)]
+    pub initiatives: Vec<InitiativeStatus>,
+    /// Present while the swarm is in maintenance mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<MaintenanceBanner>,
+    /// Achievements the agents have earned, latest first.
+    #[serde(default)]
+    pub achievements: Vec<Achievement>,
+}
+
+/// A milestone an agent reached, such as its first merged pull request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Achievement {
+    pub id: String,
+    /// `first-pr-merged`, `ten-in-a-day` or `flawless-week`.
+    pub kind: String,
+    pub title: String,
+    /// IRI of the agent that earned it.
+    pub agent: String,
+    /// RFC3339 time it was earned.
+    pub earned_at: String,
+    /// Experience it granted.
+    pub reward: i64,
+}
+
+/// The game state as the gateway computed it at `at`, for scrubbing a timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GameStateSnapshot {
+    /// RFC3339 time the snapshot was taken.
+    pub at: String,
+    pub state: GameState,
+}
+
+/// Range of `/api/v1/game-state/history`; either end left out is open.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct GameStateHistoryQuery {
+    /// Only snapshots taken at or after this RFC3339 time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Only snapshots taken at or before this RFC3339 time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// How many to return, the earliest ones in the range; 100 by default, at most 1000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphNodeData {
+    pub id: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub active: bool,
+    #[serde(default)]
+    pub triples: Vec<GraphTriple>,
+    /// Sources (`prov:wasAttributedTo`) that contributed facts about this node.
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphTriple {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphNode {
+    pub data: GraphNodeData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphEdgeData {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphEdge {
+    pub data: GraphEdgeData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphElements {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphData {
+    pub elements: GraphElements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ControlCommandType {
+    AssignMission,
+    PauseAgent,
+    ResumeAgent,
+    RefreshGraph,
+    #[serde(alias = "ISOLATE_SERVICE")]
+    Halt,
+    #[serde(alias = "RESTART_SERVICE")]
+    Resume,
+    SetAgentPriority,
+    #[serde(alias = "PATCH_SERVICE")]
+    Deploy,
+    #[serde(alias = "ROLLBACK_SERVICE")]
+    Rollback,
+    ConfigureAgentModel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LlmProfile {
+    pub provider: String,
+    pub model: String,
+    pub hierarchy: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ControlCommand {
+    pub command: ControlCommandType,
+    pub actor: String,
+    pub agent_id: Option<String>,
+    pub repo_id: Option<String>,
+    pub task: Option<String>,
+    pub mission_id: Option<String>,
+    pub priority: Option<u8>,
+    pub deployment_target: Option<String>,
+    pub rollback_to: Option<String>,
+    pub llm_profile: Option<LlmProfile>,
+    pub nist_policy_id: String,
+    pub approved_by: Option<String>,
+    #[serde(default)]
+    pub metadata: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CommandPhase {
+    Sent,
+    Accepted,
+    Rejected,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ControlCommandAck {
+    pub tracking_id: String,
+    pub status: CommandPhase,
+    pub reason: Option<String>,
+    pub final_state: Option<String>,
+    pub command: ControlCommand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditRecord {
+    pub tracking_id: String,
+    pub actor: String,
+    pub command: ControlCommandType,
+    pub phase: CommandPhase,
+    pub timestamp: String,
+    pub policy_id: String,
+    pub approved_by: Option<String>,
+    pub details: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventType {
+    MissionAssigned,
+    HardeningEvent,
+    BugSpawned,
+    ServiceDamaged,
+    ServiceRecovered,
+    JulesCloudBuilding,
+    ChaosAnomaly,
+    ControlCommand,
+    GameStateUpdate,
+    AgentThought,
+    ToolExecution,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GatewayEvent {
+    pub r#type: EventType,
+    pub message: String,
+    #[serde(default)]
+    pub details: std::collections::HashMap<String, String>,
+    pub severity: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EventBusStats {
+    pub subscribers: usize,
+    pub queued: usize,
+    pub capacity: usize,
+    pub stuck: bool,
+    pub dropped_events: u64,
+    pub lagged_receivers: u64,
+    pub resyncs: u64,
+}
+
+/// Hit/miss counters of the gateway's Synapse query pool.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueryCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped because a write touched a predicate they read.
+    pub invalidations: u64,
+    pub hit_ratio: f64,
+}
+
+/// Resource usage of the orchestrator runs of one agent class.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AgentClassResources {
+    /// `swarm:class` of the agent, or `unclassified`.
+    pub agent_class: String,
+    pub runs: usize,
+    pub cpu_seconds_total: f64,
+    pub cpu_seconds_avg: f64,
+    pub peak_memory_bytes_avg: u64,
+    pub peak_memory_bytes_max: u64,
+    pub wall_seconds_avg: f64,
+    pub wall_seconds_max: f64,
+    /// CPU seconds per wall-clock second; above 1 means the runs use several cores.
+    pub cpu_utilization: f64,
+}
+
+/// Registers a callback URL for signed event webhooks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookSubscriptionRequest {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub url: String,
+    /// HMAC key for `X-Swarm-Signature`; only returned when the subscription is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: String,
+    /// Events queued but not yet acknowledged by the receiver.
+    #[serde(default)]
+    pub pending: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    TaskStateChanged,
+    AgentStatusChanged,
+    /// Events were lost (queue overflow or restart); reload `game-state`.
+    Resync,
+}
+
+/// Body of every webhook delivery. Delivery is at-least-once: de-duplicate on `id`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookEnvelope {
+    pub id: String,
+    pub version: u32,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEventType,
+    pub occurred_at: String,
+    /// Task or agent IRI; absent for `resync`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// New task state or agent status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// Shown while maintenance mode is on: intake continues, scheduling and notifications pause.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MaintenanceBanner {
+    pub since: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banner: Option<MaintenanceBanner>,
+    /// Tasks waiting in intake, to be scheduled by priority once maintenance ends.
+    pub queued_tasks: usize,
+}
+
+/// One firing health rule, shaped like an entry of Prometheus' `/api/v1/alerts`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct HealthAlert {
+    pub labels: std::collections::HashMap<String, String>,
+    pub annotations: std::collections::HashMap<String, String>,
+    pub state: String,
+    pub active_at: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthAlertsData {
+    pub alerts: Vec<HealthAlert>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthAlertsResponse {
+    pub status: String,
+    pub data: HealthAlertsData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EventAck {
+    pub status: String,
+    pub event: GatewayEvent,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KnowledgeNodeDocumentationResponse {
+    pub node_id: String,
+    pub documentation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KnowledgeNodeIngestRequest {
+    pub node_id: String,
+    pub domain: String,
+    pub name: String,
+    pub capability: String,
+    pub level: i32,
+    pub budget_cost: f64,
+    pub time_cost_hours: i32,
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    #[serde(default)]
+    pub docs_text: String,
+    #[serde(default = "default_source_type")]
+    pub source_type: String,
+    #[serde(default = "default_source_ref")]
+    pub source_ref: String,
+}
+
+fn default_source_type() -> String {
+    "custom".to_string()
+}
+
+fn default_source_ref() -> String {
+    "game://manual".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct IngestKnowledgeNodeResponse {
+    pub status: String,
+    pub node: KnowledgeNode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProvenanceSourceSummary {
+    pub source: String,
+    pub subjects: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProvenanceSubjectsResponse {
+    pub source: String,
+    pub subjects: Vec<String>,
+}
+
+/// What purging a provenance source removed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProvenancePurge {
+    pub source: String,
+    /// Triples only this source wrote.
+    pub triples: u64,
+    /// Triples another source wrote as well, which stay.
+    pub kept: u64,
+    pub subjects: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MissionAssignment {
+    pub agent_id: String,
+    pub repo_id: String,
+    pub task: String,
+}
+
+/// A task as listed by `GET /api/v1/tasks`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskView {
+    pub id: String,
+    pub title: String,
+    pub state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+}
+
+/// Queues a new task for the agents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskRequest {
+    pub title: String,
+    /// Higher runs first; omitted means normal priority.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    /// Repository id the task belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_id: Option<String>,
+    /// Tasks (IRIs) that must be `DONE` before the agents start this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Held in `AWAITING_APPROVAL` until a human approves it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub requires_approval: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    Approve,
+    Reject,
+}
+
+/// Approves or rejects a task held in `AWAITING_APPROVAL`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApprovalDecisionRequest {
+    pub decision: ApprovalDecision,
+    /// Who decided, for the record.
+    pub decided_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApprovalDecisionAck {
+    pub task_id: String,
+    pub title: String,
+    /// `APPROVED` or `REJECTED`.
+    pub state: String,
+}
+
+/// A task with everything it waits for and everything waiting for it, from
+/// `/api/v1/tasks/:id/graph`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskGraph {
+    pub task_id: String,
+    pub nodes: Vec<TaskGraphNode>,
+    pub edges: Vec<TaskGraphEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskGraphNode {
+    pub id: String,
+    pub title: String,
+    pub state: String,
+    /// Direct dependencies not `DONE` yet; the task cannot start while any are left.
+    #[serde(default)]
+    pub waiting_on: Vec<String>,
+}
+
+/// `task` waits for `depends_on`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskGraphEdge {
+    pub task: String,
+    pub depends_on: String,
+}
+
+/// Queues a copy of a task on a cron schedule.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ScheduledTaskRequest {
+    /// Five fields (`minute hour day-of-month month day-of-week`), in the swarm's timezone.
+    pub cron: String,
+    /// Title of every copy; `{date}` becomes the date of the run.
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_id: Option<String>,
+    /// Agent classes that may take the copies (any one of them).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires_classes: Vec<String>,
+    /// Skills the agent needs (all of them).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires_skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ScheduledTaskStatus {
+    pub id: String,
+    pub cron: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_id: Option<String>,
+    #[serde(default)]
+    pub requires_classes: Vec<String>,
+    #[serde(default)]
+    pub requires_skills: Vec<String>,
+    /// RFC3339 occurrence of the last queued copy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<String>,
+    /// RFC3339; in the past while a due copy waits for the schedules worker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<String>,
+}
+
+/// Hands an escalated task back to the agents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskResetRequest {
+    /// The task's IRI.
+    pub task_id: String,
+    /// Who reset it, for the record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskResetAck {
+    pub task_id: String,
+    pub title: String,
+    pub state: String,
+}
+
+/// Cancels a task, stopping its orchestrator if it is running.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskCancelRequest {
+    /// Who cancelled it, for the record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancelled_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskCancelAck {
+    pub task_id: String,
+    pub title: String,
+    pub state: String,
+    /// Whether a running orchestrator was told to stop; its agent is freed once it has.
+    pub stopped_run: bool,
+}
+
+/// What an agent produced: a file, a diff to apply, or a report to read.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    #[default]
+    File,
+    Diff,
+    Report,
+}
+
+impl ArtifactKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactKind::File => "file",
+            ArtifactKind::Diff => "diff",
+            ArtifactKind::Report => "report",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        [ArtifactKind::File, ArtifactKind::Diff, ArtifactKind::Report].into_iter().find(|kind| kind.as_str() == raw)
+    }
+}
+
+/// An artifact registered on a task; its content is served at `url`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArtifactView {
+    /// Artifact IRI.
+    pub id: String,
+    pub task_id: String,
+    pub name: String,
+    pub kind: ArtifactKind,
+    pub content_type: String,
+    pub size_bytes: u64,
+    /// Hex SHA-256 of the content.
+    pub sha256: String,
+    /// RFC3339, in UTC.
+    pub created_at: String,
+    /// Gateway path of the content, e.g. `/api/v1/artifacts/3f2a…`.
+    pub url: String,
+}
+
+/// The system status after a halt or resume.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SystemStatusAck {
+    pub system_status: SystemStatus,
+}
+
+/// A control action from the audit log of `/api/v1/audit`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditEvent {
+    pub id: String,
+    /// `halt`, `resume`, `approve`, `reject`, `set_budget` or `assign`.
+    pub action: String,
+    /// Who did it, e.g. `telegram:alice` or `gateway:operator`.
+    pub actor: String,
+    /// Where it came from: `gateway`, `telegram`, `slack` or `discord`.
+    pub channel: String,
+    /// The task the action was about, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// RFC3339 time of the action.
+    pub at: String,
+}
+
+/// Filters of `/api/v1/audit`; each one left out matches every event.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct AuditQuery {
+    /// Only this action, e.g. `halt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Only actions by this actor, e.g. `telegram:alice`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// Only actions through this channel, e.g. `slack`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Only actions at or after this RFC3339 time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// How many to return, newest first; 100 by default, at most 1000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// One message of `/api/v1/notifications/stream` or `/api/v1/notifications`, as the chat
+/// channels receive it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NotificationEnvelope {
+    /// `trace`, `alert` or `approval`.
+    pub kind: String,
+    pub text: String,
+    /// RFC3339 time it was sent.
+    #[serde(default)]
+    pub at: String,
+    /// `debug`, `info`, `warn` or `critical`.
+    #[serde(default)]
+    pub severity: String,
+    /// The part of the swarm that raised it, e.g. `agency`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// IRI of the task it is about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+}
+
+/// Filters of `/api/v1/notifications`; each one left out matches every notification.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct NotificationQuery {
+    /// Only notifications sent at or after this RFC3339 time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Only this severity and above, e.g. `warn` for alerts and critical ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// How many to return, the latest ones, oldest first; 50 by default, at most 1000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Running,
+    /// Stopped through the admin API.
+    Stopped,
+    /// The worker's task ended by itself (it returned or panicked).
+    Exited,
+}
+
+/// A supervised background worker (`/api/v1/admin/workers`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WorkerStatus {
+    pub name: String,
+    pub state: WorkerState,
+    /// RFC3339 time of the last start or stop.
+    pub since: String,
+    /// Times the worker was started, the first start included.
+    pub starts: u32,
+    /// Current pause between passes, for running workers that adapt it (the agency).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_interval_secs: Option<u64>,
+}
+
+/// RFC 7807 body of every gateway error response.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProblemDetails {
+    pub r#type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    pub code: String,
+    pub correlation_id: String,
+    /// One entry per invalid field of a rejected request body or query.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// Why one field of a request was rejected. `field` is the JSON path, e.g. `steps[1].key`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// One step every repository of an initiative goes through. `depends_on` names other
+/// steps of the same blueprint by `key`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BlueprintStep {
+    pub key: String,
+    pub title: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InitiativeRequest {
+    pub title: String,
+    /// Repository ids, as in `GameState.repositories[].id`.
+    pub repositories: Vec<String>,
+    /// Defaults to design → implement → verify when empty.
+    #[serde(default)]
+    pub steps: Vec<BlueprintStep>,
+    /// Repository that must finish first, e.g. the shared library the others adopt.
+    #[serde(default)]
+    pub lead_repository: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InitiativeRepositoryProgress {
+    pub repository: String,
+    pub tasks_total: usize,
+    pub tasks_done: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InitiativeStatus {
+    pub id: String,
+    pub title: String,
+    pub status: QuestStatus,
+    pub tasks_total: usize,
+    pub tasks_done: usize,
+    /// Tasks that failed for good (dead-lettered or rejected).
+    pub tasks_blocked: usize,
+    /// Share of tasks done, from 0.0 to 1.0.
+    pub progress: f64,
+    pub repositories: Vec<InitiativeRepositoryProgress>,
+}
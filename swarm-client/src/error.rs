@@ -0,0 +1,31 @@
+use crate::contracts::ProblemDetails;
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("invalid gateway URL: {0}")]
+    InvalidBaseUrl(String),
+
+    /// The request never got a usable response (connection refused, timeout, bad JSON).
+    #[error("gateway request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The gateway answered with an RFC 7807 problem.
+    #[error("{} ({}): {}", .0.title, .0.code, .0.detail)]
+    Api(Box<ProblemDetails>),
+
+    /// A non-success response that was not a problem document, e.g. from a proxy.
+    #[error("gateway returned HTTP {status}: {body}")]
+    UnexpectedStatus { status: u16, body: String },
+}
+
+impl ClientError {
+    /// The gateway's stable error code (`NOT_FOUND`, `FORBIDDEN`, ...), if it sent one.
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api(problem) => Some(&problem.code),
+            _ => None,
+        }
+    }
+}
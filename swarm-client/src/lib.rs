@@ -0,0 +1,381 @@
+//! Typed async client for the swarmd gateway (`/api/v1`).
+//!
+//! The request and response types in [`contracts`] are the ones the gateway itself
+//! serializes, so a client built against this crate cannot drift from the server. With the
+//! `openapi` feature they also describe themselves as OpenAPI schemas.
+//!
+//! ```no_run
+//! # async fn run() -> swarm_client::Result<()> {
+//! let client = swarm_client::SwarmClient::new("http://127.0.0.1:18789")?.with_token("s3cret");
+//! let state = client.game_state().await?;
+//! println!("{:?}", state.system_status);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod contracts;
+mod error;
+
+pub use error::{ClientError, Result};
+
+use reqwest::{Method, Url};
+use serde::{de::DeserializeOwned, Serialize};
+
+use contracts::{
+    AgentClassResources, ApprovalDecisionAck, ArtifactKind, ArtifactView, ApprovalDecisionRequest, AuditEvent, AuditQuery, AuditRecord, BudgetReport, BudgetReportQuery, BudgetUpdateRequest, CharacterLoadoutSaveRequest, CharacterSelectionRequest, ControlCommand,
+    ControlCommandAck, DailyBudget, EventAck, EventBusStats, GameState, GameStateHistoryQuery, GameStateSnapshot, GatewayEvent, GraphData,
+    HealthAlertsResponse, IngestKnowledgeNodeResponse, QueryCacheStats, InitiativeRequest, InitiativeStatus,
+    KnowledgeNodeDocumentationResponse, KnowledgeNodeIngestRequest, MaintenanceRequest, MaintenanceStatus, MissionAssignment, NotificationEnvelope, NotificationQuery, ProblemDetails, ProvenancePurge,
+    ProvenanceSourceSummary, ProvenanceSubjectsResponse, ScheduledTaskRequest, ScheduledTaskStatus, SpendAck, SpendReport, SystemStatusAck, TaskCancelAck, TaskCancelRequest, TaskGraph, TaskRequest, TaskResetAck, TaskResetRequest, TaskView, WebhookSubscription,
+    WebhookSubscriptionRequest, WorkerStatus,
+};
+
+/// Where `swarmd` listens by default.
+pub const DEFAULT_BASE_URL: &str = "http://127.0.0.1:18789";
+
+#[derive(Debug, Clone)]
+pub struct SwarmClient {
+    base_url: Url,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl SwarmClient {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let base_url = Url::parse(base_url).map_err(|e| ClientError::InvalidBaseUrl(e.to_string()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidBaseUrl(base_url.to_string()));
+        }
+        Ok(Self { base_url, token: None, http: reqwest::Client::new() })
+    }
+
+    /// Sends `Authorization: Bearer <token>`; the token's role decides what the gateway returns.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Reuses an existing `reqwest` client, e.g. one with custom timeouts.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    // --- Game state & graph ---
+
+    pub async fn game_state(&self) -> Result<GameState> {
+        self.get(&["game-state"]).await
+    }
+
+    /// Stored game-state snapshots in the range of `query`, oldest first.
+    pub async fn game_state_history(&self, query: &GameStateHistoryQuery) -> Result<Vec<GameStateSnapshot>> {
+        self.execute(self.request(Method::GET, &["game-state", "history"]).query(query)).await
+    }
+
+    pub async fn graph_nodes(&self) -> Result<GraphData> {
+        self.get(&["graph-nodes"]).await
+    }
+
+    // --- Agents ---
+
+    /// The raw character profile document.
+    pub async fn characters(&self) -> Result<serde_json::Value> {
+        self.get(&["characters"]).await
+    }
+
+    pub async fn select_character(&self, character_id: &str) -> Result<serde_json::Value> {
+        let body = CharacterSelectionRequest { character_id: character_id.to_string() };
+        self.send(Method::POST, &["characters", "select"], &body).await
+    }
+
+    pub async fn save_character_loadout(&self, request: &CharacterLoadoutSaveRequest) -> Result<serde_json::Value> {
+        self.send(Method::POST, &["characters", "loadout"], request).await
+    }
+
+    // --- Tasks & knowledge ---
+
+    /// Tasks not yet finished, optionally only those in `state` (e.g. `PROCESSING`).
+    pub async fn tasks(&self, state: Option<&str>) -> Result<Vec<TaskView>> {
+        let req = self.request(Method::GET, &["tasks"]);
+        self.execute(match state {
+            Some(state) => req.query(&[("state", state)]),
+            None => req,
+        })
+        .await
+    }
+
+    /// Queues a task for the agents, as a Trello card or GitHub issue would.
+    pub async fn create_task(&self, request: &TaskRequest) -> Result<TaskView> {
+        self.send(Method::POST, &["tasks"], request).await
+    }
+
+    /// What `task_id` (a task IRI or gateway task id) waits for and what waits for it.
+    pub async fn task_graph(&self, task_id: &str) -> Result<TaskGraph> {
+        self.get(&["tasks", task_id, "graph"]).await
+    }
+
+    /// Approves or rejects a task held in `AWAITING_APPROVAL`.
+    pub async fn decide_task(&self, task_id: &str, request: &ApprovalDecisionRequest) -> Result<ApprovalDecisionAck> {
+        self.send(Method::POST, &["tasks", task_id, "approval"], request).await
+    }
+
+    pub async fn assign_mission(&self, mission: &MissionAssignment) -> Result<ControlCommandAck> {
+        self.send(Method::POST, &["mission", "assign"], mission).await
+    }
+
+    /// Lets the agents try an escalated task again.
+    /// Cancels a task; a running one has its orchestrator stopped.
+    pub async fn cancel_task(&self, task_id: &str, request: &TaskCancelRequest) -> Result<TaskCancelAck> {
+        self.send(Method::POST, &["tasks", task_id, "cancel"], request).await
+    }
+
+    pub async fn reset_task(&self, request: &TaskResetRequest) -> Result<TaskResetAck> {
+        self.send(Method::POST, &["tasks", "reset"], request).await
+    }
+
+    /// Registers `content` as an artifact of `task_id`, for reviewers to fetch later.
+    pub async fn upload_artifact(&self, task_id: &str, name: &str, kind: ArtifactKind, content_type: &str, content: Vec<u8>) -> Result<ArtifactView> {
+        let req = self
+            .request(Method::POST, &["tasks", task_id, "artifacts"])
+            .query(&[("name", name), ("kind", kind.as_str())])
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(content);
+        self.execute(req).await
+    }
+
+    /// The artifacts registered on `task_id`, oldest first.
+    pub async fn task_artifacts(&self, task_id: &str) -> Result<Vec<ArtifactView>> {
+        self.get(&["tasks", task_id, "artifacts"]).await
+    }
+
+    /// The content of an artifact, by IRI or the id after `http://swarm.os/artifact/`.
+    pub async fn artifact_content(&self, artifact_id: &str) -> Result<Vec<u8>> {
+        let res = self.checked(self.request(Method::GET, &["artifacts", artifact_id])).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    pub async fn ingest_knowledge_node(&self, request: &KnowledgeNodeIngestRequest) -> Result<IngestKnowledgeNodeResponse> {
+        self.send(Method::POST, &["knowledge-tree", "nodes"], request).await
+    }
+
+    pub async fn knowledge_node_docs(&self, node_id: &str) -> Result<KnowledgeNodeDocumentationResponse> {
+        self.get(&["knowledge-tree", node_id, "docs"]).await
+    }
+
+    pub async fn initiatives(&self) -> Result<Vec<InitiativeStatus>> {
+        self.get(&["initiatives"]).await
+    }
+
+    /// Plans an initiative across repositories and queues its tasks.
+    pub async fn create_initiative(&self, request: &InitiativeRequest) -> Result<InitiativeStatus> {
+        self.send(Method::POST, &["initiatives"], request).await
+    }
+
+    /// Scheduled tasks that are still active.
+    pub async fn schedules(&self) -> Result<Vec<ScheduledTaskStatus>> {
+        self.get(&["schedules"]).await
+    }
+
+    /// Queues a copy of a task on every occurrence of a cron expression.
+    pub async fn create_schedule(&self, request: &ScheduledTaskRequest) -> Result<ScheduledTaskStatus> {
+        self.send(Method::POST, &["schedules"], request).await
+    }
+
+    pub async fn retire_schedule(&self, id: &str) -> Result<ScheduledTaskStatus> {
+        self.execute(self.request(Method::DELETE, &["schedules", id])).await
+    }
+
+    // --- Control & admin ---
+
+    pub async fn send_command(&self, command: &ControlCommand) -> Result<ControlCommandAck> {
+        self.send(Method::POST, &["control", "commands"], command).await
+    }
+
+    /// Emergency stop: no new tasks are scheduled until [`resume`](Self::resume).
+    pub async fn halt(&self) -> Result<SystemStatusAck> {
+        self.send(Method::POST, &["control", "halt"], &serde_json::json!({})).await
+    }
+
+    pub async fn resume(&self) -> Result<SystemStatusAck> {
+        self.send(Method::POST, &["control", "resume"], &serde_json::json!({})).await
+    }
+
+    pub async fn audit_log(&self) -> Result<Vec<AuditRecord>> {
+        self.get(&["control", "audit"]).await
+    }
+
+    /// Halts, resumes, approvals, budget changes and manual assignments, newest first.
+    pub async fn audit_events(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        self.execute(self.request(Method::GET, &["audit"]).query(query)).await
+    }
+
+    pub async fn set_daily_budget(&self, max_usd: f64) -> Result<DailyBudget> {
+        self.send(Method::PUT, &["budget"], &BudgetUpdateRequest { max: max_usd }).await
+    }
+
+    pub async fn report_spend(&self, spend: &SpendReport) -> Result<SpendAck> {
+        self.send(Method::POST, &["spend"], spend).await
+    }
+
+    /// Spend over a week or a month per agent and repository, with the month's burn rate.
+    pub async fn budget_report(&self, query: &BudgetReportQuery) -> Result<BudgetReport> {
+        self.execute(self.request(Method::GET, &["budget", "report"]).query(query)).await
+    }
+
+    pub async fn maintenance(&self) -> Result<MaintenanceStatus> {
+        self.get(&["maintenance"]).await
+    }
+
+    /// Enters or leaves maintenance mode.
+    pub async fn set_maintenance(&self, request: &MaintenanceRequest) -> Result<MaintenanceStatus> {
+        self.send(Method::PUT, &["maintenance"], request).await
+    }
+
+    pub async fn workers(&self) -> Result<Vec<WorkerStatus>> {
+        self.get(&["admin", "workers"]).await
+    }
+
+    /// Aborts a background worker, e.g. a poller stuck in a loop; the daemon keeps running.
+    pub async fn stop_worker(&self, name: &str) -> Result<WorkerStatus> {
+        self.send(Method::POST, &["admin", "workers", name, "stop"], &serde_json::json!({})).await
+    }
+
+    pub async fn start_worker(&self, name: &str) -> Result<WorkerStatus> {
+        self.send(Method::POST, &["admin", "workers", name, "start"], &serde_json::json!({})).await
+    }
+
+    pub async fn restart_worker(&self, name: &str) -> Result<WorkerStatus> {
+        self.send(Method::POST, &["admin", "workers", name, "restart"], &serde_json::json!({})).await
+    }
+
+    pub async fn provenance_summary(&self) -> Result<Vec<ProvenanceSourceSummary>> {
+        self.get(&["provenance"]).await
+    }
+
+    pub async fn provenance_subjects(&self, source: &str) -> Result<ProvenanceSubjectsResponse> {
+        self.get(&["provenance", source]).await
+    }
+
+    /// Removes what a source wrote; triples another source also wrote stay.
+    pub async fn purge_provenance(&self, source: &str) -> Result<ProvenancePurge> {
+        self.execute(self.request(Method::DELETE, &["provenance", source])).await
+    }
+
+    // --- Events & health ---
+
+    pub async fn publish_event(&self, event: &GatewayEvent) -> Result<EventAck> {
+        self.send(Method::POST, &["events"], event).await
+    }
+
+    pub async fn event_bus_stats(&self) -> Result<EventBusStats> {
+        self.get(&["events", "bus"]).await
+    }
+
+    pub async fn query_cache_stats(&self) -> Result<QueryCacheStats> {
+        self.get(&["synapse", "cache"]).await
+    }
+
+    pub async fn resource_stats(&self) -> Result<Vec<AgentClassResources>> {
+        self.get(&["metrics", "resources"]).await
+    }
+
+    /// Registers a callback URL; the returned subscription carries the signing secret.
+    pub async fn subscribe_webhook(&self, url: &str) -> Result<WebhookSubscription> {
+        self.send(Method::POST, &["webhooks", "subscriptions"], &WebhookSubscriptionRequest { url: url.to_string() }).await
+    }
+
+    pub async fn webhook_subscriptions(&self) -> Result<Vec<WebhookSubscription>> {
+        self.get(&["webhooks", "subscriptions"]).await
+    }
+
+    pub async fn unsubscribe_webhook(&self, id: &str) -> Result<WebhookSubscription> {
+        self.execute(self.request(Method::DELETE, &["webhooks", "subscriptions", id])).await
+    }
+
+    pub async fn health_alerts(&self) -> Result<HealthAlertsResponse> {
+        self.get(&["alerts"]).await
+    }
+
+    /// The latest notifications `query` matches, oldest first.
+    pub async fn notifications(&self, query: &NotificationQuery) -> Result<Vec<NotificationEnvelope>> {
+        self.execute(self.request(Method::GET, &["notifications"]).query(query)).await
+    }
+
+    /// WebSocket URL of the notification stream; each message is a
+    /// [`NotificationEnvelope`](contracts::NotificationEnvelope). Send the token as a bearer
+    /// header when connecting.
+    pub fn notifications_url(&self) -> Url {
+        let mut url = self.endpoint(&["notifications", "stream"]);
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        let _ = url.set_scheme(scheme);
+        url
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// `/api/v1/<segments>`, with each segment percent-encoded so ids such as IRIs stay intact.
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(["api", "v1"]).extend(segments);
+        }
+        url
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        self.execute(self.request(Method::GET, segments)).await
+    }
+
+    async fn send<B: Serialize + ?Sized, T: DeserializeOwned>(&self, method: Method, segments: &[&str], body: &B) -> Result<T> {
+        self.execute(self.request(method, segments).json(body)).await
+    }
+
+    fn request(&self, method: Method, segments: &[&str]) -> reqwest::RequestBuilder {
+        let req = self.http.request(method, self.endpoint(segments));
+        match &self.token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
+
+    async fn execute<T: DeserializeOwned>(&self, req: reqwest::RequestBuilder) -> Result<T> {
+        Ok(self.checked(req).await?.json().await?)
+    }
+
+    /// Sends `req`, turning an unsuccessful answer into an error.
+    async fn checked(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let res = req.send().await?;
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let body = res.text().await.unwrap_or_default();
+        Err(match serde_json::from_str::<ProblemDetails>(&body) {
+            Ok(problem) => ClientError::Api(Box::new(problem)),
+            Err(_) => ClientError::UnexpectedStatus { status: status.as_u16(), body },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_are_rooted_at_api_v1_and_encode_ids() {
+        let client = SwarmClient::new("http://gateway:18789/").unwrap();
+        assert_eq!(client.endpoint(&["events", "bus"]).as_str(), "http://gateway:18789/api/v1/events/bus");
+        assert_eq!(
+            client.endpoint(&["knowledge-tree", "http://swarm.os/k/rust", "docs"]).as_str(),
+            "http://gateway:18789/api/v1/knowledge-tree/http:%2F%2Fswarm.os%2Fk%2Frust/docs"
+        );
+
+        let prefixed = SwarmClient::new("https://example.com/swarm").unwrap();
+        assert_eq!(prefixed.endpoint(&["game-state"]).as_str(), "https://example.com/swarm/api/v1/game-state");
+        assert!(SwarmClient::new("not a url").is_err());
+        assert_eq!(prefixed.notifications_url().as_str(), "wss://example.com/swarm/api/v1/notifications/stream");
+    }
+}
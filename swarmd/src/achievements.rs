@@ -0,0 +1,254 @@
+//! Achievements: milestones an agent reaches, evaluated over what the swarm already records.
+//! A pull request merged for a task the agent held earns `first-pr-merged`, ten runs finished
+//! on one day (display timezone) earn `ten-in-a-day`, and a week of runs without a failure, by
+//! an agent that has been at work for at least that long, earns `flawless-week`. Each is earned
+//! once per agent, kept as a `swarm:Achievement` and pays its reward as experience.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+use crate::provenance::Source;
+use crate::resource_usage::{COMPLETED_AT, OUTCOME, RUN, RUN_AGENT};
+use crate::server::contracts::Achievement;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, ASSIGNED_TO, UNASSIGNED_FROM};
+use crate::workers::github::pulls::{HAS_PULL_REQUEST, PULL_REQUEST_STATE};
+
+pub const ACHIEVEMENT: &str = "http://swarm.os/ontology/Achievement";
+pub const KIND: &str = "http://swarm.os/ontology/achievementKind";
+pub const EARNED_AT: &str = "http://swarm.os/ontology/earnedAt";
+/// Integer; the experience the achievement granted.
+pub const REWARD: &str = "http://swarm.os/ontology/reward";
+const TITLE: &str = "http://swarm.os/ontology/title";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Finished runs on one day that earn [`Kind::TenInADay`].
+const RUNS_IN_A_DAY: usize = 10;
+const FLAWLESS_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    FirstPrMerged,
+    TenInADay,
+    FlawlessWeek,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 3] = [Kind::FirstPrMerged, Kind::TenInADay, Kind::FlawlessWeek];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::FirstPrMerged => "first-pr-merged",
+            Kind::TenInADay => "ten-in-a-day",
+            Kind::FlawlessWeek => "flawless-week",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Kind> {
+        Kind::ALL.into_iter().find(|k| k.as_str() == raw)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Kind::FirstPrMerged => "First PR merged",
+            Kind::TenInADay => "10 tasks in a day",
+            Kind::FlawlessWeek => "Zero failures for a week",
+        }
+    }
+
+    /// Experience granted on earning it.
+    pub fn reward(&self) -> i64 {
+        match self {
+            Kind::FirstPrMerged => 50,
+            Kind::TenInADay => 100,
+            Kind::FlawlessWeek => 150,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentRow {
+    agent: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunRow {
+    agent: String,
+    outcome: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    at: Option<DateTime<Utc>>,
+}
+
+/// What the rules look at: agents with a merged pull request, and every run with its outcome.
+#[derive(Debug, Default)]
+pub struct Facts {
+    merged: HashSet<String>,
+    /// Agent, whether the run finished its task, and when it ended.
+    runs: Vec<(String, bool, DateTime<Utc>)>,
+}
+
+pub async fn facts(synapse: &Synapse) -> anyhow::Result<Facts> {
+    let merged = format!(
+        r#"
+        SELECT DISTINCT ?agent
+        WHERE {{
+            ?task <{HAS_PULL_REQUEST}> ?pr ;
+                  <{ASSIGNED_TO}> ?agent .
+            ?pr <{PULL_REQUEST_STATE}> ?state .
+            FILTER(STR(?state) = "MERGED")
+            FILTER NOT EXISTS {{ ?task <{UNASSIGNED_FROM}> ?agent }}
+        }}
+    "#
+    );
+    let runs = format!(
+        r#"
+        SELECT ?agent ?outcome ?at
+        WHERE {{
+            ?run a <{RUN}> ;
+                 <{RUN_AGENT}> ?agent ;
+                 <{OUTCOME}> ?outcome ;
+                 <{COMPLETED_AT}> ?at .
+        }}
+    "#
+    );
+    let (merged, runs) = tokio::try_join!(synapse.query(&merged), synapse.query(&runs))?;
+    let merged: Vec<AgentRow> = sparql::rows(&merged)?;
+    let runs: Vec<RunRow> = sparql::rows(&runs)?;
+    Ok(Facts {
+        merged: merged.into_iter().map(|r| r.agent).collect(),
+        runs: runs
+            .into_iter()
+            .filter_map(|r| Some((r.agent, crate::party::succeeded(&r.outcome)?, r.at?)))
+            .collect(),
+    })
+}
+
+/// Every achievement `facts` show, as (agent, kind), whether or not it was earned before.
+pub fn earned(facts: &Facts, now: DateTime<Utc>) -> BTreeSet<(String, Kind)> {
+    let mut earned: BTreeSet<(String, Kind)> = facts.merged.iter().map(|agent| (agent.clone(), Kind::FirstPrMerged)).collect();
+
+    let mut finished_per_day: BTreeMap<(&str, chrono::NaiveDate), usize> = BTreeMap::new();
+    for (agent, ok, at) in &facts.runs {
+        if *ok {
+            *finished_per_day.entry((agent.as_str(), crate::clock::date_of(*at))).or_default() += 1;
+        }
+    }
+    earned.extend(
+        finished_per_day
+            .into_iter()
+            .filter(|(_, finished)| *finished >= RUNS_IN_A_DAY)
+            .map(|((agent, _), _)| (agent.to_string(), Kind::TenInADay)),
+    );
+
+    let week_ago = now - Duration::days(FLAWLESS_DAYS);
+    let mut weeks: BTreeMap<&str, (DateTime<Utc>, usize, usize)> = BTreeMap::new();
+    for (agent, ok, at) in &facts.runs {
+        let (first, finished, failed) = weeks.entry(agent.as_str()).or_insert((*at, 0, 0));
+        *first = (*first).min(*at);
+        if *at > week_ago && *at <= now {
+            if *ok { *finished += 1 } else { *failed += 1 }
+        }
+    }
+    earned.extend(
+        weeks
+            .into_iter()
+            .filter(|(_, (first, finished, failed))| *first <= week_ago && *finished > 0 && *failed == 0)
+            .map(|(agent, _)| (agent.to_string(), Kind::FlawlessWeek)),
+    );
+    earned
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AchievementRow {
+    achievement: String,
+    kind: String,
+    agent: String,
+    earned_at: String,
+    title: Option<String>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    reward: Option<i64>,
+}
+
+/// Every achievement earned so far, latest first.
+pub async fn all(synapse: &Synapse) -> anyhow::Result<Vec<Achievement>> {
+    let query = format!(
+        r#"
+        SELECT ?achievement ?kind ?agent ?earnedAt ?title ?reward
+        WHERE {{
+            ?achievement a <{ACHIEVEMENT}> ;
+                         <{KIND}> ?kind ;
+                         <{RUN_AGENT}> ?agent ;
+                         <{EARNED_AT}> ?earnedAt .
+            OPTIONAL {{ ?achievement <{TITLE}> ?title }}
+            OPTIONAL {{ ?achievement <{REWARD}> ?reward }}
+        }}
+    "#
+    );
+    let rows: Vec<AchievementRow> = sparql::rows(&synapse.query_cached(&query, crate::query_cache::DEFAULT_TTL).await?)?;
+    let mut achievements: Vec<Achievement> = rows
+        .into_iter()
+        .map(|r| Achievement {
+            title: r.title.or_else(|| Kind::parse(&r.kind).map(|k| k.title().to_string())).unwrap_or_else(|| r.kind.clone()),
+            id: r.achievement,
+            kind: r.kind,
+            agent: r.agent,
+            earned_at: r.earned_at,
+            reward: r.reward.unwrap_or_default(),
+        })
+        .collect();
+    achievements.sort_by(|a, b| b.earned_at.cmp(&a.earned_at).then_with(|| a.id.cmp(&b.id)));
+    achievements.dedup_by(|a, b| a.id == b.id);
+    Ok(achievements)
+}
+
+/// Records that `agent` earned `kind` at `now`.
+pub async fn record(synapse: &Synapse, agent: &str, kind: Kind, now: DateTime<Utc>) -> anyhow::Result<String> {
+    let id = format!("http://swarm.os/achievement/{}", uuid::Uuid::new_v4());
+    let kind_lit = sparql::literal(kind.as_str());
+    let title_lit = sparql::literal(kind.title());
+    let earned_lit = tasks::datetime_literal(&now);
+    let reward_lit = tasks::integer_literal(kind.reward());
+    let triples = vec![
+        (id.as_str(), RDF_TYPE, ACHIEVEMENT),
+        (id.as_str(), KIND, kind_lit.as_str()),
+        (id.as_str(), TITLE, title_lit.as_str()),
+        (id.as_str(), RUN_AGENT, agent),
+        (id.as_str(), EARNED_AT, earned_lit.as_str()),
+        (id.as_str(), REWARD, reward_lit.as_str()),
+    ];
+    synapse.ingest_from(Source::Scheduler, triples).await?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn milestones_are_earned_from_merges_and_run_outcomes() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let at = |days_ago: i64, minute: i64| now - Duration::days(days_ago) + Duration::minutes(minute);
+        let mut facts = Facts { merged: HashSet::from(["coder".to_string()]), runs: Vec::new() };
+        // Ten on one day, all of them within the week.
+        facts.runs.extend((0..10).map(|m| ("busy".to_string(), true, at(2, m))));
+        // At work for two weeks; one failure, but before the last seven days.
+        facts.runs.push(("steady".to_string(), true, at(14, 0)));
+        facts.runs.push(("steady".to_string(), false, at(8, 0)));
+        facts.runs.push(("steady".to_string(), true, at(1, 0)));
+        // Failed within the week.
+        facts.runs.push(("shaky".to_string(), true, at(10, 0)));
+        facts.runs.push(("shaky".to_string(), false, at(3, 0)));
+        // Not at work for a week yet.
+        facts.runs.push(("new".to_string(), true, at(1, 0)));
+
+        let earned = earned(&facts, now);
+        let expected = [("busy", Kind::TenInADay), ("coder", Kind::FirstPrMerged), ("steady", Kind::FlawlessWeek)];
+        assert_eq!(earned, expected.into_iter().map(|(a, k)| (a.to_string(), k)).collect());
+        assert_eq!(Kind::ALL.map(|k| Kind::parse(k.as_str())), Kind::ALL.map(Some));
+    }
+}
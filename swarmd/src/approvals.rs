@@ -0,0 +1,223 @@
+//! Human approval gates. A task flagged `swarm:requiresApproval "true"` (or, as intake used to
+//! write it, `swarm:riskLevel "HIGH"`) is held by the agency in `AWAITING_APPROVAL` with an
+//! approval request to the humans, and no agent starts it until someone records an `APPROVED`
+//! decision, from the Telegram buttons or `POST /api/v1/tasks/{id}/approval`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::audit;
+use crate::provenance::Source;
+use crate::server::contracts::ApprovalDecision;
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks::{self, TaskState, INTERNAL_STATE};
+
+pub const RISK_LEVEL: &str = "http://swarm.os/ontology/riskLevel";
+pub const REQUIRES_APPROVAL: &str = "http://swarm.os/ontology/requiresApproval";
+pub const APPROVAL_STATE: &str = "http://swarm.os/ontology/approvalState";
+/// Why the task needs approval, shown in the request.
+pub const APPROVAL_REASON: &str = "http://swarm.os/ontology/approvalReason";
+const DECIDED_BY: &str = "http://swarm.os/ontology/approvalDecidedBy";
+const DECIDED_AT: &str = "http://swarm.os/ontology/approvalDecidedAt";
+
+/// Trello labels that mark a card as high risk (compared case-insensitively).
+pub const HIGH_RISK_LABELS: &[&str] = &["high-risk", "security", "production"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Approve,
+    Reject,
+}
+
+impl Decision {
+    pub fn state(&self) -> &'static str {
+        match self {
+            Decision::Approve => "APPROVED",
+            Decision::Reject => "REJECTED",
+        }
+    }
+
+    pub fn audit_action(&self) -> audit::Action {
+        match self {
+            Decision::Approve => audit::Action::Approve,
+            Decision::Reject => audit::Action::Reject,
+        }
+    }
+}
+
+impl From<ApprovalDecision> for Decision {
+    fn from(decision: ApprovalDecision) -> Self {
+        match decision {
+            ApprovalDecision::Approve => Decision::Approve,
+            ApprovalDecision::Reject => Decision::Reject,
+        }
+    }
+}
+
+/// A task waiting for a human decision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub task_id: String,
+    pub title: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecisionOutcome {
+    Recorded { title: String },
+    UnknownTask,
+    /// The task does not need approval, or was already approved or rejected.
+    NotAwaiting,
+}
+
+pub fn is_high_risk_label(label: &str) -> bool {
+    HIGH_RISK_LABELS.iter().any(|l| l.eq_ignore_ascii_case(label.trim()))
+}
+
+/// Triples that make a task wait for approval; `reason` is a quoted literal.
+pub fn flag_triples<'a>(task_id: &'a str, reason: &'a str) -> Vec<(&'a str, &'static str, &'a str)> {
+    vec![
+        (task_id, RISK_LEVEL, "\"HIGH\""),
+        (task_id, REQUIRES_APPROVAL, "\"true\""),
+        (task_id, APPROVAL_REASON, reason),
+    ]
+}
+
+/// Tasks in the intake state that need approval but have neither been held nor decided.
+pub async fn unheld(synapse: &Synapse) -> anyhow::Result<Vec<ApprovalRequest>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?task ?title ?reason
+        WHERE {
+            ?task a swarm:Task ;
+                  swarm:internalState "REQUIREMENTS" ;
+                  swarm:title ?title .
+            { ?task swarm:requiresApproval "true" } UNION { ?task swarm:riskLevel "HIGH" }
+            FILTER NOT EXISTS { ?task swarm:internalState "AWAITING_APPROVAL" }
+            FILTER NOT EXISTS { ?task swarm:approvalState "APPROVED" }
+            FILTER NOT EXISTS { ?task swarm:approvalState "REJECTED" }
+            OPTIONAL { ?task swarm:approvalReason ?reason }
+        }
+    "#;
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(query).await?).unwrap_or_default();
+    let mut requests: Vec<ApprovalRequest> = Vec::new();
+    for row in &rows {
+        let (Some(task_id), Some(title)) = (field(row, "task"), field(row, "title")) else { continue };
+        if requests.iter().any(|r| r.task_id == task_id) {
+            continue;
+        }
+        let reason = field(row, "reason").unwrap_or_else(|| "marked as requiring approval".to_string());
+        requests.push(ApprovalRequest { task_id, title, reason });
+    }
+    Ok(requests)
+}
+
+/// Parks a task in `AWAITING_APPROVAL` until a decision is recorded.
+pub async fn hold(synapse: &Synapse, task_id: &str) -> anyhow::Result<()> {
+    let state = TaskState::AwaitingApproval.literal();
+    synapse
+        .ingest_from(Source::Agency, vec![
+            (task_id, INTERNAL_STATE, state.as_str()),
+            (task_id, APPROVAL_STATE, "\"PENDING\""),
+        ])
+        .await
+}
+
+/// Records that `decided_by` approved or rejected `task_id`, if it is still waiting for a
+/// decision. The task moves to `APPROVED`, which lets the agency start it, or `REJECTED`,
+/// which takes it out of the queue for good.
+pub async fn decide(
+    synapse: &Synapse,
+    source: Source,
+    task_id: &str,
+    decision: Decision,
+    decided_by: &str,
+) -> anyhow::Result<DecisionOutcome> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?title ?required ?risk ?decided
+        WHERE {
+            $task a swarm:Task ;
+                  swarm:title ?title .
+            OPTIONAL { $task swarm:requiresApproval ?required }
+            OPTIONAL { $task swarm:riskLevel ?risk }
+            OPTIONAL { $task swarm:approvalState ?decided }
+        }
+    "#,
+    )
+    .iri("task", task_id);
+    let rows: Vec<Value> = serde_json::from_str(&synapse.select(&query).await?).unwrap_or_default();
+    let Some((title, awaiting)) = awaiting_decision(&rows) else {
+        return Ok(DecisionOutcome::UnknownTask);
+    };
+    if !awaiting {
+        return Ok(DecisionOutcome::NotAwaiting);
+    }
+
+    let state = format!("\"{}\"", decision.state());
+    let by = sparql::literal(decided_by);
+    let at = tasks::datetime_literal(&chrono::Utc::now());
+    let audit = audit::triples(decision.audit_action(), decided_by, Some(task_id), Some(&title));
+    let mut triples = vec![
+        (task_id, APPROVAL_STATE, state.as_str()),
+        (task_id, DECIDED_BY, by.as_str()),
+        (task_id, DECIDED_AT, at.as_str()),
+        (task_id, INTERNAL_STATE, state.as_str()),
+    ];
+    triples.extend(audit.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())));
+    synapse.ingest_from(source, triples).await?;
+    Ok(DecisionOutcome::Recorded { title })
+}
+
+/// The task's title, and whether it needs approval that nobody has given or refused yet.
+fn awaiting_decision(rows: &[Value]) -> Option<(String, bool)> {
+    let title = rows.iter().find_map(|row| field(row, "title"))?;
+    let required = rows.iter().any(|row| {
+        field(row, "required").as_deref() == Some("true") || field(row, "risk").as_deref() == Some("HIGH")
+    });
+    let decided = rows
+        .iter()
+        .filter_map(|row| field(row, "decided"))
+        .any(|state| state == Decision::Approve.state() || state == Decision::Reject.state());
+    Some((title, required && !decided))
+}
+
+fn field(row: &Value, name: &str) -> Option<String> {
+    row.get(format!("?{name}").as_str())
+        .or_else(|| row.get(name))
+        .and_then(Value::as_str)
+        .map(|v| tasks::literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn high_risk_labels_match_case_insensitively() {
+        assert!(is_high_risk_label("High-Risk"));
+        assert!(is_high_risk_label(" security "));
+        assert!(!is_high_risk_label("frontend"));
+    }
+
+    #[test]
+    fn only_undecided_gated_tasks_take_a_decision() {
+        let row = |extra: Value| {
+            let mut row = json!({"title": "\"Rotate keys\""});
+            row.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            row
+        };
+        let flagged = [row(json!({"required": "\"true\"", "decided": "\"PENDING\""}))];
+        assert_eq!(awaiting_decision(&flagged), Some(("Rotate keys".to_string(), true)));
+        let legacy = [row(json!({"?risk": "\"HIGH\""}))];
+        assert_eq!(awaiting_decision(&legacy).map(|(_, awaiting)| awaiting), Some(true));
+        let decided = [row(json!({"required": "\"true\"", "decided": "\"PENDING\""})), row(json!({"decided": "\"APPROVED\""}))];
+        assert_eq!(awaiting_decision(&decided).map(|(_, awaiting)| awaiting), Some(false));
+        assert_eq!(awaiting_decision(&[row(json!({}))]).map(|(_, awaiting)| awaiting), Some(false));
+        assert_eq!(awaiting_decision(&[]), None);
+    }
+}
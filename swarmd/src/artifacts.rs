@@ -0,0 +1,275 @@
+//! Files, diffs and reports agents produce, kept for review. A run registers one with
+//! `POST /api/v1/tasks/{id}/artifacts`; the content goes to the artifact store and the graph
+//! gets a `swarm:Artifact` with its name, kind, type, size and digest, linked from the task with
+//! `swarm:hasArtifact`. The gateway serves the content back at `/api/v1/artifacts/{id}`.
+//!
+//! The store is a directory under `SWARM_ARTIFACTS_DIR/registry`, or an S3 bucket when
+//! `SWARM_ARTIFACT_STORE` is `s3://bucket/prefix`; see [`s3`].
+
+mod s3;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::AppConfig;
+use crate::provenance::Source;
+use crate::server::contracts::{ArtifactKind, ArtifactView};
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks;
+
+pub const HAS_ARTIFACT: &str = "http://swarm.os/ontology/hasArtifact";
+const ARTIFACT: &str = "http://swarm.os/ontology/Artifact";
+const NAME: &str = "http://swarm.os/ontology/name";
+const KIND: &str = "http://swarm.os/ontology/artifactKind";
+const CONTENT_TYPE: &str = "http://swarm.os/ontology/contentType";
+const SIZE_BYTES: &str = "http://swarm.os/ontology/sizeBytes";
+const SHA256: &str = "http://swarm.os/ontology/sha256";
+const STORAGE_KEY: &str = "http://swarm.os/ontology/storageKey";
+const CREATED_AT: &str = "http://swarm.os/ontology/createdAt";
+
+/// Where the content of artifacts is kept.
+#[derive(Debug, Clone)]
+enum Blobs {
+    Local(PathBuf),
+    S3(s3::Bucket),
+}
+
+impl Blobs {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        match self {
+            Blobs::Local(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&path, bytes).await.with_context(|| format!("writing {}", path.display()))
+            }
+            Blobs::S3(bucket) => bucket.put(key, content_type, bytes).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Blobs::Local(dir) => {
+                let path = dir.join(key);
+                tokio::fs::read(&path).await.with_context(|| format!("reading {}", path.display()))
+            }
+            Blobs::S3(bucket) => bucket.get(key).await,
+        }
+    }
+}
+
+/// An artifact as an agent uploads it.
+#[derive(Debug, Clone)]
+pub struct Upload {
+    pub name: String,
+    pub kind: ArtifactKind,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct ArtifactRow {
+    artifact: String,
+    task: String,
+    name: String,
+    kind: String,
+    #[serde(rename = "type")]
+    content_type: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    size: Option<u64>,
+    sha: String,
+    key: String,
+    at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Artifacts {
+    blobs: Blobs,
+    /// Larger uploads are refused.
+    pub max_bytes: usize,
+}
+
+impl Artifacts {
+    /// A registry storing under `dir`.
+    pub fn local(dir: &Path, max_bytes: usize) -> Self {
+        Self { blobs: Blobs::Local(dir.to_path_buf()), max_bytes }
+    }
+
+    pub fn from_config(cfg: &AppConfig) -> anyhow::Result<Self> {
+        let max_bytes = cfg.artifact_max_mb.saturating_mul(1024 * 1024);
+        match cfg.artifact_store.as_deref() {
+            None => Ok(Self::local(&Path::new(&cfg.artifacts_dir).join("registry"), max_bytes)),
+            Some(url) => Ok(Self { blobs: Blobs::S3(s3::Bucket::from_config(url, cfg)?), max_bytes }),
+        }
+    }
+
+    /// Where artifacts are kept, for the startup log.
+    pub fn location(&self) -> String {
+        match &self.blobs {
+            Blobs::Local(dir) => dir.display().to_string(),
+            Blobs::S3(bucket) => bucket.to_string(),
+        }
+    }
+
+    /// Stores `upload` and links it to `task_id`, which the caller has checked exists.
+    pub async fn register(&self, synapse: &Synapse, task_id: &str, upload: Upload) -> anyhow::Result<ArtifactView> {
+        if upload.bytes.len() > self.max_bytes {
+            bail!("{} is {} bytes; artifacts are limited to {}", upload.name, upload.bytes.len(), self.max_bytes);
+        }
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let task_key: String = task_id.rsplit('/').next().unwrap_or_default().chars().filter(|c| c.is_ascii_alphanumeric() || "-_".contains(*c)).collect();
+        let base = upload.name.rsplit(['/', '\\']).next().unwrap_or_default();
+        let file_name: String = base.chars().map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' }).collect();
+        let key = format!("{}/{}-{}", if task_key.is_empty() { "task" } else { &task_key }, id, file_name.trim_start_matches('.'));
+        let sha256 = hex::encode(Sha256::digest(&upload.bytes));
+        let size_bytes = upload.bytes.len() as u64;
+        let created_at = chrono::Utc::now();
+
+        self.blobs.put(&key, &upload.content_type, upload.bytes).await?;
+
+        let artifact = format!("http://swarm.os/artifact/{}", id);
+        let (name, kind, content_type) = (sparql::literal(&upload.name), sparql::literal(upload.kind.as_str()), sparql::literal(&upload.content_type));
+        let (size, digest, stored, at) = (
+            tasks::integer_literal(size_bytes as i64),
+            sparql::literal(&sha256),
+            sparql::literal(&key),
+            tasks::datetime_literal(&created_at),
+        );
+        let triples = vec![
+            (artifact.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", ARTIFACT),
+            (artifact.as_str(), NAME, name.as_str()),
+            (artifact.as_str(), KIND, kind.as_str()),
+            (artifact.as_str(), CONTENT_TYPE, content_type.as_str()),
+            (artifact.as_str(), SIZE_BYTES, size.as_str()),
+            (artifact.as_str(), SHA256, digest.as_str()),
+            (artifact.as_str(), STORAGE_KEY, stored.as_str()),
+            (artifact.as_str(), CREATED_AT, at.as_str()),
+            (task_id, HAS_ARTIFACT, artifact.as_str()),
+        ];
+        synapse.ingest_from(Source::Gateway, triples).await?;
+
+        Ok(ArtifactView {
+            url: url(&artifact),
+            id: artifact,
+            task_id: task_id.to_string(),
+            name: upload.name,
+            kind: upload.kind,
+            content_type: upload.content_type,
+            size_bytes,
+            sha256,
+            created_at: created_at.to_rfc3339(),
+        })
+    }
+
+    /// The artifacts of `task_id`, oldest first.
+    pub async fn list(&self, synapse: &Synapse, task_id: &str) -> anyhow::Result<Vec<ArtifactView>> {
+        let rows = find(synapse, "task", task_id).await?;
+        Ok(rows.into_iter().map(|(view, _)| view).collect())
+    }
+
+    /// An artifact and its content; `None` when there is no artifact `artifact_id`.
+    pub async fn open(&self, synapse: &Synapse, artifact_id: &str) -> anyhow::Result<Option<(ArtifactView, Vec<u8>)>> {
+        let rows = find(synapse, "artifact", artifact_id).await?;
+        let Some((view, key)) = rows.into_iter().next() else { return Ok(None) };
+        let bytes = self.blobs.get(&key).await?;
+        Ok(Some((view, bytes)))
+    }
+}
+
+/// The IRI of an artifact given its IRI or the id after `http://swarm.os/artifact/`.
+pub fn artifact_iri(id: &str) -> String {
+    if id.contains("://") { id.to_string() } else { format!("http://swarm.os/artifact/{}", id) }
+}
+
+fn url(artifact: &str) -> String {
+    format!("/api/v1/artifacts/{}", artifact.rsplit('/').next().unwrap_or_default())
+}
+
+/// The artifacts whose `?task` or `?artifact` (`variable`) is `iri`, with their storage keys.
+async fn find(synapse: &Synapse, variable: &str, iri: &str) -> anyhow::Result<Vec<(ArtifactView, String)>> {
+    let query = Query::new(format!(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?artifact ?task ?name ?kind ?type ?size ?sha ?key ?at WHERE {{
+            VALUES ?{variable} {{ $bound }}
+            ?task swarm:hasArtifact ?artifact .
+            ?artifact swarm:name ?name ;
+                      swarm:artifactKind ?kind ;
+                      swarm:contentType ?type ;
+                      swarm:sizeBytes ?size ;
+                      swarm:sha256 ?sha ;
+                      swarm:storageKey ?key ;
+                      swarm:createdAt ?at .
+        }}
+    "#
+    ))
+    .iri("bound", iri);
+    let mut rows: Vec<ArtifactRow> = synapse.select_as(&query).await?;
+    rows.sort_by(|a, b| a.at.cmp(&b.at));
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let kind = ArtifactKind::parse(&row.kind)?;
+            let view = ArtifactView {
+                url: url(&row.artifact),
+                id: row.artifact,
+                task_id: row.task,
+                name: row.name,
+                kind,
+                content_type: row.content_type,
+                size_bytes: row.size.unwrap_or_default(),
+                sha256: row.sha,
+                created_at: row.at,
+            };
+            Some((view, row.key))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn registered_content_is_linked_to_its_task_and_read_back() {
+        let dir = std::env::temp_dir().join(format!("swarm-artifacts-{}", uuid::Uuid::new_v4()));
+        let artifacts = Artifacts::local(&dir, 16);
+        let (store, synapse) = MemoryStore::shared();
+        let upload = |name: &str, bytes: &[u8]| Upload {
+            name: name.into(),
+            kind: ArtifactKind::Diff,
+            content_type: "text/x-diff".into(),
+            bytes: bytes.to_vec(),
+        };
+
+        let view = artifacts.register(&synapse, "http://swarm.os/task/1", upload("../fix.diff", b"+ok")).await.unwrap();
+        assert!(view.url.starts_with("/api/v1/artifacts/") && view.id.ends_with(view.url.rsplit('/').next().unwrap()));
+        assert_eq!(view.sha256, hex::encode(Sha256::digest(b"+ok")));
+        assert!(store.triples().contains(&("http://swarm.os/task/1".into(), HAS_ARTIFACT.into(), view.id.clone())));
+        let key = store.triples().into_iter().find(|(_, p, _)| p == STORAGE_KEY).unwrap().2;
+        assert!(key.starts_with("\"1/") && key.ends_with("-fix.diff\""), "{key}");
+
+        store.answer(
+            "swarm:storageKey",
+            json!([{
+                "?artifact": format!("<{}>", view.id), "?task": "<http://swarm.os/task/1>", "?name": "\"../fix.diff\"",
+                "?kind": "\"diff\"", "?type": "\"text/x-diff\"", "?size": "\"3\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+                "?sha": format!("\"{}\"", view.sha256), "?key": key, "?at": format!("\"{}\"", view.created_at),
+            }]),
+        );
+        assert_eq!(artifacts.list(&synapse, "http://swarm.os/task/1").await.unwrap(), std::slice::from_ref(&view));
+        let (opened, bytes) = artifacts.open(&synapse, &view.id).await.unwrap().unwrap();
+        assert_eq!((opened, bytes), (view, b"+ok".to_vec()));
+
+        let e = artifacts.register(&synapse, "http://swarm.os/task/1", upload("big.diff", &[b'+'; 17])).await.unwrap_err();
+        assert!(e.to_string().contains("limited to 16"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+//! Audit trail of control actions: halts and resumes, approval decisions, budget changes and
+//! manual assignments, with who made them, from which channel and when. Each action is a
+//! `swarm:AuditEvent`, written in the same ingest as the change it records and attributed to
+//! the source it came through, and `GET /api/v1/audit` lists them newest first.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::provenance::Source;
+use crate::server::contracts::AuditEvent;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks;
+
+pub const AUDIT_EVENT: &str = "http://swarm.os/ontology/AuditEvent";
+const ACTION: &str = "http://swarm.os/ontology/auditAction";
+const ACTOR: &str = "http://swarm.os/ontology/auditActor";
+const TARGET: &str = "http://swarm.os/ontology/auditTarget";
+const DETAILS: &str = "http://swarm.os/ontology/auditDetails";
+const AT: &str = "http://swarm.os/ontology/auditAt";
+const EVENT_PREFIX: &str = "http://swarm.os/audit/";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Events listed when the caller does not say how many.
+pub const DEFAULT_LIMIT: usize = 100;
+pub const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Halt,
+    Resume,
+    Throttle,
+    Approve,
+    Reject,
+    SetBudget,
+    Assign,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [Action::Halt, Action::Resume, Action::Throttle, Action::Approve, Action::Reject, Action::SetBudget, Action::Assign];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Halt => "halt",
+            Action::Resume => "resume",
+            Action::Throttle => "throttle",
+            Action::Approve => "approve",
+            Action::Reject => "reject",
+            Action::SetBudget => "set_budget",
+            Action::Assign => "assign",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str().eq_ignore_ascii_case(raw.trim()))
+    }
+}
+
+/// Which events to list; every field left out matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub action: Option<Action>,
+    pub actor: Option<String>,
+    pub channel: Option<Source>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: usize,
+}
+
+/// Triples recording that `actor` did `action`, to ingest with the action itself through
+/// the channel it came from. `target` is the IRI acted on, if any.
+pub fn triples(action: Action, actor: &str, target: Option<&str>, details: Option<&str>) -> Vec<(String, String, String)> {
+    let event = format!("{EVENT_PREFIX}{}", uuid::Uuid::new_v4());
+    let mut triples = vec![
+        (event.clone(), RDF_TYPE.to_string(), AUDIT_EVENT.to_string()),
+        (event.clone(), ACTION.to_string(), format!("\"{}\"", action.as_str())),
+        (event.clone(), ACTOR.to_string(), sparql::literal(actor)),
+        (event.clone(), AT.to_string(), tasks::datetime_literal(&Utc::now())),
+    ];
+    if let Some(target) = target {
+        triples.push((event.clone(), TARGET.to_string(), target.to_string()));
+    }
+    if let Some(details) = details {
+        triples.push((event, DETAILS.to_string(), sparql::literal(details)));
+    }
+    triples
+}
+
+#[derive(Debug, Deserialize)]
+struct EventRow {
+    event: String,
+    action: String,
+    actor: String,
+    channel: String,
+    target: Option<String>,
+    details: Option<String>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    at: Option<DateTime<Utc>>,
+}
+
+/// The events `filter` matches, newest first.
+pub async fn list(synapse: &Synapse, filter: &Filter) -> anyhow::Result<Vec<AuditEvent>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        PREFIX prov: <http://www.w3.org/ns/prov#>
+        SELECT ?event ?action ?actor ?channel ?target ?details ?at
+        WHERE {
+            ?event a swarm:AuditEvent ;
+                   swarm:auditAction ?action ;
+                   swarm:auditActor ?actor ;
+                   swarm:auditAt ?at ;
+                   prov:wasAttributedTo ?channel .
+            OPTIONAL { ?event swarm:auditTarget ?target }
+            OPTIONAL { ?event swarm:auditDetails ?details }
+        }
+    "#;
+    Ok(matching(synapse.query_as(query).await?, filter))
+}
+
+fn matching(rows: Vec<EventRow>, filter: &Filter) -> Vec<AuditEvent> {
+    let mut events: Vec<(DateTime<Utc>, AuditEvent)> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let at = row.at?;
+            let channel = Source::parse(&row.channel);
+            let wanted = filter.action.is_none_or(|a| a.as_str() == row.action)
+                && filter.actor.as_deref().is_none_or(|actor| actor == row.actor)
+                && filter.channel.is_none_or(|c| Some(c) == channel)
+                && filter.since.is_none_or(|since| at >= since);
+            wanted.then(|| {
+                let event = AuditEvent {
+                    id: row.event,
+                    action: row.action,
+                    actor: row.actor,
+                    channel: channel.map_or(row.channel, |c| c.as_str().to_string()),
+                    target: row.target,
+                    details: row.details,
+                    at: at.to_rfc3339(),
+                };
+                (at, event)
+            })
+        })
+        .collect();
+    events.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    let mut seen = HashSet::new();
+    events.retain(|(_, event)| seen.insert(event.id.clone()));
+    events.into_iter().take(filter.limit).map(|(_, event)| event).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn recorded_triples_are_well_formed() {
+        let triples = triples(Action::Approve, "telegram:\"ana\"", Some("http://swarm.os/task/1"), Some("rotate keys"));
+        assert_eq!(triples.len(), 6);
+        for (s, p, o) in &triples {
+            sparql::check_triple(s, p, o).unwrap();
+        }
+        assert!(triples.iter().all(|(s, _, _)| s.starts_with(EVENT_PREFIX)));
+    }
+
+    #[test]
+    fn events_are_filtered_newest_first() {
+        let row = |id: &str, action: &str, actor: &str, channel: &str, at: &str| {
+            json!({
+                "?event": format!("<{EVENT_PREFIX}{id}>"),
+                "?action": format!("\"{action}\""),
+                "?actor": format!("\"{actor}\""),
+                "?channel": format!("<http://swarm.os/source/{channel}>"),
+                "?at": format!("\"{at}\"^^<http://www.w3.org/2001/XMLSchema#dateTime>"),
+            })
+        };
+        let results = json!([
+            row("1", "halt", "telegram:ana", "telegram", "2026-03-01T09:00:00Z"),
+            row("2", "resume", "gateway:admin", "gateway", "2026-03-01T10:00:00Z"),
+            row("3", "halt", "slack:bo", "slack", "2026-03-02T08:00:00Z"),
+        ])
+        .to_string();
+        let rows = || sparql::rows::<EventRow>(&results).unwrap();
+
+        let all = matching(rows(), &Filter { limit: 10, ..Filter::default() });
+        let ids: Vec<&str> = all.iter().map(|e| e.id.trim_start_matches(EVENT_PREFIX)).collect();
+        assert_eq!(ids, ["3", "2", "1"]);
+        assert_eq!(all[0].channel, "slack");
+
+        let halts = matching(rows(), &Filter { action: Some(Action::Halt), limit: 1, ..Filter::default() });
+        assert_eq!(halts.len(), 1);
+        assert_eq!(halts[0].actor, "slack:bo");
+
+        let since = "2026-03-01T09:30:00Z".parse().ok();
+        let recent = matching(rows(), &Filter { channel: Some(Source::Gateway), since, limit: 10, ..Filter::default() });
+        assert_eq!(recent.iter().map(|e| e.actor.as_str()).collect::<Vec<_>>(), ["gateway:admin"]);
+    }
+}
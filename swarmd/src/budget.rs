@@ -0,0 +1,665 @@
+//! Daily spend accounting shared by the game-state endpoint and the agency budget gate.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::audit;
+use crate::clock;
+use crate::config::AppConfig;
+use crate::initiatives::REPOSITORY;
+use crate::provenance::Source;
+use crate::server::contracts::{BudgetReport, BurnRate, SpendShare, TokenUsage};
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks;
+
+const SYSTEM_CONTROL: &str = "http://nist.gov/caisi/SystemControl";
+const DAILY_BUDGET: &str = "http://swarm.os/ontology/dailyBudget";
+const BUDGET_CHANGE: &str = "http://swarm.os/ontology/budgetChange";
+const GENERATED_AT: &str = "http://www.w3.org/ns/prov#generatedAtTime";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub const SPEND_EVENT: &str = "http://swarm.os/ontology/SpendEvent";
+pub const PROMPT_TOKENS: &str = "http://swarm.os/ontology/promptTokens";
+pub const COMPLETION_TOKENS: &str = "http://swarm.os/ontology/completionTokens";
+pub const MODEL: &str = "http://swarm.os/ontology/model";
+
+/// A cost reported by an agent or orchestrator.
+#[derive(Debug, Clone)]
+pub struct Spend {
+    pub amount_usd: f64,
+    pub agent_id: Option<String>,
+    pub task_id: Option<String>,
+    /// Repository charged; when unset, the task's or else the agent's home repository.
+    pub repository: Option<String>,
+    /// Tokens behind the amount, kept apart for token-metered contracts.
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    pub model: Option<String>,
+}
+
+/// Writes `spend` as a `swarm:SpendEvent` dated today and returns the event IRI.
+pub async fn record_spend(synapse: &Synapse, spend: &Spend) -> anyhow::Result<String> {
+    let mut spend = spend.clone();
+    if spend.repository.is_none() {
+        // Attribution is best effort; the spend itself must not be lost over it.
+        spend.repository = repository_of(synapse, &spend).await.unwrap_or_else(|e| {
+            warn!("Could not find the repository to charge a spend to: {}", e);
+            None
+        });
+    }
+    let (event_id, properties) = spend_event(&spend, chrono::Utc::now());
+    synapse
+        .ingest_from(Source::Gateway, properties.iter().map(|(p, o)| (event_id.as_str(), *p, o.as_str())).collect())
+        .await?;
+    Ok(event_id)
+}
+
+/// A `swarm:SpendEvent` that happened `at`, dated in the display timezone: its IRI and its
+/// predicate/object pairs.
+pub fn spend_event(spend: &Spend, at: DateTime<Utc>) -> (String, Vec<(&'static str, String)>) {
+    let event_id = format!("http://swarm.os/ontology/event/spend/{}", uuid::Uuid::new_v4());
+    let date = format!("\"{}\"", clock::date_of(at).format("%Y-%m-%d"));
+    // Same plain-literal shape the Python LLM client writes, so both sum together.
+    let amount = format!("\"{:.6}\"", spend.amount_usd);
+    let timestamp = tasks::datetime_literal(&at);
+
+    let mut properties = vec![
+        (RDF_TYPE, SPEND_EVENT.to_string()),
+        ("http://swarm.os/ontology/date", date),
+        ("http://swarm.os/ontology/amount", amount),
+        ("http://swarm.os/ontology/currency", "\"USD\"".to_string()),
+        ("http://www.w3.org/ns/prov#generatedAtTime", timestamp),
+    ];
+    if let Some(agent) = spend.agent_id.as_deref() {
+        properties.push(("http://swarm.os/ontology/agent", expand_iri(agent, "http://swarm.os/agent/")));
+    }
+    if let Some(task) = spend.task_id.as_deref() {
+        properties.push(("http://swarm.os/ontology/task", expand_iri(task, "http://swarm.os/task/")));
+    }
+    if let Some(repository) = spend.repository.as_deref() {
+        properties.push((REPOSITORY, expand_iri(repository, "http://swarm.os/repository/")));
+    }
+    for (predicate, tokens) in [(PROMPT_TOKENS, spend.prompt_tokens), (COMPLETION_TOKENS, spend.completion_tokens)] {
+        if let Some(tokens) = tokens {
+            properties.push((predicate, tasks::integer_literal(i64::try_from(tokens).unwrap_or(i64::MAX))));
+        }
+    }
+    if let Some(model) = spend.model.as_deref() {
+        properties.push((MODEL, sparql::literal(model)));
+    }
+    (event_id, properties)
+}
+
+/// The repository of the task `spend` is for, or else the one its agent belongs to.
+async fn repository_of(synapse: &Synapse, spend: &Spend) -> anyhow::Result<Option<String>> {
+    #[derive(Deserialize)]
+    struct RepoRow {
+        repo: String,
+    }
+    let lookups = [
+        (spend.task_id.as_deref().map(|task| expand_iri(task, "http://swarm.os/task/")), "$subject swarm:repository ?repo"),
+        (spend.agent_id.as_deref().map(|agent| expand_iri(agent, "http://swarm.os/agent/")), "?repo swarm:hasPopulation $subject"),
+    ];
+    for (subject, pattern) in lookups {
+        let Some(subject) = subject else { continue };
+        let query = Query::new(format!("PREFIX swarm: <http://swarm.os/ontology/>\nSELECT ?repo WHERE {{ {} }} LIMIT 1", pattern))
+            .iri("subject", &subject);
+        if let Some(row) = synapse.select_as::<RepoRow>(&query).await?.into_iter().next() {
+            return Ok(Some(row.repo));
+        }
+    }
+    Ok(None)
+}
+
+/// Bare ids are minted under `base`; full IRIs are kept as they are.
+fn expand_iri(id: &str, base: &str) -> String {
+    if id.starts_with("http://") || id.starts_with("https://") {
+        id.to_string()
+    } else {
+        format!("{base}{id}")
+    }
+}
+
+/// The daily budget in USD. Starts from `DAILY_BUDGET_USD`, is overridden by the value
+/// persisted on `SystemControl`, and can be changed at runtime through the gateway. Teams on
+/// token-metered contracts also cap tokens a day with `DAILY_TOKEN_BUDGET`.
+#[derive(Debug, Clone)]
+pub struct BudgetLimit {
+    usd: Arc<RwLock<f64>>,
+    /// Prompt plus completion tokens a day; 0 leaves tokens uncapped.
+    tokens: Arc<AtomicU64>,
+}
+
+impl BudgetLimit {
+    pub fn new(max_usd: f64) -> Self {
+        Self { usd: Arc::new(RwLock::new(max_usd)), tokens: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Restores the newest persisted budget, falling back to `default_usd`. Each change is its
+    /// own timestamped node; a limit stored on `SystemControl` itself, as before changes were
+    /// timestamped, only counts when there is no change.
+    pub async fn load(synapse: &Synapse, default_usd: f64) -> Self {
+        let query = format!(
+            r#"SELECT ?budget ?at WHERE {{
+                {{ <{SYSTEM_CONTROL}> <{BUDGET_CHANGE}> ?change . ?change <{DAILY_BUDGET}> ?budget ; <{GENERATED_AT}> ?at }}
+                UNION {{ <{SYSTEM_CONTROL}> <{DAILY_BUDGET}> ?budget }}
+            }}"#
+        );
+        #[derive(Deserialize)]
+        struct LimitRow {
+            #[serde(default, deserialize_with = "sparql::parsed")]
+            budget: Option<f64>,
+            #[serde(default, deserialize_with = "sparql::parsed")]
+            at: Option<DateTime<Utc>>,
+        }
+        let persisted = match synapse.query(&query).await {
+            Ok(res_json) => sparql::rows::<LimitRow>(&res_json)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|row| row.budget.is_some())
+                .max_by_key(|row| row.at)
+                .and_then(|row| row.budget),
+            Err(_) => None,
+        };
+
+        if let Some(max) = persisted {
+            info!("💰 Daily budget restored from Synapse: ${:.2}", max);
+        }
+        Self::new(persisted.unwrap_or(default_usd))
+    }
+
+    pub fn get(&self) -> f64 {
+        *self.usd.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The daily token cap, if tokens are capped.
+    pub fn max_tokens(&self) -> Option<u64> {
+        Some(self.tokens.load(Ordering::Relaxed)).filter(|max| *max > 0)
+    }
+
+    /// Caps tokens a day at `max`, or lifts the cap with 0. Follows config, never persisted.
+    pub fn set_max_tokens(&self, max: u64) {
+        self.tokens.store(max, Ordering::Relaxed);
+    }
+
+    /// Whether `spent` USD or `tokens` has reached its daily cap.
+    pub fn exhausted(&self, spent: f64, tokens: &TokenUsage) -> bool {
+        spent >= self.get() || self.max_tokens().is_some_and(|max| tokens.total() >= max)
+    }
+
+    /// Persists a new limit, audited as `actor`'s budget change.
+    pub async fn set(&self, synapse: &Synapse, max_usd: f64, actor: &str) -> anyhow::Result<()> {
+        let change = format!("http://swarm.os/budget/change/{}", uuid::Uuid::new_v4());
+        let limit = tasks::decimal_literal(max_usd);
+        let at = tasks::datetime_literal(&Utc::now());
+        let audit = audit::triples(audit::Action::SetBudget, actor, None, Some(&format!("${:.2} a day", max_usd)));
+        let mut triples = vec![
+            (SYSTEM_CONTROL, BUDGET_CHANGE, change.as_str()),
+            (change.as_str(), DAILY_BUDGET, limit.as_str()),
+            (change.as_str(), GENERATED_AT, at.as_str()),
+        ];
+        triples.extend(audit.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())));
+        synapse.ingest_from(Source::Gateway, triples).await?;
+        *self.usd.write().unwrap_or_else(|e| e.into_inner()) = max_usd;
+        Ok(())
+    }
+
+    /// Applies `DAILY_BUDGET_USD` changes from config reloads, persisted like a gateway update.
+    /// Reloads that leave it alone keep whatever the gateway last set. `DAILY_TOKEN_BUDGET`
+    /// simply follows every reload.
+    pub async fn follow_config(self, synapse: Synapse, mut config: watch::Receiver<AppConfig>) {
+        let mut configured = config.borrow_and_update().daily_budget_usd;
+        while config.changed().await.is_ok() {
+            let (max_usd, max_tokens) = {
+                let cfg = config.borrow_and_update();
+                (cfg.daily_budget_usd, cfg.daily_token_budget)
+            };
+            self.set_max_tokens(max_tokens);
+            if max_usd == configured {
+                continue;
+            }
+            configured = max_usd;
+            match self.set(&synapse, max_usd, "config reload").await {
+                Ok(()) => info!("💰 Daily budget set to ${:.2} by a config reload", max_usd),
+                Err(e) => warn!("Could not apply the reloaded daily budget: {}", e),
+            }
+        }
+    }
+}
+
+/// `BUDGET_ALERT_THRESHOLDS` as ascending percentages, see [`crate::config::thresholds`]; 50,
+/// 80 and 100 apply when it is unset.
+pub fn alert_thresholds(raw: Option<&str>) -> Vec<u32> {
+    raw.map_or_else(|| vec![50, 80, 100], crate::config::thresholds)
+}
+
+/// The thresholds `spent` has reached out of `max`.
+pub fn crossed(thresholds: &[u32], spent: f64, max: f64) -> Vec<u32> {
+    thresholds.iter().copied().filter(|t| spent >= max * f64::from(*t) / 100.0).collect()
+}
+
+/// Sum of today's `swarm:SpendEvent` amounts, in USD. Spend is dated in the display
+/// timezone, so the budget resets at local midnight.
+pub async fn spent_today(synapse: &Synapse) -> anyhow::Result<f64> {
+    spent_on(synapse, clock::today()).await
+}
+
+/// Sum of the `swarm:SpendEvent` amounts dated `date`, in USD.
+pub async fn spent_on(synapse: &Synapse, date: NaiveDate) -> anyhow::Result<f64> {
+    let today = date.format("%Y-%m-%d").to_string();
+    let spend_query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT (SUM(?amount) as ?total)
+        WHERE {
+            ?event a swarm:SpendEvent .
+            ?event swarm:date $today .
+            ?event swarm:amount ?amount .
+        }
+    "#,
+    )
+    .text("today", &today);
+
+    let res_json = synapse.select_cached(&spend_query, crate::query_cache::DEFAULT_TTL).await?;
+    let parsed = serde_json::from_str::<Vec<serde_json::Value>>(&res_json).unwrap_or_default();
+    Ok(parsed
+        .first()
+        .and_then(|first| first.get("total").or_else(|| first.get("?total")))
+        .and_then(|t| t.as_str())
+        .and_then(|raw| tasks::literal_value(raw).parse().ok())
+        .unwrap_or(0.0))
+}
+
+/// Tokens reported with today's spend, against the daily token cap of `budget`.
+pub async fn tokens_today(synapse: &Synapse, budget: &BudgetLimit) -> anyhow::Result<TokenUsage> {
+    let tokens = tokens_on(synapse, clock::today()).await?;
+    Ok(TokenUsage { max: budget.max_tokens(), ..tokens })
+}
+
+/// Prompt and completion tokens of the `swarm:SpendEvent`s dated `date`; events without
+/// counts add nothing. `max` is left for the caller.
+pub async fn tokens_on(synapse: &Synapse, date: NaiveDate) -> anyhow::Result<TokenUsage> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TokenRow {
+        #[serde(default, deserialize_with = "sparql::parsed")]
+        prompt_tokens: Option<u64>,
+        #[serde(default, deserialize_with = "sparql::parsed")]
+        completion_tokens: Option<u64>,
+    }
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?event ?promptTokens ?completionTokens
+        WHERE {
+            ?event a swarm:SpendEvent ;
+                   swarm:date $today .
+            OPTIONAL { ?event swarm:promptTokens ?promptTokens }
+            OPTIONAL { ?event swarm:completionTokens ?completionTokens }
+            FILTER(BOUND(?promptTokens) || BOUND(?completionTokens))
+        }
+    "#,
+    )
+    .text("today", &date.format("%Y-%m-%d").to_string());
+    let rows: Vec<TokenRow> = sparql::rows(&synapse.select_cached(&query, crate::query_cache::DEFAULT_TTL).await?)?;
+    Ok(rows.iter().fold(TokenUsage::default(), |mut usage, row| {
+        usage.prompt += row.prompt_tokens.unwrap_or(0);
+        usage.completion += row.completion_tokens.unwrap_or(0);
+        usage
+    }))
+}
+
+/// Span of a spend report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Week,
+    Month,
+}
+
+impl Period {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "week" => Some(Period::Week),
+            "month" => Some(Period::Month),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+
+    /// First and last day of the period `date` falls in; weeks start on Monday.
+    pub fn bounds(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Week => {
+                let monday = date - Days::new(date.weekday().num_days_from_monday().into());
+                (monday, monday + Days::new(6))
+            }
+            Period::Month => {
+                let first = date.with_day(1).unwrap_or(date);
+                (first, first + Months::new(1) - Days::new(1))
+            }
+        }
+    }
+}
+
+/// One `swarm:SpendEvent` row; an event repeats when its agent belongs to several repositories.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpendRow {
+    event: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    amount: Option<f64>,
+    agent: Option<String>,
+    repository: Option<String>,
+    task_repo: Option<String>,
+    agent_repo: Option<String>,
+    task: Option<String>,
+    model: Option<String>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    prompt_tokens: Option<u64>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    completion_tokens: Option<u64>,
+}
+
+/// A spend event with who and where it is charged to.
+struct Attributed<'a> {
+    date: NaiveDate,
+    amount: f64,
+    agent: Option<&'a String>,
+    repository: Option<&'a String>,
+    task: Option<&'a String>,
+    model: Option<&'a String>,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+/// Spend events dated `from` to `to`.
+async fn spend_rows(synapse: &Synapse, from: NaiveDate, to: NaiveDate) -> anyhow::Result<Vec<SpendRow>> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?event ?date ?amount ?agent ?repository ?taskRepo ?agentRepo ?task ?model ?promptTokens ?completionTokens
+        WHERE {
+            ?event a swarm:SpendEvent ;
+                   swarm:date ?date ;
+                   swarm:amount ?amount .
+            FILTER(STR(?date) >= $from && STR(?date) <= $to)
+            OPTIONAL { ?event swarm:repository ?repository }
+            OPTIONAL { ?event swarm:model ?model }
+            OPTIONAL { ?event swarm:promptTokens ?promptTokens }
+            OPTIONAL { ?event swarm:completionTokens ?completionTokens }
+            OPTIONAL {
+                ?event swarm:agent ?agent .
+                OPTIONAL { ?agentRepo swarm:hasPopulation ?agent }
+            }
+            OPTIONAL {
+                ?event swarm:task ?task .
+                OPTIONAL { ?task swarm:repository ?taskRepo }
+            }
+        }
+    "#,
+    )
+    .text("from", &from.format("%Y-%m-%d").to_string())
+    .text("to", &to.format("%Y-%m-%d").to_string());
+    sparql::rows(&synapse.select_cached(&query, crate::query_cache::DEFAULT_TTL).await?)
+}
+
+/// Each event once, charged to the repository it names, else its task's, else its agent's.
+/// Events written before they named one fall back the same way.
+fn attributed(rows: &[SpendRow]) -> Vec<Attributed<'_>> {
+    let mut seen = HashSet::new();
+    rows.iter()
+        .filter(|row| seen.insert(row.event.as_str()))
+        .filter_map(|row| {
+            Some(Attributed {
+                date: row.date?,
+                amount: row.amount?,
+                agent: row.agent.as_ref(),
+                repository: row.repository.as_ref().or(row.task_repo.as_ref()).or(row.agent_repo.as_ref()),
+                task: row.task.as_ref(),
+                model: row.model.as_ref(),
+                prompt_tokens: row.prompt_tokens.unwrap_or(0),
+                completion_tokens: row.completion_tokens.unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Spend of some events: the total and its shares per agent, repository, model and task.
+struct Breakdown {
+    spent: f64,
+    tokens: TokenUsage,
+    by_agent: Vec<SpendShare>,
+    by_repository: Vec<SpendShare>,
+    by_model: Vec<SpendShare>,
+    by_task: Vec<SpendShare>,
+}
+
+fn breakdown<'a>(events: impl Iterator<Item = &'a Attributed<'a>>) -> Breakdown {
+    let mut spent = 0.0;
+    let mut tokens = TokenUsage::default();
+    let mut totals: [BTreeMap<Option<&String>, SpendShare>; 4] = Default::default();
+    for event in events {
+        spent += event.amount;
+        tokens.prompt += event.prompt_tokens;
+        tokens.completion += event.completion_tokens;
+        for (share, key) in totals.iter_mut().zip([event.agent, event.repository, event.model, event.task]) {
+            let entry = share.entry(key).or_insert_with(|| SpendShare {
+                id: key.cloned(),
+                spent: 0.0,
+                events: 0,
+                prompt_tokens: 0,
+                completion_tokens: 0,
+            });
+            entry.spent += event.amount;
+            entry.events += 1;
+            entry.prompt_tokens += event.prompt_tokens;
+            entry.completion_tokens += event.completion_tokens;
+        }
+    }
+    let [by_agent, by_repository, mut by_model, mut by_task] = totals.map(shares);
+    // Spend that names no model, such as a run's reported cost, is left out of the per-model
+    // view, and spend charged to no task out of the per-task one.
+    by_model.retain(|share| share.id.is_some());
+    by_task.retain(|share| share.id.is_some());
+    Breakdown { spent, tokens, by_agent, by_repository, by_model, by_task }
+}
+
+/// Today's spend per agent and per repository, highest first.
+pub async fn shares_today(synapse: &Synapse) -> anyhow::Result<(Vec<SpendShare>, Vec<SpendShare>)> {
+    let today = clock::today();
+    let rows = spend_rows(synapse, today, today).await?;
+    let breakdown = breakdown(attributed(&rows).iter());
+    Ok((breakdown.by_agent, breakdown.by_repository))
+}
+
+/// Spend of the `period` that `date` falls in, per agent, repository, model and task, with the burn
+/// rate of that month. Budgets are the current daily limits of `budget` times the days.
+pub async fn report(synapse: &Synapse, period: Period, date: NaiveDate, budget: &BudgetLimit) -> anyhow::Result<BudgetReport> {
+    let (from, to) = period.bounds(date);
+    let (month_from, month_to) = Period::Month.bounds(date);
+    let rows = spend_rows(synapse, from.min(month_from), to.max(month_to)).await?;
+    Ok(rollup(period, date, clock::today(), &rows, budget.get(), budget.max_tokens()))
+}
+
+fn rollup(period: Period, date: NaiveDate, today: NaiveDate, rows: &[SpendRow], daily_max: f64, max_tokens: Option<u64>) -> BudgetReport {
+    let events = attributed(rows);
+    let (from, to) = period.bounds(date);
+    let days = (to - from).num_days() + 1;
+    let mut spend = breakdown(events.iter().filter(|e| (from..=to).contains(&e.date)));
+    spend.tokens.max = max_tokens.map(|max| max * days as u64);
+
+    let (month_from, month_to) = Period::Month.bounds(date);
+    let days_in_month = month_to.day();
+    let days_elapsed = match today {
+        t if t < month_from => 0,
+        t if t > month_to => days_in_month,
+        t => t.day(),
+    };
+    let month_spent: f64 = events.iter().filter(|e| (month_from..=month_to).contains(&e.date)).map(|e| e.amount).sum();
+    let daily_average = if days_elapsed > 0 { month_spent / f64::from(days_elapsed) } else { 0.0 };
+
+    BudgetReport {
+        period: period.as_str().to_string(),
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        spent: spend.spent,
+        budget: daily_max * days as f64,
+        unit: "USD".to_string(),
+        by_agent: spend.by_agent,
+        by_repository: spend.by_repository,
+        by_model: spend.by_model,
+        by_task: spend.by_task,
+        tokens: spend.tokens,
+        burn_rate: BurnRate {
+            month: month_from.format("%Y-%m").to_string(),
+            days_elapsed,
+            days_in_month,
+            spent: month_spent,
+            daily_average,
+            projected: daily_average * f64::from(days_in_month),
+            budget: daily_max * f64::from(days_in_month),
+        },
+    }
+}
+
+/// Highest spend first.
+fn shares(totals: BTreeMap<Option<&String>, SpendShare>) -> Vec<SpendShare> {
+    let mut shares: Vec<SpendShare> = totals.into_values().collect();
+    shares.sort_by(|a, b| b.spent.total_cmp(&a.spent));
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    use crate::store::MemoryStore;
+
+    #[test]
+    fn bare_ids_are_minted_and_iris_kept() {
+        assert_eq!(expand_iri("PM_1", "http://swarm.os/agent/"), "http://swarm.os/agent/PM_1");
+        assert_eq!(
+            expand_iri("http://swarm.os/trello/card/42", "http://swarm.os/task/"),
+            "http://swarm.os/trello/card/42"
+        );
+    }
+
+    #[test]
+    fn thresholds_are_parsed_and_crossed_in_order() {
+        assert_eq!(alert_thresholds(None), vec![50, 80, 100]);
+        assert_eq!(alert_thresholds(Some("100, 75%,x,0,75")), vec![75, 100]);
+        assert_eq!(crossed(&[50, 80, 100], 8.0, 10.0), vec![50, 80]);
+        assert_eq!(crossed(&[50, 80, 100], 10.0, 10.0), vec![50, 80, 100]);
+        assert!(crossed(&[50], 4.99, 10.0).is_empty());
+    }
+
+    #[test]
+    fn spend_rolls_up_per_agent_and_repository_with_a_burn_rate() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let row = |event: &str, d: u32, amount: f64, agent: Option<&str>, task_repo: Option<&str>, agent_repo: Option<&str>| SpendRow {
+            event: event.into(),
+            date: Some(day(d)),
+            amount: Some(amount),
+            agent: agent.map(str::to_string),
+            repository: None,
+            task_repo: task_repo.map(str::to_string),
+            agent_repo: agent_repo.map(str::to_string),
+            ..SpendRow::default()
+        };
+        let mut rows = vec![
+            row("e1", 12, 2.0, Some("coder"), Some("api"), Some("web")),
+            row("e1", 12, 2.0, Some("coder"), Some("api"), Some("ops")),
+            row("e2", 14, 1.0, Some("coder"), None, Some("web")),
+            row("e3", 15, 0.5, None, None, None),
+            row("e4", 2, 4.0, Some("pm"), None, None),
+            row("e5", 16, 7.5, Some("coder"), Some("api"), Some("web")),
+        ];
+        // The repository an event names wins over its task's and its agent's.
+        rows[5].repository = Some("billing".into());
+        for (i, prompt, completion) in [(2, 1000, 200), (5, 3000, 500)] {
+            rows[i].model = Some("gpt-4o".into());
+            rows[i].prompt_tokens = Some(prompt);
+            rows[i].completion_tokens = Some(completion);
+        }
+
+        // Thursday 15 October 2026: the week runs from Monday the 12th to Sunday the 18th.
+        rows[5].task = Some("http://swarm.os/tasks/7".into());
+        let week = rollup(Period::Week, day(15), day(15), &rows, 10.0, Some(100_000));
+        assert_eq!((week.from.as_str(), week.to.as_str(), week.spent, week.budget), ("2026-10-12", "2026-10-18", 11.0, 70.0));
+        assert_eq!(week.by_agent[0], SpendShare { id: Some("coder".into()), spent: 10.5, events: 3, prompt_tokens: 4000, completion_tokens: 700 });
+        assert_eq!(week.by_agent[1], SpendShare { id: None, spent: 0.5, events: 1, prompt_tokens: 0, completion_tokens: 0 });
+        // Only spend that names its model is broken down by model; tokens are capped per day.
+        assert_eq!(week.by_model, [SpendShare { id: Some("gpt-4o".into()), spent: 8.5, events: 2, prompt_tokens: 4000, completion_tokens: 700 }]);
+        assert_eq!(week.by_task, [SpendShare { id: Some("http://swarm.os/tasks/7".into()), spent: 7.5, events: 1, prompt_tokens: 3000, completion_tokens: 500 }]);
+        assert_eq!(week.tokens, TokenUsage { prompt: 4000, completion: 700, max: Some(700_000) });
+        let repos: Vec<_> = week.by_repository.iter().map(|s| (s.id.as_deref(), s.spent)).collect();
+        assert_eq!(repos, [(Some("billing"), 7.5), (Some("api"), 2.0), (Some("web"), 1.0), (None, 0.5)]);
+
+        let month = rollup(Period::Month, day(15), day(15), &rows, 10.0, None);
+        assert_eq!((month.from.as_str(), month.to.as_str(), month.spent, month.budget), ("2026-10-01", "2026-10-31", 15.0, 310.0));
+        assert_eq!(month.burn_rate, BurnRate {
+            month: "2026-10".into(),
+            days_elapsed: 15,
+            days_in_month: 31,
+            spent: 15.0,
+            daily_average: 1.0,
+            projected: 31.0,
+            budget: 310.0,
+        });
+        assert_eq!(month.tokens.max, None);
+        assert_eq!(rollup(Period::Month, day(15), day(15) + Months::new(1), &rows, 10.0, None).burn_rate.projected, 15.0);
+        assert_eq!(Period::Week.bounds(NaiveDate::from_ymd_opt(2026, 12, 31).unwrap()).1, NaiveDate::from_ymd_opt(2027, 1, 3).unwrap());
+        assert_eq!(Period::parse(" Month"), Some(Period::Month));
+    }
+
+    #[tokio::test]
+    async fn the_persisted_budget_is_restored_and_an_outage_falls_back_to_the_default() {
+        let (store, synapse) = MemoryStore::shared();
+        store.answer(DAILY_BUDGET, serde_json::json!([{ "?budget": tasks::decimal_literal(12.5) }]));
+        assert_eq!(BudgetLimit::load(&synapse, 50.0).await.get(), 12.5);
+
+        let limit = BudgetLimit::new(50.0);
+        limit.set(&synapse, 20.0, "ops").await.unwrap();
+        assert_eq!(limit.get(), 20.0);
+        let triples = store.triples();
+        let (_, _, change) = triples.iter().find(|(s, p, _)| s == SYSTEM_CONTROL && p == BUDGET_CHANGE).unwrap();
+        assert!(triples.iter().any(|(s, p, o)| s == change && p == DAILY_BUDGET && o == &tasks::decimal_literal(20.0)));
+        assert!(triples.iter().any(|(s, p, _)| s == change && p == GENERATED_AT));
+
+        store.set_down(true);
+        assert_eq!(BudgetLimit::load(&synapse, 50.0).await.get(), 50.0);
+        assert!(limit.set(&synapse, 30.0, "ops").await.is_err());
+        assert_eq!(limit.get(), 20.0);
+    }
+
+    #[tokio::test]
+    async fn the_newest_budget_change_wins_over_older_and_untimed_ones() {
+        let (store, synapse) = MemoryStore::shared();
+        let at = |hour| tasks::datetime_literal(&Utc.with_ymd_and_hms(2026, 10, 17, hour, 0, 0).unwrap());
+        store.answer(
+            DAILY_BUDGET,
+            serde_json::json!([
+                { "?budget": tasks::decimal_literal(40.0), "?at": at(9) },
+                { "?budget": tasks::decimal_literal(15.0), "?at": at(11) },
+                { "?budget": tasks::decimal_literal(99.0) },
+                { "?budget": tasks::decimal_literal(25.0), "?at": at(10) },
+            ]),
+        );
+        assert_eq!(BudgetLimit::load(&synapse, 50.0).await.get(), 15.0);
+    }
+}
@@ -0,0 +1,89 @@
+//! Which agents may take a task. A task can require an agent class (`swarm:requiresClass`,
+//! any one of them) and skills (`swarm:requiresSkill`, all of them); agents list theirs as
+//! `swarm:class` and `swarm:skill`. Requirements come from card and issue labels such as
+//! `class: Coder` or `skill/rust`. A task without requirements suits every agent. Classes and
+//! skills compare case-insensitively.
+
+use crate::sparql::literal;
+
+pub const REQUIRES_CLASS: &str = "http://swarm.os/ontology/requiresClass";
+pub const REQUIRES_SKILL: &str = "http://swarm.os/ontology/requiresSkill";
+pub const SKILL: &str = "http://swarm.os/ontology/skill";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Requirements {
+    pub classes: Vec<String>,
+    pub skills: Vec<String>,
+}
+
+impl Requirements {
+    /// What labels like `class: Coder`, `class/Reviewer` or `skill:rust` ask for.
+    pub fn from_labels<'a>(labels: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut requirements = Self::default();
+        for label in labels {
+            if let Some(class) = labelled(label, "class") {
+                requirements.add_class(class);
+            } else if let Some(skill) = labelled(label, "skill") {
+                requirements.add_skill(skill);
+            }
+        }
+        requirements
+    }
+
+    pub fn add_class(&mut self, class: &str) {
+        push_unique(&mut self.classes, class);
+    }
+
+    pub fn add_skill(&mut self, skill: &str) {
+        push_unique(&mut self.skills, skill);
+    }
+
+    /// Predicates and quoted literals that record the requirements on a task.
+    pub fn triples(&self) -> Vec<(&'static str, String)> {
+        let classes = self.classes.iter().map(|c| (REQUIRES_CLASS, literal(c)));
+        classes.chain(self.skills.iter().map(|s| (REQUIRES_SKILL, literal(s)))).collect()
+    }
+
+    pub fn met_by(&self, class: &str, skills: &[String]) -> bool {
+        let class_ok = self.classes.is_empty() || self.classes.iter().any(|c| c.eq_ignore_ascii_case(class));
+        class_ok && self.skills.iter().all(|needed| skills.iter().any(|s| s.eq_ignore_ascii_case(needed)))
+    }
+}
+
+/// The value of a `prefix: value` label (also `prefix/value`, `prefix=value`).
+fn labelled<'a>(label: &'a str, prefix: &str) -> Option<&'a str> {
+    let label = label.trim();
+    let head = label.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = &label[prefix.len()..];
+    let value = rest.strip_prefix([':', '/', '='])?.trim();
+    (!value.is_empty()).then_some(value)
+}
+
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() && !values.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+        values.push(value.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_name_the_class_and_skills_a_task_needs() {
+        let requirements = Requirements::from_labels(["class: Coder", "Skill/rust", "skill=SQL", "urgent", "classy", "skill:"]);
+        assert_eq!(requirements.classes, vec!["Coder"]);
+        assert_eq!(requirements.skills, vec!["rust", "SQL"]);
+        assert_eq!(requirements.triples()[0], (REQUIRES_CLASS, "\"Coder\"".to_string()));
+
+        let skills = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(requirements.met_by("coder", &skills(&["sql", "Rust", "go"])));
+        assert!(!requirements.met_by("Coder", &skills(&["rust"])));
+        assert!(!requirements.met_by("Reviewer", &skills(&["rust", "sql"])));
+        assert!(Requirements::default().met_by("Anyone", &[]));
+    }
+}
@@ -1,5 +1,4 @@
 use ratatui::widgets::ListState;
-use std::time::Duration;
 use tokio::sync::mpsc;
 use serde::Deserialize;
 
@@ -71,67 +70,34 @@ impl App {
     }
     
     pub async fn fetch_knowledge_nodes() -> Vec<KnowledgeNode> {
-        let client = reqwest::Client::new();
-        match client.get("http://127.0.0.1:18789/api/v1/game-state")
-            .timeout(Duration::from_secs(3))
-            .send()
-            .await
-        {
-            Ok(resp) => {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(json) => {
-                        let nodes = json.get("knowledge_tree")
-                            .and_then(|n| n.as_array())
-                            .map(|arr| arr.iter()
-                                .filter_map(|n| {
-                                    let id = n.get("id")?.as_str()?.to_string();
-                                    let name = n.get("name")?.as_str()?.to_string();
-                                    let domain = n.get("domain").and_then(|d| d.as_str()).unwrap_or("General").to_string();
-                                    let level = n.get("level").and_then(|l| l.as_u64()).unwrap_or(1) as u32;
-                                    Some(KnowledgeNode { id, name, domain, level })
-                                })
-                                .collect::<Vec<KnowledgeNode>>()
-                            );
-                        
-                        let resolved = nodes.unwrap_or_default();
-                        if resolved.is_empty() {
-                            vec![KnowledgeNode { id: "none".to_string(), name: "No knowledge nodes found".to_string(), domain: "SYSTEM".to_string(), level: 0 }]
-                        } else {
-                            resolved
-                        }
-                    }
-                    Err(_) => vec![KnowledgeNode { id: "error".to_string(), name: "Failed to parse knowledge".to_string(), domain: "ERROR".to_string(), level: 0 }]
+        match super::gateway().game_state().await {
+            Ok(state) => {
+                let resolved: Vec<KnowledgeNode> = state
+                    .knowledge_tree
+                    .into_iter()
+                    .map(|n| KnowledgeNode { id: n.id, name: n.name, domain: n.domain, level: n.level.max(0) as u32 })
+                    .collect();
+                if resolved.is_empty() {
+                    vec![KnowledgeNode { id: "none".to_string(), name: "No knowledge nodes found".to_string(), domain: "SYSTEM".to_string(), level: 0 }]
+                } else {
+                    resolved
                 }
             }
+            Err(swarm_client::ClientError::Http(e)) if e.is_decode() => {
+                vec![KnowledgeNode { id: "error".to_string(), name: "Failed to parse knowledge".to_string(), domain: "ERROR".to_string(), level: 0 }]
+            }
             Err(_) => vec![KnowledgeNode { id: "error".to_string(), name: "Connect to gateway for knowledge".to_string(), domain: "NETWORK".to_string(), level: 0 }]
         }
     }
-    
+
     pub async fn fetch_system_status() -> Option<String> {
-        let client = reqwest::Client::new();
-        match client.get("http://127.0.0.1:18789/api/v1/game-state")
-            .timeout(Duration::from_secs(3))
-            .send()
-            .await
-        {
-            Ok(resp) => {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(json) => {
-                        let status = json.get("system_status")
-                            .and_then(|s| s.as_str())
-                            .unwrap_or("UNKNOWN");
-                        let budget = json.get("daily_budget").and_then(|b| {
-                            let max = b.get("max").and_then(|m| m.as_f64()).unwrap_or(0.0);
-                            let spent = b.get("spent").and_then(|s| s.as_f64()).unwrap_or(0.0);
-                            Some(format!("${spent:.2}/${max:.2}"))
-                        });
-                        Some(format!("System: {} | Budget: {}", status, budget.unwrap_or_default()))
-                    }
-                    Err(_) => None
-                }
-            }
-            Err(_) => None
-        }
+        let state = super::gateway().game_state().await.ok()?;
+        // Only admin tokens see spend.
+        let budget = state
+            .daily_budget
+            .map(|b| format!("${:.2}/${:.2}", b.spent, b.max))
+            .unwrap_or_default();
+        Some(format!("System: {} | Budget: {}", state.system_status.as_str(), budget))
     }
 
     pub fn on_tick(&mut self) {
@@ -3,6 +3,7 @@ use futures_util::StreamExt;
 use tokio_tungstenite::connect_async;
 use serde_json::Value;
 use std::time::Duration;
+use swarm_client::contracts::{ControlCommand, ControlCommandType, MissionAssignment};
 
 pub async fn spawn_telemetry_handler(tx: mpsc::Sender<String>, status_tx: mpsc::Sender<bool>) {
     let url = "ws://127.0.0.1:18789/api/v1/events/combat/stream";
@@ -43,55 +44,57 @@ pub async fn spawn_telemetry_handler(tx: mpsc::Sender<String>, status_tx: mpsc::
 
 pub async fn spawn_command_handler(tx_msg: mpsc::Sender<String>, mut rx_cmd: mpsc::Receiver<String>, command_tx_internal: mpsc::Sender<String>) {
     let client = reqwest::Client::new();
+    let gateway = super::gateway();
     tokio::spawn(async move {
         while let Some(cmd) = rx_cmd.recv().await {
             match cmd.as_str() {
                 "HALT SWARM" => {
-                    let res = client.post("http://127.0.0.1:18789/api/v1/control/commands")
-                        .json(&serde_json::json!({
-                            "command_type": "Halt",
-                            "actor": "operator",
-                            "nist_policy_id": "NIST-800-53-REV5",
-                            "approved_by": "operator"
-                        }))
-                        .send().await;
-                    match res {
-                        Ok(r) if r.status().is_success() => { let _ = tx_msg.send("[SUCCESS] System HALTED command sent.".to_string()).await; }
-                        _ => { let _ = tx_msg.send("[ERROR] HALT command failed.".to_string()).await; }
+                    let halt = ControlCommand {
+                        command: ControlCommandType::Halt,
+                        actor: "operator".to_string(),
+                        agent_id: None,
+                        repo_id: None,
+                        task: None,
+                        mission_id: None,
+                        priority: None,
+                        deployment_target: None,
+                        rollback_to: None,
+                        llm_profile: None,
+                        nist_policy_id: "NIST-800-53-REV5".to_string(),
+                        approved_by: Some("operator".to_string()),
+                        metadata: Default::default(),
+                    };
+                    match gateway.send_command(&halt).await {
+                        Ok(_) => { let _ = tx_msg.send("[SUCCESS] System HALTED command sent.".to_string()).await; }
+                        Err(_) => { let _ = tx_msg.send("[ERROR] HALT command failed.".to_string()).await; }
                     }
                 }
                 "SCAN SECTOR" => {
-                    let res = client.get("http://127.0.0.1:18789/api/v1/game-state").send().await;
-                    match res {
-                        Ok(r) if r.status().is_success() => { let _ = tx_msg.send("[SUCCESS] Sector scanned. Neural sensors active.".to_string()).await; }
-                        _ => { let _ = tx_msg.send("[ERROR] Scan failed.".to_string()).await; }
+                    match gateway.game_state().await {
+                        Ok(_) => { let _ = tx_msg.send("[SUCCESS] Sector scanned. Neural sensors active.".to_string()).await; }
+                        Err(_) => { let _ = tx_msg.send("[ERROR] Scan failed.".to_string()).await; }
                     }
                 }
                 c if c.starts_with("MISSION:") => {
                     let task = &c[8..];
-                    let res = client.post("http://127.0.0.1:18789/api/v1/mission/assign")
-                        .json(&serde_json::json!({
-                            "agent_id": "http://swarm.os/agents/Coder",
-                            "repo_id": "root",
-                            "task": task
-                        }))
-                        .send().await;
-                    match res {
-                        Ok(r) if r.status().is_success() => { let _ = tx_msg.send(format!("[SUCCESS] Mission '{}' dispatched.", task)).await; }
-                        _ => { let _ = tx_msg.send("[ERROR] Mission rejected by gateway.".to_string()).await; }
+                    let mission = MissionAssignment {
+                        agent_id: "http://swarm.os/agents/Coder".to_string(),
+                        repo_id: "root".to_string(),
+                        task: task.to_string(),
+                    };
+                    match gateway.assign_mission(&mission).await {
+                        Ok(_) => { let _ = tx_msg.send(format!("[SUCCESS] Mission '{}' dispatched.", task)).await; }
+                        Err(_) => { let _ = tx_msg.send("[ERROR] Mission rejected by gateway.".to_string()).await; }
                     }
                 }
                 c if c.starts_with("KNOWLEDGE:") => {
                     let id = &c[10..];
-                    let url = format!("http://127.0.0.1:18789/api/v1/knowledge-tree/{}/docs", id);
-                    match client.get(&url).send().await {
-                        Ok(r) if r.status().is_success() => {
-                            if let Ok(json) = r.json::<serde_json::Value>().await {
-                                let docs = json.get("documentation").and_then(|d| d.as_str()).unwrap_or("No docs.");
-                                let _ = tx_msg.send(format!("DETAIL_VIEW:{}", docs)).await;
-                            }
+                    match gateway.knowledge_node_docs(id).await {
+                        Ok(res) => {
+                            let docs = if res.documentation.is_empty() { "No docs." } else { res.documentation.as_str() };
+                            let _ = tx_msg.send(format!("DETAIL_VIEW:{}", docs)).await;
                         }
-                        _ => { let _ = tx_msg.send(format!("DETAIL_VIEW:[ERROR] Failed to access node {}", id)).await; }
+                        Err(_) => { let _ = tx_msg.send(format!("DETAIL_VIEW:[ERROR] Failed to access node {}", id)).await; }
                     }
                 }
                 c if c.starts_with("CHAT:") => {
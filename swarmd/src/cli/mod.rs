@@ -1,3 +1,24 @@
 pub mod app;
 pub mod ui;
 pub mod handlers;
+
+use std::time::Duration;
+use swarm_client::{SwarmClient, DEFAULT_BASE_URL};
+
+/// Gateway client for the TUI. `SWARM_GATEWAY_URL` and `SWARM_API_TOKEN` point it at a
+/// remote or token-protected gateway; by default it talks to the local one.
+pub fn gateway() -> SwarmClient {
+    let base_url = std::env::var("SWARM_GATEWAY_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+    let client = SwarmClient::new(&base_url)
+        .or_else(|_| SwarmClient::new(DEFAULT_BASE_URL))
+        .expect("default gateway URL is valid");
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(3))
+        .build()
+        .unwrap_or_default();
+    let client = client.with_http_client(http);
+    match std::env::var("SWARM_API_TOKEN") {
+        Ok(token) if !token.is_empty() => client.with_token(token),
+        _ => client,
+    }
+}
@@ -0,0 +1,103 @@
+//! The operators' timezone (`SWARM_TIMEZONE`, an IANA name such as `Europe/Madrid`).
+//! Synapse and the API keep UTC RFC3339; chat messages and reports show local time, and
+//! "today" (daily budget, spend dates, report dates) runs from local midnight to midnight.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Sets the display timezone once at startup.
+pub fn init(name: &str) -> anyhow::Result<Tz> {
+    let tz: Tz = name
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("unknown timezone {:?}; use an IANA name such as Europe/Madrid", name))?;
+    Ok(*TIMEZONE.get_or_init(|| tz))
+}
+
+/// The display timezone; UTC until [`init`] runs.
+pub fn tz() -> Tz {
+    TIMEZONE.get().copied().unwrap_or(Tz::UTC)
+}
+
+/// Today's date in the display timezone.
+pub fn today() -> NaiveDate {
+    date_of(Utc::now())
+}
+
+/// The date of `at` in the display timezone.
+pub fn date_of(at: DateTime<Utc>) -> NaiveDate {
+    date_in(tz(), at)
+}
+
+fn date_in(tz: Tz, at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&tz).date_naive()
+}
+
+/// `2026-10-17 11:30 CEST`, for humans.
+pub fn display(at: DateTime<Utc>) -> String {
+    display_in(tz(), at)
+}
+
+fn display_in(tz: Tz, at: DateTime<Utc>) -> String {
+    at.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z").to_string()
+}
+
+/// [`display`] for a stored RFC3339 timestamp; anything unparseable is shown as it is.
+pub fn display_rfc3339(raw: &str) -> String {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|at| display(at.with_timezone(&Utc)))
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+/// The next `hour`:00 local time strictly after `now`. On a DST gap the hour is skipped
+/// forward to the first valid instant.
+pub fn next_local_hour(tz: Tz, hour: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    let local_today = date_in(tz, now);
+    (0..=2)
+        .filter_map(|days| {
+            let naive = (local_today + chrono::Duration::days(days)).and_hms_opt(hour.min(23), 0, 0)?;
+            let local = tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .or_else(|| tz.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest())?;
+            Some(local.with_timezone(&Utc))
+        })
+        .find(|at| *at > now)
+        .unwrap_or(now + chrono::Duration::days(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn local_day_and_display_follow_the_timezone() {
+        let madrid: Tz = "Europe/Madrid".parse().unwrap();
+        // 23:30 UTC on 17 October is already the 18th in Madrid (CEST, UTC+2).
+        let late = utc("2026-10-17T23:30:00Z");
+        assert_eq!(date_in(madrid, late), NaiveDate::from_ymd_opt(2026, 10, 18).unwrap());
+        assert_eq!(date_in(Tz::UTC, late), NaiveDate::from_ymd_opt(2026, 10, 17).unwrap());
+        assert_eq!(display_in(madrid, late), "2026-10-18 01:30 CEST");
+        assert_eq!(display_in(madrid, utc("2026-01-10T09:00:00Z")), "2026-01-10 10:00 CET");
+        assert!(init("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn next_local_hour_handles_dst_changes() {
+        let madrid: Tz = "Europe/Madrid".parse().unwrap();
+        assert_eq!(next_local_hour(madrid, 3, utc("2026-10-17T10:00:00Z")), utc("2026-10-18T01:00:00Z"));
+        // Clocks go back on 25 October 2026: 03:00 local is 02:00 UTC again.
+        assert_eq!(next_local_hour(madrid, 3, utc("2026-10-24T10:00:00Z")), utc("2026-10-25T02:00:00Z"));
+        // 29 March 2026 has no 02:00 in Madrid; the run moves to 03:00 CEST.
+        assert_eq!(next_local_hour(madrid, 2, utc("2026-03-28T12:00:00Z")), utc("2026-03-29T01:00:00Z"));
+        assert_eq!(next_local_hour(Tz::UTC, 3, utc("2026-10-17T03:00:00Z")), utc("2026-10-18T03:00:00Z"));
+    }
+}
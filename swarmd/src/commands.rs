@@ -0,0 +1,268 @@
+//! `swarmd` subcommands. Without one, `swarmd` runs the daemon; the others are one-off
+//! operations that exit when done and log to stderr, so their output can be piped.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use tokio::sync::mpsc;
+
+use crate::budget::BudgetLimit;
+use crate::config::{self, AppConfig};
+use crate::llm::{Caller, Llm};
+use crate::notifications::{ChannelRenderer, Notification, PlainText};
+use crate::store::{Endpoint, Synapse};
+use crate::workers::trello;
+use crate::{clock, discovery, roster};
+
+#[derive(Debug, Parser)]
+#[command(name = "swarmd", version, about = "Swarm orchestrator daemon")]
+pub struct Cli {
+    /// Config file; defaults to SWARMD_CONFIG, then ./swarmd.toml if present.
+    // Parsed here for `--help`; `AppConfig::load` reads it from the arguments itself so
+    // that config reloads find the same file.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum Command {
+    /// Run the daemon (the default).
+    Run,
+    /// Load and validate the configuration and agent roster, then exit.
+    CheckConfig,
+    /// Scan the workspace for repositories and sync them and the agents into Synapse.
+    Discover {
+        /// Sync once and exit instead of rescanning every `--interval` seconds.
+        #[arg(long)]
+        once: bool,
+        /// Seconds between rescans.
+        #[arg(long, default_value_t = 300, value_name = "SECS")]
+        interval: u64,
+    },
+    /// Run a SPARQL query against Synapse and print the rows as JSON (`-` reads stdin).
+    Query { sparql: String },
+    /// Backfill completed tasks, durations and spend estimates from the Trello board,
+    /// archived cards included.
+    ImportTrello {
+        /// Only cards completed on or after this date (YYYY-MM-DD, display timezone).
+        #[arg(long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+        /// Estimated spend recorded per imported task; 0 records none.
+        #[arg(long, default_value_t = 0.5, value_name = "USD")]
+        usd_per_task: f64,
+        /// Report what would be imported without writing to Synapse.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send a prompt to the native LLM client and print the reply (`-` reads stdin), to check
+    /// its settings. The call is charged to the daily budget.
+    Ask {
+        prompt: String,
+        /// System prompt.
+        #[arg(long, default_value = "", value_name = "TEXT")]
+        system: String,
+    },
+    /// Interactive setup that writes a starter `.env`.
+    Init,
+}
+
+/// Logs go to stderr so that `swarmd query` output stays clean.
+fn init_logging() {
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+}
+
+/// Loads the configuration with the settings every command depends on applied.
+fn load_config() -> Result<AppConfig> {
+    let cfg = AppConfig::load()?;
+    clock::init(&cfg.display_timezone)?;
+    if let Some(path) = &cfg.agent_roster_path {
+        roster::load(Path::new(path))?;
+    }
+    Ok(cfg)
+}
+
+pub fn check_config() -> Result<()> {
+    init_logging();
+    let cfg = load_config()?;
+
+    if !Path::new(&cfg.workspace_root).is_dir() {
+        bail!("SWARM_WORKSPACE_ROOT {} is not a directory", cfg.workspace_root);
+    }
+    if cfg.trello_reconcile_hour > 23 {
+        bail!("TRELLO_RECONCILE_HOUR must be 0-23, got {}", cfg.trello_reconcile_hour);
+    }
+    if let Some(path) = &cfg.scheduler_script {
+        crate::workers::agency::check_scheduler_script(path)?;
+    }
+    crate::workers::agency::assignment_strategies(&cfg).map_err(anyhow::Error::msg)?;
+    let tokens = crate::server::auth::ApiTokens::parse(cfg.api_tokens.as_deref().unwrap_or_default());
+
+    let file = config::config_file();
+    println!("Configuration OK{}", if file.exists() { format!(" ({})", file.display()) } else { String::new() });
+    println!("  synapse       {}:{}", cfg.synapse_grpc_host, cfg.synapse_grpc_port);
+    println!("  gateway port  {}", cfg.gateway_port);
+    if cfg.demo_mode {
+        println!("  demo mode     seed {}, {} requests/min per client", cfg.demo_seed, cfg.demo_rate_limit);
+    }
+    println!("  timezone      {}", clock::tz());
+    println!("  daily budget  ${:.2}", cfg.daily_budget_usd);
+    println!("  api tokens    {}", if tokens.is_empty() { "none (open gateway)".to_string() } else { tokens.len().to_string() });
+    println!(
+        "  agents        {}",
+        roster::agents().map_or_else(
+            || "default squads".to_string(),
+            |a| {
+                let kinds = [roster::RuntimeKind::Docker, roster::RuntimeKind::Http, roster::RuntimeKind::Native];
+                let others: Vec<String> = kinds
+                    .iter()
+                    .filter_map(|kind| match a.iter().filter(|a| a.runtime == *kind).count() {
+                        0 => None,
+                        n => Some(format!("{} {}", n, kind.as_str())),
+                    })
+                    .collect();
+                if others.is_empty() { a.len().to_string() } else { format!("{} ({})", a.len(), others.join(", ")) }
+            }
+        )
+    );
+    println!("  scheduling    {}", cfg.scheduler_script.as_deref().unwrap_or("built-in policy"));
+    let provider: crate::llm::Provider = cfg.llm_provider.parse()?;
+    match &cfg.llm_api_key {
+        Some(_) => println!(
+            "  llm           {} {}{}",
+            provider.as_str(),
+            cfg.llm_model.as_deref().unwrap_or("(default model)"),
+            if cfg.triage { ", triaging new tasks" } else { "" }
+        ),
+        None => println!("  llm           off (no API key)"),
+    }
+    let workers: Vec<&str> = [
+        ("telegram", cfg.telegram_bot_token.is_some()),
+        ("slack", cfg.slack_signing_secret.is_some()),
+        ("discord", cfg.discord_bot_token.is_some() && cfg.discord_channel_id.is_some()),
+        ("trello", cfg.trello_api_key.is_some() && cfg.trello_token.is_some() && cfg.trello_board_id.is_some()),
+        ("github", cfg.github_token.is_some()),
+        ("agency", true),
+    ]
+    .into_iter()
+    .filter(|(name, configured)| *configured && cfg.worker_enabled(name))
+    .map(|(name, _)| name)
+    .collect();
+    println!("  workers       {}", workers.join(", "));
+    Ok(())
+}
+
+pub async fn discover(once: bool, interval: u64) -> Result<()> {
+    init_logging();
+    let cfg = load_config()?;
+    let synapse = connect(&cfg).await?;
+    let (tx, mut rx) = mpsc::channel(100);
+
+    loop {
+        discovery::discover_repositories(&synapse, &cfg.workspace_root, &tx).await;
+        while let Ok(note) = rx.try_recv() {
+            println!("{}", PlainText.render_message(&Notification::decorated(&note)));
+        }
+        if once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+    }
+}
+
+pub async fn query(sparql: &str) -> Result<()> {
+    init_logging();
+    let sparql = read_arg(sparql, "the query")?;
+    let cfg = AppConfig::load()?;
+    let synapse = connect(&cfg).await?;
+
+    let rows = synapse.query(&sparql).await?;
+    match serde_json::from_str::<serde_json::Value>(&rows) {
+        Ok(rows) => println!("{}", serde_json::to_string_pretty(&rows)?),
+        Err(_) => println!("{}", rows),
+    }
+    Ok(())
+}
+
+pub async fn import_trello(options: trello::ImportOptions) -> Result<()> {
+    init_logging();
+    let cfg = load_config()?;
+    let (Some(api_key), Some(token), Some(board_id)) = (&cfg.trello_api_key, &cfg.trello_token, &cfg.trello_board_id) else {
+        bail!("TRELLO_API_KEY, TRELLO_TOKEN and TRELLO_BOARD_ID must be set");
+    };
+    let synapse = connect(&cfg).await?;
+
+    let summary = trello::import_history(api_key, token, board_id, &synapse, &reqwest::Client::new(), &options).await?;
+    println!(
+        "{} {} completed tasks (${:.2} estimated spend); {} already known, {} skipped",
+        if options.dry_run { "Would import" } else { "Imported" },
+        summary.imported,
+        summary.estimated_usd,
+        summary.known,
+        summary.skipped,
+    );
+    Ok(())
+}
+
+pub async fn ask(prompt: &str, system: &str) -> Result<()> {
+    init_logging();
+    let prompt = read_arg(prompt, "the prompt")?;
+    let cfg = load_config()?;
+    let synapse = connect(&cfg).await?;
+    let budget = BudgetLimit::load(&synapse, cfg.daily_budget_usd).await;
+    budget.set_max_tokens(cfg.daily_token_budget);
+    let Some(llm) = Llm::from_config(&cfg, synapse, budget)? else {
+        bail!("No LLM API key: set SWARM_LLM_API_KEY, or the provider's own OPENAI_API_KEY or ANTHROPIC_API_KEY");
+    };
+
+    let completion = llm.complete(&Caller { agent_id: Some("swarmd".into()), task_id: None }, system, &prompt).await?;
+    println!("{}", completion.text);
+    eprintln!(
+        "{}: {} prompt and {} completion tokens, ${:.4}",
+        llm, completion.input_tokens, completion.output_tokens, completion.cost_usd
+    );
+    Ok(())
+}
+
+/// `arg`, or stdin when it is `-`.
+fn read_arg(arg: &str, what: &str) -> Result<String> {
+    if arg != "-" {
+        return Ok(arg.to_string());
+    }
+    let mut raw = String::new();
+    std::io::stdin().read_to_string(&mut raw).with_context(|| format!("reading {} from stdin", what))?;
+    Ok(raw)
+}
+
+async fn connect(cfg: &AppConfig) -> Result<Synapse> {
+    Synapse::connect(&Endpoint::from_config(cfg)?)
+        .await
+        .with_context(|| format!("connecting to Synapse at {}:{}", cfg.synapse_grpc_host, cfg.synapse_grpc_port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subcommands() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).map(|cli| cli.command);
+        assert_eq!(parse(&["swarmd"]).unwrap(), None);
+        assert_eq!(parse(&["swarmd", "discover", "--once"]).unwrap(), Some(Command::Discover { once: true, interval: 300 }));
+        assert_eq!(parse(&["swarmd", "query", "SELECT * WHERE { ?s ?p ?o }"]).unwrap(), Some(Command::Query { sparql: "SELECT * WHERE { ?s ?p ?o }".into() }));
+        let cli = Cli::try_parse_from(["swarmd", "check-config", "--config", "prod.toml"]).unwrap();
+        assert_eq!((cli.command, cli.config), (Some(Command::CheckConfig), Some("prod.toml".into())));
+        assert!(parse(&["swarmd", "query"]).is_err());
+        assert_eq!(parse(&["swarmd", "ask", "-"]).unwrap(), Some(Command::Ask { prompt: "-".into(), system: String::new() }));
+        assert_eq!(
+            parse(&["swarmd", "import-trello", "--since", "2026-01-01", "--dry-run"]).unwrap(),
+            Some(Command::ImportTrello { since: NaiveDate::from_ymd_opt(2026, 1, 1), usd_per_task: 0.5, dry_run: true })
+        );
+    }
+}
@@ -1,20 +1,174 @@
-use anyhow::Result;
-use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Deserialize)]
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+mod watcher;
+pub use watcher::watch;
+
+/// Read from the working directory when neither `--config` nor `SWARMD_CONFIG` is given.
+pub const DEFAULT_CONFIG_FILE: &str = "swarmd.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub synapse_grpc_host: String,
     pub synapse_grpc_port: String,
+    // Named graph (an IRI such as `urn:swarm:prod`) every read and write is scoped to
+    pub synapse_graph: Option<String>,
+    // Keep writes in the state store while Synapse is unreachable and replay them once it is back
+    pub synapse_write_buffer: bool,
+    // Synapse calls fail fast for the cooldown after this many in a row could not reach it; 0 disables
+    pub synapse_breaker_failures: u32,
+    pub synapse_breaker_cooldown_secs: u64,
+    // TLS to Synapse, checked against the bundled web PKI roots unless a CA is given; implied by
+    // any of the files below
+    pub synapse_tls: bool,
+    pub synapse_tls_ca: Option<String>,
+    // PEM client certificate and key for mutual TLS; both or neither
+    pub synapse_tls_cert: Option<String>,
+    pub synapse_tls_key: Option<String>,
+    // Name Synapse's certificate is checked against when it is not SYNAPSE_GRPC_HOST
+    pub synapse_tls_domain: Option<String>,
+    // Sent as `authorization: Bearer <token>` with every Synapse call
+    pub synapse_token: Option<String>,
     pub gateway_port: u16,
 
     // Telegram
     pub telegram_bot_token: Option<String>,
     pub telegram_chat_id: Option<String>,
+    // `chat_id=role` and `user_id=role` pairs; without either everyone in the chat above is an admin
+    pub telegram_chats: Option<String>,
+    pub telegram_user_roles: Option<String>,
+    // Public gateway base URL; when set, Telegram pushes updates to a webhook instead of being polled
+    pub telegram_webhook_url: Option<String>,
+    pub telegram_webhook_secret: Option<String>,
+
+    // Notification sinks: comma-separated list of telegram, slack, discord, webhook, stdout
+    pub notification_sinks: Option<String>,
+    // `severity[@source]=sink,sink` rules separated by `;`; every sink gets everything without them
+    pub notification_routes: Option<String>,
+    // Identical notifications within this many seconds collapse into one summary; 0 disables
+    pub notification_dedupe_secs: u64,
+    pub slack_webhook_url: Option<String>,
+    pub notify_webhook_url: Option<String>,
+
+    // Slack bot: notifications via chat.postMessage, `/swarm` slash commands via the gateway
+    pub slack_bot_token: Option<String>,
+    pub slack_channel_id: Option<String>,
+    pub slack_signing_secret: Option<String>,
+
+    // Discord bot: notifications and `!` commands in one channel
+    pub discord_bot_token: Option<String>,
+    pub discord_channel_id: Option<String>,
 
     // Trello
     pub trello_api_key: Option<String>,
     pub trello_token: Option<String>,
     pub trello_board_id: Option<String>,
+    // Hour (display timezone) of the nightly board/Synapse reconciliation
+    pub trello_reconcile_hour: u32,
+    pub trello_poll_secs: u64,
+
+    // GitHub Issues: `swarm`-labelled issues of these `owner/repo`s become tasks
+    pub github_token: Option<String>,
+    pub github_repos: Option<String>,
+    pub github_poll_secs: u64,
+
+    // Seconds between checks for due scheduled tasks
+    pub schedules_poll_secs: u64,
+
+    // Workers (trello, github, agency) that stay idle, comma-separated; reloadable
+    pub disabled_workers: Option<String>,
+
+    // Task lifecycle hooks (webhook URL or shell command)
+    pub hook_pre_assign: Option<String>,
+    pub hook_post_complete: Option<String>,
+    pub hook_on_fail: Option<String>,
+
+    // Agency retry policy
+    pub agency_max_attempts: u32,
+    pub agency_retry_base_secs: u64,
+    pub agency_retry_max_secs: u64,
+    // Default per-task execution timeout; tasks may override it with swarm:timeoutSeconds
+    pub agency_task_timeout_secs: u64,
+    // Minutes a run may go without a heartbeat before it is stopped as STALLED; 0 disables
+    pub agency_stall_minutes: u64,
+    // Forward the output of running orchestrators to the notification channels as traces
+    pub agency_stream_output: bool,
+    // Chores idle agents take when no task is runnable (built-in names or name=title pairs),
+    // their shared daily spend cap and how often each one repeats per repository
+    pub agency_chores: Option<String>,
+    pub chore_daily_budget_usd: f64,
+    pub chore_interval_hours: u64,
+    // Hours of waiting that raise a task's priority by one level, so old tasks are not starved; 0 disables
+    pub priority_aging_hours: u64,
+    // Experience (comma-separated, ascending) an agent needs for level 2, 3 and so on
+    pub level_thresholds: Option<String>,
+    // Humans (comma-separated) who take over tasks the agents gave up on; dead letter without them
+    pub escalation_owners: Option<String>,
+    // Rhai script scoring (task, agent) pairs; the built-in policy without it
+    pub scheduler_script: Option<String>,
+    // Built-in assignment strategy, and `repo=strategy` overrides (comma-separated)
+    pub assignment_strategy: Option<String>,
+    pub assignment_strategies: Option<String>,
+
+    // Gateway bearer tokens as `token=role` pairs (viewer, operator, admin)
+    pub api_tokens: Option<String>,
+    // Public demo: the gateway serves a seeded simulation, read-only and rate-limited per client
+    pub demo_mode: bool,
+    pub demo_seed: u64,
+    pub demo_rate_limit: u32,
+    // Seconds between game-state snapshots kept for the timeline; 0 disables
+    pub game_state_snapshot_secs: u64,
+
+    // Daily LLM spend limit; the agency stops launching work once it is reached
+    pub daily_budget_usd: f64,
+    // Prompt plus completion tokens a day, for token-metered contracts; 0 leaves tokens uncapped
+    pub daily_token_budget: u64,
+    // Percentages of the daily budget that raise an alert, once each per day
+    pub budget_alert_thresholds: Option<String>,
+
+    // IANA timezone for chat messages, reports and the daily budget's "today"; storage stays UTC
+    pub display_timezone: String,
+    // Hour (display timezone) of the morning standup digest
+    pub standup_hour: u32,
+
+    // Directory scanned for git checkouts at startup
+    pub workspace_root: String,
+    // YAML agent roster (id, name, class, home repo, command); default squads without it
+    pub agent_roster_path: Option<String>,
+
+    // Registered visualizer callback URLs and their signing secrets
+    pub webhook_subscriptions_path: String,
+    // SQLite file with worker offsets, ingested cards, task leases and notification history
+    pub state_path: String,
+    // Files runs leave for later stages, such as handoff documents
+    pub artifacts_dir: String,
+    // Native LLM client: `openai` (or any server speaking its API) or `anthropic`; off without a key
+    pub llm_provider: String,
+    pub llm_base_url: Option<String>,
+    pub llm_model: Option<String>,
+    pub llm_api_key: Option<String>,
+    // Most tokens one reply may use
+    pub llm_max_tokens: u32,
+    // USD per 1K prompt and completion tokens, charged to the daily budget
+    pub llm_input_usd_per_1k: f64,
+    pub llm_output_usd_per_1k: f64,
+    // Have the LLM client triage new cards and issues before they are queued
+    pub triage: bool,
+    // Where registered artifacts are kept: s3://bucket/prefix, else SWARM_ARTIFACTS_DIR/registry
+    pub artifact_store: Option<String>,
+    // Largest artifact an agent may register, in MiB
+    pub artifact_max_mb: usize,
+    // S3-compatible endpoint of an s3:// artifact store, e.g. MinIO; AWS when unset
+    pub s3_endpoint: Option<String>,
+    pub s3_region: String,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
 }
 
 impl AppConfig {
@@ -28,20 +182,292 @@ impl AppConfig {
             }
         }
 
-        Ok(Self {
-            synapse_grpc_host: std::env::var("SYNAPSE_GRPC_HOST").unwrap_or_else(|_| "127.0.0.1".into()),
-            synapse_grpc_port: std::env::var("SYNAPSE_GRPC_PORT").unwrap_or_else(|_| "50051".into()),
-            gateway_port: std::env::var("GATEWAY_PORT")
-                .unwrap_or_else(|_| "18789".into())
-                .parse()
-                .unwrap_or(18789),
-
-            telegram_bot_token: std::env::var("TELEGRAM_BOT_TOKEN").ok(),
-            telegram_chat_id: std::env::var("TELEGRAM_CHAT_ID").ok(),
-
-            trello_api_key: std::env::var("TRELLO_API_KEY").ok(),
-            trello_token: std::env::var("TRELLO_TOKEN").ok(),
-            trello_board_id: std::env::var("TRELLO_BOARD_ID").ok(),
-        })
+        let settings = match config_path(std::env::args().skip(1)) {
+            Some((path, required)) => match std::fs::read_to_string(&path) {
+                Ok(raw) => {
+                    info!("⚙️ Loading configuration from {} (environment variables take precedence)", path.display());
+                    Settings::new(&raw, env_var).with_context(|| format!("invalid config file {}", path.display()))?
+                }
+                Err(e) if required => return Err(e).with_context(|| format!("reading config file {}", path.display())),
+                Err(_) => Settings::new("", env_var)?,
+            },
+            None => Settings::new("", env_var)?,
+        };
+
+        let cfg = Self::from_settings(&settings);
+        for key in settings.unknown_keys() {
+            warn!("Ignoring unknown setting `{}` in the config file", key);
+        }
+        Ok(cfg)
+    }
+
+    fn from_settings(settings: &Settings) -> Self {
+        let llm_provider = settings.var("SWARM_LLM_PROVIDER", "llm_provider").filter(|v| !v.is_empty()).unwrap_or_else(|| "openai".into());
+        // The provider's own variable is enough when swarmd shares the agents' key.
+        let provider_key = if llm_provider.eq_ignore_ascii_case("anthropic") { "ANTHROPIC_API_KEY" } else { "OPENAI_API_KEY" };
+        Self {
+            synapse_grpc_host: settings.var("SYNAPSE_GRPC_HOST", "synapse_grpc_host").unwrap_or_else(|| "127.0.0.1".into()),
+            synapse_grpc_port: settings.var("SYNAPSE_GRPC_PORT", "synapse_grpc_port").unwrap_or_else(|| "50051".into()),
+            synapse_graph: settings.var("SYNAPSE_GRAPH", "synapse_graph").filter(|v| !v.is_empty()),
+            synapse_write_buffer: settings.parse_or("SYNAPSE_WRITE_BUFFER", "synapse_write_buffer", false),
+            synapse_breaker_failures: settings.parse_or("SYNAPSE_BREAKER_FAILURES", "synapse_breaker_failures", 5),
+            synapse_breaker_cooldown_secs: settings.parse_or("SYNAPSE_BREAKER_COOLDOWN_SECS", "synapse_breaker_cooldown_secs", 30),
+            synapse_tls: settings.parse_or("SYNAPSE_TLS", "synapse_tls", false),
+            synapse_tls_ca: settings.var("SYNAPSE_TLS_CA", "synapse_tls_ca").filter(|v| !v.is_empty()),
+            synapse_tls_cert: settings.var("SYNAPSE_TLS_CERT", "synapse_tls_cert").filter(|v| !v.is_empty()),
+            synapse_tls_key: settings.var("SYNAPSE_TLS_KEY", "synapse_tls_key").filter(|v| !v.is_empty()),
+            synapse_tls_domain: settings.var("SYNAPSE_TLS_DOMAIN", "synapse_tls_domain").filter(|v| !v.is_empty()),
+            synapse_token: settings.var("SYNAPSE_TOKEN", "synapse_token").filter(|v| !v.is_empty()),
+            gateway_port: settings.parse_or("GATEWAY_PORT", "gateway_port", 18789),
+
+            telegram_bot_token: settings.var("TELEGRAM_BOT_TOKEN", "telegram_bot_token"),
+            telegram_chat_id: settings.var("TELEGRAM_CHAT_ID", "telegram_chat_id"),
+            telegram_chats: settings.var("TELEGRAM_CHATS", "telegram_chats"),
+            telegram_user_roles: settings.var("TELEGRAM_USER_ROLES", "telegram_user_roles"),
+            telegram_webhook_url: settings.var("TELEGRAM_WEBHOOK_URL", "telegram_webhook_url").filter(|v| !v.is_empty()),
+            telegram_webhook_secret: settings.var("TELEGRAM_WEBHOOK_SECRET", "telegram_webhook_secret").filter(|v| !v.is_empty()),
+
+            notification_sinks: settings.var("NOTIFICATION_SINKS", "notification_sinks").filter(|v| !v.is_empty()),
+            notification_routes: settings.var("NOTIFICATION_ROUTES", "notification_routes").filter(|v| !v.is_empty()),
+            notification_dedupe_secs: settings.parse_or("NOTIFICATION_DEDUPE_SECS", "notification_dedupe_secs", 600),
+            slack_webhook_url: settings.var("SLACK_WEBHOOK_URL", "slack_webhook_url").filter(|v| !v.is_empty()),
+            notify_webhook_url: settings.var("NOTIFY_WEBHOOK_URL", "notify_webhook_url").filter(|v| !v.is_empty()),
+
+            slack_bot_token: settings.var("SLACK_BOT_TOKEN", "slack_bot_token").filter(|v| !v.is_empty()),
+            slack_channel_id: settings.var("SLACK_CHANNEL_ID", "slack_channel_id").filter(|v| !v.is_empty()),
+            slack_signing_secret: settings.var("SLACK_SIGNING_SECRET", "slack_signing_secret").filter(|v| !v.is_empty()),
+
+            discord_bot_token: settings.var("DISCORD_BOT_TOKEN", "discord_bot_token").filter(|v| !v.is_empty()),
+            discord_channel_id: settings.var("DISCORD_CHANNEL_ID", "discord_channel_id").filter(|v| !v.is_empty()),
+
+            trello_api_key: settings.var("TRELLO_API_KEY", "trello_api_key"),
+            trello_token: settings.var("TRELLO_TOKEN", "trello_token"),
+            trello_board_id: settings.var("TRELLO_BOARD_ID", "trello_board_id"),
+            trello_reconcile_hour: settings.parse_or("TRELLO_RECONCILE_HOUR", "trello_reconcile_hour", 3),
+            trello_poll_secs: settings.parse_or("TRELLO_POLL_SECS", "trello_poll_secs", 10),
+
+            github_token: settings.var("GITHUB_TOKEN", "github_token").filter(|v| !v.is_empty()),
+            github_repos: settings.var("GITHUB_REPOS", "github_repos").filter(|v| !v.is_empty()),
+            github_poll_secs: settings.parse_or("GITHUB_POLL_SECS", "github_poll_secs", 60),
+
+            schedules_poll_secs: settings.parse_or("SCHEDULES_POLL_SECS", "schedules_poll_secs", 30),
+
+            disabled_workers: settings.var("DISABLED_WORKERS", "disabled_workers").filter(|v| !v.is_empty()),
+
+            hook_pre_assign: settings.var("SWARM_HOOK_PRE_ASSIGN", "hook_pre_assign"),
+            hook_post_complete: settings.var("SWARM_HOOK_POST_COMPLETE", "hook_post_complete"),
+            hook_on_fail: settings.var("SWARM_HOOK_ON_FAIL", "hook_on_fail"),
+
+            agency_max_attempts: settings.parse_or("AGENCY_MAX_ATTEMPTS", "agency_max_attempts", 3),
+            agency_retry_base_secs: settings.parse_or("AGENCY_RETRY_BASE_SECS", "agency_retry_base_secs", 30),
+            agency_retry_max_secs: settings.parse_or("AGENCY_RETRY_MAX_SECS", "agency_retry_max_secs", 900),
+            agency_task_timeout_secs: settings.parse_or("AGENCY_TASK_TIMEOUT_SECS", "agency_task_timeout_secs", 1800),
+            agency_stall_minutes: settings.parse_or("AGENCY_STALL_MINUTES", "agency_stall_minutes", 10),
+            agency_stream_output: settings.parse_or("AGENCY_STREAM_OUTPUT", "agency_stream_output", true),
+            agency_chores: settings.var("AGENCY_CHORES", "agency_chores").filter(|v| !v.is_empty()),
+            chore_daily_budget_usd: settings.parse_or("CHORE_DAILY_BUDGET_USD", "chore_daily_budget_usd", 1.0),
+            chore_interval_hours: settings.parse_or("CHORE_INTERVAL_HOURS", "chore_interval_hours", 24),
+            priority_aging_hours: settings.parse_or("PRIORITY_AGING_HOURS", "priority_aging_hours", 24),
+            level_thresholds: settings.var("LEVEL_THRESHOLDS", "level_thresholds").filter(|v| !v.is_empty()),
+            escalation_owners: settings.var("ESCALATION_OWNERS", "escalation_owners").filter(|v| !v.is_empty()),
+            scheduler_script: settings.var("SCHEDULER_SCRIPT", "scheduler_script").filter(|v| !v.is_empty()),
+            assignment_strategy: settings.var("ASSIGNMENT_STRATEGY", "assignment_strategy").filter(|v| !v.is_empty()),
+            assignment_strategies: settings.var("ASSIGNMENT_STRATEGIES", "assignment_strategies").filter(|v| !v.is_empty()),
+
+            api_tokens: settings.var("SWARM_API_TOKENS", "api_tokens"),
+            demo_mode: settings.parse_or("SWARM_DEMO_MODE", "demo_mode", false),
+            demo_seed: settings.parse_or("SWARM_DEMO_SEED", "demo_seed", 42),
+            demo_rate_limit: settings.parse_or("DEMO_RATE_LIMIT", "demo_rate_limit", 60),
+            game_state_snapshot_secs: settings.parse_or("GAME_STATE_SNAPSHOT_SECS", "game_state_snapshot_secs", 300),
+
+            daily_budget_usd: settings.parse_or("DAILY_BUDGET_USD", "daily_budget_usd", 10.0),
+            daily_token_budget: settings.parse_or("DAILY_TOKEN_BUDGET", "daily_token_budget", 0),
+            budget_alert_thresholds: settings.var("BUDGET_ALERT_THRESHOLDS", "budget_alert_thresholds").filter(|v| !v.is_empty()),
+
+            display_timezone: settings.var("SWARM_TIMEZONE", "display_timezone")
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "UTC".into()),
+            standup_hour: settings.parse_or("STANDUP_HOUR", "standup_hour", 9),
+
+            workspace_root: settings.var("SWARM_WORKSPACE_ROOT", "workspace_root")
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| ".".into()),
+            agent_roster_path: settings.var("AGENT_ROSTER_PATH", "agent_roster_path").filter(|v| !v.is_empty()),
+
+            webhook_subscriptions_path: settings.var("WEBHOOK_SUBSCRIPTIONS_PATH", "webhook_subscriptions_path")
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "data/webhook_subscriptions.json".into()),
+            state_path: settings.var("SWARM_STATE_PATH", "state_path")
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "data/swarmd-state.db".into()),
+            artifacts_dir: settings.var("SWARM_ARTIFACTS_DIR", "artifacts_dir")
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "data/artifacts".into()),
+            llm_api_key: settings
+                .var("SWARM_LLM_API_KEY", "llm_api_key")
+                .filter(|v| !v.is_empty())
+                .or_else(|| settings.var(provider_key, "llm_api_key"))
+                .filter(|v| !v.is_empty()),
+            llm_provider,
+            llm_base_url: settings.var("SWARM_LLM_BASE_URL", "llm_base_url").filter(|v| !v.is_empty()),
+            llm_model: settings.var("SWARM_LLM_MODEL", "llm_model").filter(|v| !v.is_empty()),
+            llm_max_tokens: settings.parse_or("SWARM_LLM_MAX_TOKENS", "llm_max_tokens", 1024),
+            llm_input_usd_per_1k: settings.parse_or("SWARM_LLM_INPUT_USD_PER_1K", "llm_input_usd_per_1k", 0.005),
+            llm_output_usd_per_1k: settings.parse_or("SWARM_LLM_OUTPUT_USD_PER_1K", "llm_output_usd_per_1k", 0.015),
+            triage: settings.parse_or("SWARM_TRIAGE", "triage", false),
+            artifact_store: settings.var("SWARM_ARTIFACT_STORE", "artifact_store").filter(|v| !v.is_empty()),
+            artifact_max_mb: settings.parse_or("SWARM_ARTIFACT_MAX_MB", "artifact_max_mb", 25),
+            s3_endpoint: settings.var("SWARM_S3_ENDPOINT", "s3_endpoint").filter(|v| !v.is_empty()),
+            s3_region: settings.var("SWARM_S3_REGION", "s3_region")
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "us-east-1".into()),
+            s3_access_key_id: settings.var("AWS_ACCESS_KEY_ID", "s3_access_key_id").filter(|v| !v.is_empty()),
+            s3_secret_access_key: settings.var("AWS_SECRET_ACCESS_KEY", "s3_secret_access_key").filter(|v| !v.is_empty()),
+        }
+    }
+
+    /// Whether `worker` is left out of `DISABLED_WORKERS`.
+    pub fn worker_enabled(&self, worker: &str) -> bool {
+        !self
+            .disabled_workers
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .any(|w| w.trim().eq_ignore_ascii_case(worker))
+    }
+}
+
+/// A comma-separated list of thresholds, such as `BUDGET_ALERT_THRESHOLDS`, ascending and
+/// without repeats. Entries that are not positive whole numbers are skipped; a trailing `%` is
+/// allowed.
+pub fn thresholds<T: FromStr + Ord + Default>(raw: &str) -> Vec<T> {
+    let mut thresholds: Vec<T> = raw
+        .split(',')
+        .filter_map(|t| t.trim().trim_end_matches('%').parse().ok())
+        .filter(|t| *t > T::default())
+        .collect();
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    thresholds
+}
+
+/// The file the watcher polls: the one [`AppConfig::load`] reads, or [`DEFAULT_CONFIG_FILE`]
+/// so that creating it later is noticed too.
+pub fn config_file() -> PathBuf {
+    config_path(std::env::args().skip(1)).map_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE), |(path, _)| path)
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Where the config file is: `--config <path>`, else `SWARMD_CONFIG`, else `swarmd.toml`
+/// if there is one. The flag and the variable must point at an existing file.
+fn config_path(args: impl IntoIterator<Item = String>) -> Option<(PathBuf, bool)> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(|p| (PathBuf::from(p), true));
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some((PathBuf::from(path), true));
+        }
+    }
+    if let Some(path) = std::env::var("SWARMD_CONFIG").ok().filter(|v| !v.is_empty()) {
+        return Some((PathBuf::from(path), true));
+    }
+    let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+    default.exists().then_some((default, false))
+}
+
+type EnvLookup = Box<dyn Fn(&str) -> Option<String>>;
+
+/// Settings from the config file, each overridden by its environment variable.
+/// File keys are the `AppConfig` field names; tables flatten into them, so
+/// `[agency] max_attempts = 5` sets `agency_max_attempts`.
+struct Settings {
+    file: BTreeMap<String, String>,
+    env: EnvLookup,
+    used: RefCell<BTreeSet<String>>,
+}
+
+impl Settings {
+    fn new(file: &str, env: impl Fn(&str) -> Option<String> + 'static) -> Result<Self> {
+        let table: toml::Table = file.parse()?;
+        let mut flat = BTreeMap::new();
+        flatten("", &table, &mut flat);
+        Ok(Self { file: flat, env: Box::new(env), used: RefCell::default() })
+    }
+
+    fn var(&self, env: &str, key: &str) -> Option<String> {
+        self.used.borrow_mut().insert(key.to_string());
+        (self.env)(env).or_else(|| self.file.get(key).cloned())
+    }
+
+    fn parse_or<T: std::str::FromStr>(&self, env: &str, key: &str, default: T) -> T {
+        self.var(env, key).and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+    }
+
+    /// File keys no setting asked for, most likely typos.
+    fn unknown_keys(&self) -> Vec<String> {
+        let used = self.used.borrow();
+        self.file.keys().filter(|k| !used.contains(*k)).cloned().collect()
+    }
+}
+
+/// Lists become comma-separated, like their environment variables.
+fn flatten(prefix: &str, table: &toml::Table, out: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}_{}", prefix, key) };
+        match value {
+            toml::Value::Table(inner) => flatten(&key, inner, out),
+            value => {
+                out.insert(key, scalar(value));
+            }
+        }
+    }
+}
+
+fn scalar(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(items) => items.iter().map(scalar).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let file = r#"
+            gateway_port = 9000
+            daily_budget_usd = 25.5
+            github_repos = ["acme/api", "acme/web"]
+            gatway_port = 1
+
+            [agency]
+            max_attempts = 5
+            task_timeout_secs = 600
+        "#;
+        let settings = Settings::new(file, |name| (name == "AGENCY_MAX_ATTEMPTS").then(|| "7".to_string())).unwrap();
+
+        assert_eq!(settings.parse_or("GATEWAY_PORT", "gateway_port", 18789u16), 9000);
+        assert_eq!(settings.parse_or("DAILY_BUDGET_USD", "daily_budget_usd", 10.0), 25.5);
+        assert_eq!(settings.var("GITHUB_REPOS", "github_repos").as_deref(), Some("acme/api,acme/web"));
+        assert_eq!(settings.parse_or("AGENCY_MAX_ATTEMPTS", "agency_max_attempts", 3u32), 7);
+        assert_eq!(settings.parse_or("AGENCY_TASK_TIMEOUT_SECS", "agency_task_timeout_secs", 1800u64), 600);
+        assert_eq!(settings.var("SWARM_TIMEZONE", "display_timezone"), None);
+        assert_eq!(settings.unknown_keys(), vec!["gatway_port"]);
+        assert!(Settings::new("gateway_port = ", |_| None).is_err());
+    }
+
+    #[test]
+    fn finds_the_config_file_flag() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(config_path(args(&["swarmd", "--config", "/etc/swarmd.toml"])), Some(("/etc/swarmd.toml".into(), true)));
+        assert_eq!(config_path(args(&["swarmd", "--config=prod.toml"])), Some(("prod.toml".into(), true)));
     }
 }
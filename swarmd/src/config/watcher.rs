@@ -0,0 +1,159 @@
+//! Hot reload. On SIGHUP, or when the config file changes, the configuration is loaded again
+//! and the settings in [`RELOADABLE`] are published to the workers through a `watch` channel.
+//! Changes to anything else (ports, credentials, Synapse address, timezone, roster) are
+//! reported as needing a restart and otherwise ignored, so every worker keeps seeing one
+//! consistent configuration.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use super::{config_file, AppConfig};
+
+/// Settings applied without a restart.
+pub const RELOADABLE: &[&str] = &[
+    "daily_budget_usd",
+    "daily_token_budget",
+    "budget_alert_thresholds",
+    "trello_poll_secs",
+    "github_repos",
+    "github_poll_secs",
+    "schedules_poll_secs",
+    "disabled_workers",
+    "agency_max_attempts",
+    "agency_retry_base_secs",
+    "agency_retry_max_secs",
+    "agency_task_timeout_secs",
+    "agency_stall_minutes",
+    "agency_stream_output",
+    "agency_chores",
+    "chore_daily_budget_usd",
+    "chore_interval_hours",
+    "priority_aging_hours",
+    "level_thresholds",
+    "escalation_owners",
+    "scheduler_script",
+    "assignment_strategy",
+    "assignment_strategies",
+    "hook_pre_assign",
+    "hook_post_complete",
+    "hook_on_fail",
+];
+/// How often the config file's modification time is checked.
+const FILE_POLL: Duration = Duration::from_secs(5);
+
+/// Publishes `initial` and every reload after it.
+pub fn watch(initial: AppConfig) -> watch::Receiver<AppConfig> {
+    let (tx, rx) = watch::channel(initial);
+    tokio::spawn(run(tx));
+    rx
+}
+
+async fn run(tx: watch::Sender<AppConfig>) {
+    let mut hangup = signal(SignalKind::hangup())
+        .map_err(|e| warn!("Cannot listen for SIGHUP, only file changes will reload the config: {}", e))
+        .ok();
+    let path = config_file();
+    let mut last_modified = modified(&path);
+
+    loop {
+        let reason = tokio::select! {
+            _ = next_hangup(&mut hangup) => "SIGHUP",
+            _ = tokio::time::sleep(FILE_POLL) => {
+                let now = modified(&path);
+                if now == last_modified {
+                    continue;
+                }
+                last_modified = now;
+                "config file change"
+            }
+        };
+        reload(&tx, reason);
+    }
+}
+
+async fn next_hangup(hangup: &mut Option<Signal>) {
+    match hangup {
+        Some(signal) => {
+            signal.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn reload(tx: &watch::Sender<AppConfig>, reason: &str) {
+    let loaded = match AppConfig::load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            warn!("Config reload on {} failed; keeping the current settings: {:#}", reason, e);
+            return;
+        }
+    };
+    let current = tx.borrow().clone();
+    let (next, applied, ignored) = match merge(&current, &loaded) {
+        Ok(merged) => merged,
+        Err(e) => {
+            warn!("Config reload on {} failed; keeping the current settings: {}", reason, e);
+            return;
+        }
+    };
+    if !ignored.is_empty() {
+        warn!("Config reload on {}: restart swarmd to apply {}", reason, ignored.join(", "));
+    }
+    if applied.is_empty() {
+        info!("🔁 Config reload on {}: nothing to apply", reason);
+        return;
+    }
+    info!("🔁 Config reload on {}: applied {}", reason, applied.join(", "));
+    tx.send_replace(next);
+}
+
+/// `current` with the [`RELOADABLE`] settings of `loaded`, plus the names of the settings
+/// applied and of those that changed but need a restart.
+fn merge(current: &AppConfig, loaded: &AppConfig) -> anyhow::Result<(AppConfig, Vec<String>, Vec<String>)> {
+    let Value::Object(mut next) = serde_json::to_value(current)? else { anyhow::bail!("config is not a map") };
+    let Value::Object(loaded) = serde_json::to_value(loaded)? else { anyhow::bail!("config is not a map") };
+
+    let mut applied = Vec::new();
+    let mut ignored = Vec::new();
+    for (key, value) in loaded {
+        if next.get(&key) == Some(&value) {
+            continue;
+        }
+        if RELOADABLE.contains(&key.as_str()) {
+            next.insert(key.clone(), value);
+            applied.push(key);
+        } else {
+            ignored.push(key);
+        }
+    }
+    Ok((serde_json::from_value(Value::Object(next))?, applied, ignored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+
+    #[test]
+    fn only_reloadable_settings_are_applied() {
+        let load = |file: &str| AppConfig::from_settings(&Settings::new(file, |_| None).unwrap());
+        let current = load("gateway_port = 9000\ndaily_budget_usd = 10.0");
+        let loaded = load("gateway_port = 9100\ndaily_budget_usd = 25.0\ndisabled_workers = \"trello\"");
+
+        let (next, applied, ignored) = merge(&current, &loaded).unwrap();
+        assert_eq!(applied, vec!["daily_budget_usd", "disabled_workers"]);
+        assert_eq!(ignored, vec!["gateway_port"]);
+        assert_eq!(next.gateway_port, 9000);
+        assert_eq!(next.daily_budget_usd, 25.0);
+        assert!(!next.worker_enabled("trello") && next.worker_enabled("agency"));
+    }
+}
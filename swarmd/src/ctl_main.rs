@@ -0,0 +1,249 @@
+//! `swarmctl`: the chat commands from a terminal. Talks to a running swarmd through the
+//! gateway API, so it needs nothing but the gateway URL and, when tokens are configured,
+//! an operator token.
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use futures_util::StreamExt;
+use serde::Serialize;
+use swarm_client::contracts::{NotificationEnvelope, NotificationQuery, TaskRequest};
+use swarm_client::SwarmClient;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+
+#[derive(Debug, Parser)]
+#[command(name = "swarmctl", version, about = "Control a running swarmd from the terminal")]
+struct Cli {
+    /// Gateway base URL.
+    #[arg(long, global = true, env = "SWARM_GATEWAY_URL", default_value = swarm_client::DEFAULT_BASE_URL)]
+    url: String,
+    /// API token; needs the operator role when the gateway has tokens configured.
+    #[arg(long, global = true, env = "SWARM_API_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Print the gateway's JSON instead of a table.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+enum Command {
+    /// System status, maintenance mode and queue size.
+    Status,
+    /// Unfinished tasks, optionally only those in STATE.
+    Tasks { state: Option<String> },
+    /// The agents and what they are doing.
+    Agents,
+    /// Emergency halt: no new tasks are scheduled until `resume`.
+    Halt,
+    /// Back to operational after a halt.
+    Resume,
+    /// Follow notifications (traces, alerts, approval requests) as they happen.
+    Tail {
+        /// Print the last N notifications first.
+        #[arg(long, default_value_t = 0, value_name = "N")]
+        history: usize,
+    },
+    /// List the background workers, or stop, start or restart one (admin token).
+    Workers {
+        #[arg(value_parser = ["stop", "start", "restart"], requires = "name")]
+        action: Option<String>,
+        name: Option<String>,
+    },
+    /// Queue a task for the agents.
+    Inject {
+        title: String,
+        /// Higher runs first.
+        #[arg(long)]
+        priority: Option<i64>,
+        /// Repository id the task belongs to.
+        #[arg(long, value_name = "ID")]
+        repo: Option<String>,
+        /// Task (IRI) that must be done first; repeat for several.
+        #[arg(long, value_name = "TASK")]
+        after: Vec<String>,
+        /// Hold it until a human approves it.
+        #[arg(long)]
+        approval: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut client = SwarmClient::new(&cli.url)?;
+    if let Some(token) = &cli.token {
+        client = client.with_token(token.clone());
+    }
+
+    match cli.command {
+        Command::Status => {
+            let state = client.game_state().await?;
+            if cli.json {
+                return print_json(&state);
+            }
+            println!("status       {}", state.system_status.as_str());
+            if let Some(banner) = &state.maintenance {
+                println!("maintenance  since {}{}", banner.since, banner.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default());
+            }
+            println!("agents       {}", state.party.len());
+            println!("tasks        {} active", state.active_quests.len());
+            if let Some(budget) = &state.daily_budget {
+                println!("spend today  ${:.2} of ${:.2}", budget.spent, budget.max);
+            }
+        }
+        Command::Tasks { state } => {
+            let tasks = client.tasks(state.as_deref()).await?;
+            if cli.json {
+                return print_json(&tasks);
+            }
+            let rows: Vec<[String; 3]> = tasks
+                .into_iter()
+                .map(|t| [t.state, t.agent.map(|a| short_id(&a)).unwrap_or_else(|| "-".into()), format!("{}  {}", t.title, t.id)])
+                .collect();
+            print_table(["STATE", "AGENT", "TASK"], &rows);
+        }
+        Command::Agents => {
+            let party = client.game_state().await?.party;
+            if cli.json {
+                return print_json(&party);
+            }
+            let rows: Vec<[String; 3]> = party.into_iter().map(|a| [a.name, a.class_name, a.current_action]).collect();
+            print_table(["AGENT", "CLASS", "DOING"], &rows);
+        }
+        Command::Halt | Command::Resume => {
+            let ack = if cli.command == Command::Halt { client.halt().await? } else { client.resume().await? };
+            if cli.json {
+                return print_json(&ack);
+            }
+            println!("System {}", ack.system_status.as_str());
+        }
+        Command::Tail { history } => tail(&client, history, cli.json).await?,
+        Command::Workers { action, name } => {
+            let workers = match (action.as_deref(), name.as_deref()) {
+                (Some("stop"), Some(name)) => vec![client.stop_worker(name).await?],
+                (Some("start"), Some(name)) => vec![client.start_worker(name).await?],
+                (Some("restart"), Some(name)) => vec![client.restart_worker(name).await?],
+                _ => client.workers().await?,
+            };
+            if cli.json {
+                return print_json(&workers);
+            }
+            let rows: Vec<[String; 3]> = workers
+                .into_iter()
+                .map(|w| {
+                    let pace = w.poll_interval_secs.map(|secs| format!(", polling every {}s", secs)).unwrap_or_default();
+                    [w.name, format!("{:?}", w.state).to_lowercase(), format!("since {} ({} starts{})", w.since, w.starts, pace)]
+                })
+                .collect();
+            print_table(["WORKER", "STATE", ""], &rows);
+        }
+        Command::Inject { title, priority, repo, after, approval } => {
+            let request = TaskRequest { title, priority, repo_id: repo, depends_on: after, requires_approval: approval };
+            let task = client.create_task(&request).await?;
+            if cli.json {
+                return print_json(&task);
+            }
+            println!("Queued {} ({})", task.title, task.id);
+        }
+    }
+    Ok(())
+}
+
+async fn tail(client: &SwarmClient, history: usize, json: bool) -> Result<()> {
+    if history > 0 {
+        for note in client.notifications(&NotificationQuery { limit: Some(history), ..NotificationQuery::default() }).await? {
+            print_notification(&note, json)?;
+        }
+    }
+    let url = client.notifications_url();
+    let mut request = url.as_str().into_client_request()?;
+    if let Some(token) = client.token() {
+        request.headers_mut().insert(AUTHORIZATION, format!("Bearer {}", token).parse()?);
+    }
+    let (mut stream, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .with_context(|| format!("connecting to {}", url))?;
+    eprintln!("Following notifications from {} (Ctrl-C to stop)", client.notifications_url());
+
+    while let Some(msg) = stream.next().await {
+        let msg = msg?;
+        let Ok(text) = msg.to_text() else { continue };
+        if let Ok(note) = serde_json::from_str::<NotificationEnvelope>(text) {
+            print_notification(&note, json)?;
+        }
+    }
+    eprintln!("The gateway closed the stream");
+    Ok(())
+}
+
+fn print_notification(note: &NotificationEnvelope, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(note)?);
+        return Ok(());
+    }
+    let at = chrono::DateTime::parse_from_rfc3339(&note.at)
+        .map(|at| at.with_timezone(&chrono::Local))
+        .unwrap_or_else(|_| chrono::Local::now());
+    println!("{}  {}", at.format("%H:%M:%S"), note.text);
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// The last path segment of an IRI, which is what people call an agent.
+fn short_id(iri: &str) -> String {
+    iri.rsplit('/').next().unwrap_or(iri).to_string()
+}
+
+/// Left-aligned columns; the last one is not padded.
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    if rows.is_empty() {
+        println!("(none)");
+        return;
+    }
+    let mut widths = header.map(|h| h.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| if i + 1 == N { cell.to_string() } else { format!("{:<width$}", cell, width = widths[i]) })
+            .collect();
+        println!("{}", padded.join("  "));
+    };
+    line(header.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_global_options() {
+        let cli = Cli::try_parse_from(["swarmctl", "inject", "Fix the login page", "--priority", "3", "--url", "http://gw:1"]).unwrap();
+        assert_eq!(cli.command, Command::Inject { title: "Fix the login page".into(), priority: Some(3), repo: None, after: vec![], approval: false });
+        assert_eq!(cli.url, "http://gw:1");
+
+        let cli = Cli::try_parse_from(["swarmctl", "--json", "tasks", "PROCESSING"]).unwrap();
+        assert_eq!(cli.command, Command::Tasks { state: Some("PROCESSING".into()) });
+        assert!(cli.json);
+        assert!(Cli::try_parse_from(["swarmctl"]).is_err());
+        assert!(Cli::try_parse_from(["swarmctl", "workers", "stop"]).is_err());
+        let cli = Cli::try_parse_from(["swarmctl", "workers", "restart", "trello"]).unwrap();
+        assert_eq!(cli.command, Command::Workers { action: Some("restart".into()), name: Some("trello".into()) });
+        assert_eq!(short_id("http://swarm.os/agent/Coder_api"), "Coder_api");
+    }
+}
@@ -0,0 +1,127 @@
+//! Ordering between tasks. A task lists the tasks it waits for as `swarm:dependsOn`, and the
+//! agency only offers it once every one of them is `DONE`. Initiatives write these links for
+//! their blueprint steps; the gateway writes them for tasks queued with `depends_on`.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::server::contracts::{TaskGraph, TaskGraphEdge, TaskGraphNode};
+use crate::store::Synapse;
+use crate::tasks::{self, TaskSummary};
+
+pub use crate::initiatives::DEPENDS_ON;
+
+/// Task IRI for a path segment: a bare id names a gateway task, anything with a scheme is
+/// taken as the IRI itself.
+pub fn task_iri(id: &str) -> String {
+    let id = id.trim();
+    if id.contains("://") { id.to_string() } else { format!("http://swarm.os/task/{}", id) }
+}
+
+/// Every `swarm:dependsOn` link, as (task, dependency).
+pub async fn links(synapse: &Synapse) -> anyhow::Result<Vec<(String, String)>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?task ?dependency
+        WHERE { ?task swarm:dependsOn ?dependency . }
+    "#;
+    let res_json = synapse.query(query).await?;
+    let rows = serde_json::from_str::<Vec<serde_json::Value>>(&res_json).unwrap_or_default();
+    let field = |row: &serde_json::Value, name: &str| {
+        row.get(format!("?{name}").as_str())
+            .or_else(|| row.get(name))
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim_matches(|c| c == '<' || c == '>').to_string())
+    };
+    Ok(rows.iter().filter_map(|row| Some((field(row, "task")?, field(row, "dependency")?))).collect())
+}
+
+/// The tasks `task_id` waits for and the tasks waiting for it, transitively; `None` when there
+/// is no such task.
+pub async fn graph(synapse: &Synapse, task_id: &str) -> anyhow::Result<Option<TaskGraph>> {
+    let tasks = tasks::all_tasks(synapse).await?;
+    let links = links(synapse).await?;
+    Ok(build(task_id, &tasks, &links))
+}
+
+fn build(root: &str, tasks: &[TaskSummary], links: &[(String, String)]) -> Option<TaskGraph> {
+    let by_id: BTreeMap<&str, &TaskSummary> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    by_id.get(root)?;
+
+    let mut upstream: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut downstream: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (task, dependency) in links {
+        upstream.entry(task.as_str()).or_default().push(dependency.as_str());
+        downstream.entry(dependency.as_str()).or_default().push(task.as_str());
+    }
+
+    // Both directions from the root; the visited set keeps a cycle from looping forever.
+    let mut reached: BTreeSet<&str> = BTreeSet::from([root]);
+    for direction in [&upstream, &downstream] {
+        let mut queue = VecDeque::from([root]);
+        let mut seen = BTreeSet::from([root]);
+        while let Some(task) = queue.pop_front() {
+            for next in direction.get(task).into_iter().flatten() {
+                if seen.insert(*next) {
+                    reached.insert(*next);
+                    queue.push_back(*next);
+                }
+            }
+        }
+    }
+
+    let state_of = |id: &str| by_id.get(id).map_or("UNKNOWN", |t| t.state.as_str()).to_string();
+    let nodes = reached
+        .iter()
+        .map(|id| TaskGraphNode {
+            id: id.to_string(),
+            title: by_id.get(id).map(|t| t.title.clone()).unwrap_or_default(),
+            state: state_of(id),
+            waiting_on: upstream
+                .get(id)
+                .into_iter()
+                .flatten()
+                .filter(|dep| state_of(dep) != "DONE")
+                .map(|dep| dep.to_string())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        })
+        .collect();
+    let edges = links
+        .iter()
+        .filter(|(task, dependency)| reached.contains(task.as_str()) && reached.contains(dependency.as_str()))
+        .map(|(task, dependency)| TaskGraphEdge { task: task.clone(), depends_on: dependency.clone() })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    Some(TaskGraph { task_id: root.to_string(), nodes, edges })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, state: &str) -> TaskSummary {
+        TaskSummary { id: id.to_string(), title: id.to_uppercase(), state: state.to_string(), agent: None }
+    }
+
+    #[test]
+    fn the_graph_follows_dependencies_both_ways_and_survives_cycles() {
+        let tasks = [task("a", "DONE"), task("b", "PROCESSING"), task("c", "REQUIREMENTS"), task("d", "REQUIREMENTS"), task("x", "TODO")];
+        let link = |t: &str, d: &str| (t.to_string(), d.to_string());
+        // c waits for a and b, d waits for c; b and x wait for each other, x is unrelated to c.
+        let links = [link("c", "a"), link("c", "b"), link("d", "c"), link("b", "x"), link("x", "b"), link("c", "b")];
+
+        let graph = build("c", &tasks, &links).unwrap();
+        let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "x"]);
+        assert_eq!(graph.edges.len(), 5);
+        let c = graph.nodes.iter().find(|n| n.id == "c").unwrap();
+        assert_eq!((c.title.as_str(), c.waiting_on.clone()), ("C", vec!["b".to_string()]));
+        assert!(graph.nodes.iter().find(|n| n.id == "a").unwrap().waiting_on.is_empty());
+
+        assert_eq!(build("d", &tasks, &links).unwrap().nodes.len(), 5);
+        assert!(build("missing", &tasks, &links).is_none());
+        assert_eq!(task_iri("1234"), "http://swarm.os/task/1234");
+    }
+}
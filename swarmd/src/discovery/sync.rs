@@ -0,0 +1,387 @@
+//! Differential sync between what discovery finds and what Synapse already holds. Synapse only
+//! appends, so nothing is deleted: an agent that leaves the roster is marked `Retired` (and its
+//! open tasks unassigned), a checkout that disappears is archived, and a checkout that moved to
+//! a new directory keeps its IRI and gets its new name and path. Changed names, classes and
+//! home repositories are appended; Synapse keeps no order among them, so where a subject has
+//! several the sync reads the greatest, the same whatever order the rows come in.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+use super::DiscoveredRepo;
+use crate::notifications::Message;
+use crate::provenance::Source;
+use crate::roster::{AgentSpec, AGENT_PREFIX};
+use crate::store::Synapse;
+use crate::tasks::{self, AGENT_STATUS, UNASSIGNED_FROM};
+
+pub const REPOSITORY_PREFIX: &str = "http://swarm.os/repository/";
+pub const RETIRED_AT: &str = "http://swarm.os/ontology/retiredAt";
+/// A repository is archived while it has more `archivedAt` than `restoredAt` markers.
+pub const ARCHIVED_AT: &str = "http://swarm.os/ontology/archivedAt";
+pub const RESTORED_AT: &str = "http://swarm.os/ontology/restoredAt";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegisteredAgent {
+    pub name: Option<String>,
+    pub class: Option<String>,
+    /// IRI of the home repository.
+    pub repo: Option<String>,
+    pub retired: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegisteredRepo {
+    pub name: Option<String>,
+    pub remote_url: Option<String>,
+    pub archived: bool,
+}
+
+/// Agents and repositories already in Synapse, keyed by IRI.
+#[derive(Debug, Default)]
+pub struct Registered {
+    pub agents: BTreeMap<String, RegisteredAgent>,
+    pub repos: BTreeMap<String, RegisteredRepo>,
+}
+
+/// A checkout found in a new directory; it keeps the IRI `id` of its old registration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncPlan {
+    pub added_repos: Vec<String>,
+    pub renamed_repos: Vec<Rename>,
+    pub archived_repos: Vec<String>,
+    pub restored_repos: Vec<String>,
+    pub added_agents: Vec<String>,
+    /// Agent IRI and the fields that changed.
+    pub updated_agents: Vec<(String, Vec<&'static str>)>,
+    pub retired_agents: Vec<String>,
+    /// Retired agents listed again; retirement is final, so they need a new id.
+    pub refused_agents: Vec<String>,
+}
+
+pub async fn registered(synapse: &Synapse) -> anyhow::Result<Registered> {
+    let agents = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?agent ?name ?class ?status ?repo
+        WHERE {
+            ?agent a swarm:Agent .
+            OPTIONAL { ?agent swarm:name ?name }
+            OPTIONAL { ?agent swarm:class ?class }
+            OPTIONAL { ?agent swarm:status ?status }
+            OPTIONAL { ?repo swarm:hasPopulation ?agent }
+        }
+    "#;
+    let repos = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?repo ?name ?remote ?archived ?restored
+        WHERE {
+            ?repo a swarm:Repository .
+            OPTIONAL { ?repo swarm:name ?name }
+            OPTIONAL { ?repo swarm:remoteUrl ?remote }
+            OPTIONAL { ?repo swarm:archivedAt ?archived }
+            OPTIONAL { ?repo swarm:restoredAt ?restored }
+        }
+    "#;
+    let agent_rows: Vec<Value> = serde_json::from_str(&synapse.query(agents).await?).unwrap_or_default();
+    let repo_rows: Vec<Value> = serde_json::from_str(&synapse.query(repos).await?).unwrap_or_default();
+    Ok(Registered { agents: parse_agents(&agent_rows), repos: parse_repos(&repo_rows) })
+}
+
+fn field(row: &Value, name: &str) -> Option<String> {
+    row.get(name)
+        .or_else(|| row.get(format!("?{name}").as_str()))
+        .and_then(Value::as_str)
+        .map(|v| tasks::literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+}
+
+/// Of the values `field` takes in `row` and the one kept so far, the greatest, so the value
+/// read does not depend on the order Synapse returns rows in.
+fn greatest(kept: Option<String>, row: &Value, field_name: &str) -> Option<String> {
+    kept.into_iter().chain(field(row, field_name)).max()
+}
+
+fn parse_agents(rows: &[Value]) -> BTreeMap<String, RegisteredAgent> {
+    let mut agents: BTreeMap<String, RegisteredAgent> = BTreeMap::new();
+    for row in rows {
+        let Some(iri) = field(row, "agent") else { continue };
+        let agent = agents.entry(iri).or_default();
+        agent.name = greatest(agent.name.take(), row, "name");
+        agent.class = greatest(agent.class.take(), row, "class");
+        agent.repo = greatest(agent.repo.take(), row, "repo");
+        agent.retired |= field(row, "status").as_deref() == Some("Retired");
+    }
+    agents
+}
+
+fn parse_repos(rows: &[Value]) -> BTreeMap<String, RegisteredRepo> {
+    let mut repos: BTreeMap<String, (RegisteredRepo, BTreeSet<String>, BTreeSet<String>)> = BTreeMap::new();
+    for row in rows {
+        let Some(iri) = field(row, "repo") else { continue };
+        let (repo, archived, restored) = repos.entry(iri).or_default();
+        repo.name = greatest(repo.name.take(), row, "name");
+        repo.remote_url = greatest(repo.remote_url.take(), row, "remote");
+        archived.extend(field(row, "archived"));
+        restored.extend(field(row, "restored"));
+    }
+    repos
+        .into_iter()
+        .map(|(iri, (repo, archived, restored))| (iri, RegisteredRepo { archived: archived.len() > restored.len(), ..repo }))
+        .collect()
+}
+
+fn same_remote(a: &str, b: &str) -> bool {
+    let normalize = |url: &str| url.trim_end_matches('/').trim_end_matches(".git").to_ascii_lowercase();
+    normalize(a) == normalize(b)
+}
+
+/// Gives each new checkout whose origin matches a registered repository that was not found
+/// again the old id, so its IRI and the agents living in it survive the move.
+pub fn follow_renames(registered: &Registered, repos: &mut [DiscoveredRepo]) -> Vec<Rename> {
+    let found: BTreeSet<String> = repos.iter().map(|r| format!("{}{}", REPOSITORY_PREFIX, r.id)).collect();
+    let mut taken = BTreeSet::new();
+    let mut renames = Vec::new();
+    for repo in repos.iter_mut() {
+        let Some(remote) = repo.remote_url.as_deref() else { continue };
+        if registered.repos.contains_key(&format!("{}{}", REPOSITORY_PREFIX, repo.id)) {
+            continue;
+        }
+        let old = registered.repos.iter().find(|(iri, old)| {
+            !found.contains(*iri) && !taken.contains(*iri) && old.remote_url.as_deref().is_some_and(|r| same_remote(r, remote))
+        });
+        let Some((iri, old)) = old else { continue };
+        let Some(id) = iri.strip_prefix(REPOSITORY_PREFIX) else { continue };
+        taken.insert(iri.clone());
+        renames.push(Rename { id: id.to_string(), from: old.name.clone().unwrap_or_else(|| id.to_string()), to: repo.name.clone() });
+        repo.id = id.to_string();
+    }
+    renames
+}
+
+/// What has to change for Synapse to match `repos` and `agents`.
+pub fn plan(registered: &Registered, repos: &[DiscoveredRepo], agents: &[AgentSpec], renamed_repos: Vec<Rename>) -> SyncPlan {
+    let mut plan = SyncPlan { renamed_repos, ..Default::default() };
+
+    let found: BTreeSet<String> = repos.iter().map(|r| format!("{}{}", REPOSITORY_PREFIX, r.id)).collect();
+    for repo in repos {
+        let iri = format!("{}{}", REPOSITORY_PREFIX, repo.id);
+        match registered.repos.get(&iri) {
+            None => plan.added_repos.push(iri),
+            Some(old) if old.archived => plan.restored_repos.push(iri),
+            Some(_) => {}
+        }
+    }
+    for (iri, old) in &registered.repos {
+        if !old.archived && !found.contains(iri) {
+            plan.archived_repos.push(iri.clone());
+        }
+    }
+
+    let listed: BTreeSet<String> = agents.iter().map(AgentSpec::subject).collect();
+    for agent in agents {
+        let iri = agent.subject();
+        let Some(old) = registered.agents.get(&iri) else {
+            plan.added_agents.push(iri);
+            continue;
+        };
+        if old.retired {
+            plan.refused_agents.push(iri);
+            continue;
+        }
+        let repo = format!("{}{}", REPOSITORY_PREFIX, agent.repo);
+        let changed: Vec<&'static str> = [
+            ("name", old.name.as_deref() != Some(agent.name.as_str())),
+            ("class", old.class.as_deref() != Some(agent.class.as_str())),
+            ("repo", old.repo.as_deref() != Some(repo.as_str())),
+        ]
+        .into_iter()
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect();
+        if !changed.is_empty() {
+            plan.updated_agents.push((iri, changed));
+        }
+    }
+    for (iri, old) in &registered.agents {
+        if !old.retired && !listed.contains(iri) {
+            plan.retired_agents.push(iri.clone());
+        }
+    }
+    plan
+}
+
+/// Writes the retirements and archive markers; additions and updates are the regular ingest.
+/// Returns how many open tasks lost their agent.
+pub async fn apply(synapse: &Synapse, plan: &SyncPlan) -> anyhow::Result<usize> {
+    let at_lit = tasks::datetime_literal(&chrono::Utc::now());
+    let mut triples = Vec::new();
+    for iri in &plan.retired_agents {
+        triples.push((iri.as_str(), AGENT_STATUS, "\"Retired\""));
+        triples.push((iri.as_str(), RETIRED_AT, at_lit.as_str()));
+    }
+    for iri in &plan.archived_repos {
+        triples.push((iri.as_str(), ARCHIVED_AT, at_lit.as_str()));
+    }
+    for iri in &plan.restored_repos {
+        triples.push((iri.as_str(), RESTORED_AT, at_lit.as_str()));
+    }
+
+    let orphaned: Vec<(String, String)> = if plan.retired_agents.is_empty() {
+        Vec::new()
+    } else {
+        tasks::active_tasks(synapse)
+            .await?
+            .into_iter()
+            .filter_map(|t| t.agent.filter(|a| plan.retired_agents.contains(a)).map(|a| (t.id, a)))
+            .collect()
+    };
+    triples.extend(orphaned.iter().map(|(task, agent)| (task.as_str(), UNASSIGNED_FROM, agent.as_str())));
+
+    if !triples.is_empty() {
+        synapse.ingest_from(Source::Discovery, triples).await?;
+    }
+    Ok(orphaned.len())
+}
+
+fn short(iri: &str) -> &str {
+    iri.strip_prefix(AGENT_PREFIX).or_else(|| iri.strip_prefix(REPOSITORY_PREFIX)).unwrap_or(iri)
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        *self == SyncPlan::default()
+    }
+
+    /// One line per kind of change, for the operators.
+    pub fn summary(&self, unassigned_tasks: usize) -> Message {
+        let list = |iris: &[String]| iris.iter().map(|i| short(i)).collect::<Vec<_>>().join(", ");
+        let mut msg = Message::new().strong("Discovery sync");
+        if !self.added_repos.is_empty() {
+            msg = msg.text(format!("\nRepositories added: {}", list(&self.added_repos)));
+        }
+        for rename in &self.renamed_repos {
+            msg = msg.text(format!("\nRepository {} renamed to {}", rename.from, rename.to));
+        }
+        if !self.archived_repos.is_empty() {
+            msg = msg.text(format!("\nRepositories archived (not found): {}", list(&self.archived_repos)));
+        }
+        if !self.restored_repos.is_empty() {
+            msg = msg.text(format!("\nRepositories restored: {}", list(&self.restored_repos)));
+        }
+        if !self.added_agents.is_empty() {
+            msg = msg.text(format!("\nAgents added: {}", list(&self.added_agents)));
+        }
+        for (iri, fields) in &self.updated_agents {
+            msg = msg.text(format!("\nAgent {} updated: {}", short(iri), fields.join(", ")));
+        }
+        if !self.retired_agents.is_empty() {
+            msg = msg.text(format!(
+                "\nAgents retired: {} ({} open tasks unassigned)",
+                list(&self.retired_agents),
+                unassigned_tasks
+            ));
+        }
+        if !self.refused_agents.is_empty() {
+            msg = msg.text(format!("\nRetired agents listed again, give them new ids: {}", list(&self.refused_agents)));
+        }
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn repo(id: &str, remote: Option<&str>) -> DiscoveredRepo {
+        DiscoveredRepo {
+            id: id.into(),
+            name: id.into(),
+            path: id.into(),
+            remote_url: remote.map(str::to_string),
+            branch: None,
+            language: None,
+            directories: Vec::new(),
+        }
+    }
+
+    fn agent(id: &str, class: &str, repo: &str) -> AgentSpec {
+        AgentSpec { id: id.into(), name: id.into(), class: class.into(), repo: repo.into(), command: None, runtime: Default::default(), skills: Vec::new() }
+    }
+
+    #[test]
+    fn renamed_checkouts_keep_their_iri() {
+        let rows = [
+            json!({ "?repo": "<http://swarm.os/repository/api>", "?name": "\"api\"", "?remote": "\"https://github.com/acme/api.git\"" }),
+            json!({ "?repo": "<http://swarm.os/repository/old>", "?name": "\"old\"", "?archived": "\"t1\"" }),
+        ];
+        let registered = Registered { repos: parse_repos(&rows), ..Default::default() };
+        assert!(registered.repos["http://swarm.os/repository/old"].archived);
+
+        let mut repos = vec![repo("api-v2", Some("https://github.com/acme/api")), repo("web", None)];
+        let renames = follow_renames(&registered, &mut repos);
+        assert_eq!(renames, vec![Rename { id: "api".into(), from: "api".into(), to: "api-v2".into() }]);
+        assert_eq!(repos[0].id, "api");
+        assert_eq!(repos[0].name, "api-v2");
+
+        let plan = plan(&registered, &repos, &[], renames);
+        assert_eq!(plan.added_repos, vec!["http://swarm.os/repository/web"]);
+        assert!(plan.archived_repos.is_empty());
+    }
+
+    #[test]
+    fn removed_agents_are_retired_and_changed_ones_updated() {
+        let rows = [
+            json!({ "?agent": "<http://swarm.os/agent/Coder_api>", "?name": "\"Coder_api\"", "?class": "\"Coder\"", "?status": "\"Standby\"", "?repo": "<http://swarm.os/repository/api>" }),
+            json!({ "?agent": "<http://swarm.os/agent/PM_1>", "?name": "\"PM_1\"", "?class": "\"ProductManager\"", "?status": "\"Standby\"" }),
+            json!({ "?agent": "<http://swarm.os/agent/Gone>", "?status": "\"Retired\"" }),
+        ];
+        let registered = Registered { agents: parse_agents(&rows), ..Default::default() };
+        let roster = [agent("Coder_api", "Reviewer", "api"), agent("Gone", "Coder", "api"), agent("New", "Coder", "web")];
+
+        let plan = plan(&registered, &[], &roster, Vec::new());
+        assert_eq!(plan.added_agents, vec!["http://swarm.os/agent/New"]);
+        assert_eq!(plan.updated_agents, vec![("http://swarm.os/agent/Coder_api".to_string(), vec!["class"])]);
+        assert_eq!(plan.retired_agents, vec!["http://swarm.os/agent/PM_1"]);
+        assert_eq!(plan.refused_agents, vec!["http://swarm.os/agent/Gone"]);
+        assert!(!plan.is_empty());
+        assert!(SyncPlan::default().is_empty());
+    }
+
+    #[test]
+    fn conflicting_values_are_read_the_same_in_any_row_order() {
+        let rows = [
+            json!({ "?agent": "<http://swarm.os/agent/Coder_api>", "?name": "\"Coder_api\"", "?class": "\"Coder\"", "?repo": "<http://swarm.os/repository/api>" }),
+            json!({ "?agent": "<http://swarm.os/agent/Coder_api>", "?name": "\"Coder (api)\"", "?class": "\"Reviewer\"" }),
+            json!({ "?agent": "<http://swarm.os/agent/Coder_api>", "?class": "\"Coder\"", "?repo": "<http://swarm.os/repository/web>" }),
+        ];
+        let mut reversed = rows.clone();
+        reversed.reverse();
+        let agents = parse_agents(&rows);
+        assert_eq!(agents, parse_agents(&reversed));
+        assert_eq!(
+            agents["http://swarm.os/agent/Coder_api"],
+            RegisteredAgent {
+                name: Some("Coder_api".into()),
+                class: Some("Reviewer".into()),
+                repo: Some("http://swarm.os/repository/web".into()),
+                retired: false,
+            }
+        );
+
+        let repo_rows = [
+            json!({ "?repo": "<http://swarm.os/repository/api>", "?name": "\"api\"", "?remote": "\"https://github.com/acme/api.git\"" }),
+            json!({ "?repo": "<http://swarm.os/repository/api>", "?name": "\"api-v2\"", "?remote": "\"https://github.com/acme/api\"" }),
+        ];
+        let mut reversed = repo_rows.clone();
+        reversed.reverse();
+        assert_eq!(parse_repos(&repo_rows), parse_repos(&reversed));
+        assert_eq!(parse_repos(&repo_rows)["http://swarm.os/repository/api"].name.as_deref(), Some("api-v2"));
+    }
+}
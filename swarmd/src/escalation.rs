@@ -0,0 +1,209 @@
+//! Handing tasks to humans. When `ESCALATION_OWNERS` is set, a task whose retries are
+//! exhausted becomes `ESCALATED` instead of dead-lettered: the owners are notified with the
+//! failure report of every attempt, and no agent touches the task until one of them resets it.
+//! A reset gives the agents a fresh set of attempts.
+//!
+//! Escalations and resets are both recorded on the task with their times, so a task can only
+//! be reset once per escalation, and a reset newer than the escalation hands it back to the
+//! agents. Queued resets are kept in the state store until the agency launches them, so a
+//! restart in between does not lose them.
+
+use serde_json::Value;
+use tracing::error;
+
+use crate::notifications::Message;
+use crate::provenance::Source;
+use crate::sparql::{self, Query};
+use crate::state::{self, QueuedReset};
+use crate::store::Synapse;
+use crate::tasks::{self, TaskState, INTERNAL_STATE};
+
+/// Who the task was handed to; one triple per owner.
+pub const HUMAN_OWNER: &str = "http://swarm.os/ontology/humanOwner";
+pub const ESCALATED_AT: &str = "http://swarm.os/ontology/escalatedAt";
+pub const RESET_AT: &str = "http://swarm.os/ontology/resetAt";
+pub const RESET_BY: &str = "http://swarm.os/ontology/resetBy";
+/// What went wrong in a failed run, on its `swarm:Run` node.
+pub const FAILURE_REPORT: &str = "http://swarm.os/ontology/failureReport";
+/// Reports keep the end of the output, where the error usually is.
+const REPORT_CHARS: usize = 600;
+
+/// `ESCALATION_OWNERS`: comma-separated handles or addresses.
+pub fn owners_from_config(raw: Option<&str>) -> Vec<String> {
+    raw.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A one-line report from a failed run's output, safe to store as a literal.
+pub fn failure_report(output: &str) -> String {
+    let flat = output.split_whitespace().collect::<Vec<_>>().join(" ").replace('"', "'").replace('\\', "/");
+    let skip = flat.chars().count().saturating_sub(REPORT_CHARS);
+    if skip == 0 {
+        flat
+    } else {
+        format!("…{}", flat.chars().skip(skip).collect::<String>())
+    }
+}
+
+/// Hands the task to `owners` after `attempts` failed agent runs.
+pub async fn escalate(synapse: &Synapse, task_id: &str, owners: &[String], attempts: u32) -> anyhow::Result<()> {
+    let state_lit = TaskState::Escalated.literal();
+    let attempts_lit = tasks::integer_literal(attempts as i64);
+    let at_lit = tasks::datetime_literal(&chrono::Utc::now());
+    let owner_lits: Vec<String> = owners.iter().map(|o| sparql::literal(o)).collect();
+
+    let mut triples = vec![
+        (task_id, INTERNAL_STATE, state_lit.as_str()),
+        (task_id, "http://swarm.os/ontology/attempts", attempts_lit.as_str()),
+        (task_id, ESCALATED_AT, at_lit.as_str()),
+    ];
+    triples.extend(owner_lits.iter().map(|o| (task_id, HUMAN_OWNER, o.as_str())));
+    synapse.ingest_from(Source::Agency, triples).await
+}
+
+/// The alert sent to the owners, with one line per failed attempt.
+pub fn escalation_message(title: &str, owners: &[String], reports: &[String]) -> Message {
+    let mut msg = Message::new()
+        .text("Task ")
+        .strong(title)
+        .text(format!(" needs a human after {} failed attempts. Owners: {}", reports.len(), owners.join(", ")));
+    for (i, report) in reports.iter().enumerate() {
+        msg = msg.text(format!("\nAttempt {}: ", i + 1)).code(report);
+    }
+    msg.text("\nAgents will not pick it up again until it is reset.")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResetOutcome {
+    /// The agency will run the task again with a fresh set of attempts.
+    Queued { title: String },
+    UnknownTask,
+    /// The task is not escalated, or its escalation was already reset.
+    NotEscalated,
+}
+
+/// A task a human has reset, waiting for the agency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResetTask {
+    pub task_id: String,
+    pub title: String,
+    /// The task's own `swarm:timeoutSeconds`, if it has one.
+    pub timeout: Option<std::time::Duration>,
+}
+
+/// Queued resets the agency has not picked up yet in this process. Each stays queued until
+/// its run is launched; see [`StateStore::finish_reset`](crate::state::StateStore::finish_reset).
+pub fn take_resets() -> Vec<ResetTask> {
+    match state::shared().claim_resets() {
+        Ok(resets) => resets
+            .into_iter()
+            .map(|reset| ResetTask {
+                task_id: reset.task,
+                title: reset.title,
+                timeout: reset.timeout_secs.map(std::time::Duration::from_secs),
+            })
+            .collect(),
+        Err(e) => {
+            error!("Could not read the queued resets: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Records that `by` reset the escalated `task_id` and queues it for the agency.
+pub async fn reset(synapse: &Synapse, task_id: &str, by: Option<&str>) -> anyhow::Result<ResetOutcome> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?title ?escalated ?reset ?timeout
+        WHERE {
+            $task a swarm:Task ;
+                  swarm:title ?title .
+            OPTIONAL { $task swarm:escalatedAt ?escalated }
+            OPTIONAL { $task swarm:resetAt ?reset }
+            OPTIONAL { $task swarm:timeoutSeconds ?timeout }
+        }
+    "#,
+    )
+    .iri("task", task_id);
+    let rows: Vec<Value> = serde_json::from_str(&synapse.select(&query).await?).unwrap_or_default();
+    let Some((title, open)) = open_escalation(&rows) else {
+        return Ok(ResetOutcome::UnknownTask);
+    };
+    if !open {
+        return Ok(ResetOutcome::NotEscalated);
+    }
+
+    let timeout_secs = rows
+        .iter()
+        .find_map(|row| row.get("?timeout").or_else(|| row.get("timeout")).and_then(Value::as_str))
+        .and_then(|raw| tasks::literal_value(raw).parse::<u64>().ok())
+        .filter(|secs| *secs > 0);
+    let at_lit = tasks::datetime_literal(&chrono::Utc::now());
+    let by_lit = sparql::literal(by.unwrap_or("operator"));
+    synapse
+        .ingest_from(Source::Gateway, vec![
+            (task_id, RESET_AT, at_lit.as_str()),
+            (task_id, RESET_BY, by_lit.as_str()),
+            (task_id, INTERNAL_STATE, TaskState::RetryScheduled.literal().as_str()),
+        ])
+        .await?;
+    state::shared().queue_reset(&QueuedReset { task: task_id.to_string(), title: title.clone(), timeout_secs })?;
+    Ok(ResetOutcome::Queued { title })
+}
+
+/// The task's title, and whether it was escalated and not reset since.
+fn open_escalation(rows: &[Value]) -> Option<(String, bool)> {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| tasks::literal_value(v).to_string())
+    };
+    let title = rows.iter().find_map(|row| field(row, "title"))?;
+    let escalations: Vec<String> = rows.iter().filter_map(|row| field(row, "escalated")).collect();
+    let resets: Vec<String> = rows.iter().filter_map(|row| field(row, "reset")).collect();
+    Some((title, !escalations.is_empty() && !tasks::reset_since_escalation(&escalations, &resets)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_keep_the_tail_of_the_output_on_one_line() {
+        assert_eq!(failure_report("Traceback:\n  File \"x.py\"\nValueError: bad\n"), "Traceback: File 'x.py' ValueError: bad");
+        let long = format!("{}END", "a".repeat(2000));
+        let report = failure_report(&long);
+        assert_eq!(report.chars().count(), REPORT_CHARS + 1);
+        assert!(report.starts_with('…') && report.ends_with("END"));
+        assert_eq!(owners_from_config(Some(" @ana, ,ops@example.com ")), vec!["@ana", "ops@example.com"]);
+    }
+
+    #[test]
+    fn a_task_can_be_reset_once_per_escalation() {
+        let row = |escalated: Option<&str>, reset: Option<&str>| {
+            let mut row = json!({ "?title": "\"Fix login\"" });
+            if let Some(at) = escalated {
+                row["?escalated"] = json!(format!("\"{at}\""));
+            }
+            if let Some(at) = reset {
+                row["?reset"] = json!(format!("\"{at}\""));
+            }
+            row
+        };
+        let (t1, r1, t2) = ("2026-10-17T09:00:00+00:00", "2026-10-17T10:00:00+00:00", "2026-10-17T11:00:00+00:00");
+        assert_eq!(open_escalation(&[]), None);
+        assert_eq!(open_escalation(&[row(None, None)]), Some(("Fix login".into(), false)));
+        assert_eq!(open_escalation(&[row(Some(t1), None)]), Some(("Fix login".into(), true)));
+        assert!(!open_escalation(&[row(Some(t1), Some(r1))]).unwrap().1);
+        // Escalated again after the reset: the cross product of the OPTIONALs repeats values.
+        let rows = [row(Some(t1), Some(r1)), row(Some(t2), Some(r1))];
+        assert!(open_escalation(&rows).unwrap().1);
+    }
+}
@@ -0,0 +1,277 @@
+//! Experience and levels of agents. A task that reaches `DONE` earns the agent of its run
+//! experience (`swarm:experience`), weighted by the task's priority and difficulty, and the total
+//! sets the agent's level (`swarm:level`) against `LEVEL_THRESHOLDS`. A task parked in
+//! `AWAITING_MERGE` pays when its pull request merges, see [`award_merged`]. Each task records
+//! what it paid out as `swarm:experienceAwarded` and pays only once; achievements pay their
+//! rewards through [`grant`].
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::notifications::Message;
+use crate::provenance::Source;
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks::{self, TaskState, NORMAL_PRIORITY};
+
+/// Integer; the agent's experience points so far.
+pub const EXPERIENCE: &str = "http://swarm.os/ontology/experience";
+/// Integer; 1 until the first threshold.
+pub const LEVEL: &str = "http://swarm.os/ontology/level";
+pub const EXPERIENCE_AWARDED: &str = "http://swarm.os/ontology/experienceAwarded";
+
+/// Points for an easy task at the lowest priority.
+const BASE_POINTS: i64 = 10;
+const DEFAULT_THRESHOLDS: [i64; 7] = [100, 250, 500, 1000, 2000, 4000, 8000];
+
+/// `LEVEL_THRESHOLDS` as the ascending experience needed for level 2, 3 and so on, see
+/// [`crate::config::thresholds`]; [`DEFAULT_THRESHOLDS`] apply when it is unset.
+pub fn level_thresholds(raw: Option<&str>) -> Vec<i64> {
+    raw.map_or_else(|| DEFAULT_THRESHOLDS.to_vec(), crate::config::thresholds)
+}
+
+/// The level `experience` reaches.
+pub fn level(thresholds: &[i64], experience: i64) -> i64 {
+    1 + thresholds.iter().filter(|t| experience >= **t).count() as i64
+}
+
+/// 1 to 3: by the cost triage estimated for the task, or else by how long its run took.
+pub fn difficulty(estimated_cost_usd: Option<f64>, wall_seconds: f64) -> i64 {
+    match estimated_cost_usd {
+        Some(cost) if cost >= 2.0 => 3,
+        Some(cost) if cost >= 0.5 => 2,
+        Some(_) => 1,
+        None if wall_seconds >= 3600.0 => 3,
+        None if wall_seconds >= 600.0 => 2,
+        None => 1,
+    }
+}
+
+/// Points for finishing a task: low priority counts once, critical four times, and a hard task
+/// three times an easy one.
+pub fn points(priority: Option<i64>, difficulty: i64) -> i64 {
+    BASE_POINTS * (priority.unwrap_or(NORMAL_PRIORITY).clamp(0, 3) + 1) * difficulty.clamp(1, 3)
+}
+
+/// Experience an agent earned, and the level it reached with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Award {
+    pub points: i64,
+    pub experience: i64,
+    pub level: i64,
+    pub previous_level: i64,
+}
+
+impl Award {
+    pub fn leveled_up(&self) -> bool {
+        self.level > self.previous_level
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskRow {
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    priority: Option<i64>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    cost: Option<f64>,
+    #[serde(default)]
+    awarded: Option<String>,
+}
+
+/// Credits `agent_id` with finishing `task_id` after a run of `wall_seconds`, and levels it up
+/// when its experience passes a threshold. A task pays once: `None` when it already has
+/// `swarm:experienceAwarded`.
+pub async fn award(synapse: &Synapse, task_id: &str, agent_id: &str, wall_seconds: f64, thresholds: &[i64]) -> anyhow::Result<Option<Award>> {
+    let _turn = GRANTS.lock().await;
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?priority ?cost ?awarded
+        WHERE {
+            OPTIONAL { $task swarm:priority ?priority }
+            OPTIONAL { $task swarm:estimatedCostUsd ?cost }
+            OPTIONAL { $task swarm:experienceAwarded ?awarded }
+        }
+    "#,
+    )
+    .iri("task", task_id);
+    let rows: Vec<TaskRow> = synapse.select_as(&query).await?;
+    if rows.iter().any(|r| r.awarded.is_some()) {
+        return Ok(None);
+    }
+    let priority = rows.iter().filter_map(|r| r.priority).max();
+    let cost = rows.iter().filter_map(|r| r.cost).reduce(f64::max);
+
+    let points = points(priority, difficulty(cost, wall_seconds));
+    let award = add(synapse, Source::Agency, agent_id, points, thresholds).await?;
+    // Only marked as paid once the agent has the points.
+    let points_lit = tasks::integer_literal(points);
+    synapse.ingest_from(Source::Agency, vec![(task_id, EXPERIENCE_AWARDED, points_lit.as_str())]).await?;
+    Ok(Some(award))
+}
+
+#[derive(Debug, Deserialize)]
+struct RunRow {
+    agent: String,
+    outcome: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    duration: Option<f64>,
+    completed: String,
+}
+
+/// Pays for a task a merge moved to `DONE`: credits the agent of the run that parked it in
+/// `AWAITING_MERGE` with that run's wall time. Returns the agent and what it earned, `None`
+/// when there is no such run or the task was already paid.
+pub async fn award_merged(synapse: &Synapse, task_id: &str, thresholds: &[i64]) -> anyhow::Result<Option<(String, Award)>> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?agent ?outcome ?duration ?completed
+        WHERE {
+            ?run swarm:runOf $task ;
+                 swarm:agent ?agent ;
+                 swarm:outcome ?outcome ;
+                 swarm:durationSeconds ?duration ;
+                 swarm:completedAt ?completed .
+        }
+    "#,
+    )
+    .iri("task", task_id);
+    let rows: Vec<RunRow> = synapse.select_as(&query).await?;
+    let Some(run) = rows
+        .into_iter()
+        .filter(|r| r.outcome == TaskState::AwaitingMerge.as_str())
+        .max_by(|a, b| a.completed.cmp(&b.completed))
+    else {
+        return Ok(None);
+    };
+    let award = award(synapse, task_id, &run.agent, run.duration.unwrap_or(0.0), thresholds).await?;
+    Ok(award.map(|award| (run.agent, award)))
+}
+
+/// Announces that `agent_id` reached `award.level` finishing the task titled `title`.
+pub fn level_up_message(agent_id: &str, award: &Award, title: &str) -> Message {
+    let agent = agent_id.rsplit('/').next().unwrap_or(agent_id);
+    Message::new()
+        .text("⭐ ")
+        .strong(agent)
+        .text(format!(" reached level {} with {} XP, finishing ", award.level, award.experience))
+        .strong(title)
+}
+
+/// Held across each read and write of an agent's experience, so the agency, the merge tracker
+/// and the achievements worker granting at once all count, and a task is paid once.
+static GRANTS: Mutex<()> = Mutex::const_new(());
+
+/// Adds `points` to the experience of `agent_id` and sets the level it reaches.
+pub async fn grant(synapse: &Synapse, source: Source, agent_id: &str, points: i64, thresholds: &[i64]) -> anyhow::Result<Award> {
+    let _turn = GRANTS.lock().await;
+    add(synapse, source, agent_id, points, thresholds).await
+}
+
+/// [`grant`] for a caller already holding [`GRANTS`].
+async fn add(synapse: &Synapse, source: Source, agent_id: &str, points: i64, thresholds: &[i64]) -> anyhow::Result<Award> {
+    #[derive(Deserialize)]
+    struct Row {
+        #[serde(default, deserialize_with = "sparql::parsed")]
+        experience: Option<i64>,
+    }
+    let query = Query::new("PREFIX swarm: <http://swarm.os/ontology/> SELECT ?experience WHERE { $agent swarm:experience ?experience }")
+        .iri("agent", agent_id);
+    let rows: Vec<Row> = synapse.select_as(&query).await?;
+    let before = rows.iter().filter_map(|r| r.experience).max().unwrap_or(0);
+
+    let award = Award {
+        points,
+        experience: before + points,
+        level: level(thresholds, before + points),
+        previous_level: level(thresholds, before),
+    };
+    synapse.replace_object(source, agent_id, EXPERIENCE, &tasks::integer_literal(award.experience)).await?;
+    synapse.replace_object(source, agent_id, LEVEL, &tasks::integer_literal(award.level)).await?;
+    Ok(award)
+}
+
+#[derive(Debug, Deserialize)]
+struct ClassRow {
+    class: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    level: Option<i64>,
+}
+
+/// The highest level among the agents of each class; agents without experience are level 1.
+pub async fn levels_by_class(synapse: &Synapse) -> anyhow::Result<HashMap<String, i64>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?class ?level
+        WHERE {
+            ?agent a swarm:Agent ;
+                   swarm:class ?class .
+            OPTIONAL { ?agent swarm:level ?level }
+        }
+    "#;
+    let rows: Vec<ClassRow> = sparql::rows(&synapse.query_cached(query, crate::query_cache::DEFAULT_TTL).await?)?;
+    let mut levels: HashMap<String, i64> = HashMap::new();
+    for row in rows {
+        let level = levels.entry(row.class).or_insert(1);
+        *level = (*level).max(row.level.unwrap_or(1));
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn experience_is_weighted_by_priority_and_difficulty_and_sets_the_level() {
+        assert_eq!(difficulty(Some(0.1), 7200.0), 1);
+        assert_eq!(difficulty(Some(0.8), 0.0), 2);
+        assert_eq!(difficulty(None, 900.0), 2);
+        assert_eq!(difficulty(None, 4000.0), 3);
+
+        assert_eq!(points(None, 1), 20);
+        assert_eq!(points(Some(0), 1), 10);
+        assert_eq!(points(Some(3), 3), 120);
+        assert_eq!(points(Some(9), 5), 120);
+
+        let thresholds = level_thresholds(Some("500, 100,x,-3,100"));
+        assert_eq!(thresholds, [100, 500]);
+        assert_eq!(level(&thresholds, 0), 1);
+        assert_eq!(level(&thresholds, 100), 2);
+        assert_eq!(level(&thresholds, 9000), 3);
+        assert_eq!(level_thresholds(None).len(), DEFAULT_THRESHOLDS.len());
+        assert!(Award { points: 20, experience: 110, level: 2, previous_level: 1 }.leveled_up());
+    }
+
+    #[tokio::test]
+    async fn a_task_pays_once() {
+        let (store, synapse) = crate::store::MemoryStore::shared();
+        let (task, agent) = ("http://swarm.os/tasks/paid", "http://swarm.os/agent/Coder_1");
+        let award = award(&synapse, task, agent, 0.0, &[100]).await.unwrap().unwrap();
+        assert_eq!(award.points, 20);
+        assert!(store.triples().iter().any(|(s, p, o)| s == task && p == EXPERIENCE_AWARDED && o.contains("20")));
+
+        store.answer("experienceAwarded ?awarded", serde_json::json!([{ "?awarded": "20" }]));
+        let paid = store.triples().len();
+        assert_eq!(super::award(&synapse, task, agent, 0.0, &[100]).await.unwrap(), None);
+        assert_eq!(store.triples().len(), paid);
+    }
+
+    #[tokio::test]
+    async fn a_merge_pays_the_agent_of_the_run_that_opened_the_pull_request() {
+        let (store, synapse) = crate::store::MemoryStore::shared();
+        let task = "http://swarm.os/tasks/merged";
+        store.answer("swarm:runOf", serde_json::json!([
+            { "?agent": "<http://swarm.os/agent/Coder_1>", "?outcome": "\"FAILED\"", "?duration": "30", "?completed": "\"2026-10-01T10:00:00Z\"" },
+            { "?agent": "<http://swarm.os/agent/Coder_2>", "?outcome": "\"AWAITING_MERGE\"", "?duration": "900", "?completed": "\"2026-10-01T11:00:00Z\"" },
+            { "?agent": "<http://swarm.os/agent/Coder_3>", "?outcome": "\"FAILED\"", "?duration": "30", "?completed": "\"2026-10-01T12:00:00Z\"" },
+        ]));
+        let (agent, award) = award_merged(&synapse, task, &[100]).await.unwrap().unwrap();
+        assert_eq!(agent, "http://swarm.os/agent/Coder_2");
+        assert_eq!(award.points, points(None, 2));
+        assert!(store.triples().iter().any(|(s, p, _)| s == "http://swarm.os/agent/Coder_2" && p == EXPERIENCE));
+    }
+}
@@ -0,0 +1,115 @@
+//! Exploration of repositories, behind the fog of war on the game map. Discovery lists the
+//! directories of each checkout down to [`DEPTH`] levels as `swarm:directory` and explores its
+//! root; a run that finishes its task explores the directories of the files it changed, as
+//! `swarm:exploredDirectory`. A repository's exploration is the share of its directories
+//! explored so far.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Deserialize;
+
+use crate::provenance::Source;
+use crate::server::contracts::Exploration;
+use crate::sparql;
+use crate::store::Synapse;
+
+/// A directory of the checkout, relative to its root: `.`, `src`, `src/server`…
+pub const DIRECTORY: &str = "http://swarm.os/ontology/directory";
+pub const EXPLORED_DIRECTORY: &str = "http://swarm.os/ontology/exploredDirectory";
+/// Directories deeper than this count as the one above them at this depth.
+pub const DEPTH: usize = 2;
+/// The checkout root, explored as soon as discovery finds it.
+pub const ROOT: &str = ".";
+
+/// The directories `files` (paths relative to the checkout root) lie in, at most [`DEPTH`]
+/// levels deep, with every directory on the way and the root.
+pub fn directories_of<'a>(files: impl IntoIterator<Item = &'a str>) -> BTreeSet<String> {
+    let mut directories = BTreeSet::new();
+    for file in files {
+        let parts: Vec<&str> = file.trim().trim_start_matches("./").split('/').filter(|p| !p.is_empty()).collect();
+        let Some((_, parents)) = parts.split_last() else { continue };
+        directories.insert(ROOT.to_string());
+        for depth in 1..=parents.len().min(DEPTH) {
+            directories.insert(parents[..depth].join("/"));
+        }
+    }
+    directories
+}
+
+/// Marks `directories` of the repository `repo` (an IRI) explored.
+pub async fn record(synapse: &Synapse, source: Source, repo: &str, directories: &BTreeSet<String>) -> anyhow::Result<()> {
+    if directories.is_empty() {
+        return Ok(());
+    }
+    let literals: Vec<String> = directories.iter().map(|d| sparql::literal(d)).collect();
+    let triples = literals.iter().map(|d| (repo, EXPLORED_DIRECTORY, d.as_str())).collect();
+    synapse.ingest_from(source, triples).await
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    repo: String,
+    directory: Option<String>,
+    explored: Option<String>,
+}
+
+/// Exploration of every repository discovery listed directories for, keyed by IRI.
+pub async fn fog_map(synapse: &Synapse) -> anyhow::Result<BTreeMap<String, Exploration>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?repo ?directory ?explored
+        WHERE {
+            ?repo a swarm:Repository .
+            { ?repo swarm:directory ?directory } UNION { ?repo swarm:exploredDirectory ?explored }
+        }
+    "#;
+    Ok(explorations(&sparql::rows(&synapse.query_cached(query, crate::query_cache::DEFAULT_TTL).await?)?))
+}
+
+/// Directories explored that discovery has not listed (yet) do not count.
+fn explorations(rows: &[Row]) -> BTreeMap<String, Exploration> {
+    let mut repos: BTreeMap<&str, (BTreeSet<&str>, BTreeSet<&str>)> = BTreeMap::new();
+    for row in rows {
+        let (listed, explored) = repos.entry(row.repo.as_str()).or_default();
+        listed.extend(row.directory.as_deref());
+        explored.extend(row.explored.as_deref());
+    }
+    repos
+        .into_iter()
+        .filter(|(_, (listed, _))| !listed.is_empty())
+        .map(|(repo, (listed, explored))| {
+            let explored = listed.intersection(&explored).count();
+            let percent = (explored as f64 * 1000.0 / listed.len() as f64).round() / 10.0;
+            (repo.to_string(), Exploration { percent, directories: listed.len(), explored })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_files_explore_their_directories_and_the_share_counts_listed_ones() {
+        let touched = directories_of(["swarmd/src/server/routes.rs", "./README.md", "swarmd/Cargo.toml", ""]);
+        assert_eq!(touched.into_iter().collect::<Vec<_>>(), [".", "swarmd", "swarmd/src"]);
+        assert!(directories_of(["  "]).is_empty());
+
+        let row = |repo: &str, directory: Option<&str>, explored: Option<&str>| Row {
+            repo: repo.into(),
+            directory: directory.map(str::to_string),
+            explored: explored.map(str::to_string),
+        };
+        let rows = [
+            row("api", Some("."), None),
+            row("api", Some("src"), None),
+            row("api", Some("docs"), None),
+            row("api", None, Some(".")),
+            row("api", None, Some("new-dir")),
+            row("web", None, Some(".")),
+        ];
+        let map = explorations(&rows);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["api"], Exploration { percent: 33.3, directories: 3, explored: 1 });
+    }
+}
@@ -0,0 +1,181 @@
+//! Built-in health rules, evaluated in-process so deployments without a Prometheus stack
+//! still get actionable signals. The `/api/v1/alerts` payload follows the shape of
+//! Prometheus' own `/api/v1/alerts`, so existing tooling can consume it.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use crate::server::contracts::HealthAlert;
+use crate::store::Synapse;
+
+/// A worker that has not reported in for this long is considered down.
+const WORKER_STALE_AFTER: Duration = Duration::from_secs(300);
+const BUDGET_WARN_RATIO: f64 = 0.9;
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+const PROBE_WINDOW: usize = 20;
+const SYNAPSE_ERROR_RATE: f64 = 0.2;
+
+/// Liveness and Synapse probe history shared by the workers and the gateway.
+#[derive(Debug, Default)]
+pub struct Health {
+    heartbeats: Mutex<HashMap<String, Instant>>,
+    probes: Mutex<VecDeque<bool>>,
+    firing_since: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+/// A rule's evaluation before it is turned into a Prometheus-style alert.
+struct Evaluation {
+    name: String,
+    severity: &'static str,
+    summary: String,
+    value: f64,
+    firing: bool,
+    extra_labels: Vec<(&'static str, String)>,
+}
+
+impl Health {
+    /// Called by every background worker once per loop iteration.
+    pub fn beat(&self, worker: &str) {
+        self.heartbeats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(worker.to_string(), Instant::now());
+    }
+
+    /// Drops a worker that was stopped on purpose, so its silence does not fire `WorkerDown`.
+    /// It is tracked again from its next beat.
+    pub fn forget(&self, worker: &str) {
+        self.heartbeats.lock().unwrap_or_else(|e| e.into_inner()).remove(worker);
+    }
+
+    pub fn record_probe(&self, ok: bool) {
+        let mut probes = self.probes.lock().unwrap_or_else(|e| e.into_inner());
+        probes.push_back(ok);
+        while probes.len() > PROBE_WINDOW {
+            probes.pop_front();
+        }
+    }
+
+    /// Issues a trivial query on a fixed interval to track the Synapse error rate.
+    pub async fn run_synapse_probe(self: Arc<Self>, synapse: Synapse) {
+        loop {
+            let ok = synapse.query("SELECT ?s WHERE { ?s ?p ?o } LIMIT 1").await.is_ok();
+            self.record_probe(ok);
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    }
+
+    fn synapse_error_rate(&self) -> Option<f64> {
+        let probes = self.probes.lock().unwrap_or_else(|e| e.into_inner());
+        if probes.is_empty() {
+            return None;
+        }
+        let failures = probes.iter().filter(|ok| !**ok).count();
+        Some(failures as f64 / probes.len() as f64)
+    }
+
+    /// Evaluates every rule and returns the ones currently firing. `budget` is
+    /// `(spent, max)` for today, when it could be read.
+    pub fn firing_alerts(&self, budget: Option<(f64, f64)>) -> Vec<HealthAlert> {
+        let now = Instant::now();
+        let mut evaluations: Vec<Evaluation> = {
+            let heartbeats = self.heartbeats.lock().unwrap_or_else(|e| e.into_inner());
+            let mut workers: Vec<_> = heartbeats.iter().collect();
+            workers.sort_by(|a, b| a.0.cmp(b.0));
+            workers
+                .into_iter()
+                .map(|(worker, last)| {
+                    let silent = now.duration_since(*last).as_secs_f64();
+                    Evaluation {
+                        name: "WorkerDown".to_string(),
+                        severity: "critical",
+                        summary: format!("Worker '{}' has not reported for {:.0}s", worker, silent),
+                        value: silent,
+                        firing: silent >= WORKER_STALE_AFTER.as_secs_f64(),
+                        extra_labels: vec![("worker", worker.clone())],
+                    }
+                })
+                .collect()
+        };
+
+        if let Some((spent, max)) = budget {
+            let ratio = if max > 0.0 { spent / max } else { 1.0 };
+            evaluations.push(Evaluation {
+                name: "DailyBudgetNearlyExhausted".to_string(),
+                severity: "warning",
+                summary: format!("Daily spend ${:.2} is {:.0}% of the ${:.2} budget", spent, ratio * 100.0, max),
+                value: ratio,
+                firing: ratio >= BUDGET_WARN_RATIO,
+                extra_labels: vec![],
+            });
+        }
+
+        if let Some(rate) = self.synapse_error_rate() {
+            evaluations.push(Evaluation {
+                name: "SynapseErrorRateHigh".to_string(),
+                severity: "critical",
+                summary: format!("{:.0}% of recent Synapse probes failed", rate * 100.0),
+                value: rate,
+                firing: rate >= SYNAPSE_ERROR_RATE,
+                extra_labels: vec![],
+            });
+        }
+
+        self.to_alerts(evaluations)
+    }
+
+    /// Tracks when each rule started firing, so `activeAt` survives re-evaluation.
+    fn to_alerts(&self, evaluations: Vec<Evaluation>) -> Vec<HealthAlert> {
+        let mut since = self.firing_since.lock().unwrap_or_else(|e| e.into_inner());
+        let mut alerts = Vec::new();
+
+        for eval in evaluations {
+            let key = std::iter::once(eval.name.clone())
+                .chain(eval.extra_labels.iter().map(|(_, v)| v.clone()))
+                .collect::<Vec<_>>()
+                .join("/");
+            if !eval.firing {
+                since.remove(&key);
+                continue;
+            }
+            let active_at = *since.entry(key).or_insert_with(Utc::now);
+
+            let mut labels = HashMap::from([
+                ("alertname".to_string(), eval.name),
+                ("severity".to_string(), eval.severity.to_string()),
+            ]);
+            labels.extend(eval.extra_labels.into_iter().map(|(k, v)| (k.to_string(), v)));
+
+            alerts.push(HealthAlert {
+                labels,
+                annotations: HashMap::from([("summary".to_string(), eval.summary)]),
+                state: "firing".to_string(),
+                active_at: active_at.to_rfc3339(),
+                value: format!("{:.4}", eval.value),
+            });
+        }
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_and_synapse_rules_fire_past_their_thresholds() {
+        let health = Health::default();
+        health.beat("agency");
+        assert!(health.firing_alerts(Some((5.0, 10.0))).is_empty());
+
+        for ok in [true, false, false, true] {
+            health.record_probe(ok);
+        }
+        let alerts = health.firing_alerts(Some((9.5, 10.0)));
+        let names: Vec<_> = alerts.iter().map(|a| a.labels["alertname"].as_str()).collect();
+        assert_eq!(names, vec!["DailyBudgetNearlyExhausted", "SynapseErrorRateHigh"]);
+        assert!(alerts.iter().all(|a| a.state == "firing"));
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    PreAssign,
+    PostComplete,
+    OnFail,
+}
+
+/// A hook is either an HTTP(S) webhook receiving the payload as a JSON POST,
+/// or a shell command receiving it on stdin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookTarget {
+    Webhook(String),
+    Command(String),
+}
+
+impl HookTarget {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            None
+        } else if raw.starts_with("http://") || raw.starts_with("https://") {
+            Some(HookTarget::Webhook(raw.to_string()))
+        } else {
+            Some(HookTarget::Command(raw.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHookPayload {
+    pub event: HookEvent,
+    pub task_id: String,
+    pub title: String,
+    pub agent_id: String,
+    pub state: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_seconds: Option<f64>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    pre_assign: Option<HookTarget>,
+    post_complete: Option<HookTarget>,
+    on_fail: Option<HookTarget>,
+    client: reqwest::Client,
+}
+
+impl Hooks {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            pre_assign: cfg.hook_pre_assign.as_deref().and_then(HookTarget::parse),
+            post_complete: cfg.hook_post_complete.as_deref().and_then(HookTarget::parse),
+            on_fail: cfg.hook_on_fail.as_deref().and_then(HookTarget::parse),
+            client: reqwest::Client::builder()
+                .timeout(HOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn target(&self, event: HookEvent) -> Option<&HookTarget> {
+        match event {
+            HookEvent::PreAssign => self.pre_assign.as_ref(),
+            HookEvent::PostComplete => self.post_complete.as_ref(),
+            HookEvent::OnFail => self.on_fail.as_ref(),
+        }
+    }
+
+    /// Invokes the hook configured for `payload.event`, if any. Hook failures are
+    /// logged and never interrupt the task lifecycle.
+    pub async fn fire(&self, payload: &TaskHookPayload) {
+        let Some(target) = self.target(payload.event) else {
+            return;
+        };
+
+        let result = match target {
+            HookTarget::Webhook(url) => self.post_webhook(url, payload).await,
+            HookTarget::Command(cmd) => run_command(cmd, payload).await,
+        };
+
+        match result {
+            Ok(()) => info!("🪝 Hook {:?} ran for task {}", payload.event, payload.task_id),
+            Err(e) => warn!("🪝 Hook {:?} failed for task {}: {}", payload.event, payload.task_id, e),
+        }
+    }
+
+    async fn post_webhook(&self, url: &str, payload: &TaskHookPayload) -> anyhow::Result<()> {
+        let res = self.client.post(url).json(payload).send().await?;
+        if !res.status().is_success() {
+            anyhow::bail!("webhook returned {}", res.status());
+        }
+        Ok(())
+    }
+}
+
+async fn run_command(cmd: &str, payload: &TaskHookPayload) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(payload)?;
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .env("SWARM_HOOK_EVENT", serde_json::to_value(payload.event)?.as_str().unwrap_or_default())
+        .env("SWARM_TASK_ID", &payload.task_id)
+        .env("SWARM_AGENT_ID", &payload.agent_id)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&body).await?;
+    }
+
+    let status = tokio::time::timeout(HOOK_TIMEOUT, child.wait())
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out after {}s", HOOK_TIMEOUT.as_secs()))??;
+    if !status.success() {
+        anyhow::bail!("command exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_become_webhooks_and_everything_else_a_command() {
+        assert_eq!(
+            HookTarget::parse("https://ci.example/trigger"),
+            Some(HookTarget::Webhook("https://ci.example/trigger".into()))
+        );
+        assert_eq!(
+            HookTarget::parse("./scripts/sync_ticket.sh"),
+            Some(HookTarget::Command("./scripts/sync_ticket.sh".into()))
+        );
+        assert_eq!(HookTarget::parse("  "), None);
+    }
+}
@@ -0,0 +1,214 @@
+//! `swarmd init`: interactive setup that writes a starter `.env` for new installs.
+
+use anyhow::Result;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::store::{Endpoint, Synapse};
+
+const ENV_PATH: &str = ".env";
+
+/// Answers collected by the wizard. Optional integrations stay `None` when skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InitAnswers {
+    pub synapse_host: String,
+    pub synapse_port: String,
+    pub gateway_port: String,
+    pub telegram_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub trello_api_key: Option<String>,
+    pub trello_token: Option<String>,
+    pub trello_board_id: Option<String>,
+    pub daily_budget_usd: f64,
+}
+
+impl InitAnswers {
+    pub fn render_env(&self) -> String {
+        let mut out = String::from("# Generated by `swarmd init`\n\n# Synapse\n");
+        push_var(&mut out, "SYNAPSE_GRPC_HOST", Some(&self.synapse_host));
+        push_var(&mut out, "SYNAPSE_GRPC_PORT", Some(&self.synapse_port));
+        push_var(&mut out, "GATEWAY_PORT", Some(&self.gateway_port));
+
+        out.push_str("\n# Telegram\n");
+        push_var(&mut out, "TELEGRAM_BOT_TOKEN", self.telegram_token.as_ref());
+        push_var(&mut out, "TELEGRAM_CHAT_ID", self.telegram_chat_id.as_ref());
+
+        out.push_str("\n# Trello\n");
+        push_var(&mut out, "TRELLO_API_KEY", self.trello_api_key.as_ref());
+        push_var(&mut out, "TRELLO_TOKEN", self.trello_token.as_ref());
+        push_var(&mut out, "TRELLO_BOARD_ID", self.trello_board_id.as_ref());
+
+        out.push_str("\n# Budget\n");
+        push_var(&mut out, "DAILY_BUDGET_USD", Some(&self.daily_budget_usd.to_string()));
+        out
+    }
+}
+
+/// Skipped integrations are written commented out so they are easy to enable later.
+fn push_var(out: &mut String, key: &str, value: Option<&String>) {
+    match value {
+        Some(v) => out.push_str(&format!("{key}={v}\n")),
+        None => out.push_str(&format!("# {key}=\n")),
+    }
+}
+
+pub async fn run() -> Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    println!("🐝 swarmd setup — press Enter to accept the [default].\n");
+
+    if Path::new(ENV_PATH).exists() && !confirm(&mut input, &format!("{ENV_PATH} already exists. Overwrite it?"), false)? {
+        println!("Nothing written.");
+        return Ok(());
+    }
+
+    let answers = ask(&mut input)?;
+
+    println!("\nChecking connectivity...");
+    let problems = validate(&answers).await;
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("  ❌ {problem}");
+        }
+        if !confirm(&mut input, "Some checks failed. Write the configuration anyway?", false)? {
+            println!("Nothing written.");
+            return Ok(());
+        }
+    }
+
+    std::fs::write(ENV_PATH, answers.render_env())?;
+    println!("\n✅ Wrote {ENV_PATH}. Start the daemon with `swarmd`.");
+    Ok(())
+}
+
+fn ask(input: &mut impl BufRead) -> Result<InitAnswers> {
+    let synapse_host = prompt(input, "Synapse gRPC host", Some("127.0.0.1"))?.unwrap_or_default();
+    let synapse_port = prompt(input, "Synapse gRPC port", Some("50051"))?.unwrap_or_default();
+    let gateway_port = prompt(input, "Gateway HTTP port", Some("18789"))?.unwrap_or_default();
+
+    let (telegram_token, telegram_chat_id) = if confirm(input, "Configure Telegram notifications?", false)? {
+        (prompt(input, "Telegram bot token", None)?, prompt(input, "Telegram chat id", None)?)
+    } else {
+        (None, None)
+    };
+
+    let (trello_api_key, trello_token, trello_board_id) = if confirm(input, "Configure Trello sync?", false)? {
+        (
+            prompt(input, "Trello API key", None)?,
+            prompt(input, "Trello token", None)?,
+            prompt(input, "Trello board id", None)?,
+        )
+    } else {
+        (None, None, None)
+    };
+
+    let daily_budget_usd = loop {
+        let raw = prompt(input, "Daily LLM budget (USD)", Some("10"))?.unwrap_or_default();
+        match raw.parse::<f64>() {
+            Ok(v) if v >= 0.0 => break v,
+            _ => println!("  Please enter a non-negative number."),
+        }
+    };
+
+    Ok(InitAnswers {
+        synapse_host,
+        synapse_port,
+        gateway_port,
+        telegram_token,
+        telegram_chat_id,
+        trello_api_key,
+        trello_token,
+        trello_board_id,
+        daily_budget_usd,
+    })
+}
+
+/// Reads one answer; an empty line yields `default`.
+fn prompt(input: &mut impl BufRead, label: &str, default: Option<&str>) -> Result<Option<String>> {
+    match default {
+        Some(d) => print!("{label} [{d}]: "),
+        None => print!("{label}: "),
+    }
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() {
+        default.map(String::from)
+    } else {
+        Some(answer.to_string())
+    })
+}
+
+fn confirm(input: &mut impl BufRead, label: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let answer = prompt(input, &format!("{label} ({hint})"), None)?;
+    Ok(match answer.as_deref().map(str::to_lowercase).as_deref() {
+        Some("y") | Some("yes") => true,
+        Some("n") | Some("no") => false,
+        _ => default,
+    })
+}
+
+/// Returns a human-readable message for every service that could not be reached.
+async fn validate(answers: &InitAnswers) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    match tokio::time::timeout(
+        Duration::from_secs(5),
+        Synapse::connect(&Endpoint::new(&answers.synapse_host, &answers.synapse_port)),
+    )
+    .await
+    {
+        Ok(Ok(_)) => println!("  ✅ Synapse at {}:{}", answers.synapse_host, answers.synapse_port),
+        Ok(Err(e)) => problems.push(format!("Synapse at {}:{}: {}", answers.synapse_host, answers.synapse_port, e)),
+        Err(_) => problems.push(format!("Synapse at {}:{}: timed out", answers.synapse_host, answers.synapse_port)),
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+
+    if let Some(token) = &answers.telegram_token {
+        let url = format!("https://api.telegram.org/bot{}/getMe", token);
+        match client.get(&url).send().await {
+            Ok(res) if res.status().is_success() => println!("  ✅ Telegram bot token"),
+            Ok(res) => problems.push(format!("Telegram rejected the bot token ({})", res.status())),
+            Err(e) => problems.push(format!("Telegram unreachable: {}", e)),
+        }
+    }
+
+    if let (Some(key), Some(token)) = (&answers.trello_api_key, &answers.trello_token) {
+        let url = "https://api.trello.com/1/members/me";
+        match client.get(url).query(&[("key", key), ("token", token)]).send().await {
+            Ok(res) if res.status().is_success() => println!("  ✅ Trello credentials"),
+            Ok(res) => problems.push(format!("Trello rejected the credentials ({})", res.status())),
+            Err(e) => problems.push(format!("Trello unreachable: {}", e)),
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_integrations_are_commented_out() {
+        let mut input = io::Cursor::new("\n\n\nn\ny\nkey\ntok\nboard\n25\n");
+        let answers = ask(&mut input).unwrap();
+        assert_eq!(answers.synapse_host, "127.0.0.1");
+        assert_eq!(answers.daily_budget_usd, 25.0);
+
+        let env = answers.render_env();
+        assert!(env.contains("SYNAPSE_GRPC_PORT=50051\n"));
+        assert!(env.contains("# TELEGRAM_BOT_TOKEN=\n"));
+        assert!(env.contains("TRELLO_BOARD_ID=board\n"));
+        assert!(env.contains("DAILY_BUDGET_USD=25\n"));
+    }
+}
@@ -0,0 +1,322 @@
+//! Initiatives: one goal rolled out across several repositories ("add SSO to every
+//! service"). A blueprint lists the steps each repository goes through; the planner
+//! expands it into per-repository `swarm:Task`s chained with `swarm:dependsOn`, and the
+//! agency only starts a task once everything it depends on is `DONE`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::provenance::Source;
+use crate::server::contracts::{
+    BlueprintStep, InitiativeRepositoryProgress, InitiativeRequest, InitiativeStatus, QuestStatus,
+};
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, INTERNAL_STATE};
+
+pub const INITIATIVE: &str = "http://swarm.os/ontology/Initiative";
+pub const PART_OF: &str = "http://swarm.os/ontology/partOfInitiative";
+pub const DEPENDS_ON: &str = "http://swarm.os/ontology/dependsOn";
+pub const REPOSITORY: &str = "http://swarm.os/ontology/repository";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const TITLE: &str = "http://swarm.os/ontology/title";
+const TASK: &str = "http://swarm.os/ontology/Task";
+
+/// Used when a request does not bring its own steps.
+pub fn default_blueprint() -> Vec<BlueprintStep> {
+    let step = |key: &str, title: &str, deps: &[&str]| BlueprintStep {
+        key: key.to_string(),
+        title: title.to_string(),
+        depends_on: deps.iter().map(|d| d.to_string()).collect(),
+    };
+    vec![
+        step("design", "Design", &[]),
+        step("implement", "Implement", &["design"]),
+        step("verify", "Verify", &["implement"]),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedTask {
+    pub id: String,
+    pub title: String,
+    pub repository: String,
+    pub depends_on: Vec<String>,
+}
+
+/// Expands a blueprint into one task per (repository, step). Steps are ordered so that
+/// every task comes after its dependencies. With a lead repository, the first steps of
+/// every other repository wait for the lead's final steps.
+pub fn plan(initiative_id: &str, request: &InitiativeRequest) -> Result<Vec<PlannedTask>, String> {
+    if request.title.trim().is_empty() {
+        return Err("title must not be empty".to_string());
+    }
+    if request.repositories.is_empty() {
+        return Err("at least one repository is required".to_string());
+    }
+    if let Some(bad) = request.repositories.iter().find(|r| !is_valid_key(r)) {
+        return Err(format!("invalid repository id '{}'", bad));
+    }
+    if let Some(lead) = &request.lead_repository {
+        if !request.repositories.contains(lead) {
+            return Err(format!("lead repository '{}' is not part of the initiative", lead));
+        }
+    }
+
+    let steps = if request.steps.is_empty() { default_blueprint() } else { request.steps.clone() };
+    let order = topological_order(&steps)?;
+    let roots: Vec<&str> = order.iter().filter(|s| s.depends_on.is_empty()).map(|s| s.key.as_str()).collect();
+    let leaves: Vec<&str> = order
+        .iter()
+        .filter(|s| !steps.iter().any(|other| other.depends_on.contains(&s.key)))
+        .map(|s| s.key.as_str())
+        .collect();
+
+    let task_id = |repo: &str, key: &str| format!("{}/{}/{}", initiative_id, repo, key);
+    // Lead repository first, so its tasks precede the ones that wait for it.
+    let mut repos: Vec<&String> = request.repositories.iter().collect();
+    repos.sort_by_key(|r| Some(*r) != request.lead_repository.as_ref());
+
+    let mut planned = Vec::new();
+    for repo in repos {
+        for step in &order {
+            let mut depends_on: Vec<String> = step.depends_on.iter().map(|d| task_id(repo, d)).collect();
+            if let Some(lead) = request.lead_repository.as_ref().filter(|l| *l != repo) {
+                if roots.contains(&step.key.as_str()) {
+                    depends_on.extend(leaves.iter().map(|leaf| task_id(lead, leaf)));
+                }
+            }
+            planned.push(PlannedTask {
+                id: task_id(repo, &step.key),
+                title: format!("[{}] {} — {}", repo, request.title.trim(), step.title),
+                repository: repo.clone(),
+                depends_on,
+            });
+        }
+    }
+    Ok(planned)
+}
+
+/// Repository ids and step keys become IRI path segments.
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Kahn's algorithm, keeping blueprint order among steps that are ready together.
+fn topological_order(steps: &[BlueprintStep]) -> Result<Vec<&BlueprintStep>, String> {
+    let mut by_key = HashMap::new();
+    for step in steps {
+        if !is_valid_key(&step.key) {
+            return Err(format!("invalid step key '{}'", step.key));
+        }
+        if by_key.insert(step.key.as_str(), step).is_some() {
+            return Err(format!("duplicate step '{}'", step.key));
+        }
+    }
+    for step in steps {
+        if let Some(missing) = step.depends_on.iter().find(|d| !by_key.contains_key(d.as_str())) {
+            return Err(format!("step '{}' depends on unknown step '{}'", step.key, missing));
+        }
+    }
+
+    let mut order: Vec<&BlueprintStep> = Vec::with_capacity(steps.len());
+    while order.len() < steps.len() {
+        let next = steps.iter().find(|s| {
+            !order.iter().any(|o| o.key == s.key)
+                && s.depends_on.iter().all(|d| order.iter().any(|o| &o.key == d))
+        });
+        match next {
+            Some(step) => order.push(step),
+            None => return Err("blueprint steps have a dependency cycle".to_string()),
+        }
+    }
+    Ok(order)
+}
+
+pub fn new_id() -> String {
+    format!("http://swarm.os/initiative/{}", uuid::Uuid::new_v4())
+}
+
+/// Writes the initiative and its planned tasks. Tasks enter the queue as `REQUIREMENTS`
+/// like any other intake.
+pub async fn record(synapse: &Synapse, id: &str, title: &str, planned: &[PlannedTask]) -> anyhow::Result<()> {
+    let title = sparql::literal(title.trim());
+    let mut triples: Vec<(String, &str, String)> = vec![
+        (id.to_string(), RDF_TYPE, INITIATIVE.to_string()),
+        (id.to_string(), TITLE, title),
+    ];
+    for task in planned {
+        let repo_iri = format!("http://swarm.os/repository/{}", task.repository);
+        triples.push((task.id.clone(), RDF_TYPE, TASK.to_string()));
+        triples.push((task.id.clone(), TITLE, sparql::literal(&task.title)));
+        triples.push((task.id.clone(), INTERNAL_STATE, "\"REQUIREMENTS\"".to_string()));
+        triples.push((task.id.clone(), PART_OF, id.to_string()));
+        triples.push((task.id.clone(), REPOSITORY, repo_iri));
+        for dep in &task.depends_on {
+            triples.push((task.id.clone(), DEPENDS_ON, dep.clone()));
+        }
+    }
+
+    synapse
+        .ingest_from(Source::Gateway, triples.iter().map(|(s, p, o)| (s.as_str(), *p, o.as_str())).collect())
+        .await
+}
+
+/// Progress of every initiative, rolled up from the states of its tasks.
+pub async fn statuses(synapse: &Synapse) -> anyhow::Result<Vec<InitiativeStatus>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?initiative ?title ?task ?repo ?state ?escalated ?reset
+        WHERE {
+            ?initiative a swarm:Initiative ;
+                        swarm:title ?title .
+            ?task swarm:partOfInitiative ?initiative ;
+                  swarm:repository ?repo ;
+                  swarm:internalState ?state .
+            OPTIONAL { ?task swarm:escalatedAt ?escalated }
+            OPTIONAL { ?task swarm:resetAt ?reset }
+        }
+    "#;
+    let res_json = synapse.query_cached(query, crate::query_cache::DEFAULT_TTL).await?;
+    let rows = serde_json::from_str::<Vec<serde_json::Value>>(&res_json).unwrap_or_default();
+    Ok(rollup(&rows))
+}
+
+/// A task's repository, and every state, escalation and reset it went through.
+#[derive(Default)]
+struct TaskHistory {
+    repo: String,
+    states: Vec<String>,
+    escalated: Vec<String>,
+    resets: Vec<String>,
+}
+
+type TaskStates = BTreeMap<String, TaskHistory>;
+
+fn rollup(rows: &[serde_json::Value]) -> Vec<InitiativeStatus> {
+    let field = |row: &serde_json::Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(|v| v.as_str())
+            .map(|v| tasks::literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+
+    let mut initiatives: BTreeMap<String, (String, TaskStates)> = BTreeMap::new();
+    for row in rows {
+        let (Some(id), Some(task), Some(repo), Some(state)) =
+            (field(row, "initiative"), field(row, "task"), field(row, "repo"), field(row, "state"))
+        else {
+            continue;
+        };
+        let entry = initiatives.entry(id).or_default();
+        if let Some(title) = field(row, "title") {
+            entry.0 = title;
+        }
+        let repo = repo.rsplit('/').next().unwrap_or(&repo).to_string();
+        let task = entry.1.entry(task).or_insert_with(|| TaskHistory { repo, ..Default::default() });
+        if !task.states.contains(&state) {
+            task.states.push(state);
+        }
+        task.escalated.extend(field(row, "escalated"));
+        task.resets.extend(field(row, "reset"));
+    }
+
+    initiatives
+        .into_iter()
+        .map(|(id, (title, tasks))| {
+            let mut repositories: BTreeMap<String, InitiativeRepositoryProgress> = BTreeMap::new();
+            let (mut done, mut blocked, mut started) = (0, 0, false);
+            for TaskHistory { repo, states, escalated, resets } in tasks.values() {
+                let state = tasks::current_state_after_resets(states, escalated, resets).unwrap_or("UNKNOWN");
+                let progress = repositories.entry(repo.clone()).or_insert_with(|| InitiativeRepositoryProgress {
+                    repository: repo.clone(),
+                    tasks_total: 0,
+                    tasks_done: 0,
+                });
+                progress.tasks_total += 1;
+                match state {
+                    "DONE" => {
+                        done += 1;
+                        progress.tasks_done += 1;
+                    }
+                    "DEAD_LETTER" | "REJECTED" | "ESCALATED" => blocked += 1,
+                    "REQUIREMENTS" | "AWAITING_APPROVAL" | "APPROVED" => {}
+                    _ => started = true,
+                }
+            }
+
+            let total = tasks.len();
+            let status = if done == total {
+                QuestStatus::Done
+            } else if blocked > 0 {
+                QuestStatus::Blocked
+            } else if started || done > 0 {
+                QuestStatus::InProgress
+            } else {
+                QuestStatus::Ready
+            };
+            InitiativeStatus {
+                id,
+                title,
+                status,
+                tasks_total: total,
+                tasks_done: done,
+                tasks_blocked: blocked,
+                progress: if total == 0 { 0.0 } else { done as f64 / total as f64 },
+                repositories: repositories.into_values().collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(lead: Option<&str>) -> InitiativeRequest {
+        InitiativeRequest {
+            title: "Add SSO".to_string(),
+            repositories: vec!["web".to_string(), "auth-lib".to_string()],
+            steps: vec![],
+            lead_repository: lead.map(String::from),
+        }
+    }
+
+    #[test]
+    fn lead_repository_is_planned_first_and_gates_the_others() {
+        let planned = plan("init", &request(Some("auth-lib"))).unwrap();
+        let ids: Vec<_> = planned.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids[..3], ["init/auth-lib/design", "init/auth-lib/implement", "init/auth-lib/verify"]);
+        assert_eq!(planned[3].id, "init/web/design");
+        assert_eq!(planned[3].depends_on, vec!["init/auth-lib/verify"]);
+        assert_eq!(planned[4].depends_on, vec!["init/web/design"]);
+
+        let cyclic = InitiativeRequest {
+            steps: vec![
+                BlueprintStep { key: "a".into(), title: "A".into(), depends_on: vec!["b".into()] },
+                BlueprintStep { key: "b".into(), title: "B".into(), depends_on: vec!["a".into()] },
+            ],
+            ..request(None)
+        };
+        assert!(plan("init", &cyclic).is_err());
+    }
+
+    #[test]
+    fn progress_is_rolled_up_per_repository() {
+        let row = |task: &str, repo: &str, state: &str| {
+            json!({"initiative": "<http://swarm.os/initiative/1>", "title": "\"Add SSO\"", "task": task, "repo": repo, "state": state})
+        };
+        let rows = vec![
+            row("t1", "<http://swarm.os/repository/web>", "\"REQUIREMENTS\""),
+            row("t1", "<http://swarm.os/repository/web>", "\"DONE\""),
+            row("t2", "<http://swarm.os/repository/web>", "\"PROCESSING\""),
+            row("t3", "<http://swarm.os/repository/api>", "\"REQUIREMENTS\""),
+        ];
+        let statuses = rollup(&rows);
+        assert_eq!(statuses.len(), 1);
+        let s = &statuses[0];
+        assert_eq!((s.tasks_total, s.tasks_done, s.status.clone()), (3, 1, QuestStatus::InProgress));
+        assert_eq!(s.repositories[1].repository, "web");
+        assert_eq!(s.repositories[1].tasks_done, 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! Native LLM client, for agent behaviors simple enough to run inside swarmd (triage,
+//! summaries, chat replies) instead of through a Python orchestrator. It speaks the OpenAI
+//! chat completions API, which most gateways and local servers also serve, or the Anthropic
+//! messages API, at `SWARM_LLM_BASE_URL` when set.
+//!
+//! Each reply is capped at `SWARM_LLM_MAX_TOKENS`. Its cost, at the per-1K token prices of
+//! `SWARM_LLM_*_USD_PER_1K`, is recorded as a `swarm:SpendEvent` like the Python client's, along
+//! with its token counts and model, and no call is made once the daily budget is spent, in
+//! dollars or in `DAILY_TOKEN_BUDGET` tokens.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::budget::{self, BudgetLimit, Spend};
+use crate::config::AppConfig;
+use crate::store::Synapse;
+
+/// The API a provider speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenAi,
+    Anthropic,
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::OpenAi => "openai",
+            Provider::Anthropic => "anthropic",
+        }
+    }
+
+    fn default_base_url(&self) -> &'static str {
+        match self {
+            Provider::OpenAi => "https://api.openai.com/v1",
+            Provider::Anthropic => "https://api.anthropic.com/v1",
+        }
+    }
+
+    fn default_model(&self) -> &'static str {
+        match self {
+            Provider::OpenAi => "gpt-4o-mini",
+            Provider::Anthropic => "claude-3-5-haiku-latest",
+        }
+    }
+}
+
+impl FromStr for Provider {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> anyhow::Result<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "openai" => Ok(Provider::OpenAi),
+            "anthropic" => Ok(Provider::Anthropic),
+            other => bail!("SWARM_LLM_PROVIDER must be openai or anthropic, not {:?}", other),
+        }
+    }
+}
+
+/// One reply and what it used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Who a call is made for, so its spend is attributed.
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    pub agent_id: Option<String>,
+    pub task_id: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Llm {
+    provider: Provider,
+    base_url: String,
+    model: String,
+    api_key: String,
+    max_tokens: u32,
+    /// USD per 1K prompt and completion tokens.
+    prices: (f64, f64),
+    synapse: Synapse,
+    budget: BudgetLimit,
+    http: reqwest::Client,
+}
+
+impl fmt::Debug for Llm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Llm").field("provider", &self.provider).field("base_url", &self.base_url).field("model", &self.model).finish()
+    }
+}
+
+impl fmt::Display for Llm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} at {}", self.provider.as_str(), self.model, self.base_url)
+    }
+}
+
+impl Llm {
+    /// The client `cfg` describes; `Ok(None)` when no API key is set.
+    pub fn from_config(cfg: &AppConfig, synapse: Synapse, budget: BudgetLimit) -> anyhow::Result<Option<Self>> {
+        let provider: Provider = cfg.llm_provider.parse()?;
+        let Some(api_key) = cfg.llm_api_key.clone() else { return Ok(None) };
+        let base_url = cfg.llm_base_url.as_deref().unwrap_or(provider.default_base_url()).trim_end_matches('/').to_string();
+        reqwest::Url::parse(&base_url).with_context(|| format!("SWARM_LLM_BASE_URL {:?} is not a URL", base_url))?;
+        Ok(Some(Self {
+            provider,
+            base_url,
+            model: cfg.llm_model.clone().unwrap_or_else(|| provider.default_model().to_string()),
+            api_key,
+            max_tokens: cfg.llm_max_tokens.max(1),
+            prices: (cfg.llm_input_usd_per_1k, cfg.llm_output_usd_per_1k),
+            synapse,
+            budget,
+            http: reqwest::Client::new(),
+        }))
+    }
+
+    /// The reply to `prompt`, whose spend is charged to the daily budget.
+    pub async fn complete(&self, caller: &Caller, system: &str, prompt: &str) -> anyhow::Result<Completion> {
+        let spent = budget::spent_today(&self.synapse).await?;
+        if spent >= self.budget.get() {
+            bail!("the daily budget of ${:.2} is spent", self.budget.get());
+        }
+        if let Some(max) = self.budget.max_tokens() {
+            if budget::tokens_today(&self.synapse, &self.budget).await?.total() >= max {
+                bail!("the daily budget of {} tokens is spent", max);
+            }
+        }
+
+        let (url, body) = self.request_body(system, prompt);
+        let request = match self.provider {
+            Provider::OpenAi => self.http.post(url).bearer_auth(&self.api_key),
+            Provider::Anthropic => self.http.post(url).header("x-api-key", &self.api_key).header("anthropic-version", "2023-06-01"),
+        };
+        let response = request.json(&body).send().await.with_context(|| format!("calling {}", self.provider.as_str()))?;
+        let status = response.status();
+        let reply: Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let detail = reply.pointer("/error/message").and_then(Value::as_str).unwrap_or_default();
+            bail!("{} answered HTTP {}: {}", self.provider.as_str(), status, detail);
+        }
+        let completion = self.parse_reply(&reply)?;
+
+        let spend = Spend {
+            amount_usd: completion.cost_usd,
+            agent_id: caller.agent_id.clone(),
+            task_id: caller.task_id.clone(),
+            repository: None,
+            prompt_tokens: Some(completion.input_tokens),
+            completion_tokens: Some(completion.output_tokens),
+            model: Some(self.model.clone()),
+        };
+        // Token-metered contracts price calls at zero and still count their tokens.
+        if spend.amount_usd > 0.0 || completion.input_tokens + completion.output_tokens > 0 {
+            if let Err(e) = budget::record_spend(&self.synapse, &spend).await {
+                warn!("Could not record ${:.4} of LLM spend: {}", spend.amount_usd, e);
+            }
+        }
+        Ok(completion)
+    }
+
+    fn request_body(&self, system: &str, prompt: &str) -> (String, Value) {
+        let mut turns = vec![json!({"role": "user", "content": prompt})];
+        match self.provider {
+            Provider::OpenAi => {
+                if !system.is_empty() {
+                    turns.insert(0, json!({"role": "system", "content": system}));
+                }
+                let body = json!({"model": self.model, "max_tokens": self.max_tokens, "messages": turns});
+                (format!("{}/chat/completions", self.base_url), body)
+            }
+            Provider::Anthropic => {
+                let mut body = json!({"model": self.model, "max_tokens": self.max_tokens, "messages": turns});
+                if !system.is_empty() {
+                    body["system"] = json!(system);
+                }
+                (format!("{}/messages", self.base_url), body)
+            }
+        }
+    }
+
+    fn parse_reply(&self, reply: &Value) -> anyhow::Result<Completion> {
+        #[derive(Deserialize, Default)]
+        #[serde(default)]
+        struct Usage {
+            prompt_tokens: u64,
+            completion_tokens: u64,
+            input_tokens: u64,
+            output_tokens: u64,
+        }
+        let usage: Usage = reply.get("usage").cloned().and_then(|u| serde_json::from_value(u).ok()).unwrap_or_default();
+        let (text, input_tokens, output_tokens) = match self.provider {
+            Provider::OpenAi => (
+                reply.pointer("/choices/0/message/content").and_then(Value::as_str).map(str::to_string),
+                usage.prompt_tokens,
+                usage.completion_tokens,
+            ),
+            Provider::Anthropic => {
+                let blocks = reply.get("content").and_then(Value::as_array);
+                let text = blocks.map(|blocks| blocks.iter().filter_map(|b| b.get("text").and_then(Value::as_str)).collect::<String>());
+                (text, usage.input_tokens, usage.output_tokens)
+            }
+        };
+        let Some(text) = text else { bail!("{} sent a reply without text", self.provider.as_str()) };
+        let cost_usd = (input_tokens as f64 * self.prices.0 + output_tokens as f64 * self.prices.1) / 1000.0;
+        Ok(Completion { text, input_tokens, output_tokens, cost_usd })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    fn client(provider: Provider, synapse: Synapse) -> Llm {
+        Llm {
+            provider,
+            base_url: provider.default_base_url().to_string(),
+            model: provider.default_model().to_string(),
+            api_key: "key".into(),
+            max_tokens: 256,
+            prices: (0.005, 0.015),
+            synapse,
+            budget: BudgetLimit::new(10.0),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    #[test]
+    fn speaks_both_apis() {
+        let (_, synapse) = MemoryStore::shared();
+
+        let openai = client(Provider::OpenAi, synapse.clone());
+        let (url, body) = openai.request_body("Be brief", "Triage this");
+        assert_eq!(url, "https://api.openai.com/v1/chat/completions");
+        assert_eq!(body["messages"][0], json!({"role": "system", "content": "Be brief"}));
+        assert_eq!(body["messages"][1], json!({"role": "user", "content": "Triage this"}));
+        assert_eq!(body["max_tokens"], 256);
+        let reply = json!({"choices": [{"message": {"content": "Coder"}}], "usage": {"prompt_tokens": 1000, "completion_tokens": 200}});
+        let completion = openai.parse_reply(&reply).unwrap();
+        assert_eq!((completion.text.as_str(), completion.input_tokens, completion.output_tokens), ("Coder", 1000, 200));
+        assert!((completion.cost_usd - 0.008).abs() < 1e-9);
+
+        let anthropic = client(Provider::Anthropic, synapse);
+        let (url, body) = anthropic.request_body("Be brief", "Triage this");
+        assert_eq!(url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(body["system"], "Be brief");
+        assert_eq!(body["messages"][0], json!({"role": "user", "content": "Triage this"}));
+        let reply = json!({"content": [{"type": "text", "text": "Co"}, {"type": "text", "text": "der"}], "usage": {"input_tokens": 10, "output_tokens": 2}});
+        assert_eq!(anthropic.parse_reply(&reply).unwrap().text, "Coder");
+        assert!(anthropic.parse_reply(&json!({"error": {"message": "overloaded"}})).is_err());
+
+        assert_eq!("Anthropic".parse::<Provider>().unwrap(), Provider::Anthropic);
+        assert!("gemini".parse::<Provider>().is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_calls_once_the_budget_is_spent() {
+        let (_, synapse) = MemoryStore::shared();
+        let llm = Llm { budget: BudgetLimit::new(0.0), ..client(Provider::OpenAi, synapse) };
+        let e = llm.complete(&Caller::default(), "", "hi").await.unwrap_err();
+        assert!(e.to_string().contains("daily budget of $0.00 is spent"));
+
+        let (store, synapse) = MemoryStore::shared();
+        store.answer("promptTokens", json!([{ "?event": "<http://swarm.os/e1>", "?promptTokens": "\"80\"", "?completionTokens": "\"20\"" }]));
+        let llm = client(Provider::OpenAi, synapse);
+        llm.budget.set_max_tokens(100);
+        let e = llm.complete(&Caller::default(), "", "hi").await.unwrap_err();
+        assert!(e.to_string().contains("daily budget of 100 tokens is spent"));
+    }
+}
@@ -1,33 +1,119 @@
 mod config;
 mod server;
-mod synapse;
+mod store;
 mod workers;
 mod notifications;
 mod discovery;
+mod exploration;
+mod experience;
+mod achievements;
+mod party;
 mod chaos;
+mod tasks;
+mod triage;
+mod hooks;
+mod provenance;
+mod budget;
+mod init;
+mod approvals;
+mod audit;
+mod capabilities;
+mod dependencies;
+mod health;
+mod initiatives;
+mod query_cache;
+mod resource_usage;
+mod outbox;
+mod maintenance;
+mod roster;
+mod schedules;
+mod sparql;
+mod escalation;
+mod clock;
+mod commands;
+mod state;
+mod telemetry;
+mod roles;
+mod artifacts;
+mod llm;
 
-use anyhow::Result;
-use tracing::info;
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::sync::Arc;
+use tracing::{info, warn};
 use tokio::sync::{mpsc, broadcast};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
-    
+    let cli = commands::Cli::parse();
+    match cli.command.unwrap_or(commands::Command::Run) {
+        commands::Command::Run => run().await,
+        commands::Command::CheckConfig => commands::check_config(),
+        commands::Command::Discover { once, interval } => commands::discover(once, interval).await,
+        commands::Command::Query { sparql } => commands::query(&sparql).await,
+        commands::Command::ImportTrello { since, usd_per_task, dry_run } => {
+            commands::import_trello(workers::trello::ImportOptions { since, usd_per_task, dry_run }).await
+        }
+        commands::Command::Ask { prompt, system } => commands::ask(&prompt, &system).await,
+        commands::Command::Init => init::run().await,
+    }
+}
+
+async fn run() -> Result<()> {
+    let _telemetry = telemetry::init()?;
+
     // 1. Load Configuration
     let cfg = config::AppConfig::load()?;
     info!("🚀 Swarm Orchestrator (swarmd) starting up...");
+    let tz = clock::init(&cfg.display_timezone)?;
+    info!("🕰️ Showing times in {}", tz);
+    if cfg.demo_mode {
+        info!("🎭 Demo mode: serving a simulated swarm; Synapse and the workers stay off");
+        return server::start_demo_server(cfg.gateway_port, cfg.demo_seed, cfg.demo_rate_limit).await;
+    }
+    state::init(&cfg.state_path).with_context(|| format!("opening the state store at {}", cfg.state_path))?;
 
     // 2. Setup Communication Channels
     let (tx, rx) = mpsc::channel(100);
-    let (event_tx, _) = broadcast::channel(100);
+    let (event_tx, _) = broadcast::channel(server::event_bus::EVENT_BUS_CAPACITY);
 
     // 3. Connect to Synapse Core
-    let syn_client = synapse::SynapseClient::connect(&cfg.synapse_grpc_host, &cfg.synapse_grpc_port).await?;
-    info!("🔗 Connected to Synapse at {}:{}", cfg.synapse_grpc_host, cfg.synapse_grpc_port);
+    let endpoint = store::Endpoint::from_config(&cfg)?;
+    let mut syn_client = store::Synapse::connect(&endpoint).await?;
+    let tls = if endpoint.tls.is_some() { " over TLS" } else { "" };
+    info!("🔗 Connected to Synapse at {}:{}{}", cfg.synapse_grpc_host, cfg.synapse_grpc_port, tls);
+    if let Some(graph) = &cfg.synapse_graph {
+        info!("🗂️ Reading and writing only the named graph {}", graph);
+    }
+    if cfg.synapse_breaker_failures > 0 {
+        let cooldown = std::time::Duration::from_secs(cfg.synapse_breaker_cooldown_secs);
+        syn_client = syn_client.with_circuit_breaker(cfg.synapse_breaker_failures, cooldown, tx.clone());
+    }
+    if cfg.synapse_write_buffer {
+        syn_client = syn_client.with_write_buffer();
+        info!("🔌 Writes are buffered in {} while Synapse is unreachable", cfg.state_path);
+    }
+
+    let budget = budget::BudgetLimit::load(&syn_client, cfg.daily_budget_usd).await;
+    budget.set_max_tokens(cfg.daily_token_budget);
+
+    if let Err(e) = maintenance::restore(&syn_client).await {
+        warn!("Could not restore maintenance mode: {}", e);
+    }
+    if let Some(banner) = maintenance::current() {
+        info!("🚧 Still in maintenance mode (since {}); no tasks will be scheduled", clock::display_rfc3339(&banner.since));
+    }
 
-    // Run geopolitical discovery
-    discovery::discover_repositories(&syn_client, ".").await;
+    let health = Arc::new(health::Health::default());
+    tokio::spawn(health.clone().run_synapse_probe(syn_client.clone()));
+
+    if let Some(path) = &cfg.agent_roster_path {
+        let agents = roster::load(std::path::Path::new(path))?;
+        info!("👥 Loaded {} agents from {}", agents.len(), path);
+    }
+
+    // Register the checkouts under the workspace root
+    discovery::discover_repositories(&syn_client, &cfg.workspace_root, &tx).await;
 
     // Start Chaos Engine
     let chaos = chaos::ChaosEngine::new(event_tx.clone());
@@ -35,20 +121,35 @@ async fn main() -> Result<()> {
         chaos.run().await;
     });
 
+    // Signed event webhooks for registered visualizer backends
+    if let Err(e) = outbox::shared().load(std::path::Path::new(&cfg.webhook_subscriptions_path)) {
+        warn!("Could not load webhook subscriptions from {}: {}", cfg.webhook_subscriptions_path, e);
+    }
+    tokio::spawn(outbox::shared().run(reqwest::Client::new()));
+
+    // Chat platforms that push to the gateway instead of being polled
+    let (chat_webhooks, chat_inbox) = workers::chat_webhooks(&cfg);
+
+    // Reloads on SIGHUP or config file changes and hands the result to the workers
+    let config = config::watch(cfg.clone());
+
     // 4. Spawn Background Workers (Telegram, Trello, etc)
     workers::start_background_workers(
-        cfg.telegram_bot_token.clone(),
-        cfg.telegram_chat_id.clone(),
-        cfg.trello_api_key,
-        cfg.trello_token,
-        cfg.trello_board_id,
+        config,
         syn_client.clone(),
+        budget.clone(),
+        health.clone(),
+        chat_inbox,
         tx.clone(),
         rx,
     ).await;
 
     // 5. Start HTTP Gateway (blocking)
-    server::start_server(cfg.gateway_port, syn_client, event_tx).await?;
+    let tokens = server::auth::ApiTokens::parse(cfg.api_tokens.as_deref().unwrap_or_default());
+    let artifacts = artifacts::Artifacts::from_config(&cfg)?;
+    info!("📦 Keeping registered artifacts in {}", artifacts.location());
+    let snapshots = (cfg.game_state_snapshot_secs > 0).then(|| std::time::Duration::from_secs(cfg.game_state_snapshot_secs));
+    server::start_server(cfg.gateway_port, syn_client, budget, health, tokens, chat_webhooks, event_tx, artifacts, snapshots).await?;
     
     Ok(())
 }
@@ -0,0 +1,138 @@
+//! Maintenance mode: unlike a HALT, intake keeps running so no task is lost, but the agency
+//! starts no new work and notifications are held back. Leaving maintenance releases the held
+//! notifications and lets the agency work through the queued backlog by priority.
+//!
+//! The mode is recorded as `swarm:MaintenanceEvent`s in Synapse and mirrored in a process-wide
+//! watch channel, so the scheduler and the notification router react without a query.
+
+use std::sync::OnceLock;
+
+use serde_json::Value;
+use tokio::sync::watch;
+
+use crate::provenance::Source;
+use crate::server::contracts::MaintenanceBanner;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, literal_value};
+
+pub const MAINTENANCE_EVENT: &str = "http://swarm.os/ontology/MaintenanceEvent";
+/// `ON` or `OFF`.
+pub const MAINTENANCE_MODE: &str = "http://swarm.os/ontology/maintenanceMode";
+pub const MAINTENANCE_REASON: &str = "http://swarm.os/ontology/reason";
+const GENERATED_AT: &str = "http://www.w3.org/ns/prov#generatedAtTime";
+
+fn channel() -> &'static watch::Sender<Option<MaintenanceBanner>> {
+    static MODE: OnceLock<watch::Sender<Option<MaintenanceBanner>>> = OnceLock::new();
+    MODE.get_or_init(|| watch::channel(None).0)
+}
+
+/// The banner while maintenance is on.
+pub fn current() -> Option<MaintenanceBanner> {
+    channel().borrow().clone()
+}
+
+pub fn is_active() -> bool {
+    channel().borrow().is_some()
+}
+
+/// Notified whenever maintenance is entered or left.
+pub fn subscribe() -> watch::Receiver<Option<MaintenanceBanner>> {
+    channel().subscribe()
+}
+
+/// Enters (`enabled`) or leaves maintenance and records the change.
+pub async fn set(
+    synapse: &Synapse,
+    enabled: bool,
+    reason: Option<&str>,
+    source: Source,
+) -> anyhow::Result<Option<MaintenanceBanner>> {
+    let now = chrono::Utc::now();
+    let event_id = format!("http://swarm.os/maintenance/{}", uuid::Uuid::new_v4());
+    let mode_lit = format!("\"{}\"", if enabled { "ON" } else { "OFF" });
+    let at_lit = tasks::datetime_literal(&now);
+    let reason_lit = reason.map(sparql::literal);
+
+    let mut triples = vec![
+        (event_id.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", MAINTENANCE_EVENT),
+        (event_id.as_str(), MAINTENANCE_MODE, mode_lit.as_str()),
+        (event_id.as_str(), GENERATED_AT, at_lit.as_str()),
+        ("http://nist.gov/caisi/SystemControl", "http://nist.gov/caisi/hasStatusHistory", event_id.as_str()),
+    ];
+    if let Some(reason_lit) = &reason_lit {
+        triples.push((event_id.as_str(), MAINTENANCE_REASON, reason_lit.as_str()));
+    }
+    synapse.ingest_from(source, triples).await?;
+
+    let banner = enabled.then(|| MaintenanceBanner { since: now.to_rfc3339(), reason: reason.map(str::to_string) });
+    channel().send_replace(banner.clone());
+    Ok(banner)
+}
+
+/// Picks up the mode recorded before the last restart.
+pub async fn restore(synapse: &Synapse) -> anyhow::Result<()> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        PREFIX prov: <http://www.w3.org/ns/prov#>
+        SELECT ?event ?mode ?at ?reason
+        WHERE {
+            ?event a swarm:MaintenanceEvent ;
+                   swarm:maintenanceMode ?mode ;
+                   prov:generatedAtTime ?at .
+            OPTIONAL { ?event swarm:reason ?reason }
+        }
+    "#;
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(query).await?).unwrap_or_default();
+    channel().send_replace(latest(&rows));
+    Ok(())
+}
+
+/// The banner described by the most recent event, if that event turned maintenance on.
+fn latest(rows: &[Value]) -> Option<MaintenanceBanner> {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| literal_value(v).to_string())
+    };
+    let newest = rows
+        .iter()
+        .filter_map(|row| {
+            let at = chrono::DateTime::parse_from_rfc3339(&field(row, "at")?).ok()?;
+            Some((at, row))
+        })
+        .max_by_key(|(at, _)| *at)?;
+
+    let (at, row) = newest;
+    (field(row, "mode")? == "ON").then(|| MaintenanceBanner {
+        since: at.with_timezone(&chrono::Utc).to_rfc3339(),
+        reason: field(row, "reason"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn the_newest_event_decides_the_mode() {
+        let event = |mode: &str, at: &str| json!({ "?mode": format!("\"{mode}\""), "?at": format!("\"{at}\"^^<{}>", tasks::XSD_DATETIME) });
+        let mut rows = vec![
+            event("ON", "2026-10-01T08:00:00+00:00"),
+            event("OFF", "2026-10-01T09:00:00+00:00"),
+        ];
+        assert_eq!(latest(&rows), None);
+
+        rows.push(json!({
+            "?mode": "\"ON\"",
+            "?at": "\"2026-10-02T07:30:00+00:00\"",
+            "?reason": "\"Synapse upgrade\"",
+        }));
+        let banner = latest(&rows).unwrap();
+        assert_eq!(banner.since, "2026-10-02T07:30:00+00:00");
+        assert_eq!(banner.reason.as_deref(), Some("Synapse upgrade"));
+        assert_eq!(latest(&[]), None);
+    }
+}
@@ -1,7 +1,194 @@
+mod dedupe;
+mod render;
+mod routing;
+mod sinks;
+
 use serde::{Serialize, Deserialize};
 
+use crate::approvals::ApprovalRequest;
+use crate::provenance::Source;
+
+pub use render::{ChannelRenderer, DiscordMarkdown, PlainText, SlackBlocks, TelegramMarkdownV2};
+pub use routing::Routes;
+pub use sinks::{subscribe, NotificationRouter, NotificationSink, SlackSink, StdoutSink, WebhookSink};
+
+/// How urgently a notification needs a human. Ordered, so routes can ask for `Warn` and above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Chatter such as a running task's output.
+    Debug,
+    /// Progress worth following.
+    Info,
+    /// Something went wrong or needs a decision.
+    Warn,
+    /// Work has stopped until someone acts: the budget ran out, a task was dead-lettered.
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [Severity::Debug, Severity::Info, Severity::Warn, Severity::Critical];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Critical => "critical",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str().eq_ignore_ascii_case(raw.trim()))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum Notification {
-    Trace(String),
-    Alert(String),
+pub enum NotificationBody {
+    Trace(Message),
+    Alert(Message),
+    /// Asks a human to approve or reject a high-risk task. Interactive channels attach
+    /// Approve / Reject buttons.
+    Approval(ApprovalRequest),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    #[serde(flatten)]
+    pub body: NotificationBody,
+    pub severity: Severity,
+    /// The part of the swarm that raised it, for routing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// IRI of the task it is about, for the history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+}
+
+impl Notification {
+    /// An `Info` trace.
+    pub fn trace(msg: impl Into<Message>) -> Self {
+        Self::new(NotificationBody::Trace(msg.into()), Severity::Info)
+    }
+
+    /// A `Warn` alert.
+    pub fn alert(msg: impl Into<Message>) -> Self {
+        Self::new(NotificationBody::Alert(msg.into()), Severity::Warn)
+    }
+
+    /// A `Critical` alert.
+    pub fn critical(msg: impl Into<Message>) -> Self {
+        Self::new(NotificationBody::Alert(msg.into()), Severity::Critical)
+    }
+
+    /// A `Warn` approval request, about its task.
+    pub fn approval(req: ApprovalRequest) -> Self {
+        let task = req.task_id.clone();
+        Self::new(NotificationBody::Approval(req), Severity::Warn).with_task(task)
+    }
+
+    fn new(body: NotificationBody, severity: Severity) -> Self {
+        Self { body, severity, source: None, task: None }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_task(mut self, task: impl Into<String>) -> Self {
+        self.task = Some(task.into());
+        self
+    }
+
+    /// `trace`, `alert` or `approval`, for machine consumers.
+    pub fn kind(&self) -> &'static str {
+        match self.body {
+            NotificationBody::Trace(_) => "trace",
+            NotificationBody::Alert(_) => "alert",
+            NotificationBody::Approval(_) => "approval",
+        }
+    }
+
+    /// How it is kept in the notification history, sent now.
+    pub fn history_entry(&self) -> crate::state::StoredNotification {
+        crate::state::StoredNotification {
+            at: chrono::Utc::now().to_rfc3339(),
+            kind: self.kind().to_string(),
+            severity: self.severity.as_str().to_string(),
+            source: self.source.map(|s| s.as_str().to_string()),
+            task: self.task.clone(),
+            text: PlainText.render(self),
+        }
+    }
+
+    /// The message as it should be shown to a human, prefixed with its level.
+    pub fn decorated(&self) -> Message {
+        let (prefix, msg) = match &self.body {
+            NotificationBody::Trace(msg) => ("👁️ [TRACE] ", msg),
+            NotificationBody::Alert(msg) if self.severity == Severity::Critical => ("🔥 [CRITICAL] ", msg),
+            NotificationBody::Alert(msg) => ("🚨 [ALERT] ", msg),
+            NotificationBody::Approval(req) => {
+                return Message::new()
+                    .text("🛡️ [APPROVAL] Task ")
+                    .strong(&req.title)
+                    .text(format!(" needs approval: {}", req.reason));
+            }
+        };
+        let mut out = Message::new().text(prefix);
+        out.fragments.extend(msg.fragments.iter().cloned());
+        out
+    }
+}
+
+/// A piece of formatted text. Senders describe emphasis structurally and each channel
+/// renderer decides how to express (and escape) it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fragment {
+    Text(String),
+    Strong(String),
+    Code(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    pub fragments: Vec<Fragment>,
+}
+
+impl Message {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, s: impl Into<String>) -> Self {
+        self.fragments.push(Fragment::Text(s.into()));
+        self
+    }
+
+    pub fn strong(mut self, s: impl Into<String>) -> Self {
+        self.fragments.push(Fragment::Strong(s.into()));
+        self
+    }
+
+    pub fn code(mut self, s: impl Into<String>) -> Self {
+        self.fragments.push(Fragment::Code(s.into()));
+        self
+    }
+}
+
+impl From<&str> for Message {
+    fn from(s: &str) -> Self {
+        Message::new().text(s)
+    }
+}
+
+impl From<String> for Message {
+    fn from(s: String) -> Self {
+        Message::new().text(s)
+    }
 }
@@ -0,0 +1,124 @@
+//! Collapses repeats. The first of identical notifications goes out; the same one raised again
+//! within the window only counts, and once the window closes the count goes out as one summary,
+//! `… (x47 in last 10m)`, which opens a new window. An alert flapping between two messages
+//! therefore costs each message at most one notification per window. Approval requests are
+//! never collapsed. Only delivery to the sinks is collapsed; the router keeps every repeat in
+//! history and passes it to the gateway feed.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::{ChannelRenderer, Fragment, Notification, NotificationBody, PlainText};
+
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(600);
+
+struct Seen {
+    since: Instant,
+    repeats: u32,
+    latest: Notification,
+}
+
+pub struct Dedupe {
+    window: Duration,
+    seen: HashMap<String, Seen>,
+}
+
+impl Dedupe {
+    pub fn new(window: Duration) -> Self {
+        Self { window, seen: HashMap::new() }
+    }
+
+    /// Whether `notification` should go out now; a repeat within the window is only counted.
+    pub fn admit(&mut self, notification: &Notification, now: Instant) -> bool {
+        if self.window.is_zero() || matches!(notification.body, NotificationBody::Approval(_)) {
+            return true;
+        }
+        let key = format!("{}:{}", notification.severity.as_str(), PlainText.render(notification));
+        match self.seen.get_mut(&key) {
+            Some(seen) if now.duration_since(seen.since) < self.window => {
+                seen.repeats += 1;
+                seen.latest = notification.clone();
+                false
+            }
+            _ => {
+                self.seen.insert(key, Seen { since: now, repeats: 0, latest: notification.clone() });
+                true
+            }
+        }
+    }
+
+    /// Summaries of the repeats whose window has closed. Notifications that were not repeated
+    /// are forgotten, so the next one goes out as it is.
+    pub fn due(&mut self, now: Instant) -> Vec<Notification> {
+        let window = self.window;
+        let mut summaries = Vec::new();
+        self.seen.retain(|_, seen| {
+            if now.duration_since(seen.since) < window {
+                return true;
+            }
+            if seen.repeats == 0 {
+                return false;
+            }
+            summaries.push(summary(&seen.latest, seen.repeats, window));
+            *seen = Seen { since: now, repeats: 0, latest: seen.latest.clone() };
+            true
+        });
+        summaries
+    }
+}
+
+fn summary(notification: &Notification, repeats: u32, window: Duration) -> Notification {
+    let mut summary = notification.clone();
+    if let NotificationBody::Trace(msg) | NotificationBody::Alert(msg) = &mut summary.body {
+        msg.fragments.push(Fragment::Text(format!(" (x{} in last {})", repeats, span(window))));
+    }
+    summary
+}
+
+/// `10m`, `90s`, `2h`.
+fn span(window: Duration) -> String {
+    let secs = window.as_secs();
+    match secs {
+        s if s >= 3600 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_collapse_into_a_summary_per_window() {
+        let mut dedupe = Dedupe::new(DEFAULT_WINDOW);
+        let start = Instant::now();
+        let failed = Notification::alert("Synapse query failed");
+
+        assert!(dedupe.admit(&failed, start));
+        for i in 1..=47 {
+            assert!(!dedupe.admit(&failed, start + Duration::from_secs(i * 10)));
+        }
+        assert!(dedupe.admit(&Notification::trace("Synapse query failed"), start));
+        assert!(dedupe.due(start + Duration::from_secs(599)).is_empty());
+
+        let summaries = dedupe.due(start + DEFAULT_WINDOW);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(PlainText.render(&summaries[0]), "🚨 [ALERT] Synapse query failed (x47 in last 10m)");
+
+        // The summary opened a new window, so the next repeat is still held back.
+        assert!(!dedupe.admit(&failed, start + DEFAULT_WINDOW + Duration::from_secs(1)));
+        assert_eq!(dedupe.due(start + DEFAULT_WINDOW * 3).len(), 1);
+        assert!(dedupe.due(start + DEFAULT_WINDOW * 5).is_empty());
+        assert!(dedupe.admit(&failed, start + DEFAULT_WINDOW * 5));
+    }
+
+    #[test]
+    fn a_zero_window_lets_everything_through() {
+        let mut dedupe = Dedupe::new(Duration::ZERO);
+        let now = Instant::now();
+        assert!(dedupe.admit(&Notification::alert("x"), now));
+        assert!(dedupe.admit(&Notification::alert("x"), now));
+        assert_eq!(span(Duration::from_secs(90)), "90s");
+    }
+}
@@ -0,0 +1,181 @@
+use serde_json::{json, Value};
+
+use super::{Fragment, Message, Notification};
+
+/// Turns a structured message into the wire format of one channel. Escaping lives
+/// here, so callers never hand-write channel markup.
+pub trait ChannelRenderer {
+    type Output;
+
+    fn render_message(&self, msg: &Message) -> Self::Output;
+
+    fn render(&self, notification: &Notification) -> Self::Output {
+        self.render_message(&notification.decorated())
+    }
+}
+
+/// Telegram `parse_mode: MarkdownV2`.
+pub struct TelegramMarkdownV2;
+
+impl TelegramMarkdownV2 {
+    pub const PARSE_MODE: &'static str = "MarkdownV2";
+}
+
+impl ChannelRenderer for TelegramMarkdownV2 {
+    type Output = String;
+
+    fn render_message(&self, msg: &Message) -> String {
+        msg.fragments
+            .iter()
+            .map(|f| match f {
+                Fragment::Text(s) => escape_markdown_v2(s),
+                Fragment::Strong(s) => format!("*{}*", escape_markdown_v2(s)),
+                Fragment::Code(s) => format!("`{}`", escape_markdown_v2_code(s)),
+            })
+            .collect()
+    }
+}
+
+/// Discord message `content` markdown.
+pub struct DiscordMarkdown;
+
+impl DiscordMarkdown {
+    /// Discord rejects longer message content.
+    pub const MAX_LEN: usize = 2000;
+}
+
+impl ChannelRenderer for DiscordMarkdown {
+    type Output = String;
+
+    fn render_message(&self, msg: &Message) -> String {
+        let content: String = msg
+            .fragments
+            .iter()
+            .map(|f| match f {
+                Fragment::Text(s) => escape_discord(s),
+                Fragment::Strong(s) => format!("**{}**", escape_discord(s)),
+                // Inline code cannot escape backticks, so they are swapped for a look-alike.
+                Fragment::Code(s) => format!("`{}`", s.replace('`', "ˋ")),
+            })
+            .collect();
+        match content.char_indices().nth(Self::MAX_LEN - 1) {
+            Some((cut, _)) => format!("{}…", &content[..cut]),
+            None => content,
+        }
+    }
+}
+
+/// Slack Block Kit payload with a plain-text fallback for notifications.
+pub struct SlackBlocks;
+
+impl ChannelRenderer for SlackBlocks {
+    type Output = Value;
+
+    fn render_message(&self, msg: &Message) -> Value {
+        let mrkdwn: String = msg
+            .fragments
+            .iter()
+            .map(|f| match f {
+                Fragment::Text(s) => escape_slack(s),
+                Fragment::Strong(s) => format!("*{}*", escape_slack(s)),
+                Fragment::Code(s) => format!("`{}`", escape_slack(s)),
+            })
+            .collect();
+        json!({
+            "text": PlainText.render_message(msg),
+            "blocks": [{
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": mrkdwn }
+            }]
+        })
+    }
+}
+
+/// Unformatted text for email bodies, webhooks and logs.
+pub struct PlainText;
+
+impl ChannelRenderer for PlainText {
+    type Output = String;
+
+    fn render_message(&self, msg: &Message) -> String {
+        msg.fragments
+            .iter()
+            .map(|f| match f {
+                Fragment::Text(s) | Fragment::Strong(s) | Fragment::Code(s) => s.as_str(),
+            })
+            .collect()
+    }
+}
+
+/// Every character Telegram reserves in MarkdownV2 outside of entities.
+fn escape_markdown_v2(s: &str) -> String {
+    const RESERVED: &[char] = &[
+        '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!', '\\',
+    ];
+    escape_chars(s, RESERVED)
+}
+
+/// Inside `code` entities only the backtick and backslash must be escaped.
+fn escape_markdown_v2_code(s: &str) -> String {
+    escape_chars(s, &['`', '\\'])
+}
+
+fn escape_chars(s: &str, reserved: &[char]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if reserved.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn escape_discord(s: &str) -> String {
+    escape_chars(s, &['\\', '*', '_', '~', '`', '|', '>', '#'])
+}
+
+fn escape_slack(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card_message() -> Message {
+        Message::new().text("New card in ").strong("To_Do").text(": fix user_id parsing (v1.2)!")
+    }
+
+    #[test]
+    fn telegram_escapes_reserved_characters_in_titles() {
+        assert_eq!(
+            TelegramMarkdownV2.render_message(&card_message()),
+            "New card in *To\\_Do*: fix user\\_id parsing \\(v1\\.2\\)\\!"
+        );
+    }
+
+    #[test]
+    fn slack_and_plain_render_the_same_message() {
+        let msg = card_message().text(" <a & b>");
+        assert_eq!(PlainText.render_message(&msg), "New card in To_Do: fix user_id parsing (v1.2)! <a & b>");
+
+        let blocks = SlackBlocks.render_message(&msg);
+        assert_eq!(
+            blocks["blocks"][0]["text"]["text"],
+            "New card in *To_Do*: fix user_id parsing (v1.2)! &lt;a &amp; b&gt;"
+        );
+    }
+
+    #[test]
+    fn discord_escapes_markdown_and_caps_length() {
+        let msg = card_message().text(" ").code("a`b");
+        assert_eq!(
+            DiscordMarkdown.render_message(&msg),
+            "New card in **To\\_Do**: fix user\\_id parsing (v1.2)! `aˋb`"
+        );
+
+        let long = Message::new().text("x".repeat(5000));
+        assert_eq!(DiscordMarkdown.render_message(&long).chars().count(), DiscordMarkdown::MAX_LEN);
+    }
+}
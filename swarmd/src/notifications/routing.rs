@@ -0,0 +1,104 @@
+//! `NOTIFICATION_ROUTES`: which sinks get which notifications. Rules are separated by `;`, each
+//! `severity[@source]=sink,sink`, and match notifications of that severity or above, raised by
+//! that source if one is named; `*` stands for every severity. A notification goes to every
+//! sink of every rule it matches, and to none when it matches no rule. The gateway's feed and
+//! the notification history get every notification whatever the routes say.
+//!
+//! `warn=telegram;critical=webhook;info@github=slack` keeps traces off the chats, sends alerts
+//! and approvals to Telegram, critical alerts to the webhook as well, and GitHub's traces to Slack.
+
+use std::collections::BTreeSet;
+
+use super::{Notification, Severity};
+use crate::provenance::Source;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    min: Severity,
+    source: Option<Source>,
+    sinks: Vec<String>,
+}
+
+impl Rule {
+    fn matches(&self, notification: &Notification) -> bool {
+        notification.severity >= self.min && self.source.is_none_or(|s| notification.source == Some(s))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Routes {
+    rules: Vec<Rule>,
+}
+
+impl Routes {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let rules = raw
+            .split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(parse_rule)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Every sink some rule names, in name order.
+    pub fn sink_names(&self) -> BTreeSet<&str> {
+        self.rules.iter().flat_map(|rule| rule.sinks.iter().map(String::as_str)).collect()
+    }
+
+    pub fn allows(&self, sink: &str, notification: &Notification) -> bool {
+        self.rules.iter().any(|rule| rule.matches(notification) && rule.sinks.iter().any(|s| s == sink))
+    }
+}
+
+fn parse_rule(rule: &str) -> Result<Rule, String> {
+    let (filter, sinks) = rule.split_once('=').ok_or_else(|| format!("route '{}' has no '='", rule))?;
+    let (severity, source) = match filter.split_once('@') {
+        Some((severity, source)) => {
+            let source = Source::parse(source.trim()).ok_or_else(|| format!("unknown source '{}' in route '{}'", source.trim(), rule))?;
+            (severity.trim(), Some(source))
+        }
+        None => (filter.trim(), None),
+    };
+    let min = match severity {
+        "*" => Severity::Debug,
+        other => Severity::parse(other).ok_or_else(|| format!("unknown severity '{}' in route '{}'", other, rule))?,
+    };
+    let sinks: Vec<String> = sinks.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect();
+    if sinks.is_empty() {
+        return Err(format!("route '{}' names no sink", rule));
+    }
+    Ok(Rule { min, source, sinks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_follow_severity_and_source() {
+        let routes = Routes::parse("warn=telegram; critical=webhook ;info@github=slack").unwrap();
+        assert_eq!(routes.sink_names().into_iter().collect::<Vec<_>>(), ["slack", "telegram", "webhook"]);
+
+        let trace = Notification::trace("Task started").with_source(Source::Agency);
+        assert!(!["telegram", "slack", "webhook"].iter().any(|sink| routes.allows(sink, &trace)));
+
+        let github = Notification::trace("PR opened").with_source(Source::GitHub);
+        assert!(routes.allows("slack", &github) && !routes.allows("telegram", &github));
+
+        let alert = Notification::alert("CI failing");
+        assert!(routes.allows("telegram", &alert) && !routes.allows("webhook", &alert));
+
+        let critical = Notification::critical("Budget exhausted");
+        assert!(routes.allows("telegram", &critical) && routes.allows("webhook", &critical));
+    }
+
+    #[test]
+    fn malformed_routes_are_rejected() {
+        assert!(Routes::parse("loud=telegram").is_err());
+        assert!(Routes::parse("warn@jira=telegram").is_err());
+        assert!(Routes::parse("warn=").is_err());
+        assert!(Routes::parse("telegram").is_err());
+        assert_eq!(Routes::parse(" ; ").unwrap(), Routes::default());
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use futures_util::future::{join_all, BoxFuture};
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, info};
+
+use super::dedupe::{self, Dedupe};
+use super::{ChannelRenderer, Notification, PlainText, Routes, SlackBlocks};
+
+/// Notifications kept back during maintenance before the oldest are dropped.
+const MAX_HELD: usize = 200;
+/// Notifications a slow gateway subscriber may fall behind by before it misses some.
+const FEED_CAPACITY: usize = 256;
+/// How often collapsed repeats are checked for a summary that is due.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(15);
+
+fn feed() -> &'static broadcast::Sender<Notification> {
+    static FEED: OnceLock<broadcast::Sender<Notification>> = OnceLock::new();
+    FEED.get_or_init(|| broadcast::channel(FEED_CAPACITY).0)
+}
+
+/// Every notification the router delivers from now on, whatever sinks are configured.
+pub fn subscribe() -> broadcast::Receiver<Notification> {
+    feed().subscribe()
+}
+
+/// A destination for notifications. Implementations render with their channel's
+/// `ChannelRenderer` and report delivery failures instead of logging them.
+pub trait NotificationSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Fans every notification out to the configured sinks its routes allow, or to all of them
+/// without routes, collapsing repeats within the dedupe window. Deduplication only spares the
+/// sinks: history and the gateway feed get every notification, repeats included. A failing
+/// sink never keeps the others from receiving it.
+pub struct NotificationRouter {
+    sinks: Vec<Box<dyn NotificationSink>>,
+    routes: Option<Routes>,
+    dedupe_window: Duration,
+}
+
+impl NotificationRouter {
+    pub fn new(sinks: Vec<Box<dyn NotificationSink>>) -> Self {
+        Self { sinks, routes: None, dedupe_window: dedupe::DEFAULT_WINDOW }
+    }
+
+    /// Zero turns deduplication off.
+    pub fn with_dedupe_window(mut self, window: Duration) -> Self {
+        self.dedupe_window = window;
+        self
+    }
+
+    pub fn with_routes(mut self, routes: Routes) -> Self {
+        self.routes = Some(routes);
+        self
+    }
+
+    pub fn sink_names(&self) -> Vec<&'static str> {
+        self.sinks.iter().map(|s| s.name()).collect()
+    }
+
+    /// Delivers notifications until every sender is gone. During maintenance they are held
+    /// (the oldest dropped past `MAX_HELD`) and released in order once it ends.
+    pub async fn run(self, mut rx: mpsc::Receiver<Notification>) {
+        info!("📣 Notification router started with sinks: {}", self.sink_names().join(", "));
+        let mut maintenance = crate::maintenance::subscribe();
+        let mut held = VecDeque::new();
+        let mut dropped = 0usize;
+        let mut dedupe = Dedupe::new(self.dedupe_window);
+        let mut summaries = tokio::time::interval(SUMMARY_INTERVAL);
+
+        loop {
+            tokio::select! {
+                received = rx.recv() => {
+                    let Some(notification) = received else { break };
+                    if maintenance.borrow().is_none() {
+                        self.record(&notification);
+                        if dedupe.admit(&notification, Instant::now()) {
+                            self.dispatch(&notification).await;
+                        }
+                        continue;
+                    }
+                    if held.len() >= MAX_HELD {
+                        held.pop_front();
+                        dropped += 1;
+                    }
+                    held.push_back(notification);
+                }
+                Ok(()) = maintenance.changed() => {
+                    if maintenance.borrow_and_update().is_some() || held.is_empty() {
+                        continue;
+                    }
+                    info!("📣 Maintenance over; releasing {} held notifications", held.len());
+                    if dropped > 0 {
+                        let note = Notification::trace(format!("{} notifications raised during maintenance were dropped", dropped));
+                        self.record(&note);
+                        self.dispatch(&note).await;
+                        dropped = 0;
+                    }
+                    for notification in held.drain(..) {
+                        self.record(&notification);
+                        if dedupe.admit(&notification, Instant::now()) {
+                            self.dispatch(&notification).await;
+                        }
+                    }
+                }
+                _ = summaries.tick() => {
+                    // Summaries wait for maintenance to end like everything else.
+                    if maintenance.borrow().is_some() {
+                        continue;
+                    }
+                    // The repeats a summary counts are in history already.
+                    for summary in dedupe.due(Instant::now()) {
+                        self.dispatch(&summary).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keeps `notification` in history and passes it to gateway subscribers.
+    fn record(&self, notification: &Notification) {
+        if let Err(e) = crate::state::shared().record_notification(&notification.history_entry()) {
+            error!("Failed to record notification history: {}", e);
+        }
+        let _ = feed().send(notification.clone());
+    }
+
+    /// Delivers `notification` to the sinks its routes allow.
+    async fn dispatch(&self, notification: &Notification) {
+        let sinks: Vec<&dyn NotificationSink> = self
+            .sinks
+            .iter()
+            .map(|sink| sink.as_ref())
+            .filter(|sink| self.routes.as_ref().is_none_or(|routes| routes.allows(sink.name(), notification)))
+            .collect();
+        let results = join_all(sinks.iter().map(|sink| sink.deliver(notification))).await;
+        for (sink, result) in sinks.iter().zip(results) {
+            if let Err(e) = result {
+                error!("Failed to deliver notification via {}: {}", sink.name(), e);
+            }
+        }
+    }
+}
+
+/// Writes notifications to the log; the fallback when no chat channel is configured.
+pub struct StdoutSink;
+
+impl NotificationSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            info!("{}", PlainText.render(notification));
+            Ok(())
+        })
+    }
+}
+
+/// Posts `{kind, text, notification}` as JSON to an arbitrary URL.
+pub struct WebhookSink {
+    url: String,
+    client: Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: String, client: Client) -> Self {
+        Self { url, client }
+    }
+}
+
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let body = json!({
+                "kind": notification.kind(),
+                "text": PlainText.render(notification),
+                "notification": notification,
+            });
+            self.client.post(&self.url).json(&body).send().await?.error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+/// Slack incoming webhook, rendered as Block Kit.
+pub struct SlackSink {
+    webhook_url: String,
+    client: Client,
+}
+
+impl SlackSink {
+    pub fn new(webhook_url: String, client: Client) -> Self {
+        Self { webhook_url, client }
+    }
+}
+
+impl NotificationSink for SlackSink {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            self.client
+                .post(&self.webhook_url)
+                .json(&SlackBlocks.render(notification))
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recording(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl NotificationSink for Recording {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async move {
+                if self.0 == "broken" {
+                    anyhow::bail!("unreachable");
+                }
+                self.1.lock().unwrap().push(format!("{}: {}", self.0, PlainText.render(notification)));
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn router_fans_out_past_failing_sinks() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let router = NotificationRouter::new(vec![
+            Box::new(Recording("broken", seen.clone())),
+            Box::new(Recording("a", seen.clone())),
+            Box::new(Recording("b", seen.clone())),
+        ]);
+
+        router.dispatch(&Notification::alert("disk full")).await;
+        assert_eq!(*seen.lock().unwrap(), vec!["a: 🚨 [ALERT] disk full", "b: 🚨 [ALERT] disk full"]);
+    }
+
+    #[tokio::test]
+    async fn routes_pick_the_sinks() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let router = NotificationRouter::new(vec![Box::new(Recording("a", seen.clone())), Box::new(Recording("b", seen.clone()))])
+            .with_routes(Routes::parse("warn=a;critical=b").unwrap());
+
+        router.dispatch(&Notification::trace("tick")).await;
+        router.dispatch(&Notification::alert("disk full")).await;
+        router.dispatch(&Notification::critical("disk gone")).await;
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["a: 🚨 [ALERT] disk full", "a: 🔥 [CRITICAL] disk gone", "b: 🔥 [CRITICAL] disk gone"]
+        );
+    }
+
+    #[tokio::test]
+    async fn repeats_reach_history_and_the_feed_but_a_sink_once() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let router = NotificationRouter::new(vec![Box::new(Recording("a", seen.clone()))]);
+        let mut feed = subscribe();
+        let (tx, rx) = mpsc::channel(8);
+        let text = "Synapse query failed (router dedupe test)";
+        for _ in 0..3 {
+            tx.send(Notification::alert(text)).await.unwrap();
+        }
+        drop(tx);
+        router.run(rx).await;
+
+        assert_eq!(*seen.lock().unwrap(), vec![format!("a: 🚨 [ALERT] {text}")]);
+        let history = crate::state::shared().recent_notifications(1000, None, &[]).unwrap();
+        assert_eq!(history.iter().filter(|n| n.text.contains(text)).count(), 3);
+        let mut fed = 0;
+        while let Ok(n) = feed.try_recv() {
+            fed += usize::from(PlainText.render(&n).contains(text));
+        }
+        assert_eq!(fed, 3);
+    }
+}
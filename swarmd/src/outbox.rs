@@ -0,0 +1,336 @@
+//! Signed, versioned event webhooks for the visualizer's backend.
+//!
+//! Every write through `ingest_from` that changes a task state or an agent status becomes a
+//! [`WebhookEnvelope`] queued for each registered callback URL. Deliveries are retried with
+//! backoff until the receiver answers 2xx, one at a time and in order per subscriber, so
+//! receivers must de-duplicate on the event id. When a queue overflows, or after a restart,
+//! the subscriber gets a `resync` event instead and should reload `/api/v1/game-state`.
+//!
+//! Each request carries `X-Swarm-Webhook-Version`, `X-Swarm-Event-Id`, `X-Swarm-Timestamp` and
+//! `X-Swarm-Signature: v1=<hex>`, the HMAC-SHA256 of `{timestamp}.{body}` keyed with the
+//! subscription secret.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::server::contracts::{WebhookEnvelope, WebhookEventType, WebhookSubscription};
+use crate::tasks::{literal_value, AGENT_STATUS, INTERNAL_STATE};
+
+/// `version` of the envelope and value of `X-Swarm-Webhook-Version`.
+pub const PAYLOAD_VERSION: u32 = 1;
+/// Undelivered events kept per subscriber before they are replaced by one `resync`.
+const MAX_QUEUED: usize = 1000;
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const AGENT_PREFIX: &str = "http://swarm.os/agent/";
+
+/// A subscription as persisted, secret included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredSubscription {
+    id: String,
+    url: String,
+    secret: String,
+    created_at: String,
+}
+
+struct Subscriber {
+    subscription: StoredSubscription,
+    queue: VecDeque<WebhookEnvelope>,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Subscriber {
+    fn new(subscription: StoredSubscription) -> Self {
+        Self { subscription, queue: VecDeque::new(), failures: 0, retry_at: None }
+    }
+
+    fn enqueue(&mut self, envelope: WebhookEnvelope) {
+        if self.queue.len() >= MAX_QUEUED {
+            warn!("📮 Webhook queue of {} overflowed; replacing it with a resync", self.subscription.url);
+            self.queue.clear();
+            self.queue.push_back(envelope_now(WebhookEventType::Resync, None, None));
+            return;
+        }
+        self.queue.push_back(envelope);
+    }
+
+    fn view(&self, with_secret: bool) -> WebhookSubscription {
+        WebhookSubscription {
+            id: self.subscription.id.clone(),
+            url: self.subscription.url.clone(),
+            secret: with_secret.then(|| self.subscription.secret.clone()),
+            created_at: self.subscription.created_at.clone(),
+            pending: self.queue.len(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Outbox {
+    subscribers: Mutex<Vec<Subscriber>>,
+    /// Where subscriptions are persisted; unset until [`Outbox::load`] runs.
+    path: OnceLock<PathBuf>,
+    wake: Notify,
+}
+
+/// The outbox fed by every Synapse write of this process.
+pub fn shared() -> &'static Outbox {
+    static OUTBOX: OnceLock<Outbox> = OnceLock::new();
+    OUTBOX.get_or_init(Outbox::default)
+}
+
+impl Outbox {
+    /// Restores the subscriptions saved at `path`. Events raised while swarmd was down are
+    /// lost, so each restored subscriber starts with a `resync`.
+    pub fn load(&self, path: &Path) -> anyhow::Result<()> {
+        let _ = self.path.set(path.to_path_buf());
+        let stored: Vec<StoredSubscription> = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut subscribers = self.lock();
+        for subscription in stored {
+            let mut subscriber = Subscriber::new(subscription);
+            subscriber.enqueue(envelope_now(WebhookEventType::Resync, None, None));
+            subscribers.push(subscriber);
+        }
+        Ok(())
+    }
+
+    /// Registers `url` and returns the subscription with its freshly generated secret,
+    /// which is never shown again.
+    pub fn subscribe(&self, url: &str) -> anyhow::Result<WebhookSubscription> {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let subscriber = Subscriber::new(StoredSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            secret: hex::encode(secret),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        });
+        let view = subscriber.view(true);
+
+        let mut subscribers = self.lock();
+        subscribers.push(subscriber);
+        self.save(&subscribers)?;
+        info!("📮 Webhook subscription {} registered for {}", view.id, view.url);
+        Ok(view)
+    }
+
+    /// Removes a subscription and drops whatever was still queued for it.
+    pub fn unsubscribe(&self, id: &str) -> anyhow::Result<Option<WebhookSubscription>> {
+        let mut subscribers = self.lock();
+        let Some(index) = subscribers.iter().position(|s| s.subscription.id == id) else {
+            return Ok(None);
+        };
+        let removed = subscribers.remove(index).view(false);
+        self.save(&subscribers)?;
+        Ok(Some(removed))
+    }
+
+    pub fn subscriptions(&self) -> Vec<WebhookSubscription> {
+        self.lock().iter().map(|s| s.view(false)).collect()
+    }
+
+    /// Queues an event for every task state and agent status among `triples`.
+    pub fn publish(&self, triples: &[(&str, &str, &str)]) {
+        let envelopes = envelopes_for(triples);
+        if envelopes.is_empty() {
+            return;
+        }
+        let mut subscribers = self.lock();
+        if subscribers.is_empty() {
+            return;
+        }
+        for subscriber in subscribers.iter_mut() {
+            for envelope in &envelopes {
+                subscriber.enqueue(envelope.clone());
+            }
+        }
+        self.wake.notify_one();
+    }
+
+    /// Delivers queued events until the process exits.
+    pub async fn run(&self, client: reqwest::Client) {
+        loop {
+            let due: Vec<(String, String, String, WebhookEnvelope)> = {
+                let now = Instant::now();
+                self.lock()
+                    .iter()
+                    .filter(|s| s.retry_at.is_none_or(|at| at <= now))
+                    .filter_map(|s| {
+                        let envelope = s.queue.front()?.clone();
+                        let sub = &s.subscription;
+                        Some((sub.id.clone(), sub.url.clone(), sub.secret.clone(), envelope))
+                    })
+                    .collect()
+            };
+            if due.is_empty() {
+                let _ = tokio::time::timeout(Duration::from_secs(1), self.wake.notified()).await;
+                continue;
+            }
+
+            let results = join_all(due.iter().map(|(_, url, secret, envelope)| deliver(&client, url, secret, envelope))).await;
+
+            let mut subscribers = self.lock();
+            for ((id, url, _, envelope), result) in due.iter().zip(results) {
+                let Some(subscriber) = subscribers.iter_mut().find(|s| &s.subscription.id == id) else { continue };
+                match result {
+                    Ok(()) => {
+                        // The queue may have been replaced by a resync while this was in flight.
+                        if subscriber.queue.front().is_some_and(|e| e.id == envelope.id) {
+                            subscriber.queue.pop_front();
+                        }
+                        subscriber.failures = 0;
+                        subscriber.retry_at = None;
+                    }
+                    Err(e) => {
+                        subscriber.failures += 1;
+                        let delay = backoff(subscriber.failures);
+                        subscriber.retry_at = Some(Instant::now() + delay);
+                        warn!("📮 Webhook delivery to {} failed ({}); retrying in {}s", url, e, delay.as_secs());
+                    }
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Subscriber>> {
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, subscribers: &[Subscriber]) -> anyhow::Result<()> {
+        let Some(path) = self.path.get() else { return Ok(()) };
+        let stored: Vec<&StoredSubscription> = subscribers.iter().map(|s| &s.subscription).collect();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_private(path, &serde_json::to_vec_pretty(&stored)?)?;
+        Ok(())
+    }
+}
+
+/// The file holds the signing secrets, so it is readable by the owner only.
+#[cfg(unix)]
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?.write_all(content)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, content)
+}
+
+fn backoff(failures: u32) -> Duration {
+    Duration::from_secs(1u64 << failures.min(16)).min(MAX_BACKOFF)
+}
+
+fn envelope_now(event_type: WebhookEventType, subject: Option<&str>, value: Option<&str>) -> WebhookEnvelope {
+    WebhookEnvelope {
+        id: uuid::Uuid::new_v4().to_string(),
+        version: PAYLOAD_VERSION,
+        event_type,
+        occurred_at: chrono::Utc::now().to_rfc3339(),
+        subject: subject.map(str::to_string),
+        value: value.map(str::to_string),
+    }
+}
+
+fn envelopes_for(triples: &[(&str, &str, &str)]) -> Vec<WebhookEnvelope> {
+    triples
+        .iter()
+        .filter_map(|(s, p, o)| {
+            let event_type = match *p {
+                INTERNAL_STATE => WebhookEventType::TaskStateChanged,
+                AGENT_STATUS if s.starts_with(AGENT_PREFIX) => WebhookEventType::AgentStatusChanged,
+                _ => return None,
+            };
+            Some(envelope_now(event_type, Some(s), Some(&literal_value(o))))
+        })
+        .collect()
+}
+
+/// `v1=<hex>`: HMAC-SHA256 of `{timestamp}.{body}` keyed with the subscription secret.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn deliver(client: &reqwest::Client, url: &str, secret: &str, envelope: &WebhookEnvelope) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(envelope)?;
+    let timestamp = chrono::Utc::now().timestamp();
+    let res = client
+        .post(url)
+        .timeout(DELIVERY_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("X-Swarm-Webhook-Version", PAYLOAD_VERSION.to_string())
+        .header("X-Swarm-Event-Id", &envelope.id)
+        .header("X-Swarm-Timestamp", timestamp.to_string())
+        .header("X-Swarm-Signature", sign(secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        anyhow::bail!("receiver returned {}", res.status());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_task_states_and_agent_statuses_become_events() {
+        let envelopes = envelopes_for(&[
+            ("http://swarm.os/trello/card/1", INTERNAL_STATE, "\"PROCESSING\""),
+            ("http://swarm.os/agent/Coder_1", AGENT_STATUS, "\"Working on: Fix login\""),
+            ("http://swarm.os/repository/api", AGENT_STATUS, "\"STABLE\""),
+            ("http://swarm.os/trello/card/1", "http://swarm.os/ontology/title", "\"Fix login\""),
+        ]);
+        let events: Vec<_> = envelopes.iter().map(|e| (e.event_type, e.value.as_deref())).collect();
+        assert_eq!(events, vec![
+            (WebhookEventType::TaskStateChanged, Some("PROCESSING")),
+            (WebhookEventType::AgentStatusChanged, Some("Working on: Fix login")),
+        ]);
+        assert!(envelopes.iter().all(|e| e.version == PAYLOAD_VERSION));
+    }
+
+    #[test]
+    fn overflow_collapses_the_queue_into_a_resync_and_signatures_are_stable() {
+        let mut subscriber = Subscriber::new(StoredSubscription {
+            id: "s".into(),
+            url: "http://visualizer.local/hooks".into(),
+            secret: "k".into(),
+            created_at: String::new(),
+        });
+        for _ in 0..=MAX_QUEUED {
+            subscriber.enqueue(envelope_now(WebhookEventType::TaskStateChanged, Some("t"), Some("DONE")));
+        }
+        assert_eq!(subscriber.queue.len(), 1);
+        assert_eq!(subscriber.queue[0].event_type, WebhookEventType::Resync);
+
+        let signature = sign("k", 1700000000, b"{}");
+        assert_eq!(signature, sign("k", 1700000000, b"{}"));
+        assert_ne!(signature, sign("k", 1700000001, b"{}"));
+        assert!(signature.starts_with("v1=") && signature.len() == 3 + 64);
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(30), MAX_BACKOFF);
+    }
+}
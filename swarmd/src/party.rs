@@ -0,0 +1,156 @@
+//! Stats of the party on the game map. Each party member stands for an agent class, and its
+//! stats come from the runs and spend of that class's agents: success rate from every run the
+//! agency recorded an outcome for, hit points from the last [`RECENT_RUNS`] (failed, timed out
+//! and stalled runs cost health), and mana from what is left of the class's equal share of the
+//! daily budget.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::resource_usage::{COMPLETED_AT, OUTCOME};
+use crate::server::contracts::SpendShare;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::TaskState;
+
+/// Runs that count towards hit points.
+pub const RECENT_RUNS: usize = 10;
+const FULL: i32 = 100;
+
+/// What the game map shows for an agent class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassStats {
+    pub hp: i32,
+    pub mana: i32,
+    /// Share of finished runs that succeeded; `None` before the class's first one.
+    pub success_rate: Option<f64>,
+}
+
+/// True for runs that finished their task, false for those that failed; `None` for outcomes
+/// that say nothing about the agent, such as a cancellation.
+pub fn succeeded(outcome: &str) -> Option<bool> {
+    match outcome {
+        o if o == TaskState::Done.as_str() || o == TaskState::AwaitingMerge.as_str() => Some(true),
+        o if o == TaskState::Failed.as_str() || o == TaskState::TimedOut.as_str() || o == TaskState::Stalled.as_str() => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    agent: String,
+    class: String,
+    outcome: Option<String>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    at: Option<DateTime<Utc>>,
+}
+
+/// Agents with their class, and the outcome of each of their runs.
+pub struct Records {
+    classes: HashMap<String, String>,
+    runs: Vec<(String, bool, Option<DateTime<Utc>>)>,
+}
+
+pub async fn records(synapse: &Synapse) -> anyhow::Result<Records> {
+    let query = format!(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?agent ?class ?outcome ?at
+        WHERE {{
+            ?agent a swarm:Agent ;
+                   swarm:class ?class .
+            OPTIONAL {{
+                ?run a swarm:Run ;
+                     swarm:agent ?agent ;
+                     <{OUTCOME}> ?outcome .
+                OPTIONAL {{ ?run <{COMPLETED_AT}> ?at }}
+            }}
+        }}
+    "#
+    );
+    let rows: Vec<Row> = sparql::rows(&synapse.query_cached(&query, crate::query_cache::DEFAULT_TTL).await?)?;
+    Ok(Records::from_rows(rows))
+}
+
+impl Records {
+    fn from_rows(rows: Vec<Row>) -> Self {
+        let mut classes = HashMap::new();
+        let mut runs = Vec::new();
+        for row in rows {
+            if let Some(ok) = row.outcome.as_deref().and_then(succeeded) {
+                runs.push((row.class.clone(), ok, row.at));
+            }
+            classes.insert(row.agent, row.class);
+        }
+        Records { classes, runs }
+    }
+
+    /// Stats of every class with agents. `by_agent` is today's spend per agent, and each of the
+    /// `members` classes on the map gets an equal share of `daily_max`.
+    pub fn stats(&self, by_agent: &[SpendShare], daily_max: f64, members: usize) -> HashMap<String, ClassStats> {
+        let mut spent: HashMap<&str, f64> = HashMap::new();
+        for share in by_agent {
+            if let Some(class) = share.id.as_ref().and_then(|agent| self.classes.get(agent)) {
+                *spent.entry(class).or_default() += share.spent;
+            }
+        }
+        let left_overall = daily_max - by_agent.iter().map(|s| s.spent).sum::<f64>();
+        let share = daily_max / members.max(1) as f64;
+
+        let mut stats = HashMap::new();
+        for class in self.classes.values() {
+            if stats.contains_key(class) {
+                continue;
+            }
+            let mut runs: Vec<&(String, bool, Option<DateTime<Utc>>)> = self.runs.iter().filter(|(c, ..)| c == class).collect();
+            runs.sort_by_key(|(.., at)| std::cmp::Reverse(*at));
+            let succeeded = runs.iter().filter(|(_, ok, _)| *ok).count();
+            let recent = &runs[..runs.len().min(RECENT_RUNS)];
+            let recent_failures = recent.iter().filter(|(_, ok, _)| !*ok).count();
+
+            let hp = if recent.is_empty() { FULL } else { percent(1.0 - recent_failures as f64 / recent.len() as f64) };
+            let left = (share - spent.get(class.as_str()).copied().unwrap_or(0.0)).min(left_overall);
+            let mana = if share > 0.0 { percent(left / share) } else { 0 };
+            let success_rate = (!runs.is_empty()).then(|| succeeded as f64 / runs.len() as f64);
+            stats.insert(class.clone(), ClassStats { hp, mana, success_rate });
+        }
+        stats
+    }
+}
+
+fn percent(ratio: f64) -> i32 {
+    (ratio.clamp(0.0, 1.0) * FULL as f64).round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_come_from_run_outcomes_and_the_class_share_of_the_budget() {
+        let row = |agent: &str, class: &str, outcome: Option<&str>, hour: u32| Row {
+            agent: agent.into(),
+            class: class.into(),
+            outcome: outcome.map(str::to_string),
+            at: Some(chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 10, 1, hour, 0, 0).unwrap()),
+        };
+        let mut rows: Vec<Row> = (0..12).map(|h| row("coder-1", "Coder", Some("DONE"), h)).collect();
+        rows.push(row("coder-2", "Coder", Some("FAILED"), 20));
+        rows.push(row("coder-2", "Coder", Some("STALLED"), 21));
+        rows.push(row("coder-2", "Coder", Some("CANCELLED"), 22));
+        rows.push(row("reviewer-1", "Reviewer", None, 0));
+        let records = Records::from_rows(rows);
+
+        let spend = |agent: &str, spent: f64| SpendShare { id: Some(agent.into()), spent, events: 1, prompt_tokens: 0, completion_tokens: 0 };
+        let stats = records.stats(&[spend("coder-1", 1.0), spend("coder-2", 1.5)], 10.0, 2);
+        let coder = stats["Coder"];
+        assert_eq!((coder.hp, coder.mana), (80, 50));
+        assert!((coder.success_rate.unwrap() - 12.0 / 14.0).abs() < 1e-9);
+        assert_eq!(stats["Reviewer"], ClassStats { hp: 100, mana: 100, success_rate: None });
+
+        let broke = records.stats(&[spend("coder-1", 9.8)], 10.0, 2);
+        assert_eq!((broke["Coder"].mana, broke["Reviewer"].mana), (0, 4));
+    }
+}
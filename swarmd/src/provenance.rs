@@ -0,0 +1,246 @@
+//! Source attribution for everything swarmd writes to Synapse.
+//!
+//! Every ingested subject gets a `prov:wasAttributedTo <http://swarm.os/source/{name}>`
+//! triple, so data coming from Trello, discovery, agents or manual API calls can be
+//! traced per source. Each ingest call is also recorded as a batch of the source that made
+//! it, holding the triples it wrote, so [`purge`] can remove one source's triples and keep
+//! those another source wrote as well, at two extra triples per call.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+
+pub const PROV_ATTRIBUTED_TO: &str = "http://www.w3.org/ns/prov#wasAttributedTo";
+/// Source of an ingest batch; kept apart from `prov:wasAttributedTo`, so batches are not
+/// counted as subjects of their source.
+pub const STATED_BY: &str = "http://swarm.os/ontology/statedBy";
+/// JSON array of the `[subject, predicate, object]` an ingest batch wrote.
+pub const BATCH_TRIPLES: &str = "http://swarm.os/ontology/batchTriples";
+const SOURCE_BASE: &str = "http://swarm.os/source/";
+const BATCH_BASE: &str = "http://swarm.os/batch/";
+/// Patterns per retraction a purge sends.
+const PURGE_CHUNK: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Discovery,
+    Trello,
+    #[serde(rename = "github")]
+    GitHub,
+    Telegram,
+    Slack,
+    Discord,
+    Agency,
+    Gateway,
+    Scheduler,
+}
+
+impl Source {
+    pub const ALL: [Source; 9] = [
+        Source::Discovery,
+        Source::Trello,
+        Source::GitHub,
+        Source::Telegram,
+        Source::Slack,
+        Source::Discord,
+        Source::Agency,
+        Source::Gateway,
+        Source::Scheduler,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Discovery => "discovery",
+            Source::Trello => "trello",
+            Source::GitHub => "github",
+            Source::Telegram => "telegram",
+            Source::Slack => "slack",
+            Source::Discord => "discord",
+            Source::Agency => "agency",
+            Source::Gateway => "gateway",
+            Source::Scheduler => "scheduler",
+        }
+    }
+
+    pub fn iri(&self) -> String {
+        format!("{SOURCE_BASE}{}", self.as_str())
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        let name = raw.strip_prefix(SOURCE_BASE).unwrap_or(raw);
+        Self::ALL.into_iter().find(|s| s.as_str() == name)
+    }
+}
+
+/// Attribution triples for every distinct subject in `triples`.
+pub fn attribution_triples(source: Source, triples: &[(&str, &str, &str)]) -> Vec<(String, String, String)> {
+    let mut subjects: Vec<&str> = triples.iter().map(|(s, _, _)| *s).collect();
+    subjects.sort_unstable();
+    subjects.dedup();
+    subjects
+        .into_iter()
+        .map(|s| (s.to_string(), PROV_ATTRIBUTED_TO.to_string(), source.iri()))
+        .collect()
+}
+
+/// The triples recording that `source` wrote `triples` in one call. A batch is named after its
+/// source and triples, so writing the same triples again adds nothing.
+pub fn batch_triples(source: Source, triples: &[(&str, &str, &str)]) -> [(String, String, String); 2] {
+    let listed = serde_json::to_string(triples).unwrap_or_default();
+    let digest = Sha256::digest(format!("{}\n{}", source.as_str(), listed));
+    let batch = format!("{BATCH_BASE}{}", hex::encode(digest));
+    [
+        (batch.clone(), STATED_BY.to_string(), source.iri()),
+        (batch, BATCH_TRIPLES.to_string(), sparql::literal(&listed)),
+    ]
+}
+
+/// What a [`purge`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Purged {
+    /// Triples only `source` wrote, now gone.
+    pub triples: usize,
+    /// Triples another source wrote as well, kept.
+    pub kept: usize,
+    /// Subjects no longer attributed to `source`.
+    pub subjects: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchRow {
+    batch: String,
+    source: String,
+    triples: String,
+}
+
+/// Removes the triples `source` wrote, with its batches and the subjects' attribution to it.
+/// Triples another source also wrote stay. Data written before ingests were recorded as
+/// batches has none, and is left alone.
+pub async fn purge(synapse: &Synapse, source: Source) -> anyhow::Result<Purged> {
+    let query = Query::new(format!("SELECT ?batch ?source ?triples WHERE {{ ?batch <{STATED_BY}> ?source ; <{BATCH_TRIPLES}> ?triples }}"));
+    let rows: Vec<BatchRow> = synapse.select_as(&query).await?;
+    let source_iri = source.iri();
+
+    let mut own: BTreeSet<(String, String, String)> = BTreeSet::new();
+    let mut others: BTreeSet<(String, String, String)> = BTreeSet::new();
+    let mut batches: BTreeSet<&str> = BTreeSet::new();
+    for row in &rows {
+        let triples: Vec<(String, String, String)> = serde_json::from_str(&row.triples).unwrap_or_default();
+        if row.source == source_iri {
+            batches.insert(&row.batch);
+            own.extend(triples);
+        } else {
+            others.extend(triples);
+        }
+    }
+
+    let subjects: BTreeSet<&str> = own.iter().map(|(s, _, _)| s.as_str()).collect();
+    let mut purged = Purged { subjects: subjects.len(), ..Purged::default() };
+    let mut patterns: Vec<crate::store::Pattern<'_>> = Vec::new();
+    for (s, p, o) in &own {
+        if others.contains(&(s.clone(), p.clone(), o.clone())) {
+            purged.kept += 1;
+        } else {
+            purged.triples += 1;
+            patterns.push((s, p, Some(o)));
+        }
+    }
+    for batch in &batches {
+        patterns.push((batch, STATED_BY, None));
+        patterns.push((batch, BATCH_TRIPLES, None));
+    }
+    for subject in &subjects {
+        patterns.push((subject, PROV_ATTRIBUTED_TO, Some(source_iri.as_str())));
+    }
+    for chunk in patterns.chunks(PURGE_CHUNK) {
+        synapse.retract(chunk.to_vec()).await?;
+    }
+    Ok(purged)
+}
+
+impl Synapse {
+    /// Ingests `triples`, attributes each touched subject to `source` and records the call as
+    /// its batch. Nothing is written if any triple is malformed.
+    #[tracing::instrument(name = "synapse.ingest", skip_all, fields(source = source.as_str(), triples = triples.len()))]
+    pub async fn ingest_from(&self, source: Source, triples: Vec<(&str, &str, &str)>) -> anyhow::Result<()> {
+        for (s, p, o) in &triples {
+            crate::sparql::check_triple(s, p, o)?;
+        }
+        let attribution = attribution_triples(source, &triples);
+        let batch = batch_triples(source, &triples);
+        let mut all = triples;
+        all.extend(attribution.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())));
+        let mut written = all.clone();
+        written.extend(batch.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())));
+        self.ingest(written.clone()).await?;
+        crate::query_cache::shared().invalidate(written.iter().map(|(_, p, _)| *p));
+        crate::outbox::shared().publish(&all);
+        crate::tasks::announce_arrivals(&all);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribution_is_emitted_once_per_subject() {
+        let triples = vec![
+            ("http://swarm.os/tasks/1", "http://swarm.os/ontology/title", "\"A\""),
+            ("http://swarm.os/tasks/1", "http://swarm.os/ontology/internalState", "\"REQUIREMENTS\""),
+            ("http://swarm.os/agent/PM_1", "http://swarm.os/ontology/status", "\"Standby\""),
+        ];
+        let attribution = attribution_triples(Source::Trello, &triples);
+        assert_eq!(attribution.len(), 2);
+        assert!(attribution.iter().all(|(_, p, o)| p == PROV_ATTRIBUTED_TO && o == "http://swarm.os/source/trello"));
+    }
+
+    #[test]
+    fn sources_round_trip_through_their_iri() {
+        for source in Source::ALL {
+            assert_eq!(Source::parse(&source.iri()), Some(source));
+        }
+        assert_eq!(Source::parse("gateway"), Some(Source::Gateway));
+        assert_eq!(Source::parse("unknown"), None);
+    }
+
+    #[tokio::test]
+    async fn purging_a_source_keeps_triples_another_source_wrote() {
+        let (store, synapse) = crate::store::MemoryStore::shared();
+        let shared = ("http://swarm.os/tasks/1", "http://swarm.os/ontology/title", "\"A \\\"quoted\\\"\"");
+        let own = ("http://swarm.os/tasks/2", "http://swarm.os/ontology/title", "\"B\"");
+        synapse.ingest_from(Source::Trello, vec![shared, own]).await.unwrap();
+        synapse.ingest_from(Source::Trello, vec![shared, own]).await.unwrap();
+        synapse.ingest_from(Source::Discovery, vec![shared]).await.unwrap();
+        assert_eq!(store.triples().iter().filter(|(_, p, _)| p == BATCH_TRIPLES).count(), 3);
+
+        // The batches as the engine would return them, read from what the ingests stored.
+        let rows: Vec<serde_json::Value> = store
+            .triples()
+            .iter()
+            .filter(|(_, p, _)| p == BATCH_TRIPLES)
+            .map(|(batch, _, listed)| {
+                let (_, _, by) = store.triples().into_iter().find(|(s, p, _)| s == batch && p == STATED_BY).unwrap();
+                serde_json::json!({ "?batch": format!("<{batch}>"), "?source": format!("<{by}>"), "?triples": listed })
+            })
+            .collect();
+        store.answer(BATCH_TRIPLES, serde_json::Value::Array(rows));
+
+        let purged = purge(&synapse, Source::Trello).await.unwrap();
+        assert_eq!(purged, Purged { triples: 1, kept: 1, subjects: 2 });
+
+        let triples = store.triples();
+        let has = |(s, p, o): (&str, &str, &str)| triples.iter().any(|t| t.0 == s && t.1 == p && t.2 == o);
+        assert!(has(shared));
+        assert!(!has(own));
+        assert!(!triples.iter().any(|(_, _, o)| o == &Source::Trello.iri()));
+        assert!(has((shared.0, PROV_ATTRIBUTED_TO, &Source::Discovery.iri())));
+        assert_eq!(triples.iter().filter(|(_, p, _)| p == STATED_BY).count(), 1);
+    }
+}
@@ -0,0 +1,257 @@
+//! Result pool for frequent, identical Synapse reads (system status, task lists, today's spend,
+//! initiative progress), so polling the game state does not put each request on the engine.
+//!
+//! Entries expire after a TTL, and every write through `ingest_from` drops the entries whose
+//! query mentions one of the written predicates, so a cached answer is never older than the
+//! last write that could have changed it. Only queries with fixed predicates may go through
+//! [`Synapse::query_cached`]; a `?s ?p ?o` scan cannot be tracked this way.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::server::contracts::QueryCacheStats;
+use crate::sparql::Query;
+use crate::store::Synapse;
+
+/// How long a cached result may be served when no relevant write happens.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+const MAX_ENTRIES: usize = 256;
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+struct Entry {
+    result: String,
+    expires: Instant,
+    /// Every IRI the query mentions; a superset of the predicates it reads.
+    depends_on: HashSet<String>,
+}
+
+#[derive(Default)]
+pub struct QueryCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    /// Bumped on every write, so a read that started before it cannot store a stale result.
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+/// The cache shared by every clone of the process' Synapse client.
+pub fn shared() -> &'static QueryCache {
+    static CACHE: OnceLock<QueryCache> = OnceLock::new();
+    CACHE.get_or_init(QueryCache::default)
+}
+
+impl QueryCache {
+    fn get(&self, query: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let hit = match entries.get(query) {
+            Some(entry) if entry.expires > Instant::now() => Some(entry.result.clone()),
+            Some(_) => {
+                entries.remove(query);
+                None
+            }
+            None => None,
+        };
+        let counter = if hit.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    fn put(&self, query: &str, result: String, ttl: Duration, generation: u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if self.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        if entries.len() >= MAX_ENTRIES {
+            let now = Instant::now();
+            entries.retain(|_, e| e.expires > now);
+            if entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+        entries.insert(
+            query.to_string(),
+            Entry { result, expires: Instant::now() + ttl, depends_on: dependencies(query) },
+        );
+    }
+
+    /// Drops every entry that may read one of `predicates`.
+    pub fn invalidate<'a>(&self, predicates: impl IntoIterator<Item = &'a str>) {
+        let predicates: HashSet<&str> = predicates.into_iter().collect();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::Release);
+        let before = entries.len();
+        entries.retain(|_, e| !predicates.iter().any(|p| e.depends_on.contains(*p)));
+        self.invalidations.fetch_add((before - entries.len()) as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> QueryCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        QueryCacheStats {
+            entries: self.entries.lock().unwrap_or_else(|e| e.into_inner()).len(),
+            hits,
+            misses,
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            hit_ratio: if hits + misses == 0 { 0.0 } else { hits as f64 / (hits + misses) as f64 },
+        }
+    }
+}
+
+impl Synapse {
+    /// `query`, answered from the shared pool when an identical query ran recently.
+    #[tracing::instrument(name = "synapse.query", skip_all, fields(cached = tracing::field::Empty))]
+    pub async fn query_cached(&self, query: &str, ttl: Duration) -> anyhow::Result<String> {
+        let cache = shared();
+        let cached = cache.get(query);
+        tracing::Span::current().record("cached", cached.is_some());
+        if let Some(result) = cached {
+            return Ok(result);
+        }
+        let generation = cache.generation.load(Ordering::Acquire);
+        let result = self.query(query).await?;
+        cache.put(query, result.clone(), ttl, generation);
+        Ok(result)
+    }
+
+    /// [`Synapse::query_cached`] for a query with bound values; each binding is its own entry.
+    pub async fn select_cached(&self, query: &Query, ttl: Duration) -> anyhow::Result<String> {
+        self.query_cached(&query.build()?, ttl).await
+    }
+}
+
+/// IRIs a SPARQL query mentions, with prefixed names expanded and `a` read as `rdf:type`.
+fn dependencies(query: &str) -> HashSet<String> {
+    let tokens = tokenize(query);
+    let mut prefixes: HashMap<&str, &str> = HashMap::new();
+    for window in tokens.windows(3) {
+        if let [Token::Word(kw), Token::Word(name), Token::Iri(iri)] = window {
+            if kw.eq_ignore_ascii_case("PREFIX") {
+                prefixes.insert(name.trim_end_matches(':'), iri);
+            }
+        }
+    }
+
+    tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Iri(iri) => Some(iri.to_string()),
+            Token::Word(w) if *w == "a" => Some(RDF_TYPE.to_string()),
+            Token::Word(w) => {
+                let (prefix, local) = w.split_once(':')?;
+                prefixes.get(prefix).filter(|_| !local.is_empty()).map(|base| format!("{}{}", base, local))
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Iri(&'a str),
+    Word(&'a str),
+}
+
+/// Just enough of SPARQL's lexer to find IRIs and prefixed names: string literals are
+/// skipped and punctuation separates words.
+fn tokenize(query: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = query.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        let separator = c.is_whitespace() || "{}().;,\"'<".contains(c);
+        if separator {
+            if let Some(s) = start.take() {
+                tokens.push(Token::Word(&query[s..i]));
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+
+        match c {
+            '"' | '\'' => {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        break;
+                    }
+                }
+            }
+            // `<iri>` has no whitespace inside; anything else is a comparison operator.
+            '<' => {
+                let rest = &query[i + 1..];
+                if let Some(end) = rest.find(|ch: char| ch == '>' || ch.is_whitespace()) {
+                    if rest[end..].starts_with('>') && end > 0 {
+                        tokens.push(Token::Iri(&rest[..end]));
+                        for _ in 0..=rest[..end].chars().count() {
+                            chars.next();
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token::Word(&query[s..]));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASKS: &str = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?task ?title WHERE {
+            ?task a swarm:Task ; swarm:title ?title .
+            FILTER(?title != "swarm:ignored <not an iri>")
+        }
+    "#;
+
+    #[test]
+    fn dependencies_expand_prefixes_and_skip_literals() {
+        let deps = dependencies(TASKS);
+        assert!(deps.contains("http://swarm.os/ontology/title"));
+        assert!(deps.contains("http://swarm.os/ontology/Task"));
+        assert!(deps.contains(RDF_TYPE));
+        assert!(!deps.iter().any(|d| d.contains("ignored") || d.contains("not an iri")));
+    }
+
+    #[test]
+    fn writes_invalidate_only_dependent_entries() {
+        let cache = QueryCache::default();
+        let status = "SELECT ?s WHERE { <http://nist.gov/caisi/SystemControl> <http://nist.gov/caisi/operationalStatus> ?s }";
+        cache.put(TASKS, "tasks".into(), DEFAULT_TTL, 0);
+        cache.put(status, "status".into(), DEFAULT_TTL, 0);
+        assert_eq!(cache.get(status).as_deref(), Some("status"));
+
+        cache.invalidate(["http://swarm.os/ontology/title"]);
+        assert_eq!(cache.get(TASKS), None);
+        assert_eq!(cache.get(status).as_deref(), Some("status"));
+
+        // A read that started before the write must not repopulate the pool.
+        cache.put(TASKS, "stale".into(), DEFAULT_TTL, 0);
+        assert_eq!(cache.get(TASKS), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses, stats.invalidations), (1, 2, 2, 1));
+    }
+
+    #[test]
+    fn recording_spend_drops_the_cached_total() {
+        let query = Query::new("PREFIX swarm: <http://swarm.os/ontology/> SELECT (SUM(?amount) as ?total) WHERE { ?e swarm:date $today ; swarm:amount ?amount }")
+            .text("today", "2026-10-17")
+            .build()
+            .unwrap();
+        let cache = QueryCache::default();
+        cache.put(&query, "12.5".into(), DEFAULT_TTL, 0);
+        cache.invalidate(["http://swarm.os/ontology/amount"]);
+        assert_eq!(cache.get(&query), None);
+        assert!(!dependencies(&query).iter().any(|d| d.contains("2026")));
+    }
+}
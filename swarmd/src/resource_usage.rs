@@ -0,0 +1,217 @@
+//! CPU, memory and wall time of each orchestrator run. The agency records every attempt as a
+//! `swarm:Run` linked to its task; [`class_stats`] aggregates them per agent class to size
+//! sandboxes and plan scaling.
+//!
+//! Usage is sampled from `/proc` once a second across the run's process group, so it covers
+//! everything the orchestrator spawned. CPU time includes reaped descendants (`cutime`);
+//! peak memory is the largest summed RSS seen in a sample. Outside Linux only wall time is known.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::server::contracts::AgentClassResources;
+use crate::store::Synapse;
+use crate::tasks::literal_value;
+
+pub const RUN: &str = "http://swarm.os/ontology/Run";
+pub const RUN_OF: &str = "http://swarm.os/ontology/runOf";
+pub const RUN_AGENT: &str = "http://swarm.os/ontology/agent";
+pub const RUN_ATTEMPT: &str = "http://swarm.os/ontology/attempt";
+pub const CPU_SECONDS: &str = "http://swarm.os/ontology/cpuSeconds";
+pub const PEAK_MEMORY_BYTES: &str = "http://swarm.os/ontology/peakMemoryBytes";
+pub const DURATION_SECONDS: &str = "http://swarm.os/ontology/durationSeconds";
+/// The state the run left its task in: `DONE`, `FAILED`, `STALLED`…
+pub const OUTCOME: &str = "http://swarm.os/ontology/outcome";
+pub const COMPLETED_AT: &str = "http://swarm.os/ontology/completedAt";
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_seconds: f64,
+    pub peak_memory_bytes: u64,
+    pub wall_seconds: f64,
+}
+
+/// Samples a process group in the background until `finish` is called.
+pub struct Sampler {
+    usage: Arc<Mutex<ResourceUsage>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Sampler {
+    pub fn spawn(pgid: u32) -> Self {
+        let usage = Arc::new(Mutex::new(ResourceUsage::default()));
+        let shared = usage.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let sample = tokio::task::spawn_blocking(move || sample_group(pgid)).await.ok().flatten();
+                if let Some((cpu_seconds, memory_bytes)) = sample {
+                    let mut usage = shared.lock().unwrap_or_else(|e| e.into_inner());
+                    usage.cpu_seconds = usage.cpu_seconds.max(cpu_seconds);
+                    usage.peak_memory_bytes = usage.peak_memory_bytes.max(memory_bytes);
+                }
+                tokio::time::sleep(SAMPLE_INTERVAL).await;
+            }
+        });
+        Self { usage, task }
+    }
+
+    pub fn finish(self, wall: Duration) -> ResourceUsage {
+        self.task.abort();
+        let usage = *self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        ResourceUsage { wall_seconds: wall.as_secs_f64(), ..usage }
+    }
+}
+
+/// The fields of `/proc/<pid>/stat` the sampler needs.
+#[derive(Debug, PartialEq, Eq)]
+struct ProcStat {
+    pgrp: u32,
+    /// utime + stime + cutime + cstime, in clock ticks.
+    cpu_ticks: u64,
+    rss_pages: u64,
+}
+
+fn parse_stat(stat: &str) -> Option<ProcStat> {
+    // `comm` may contain spaces and parentheses, so fields are counted from the last `)`.
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 1..)?.split_whitespace().collect();
+    let field = |i: usize| fields.get(i).and_then(|f| f.parse::<i64>().ok()).map(|v| v.max(0) as u64);
+    Some(ProcStat {
+        pgrp: field(2)? as u32,
+        cpu_ticks: field(11)? + field(12)? + field(13)? + field(14)?,
+        rss_pages: field(21)?,
+    })
+}
+
+/// Total CPU seconds and resident bytes of every live process in the group.
+#[cfg(target_os = "linux")]
+fn sample_group(pgid: u32) -> Option<(f64, u64)> {
+    // SAFETY: sysconf only reads configuration values.
+    let (ticks_per_sec, page_size) = unsafe { (libc::sysconf(libc::_SC_CLK_TCK), libc::sysconf(libc::_SC_PAGESIZE)) };
+    if ticks_per_sec <= 0 || page_size <= 0 {
+        return None;
+    }
+
+    let mut found = false;
+    let (mut ticks, mut pages) = (0u64, 0u64);
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        if !entry.file_name().to_str().is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit())) {
+            continue;
+        }
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else { continue };
+        if let Some(proc_stat) = parse_stat(&stat).filter(|s| s.pgrp == pgid) {
+            found = true;
+            ticks += proc_stat.cpu_ticks;
+            pages += proc_stat.rss_pages;
+        }
+    }
+    found.then(|| (ticks as f64 / ticks_per_sec as f64, pages * page_size as u64))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sample_group(_pgid: u32) -> Option<(f64, u64)> {
+    None
+}
+
+/// Resource usage of every recorded run, grouped by the `swarm:class` of the agent it ran on.
+pub async fn class_stats(synapse: &Synapse) -> anyhow::Result<Vec<AgentClassResources>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?run ?class ?cpu ?memory ?wall
+        WHERE {
+            ?run a swarm:Run ;
+                 swarm:agent ?agent ;
+                 swarm:cpuSeconds ?cpu ;
+                 swarm:peakMemoryBytes ?memory ;
+                 swarm:durationSeconds ?wall .
+            OPTIONAL { ?agent swarm:class ?class }
+        }
+    "#;
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(query).await?).unwrap_or_default();
+    Ok(aggregate(&rows))
+}
+
+fn aggregate(rows: &[Value]) -> Vec<AgentClassResources> {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| literal_value(v).to_string())
+    };
+    let number = |row: &Value, name: &str| field(row, name).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+
+    let mut seen = HashSet::new();
+    let mut by_class: BTreeMap<String, Vec<ResourceUsage>> = BTreeMap::new();
+    for row in rows {
+        // A run whose agent has several classes is counted once, under the first.
+        if !field(row, "run").is_some_and(|run| seen.insert(run)) {
+            continue;
+        }
+        let class = field(row, "class").unwrap_or_else(|| "unclassified".to_string());
+        by_class.entry(class).or_default().push(ResourceUsage {
+            cpu_seconds: number(row, "cpu"),
+            peak_memory_bytes: number(row, "memory") as u64,
+            wall_seconds: number(row, "wall"),
+        });
+    }
+
+    by_class
+        .into_iter()
+        .map(|(agent_class, runs)| {
+            let n = runs.len() as f64;
+            let cpu_total: f64 = runs.iter().map(|r| r.cpu_seconds).sum();
+            let wall_total: f64 = runs.iter().map(|r| r.wall_seconds).sum();
+            let memory_total: f64 = runs.iter().map(|r| r.peak_memory_bytes as f64).sum();
+            AgentClassResources {
+                agent_class,
+                runs: runs.len(),
+                cpu_seconds_total: cpu_total,
+                cpu_seconds_avg: cpu_total / n,
+                peak_memory_bytes_avg: (memory_total / n) as u64,
+                peak_memory_bytes_max: runs.iter().map(|r| r.peak_memory_bytes).max().unwrap_or(0),
+                wall_seconds_avg: wall_total / n,
+                wall_seconds_max: runs.iter().map(|r| r.wall_seconds).fold(0.0, f64::max),
+                cpu_utilization: if wall_total > 0.0 { cpu_total / wall_total } else { 0.0 },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_proc_stat_with_awkward_command_names() {
+        let stat = "4242 (python3 (agent) x) S 1 4242 4242 0 -1 4194304 900 0 0 0 150 50 20 5 20 0 3 0 12345 98765432 2048 18446744073709551615";
+        assert_eq!(parse_stat(stat), Some(ProcStat { pgrp: 4242, cpu_ticks: 225, rss_pages: 2048 }));
+        assert_eq!(parse_stat("garbage"), None);
+    }
+
+    #[test]
+    fn aggregates_runs_per_agent_class() {
+        let row = |run: &str, class: Option<&str>, cpu: &str, memory: &str, wall: &str| {
+            let mut row = json!({ "?run": run, "?cpu": cpu, "?memory": memory, "?wall": wall });
+            if let Some(class) = class {
+                row["?class"] = json!(format!("\"{}\"", class));
+            }
+            row
+        };
+        let rows = vec![
+            row("r1", Some("Coder"), "\"10.0\"", "\"100\"", "\"20.0\""),
+            row("r2", Some("Coder"), "\"30.0\"", "\"300\"", "\"20.0\""),
+            row("r2", Some("Reviewer"), "\"30.0\"", "\"300\"", "\"20.0\""),
+            row("r3", None, "\"1.0\"", "\"50\"", "\"4.0\""),
+        ];
+
+        let stats = aggregate(&rows);
+        assert_eq!(stats.iter().map(|s| s.agent_class.as_str()).collect::<Vec<_>>(), vec!["Coder", "unclassified"]);
+        let coder = &stats[0];
+        assert_eq!((coder.runs, coder.peak_memory_bytes_avg, coder.peak_memory_bytes_max), (2, 200, 300));
+        assert_eq!((coder.cpu_seconds_avg, coder.wall_seconds_max, coder.cpu_utilization), (20.0, 20.0, 1.0));
+    }
+}
@@ -0,0 +1,108 @@
+//! Who may do what, the same on every channel: a viewer reads, an operator also handles tasks,
+//! an admin also halts the swarm and changes the budget. Gateway callers get their role from
+//! their API token (`SWARM_API_TOKENS`, see `server::auth`), Telegram users from their user id
+//! (`TELEGRAM_USER_ROLES`) or the chat they write in (`TELEGRAM_CHATS`).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// What a caller is allowed to see and do. Ordered, so `role >= Role::Operator` reads naturally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// The gamified view and read-only commands: no spend, no control state, no raw graph facts.
+    Viewer,
+    /// Tasks, agents and control state on top of the viewer's view; cancels and approvals.
+    Operator,
+    /// Everything, including spend data, budget changes and the emergency switch.
+    Admin,
+}
+
+impl Role {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// Parses `key=role` pairs separated by commas, e.g. `s3cret=admin,dash=viewer`.
+/// Entries with an empty key or an unknown role are ignored.
+pub fn pairs(raw: &str) -> impl Iterator<Item = (String, Role)> + '_ {
+    raw.split(',').filter_map(|entry| {
+        let (key, role) = entry.split_once('=')?;
+        let key = key.trim();
+        (!key.is_empty()).then_some((key.to_string(), Role::parse(role)?))
+    })
+}
+
+/// Roles of the people talking to the Telegram bot, per chat and per user.
+#[derive(Debug, Clone, Default)]
+pub struct TelegramRoles {
+    chats: HashMap<String, Role>,
+    users: HashMap<String, Role>,
+}
+
+impl TelegramRoles {
+    /// `chats` and `users` are `id=role` pairs, e.g. `-1001234=operator` and `123456=admin`.
+    /// `chat_id` is the notification chat; with neither list set it keeps the admin role it
+    /// had before roles existed.
+    pub fn new(chat_id: Option<&str>, chats: &str, users: &str) -> Self {
+        let mut roles = Self { chats: pairs(chats).collect(), users: pairs(users).collect() };
+        if let Some(chat_id) = chat_id.filter(|_| roles.is_empty()) {
+            roles.chats.insert(chat_id.trim().to_string(), Role::Admin);
+        }
+        roles
+    }
+
+    /// No chat or user is authorized, so nobody may run more than read commands.
+    pub fn is_empty(&self) -> bool {
+        self.chats.is_empty() && self.users.is_empty()
+    }
+
+    /// A listed user has their own role wherever they write from; anyone else has the role of
+    /// the chat they write in, and outside the listed chats everyone is a viewer.
+    pub fn resolve(&self, chat_id: &str, user_id: Option<i64>) -> Role {
+        user_id
+            .and_then(|id| self.users.get(&id.to_string()))
+            .or_else(|| self.chats.get(chat_id))
+            .copied()
+            .unwrap_or(Role::Viewer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn telegram_chats_and_users_resolve_to_roles() {
+        let legacy = TelegramRoles::new(Some("-100"), "", "");
+        assert_eq!(legacy.resolve("-100", Some(1)), Role::Admin);
+        assert_eq!(legacy.resolve("42", Some(1)), Role::Viewer);
+
+        let roles = TelegramRoles::new(Some("-100"), "-100=operator, -200 = viewer", "1=admin,2=viewer,3=root");
+        assert_eq!(roles.resolve("-100", Some(1)), Role::Admin);
+        assert_eq!(roles.resolve("42", Some(1)), Role::Admin);
+        assert_eq!(roles.resolve("-100", Some(2)), Role::Viewer);
+        assert_eq!(roles.resolve("-100", Some(3)), Role::Operator);
+        assert_eq!(roles.resolve("-100", None), Role::Operator);
+        assert_eq!(roles.resolve("-200", Some(4)), Role::Viewer);
+
+        let unset = TelegramRoles::new(None, "", "");
+        assert!(unset.is_empty());
+        assert_eq!(unset.resolve("42", Some(1)), Role::Viewer);
+    }
+}
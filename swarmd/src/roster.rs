@@ -0,0 +1,341 @@
+//! The agent roster: which agents the swarm has, where they live and how each one runs a task.
+//! Deployments describe it in a YAML file (`AGENT_ROSTER_PATH`):
+//!
+//! ```yaml
+//! agents:
+//!   - id: Coder_api
+//!     name: API Coder
+//!     class: Coder
+//!     repo: api
+//!     command: [python3, sdk/python/agents/orchestrator.py]
+//!     runtime: docker
+//!     skills: [rust, sql]
+//! docker:
+//!   Coder:
+//!     image: ghcr.io/acme/swarm-orchestrator:latest
+//!     mounts: ["./workspace/api:/workspace"]
+//!     pass_env: [OPENAI_API_KEY]
+//!     cpus: 2
+//!     memory: 4g
+//! http:
+//!   Reviewer:
+//!     url: https://review-bot.internal/swarm/run
+//!     token_env: REVIEW_BOT_TOKEN
+//! native:
+//!   Smoke:
+//!     agent: echo
+//! ```
+//!
+//! `repo` is the id of a discovered repository. The task title is appended to `command`,
+//! which defaults to the Python orchestrator. `runtime` picks how it runs: `host` (the
+//! default, also `process`) as a child process of swarmd, `docker` in a container, `http` by
+//! calling out to a service, `native` with an agent built into swarmd. The last three are set
+//! up by the entry of the agent's class in the section of that name. Without a roster file,
+//! discovery gives every repository a default squad on the host.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+pub const AGENT_PREFIX: &str = "http://swarm.os/agent/";
+pub const DEFAULT_COMMAND: &[&str] = &["python3", "sdk/python/agents/orchestrator.py"];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AgentSpec {
+    pub id: String,
+    pub name: String,
+    pub class: String,
+    pub repo: String,
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    #[serde(default)]
+    pub runtime: RuntimeKind,
+    /// What tasks labelled `skill: …` need; matched case-insensitively.
+    #[serde(default)]
+    pub skills: Vec<String>,
+}
+
+/// How an agent runs a task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeKind {
+    #[default]
+    #[serde(alias = "process")]
+    Host,
+    Docker,
+    Http,
+    Native,
+}
+
+impl RuntimeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RuntimeKind::Host => "host",
+            RuntimeKind::Docker => "docker",
+            RuntimeKind::Http => "http",
+            RuntimeKind::Native => "native",
+        }
+    }
+}
+
+/// The container the agents of one class run in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DockerSpec {
+    pub image: String,
+    /// Bind mounts as `host:container[:options]`; relative host paths are resolved against
+    /// swarmd's working directory.
+    #[serde(default)]
+    pub mounts: Vec<String>,
+    /// Fixed variables, visible in the `docker` command line; keep secrets in `pass_env`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Variables copied from swarmd's own environment.
+    #[serde(default)]
+    pub pass_env: Vec<String>,
+    /// CPU limit, as for `docker run --cpus`.
+    #[serde(default)]
+    pub cpus: Option<f64>,
+    /// Memory limit, as for `docker run --memory` (`512m`, `4g`).
+    #[serde(default)]
+    pub memory: Option<String>,
+    #[serde(default)]
+    pub workdir: Option<String>,
+    #[serde(default)]
+    pub network: Option<String>,
+}
+
+/// The service the agents of one class call out to. Each run is a POST of the task; the
+/// response body is read as the run's output and its status as the exit.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpSpec {
+    pub url: String,
+    /// Variable in swarmd's environment holding a bearer token for the service.
+    #[serde(default)]
+    pub token_env: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// The agent built into swarmd that the agents of one class run as.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NativeSpec {
+    pub agent: String,
+}
+
+/// How one agent runs, with the settings of its class.
+#[derive(Debug, Clone, Copy)]
+pub enum RuntimeSpec {
+    Host,
+    Docker(&'static DockerSpec),
+    Http(&'static HttpSpec),
+    Native(&'static NativeSpec),
+}
+
+impl AgentSpec {
+    pub fn subject(&self) -> String {
+        format!("{}{}", AGENT_PREFIX, self.id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RosterFile {
+    agents: Vec<AgentSpec>,
+    /// Container settings per agent class.
+    #[serde(default)]
+    docker: BTreeMap<String, DockerSpec>,
+    /// Services per agent class.
+    #[serde(default)]
+    http: BTreeMap<String, HttpSpec>,
+    /// Built-in agents per agent class.
+    #[serde(default)]
+    native: BTreeMap<String, NativeSpec>,
+}
+
+static ROSTER: OnceLock<RosterFile> = OnceLock::new();
+
+/// Loads the roster file once at startup; an invalid file is a configuration error.
+pub fn load(path: &Path) -> anyhow::Result<&'static [AgentSpec]> {
+    let raw = std::fs::read_to_string(path).with_context(|| format!("reading agent roster {}", path.display()))?;
+    let roster = parse(&raw).with_context(|| format!("invalid agent roster {}", path.display()))?;
+    Ok(&ROSTER.get_or_init(|| roster).agents)
+}
+
+/// The configured agents, or `None` when no roster file was loaded.
+pub fn agents() -> Option<&'static [AgentSpec]> {
+    ROSTER.get().map(|r| r.agents.as_slice())
+}
+
+fn spec(agent: &str) -> Option<&'static AgentSpec> {
+    let id = agent.strip_prefix(AGENT_PREFIX).unwrap_or(agent);
+    agents()?.iter().find(|a| a.id == id)
+}
+
+/// The program and arguments that run a task on `agent` (an agent IRI); the title goes last.
+pub fn command_for(agent: &str) -> Vec<String> {
+    spec(agent)
+        .and_then(|a| a.command.clone())
+        .unwrap_or_else(|| DEFAULT_COMMAND.iter().map(|s| s.to_string()).collect())
+}
+
+/// How `agent` (an agent IRI) runs a task; on the host unless the roster says otherwise.
+pub fn runtime_for(agent: &str) -> RuntimeSpec {
+    let (Some(roster), Some(agent)) = (ROSTER.get(), spec(agent)) else {
+        return RuntimeSpec::Host;
+    };
+    // Parsing made sure the class has an entry for the agent's runtime.
+    let class = agent.class.as_str();
+    match agent.runtime {
+        RuntimeKind::Host => None,
+        RuntimeKind::Docker => roster.docker.get(class).map(RuntimeSpec::Docker),
+        RuntimeKind::Http => roster.http.get(class).map(RuntimeSpec::Http),
+        RuntimeKind::Native => roster.native.get(class).map(RuntimeSpec::Native),
+    }
+    .unwrap_or(RuntimeSpec::Host)
+}
+
+fn parse(raw: &str) -> anyhow::Result<RosterFile> {
+    let file: RosterFile = serde_yaml::from_str(raw)?;
+    let mut ids = HashSet::new();
+    for agent in &file.agents {
+        // Ids become IRI path segments.
+        if agent.id.is_empty() || !agent.id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+            bail!("agent id {:?} must be non-empty and use only letters, digits, '-', '_' or '.'", agent.id);
+        }
+        if !ids.insert(agent.id.as_str()) {
+            bail!("agent {} is listed twice", agent.id);
+        }
+        for (field, value) in [("name", &agent.name), ("class", &agent.class), ("repo", &agent.repo)] {
+            if value.trim().is_empty() {
+                bail!("agent {} has an empty {}", agent.id, field);
+            }
+        }
+        if agent.skills.iter().any(|s| s.trim().is_empty()) {
+            bail!("agent {} has an empty skill", agent.id);
+        }
+        if agent.command.as_ref().is_some_and(|c| c.is_empty()) {
+            bail!("agent {} has an empty command", agent.id);
+        }
+        let configured = match agent.runtime {
+            RuntimeKind::Host => true,
+            RuntimeKind::Docker => file.docker.contains_key(&agent.class),
+            RuntimeKind::Http => file.http.contains_key(&agent.class),
+            RuntimeKind::Native => file.native.contains_key(&agent.class),
+        };
+        if !configured {
+            let kind = agent.runtime.as_str();
+            bail!("agent {} runs in {}, but there is no {} entry for class {}", agent.id, kind, kind, agent.class);
+        }
+    }
+    for (class, docker) in &file.docker {
+        if docker.image.trim().is_empty() {
+            bail!("docker entry for class {} has an empty image", class);
+        }
+        if let Some(mount) = docker.mounts.iter().find(|m| m.split(':').filter(|p| !p.is_empty()).count() < 2) {
+            bail!("docker mount {:?} of class {} must be host:container", mount, class);
+        }
+        if docker.cpus.is_some_and(|cpus| !cpus.is_finite() || cpus <= 0.0) {
+            bail!("docker cpus of class {} must be a positive number", class);
+        }
+    }
+    for (class, http) in &file.http {
+        if !http.url.starts_with("http://") && !http.url.starts_with("https://") {
+            bail!("http url of class {} must start with http:// or https://", class);
+        }
+    }
+    for (class, native) in &file.native {
+        if native.agent.trim().is_empty() {
+            bail!("native entry for class {} names no agent", class);
+        }
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_a_roster() {
+        let raw = r#"
+agents:
+  - id: Coder_api
+    name: API Coder
+    class: Coder
+    repo: api
+  - id: Sentinel
+    name: The Sentinel
+    class: Security
+    repo: swarm-security
+    command: [node, agents/sentinel.js, --strict]
+    skills: [security, rust]
+"#;
+        let agents = parse(raw).unwrap().agents;
+        assert_eq!(agents.len(), 2);
+        assert_eq!(agents[0].command, None);
+        assert!(agents[0].skills.is_empty());
+        assert_eq!(agents[1].skills, vec!["security", "rust"]);
+        assert_eq!(agents[1].subject(), "http://swarm.os/agent/Sentinel");
+        assert_eq!(agents[1].command.as_deref(), Some(&["node".to_string(), "agents/sentinel.js".into(), "--strict".into()][..]));
+
+        let duplicate = "agents:\n  - {id: A, name: a, class: Coder, repo: r}\n  - {id: A, name: b, class: Coder, repo: r}\n";
+        assert!(parse(duplicate).unwrap_err().to_string().contains("listed twice"));
+        assert!(parse("agents:\n  - {id: 'a b', name: a, class: Coder, repo: r}\n").is_err());
+        assert!(parse("agents:\n  - {id: A, name: a, class: Coder, repo: r, command: []}\n").is_err());
+        assert!(parse("agents:\n  - {id: A, name: a, class: Coder}\n").is_err());
+    }
+
+    #[test]
+    fn docker_agents_need_a_container_for_their_class() {
+        let raw = r#"
+agents:
+  - {id: Coder_api, name: API Coder, class: Coder, repo: api, runtime: docker}
+  - {id: Reviewer_api, name: API Reviewer, class: Reviewer, repo: api}
+docker:
+  Coder:
+    image: ghcr.io/acme/orchestrator:1
+    mounts: ["./workspace/api:/workspace"]
+    pass_env: [OPENAI_API_KEY]
+    cpus: 2
+    memory: 4g
+"#;
+        let roster = parse(raw).unwrap();
+        assert_eq!(roster.agents[0].runtime, RuntimeKind::Docker);
+        assert_eq!(roster.agents[1].runtime, RuntimeKind::Host);
+        assert_eq!(roster.docker["Coder"].memory.as_deref(), Some("4g"));
+
+        let orphan = "agents:\n  - {id: A, name: a, class: Coder, repo: r, runtime: docker}\n";
+        assert!(parse(orphan).unwrap_err().to_string().contains("no docker entry for class Coder"));
+        assert!(parse(&format!("{orphan}docker:\n  Coder: {{image: x, mounts: [/only-host]}}\n")).is_err());
+        assert!(parse(&format!("{orphan}docker:\n  Coder: {{image: x, cpus: 0}}\n")).is_err());
+        assert!(parse(&format!("{orphan}docker:\n  Coder: {{image: x, privileged: true}}\n")).is_err());
+    }
+
+    #[test]
+    fn http_and_native_agents_are_set_up_per_class() {
+        let raw = r#"
+agents:
+  - {id: Reviewer_api, name: Review Bot, class: Reviewer, repo: api, runtime: http}
+  - {id: Smoke_api, name: Smoke, class: Smoke, repo: api, runtime: native}
+  - {id: Coder_api, name: API Coder, class: Coder, repo: api, runtime: process}
+http:
+  Reviewer: {url: "https://review-bot.internal/run", token_env: REVIEW_BOT_TOKEN}
+native:
+  Smoke: {agent: echo}
+"#;
+        let roster = parse(raw).unwrap();
+        assert_eq!(roster.agents[0].runtime, RuntimeKind::Http);
+        assert_eq!(roster.agents[2].runtime, RuntimeKind::Host);
+        assert_eq!(roster.http["Reviewer"].token_env.as_deref(), Some("REVIEW_BOT_TOKEN"));
+        assert_eq!(roster.native["Smoke"].agent, "echo");
+
+        let orphan = "agents:\n  - {id: A, name: a, class: Bot, repo: r, runtime: http}\n";
+        assert!(parse(orphan).unwrap_err().to_string().contains("no http entry for class Bot"));
+        assert!(parse(&format!("{orphan}http:\n  Bot: {{url: 'ftp://x'}}\n")).is_err());
+    }
+}
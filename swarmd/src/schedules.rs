@@ -0,0 +1,330 @@
+//! Recurring tasks. A `swarm:ScheduledTask` pairs a cron expression (`swarm:cron`, five fields,
+//! read in the operators' timezone) with a task template: title, priority, repository and
+//! requirements, stored with the same predicates a task uses. The schedules worker queues a copy
+//! of the template in `REQUIREMENTS` whenever an occurrence comes due and records it as
+//! `swarm:lastRunAt`.
+//!
+//! Runs missed while swarmd was down are caught up on the next tick, collapsed into one task for
+//! the latest missed occurrence rather than one per missed run. Each copy's IRI is derived from
+//! the schedule and the occurrence, so writing the same occurrence twice queues it only once.
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+
+use crate::capabilities::Requirements;
+use crate::initiatives::REPOSITORY;
+use crate::provenance::Source;
+use crate::server::contracts::{ScheduledTaskRequest, ScheduledTaskStatus};
+use crate::sparql::literal;
+use crate::store::Synapse;
+use crate::tasks;
+
+pub const SCHEDULED_TASK: &str = "http://swarm.os/ontology/ScheduledTask";
+pub const CRON: &str = "http://swarm.os/ontology/cron";
+/// When the schedule was created; nothing before it is caught up.
+pub const SCHEDULED_SINCE: &str = "http://swarm.os/ontology/scheduledSince";
+/// Occurrences already queued; append-only, the latest counts.
+pub const LAST_RUN_AT: &str = "http://swarm.os/ontology/lastRunAt";
+pub const RETIRED_AT: &str = "http://swarm.os/ontology/retiredAt";
+/// On a queued copy: the schedule it came from.
+pub const SCHEDULED_FROM: &str = "http://swarm.os/ontology/scheduledFrom";
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const TASK: &str = "http://swarm.os/ontology/Task";
+const TITLE: &str = "http://swarm.os/ontology/title";
+const SCHEDULE_PREFIX: &str = "http://swarm.os/schedule/";
+/// Occurrences walked per catch-up; a minutely schedule down for weeks still ends quickly.
+const MAX_CATCH_UP: usize = 10_000;
+
+/// A standard five-field cron expression (`0 3 * * *`).
+pub fn parse_cron(expression: &str) -> Result<Cron, String> {
+    Cron::new(expression.trim()).parse().map_err(|e| format!("invalid cron expression {:?}: {}", expression, e))
+}
+
+/// Schedule IRI for a path segment: a bare id or the IRI itself.
+pub fn schedule_iri(id: &str) -> String {
+    let id = id.trim();
+    if id.contains("://") { id.to_string() } else { format!("{SCHEDULE_PREFIX}{id}") }
+}
+
+pub fn new_id() -> String {
+    format!("{SCHEDULE_PREFIX}{}", uuid::Uuid::new_v4())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub id: String,
+    pub cron: String,
+    pub title: String,
+    pub priority: Option<i64>,
+    /// Repository IRI.
+    pub repository: Option<String>,
+    pub requirements: Requirements,
+    pub since: Option<DateTime<Utc>>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub retired: bool,
+}
+
+/// The occurrence to queue now, and how many came due since the last run (more than one
+/// after downtime).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Due {
+    pub at: DateTime<Utc>,
+    pub occurrences: usize,
+}
+
+impl Schedule {
+    /// Occurrences after this point are still to run.
+    fn covered_until(&self) -> Option<DateTime<Utc>> {
+        self.last_run.max(self.since)
+    }
+
+    pub fn due(&self, cron: &Cron, tz: Tz, now: DateTime<Utc>) -> Option<Due> {
+        let mut at = self.covered_until()?.with_timezone(&tz);
+        let mut due = None;
+        for occurrences in 1..=MAX_CATCH_UP {
+            let next = cron.find_next_occurrence(&at, false).ok()?;
+            if next.with_timezone(&Utc) > now {
+                break;
+            }
+            due = Some(Due { at: next.with_timezone(&Utc), occurrences });
+            at = next;
+        }
+        due
+    }
+
+    /// The first occurrence not queued yet; in the past while one is waiting for the worker.
+    pub fn next_run(&self, cron: &Cron, tz: Tz) -> Option<DateTime<Utc>> {
+        let after = self.covered_until()?.with_timezone(&tz);
+        cron.find_next_occurrence(&after, false).ok().map(|at| at.with_timezone(&Utc))
+    }
+
+    /// The copy queued for the occurrence `at`.
+    pub fn task_id(&self, at: DateTime<Utc>) -> String {
+        let key = self.id.strip_prefix(SCHEDULE_PREFIX).unwrap_or(&self.id).replace(['/', ':', '#'], "-");
+        format!("http://swarm.os/task/scheduled/{}/{}", key, at.format("%Y%m%dT%H%MZ"))
+    }
+
+    /// The copy for `at`, in `REQUIREMENTS`, and the schedule's `lastRunAt`. A `{date}` in the
+    /// title becomes the occurrence's local date.
+    pub fn run_triples(&self, at: DateTime<Utc>, tz: Tz) -> Vec<(String, &'static str, String)> {
+        let task = self.task_id(at);
+        let title = self.title.replace("{date}", &at.with_timezone(&tz).format("%Y-%m-%d").to_string());
+        let mut triples = vec![
+            (task.clone(), RDF_TYPE, TASK.to_string()),
+            (task.clone(), tasks::INTERNAL_STATE, "\"REQUIREMENTS\"".to_string()),
+            (task.clone(), TITLE, literal(&title)),
+            (task.clone(), SCHEDULED_FROM, self.id.clone()),
+        ];
+        triples.extend(self.template_triples(&task));
+        triples.push((self.id.clone(), LAST_RUN_AT, tasks::datetime_literal(&at)));
+        triples
+    }
+
+    /// Priority, repository and requirements, on `subject`.
+    fn template_triples(&self, subject: &str) -> Vec<(String, &'static str, String)> {
+        let mut triples = Vec::new();
+        if let Some(priority) = self.priority {
+            triples.push((subject.to_string(), tasks::PRIORITY, tasks::integer_literal(priority)));
+        }
+        if let Some(repo) = &self.repository {
+            triples.push((subject.to_string(), REPOSITORY, repo.clone()));
+        }
+        for (predicate, object) in self.requirements.triples() {
+            triples.push((subject.to_string(), predicate, object));
+        }
+        triples
+    }
+
+    pub fn status(&self, tz: Tz) -> ScheduledTaskStatus {
+        let next = parse_cron(&self.cron).ok().and_then(|cron| self.next_run(&cron, tz));
+        ScheduledTaskStatus {
+            id: self.id.clone(),
+            cron: self.cron.clone(),
+            title: self.title.clone(),
+            priority: self.priority,
+            repo_id: self.repository.as_deref().map(|repo| repo.rsplit('/').next().unwrap_or(repo).to_string()),
+            requires_classes: self.requirements.classes.clone(),
+            requires_skills: self.requirements.skills.clone(),
+            last_run_at: self.last_run.map(|at| at.to_rfc3339()),
+            next_run_at: next.map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+/// Every schedule, retired ones included, ordered by title.
+pub async fn load(synapse: &Synapse) -> anyhow::Result<Vec<Schedule>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?schedule ?cron ?title ?priority ?repo ?class ?skill ?since ?lastRun ?retired
+        WHERE {
+            ?schedule a swarm:ScheduledTask ;
+                      swarm:cron ?cron ;
+                      swarm:title ?title .
+            OPTIONAL { ?schedule swarm:priority ?priority }
+            OPTIONAL { ?schedule swarm:repository ?repo }
+            OPTIONAL { ?schedule swarm:requiresClass ?class }
+            OPTIONAL { ?schedule swarm:requiresSkill ?skill }
+            OPTIONAL { ?schedule swarm:scheduledSince ?since }
+            OPTIONAL { ?schedule swarm:lastRunAt ?lastRun }
+            OPTIONAL { ?schedule swarm:retiredAt ?retired }
+        }
+    "#;
+    let res_json = synapse.query(query).await?;
+    let rows = serde_json::from_str::<Vec<serde_json::Value>>(&res_json).unwrap_or_default();
+    Ok(collect(&rows))
+}
+
+fn collect(rows: &[serde_json::Value]) -> Vec<Schedule> {
+    let field = |row: &serde_json::Value, name: &str| {
+        row.get(format!("?{name}").as_str())
+            .or_else(|| row.get(name))
+            .and_then(|v| v.as_str())
+            .map(|v| tasks::literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+    let time = |row: &serde_json::Value, name: &str| {
+        field(row, name).and_then(|raw| DateTime::parse_from_rfc3339(&raw).ok()).map(|at| at.with_timezone(&Utc))
+    };
+
+    let mut schedules: Vec<Schedule> = Vec::new();
+    for row in rows {
+        let (Some(id), Some(cron), Some(title)) = (field(row, "schedule"), field(row, "cron"), field(row, "title")) else {
+            continue;
+        };
+        let index = match schedules.iter().position(|s| s.id == id) {
+            Some(index) => index,
+            None => {
+                schedules.push(Schedule {
+                    id,
+                    cron,
+                    title,
+                    priority: None,
+                    repository: None,
+                    requirements: Requirements::default(),
+                    since: None,
+                    last_run: None,
+                    retired: false,
+                });
+                schedules.len() - 1
+            }
+        };
+        let schedule = &mut schedules[index];
+        if let Some(priority) = field(row, "priority").and_then(|p| p.parse().ok()) {
+            schedule.priority = Some(priority);
+        }
+        if let Some(repo) = field(row, "repo") {
+            schedule.repository = Some(repo);
+        }
+        if let Some(class) = field(row, "class") {
+            schedule.requirements.add_class(&class);
+        }
+        if let Some(skill) = field(row, "skill") {
+            schedule.requirements.add_skill(&skill);
+        }
+        schedule.since = schedule.since.max(time(row, "since"));
+        schedule.last_run = schedule.last_run.max(time(row, "lastRun"));
+        schedule.retired |= field(row, "retired").is_some();
+    }
+    schedules.sort_by(|a, b| a.title.cmp(&b.title));
+    schedules
+}
+
+/// Writes a new schedule; its first run is the first occurrence after `now`.
+pub async fn record(synapse: &Synapse, id: &str, request: &ScheduledTaskRequest, now: DateTime<Utc>) -> anyhow::Result<Schedule> {
+    let mut requirements = Requirements::default();
+    request.requires_classes.iter().for_each(|class| requirements.add_class(class));
+    request.requires_skills.iter().for_each(|skill| requirements.add_skill(skill));
+    let schedule = Schedule {
+        id: id.to_string(),
+        cron: request.cron.trim().to_string(),
+        title: request.title.trim().to_string(),
+        priority: request.priority,
+        repository: request.repo_id.as_deref().map(str::trim).filter(|r| !r.is_empty()).map(|repo| {
+            if repo.contains("://") { repo.to_string() } else { format!("http://swarm.os/repository/{}", repo) }
+        }),
+        requirements,
+        since: Some(now),
+        last_run: None,
+        retired: false,
+    };
+
+    let mut triples = vec![
+        (id.to_string(), RDF_TYPE, SCHEDULED_TASK.to_string()),
+        (id.to_string(), CRON, literal(&schedule.cron)),
+        (id.to_string(), TITLE, literal(&schedule.title)),
+        (id.to_string(), SCHEDULED_SINCE, tasks::datetime_literal(&now)),
+    ];
+    triples.extend(schedule.template_triples(id));
+    ingest(synapse, Source::Gateway, &triples).await?;
+    Ok(schedule)
+}
+
+/// Starts the clock of a schedule written without `swarm:scheduledSince`, e.g. by hand.
+pub async fn start(synapse: &Synapse, id: &str, now: DateTime<Utc>) -> anyhow::Result<()> {
+    ingest(synapse, Source::Scheduler, &[(id.to_string(), SCHEDULED_SINCE, tasks::datetime_literal(&now))]).await
+}
+
+/// Stops a schedule for good. Tasks it already queued are left alone.
+pub async fn retire(synapse: &Synapse, id: &str, now: DateTime<Utc>) -> anyhow::Result<()> {
+    ingest(synapse, Source::Gateway, &[(id.to_string(), RETIRED_AT, tasks::datetime_literal(&now))]).await
+}
+
+/// Queues the copy for `at` and marks it as run.
+pub async fn run(synapse: &Synapse, schedule: &Schedule, at: DateTime<Utc>, tz: Tz) -> anyhow::Result<String> {
+    ingest(synapse, Source::Scheduler, &schedule.run_triples(at, tz)).await?;
+    Ok(schedule.task_id(at))
+}
+
+async fn ingest(synapse: &Synapse, source: Source, triples: &[(String, &'static str, String)]) -> anyhow::Result<()> {
+    synapse.ingest_from(source, triples.iter().map(|(s, p, o)| (s.as_str(), *p, o.as_str())).collect()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capabilities::{REQUIRES_CLASS, REQUIRES_SKILL};
+    use serde_json::json;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw).unwrap().with_timezone(&Utc)
+    }
+
+    fn nightly() -> Schedule {
+        let rows = [
+            json!({"schedule": "<http://swarm.os/schedule/audit>", "cron": "\"0 3 * * *\"", "title": "\"Dependency audit {date}\"",
+                   "priority": tasks::integer_literal(0), "repo": "<http://swarm.os/repository/api>", "skill": "\"security\"",
+                   "since": tasks::datetime_literal(&utc("2026-10-10T12:00:00Z")), "lastRun": tasks::datetime_literal(&utc("2026-10-13T01:00:00Z"))}),
+            json!({"?schedule": "<http://swarm.os/schedule/audit>", "?cron": "\"0 3 * * *\"", "?title": "\"Dependency audit {date}\"",
+                   "?lastRun": tasks::datetime_literal(&utc("2026-10-14T01:00:00Z"))}),
+        ];
+        let mut schedules = collect(&rows);
+        assert_eq!(schedules.len(), 1);
+        schedules.remove(0)
+    }
+
+    #[test]
+    fn missed_runs_collapse_into_the_latest_occurrence() {
+        let schedule = nightly();
+        assert_eq!(schedule.last_run, Some(utc("2026-10-14T01:00:00Z")));
+        assert_eq!(schedule.requirements.skills, vec!["security"]);
+        let cron = parse_cron(&schedule.cron).unwrap();
+        let madrid: Tz = "Europe/Madrid".parse().unwrap();
+
+        // 03:00 in Madrid is 01:00 UTC in October; down from the 14th to the 17th at noon.
+        let due = schedule.due(&cron, madrid, utc("2026-10-17T12:00:00Z")).unwrap();
+        assert_eq!(due, Due { at: utc("2026-10-17T01:00:00Z"), occurrences: 3 });
+        assert_eq!(schedule.due(&cron, madrid, utc("2026-10-15T00:59:00Z")), None);
+        assert_eq!(schedule.next_run(&cron, madrid), Some(utc("2026-10-15T01:00:00Z")));
+
+        let triples = schedule.run_triples(due.at, madrid);
+        let task = "http://swarm.os/task/scheduled/audit/20261017T0100Z";
+        assert!(triples.contains(&(task.to_string(), TITLE, "\"Dependency audit 2026-10-17\"".to_string())));
+        assert!(triples.contains(&(task.to_string(), REQUIRES_SKILL, "\"security\"".to_string())));
+        assert!(triples.contains(&(schedule.id.clone(), LAST_RUN_AT, tasks::datetime_literal(&due.at))));
+        assert!(!triples.iter().any(|(_, p, _)| *p == REQUIRES_CLASS));
+
+        assert!(parse_cron("0 3 * *").is_err());
+        assert_eq!(Schedule { since: None, last_run: None, ..schedule }.due(&cron, madrid, utc("2026-10-17T12:00:00Z")), None);
+    }
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub use crate::roles::Role;
+use crate::server::problem::{ApiError, ErrorCode};
+
+/// Bearer tokens accepted by the gateway and the role each one resolves to.
+#[derive(Debug, Clone, Default)]
+pub struct ApiTokens {
+    tokens: HashMap<String, Role>,
+}
+
+impl ApiTokens {
+    /// Parses `token=role` pairs separated by commas, e.g. `s3cret=admin,dash=viewer`.
+    /// Entries with an unknown role are ignored.
+    pub fn parse(raw: &str) -> Self {
+        Self { tokens: crate::roles::pairs(raw).collect() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// With no tokens configured the gateway stays open and every caller is an admin,
+    /// which keeps local setups working unchanged. Otherwise anonymous callers are viewers.
+    pub fn resolve(&self, bearer: Option<&str>) -> Result<Role, ApiError> {
+        if self.tokens.is_empty() {
+            return Ok(Role::Admin);
+        }
+        match bearer {
+            None => Ok(Role::Viewer),
+            Some(token) => self
+                .tokens
+                .get(token)
+                .copied()
+                .ok_or_else(|| ApiError::new(ErrorCode::Unauthorized, "Unknown API token")),
+        }
+    }
+}
+
+/// Resolves the caller's role from `Authorization: Bearer <token>` and stores it in the
+/// request extensions for handlers. The role is also attached to the response so
+/// `problem_layer` can decide how much error detail to reveal.
+pub async fn auth_layer(State(tokens): State<Arc<ApiTokens>>, mut req: Request, next: Next) -> Response {
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+
+    let role = match tokens.resolve(bearer) {
+        Ok(role) => role,
+        Err(err) => return err.into_response(),
+    };
+
+    req.extensions_mut().insert(role);
+    let mut res = next.run(req).await;
+    res.extensions_mut().insert(role);
+    res
+}
+
+/// Rejects callers below `required` before the handler runs. Layered with `route_layer` on
+/// routes whose every method needs the same role, inside `auth_layer`.
+pub async fn require(State(required): State<Role>, req: Request, next: Next) -> Response {
+    let role = req.extensions().get::<Role>().copied().unwrap_or(Role::Viewer);
+    match require_role(role, required) {
+        Ok(()) => next.run(req).await,
+        Err(err) => err.into_response(),
+    }
+}
+
+pub fn require_role(role: Role, required: Role) -> Result<(), ApiError> {
+    if role < required {
+        return Err(ApiError::new(ErrorCode::Forbidden, format!("Requires the {} role", required.as_str())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_resolve_to_roles() {
+        let tokens = ApiTokens::parse("root=admin, ops = operator ,dash=viewer,bad=superuser");
+        assert_eq!(tokens.resolve(Some("root")).unwrap(), Role::Admin);
+        assert_eq!(tokens.resolve(Some("ops")).unwrap(), Role::Operator);
+        assert_eq!(tokens.resolve(None).unwrap(), Role::Viewer);
+        assert!(tokens.resolve(Some("bad")).is_err());
+        assert_eq!(ApiTokens::default().resolve(None).unwrap(), Role::Admin);
+    }
+}
@@ -0,0 +1,382 @@
+//! Public demo mode (`SWARM_DEMO_MODE`). The gateway serves a made-up swarm from a seeded
+//! simulator instead of Synapse, so the dashboard can be shown to anyone without exposing a
+//! real task, agent or spend figure. The simulated state moves on every ten seconds and is the
+//! same for every visitor; the same seed replays the same story.
+//!
+//! Only the reads the dashboard needs are served. Every other method is refused, and each
+//! client address gets `DEMO_RATE_LIMIT` requests a minute.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::server::contracts::{
+    ActiveQuest, CharacterLoadoutSelection, DailyBudget, Exploration, GameState, GraphData, GraphEdge, GraphEdgeData,
+    GraphElements, GraphNode, GraphNodeData, InitiativeRepositoryProgress, InitiativeStatus, PartyMember,
+    PartyStats, PolicyApprovalStatus, QuestStatus, RepositoryState, SystemStatus, TokenUsage,
+};
+use crate::server::problem::{ApiError, ErrorCode};
+use crate::server::routes::{build_countries, build_knowledge_tree};
+
+/// How often the simulated swarm moves on.
+const TICK: Duration = Duration::from_secs(10);
+/// Ticks a quest spends in each stage, at most; each quest draws its own pace.
+const MAX_TICKS_PER_STAGE: u64 = 6;
+const STAGES: [QuestStatus; 5] =
+    [QuestStatus::Requirements, QuestStatus::Design, QuestStatus::Ready, QuestStatus::InProgress, QuestStatus::Done];
+
+/// (name, class, what they do, where)
+const PARTY: [(&str, &str, &str, &str); 5] = [
+    ("ProductManager", "Bard", "Prioritizing backlog", "The Requirements Hall"),
+    ("Architect", "Wizard", "Modeling system boundaries", "The Tower of Design"),
+    ("Coder", "Warrior", "Implementing features", "The Shell Dungeon"),
+    ("Reviewer", "Cleric", "Reviewing pull requests", "The Gate of Judgment"),
+    ("Deployer", "Rogue", "Monitoring rollout", "The Cloud Kingdom"),
+];
+const REPOSITORIES: [(&str, &str); 3] = [("repo-api", "api"), ("repo-web", "web"), ("repo-infra", "infra")];
+/// Directories of each simulated repository.
+const DIRECTORIES: usize = 24;
+const QUESTS: [&str; 10] = [
+    "Add SSO login",
+    "Cache the product catalogue",
+    "Fix flaky checkout test",
+    "Dark mode for the dashboard",
+    "Rotate database credentials",
+    "Paginate the orders endpoint",
+    "Upgrade the web bundler",
+    "Alert on queue backlog",
+    "Translate onboarding emails",
+    "Trim cold-start latency",
+];
+
+/// The made-up swarm. Everything is derived from the seed and the tick, so it needs no
+/// storage and concurrent requests agree.
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    seed: u64,
+    started: Instant,
+}
+
+struct Quest {
+    id: String,
+    title: &'static str,
+    repo: usize,
+    status: QuestStatus,
+}
+
+impl Simulator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, started: Instant::now() }
+    }
+
+    fn tick(&self) -> u64 {
+        self.started.elapsed().as_secs() / TICK.as_secs()
+    }
+
+    fn rng(&self, salt: u64) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    /// Each quest walks the stages at its own pace and, once done for a while, is replaced by
+    /// the next round of the same work.
+    fn quests(&self, tick: u64) -> Vec<Quest> {
+        let mut world = self.rng(0);
+        QUESTS
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                let pace = world.gen_range(1..=MAX_TICKS_PER_STAGE);
+                let offset = world.gen_range(0..pace * STAGES.len() as u64);
+                let ticks = tick + offset;
+                let lap = pace * (STAGES.len() as u64 + 1);
+                let stage = ((ticks % lap) / pace).min(STAGES.len() as u64 - 1) as usize;
+                Quest {
+                    id: format!("quest-{}-{}", i, ticks / lap),
+                    title,
+                    repo: i % REPOSITORIES.len(),
+                    status: STAGES[stage].clone(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn game_state(&self) -> GameState {
+        self.game_state_at(self.tick())
+    }
+
+    fn game_state_at(&self, tick: u64) -> GameState {
+        let mut now = self.rng(tick + 1);
+        let quests = self.quests(tick);
+        let in_progress: Vec<&Quest> = quests.iter().filter(|q| q.status == QuestStatus::InProgress).collect();
+        let system_status = if now.gen_bool(0.1) { SystemStatus::Degraded } else { SystemStatus::Operational };
+
+        let party = PARTY
+            .iter()
+            .enumerate()
+            .map(|(i, (name, class_name, doing, location))| {
+                let current_action = match in_progress.get(i) {
+                    Some(quest) if *name == "Coder" || now.gen_bool(0.5) => format!("Working on: {}", quest.title),
+                    _ if now.gen_bool(0.3) => "Standby".to_string(),
+                    _ => doing.to_string(),
+                };
+                PartyMember {
+                    id: format!("char-{}", name.to_lowercase()),
+                    name: name.to_string(),
+                    class_name: class_name.to_string(),
+                    level: 3 + (self.seed.wrapping_add(i as u64) % 5) as i32,
+                    stats: PartyStats {
+                        hp: now.gen_range(60..=100),
+                        mana: now.gen_range(20..=100),
+                        success_rate: format!("{}%", now.gen_range(72..=98)),
+                    },
+                    current_action,
+                    location: location.to_string(),
+                }
+            })
+            .collect();
+
+        let repositories = REPOSITORIES
+            .iter()
+            .map(|(id, name)| RepositoryState {
+                id: id.to_string(),
+                name: name.to_string(),
+                swarm: PARTY.iter().map(|(name, ..)| format!("char-{}", name.to_lowercase())).collect(),
+            })
+            .collect();
+
+        GameState {
+            system_status: system_status.clone(),
+            selected_character_id: Some("char-coder".to_string()),
+            selected_character_loadout: CharacterLoadoutSelection::default(),
+            daily_budget: Some(DailyBudget {
+                max: 10.0,
+                // Spend builds up over a simulated day of 8640 ticks.
+                spent: ((tick % 8640) as f64 * 0.0011 * 100.0).round() / 100.0,
+                unit: "USD".to_string(),
+                by_agent: Vec::new(),
+                by_country: Vec::new(),
+                tokens: TokenUsage::default(),
+            }),
+            party,
+            active_quests: quests
+                .iter()
+                .filter(|q| q.status != QuestStatus::Done)
+                .map(|q| ActiveQuest { id: q.id.clone(), title: q.title.to_string(), status: q.status.clone() })
+                .collect(),
+            fog_map: fog_map(&quests),
+            repositories,
+            countries: build_countries(&system_status),
+            knowledge_tree: build_knowledge_tree(),
+            sovereign_controls: Some(PolicyApprovalStatus {
+                approved: true,
+                approved_by: Some("security-council".to_string()),
+                policy_id: "NIST-800-53-REV5".to_string(),
+            }),
+            initiatives: vec![initiative(&quests)],
+            maintenance: None,
+            achievements: Vec::new(),
+        }
+    }
+
+    /// Agents, repositories and the unfinished quests, linked the way Synapse links them.
+    fn graph_at(&self, tick: u64) -> GraphData {
+        let mut elements = GraphElements::default();
+        let mut node = |id: String, label: &str, node_type: &str, active: bool| {
+            elements.nodes.push(GraphNode {
+                data: GraphNodeData {
+                    id,
+                    label: label.to_string(),
+                    node_type: node_type.to_string(),
+                    active,
+                    triples: vec![],
+                    sources: vec![],
+                },
+            })
+        };
+        for (name, ..) in PARTY {
+            node(format!("http://swarm.os/agent/{name}"), name, "Agent", true);
+        }
+        for (id, name) in REPOSITORIES {
+            node(format!("http://swarm.os/repository/{id}"), name, "Repository", true);
+        }
+        let quests = self.quests(tick);
+        let open: Vec<&Quest> = quests.iter().filter(|q| q.status != QuestStatus::Done).collect();
+        for quest in &open {
+            node(format!("http://swarm.os/task/{}", quest.id), quest.title, "Task", quest.status == QuestStatus::InProgress);
+        }
+
+        let mut edge = |source: String, target: String, label: &str| {
+            elements.edges.push(GraphEdge {
+                data: GraphEdgeData { id: format!("{}-{}", source, target), source, target, label: label.to_string() },
+            })
+        };
+        for (i, quest) in open.iter().enumerate() {
+            let task = format!("http://swarm.os/task/{}", quest.id);
+            edge(task.clone(), format!("http://swarm.os/repository/{}", REPOSITORIES[quest.repo].0), "repository");
+            if quest.status == QuestStatus::InProgress {
+                edge(task, format!("http://swarm.os/agent/{}", PARTY[i % PARTY.len()].0), "assignedTo");
+            }
+        }
+        GraphData { elements }
+    }
+}
+
+/// Each repository is a little more explored for every quest done in it.
+fn fog_map(quests: &[Quest]) -> BTreeMap<String, Exploration> {
+    REPOSITORIES
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| {
+            let done = quests.iter().filter(|q| q.repo == i && q.status == QuestStatus::Done).count();
+            let explored = (DIRECTORIES / 4 + 3 * done).min(DIRECTORIES);
+            let percent = (explored as f64 * 1000.0 / DIRECTORIES as f64).round() / 10.0;
+            (format!("http://swarm.os/repository/{id}"), Exploration { percent, directories: DIRECTORIES, explored })
+        })
+        .collect()
+}
+
+/// One initiative spanning the simulated repositories, made of all the quests.
+fn initiative(quests: &[Quest]) -> InitiativeStatus {
+    let done = |q: &&Quest| q.status == QuestStatus::Done;
+    let tasks_done = quests.iter().filter(done).count();
+    let repositories = REPOSITORIES
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| {
+            let own: Vec<&Quest> = quests.iter().filter(|q| q.repo == i).collect();
+            InitiativeRepositoryProgress {
+                repository: id.to_string(),
+                tasks_total: own.len(),
+                tasks_done: own.iter().copied().filter(done).count(),
+            }
+        })
+        .collect();
+    InitiativeStatus {
+        id: "initiative-demo".to_string(),
+        title: "Ship the spring release".to_string(),
+        status: if tasks_done == quests.len() { QuestStatus::Done } else { QuestStatus::InProgress },
+        tasks_total: quests.len(),
+        tasks_done,
+        tasks_blocked: 0,
+        progress: tasks_done as f64 / quests.len().max(1) as f64,
+        repositories,
+    }
+}
+
+/// Requests per client address and minute, counted in fixed one-minute windows.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    windows: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+/// Above this many tracked addresses, finished windows are dropped.
+const MAX_TRACKED_CLIENTS: usize = 4096;
+
+impl RateLimiter {
+    /// `per_minute` of 0 lets everything through.
+    pub fn new(per_minute: u32) -> Self {
+        Self { per_minute, windows: Mutex::new(HashMap::new()) }
+    }
+
+    /// Counts a request from `client`; over the limit, how long until its window resets.
+    fn check(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        const WINDOW: Duration = Duration::from_secs(60);
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() > MAX_TRACKED_CLIENTS {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        }
+        let (start, count) = windows.entry(client).or_insert((now, 0));
+        if now.duration_since(*start) >= WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.per_minute {
+            return Err(WINDOW.saturating_sub(now.duration_since(*start)));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Refuses anything but reads, then applies the rate limit.
+pub async fn demo_guard(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return ApiError::new(ErrorCode::Forbidden, "This is a read-only demo").into_response();
+    }
+    if let Err(wait) = limiter.check(addr.ip(), Instant::now()) {
+        let secs = wait.as_secs().max(1);
+        let mut res = ApiError::new(ErrorCode::RateLimited, format!("Demo rate limit reached; try again in {}s", secs))
+            .into_response();
+        res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        return res;
+    }
+    next.run(req).await
+}
+
+pub async fn get_game_state(State(sim): State<Arc<Simulator>>) -> Json<GameState> {
+    Json(sim.game_state())
+}
+
+pub async fn get_graph_nodes(State(sim): State<Arc<Simulator>>) -> Json<GraphData> {
+    Json(sim.graph_at(sim.tick()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_replays_the_same_swarm_and_it_moves_on() {
+        let (a, b) = (Simulator::new(7), Simulator::new(7));
+        assert_eq!(a.game_state_at(12), b.game_state_at(12));
+        assert_eq!(a.graph_at(12), b.graph_at(12));
+        assert_ne!(a.game_state_at(12), Simulator::new(8).game_state_at(12));
+
+        // Over a few minutes every quest goes through each stage and comes back as new work.
+        let statuses = |tick| a.quests(tick).into_iter().map(|q| (q.id, q.status)).collect::<Vec<_>>();
+        let later = (1..=60).map(statuses).collect::<Vec<_>>();
+        for stage in STAGES {
+            assert!(later.iter().flatten().any(|(_, status)| *status == stage));
+        }
+        assert_ne!(statuses(0)[0].0, statuses(60)[0].0);
+
+        let state = a.game_state_at(30);
+        assert_eq!(state.party.len(), PARTY.len());
+        assert!(state.active_quests.iter().all(|q| q.status != QuestStatus::Done));
+        assert_eq!(state.initiatives[0].tasks_total, QUESTS.len());
+    }
+
+    #[test]
+    fn rate_limit_counts_per_client_and_minute() {
+        let limiter = RateLimiter::new(2);
+        let (alice, bob): (IpAddr, IpAddr) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+        let start = Instant::now();
+        assert!(limiter.check(alice, start).is_ok());
+        assert!(limiter.check(alice, start).is_ok());
+        let wait = limiter.check(alice, start + Duration::from_secs(20)).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(40));
+        assert!(limiter.check(bob, start).is_ok());
+        assert!(limiter.check(alice, start + Duration::from_secs(60)).is_ok());
+        assert!(RateLimiter::new(0).check(alice, start).is_ok());
+    }
+}
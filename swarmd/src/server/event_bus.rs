@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::server::contracts::{EventBusStats, GatewayEvent};
+
+pub const EVENT_BUS_CAPACITY: usize = 100;
+/// Queue depth (as a share of capacity) at which the slowest subscriber is reported as stuck.
+const STUCK_THRESHOLD: f64 = 0.8;
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What a subscriber gets out of the bus: the next event, or notice that it fell
+/// behind and must rebuild its view from Synapse.
+#[derive(Debug)]
+pub enum Delivery {
+    Event(GatewayEvent),
+    Resync { missed: u64 },
+}
+
+/// Lag accounting for every subscriber of the gateway event bus.
+#[derive(Debug, Default)]
+pub struct BusMonitor {
+    dropped_events: AtomicU64,
+    lagged_receivers: AtomicU64,
+    resyncs: AtomicU64,
+}
+
+impl BusMonitor {
+    /// Receives the next event, turning broadcast lag into an explicit `Resync`.
+    /// Returns `None` once the bus is closed.
+    pub async fn recv(&self, rx: &mut broadcast::Receiver<GatewayEvent>, consumer: &str) -> Option<Delivery> {
+        match rx.recv().await {
+            Ok(event) => Some(Delivery::Event(event)),
+            Err(RecvError::Lagged(missed)) => {
+                self.dropped_events.fetch_add(missed, Ordering::Relaxed);
+                self.lagged_receivers.fetch_add(1, Ordering::Relaxed);
+                warn!("📉 Event bus subscriber '{}' lagged and missed {} events; resyncing", consumer, missed);
+                Some(Delivery::Resync { missed })
+            }
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    pub fn record_resync(&self) {
+        self.resyncs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self, tx: &broadcast::Sender<GatewayEvent>) -> EventBusStats {
+        let queued = tx.len();
+        EventBusStats {
+            subscribers: tx.receiver_count(),
+            queued,
+            capacity: EVENT_BUS_CAPACITY,
+            stuck: is_stuck(queued),
+            dropped_events: self.dropped_events.load(Ordering::Relaxed),
+            lagged_receivers: self.lagged_receivers.load(Ordering::Relaxed),
+            resyncs: self.resyncs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Periodically warns while the slowest subscriber sits near the lag limit, so a
+    /// stalled consumer is visible before it starts dropping events.
+    pub async fn watch(self: Arc<Self>, tx: broadcast::Sender<GatewayEvent>) {
+        let mut was_stuck = false;
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            let stats = self.stats(&tx);
+            if stats.stuck && !was_stuck {
+                warn!(
+                    "🚧 Event bus backlog at {}/{} with {} subscribers; a consumer appears stuck",
+                    stats.queued, stats.capacity, stats.subscribers
+                );
+            }
+            was_stuck = stats.stuck;
+        }
+    }
+}
+
+fn is_stuck(queued: usize) -> bool {
+    queued as f64 >= EVENT_BUS_CAPACITY as f64 * STUCK_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::contracts::EventType;
+
+    fn event(n: usize) -> GatewayEvent {
+        GatewayEvent {
+            r#type: EventType::AgentThought,
+            message: format!("event {n}"),
+            details: Default::default(),
+            severity: "info".to_string(),
+            timestamp: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_is_told_to_resync() {
+        let (tx, mut rx) = broadcast::channel(4);
+        let monitor = BusMonitor::default();
+        for n in 0..6 {
+            tx.send(event(n)).unwrap();
+        }
+
+        assert!(matches!(monitor.recv(&mut rx, "test").await, Some(Delivery::Resync { missed: 2 })));
+        assert!(matches!(monitor.recv(&mut rx, "test").await, Some(Delivery::Event(_))));
+
+        let stats = monitor.stats(&tx);
+        assert_eq!(stats.dropped_events, 2);
+        assert_eq!(stats.lagged_receivers, 1);
+        assert!(!is_stuck(3) && is_stuck(EVENT_BUS_CAPACITY));
+    }
+}
@@ -0,0 +1,507 @@
+//! `POST /api/v1/graphql`: agents, repositories, tasks and spend events with their relations,
+//! for clients that want a few fields rather than the whole game state. `GET` serves GraphiQL.
+//!
+//! Each object type is read by its `rdf:type`, one predicate per field. A resolver asks Synapse
+//! only for the fields the query selects (one OPTIONAL each), and a relation is one more query
+//! per parent, so deep lists cost a query per item; [`MAX_DEPTH`] bounds how far that goes.
+
+use std::collections::HashMap;
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{ComplexObject, Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject, ID};
+use axum::{extract::State, response::Html, Extension, Json};
+
+use crate::capabilities::SKILL;
+use crate::dependencies;
+use crate::discovery;
+use crate::initiatives::{DEPENDS_ON, REPOSITORY};
+use crate::server::auth::{require_role, Role};
+use crate::server::problem::ApiResult;
+use crate::server::AppState;
+use crate::sparql::Query;
+use crate::store::Synapse;
+use crate::tasks::{self, AGENT_STATUS, ASSIGNED_TO, INTERNAL_STATE, PRIORITY, UNASSIGNED_FROM};
+
+pub const PATH: &str = "/api/v1/graphql";
+/// Deepest selection accepted, e.g. `tasks { agent { repository { agents { name } } } }` is 5.
+pub const MAX_DEPTH: usize = 6;
+
+const NAME: &str = "http://swarm.os/ontology/name";
+const CLASS: &str = "http://swarm.os/ontology/class";
+const TITLE: &str = "http://swarm.os/ontology/title";
+const HAS_POPULATION: &str = "http://swarm.os/ontology/hasPopulation";
+const SPEND_AGENT: &str = "http://swarm.os/ontology/agent";
+const SPEND_TASK: &str = "http://swarm.os/ontology/task";
+
+pub type SwarmSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn schema(synapse: Synapse) -> SwarmSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription).data(synapse).limit_depth(MAX_DEPTH).finish()
+}
+
+/// Tasks and agents are operator data; spend additionally needs an admin.
+#[utoipa::path(
+    post,
+    path = "/api/v1/graphql",
+    tag = "graphql",
+    request_body(content = serde_json::Value, description = "GraphQL request: `query`, optional `variables` and `operationName`"),
+    responses((status = 200, description = "GraphQL response: `data` and, for failed fields, `errors`", body = serde_json::Value)),
+)]
+pub async fn post_graphql(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Json(request): Json<async_graphql::Request>,
+) -> ApiResult<async_graphql::Response> {
+    require_role(role, Role::Operator)?;
+    Ok(Json(state.graphql.execute(request.data(role)).await))
+}
+
+#[utoipa::path(get, path = "/api/v1/graphql", tag = "graphql", responses((status = 200, description = "GraphiQL", content_type = "text/html")))]
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint(PATH).finish())
+}
+
+/// A GraphQL object type: its `rdf:type` and the predicate each scalar field is read from.
+struct Shape {
+    class: &'static str,
+    fields: &'static [(&'static str, &'static str)],
+}
+
+const AGENT: Shape = Shape {
+    class: "http://swarm.os/ontology/Agent",
+    fields: &[
+        ("name", NAME),
+        ("class", CLASS),
+        ("status", AGENT_STATUS),
+        ("skills", SKILL),
+        ("experience", crate::experience::EXPERIENCE),
+        ("level", crate::experience::LEVEL),
+    ],
+};
+const REPOSITORY_SHAPE: Shape = Shape {
+    class: discovery::REPOSITORY,
+    fields: &[
+        ("name", NAME),
+        ("status", AGENT_STATUS),
+        ("language", discovery::LANGUAGE),
+        ("remoteUrl", discovery::REMOTE_URL),
+        ("branch", discovery::BRANCH),
+    ],
+};
+const TASK: Shape = Shape {
+    class: "http://swarm.os/ontology/Task",
+    fields: &[
+        ("title", TITLE),
+        ("state", INTERNAL_STATE),
+        ("priority", PRIORITY),
+        ("escalatedAt", crate::escalation::ESCALATED_AT),
+        ("resetAt", crate::escalation::RESET_AT),
+    ],
+};
+/// Fields read along with `state`, which they decide; not exposed on their own.
+const STATE_INPUTS: &[&str] = &["escalatedAt", "resetAt"];
+const SPEND_EVENT: Shape = Shape {
+    class: crate::budget::SPEND_EVENT,
+    fields: &[
+        ("amount", "http://swarm.os/ontology/amount"),
+        ("currency", "http://swarm.os/ontology/currency"),
+        ("date", "http://swarm.os/ontology/date"),
+        ("at", "http://www.w3.org/ns/prov#generatedAtTime"),
+        ("promptTokens", crate::budget::PROMPT_TOKENS),
+        ("completionTokens", crate::budget::COMPLETION_TOKENS),
+        ("model", crate::budget::MODEL),
+    ],
+};
+
+/// Which subjects of a type to read: all of them, or those `pattern` links to `$subject`.
+/// The pattern names the subjects read `?id`.
+struct Scope<'a> {
+    pattern: String,
+    subject: Option<&'a str>,
+}
+
+impl<'a> Scope<'a> {
+    fn all() -> Self {
+        Scope { pattern: String::new(), subject: None }
+    }
+
+    fn one(subject: &'a str) -> Self {
+        Scope { pattern: "VALUES ?id { $subject }".to_string(), subject: Some(subject) }
+    }
+
+    /// Objects of `subject predicate ?id`.
+    fn objects(subject: &'a str, predicate: &str) -> Self {
+        Scope { pattern: format!("$subject <{predicate}> ?id ."), subject: Some(subject) }
+    }
+
+    /// Subjects of `?id predicate subject`.
+    fn referrers(subject: &'a str, predicate: &str) -> Self {
+        Scope { pattern: format!("?id <{predicate}> $subject ."), subject: Some(subject) }
+    }
+
+    /// The agent `task` is assigned to, unless it was handed off since.
+    fn assignee(task: &'a str) -> Self {
+        Scope {
+            pattern: format!("$subject <{ASSIGNED_TO}> ?id . FILTER NOT EXISTS {{ $subject <{UNASSIGNED_FROM}> ?id }}"),
+            subject: Some(task),
+        }
+    }
+
+    /// Tasks assigned to `agent` and not handed off since.
+    fn assigned(agent: &'a str) -> Self {
+        Scope {
+            pattern: format!("?id <{ASSIGNED_TO}> $subject . FILTER NOT EXISTS {{ ?id <{UNASSIGNED_FROM}> $subject }}"),
+            subject: Some(agent),
+        }
+    }
+}
+
+/// A subject and the values read for the selected fields.
+#[derive(Debug, Default, PartialEq)]
+struct Node {
+    id: String,
+    values: HashMap<&'static str, Vec<String>>,
+}
+
+impl Node {
+    fn first(&self, field: &str) -> Option<String> {
+        self.values.get(field).and_then(|v| v.first()).cloned()
+    }
+
+    fn all(&self, field: &str) -> Vec<String> {
+        self.values.get(field).cloned().unwrap_or_default()
+    }
+}
+
+/// Fields of `shape` the current selection asks for, plus `also`.
+fn selected(ctx: &Context<'_>, shape: &Shape, also: &[&str]) -> Vec<(&'static str, &'static str)> {
+    let look_ahead = ctx.look_ahead();
+    let state = look_ahead.field("state").exists();
+    shape
+        .fields
+        .iter()
+        .copied()
+        .filter(|(field, _)| also.contains(field) || look_ahead.field(field).exists() || (state && STATE_INPUTS.contains(field)))
+        .collect()
+}
+
+fn select_query(shape: &Shape, fields: &[(&str, &str)], scope: &Scope<'_>) -> anyhow::Result<String> {
+    let mut query = String::from("SELECT ?id");
+    for i in 0..fields.len() {
+        query.push_str(&format!(" ?v{i}"));
+    }
+    query.push_str(&format!(" WHERE {{\n    ?id a <{}> .\n", shape.class));
+    if !scope.pattern.is_empty() {
+        query.push_str(&format!("    {}\n", scope.pattern));
+    }
+    for (i, (_, predicate)) in fields.iter().enumerate() {
+        query.push_str(&format!("    OPTIONAL {{ ?id <{predicate}> ?v{i} }}\n"));
+    }
+    query.push('}');
+    let mut query = Query::new(query);
+    if let Some(subject) = scope.subject {
+        query = query.iri("subject", subject);
+    }
+    query.build()
+}
+
+/// Rows grouped by subject in first-seen order; the OPTIONALs repeat a subject once per
+/// combination of values.
+fn group(fields: &[(&'static str, &'static str)], rows: Vec<HashMap<String, String>>) -> Vec<Node> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let Some(id) = row.get("id") else { continue };
+        let at = *index.entry(id.clone()).or_insert_with(|| {
+            nodes.push(Node { id: id.clone(), ..Default::default() });
+            nodes.len() - 1
+        });
+        for (i, (field, _)) in fields.iter().enumerate() {
+            let Some(value) = row.get(&format!("v{i}")) else { continue };
+            let values = nodes[at].values.entry(field).or_default();
+            if !values.contains(value) {
+                values.push(value.clone());
+            }
+        }
+    }
+    nodes
+}
+
+async fn fetch(ctx: &Context<'_>, shape: &Shape, also: &[&str], scope: Scope<'_>) -> async_graphql::Result<Vec<Node>> {
+    let fields = selected(ctx, shape, also);
+    let query = select_query(shape, &fields, &scope)?;
+    let rows = ctx.data::<Synapse>()?.query_as(&query).await?;
+    Ok(group(&fields, rows))
+}
+
+fn require_admin(ctx: &Context<'_>) -> async_graphql::Result<()> {
+    match ctx.data_opt::<Role>() {
+        Some(role) if *role >= Role::Admin => Ok(()),
+        _ => Err("Spend requires the admin role".into()),
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn agents(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Agent>> {
+        Ok(fetch(ctx, &AGENT, &[], Scope::all()).await?.into_iter().map(Agent::from).collect())
+    }
+
+    /// An agent by IRI.
+    async fn agent(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Agent>> {
+        Ok(fetch(ctx, &AGENT, &[], Scope::one(&id)).await?.into_iter().next().map(Agent::from))
+    }
+
+    async fn repositories(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Repository>> {
+        Ok(fetch(ctx, &REPOSITORY_SHAPE, &[], Scope::all()).await?.into_iter().map(Repository::from).collect())
+    }
+
+    /// Every task, or only those whose current state is `state`.
+    async fn tasks(&self, ctx: &Context<'_>, state: Option<String>) -> async_graphql::Result<Vec<Task>> {
+        let also: &[&str] = if state.is_some() { &["state"] } else { &[] };
+        let tasks = fetch(ctx, &TASK, also, Scope::all()).await?.into_iter().map(Task::from);
+        Ok(match state {
+            Some(wanted) => tasks.filter(|t| t.state.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(&wanted))).collect(),
+            None => tasks.collect(),
+        })
+    }
+
+    /// A task by IRI, or by the id after `http://swarm.os/task/`.
+    async fn task(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Task>> {
+        let iri = dependencies::task_iri(&id);
+        Ok(fetch(ctx, &TASK, &[], Scope::one(&iri)).await?.into_iter().next().map(Task::from))
+    }
+
+    /// Spend events, all of them or those of one `YYYY-MM-DD` date. Admin only.
+    async fn spend_events(&self, ctx: &Context<'_>, date: Option<String>) -> async_graphql::Result<Vec<SpendEvent>> {
+        require_admin(ctx)?;
+        let also: &[&str] = if date.is_some() { &["date"] } else { &[] };
+        let events = fetch(ctx, &SPEND_EVENT, also, Scope::all()).await?.into_iter().map(SpendEvent::from);
+        Ok(match date {
+            Some(wanted) => events.filter(|e| e.date.as_deref() == Some(wanted.trim())).collect(),
+            None => events.collect(),
+        })
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Agent {
+    id: ID,
+    name: Option<String>,
+    class: Option<String>,
+    /// `Standby`, `Busy`, `Retired`…
+    status: Option<String>,
+    skills: Vec<String>,
+    /// Experience points earned by finishing tasks.
+    experience: Option<i64>,
+    /// Unset, meaning 1, until the agent finishes its first task.
+    level: Option<i64>,
+}
+
+impl From<Node> for Agent {
+    fn from(node: Node) -> Self {
+        Agent {
+            name: node.first("name"),
+            class: node.first("class"),
+            status: node.first("status"),
+            skills: node.all("skills"),
+            experience: node.first("experience").and_then(|x| x.parse().ok()),
+            level: node.first("level").and_then(|l| l.parse().ok()),
+            id: ID(node.id),
+        }
+    }
+}
+
+#[ComplexObject]
+impl Agent {
+    async fn repository(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Repository>> {
+        let found = fetch(ctx, &REPOSITORY_SHAPE, &[], Scope::referrers(&self.id, HAS_POPULATION)).await?;
+        Ok(found.into_iter().next().map(Repository::from))
+    }
+
+    /// Tasks the agent was assigned and did not hand off.
+    async fn tasks(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Task>> {
+        Ok(fetch(ctx, &TASK, &[], Scope::assigned(&self.id)).await?.into_iter().map(Task::from).collect())
+    }
+
+    /// Admin only.
+    async fn spend(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<SpendEvent>> {
+        require_admin(ctx)?;
+        let events = fetch(ctx, &SPEND_EVENT, &[], Scope::referrers(&self.id, SPEND_AGENT)).await?;
+        Ok(events.into_iter().map(SpendEvent::from).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Repository {
+    id: ID,
+    name: Option<String>,
+    status: Option<String>,
+    language: Option<String>,
+    remote_url: Option<String>,
+    branch: Option<String>,
+}
+
+impl From<Node> for Repository {
+    fn from(node: Node) -> Self {
+        Repository {
+            name: node.first("name"),
+            status: node.first("status"),
+            language: node.first("language"),
+            remote_url: node.first("remoteUrl"),
+            branch: node.first("branch"),
+            id: ID(node.id),
+        }
+    }
+}
+
+#[ComplexObject]
+impl Repository {
+    async fn agents(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Agent>> {
+        Ok(fetch(ctx, &AGENT, &[], Scope::objects(&self.id, HAS_POPULATION)).await?.into_iter().map(Agent::from).collect())
+    }
+
+    async fn tasks(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Task>> {
+        Ok(fetch(ctx, &TASK, &[], Scope::referrers(&self.id, REPOSITORY)).await?.into_iter().map(Task::from).collect())
+    }
+
+    /// Spend charged to the repository. Admin only.
+    async fn spend(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<SpendEvent>> {
+        require_admin(ctx)?;
+        let events = fetch(ctx, &SPEND_EVENT, &[], Scope::referrers(&self.id, REPOSITORY)).await?;
+        Ok(events.into_iter().map(SpendEvent::from).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Task {
+    id: ID,
+    title: Option<String>,
+    /// Current state; see `GET /api/v1/tasks`.
+    state: Option<String>,
+    /// Higher runs first; unset is normal priority.
+    priority: Option<i64>,
+}
+
+impl From<Node> for Task {
+    fn from(node: Node) -> Self {
+        Task {
+            title: node.first("title"),
+            state: tasks::current_state_after_resets(&node.all("state"), &node.all("escalatedAt"), &node.all("resetAt"))
+                .map(str::to_string),
+            priority: node.first("priority").and_then(|p| p.parse().ok()),
+            id: ID(node.id),
+        }
+    }
+}
+
+#[ComplexObject]
+impl Task {
+    async fn repository(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Repository>> {
+        let found = fetch(ctx, &REPOSITORY_SHAPE, &[], Scope::objects(&self.id, REPOSITORY)).await?;
+        Ok(found.into_iter().next().map(Repository::from))
+    }
+
+    /// The agent working on it or that last did.
+    async fn agent(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Agent>> {
+        Ok(fetch(ctx, &AGENT, &[], Scope::assignee(&self.id)).await?.into_iter().next().map(Agent::from))
+    }
+
+    /// Tasks that must be `DONE` before this one starts.
+    async fn depends_on(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Task>> {
+        Ok(fetch(ctx, &TASK, &[], Scope::objects(&self.id, DEPENDS_ON)).await?.into_iter().map(Task::from).collect())
+    }
+
+    /// Tasks waiting for this one.
+    async fn dependents(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Task>> {
+        Ok(fetch(ctx, &TASK, &[], Scope::referrers(&self.id, DEPENDS_ON)).await?.into_iter().map(Task::from).collect())
+    }
+
+    /// Admin only.
+    async fn spend(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<SpendEvent>> {
+        require_admin(ctx)?;
+        let events = fetch(ctx, &SPEND_EVENT, &[], Scope::referrers(&self.id, SPEND_TASK)).await?;
+        Ok(events.into_iter().map(SpendEvent::from).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct SpendEvent {
+    id: ID,
+    amount: Option<f64>,
+    currency: Option<String>,
+    /// `YYYY-MM-DD` in the display timezone, the day whose budget it counts against.
+    date: Option<String>,
+    /// RFC3339.
+    at: Option<String>,
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+    /// The model called, for spend of a single LLM call.
+    model: Option<String>,
+}
+
+impl From<Node> for SpendEvent {
+    fn from(node: Node) -> Self {
+        SpendEvent {
+            amount: node.first("amount").and_then(|a| a.parse().ok()),
+            currency: node.first("currency"),
+            date: node.first("date"),
+            at: node.first("at"),
+            prompt_tokens: node.first("promptTokens").and_then(|t| t.parse().ok()),
+            completion_tokens: node.first("completionTokens").and_then(|t| t.parse().ok()),
+            model: node.first("model"),
+            id: ID(node.id),
+        }
+    }
+}
+
+#[ComplexObject]
+impl SpendEvent {
+    async fn agent(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Agent>> {
+        Ok(fetch(ctx, &AGENT, &[], Scope::objects(&self.id, SPEND_AGENT)).await?.into_iter().next().map(Agent::from))
+    }
+
+    async fn task(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Task>> {
+        Ok(fetch(ctx, &TASK, &[], Scope::objects(&self.id, SPEND_TASK)).await?.into_iter().next().map(Task::from))
+    }
+
+    async fn repository(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Repository>> {
+        let found = fetch(ctx, &REPOSITORY_SHAPE, &[], Scope::objects(&self.id, REPOSITORY)).await?;
+        Ok(found.into_iter().next().map(Repository::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sparql;
+    use serde_json::json;
+
+    #[test]
+    fn only_selected_fields_are_queried_and_grouped_by_subject() {
+        let fields = [("title", TITLE), ("state", INTERNAL_STATE)];
+        let query = select_query(&TASK, &fields, &Scope::referrers("http://swarm.os/repository/api", REPOSITORY)).unwrap();
+        assert!(query.starts_with("SELECT ?id ?v0 ?v1 WHERE {"));
+        assert!(query.contains("?id <http://swarm.os/ontology/repository> <http://swarm.os/repository/api> ."));
+        assert!(query.contains(&format!("OPTIONAL {{ ?id <{INTERNAL_STATE}> ?v1 }}")));
+        assert!(!query.contains(PRIORITY));
+        assert!(select_query(&TASK, &fields, &Scope::one("not an iri> }")).is_err());
+
+        let rows = sparql::rows(&json!([
+            {"?id": "<http://swarm.os/task/1>", "?v0": "\"Fix login\"", "?v1": "\"REQUIREMENTS\""},
+            {"?id": "<http://swarm.os/task/1>", "?v0": "\"Fix login\"", "?v1": "\"PROCESSING\""},
+            {"?id": "<http://swarm.os/task/2>"},
+        ]).to_string())
+        .unwrap();
+        let tasks: Vec<Task> = group(&fields, rows).into_iter().map(Task::from).collect();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!((tasks[0].title.as_deref(), tasks[0].state.as_deref()), (Some("Fix login"), Some("PROCESSING")));
+        assert_eq!((tasks[1].title.as_deref(), tasks[1].priority), (None, None));
+    }
+}
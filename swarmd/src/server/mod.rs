@@ -1,41 +1,139 @@
 pub mod routes;
 pub mod contracts;
+pub mod problem;
+pub mod event_bus;
+pub mod auth;
+pub mod demo;
+pub mod validate;
+pub mod openapi;
+pub mod graphql;
 
-use axum::{routing::{get, post}, Router};
-use std::{net::SocketAddr, sync::Arc};
+use axum::{extract::DefaultBodyLimit, middleware, routing::{delete, get, post, put}, Router};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tokio::sync::{Mutex, broadcast};
-use tracing::info;
-use crate::synapse::SynapseClient;
+use tower_http::trace::TraceLayer;
+use tracing::{info, Span};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+use crate::artifacts::Artifacts;
+use crate::budget::BudgetLimit;
+use crate::health::Health;
+use crate::workers::{slack, telegram, ChatWebhooks};
+use crate::store::Synapse;
 use crate::server::contracts::{AuditRecord, GatewayEvent};
+use crate::telemetry;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub synapse: SynapseClient,
+    pub synapse: Synapse,
+    pub budget: BudgetLimit,
+    pub health: Arc<Health>,
     pub audit_log: Arc<Mutex<Vec<AuditRecord>>>,
     pub event_tx: broadcast::Sender<GatewayEvent>,
+    pub bus: Arc<event_bus::BusMonitor>,
+    pub webhooks: ChatWebhooks,
+    pub graphql: graphql::SwarmSchema,
+    pub artifacts: Arc<Artifacts>,
 }
 
-pub async fn start_server(port: u16, synapse: SynapseClient, event_tx: broadcast::Sender<GatewayEvent>) -> anyhow::Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub async fn start_server(
+    port: u16,
+    synapse: Synapse,
+    budget: BudgetLimit,
+    health: Arc<Health>,
+    tokens: auth::ApiTokens,
+    webhooks: ChatWebhooks,
+    event_tx: broadcast::Sender<GatewayEvent>,
+    artifacts: Artifacts,
+    snapshots: Option<Duration>,
+) -> anyhow::Result<()> {
+    let artifact_limit = DefaultBodyLimit::max(artifacts.max_bytes);
     let state = AppState {
+        graphql: graphql::schema(synapse.clone()),
         synapse,
+        budget,
+        health,
         audit_log: Arc::new(Mutex::new(Vec::new())),
-        event_tx,
+        event_tx: event_tx.clone(),
+        bus: Arc::new(event_bus::BusMonitor::default()),
+        webhooks,
+        artifacts: Arc::new(artifacts),
     };
+    tokio::spawn(state.bus.clone().watch(event_tx));
+    if let Some(every) = snapshots {
+        tokio::spawn(routes::snapshot_game_states(state.clone(), every));
+    }
+
+    // Budget changes, the emergency switch, control commands, worker control and provenance
+    // purges are for admins only.
+    let admin = Router::new()
+        .route("/api/v1/budget", put(routes::put_budget))
+        .route("/api/v1/control/halt", post(routes::post_halt))
+        .route("/api/v1/control/resume", post(routes::post_resume))
+        .route("/api/v1/control/commands", post(routes::post_control_command))
+        .route("/api/v1/admin/workers", get(routes::get_workers))
+        .route("/api/v1/admin/workers/:name/:action", post(routes::post_worker_action))
+        .route("/api/v1/provenance/:source", delete(routes::delete_provenance_source))
+        .route_layer(middleware::from_fn_with_state(auth::Role::Admin, auth::require));
 
     let app = Router::new()
         .route("/api/v1/game-state", get(routes::get_game_state))
+        .route("/api/v1/game-state/history", get(routes::get_game_state_history))
         .route("/api/v1/graph-nodes", get(routes::get_graph_nodes))
+        .route("/api/v1/spend", post(routes::post_spend))
+        .route("/api/v1/budget/report", get(routes::get_budget_report))
         .route("/api/v1/characters", get(routes::get_characters))
         .route("/api/v1/characters/select", post(routes::select_character))
         .route("/api/v1/characters/loadout", post(routes::save_character_loadout))
-        .route("/api/v1/control/commands", post(routes::post_control_command))
         .route("/api/v1/control/audit", get(routes::get_audit_log))
+        .route("/api/v1/audit", get(routes::get_audit))
         .route("/api/v1/events", post(routes::post_event))
         .route("/api/v1/mission/assign", post(routes::post_mission_assign))
+        .route("/api/v1/tasks", get(routes::get_tasks).post(routes::post_task))
+        .route("/api/v1/tasks/reset", post(routes::post_task_reset))
+        .route("/api/v1/tasks/:id/graph", get(routes::get_task_graph))
+        .route("/api/v1/tasks/:id/approval", post(routes::post_task_approval))
+        .route("/api/v1/tasks/:id/cancel", post(routes::post_task_cancel))
+        .route(
+            "/api/v1/tasks/:id/artifacts",
+            get(routes::get_task_artifacts).post(routes::post_task_artifact).layer(artifact_limit),
+        )
+        .route("/api/v1/artifacts/:id", get(routes::get_artifact))
         .route("/api/v1/knowledge-tree/nodes", post(routes::post_knowledge_tree_node))
         .route("/api/v1/knowledge-tree/:node_id/docs", get(routes::get_knowledge_node_documentation))
+        .route("/api/v1/provenance", get(routes::get_provenance_summary))
+        .route("/api/v1/provenance/:source", get(routes::get_provenance_subjects))
+        .route(graphql::PATH, get(graphql::graphiql).post(graphql::post_graphql))
         .route("/api/v1/events/combat/stream", get(routes::ws_handler))
+        .route("/api/v1/notifications", get(routes::get_notifications))
+        .route("/api/v1/notifications/stream", get(routes::ws_notifications))
+        .route("/api/v1/events/bus", get(routes::get_event_bus_stats))
+        .route("/api/v1/synapse/cache", get(routes::get_query_cache_stats))
+        .route("/api/v1/metrics/resources", get(routes::get_resource_stats))
+        .route("/api/v1/maintenance", get(routes::get_maintenance).put(routes::put_maintenance))
+        .route("/api/v1/alerts", get(routes::get_health_alerts))
+        .route("/api/v1/initiatives", get(routes::get_initiatives).post(routes::post_initiative))
+        .route("/api/v1/schedules", get(routes::get_schedules).post(routes::post_schedule))
+        .route("/api/v1/schedules/:id", delete(routes::delete_schedule))
+        .route("/api/v1/webhooks/subscriptions", get(routes::get_webhook_subscriptions).post(routes::post_webhook_subscription))
+        .route("/api/v1/webhooks/subscriptions/:id", delete(routes::delete_webhook_subscription))
+        .route(telegram::WEBHOOK_PATH, post(routes::post_telegram_webhook))
+        .route(slack::COMMANDS_PATH, post(routes::post_slack_command))
+        .merge(admin)
+        .merge(SwaggerUi::new(openapi::DOCS_PATH).url(openapi::SPEC_PATH, openapi::ApiDoc::openapi()))
         .fallback_service(tower_http::services::ServeDir::new("commander-dashboard/dist/"))
+        .layer(middleware::from_fn_with_state(Arc::new(tokens), auth::auth_layer))
+        .layer(middleware::from_fn(problem::problem_layer))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::http_span::<axum::body::Body>)
+                .on_response(|response: &axum::response::Response, _: Duration, span: &Span| {
+                    span.record("http.status_code", response.status().as_u16());
+                })
+                // Errors are already in the span through its status code.
+                .on_failure(()),
+        )
         .with_state(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
@@ -46,3 +144,24 @@ pub async fn start_server(port: u16, synapse: SynapseClient, event_tx: broadcast
 
     Ok(())
 }
+
+/// The public demo gateway: the dashboard and the simulated swarm's read endpoints, nothing
+/// that touches Synapse.
+pub async fn start_demo_server(port: u16, seed: u64, requests_per_minute: u32) -> anyhow::Result<()> {
+    let limiter = Arc::new(demo::RateLimiter::new(requests_per_minute));
+    let app = Router::new()
+        .route("/api/v1/game-state", get(demo::get_game_state))
+        .route("/api/v1/graph-nodes", get(demo::get_graph_nodes))
+        .fallback_service(tower_http::services::ServeDir::new("commander-dashboard/dist/"))
+        .layer(middleware::from_fn_with_state(limiter, demo::demo_guard))
+        .layer(middleware::from_fn(problem::problem_layer))
+        .with_state(Arc::new(demo::Simulator::new(seed)));
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!("🎭 Starting the demo gateway on {} (seed {}, {} requests/min per client)", addr, seed, requests_per_minute);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+
+    Ok(())
+}
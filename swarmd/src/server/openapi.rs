@@ -0,0 +1,140 @@
+//! OpenAPI document of the gateway, served as JSON at `/api/docs/openapi.json` with Swagger UI
+//! at `/api/docs`. Request and response schemas come from the `swarm-client` contracts; each
+//! handler describes its own method, path and bodies.
+
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::openapi::{Ref, RefOr, Response, SecurityRequirement};
+use utoipa::{Modify, OpenApi};
+
+use crate::server::contracts::{ProblemDetails, WebhookEnvelope};
+use crate::server::{graphql, routes};
+
+pub const DOCS_PATH: &str = "/api/docs";
+pub const SPEC_PATH: &str = "/api/docs/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "swarmd gateway",
+        description = "Tasks, agents, budget and control of the swarm. Callers without a token are viewers; \
+                       see `SWARM_API_TOKENS` for operator and admin tokens."
+    ),
+    paths(
+        routes::get_game_state,
+        routes::get_game_state_history,
+        routes::get_graph_nodes,
+        routes::get_characters,
+        routes::select_character,
+        routes::save_character_loadout,
+        routes::put_budget,
+        routes::post_spend,
+        routes::get_budget_report,
+        routes::post_control_command,
+        routes::get_audit_log,
+        routes::get_audit,
+        routes::post_mission_assign,
+        routes::post_halt,
+        routes::post_resume,
+        routes::post_event,
+        routes::get_event_bus_stats,
+        routes::ws_handler,
+        routes::get_tasks,
+        routes::post_task,
+        routes::post_task_reset,
+        routes::get_task_graph,
+        routes::post_task_approval,
+        routes::post_task_cancel,
+        routes::post_task_artifact,
+        routes::get_task_artifacts,
+        routes::get_artifact,
+        routes::get_workers,
+        routes::post_worker_action,
+        routes::post_knowledge_tree_node,
+        routes::get_knowledge_node_documentation,
+        routes::get_provenance_summary,
+        routes::get_provenance_subjects,
+        routes::delete_provenance_source,
+        routes::get_notifications,
+        routes::ws_notifications,
+        routes::get_query_cache_stats,
+        routes::get_resource_stats,
+        routes::get_health_alerts,
+        routes::get_maintenance,
+        routes::put_maintenance,
+        routes::get_initiatives,
+        routes::post_initiative,
+        routes::get_schedules,
+        routes::post_schedule,
+        routes::delete_schedule,
+        routes::get_webhook_subscriptions,
+        routes::post_webhook_subscription,
+        routes::delete_webhook_subscription,
+        routes::post_telegram_webhook,
+        routes::post_slack_command,
+        graphql::post_graphql,
+        graphql::graphiql,
+    ),
+    // Not returned by any route: the body of every error, and of the signed webhook deliveries.
+    components(schemas(ProblemDetails, WebhookEnvelope)),
+    modifiers(&BearerTokens, &ProblemResponses)
+)]
+pub struct ApiDoc;
+
+/// Gateway tokens are optional: without one the caller is a viewer.
+struct BearerTokens;
+
+impl Modify for BearerTokens {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+        openapi.security = Some(vec![SecurityRequirement::default(), SecurityRequirement::new("bearer", Vec::<String>::new())]);
+    }
+}
+
+/// Every error is an RFC 7807 problem, whatever the route.
+struct ProblemResponses;
+
+impl Modify for ProblemResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let problem = Response::builder()
+            .description("RFC 7807 problem details")
+            .content(
+                "application/problem+json",
+                utoipa::openapi::Content::new(Some(RefOr::Ref(Ref::from_schema_name("ProblemDetails")))),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                operation.responses.responses.entry("default".to_string()).or_insert_with(|| problem.clone().into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workers::{slack, telegram};
+
+    #[test]
+    fn every_route_is_documented_with_problem_errors() {
+        let doc = ApiDoc::openapi();
+        for path in ["/api/v1/tasks/{id}/cancel", "/api/v1/tasks/{id}/artifacts", "/api/v1/admin/workers/{name}/{action}", telegram::WEBHOOK_PATH, slack::COMMANDS_PATH] {
+            assert!(doc.paths.paths.contains_key(path), "{path} is missing from the OpenAPI document");
+        }
+        let tasks = &doc.paths.paths["/api/v1/tasks"];
+        assert!(tasks.get.is_some() && tasks.post.is_some());
+        assert!(tasks.post.as_ref().unwrap().responses.responses.contains_key("default"));
+
+        let json = doc.to_json().unwrap();
+        for schema in ["TaskRequest", "GameState", "ProblemDetails", "WebhookEnvelope"] {
+            assert!(json.contains(&format!("\"{schema}\"")), "{schema} schema is missing");
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use crate::server::auth::Role;
+use crate::server::contracts::{FieldError, ProblemDetails};
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+pub const CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// Stable machine-readable error codes. Clients switch on these, never on titles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    InvalidRequest,
+    NotFound,
+    Unauthorized,
+    Forbidden,
+    Conflict,
+    RateLimited,
+    SynapseUnavailable,
+    StorageFailure,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::SynapseUnavailable => "SYNAPSE_UNAVAILABLE",
+            ErrorCode::StorageFailure => "STORAGE_FAILURE",
+            ErrorCode::Internal => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::SynapseUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::StorageFailure => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Best-effort mapping for responses produced outside our handlers (extractor rejections, 404 fallbacks).
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+            StatusCode::BAD_GATEWAY => ErrorCode::SynapseUnavailable,
+            s if s.is_client_error() => ErrorCode::InvalidRequest,
+            _ => ErrorCode::Internal,
+        }
+    }
+
+    pub fn title(&self, locale: Locale) -> &'static str {
+        match (self, locale) {
+            (ErrorCode::InvalidRequest, Locale::En) => "The request is invalid",
+            (ErrorCode::InvalidRequest, Locale::Es) => "La solicitud no es válida",
+            (ErrorCode::NotFound, Locale::En) => "Resource not found",
+            (ErrorCode::NotFound, Locale::Es) => "Recurso no encontrado",
+            (ErrorCode::Unauthorized, Locale::En) => "Authentication required",
+            (ErrorCode::Unauthorized, Locale::Es) => "Se requiere autenticación",
+            (ErrorCode::Forbidden, Locale::En) => "Operation not permitted",
+            (ErrorCode::Forbidden, Locale::Es) => "Operación no permitida",
+            (ErrorCode::Conflict, Locale::En) => "Conflicting resource state",
+            (ErrorCode::Conflict, Locale::Es) => "Estado del recurso en conflicto",
+            (ErrorCode::RateLimited, Locale::En) => "Too many requests",
+            (ErrorCode::RateLimited, Locale::Es) => "Demasiadas solicitudes",
+            (ErrorCode::SynapseUnavailable, Locale::En) => "Knowledge graph unavailable",
+            (ErrorCode::SynapseUnavailable, Locale::Es) => "Grafo de conocimiento no disponible",
+            (ErrorCode::StorageFailure, Locale::En) => "Local storage failure",
+            (ErrorCode::StorageFailure, Locale::Es) => "Fallo del almacenamiento local",
+            (ErrorCode::Internal, Locale::En) => "Internal gateway error",
+            (ErrorCode::Internal, Locale::Es) => "Error interno del gateway",
+        }
+    }
+
+    fn type_uri(&self) -> String {
+        format!(
+            "https://swarm.os/problems/{}",
+            self.as_str().to_lowercase().replace('_', "-")
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    Es,
+}
+
+impl Locale {
+    /// Picks the first supported language from `Accept-Language`, defaulting to English.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let raw = headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+
+        for lang in raw.split(',') {
+            let tag = lang.split(';').next().unwrap_or("").trim().to_lowercase();
+            if tag.starts_with("es") {
+                return Locale::Es;
+            }
+            if tag.starts_with("en") {
+                return Locale::En;
+            }
+        }
+        Locale::En
+    }
+}
+
+/// Error returned by gateway handlers. The body is rendered by `problem_layer`,
+/// which knows the request locale and correlation id.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub detail: String,
+    /// Field-level reasons of an `INVALID_REQUEST`; empty for every other error.
+    pub errors: Vec<FieldError>,
+}
+
+pub type ApiResult<T> = Result<axum::Json<T>, ApiError>;
+
+impl ApiError {
+    pub fn new(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self { code, detail: detail.into(), errors: Vec::new() }
+    }
+
+    pub fn invalid(detail: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, detail)
+    }
+
+    /// A request rejected field by field. The detail names the single field, or counts them.
+    pub fn fields(errors: Vec<FieldError>) -> Self {
+        let detail = match errors.as_slice() {
+            [only] => format!("{}: {}", only.field, only.message),
+            many => format!(
+                "{} fields are invalid: {}",
+                many.len(),
+                many.iter().map(|e| e.field.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+        };
+        Self { errors, ..Self::invalid(detail) }
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, detail)
+    }
+
+    pub fn synapse(err: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::SynapseUnavailable, format!("Synapse request failed: {err}"))
+    }
+
+    pub fn storage(err: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::StorageFailure, err.to_string())
+    }
+
+    pub fn internal(err: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Internal, err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut res = self.code.status().into_response();
+        res.extensions_mut().insert(self);
+        res
+    }
+}
+
+pub fn problem_details(err: &ApiError, locale: Locale, instance: &str, correlation_id: &str) -> ProblemDetails {
+    ProblemDetails {
+        r#type: err.code.type_uri(),
+        title: err.code.title(locale).to_string(),
+        status: err.code.status().as_u16(),
+        detail: err.detail.clone(),
+        instance: instance.to_string(),
+        code: err.code.as_str().to_string(),
+        correlation_id: correlation_id.to_string(),
+        errors: err.errors.clone(),
+    }
+}
+
+/// Assigns a correlation id to every request and renders all error responses as
+/// `application/problem+json`, including axum extractor rejections.
+pub async fn problem_layer(req: Request, next: Next) -> Response {
+    let correlation_id = req
+        .headers()
+        .get(CORRELATION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(ToString::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let locale = Locale::from_headers(req.headers());
+    let instance = req.uri().path().to_string();
+
+    let mut res = next.run(req).await;
+
+    let error = match res.extensions_mut().remove::<ApiError>() {
+        Some(err) => Some(err),
+        None if is_unrendered_error(&res) => {
+            let status = res.status();
+            let body = axum::body::to_bytes(std::mem::replace(res.body_mut(), Body::empty()), 64 * 1024)
+                .await
+                .unwrap_or_default();
+            let detail = String::from_utf8_lossy(&body).trim().to_string();
+            let code = ErrorCode::from_status(status);
+            Some(ApiError::new(code, if detail.is_empty() { status.to_string() } else { detail }))
+        }
+        None => None,
+    };
+
+    if let Some(mut err) = error {
+        let retry_after = res.headers().get(header::RETRY_AFTER).cloned();
+        // Server-side failure details can leak internals; viewers only get the title.
+        if res.extensions().get::<Role>() == Some(&Role::Viewer) && err.code.status().is_server_error() {
+            err.detail = err.code.title(locale).to_string();
+        }
+        let problem = problem_details(&err, locale, &instance, &correlation_id);
+        let body = serde_json::to_vec(&problem).unwrap_or_default();
+        res = Response::builder()
+            .status(err.code.status())
+            .header(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)
+            .header(header::CONTENT_LANGUAGE, match locale {
+                Locale::En => "en",
+                Locale::Es => "es",
+            })
+            .body(Body::from(body))
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        if let Some(retry_after) = retry_after {
+            res.headers_mut().insert(header::RETRY_AFTER, retry_after);
+        }
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        res.headers_mut().insert(CORRELATION_HEADER, value);
+    }
+    res
+}
+
+fn is_unrendered_error(res: &Response) -> bool {
+    let status = res.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return false;
+    }
+    let content_type = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    !content_type.starts_with(PROBLEM_CONTENT_TYPE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_prefers_first_supported_language() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_LANGUAGE, HeaderValue::from_static("fr-FR, es-ES;q=0.8, en;q=0.5"));
+        assert_eq!(Locale::from_headers(&headers), Locale::Es);
+        assert_eq!(Locale::from_headers(&HeaderMap::new()), Locale::En);
+    }
+
+    #[test]
+    fn problem_details_carry_code_and_correlation_id() {
+        let err = ApiError::synapse("connection refused");
+        let problem = problem_details(&err, Locale::Es, "/api/v1/game-state", "abc-123");
+        assert_eq!(problem.status, 502);
+        assert_eq!(problem.code, "SYNAPSE_UNAVAILABLE");
+        assert_eq!(problem.r#type, "https://swarm.os/problems/synapse-unavailable");
+        assert_eq!(problem.title, "Grafo de conocimiento no disponible");
+        assert_eq!(problem.correlation_id, "abc-123");
+    }
+
+    #[test]
+    fn unknown_statuses_map_to_stable_codes() {
+        assert_eq!(ErrorCode::from_status(StatusCode::UNPROCESSABLE_ENTITY), ErrorCode::InvalidRequest);
+        assert_eq!(ErrorCode::from_status(StatusCode::SERVICE_UNAVAILABLE), ErrorCode::Internal);
+    }
+}
@@ -1,66 +1,142 @@
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Path, State},
-    response::IntoResponse,
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Extension, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::Deserialize;
 use chrono::Utc;
-use tracing::info;
+use serde::Deserialize;
+use tracing::{info, warn};
 
 use crate::server::contracts::{
-    AuditRecord, CommandPhase, ControlCommand, ControlCommandAck, CountryState,
-    DailyBudget, EventAck, GatewayEvent, GameState, GraphData, GraphEdge, GraphEdgeData,
-    GraphElements, GraphNode, GraphNodeData, GraphTriple, IngestKnowledgeNodeResponse,
+    AgentClassResources, ApprovalDecisionAck, ApprovalDecisionRequest, ArtifactKind, ArtifactView, AuditEvent, AuditQuery, AuditRecord, BudgetReport, BudgetReportQuery, BudgetUpdateRequest, CharacterLoadoutSaveRequest, CharacterSelectionRequest, CommandPhase, ControlCommand, ControlCommandAck, CountryState,
+    DailyBudget, EventAck, EventBusStats, QueryCacheStats, EventType, GatewayEvent, GameState, GameStateHistoryQuery, GameStateSnapshot, GraphData, GraphEdge,
+    GraphEdgeData, GraphElements, GraphNode, GraphNodeData, GraphTriple, HealthAlertsData,
+    HealthAlertsResponse, IngestKnowledgeNodeResponse, InitiativeRequest, InitiativeStatus,
     KnowledgeNode, KnowledgeNodeCost, KnowledgeNodeDocumentationResponse, KnowledgeNodeIngestRequest,
-    MissionAssignment, PartyMember, PartyStats, PolicyApprovalStatus, RepositoryState,
-    ServiceHealth, ServiceState, SystemStatus,
+    MaintenanceBanner, MaintenanceRequest, MaintenanceStatus, MissionAssignment, NotificationEnvelope, NotificationQuery, PartyMember, PartyStats, PolicyApprovalStatus, ProvenancePurge,
+    ProvenanceSourceSummary, ProvenanceSubjectsResponse, RepositoryState, ScheduledTaskRequest, ScheduledTaskStatus, ServiceHealth, ServiceState, SpendAck, SpendReport,
+    FieldError, SystemStatus, SystemStatusAck, TaskCancelAck, TaskCancelRequest, TaskGraph, TaskRequest, TaskResetAck, TaskResetRequest, TaskView, WebhookSubscription,
+    WebhookSubscriptionRequest, WorkerStatus,
 };
+use crate::approvals::{self, DecisionOutcome};
+use crate::artifacts;
+use crate::audit;
+use crate::dependencies;
+use crate::escalation::ResetOutcome;
+use crate::workers::agency::CancelOutcome;
+use crate::workers::supervisor::SupervisorError;
+use crate::notifications::{Notification, Severity};
+use crate::sparql;
+use crate::tasks::{self, TaskState};
+use crate::provenance::{Source, PROV_ATTRIBUTED_TO};
+use crate::server::event_bus::Delivery;
+use crate::server::auth::{require_role, Role};
+use crate::server::problem::{ApiError, ApiResult, ErrorCode};
+use crate::server::validate::{self, Valid};
 use crate::server::AppState;
 
-pub async fn get_game_state(State(state): State<AppState>) -> Json<GameState> {
-    info!("Fetching Game State from Synapse...");
+#[utoipa::path(
+    get,
+    path = "/api/v1/game-state",
+    tag = "game",
+    responses((status = 200, body = GameState)),
+)]
+pub async fn get_game_state(State(state): State<AppState>, Extension(role): Extension<Role>) -> ApiResult<GameState> {
+    let game_state = build_game_state(&state).await?;
+    Ok(Json(redact_game_state(game_state, role)))
+}
 
-    let status_query = r#"
-        PREFIX nist: <http://nist.gov/caisi/>
-        SELECT ?status WHERE { <http://nist.gov/caisi/SystemControl> nist:operationalStatus ?status }
-    "#;
-
-    let mut current_status = SystemStatus::Operational;
-    if let Ok(res_json) = state.synapse.query(status_query).await {
-        if let Ok(parsed) = serde_json::from_str::<Vec<serde_json::Value>>(&res_json) {
-            if let Some(last) = parsed.last() {
-                if let Some(s) = last.get("status").or_else(|| last.get("?status")) {
-                    current_status = parse_system_status(s.as_str().unwrap_or("UNKNOWN"));
-                }
+/// Snapshots the game state stored in a range, oldest first; each is redacted like
+/// `/api/v1/game-state`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/game-state/history",
+    tag = "game",
+    params(GameStateHistoryQuery),
+    responses((status = 200, body = Vec<GameStateSnapshot>)),
+)]
+pub async fn get_game_state_history(
+    Extension(role): Extension<Role>,
+    Query(query): Query<GameStateHistoryQuery>,
+) -> ApiResult<Vec<GameStateSnapshot>> {
+    let time = |name: &str, raw: Option<&str>| {
+        raw.map(|raw| {
+            chrono::DateTime::parse_from_rfc3339(raw)
+                .map(|at| at.with_timezone(&Utc))
+                .map_err(|_| ApiError::invalid(format!("'{}' must be an RFC3339 time, not '{}'", name, raw)))
+        })
+        .transpose()
+    };
+    let (from, to) = (time("from", query.from.as_deref())?, time("to", query.to.as_deref())?);
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let stored = crate::state::shared()
+        .game_states(from, to, limit)
+        .map_err(|e| ApiError::internal(format!("Could not read game-state history: {}", e)))?;
+    let snapshots = stored
+        .into_iter()
+        .filter_map(|s| match serde_json::from_str(&s.state) {
+            Ok(state) => Some(GameStateSnapshot { at: s.at, state: redact_game_state(state, role) }),
+            Err(e) => {
+                warn!("Skipping the game-state snapshot of {}: {}", s.at, e);
+                None
             }
-        }
-    }
-
-    let today = Utc::now().format("%Y-%m-%d").to_string();
-    let spend_query = format!(
-        r#"
-        PREFIX swarm: <http://swarm.os/ontology/>
-        SELECT (SUM(?amount) as ?total)
-        WHERE {{
-            ?event a swarm:SpendEvent .
-            ?event swarm:date "{}" .
-            ?event swarm:amount ?amount .
-        }}
-    "#,
-        today
-    );
+        })
+        .collect();
+    Ok(Json(snapshots))
+}
 
-    let mut spend = 0.0;
-    if let Ok(res_json) = state.synapse.query(&spend_query).await {
-        if let Ok(parsed) = serde_json::from_str::<Vec<serde_json::Value>>(&res_json) {
-            if let Some(first) = parsed.first() {
-                if let Some(t) = first.get("total").or_else(|| first.get("?total")) {
-                    let cleaned = _clean_numeric(t.as_str().unwrap_or("0"));
-                    spend = cleaned.parse().unwrap_or(0.0);
-                }
+/// Keeps the game state every `every` for `/api/v1/game-state/history`; one the same as the
+/// last is not kept again.
+pub async fn snapshot_game_states(state: AppState, every: std::time::Duration) {
+    info!("🎞️ Keeping a game-state snapshot every {}s", every.as_secs());
+    let mut ticks = tokio::time::interval(every);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticks.tick().await;
+        let snapshot = match build_game_state(&state).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                warn!("Could not build a game-state snapshot: {}", e.detail);
+                continue;
             }
+        };
+        let kept = serde_json::to_string(&snapshot)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(crate::state::shared().record_game_state(Utc::now(), &json)?));
+        if let Err(e) = kept {
+            warn!("Could not keep a game-state snapshot: {}", e);
         }
     }
+}
+
+/// Spend is admin-only; control state is hidden from viewers.
+fn redact_game_state(mut game_state: GameState, role: Role) -> GameState {
+    if role < Role::Admin {
+        game_state.daily_budget = None;
+    }
+    if role < Role::Operator {
+        game_state.sovereign_controls = None;
+    }
+    game_state
+}
+
+async fn build_game_state(state: &AppState) -> Result<GameState, ApiError> {
+    info!("Fetching Game State from Synapse...");
+
+    // The reads are independent, so the response waits for the slowest rather than their sum.
+    let (current_status, spend, tokens, (by_agent, by_country), initiatives, fog_map, levels, records, achievements) = tokio::try_join!(
+        system_status(&state.synapse),
+        crate::budget::spent_today(&state.synapse),
+        crate::budget::tokens_today(&state.synapse, &state.budget),
+        crate::budget::shares_today(&state.synapse),
+        crate::initiatives::statuses(&state.synapse),
+        crate::exploration::fog_map(&state.synapse),
+        crate::experience::levels_by_class(&state.synapse),
+        crate::party::records(&state.synapse),
+        crate::achievements::all(&state.synapse),
+    )
+    .map_err(ApiError::synapse)?;
 
     // Load Character Profiles
     let char_path = std::path::Path::new("sdk/python/data/character_profiles.json");
@@ -71,28 +147,26 @@ pub async fn get_game_state(State(state): State<AppState>) -> Json<GameState> {
         profiles: vec![],
     });
 
-    let party: Vec<PartyMember> = char_doc.profiles.iter().map(|p| PartyMember {
-        id: p.id.clone(),
-        name: p.display_name.clone(),
-        class_name: p.class_name.clone(),
-        level: p.level,
-        stats: PartyStats {
-            hp: p.loadout.hit_points,
-            mana: p.loadout.mana,
-            success_rate: format!("{:.0}%", p.base_success_rate * 100.0),
-        },
-        current_action: p.current_action.clone(),
-        location: p.location.clone(),
+    let class_stats = records.stats(&by_agent, state.budget.get(), char_doc.profiles.len());
+    let party: Vec<PartyMember> = char_doc.profiles.iter().map(|p| {
+        // Profiles are named after the agent class they play; the file's values stand in for
+        // classes no agent has joined yet.
+        let stats = class_stats.get(&p.display_name);
+        PartyMember {
+            id: p.id.clone(),
+            name: p.display_name.clone(),
+            class_name: p.class_name.clone(),
+            level: levels.get(&p.display_name).map_or(p.level, |l| *l as i32),
+            stats: PartyStats {
+                hp: stats.map_or(p.loadout.hit_points, |s| s.hp),
+                mana: stats.map_or(p.loadout.mana, |s| s.mana),
+                success_rate: format!("{:.0}%", stats.and_then(|s| s.success_rate).unwrap_or(p.base_success_rate) * 100.0),
+            },
+            current_action: p.current_action.clone(),
+            location: p.location.clone(),
+        }
     }).collect();
 
-    // Load Fog Map
-    let fog_path = std::path::Path::new("sdk/python/data/fog_state.json");
-    let fog_map = if let Ok(content) = std::fs::read_to_string(fog_path) {
-        serde_json::from_str(&content).unwrap_or(serde_json::json!({}))
-    } else {
-        serde_json::json!({})
-    };
-
     // Load Repositories from JSON file
     let repo_path = std::path::Path::new("sdk/python/data/repositories.json");
     let repositories: Vec<RepositoryState> = if let Ok(content) = std::fs::read_to_string(repo_path) {
@@ -119,94 +193,366 @@ pub async fn get_game_state(State(state): State<AppState>) -> Json<GameState> {
         vec![]
     };
 
-    Json(GameState {
+    Ok(GameState {
         system_status: current_status.clone(),
         selected_character_id: char_doc.selected_character_id,
         selected_character_loadout: char_doc.selected_character_loadout,
-        daily_budget: DailyBudget {
-            max: 10.0,
+        daily_budget: Some(DailyBudget {
+            max: state.budget.get(),
             spent: spend,
             unit: "USD".to_string(),
-        },
+            by_agent,
+            by_country,
+            tokens,
+        }),
         party,
         active_quests: vec![],
         fog_map,
         repositories,
         countries: build_countries(&current_status),
         knowledge_tree: build_knowledge_tree(),
-        sovereign_controls: PolicyApprovalStatus {
+        sovereign_controls: Some(PolicyApprovalStatus {
             approved: true,
             approved_by: Some("security-council".to_string()),
             policy_id: "NIST-800-53-REV5".to_string(),
-        },
+        }),
+        initiatives,
+        maintenance: crate::maintenance::current(),
+        achievements,
+    })
+}
+
+/// The current operational status of the control node; `Operational` when none was set.
+async fn system_status(synapse: &crate::store::Synapse) -> anyhow::Result<SystemStatus> {
+    Ok(match crate::workers::ops::operational_status(synapse).await? {
+        Some(status) => parse_system_status(&status),
+        None => SystemStatus::Operational,
     })
 }
 
-pub async fn get_graph_nodes(State(state): State<AppState>) -> Json<GraphData> {
+#[utoipa::path(
+    get,
+    path = "/api/v1/graph-nodes",
+    tag = "game",
+    responses((status = 200, body = GraphData)),
+)]
+pub async fn get_graph_nodes(State(state): State<AppState>, Extension(role): Extension<Role>) -> ApiResult<GraphData> {
     // 1. Fetch all triples from Synapse
     let query = "SELECT ?s ?p ?o WHERE { ?s ?p ?o } LIMIT 500";
     let mut elements = GraphElements::default();
     
-    if let Ok(res_json) = state.synapse.query(query).await {
-        if let Ok(parsed) = serde_json::from_str::<Vec<serde_json::Value>>(&res_json) {
-            let mut node_map = std::collections::HashMap::new();
-            
-            for row in parsed {
-                let s = _clean_val(row.get("s").or_else(|| row.get("?s")));
-                let p = _clean_val(row.get("p").or_else(|| row.get("?p")));
-                let o = _clean_val(row.get("o").or_else(|| row.get("?o")));
-                
-                if s.is_empty() { continue; }
-
-                // Group by subject to form nodes
-                let node_data = node_map.entry(s.clone()).or_insert_with(|| GraphNodeData {
-                    id: s.clone(),
-                    label: s.split('/').last().unwrap_or(&s).to_string(),
-                    node_type: "Entity".to_string(),
-                    active: false,
-                    triples: vec![],
-                });
-                
-                node_data.triples.push(GraphTriple {
-                    subject: s.clone(),
-                    predicate: p.clone(),
-                    object: o.clone(),
-                });
-
-                // If object looks like a URI, create an edge
-                if o.starts_with("http") {
-                    elements.edges.push(GraphEdge {
-                        data: GraphEdgeData {
-                            id: format!("{}-{}", s, o),
-                            source: s.clone(),
-                            target: o.clone(),
-                            label: p.split('/').last().unwrap_or(&p).to_string(),
-                        }
-                    });
-                }
-            }
+    let rows: Vec<TripleRow> = state.synapse.query_as(query).await.map_err(ApiError::synapse)?;
+    let mut node_map = std::collections::HashMap::new();
+    
+    for TripleRow { s, p, o } in rows {
+        if s.is_empty() { continue; }
+
+        // Group by subject to form nodes
+        let node_data = node_map.entry(s.clone()).or_insert_with(|| GraphNodeData {
+            id: s.clone(),
+            label: s.split('/').last().unwrap_or(&s).to_string(),
+            node_type: "Entity".to_string(),
+            active: false,
+            triples: vec![],
+            sources: vec![],
+        });
 
-            for (_, data) in node_map {
-                elements.nodes.push(GraphNode { data });
+        if p == PROV_ATTRIBUTED_TO {
+            if let Some(source) = Source::parse(&o) {
+                node_data.sources.push(source.as_str().to_string());
             }
+            continue;
+        }
+        
+        node_data.triples.push(GraphTriple {
+            subject: s.clone(),
+            predicate: p.clone(),
+            object: o.clone(),
+        });
+
+        // If object looks like a URI, create an edge
+        if o.starts_with("http") {
+            elements.edges.push(GraphEdge {
+                data: GraphEdgeData {
+                    id: format!("{}-{}", s, o),
+                    source: s.clone(),
+                    target: o.clone(),
+                    label: p.split('/').last().unwrap_or(&p).to_string(),
+                }
+            });
         }
     }
 
-    Json(GraphData { elements })
+    for (_, data) in node_map {
+        elements.nodes.push(GraphNode { data });
+    }
+
+    // Viewers get the shape of the graph, not the raw facts behind it.
+    if role < Role::Operator {
+        for node in &mut elements.nodes {
+            node.data.triples.clear();
+            node.data.sources.clear();
+        }
+    }
+
+    Ok(Json(GraphData { elements }))
+}
+
+#[derive(Deserialize)]
+struct TripleRow {
+    s: String,
+    p: String,
+    o: String,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/budget",
+    tag = "budget",
+    request_body = BudgetUpdateRequest,
+    responses((status = 200, body = DailyBudget)),
+)]
+pub async fn put_budget(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(payload): Valid<BudgetUpdateRequest>,
+) -> ApiResult<DailyBudget> {
+    state.budget.set(&state.synapse, payload.max, &gateway_actor(role)).await.map_err(ApiError::synapse)?;
+    let spent = crate::budget::spent_today(&state.synapse).await.map_err(ApiError::synapse)?;
+    let tokens = crate::budget::tokens_today(&state.synapse, &state.budget).await.map_err(ApiError::synapse)?;
+    info!("💰 Daily budget set to ${:.2}", payload.max);
+
+    Ok(Json(DailyBudget {
+        max: payload.max,
+        spent,
+        unit: "USD".to_string(),
+        by_agent: Vec::new(),
+        by_country: Vec::new(),
+        tokens,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/spend",
+    tag = "budget",
+    request_body = SpendReport,
+    responses((status = 200, body = SpendAck)),
+)]
+pub async fn post_spend(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(payload): Valid<SpendReport>,
+) -> ApiResult<SpendAck> {
+    require_role(role, Role::Operator)?;
+    let spend = crate::budget::Spend {
+        amount_usd: payload.amount,
+        agent_id: payload.agent_id.filter(|id| !id.trim().is_empty()),
+        task_id: payload.task_id.filter(|id| !id.trim().is_empty()),
+        repository: payload.repo_id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty()),
+        prompt_tokens: payload.prompt_tokens,
+        completion_tokens: payload.completion_tokens,
+        model: payload.model.map(|model| model.trim().to_string()).filter(|model| !model.is_empty()),
+    };
+    let event_id = crate::budget::record_spend(&state.synapse, &spend).await.map_err(ApiError::synapse)?;
+    let spent = crate::budget::spent_today(&state.synapse).await.map_err(ApiError::synapse)?;
+    let tokens = crate::budget::tokens_today(&state.synapse, &state.budget).await.map_err(ApiError::synapse)?;
+    info!("💸 Spend of ${:.4} recorded as {}", spend.amount_usd, event_id);
+
+    Ok(Json(SpendAck {
+        event_id,
+        daily_budget: DailyBudget {
+            max: state.budget.get(),
+            spent,
+            unit: "USD".to_string(),
+            by_agent: Vec::new(),
+            by_country: Vec::new(),
+            tokens,
+        },
+    }))
 }
 
-fn _clean_val(val: Option<&serde_json::Value>) -> String {
-    match val {
-        Some(serde_json::Value::String(s)) => s.trim_matches(|c| c == '"' || c == '<' || c == '>').to_string(),
-        _ => String::new(),
+/// Spend of a week or a month per agent and per repository, with the month's burn rate.
+#[utoipa::path(
+    get,
+    path = "/api/v1/budget/report",
+    tag = "budget",
+    params(BudgetReportQuery),
+    responses((status = 200, body = BudgetReport)),
+)]
+pub async fn get_budget_report(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Query(query): Query<BudgetReportQuery>,
+) -> ApiResult<BudgetReport> {
+    require_role(role, Role::Admin)?;
+    let period = match query.period.as_deref() {
+        Some(raw) => crate::budget::Period::parse(raw)
+            .ok_or_else(|| ApiError::invalid(format!("Unknown period '{}'; expected week or month", raw)))?,
+        None => crate::budget::Period::Week,
+    };
+    let date = match query.date.as_deref() {
+        Some(raw) => chrono::NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+            .map_err(|_| ApiError::invalid(format!("'date' must be a YYYY-MM-DD day, not '{}'", raw)))?,
+        None => crate::clock::today(),
+    };
+    let report = crate::budget::report(&state.synapse, period, date, &state.budget).await.map_err(ApiError::synapse)?;
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/provenance",
+    tag = "provenance",
+    responses((status = 200, body = Vec<ProvenanceSourceSummary>)),
+)]
+pub async fn get_provenance_summary(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+) -> ApiResult<Vec<ProvenanceSourceSummary>> {
+    require_role(role, Role::Operator)?;
+    let query = format!(
+        "SELECT ?source (COUNT(DISTINCT ?s) AS ?subjects) WHERE {{ ?s <{PROV_ATTRIBUTED_TO}> ?source }} GROUP BY ?source"
+    );
+    #[derive(Deserialize)]
+    struct SourceRow {
+        source: String,
+        #[serde(default, deserialize_with = "sparql::parsed")]
+        subjects: Option<u64>,
+    }
+    let rows: Vec<SourceRow> = state.synapse.query_as(&query).await.map_err(ApiError::synapse)?;
+
+    let summary = rows
+        .into_iter()
+        .filter_map(|row| {
+            let source = Source::parse(&row.source)?;
+            Some(ProvenanceSourceSummary {
+                source: source.as_str().to_string(),
+                subjects: row.subjects.unwrap_or(0),
+            })
+        })
+        .collect();
+
+    Ok(Json(summary))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/provenance/{source}",
+    tag = "provenance",
+    params(("source" = String, Path, description = "Provenance source, e.g. `trello`")),
+    responses((status = 200, body = ProvenanceSubjectsResponse)),
+)]
+pub async fn get_provenance_subjects(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(source): Path<String>,
+) -> ApiResult<ProvenanceSubjectsResponse> {
+    require_role(role, Role::Operator)?;
+    let source = Source::parse(&source)
+        .ok_or_else(|| ApiError::not_found(format!("Unknown provenance source '{source}'")))?;
+    let query = sparql::Query::new(format!("SELECT DISTINCT ?s WHERE {{ ?s <{PROV_ATTRIBUTED_TO}> $source }} LIMIT 500"))
+        .iri("source", &source.iri());
+    #[derive(Deserialize)]
+    struct SubjectRow {
+        s: String,
     }
+    let rows: Vec<SubjectRow> = state.synapse.select_as(&query).await.map_err(ApiError::synapse)?;
+
+    Ok(Json(ProvenanceSubjectsResponse {
+        source: source.as_str().to_string(),
+        subjects: rows.into_iter().map(|row| row.s).filter(|s| !s.is_empty()).collect(),
+    }))
+}
+
+/// Removes the triples a source wrote, keeping those another source wrote as well.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/provenance/{source}",
+    tag = "provenance",
+    params(("source" = String, Path, description = "Provenance source, e.g. `trello`")),
+    responses((status = 200, body = ProvenancePurge)),
+)]
+pub async fn delete_provenance_source(
+    State(state): State<AppState>,
+    Path(source): Path<String>,
+) -> ApiResult<ProvenancePurge> {
+    let source = Source::parse(&source)
+        .ok_or_else(|| ApiError::not_found(format!("Unknown provenance source '{source}'")))?;
+    let purged = crate::provenance::purge(&state.synapse, source).await.map_err(ApiError::synapse)?;
+    info!(
+        "🧹 Purged provenance source '{}': {} triples removed, {} kept",
+        source.as_str(),
+        purged.triples,
+        purged.kept
+    );
+    Ok(Json(ProvenancePurge {
+        source: source.as_str().to_string(),
+        triples: purged.triples as u64,
+        kept: purged.kept as u64,
+        subjects: purged.subjects as u64,
+    }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/control/audit",
+    tag = "control",
+    responses((status = 200, body = Vec<AuditRecord>)),
+)]
 pub async fn get_audit_log(State(state): State<AppState>) -> Json<Vec<AuditRecord>> {
     let audit = state.audit_log.lock().await;
     Json(audit.clone())
 }
 
+/// Halts, resumes, approval decisions, budget changes and manual assignments from every
+/// channel, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "control",
+    params(AuditQuery),
+    responses((status = 200, body = Vec<AuditEvent>)),
+)]
+pub async fn get_audit(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Query(query): Query<AuditQuery>,
+) -> ApiResult<Vec<AuditEvent>> {
+    require_role(role, Role::Operator)?;
+    let action = match query.action.as_deref() {
+        Some(raw) => Some(audit::Action::parse(raw).ok_or_else(|| ApiError::invalid(format!("Unknown action '{}'", raw)))?),
+        None => None,
+    };
+    let channel = match query.channel.as_deref() {
+        Some(raw) => Some(Source::parse(raw.trim()).ok_or_else(|| ApiError::invalid(format!("Unknown channel '{}'", raw)))?),
+        None => None,
+    };
+    let since = match query.since.as_deref() {
+        Some(raw) => Some(
+            chrono::DateTime::parse_from_rfc3339(raw.trim())
+                .map_err(|e| ApiError::invalid(format!("`since` is not an RFC3339 time: {}", e)))?
+                .with_timezone(&Utc),
+        ),
+        None => None,
+    };
+    let filter = audit::Filter {
+        action,
+        actor: query.actor.map(|actor| actor.trim().to_string()).filter(|actor| !actor.is_empty()),
+        channel,
+        since,
+        limit: query.limit.unwrap_or(audit::DEFAULT_LIMIT).min(audit::MAX_LIMIT),
+    };
+    Ok(Json(audit::list(&state.synapse, &filter).await.map_err(ApiError::synapse)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/control/commands",
+    tag = "control",
+    request_body = ControlCommand,
+    responses((status = 200, body = ControlCommandAck)),
+)]
 pub async fn post_control_command(
     State(state): State<AppState>,
     Json(command): Json<ControlCommand>,
@@ -296,22 +642,50 @@ pub async fn post_control_command(
     })
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/events",
+    tag = "events",
+    request_body = GatewayEvent,
+    responses((status = 200, body = EventAck)),
+)]
 pub async fn post_event(
     State(state): State<AppState>,
+    Extension(role): Extension<Role>,
     Json(event): Json<GatewayEvent>,
-) -> Json<EventAck> {
+) -> ApiResult<EventAck> {
+    require_role(role, Role::Operator)?;
     let _ = state.event_tx.send(event.clone());
 
-    Json(EventAck {
+    Ok(Json(EventAck {
         status: "broadcasted".to_string(),
         event,
-    })
+    }))
 }
 
+/// A new task titled `title` (a literal) and assigned to `agent_uri`.
+fn mission_triples<'a>(task_uri: &'a str, title: &'a str, agent_uri: &'a str) -> Vec<(&'a str, &'a str, &'a str)> {
+    vec![
+        (task_uri, "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task"),
+        (task_uri, "http://swarm.os/ontology/title", title),
+        (task_uri, "http://swarm.os/ontology/internalState", "\"REQUIREMENTS\""),
+        (task_uri, "http://swarm.os/ontology/assignedTo", agent_uri),
+    ]
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/mission/assign",
+    tag = "control",
+    request_body = MissionAssignment,
+    responses((status = 200, body = ControlCommandAck)),
+)]
 pub async fn post_mission_assign(
     State(state): State<AppState>,
-    Json(mission): Json<MissionAssignment>,
-) -> Json<ControlCommandAck> {
+    Extension(role): Extension<Role>,
+    Valid(mission): Valid<MissionAssignment>,
+) -> ApiResult<ControlCommandAck> {
+    require_role(role, Role::Operator)?;
     let task_id = uuid::Uuid::new_v4().to_string();
     let task_uri = format!("http://swarm.os/tasks/{}", task_id);
     let agent_uri = if mission.agent_id.is_empty() {
@@ -320,17 +694,19 @@ pub async fn post_mission_assign(
         mission.agent_id.clone()
     };
 
-    let title_lit = format!("\"{}\"", mission.task);
-    let agent_ref = format!("<{}>", agent_uri);
+    let title_lit = sparql::literal(&mission.task);
 
-    let triples = vec![
-        (task_uri.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task"),
-        (task_uri.as_str(), "http://swarm.os/ontology/title", title_lit.as_str()),
-        (task_uri.as_str(), "http://swarm.os/ontology/internalState", "\"REQUIREMENTS\""),
-        (task_uri.as_str(), "http://swarm.os/ontology/assignedTo", agent_ref.as_str()),
-    ];
+    let audit = audit::triples(
+        audit::Action::Assign,
+        &gateway_actor(role),
+        Some(&task_uri),
+        Some(&format!("'{}' assigned to {}", mission.task, agent_uri)),
+    );
+
+    let mut triples = mission_triples(&task_uri, &title_lit, &agent_uri);
+    triples.extend(audit.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())));
 
-    let _ = state.synapse.ingest(triples).await;
+    state.synapse.ingest_from(Source::Gateway, triples).await.map_err(ApiError::synapse)?;
 
     let command = ControlCommand {
         command: crate::server::contracts::ControlCommandType::AssignMission,
@@ -365,30 +741,45 @@ pub async fn post_mission_assign(
     )
     .await;
 
-    Json(ControlCommandAck {
+    Ok(Json(ControlCommandAck {
         tracking_id,
         status: CommandPhase::Completed,
         reason: None,
         final_state: Some(final_state),
         command,
-    })
+    }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/knowledge-tree/nodes",
+    tag = "knowledge",
+    request_body = KnowledgeNodeIngestRequest,
+    responses((status = 200, body = IngestKnowledgeNodeResponse)),
+)]
 pub async fn post_knowledge_tree_node(
     State(state): State<AppState>,
-    Json(payload): Json<KnowledgeNodeIngestRequest>,
-) -> Json<IngestKnowledgeNodeResponse> {
+    Extension(role): Extension<Role>,
+    Valid(payload): Valid<KnowledgeNodeIngestRequest>,
+) -> ApiResult<IngestKnowledgeNodeResponse> {
+    require_role(role, Role::Operator)?;
     let node = map_ingest_request_to_node(&payload);
     let triples = knowledge_node_to_triples(&node, &payload);
     let triples_refs: Vec<(&str, &str, &str)> = triples.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())).collect();
-    let _ = state.synapse.ingest(triples_refs).await;
+    state.synapse.ingest_from(Source::Gateway, triples_refs).await.map_err(ApiError::synapse)?;
 
-    Json(IngestKnowledgeNodeResponse {
+    Ok(Json(IngestKnowledgeNodeResponse {
         status: "ingested".to_string(),
         node,
-    })
+    }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/characters",
+    tag = "game",
+    responses((status = 200, body = serde_json::Value)),
+)]
 pub async fn get_characters() -> Json<serde_json::Value> {
     let path = std::path::Path::new("sdk/python/data/character_profiles.json");
     let content = std::fs::read_to_string(path).unwrap_or_else(|_| "{}".to_string());
@@ -396,14 +787,18 @@ pub async fn get_characters() -> Json<serde_json::Value> {
     Json(val)
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CharacterSelectionRequest {
-    pub character_id: String,
-}
-
+#[utoipa::path(
+    post,
+    path = "/api/v1/characters/select",
+    tag = "game",
+    request_body = CharacterSelectionRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
 pub async fn select_character(
+    Extension(role): Extension<Role>,
     Json(payload): Json<CharacterSelectionRequest>,
-) -> impl IntoResponse {
+) -> ApiResult<serde_json::Value> {
+    require_role(role, Role::Operator)?;
     let path = std::path::Path::new("sdk/python/data/character_profiles.json");
     let content = std::fs::read_to_string(path).unwrap_or_else(|_| "{}".to_string());
     let mut doc: crate::server::contracts::CharacterProfileDocument = 
@@ -415,22 +810,24 @@ pub async fn select_character(
 
     doc.selected_character_id = Some(payload.character_id.clone());
     
-    if let Ok(updated) = serde_json::to_string_pretty(&doc) {
-        let _ = std::fs::write(path, updated);
-    }
-
-    Json(serde_json::json!({ "selected_character_id": payload.character_id }))
-}
+    let updated = serde_json::to_string_pretty(&doc).map_err(ApiError::internal)?;
+    std::fs::write(path, updated).map_err(ApiError::storage)?;
 
-#[derive(Debug, Deserialize)]
-pub struct CharacterLoadoutSaveRequest {
-    pub character_id: String,
-    pub loadout: crate::server::contracts::CharacterLoadoutSelection,
+    Ok(Json(serde_json::json!({ "selected_character_id": payload.character_id })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/characters/loadout",
+    tag = "game",
+    request_body = CharacterLoadoutSaveRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
 pub async fn save_character_loadout(
+    Extension(role): Extension<Role>,
     Json(payload): Json<CharacterLoadoutSaveRequest>,
-) -> impl IntoResponse {
+) -> ApiResult<serde_json::Value> {
+    require_role(role, Role::Operator)?;
     let path = std::path::Path::new("sdk/python/data/character_profiles.json");
     let content = std::fs::read_to_string(path).unwrap_or_else(|_| "{}".to_string());
     let mut doc: crate::server::contracts::CharacterProfileDocument = 
@@ -443,39 +840,43 @@ pub async fn save_character_loadout(
     doc.selected_character_id = Some(payload.character_id.clone());
     doc.selected_character_loadout = payload.loadout.clone();
     
-    if let Ok(updated) = serde_json::to_string_pretty(&doc) {
-        let _ = std::fs::write(path, updated);
-    }
+    let updated = serde_json::to_string_pretty(&doc).map_err(ApiError::internal)?;
+    std::fs::write(path, updated).map_err(ApiError::storage)?;
 
-    Json(serde_json::json!({
+    Ok(Json(serde_json::json!({
         "selected_character_id": payload.character_id,
         "selected_character_loadout": payload.loadout
-    }))
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/knowledge-tree/{node_id}/docs",
+    tag = "knowledge",
+    params(("node_id" = String, Path, description = "Knowledge node id")),
+    responses((status = 200, body = KnowledgeNodeDocumentationResponse)),
+)]
 pub async fn get_knowledge_node_documentation(
     State(state): State<AppState>,
     Path(node_id): Path<String>,
-) -> Json<KnowledgeNodeDocumentationResponse> {
-    let query = format!(
+) -> ApiResult<KnowledgeNodeDocumentationResponse> {
+    let query = sparql::Query::new(
         r#"
         PREFIX swarm: <http://swarm.os/ontology/>
-        SELECT ?docs WHERE {{
-            <http://swarm.os/ontology/knowledge/{node_id}> swarm:documentation ?docs .
-        }} LIMIT 1
-        "#
-    );
+        SELECT ?docs WHERE {
+            $node swarm:documentation ?docs .
+        } LIMIT 1
+        "#,
+    )
+    .iri("node", &format!("http://swarm.os/ontology/knowledge/{node_id}"));
+    let query = query.build().map_err(|_| ApiError::invalid(format!("'{node_id}' is not a valid knowledge node id")))?;
 
-    let mut documentation = state
-        .synapse
-        .query(&query)
-        .await
-        .ok()
-        .and_then(|json| serde_json::from_str::<Vec<serde_json::Value>>(&json).ok())
-        .and_then(|rows| rows.first().cloned())
-        .and_then(|row| row.get("docs").or_else(|| row.get("?docs")).cloned())
-        .and_then(|value| value.as_str().map(ToString::to_string))
-        .unwrap_or_default();
+    #[derive(Deserialize)]
+    struct DocsRow {
+        docs: String,
+    }
+    let rows: Vec<DocsRow> = state.synapse.query_as(&query).await.map_err(ApiError::synapse)?;
+    let mut documentation = rows.into_iter().next().map(|row| row.docs).unwrap_or_default();
 
     // Fallback to local build_knowledge_tree if empty
     if documentation.is_empty() {
@@ -484,29 +885,847 @@ pub async fn get_knowledge_node_documentation(
         }
     }
 
-    Json(KnowledgeNodeDocumentationResponse {
+    if documentation.is_empty() {
+        return Err(ApiError::not_found(format!("No documentation for knowledge node '{node_id}'")));
+    }
+
+    Ok(Json(KnowledgeNodeDocumentationResponse {
         node_id,
         documentation,
-    })
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/bus",
+    tag = "events",
+    responses((status = 200, body = EventBusStats)),
+)]
+pub async fn get_event_bus_stats(State(state): State<AppState>) -> Json<EventBusStats> {
+    Json(state.bus.stats(&state.event_tx))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/synapse/cache",
+    tag = "metrics",
+    responses((status = 200, body = QueryCacheStats)),
+)]
+pub async fn get_query_cache_stats() -> Json<QueryCacheStats> {
+    Json(crate::query_cache::shared().stats())
+}
+
+/// Resource usage of orchestrator runs, aggregated per agent class.
+#[utoipa::path(
+    get,
+    path = "/api/v1/metrics/resources",
+    tag = "metrics",
+    responses((status = 200, body = Vec<AgentClassResources>)),
+)]
+pub async fn get_resource_stats(State(state): State<AppState>) -> ApiResult<Vec<AgentClassResources>> {
+    let stats = crate::resource_usage::class_stats(&state.synapse).await.map_err(ApiError::synapse)?;
+    Ok(Json(stats))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/maintenance",
+    tag = "maintenance",
+    responses((status = 200, body = MaintenanceStatus)),
+)]
+pub async fn get_maintenance(State(state): State<AppState>) -> ApiResult<MaintenanceStatus> {
+    maintenance_status(&state, crate::maintenance::current()).await
 }
 
+/// Hands a task the agents gave up on back to them, with a fresh set of attempts.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/reset",
+    tag = "tasks",
+    request_body = TaskResetRequest,
+    responses((status = 200, body = TaskResetAck)),
+)]
+pub async fn post_task_reset(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(request): Valid<TaskResetRequest>,
+) -> ApiResult<TaskResetAck> {
+    require_role(role, Role::Operator)?;
+    let task_id = request.task_id.trim();
+
+    let outcome = crate::escalation::reset(&state.synapse, task_id, request.reset_by.as_deref())
+        .await
+        .map_err(ApiError::synapse)?;
+    match outcome {
+        ResetOutcome::Queued { title } => {
+            info!("🙋 Task {} reset by {}", task_id, request.reset_by.as_deref().unwrap_or("an operator"));
+            Ok(Json(TaskResetAck { task_id: task_id.to_string(), title, state: TaskState::RetryScheduled.as_str().to_string() }))
+        }
+        ResetOutcome::UnknownTask => Err(ApiError::not_found(format!("No task {}", task_id))),
+        ResetOutcome::NotEscalated => Err(ApiError::new(
+            ErrorCode::Conflict,
+            format!("Task {} is not escalated to humans, or was already reset", task_id),
+        )),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskFilter {
+    /// Only tasks in this state, e.g. `PROCESSING`.
+    pub state: Option<String>,
+}
+
+/// Unfinished tasks, like the chat `/tasks` command.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks",
+    tag = "tasks",
+    params(TaskFilter),
+    responses((status = 200, body = Vec<TaskView>)),
+)]
+pub async fn get_tasks(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Query(filter): Query<TaskFilter>,
+) -> ApiResult<Vec<TaskView>> {
+    require_role(role, Role::Operator)?;
+    validate::check(&filter)?;
+    let mut active = tasks::active_tasks(&state.synapse).await.map_err(ApiError::synapse)?;
+    if let Some(wanted) = filter.state.as_deref().filter(|s| !s.is_empty()) {
+        active.retain(|t| t.state.eq_ignore_ascii_case(wanted));
+    }
+    Ok(Json(
+        active
+            .into_iter()
+            .map(|t| TaskView { id: t.id, title: t.title, state: t.state, agent: t.agent })
+            .collect(),
+    ))
+}
+
+/// Queues a task in the agency's intake state.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks",
+    tag = "tasks",
+    request_body = TaskRequest,
+    responses((status = 200, body = TaskView)),
+)]
+pub async fn post_task(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(request): Valid<TaskRequest>,
+) -> ApiResult<TaskView> {
+    require_role(role, Role::Operator)?;
+    let title = request.title.trim();
+
+    let task_id = format!("http://swarm.os/task/{}", uuid::Uuid::new_v4());
+    let title_lit = sparql::literal(title);
+    let priority_lit = request.priority.map(tasks::integer_literal);
+    let repo_iri = request.repo_id.as_deref().map(str::trim).filter(|r| !r.is_empty()).map(|repo| {
+        if repo.contains("://") { repo.to_string() } else { format!("http://swarm.os/repository/{}", repo) }
+    });
+
+    let depends_on: Vec<&str> = request.depends_on.iter().map(|d| d.trim()).collect();
+    if !depends_on.is_empty() {
+        let known = tasks::all_tasks(&state.synapse).await.map_err(ApiError::synapse)?;
+        let errors: Vec<FieldError> = depends_on
+            .iter()
+            .enumerate()
+            .filter(|(_, dep)| !known.iter().any(|t| t.id == **dep))
+            .map(|(i, dep)| FieldError { field: format!("depends_on[{}]", i), message: format!("no task {}", dep) })
+            .collect();
+        if !errors.is_empty() {
+            return Err(ApiError::fields(errors));
+        }
+    }
+
+    let mut triples = vec![
+        (task_id.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task"),
+        (task_id.as_str(), tasks::INTERNAL_STATE, "\"REQUIREMENTS\""),
+        (task_id.as_str(), "http://swarm.os/ontology/title", title_lit.as_str()),
+    ];
+    if let Some(priority_lit) = &priority_lit {
+        triples.push((task_id.as_str(), tasks::PRIORITY, priority_lit.as_str()));
+    }
+    if let Some(repo_iri) = &repo_iri {
+        triples.push((task_id.as_str(), crate::initiatives::REPOSITORY, repo_iri.as_str()));
+    }
+    if request.requires_approval {
+        triples.push((task_id.as_str(), approvals::REQUIRES_APPROVAL, "\"true\""));
+    }
+    // A new task has nothing waiting for it yet, so these links cannot close a cycle.
+    for dep in &depends_on {
+        triples.push((task_id.as_str(), dependencies::DEPENDS_ON, dep));
+    }
+    state.synapse.ingest_from(Source::Gateway, triples).await.map_err(ApiError::synapse)?;
+    info!("📝 Task '{}' queued through the gateway", title);
+
+    Ok(Json(TaskView { id: task_id, title: title.to_string(), state: "REQUIREMENTS".to_string(), agent: None }))
+}
+
+/// Approves or rejects a task held in `AWAITING_APPROVAL`, like the Telegram buttons.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/approval",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task IRI, or the id after `http://swarm.os/task/`")),
+    request_body = ApprovalDecisionRequest,
+    responses((status = 200, body = ApprovalDecisionAck)),
+)]
+pub async fn post_task_approval(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+    Valid(request): Valid<ApprovalDecisionRequest>,
+) -> ApiResult<ApprovalDecisionAck> {
+    require_role(role, Role::Operator)?;
+    let task_id = dependencies::task_iri(&id);
+    let decision = approvals::Decision::from(request.decision);
+    let decided_by = request.decided_by.trim();
+
+    let outcome = approvals::decide(&state.synapse, Source::Gateway, &task_id, decision, decided_by)
+        .await
+        .map_err(ApiError::synapse)?;
+    match outcome {
+        DecisionOutcome::Recorded { title } => {
+            info!("🛡️ Task {} {} by {}", task_id, decision.state().to_lowercase(), decided_by);
+            Ok(Json(ApprovalDecisionAck { task_id, title, state: decision.state().to_string() }))
+        }
+        DecisionOutcome::UnknownTask => Err(ApiError::not_found(format!("No task {}", task_id))),
+        DecisionOutcome::NotAwaiting => Err(ApiError::new(
+            ErrorCode::Conflict,
+            format!("Task {} does not need approval, or was already approved or rejected", task_id),
+        )),
+    }
+}
+
+/// Cancels a task, stopping its orchestrator and freeing its agent if it is running.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/cancel",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task IRI, or the id after `http://swarm.os/task/`")),
+    request_body = TaskCancelRequest,
+    responses((status = 200, body = TaskCancelAck)),
+)]
+pub async fn post_task_cancel(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+    Valid(request): Valid<TaskCancelRequest>,
+) -> ApiResult<TaskCancelAck> {
+    require_role(role, Role::Operator)?;
+    let task_id = dependencies::task_iri(&id);
+    let by = request.cancelled_by.as_deref().map(str::trim).filter(|b| !b.is_empty()).unwrap_or("an operator");
+
+    let outcome = crate::workers::agency::cancel(&state.synapse, Source::Gateway, &task_id, by)
+        .await
+        .map_err(ApiError::synapse)?;
+    let cancelled = TaskState::Cancelled.as_str().to_string();
+    match outcome {
+        CancelOutcome::Stopping { title } => Ok(Json(TaskCancelAck { task_id, title, state: cancelled, stopped_run: true })),
+        CancelOutcome::Cancelled { title } => Ok(Json(TaskCancelAck { task_id, title, state: cancelled, stopped_run: false })),
+        CancelOutcome::UnknownTask => Err(ApiError::not_found(format!("No task {}", task_id))),
+        CancelOutcome::Finished { state } => {
+            Err(ApiError::new(ErrorCode::Conflict, format!("Task {} already finished as {}", task_id, state)))
+        }
+    }
+}
+
+/// What a task waits for and what waits for it. `:id` is a gateway task id or a full task IRI.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/graph",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task IRI, or the id after `http://swarm.os/task/`")),
+    responses((status = 200, body = TaskGraph)),
+)]
+pub async fn get_task_graph(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+) -> ApiResult<TaskGraph> {
+    require_role(role, Role::Operator)?;
+    let task_id = dependencies::task_iri(&id);
+    match dependencies::graph(&state.synapse, &task_id).await.map_err(ApiError::synapse)? {
+        Some(graph) => Ok(Json(graph)),
+        None => Err(ApiError::not_found(format!("No task {}", task_id))),
+    }
+}
+
+/// What an uploaded artifact is; the request body is its content.
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArtifactUpload {
+    /// File name shown to reviewers, e.g. `fix.diff`.
+    pub name: String,
+    /// `file` when omitted.
+    #[serde(default)]
+    pub kind: ArtifactKind,
+}
+
+/// Registers a file, diff or report an agent produced for a task. The body is the content and
+/// its `Content-Type` is kept for serving it back.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/artifacts",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task IRI, or the id after `http://swarm.os/task/`"), ArtifactUpload),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses((status = 200, body = ArtifactView)),
+)]
+pub async fn post_task_artifact(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+    Query(upload): Query<ArtifactUpload>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> ApiResult<ArtifactView> {
+    require_role(role, Role::Operator)?;
+    validate::check(&upload)?;
+    let task_id = dependencies::task_iri(&id);
+    require_task(&state, &task_id).await?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or("application/octet-stream");
+    let upload = artifacts::Upload {
+        name: upload.name.trim().to_string(),
+        kind: upload.kind,
+        content_type: content_type.to_string(),
+        bytes: body.to_vec(),
+    };
+    let artifact = state.artifacts.register(&state.synapse, &task_id, upload).await.map_err(ApiError::storage)?;
+    info!("📦 {} {} ({} bytes) registered on {}", artifact.kind.as_str(), artifact.name, artifact.size_bytes, task_id);
+    Ok(Json(artifact))
+}
+
+/// The artifacts registered on a task, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/artifacts",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task IRI, or the id after `http://swarm.os/task/`")),
+    responses((status = 200, body = Vec<ArtifactView>)),
+)]
+pub async fn get_task_artifacts(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+) -> ApiResult<Vec<ArtifactView>> {
+    require_role(role, Role::Operator)?;
+    let task_id = dependencies::task_iri(&id);
+    require_task(&state, &task_id).await?;
+    Ok(Json(state.artifacts.list(&state.synapse, &task_id).await.map_err(ApiError::synapse)?))
+}
+
+/// The content of an artifact, for review. It is served sandboxed, so an HTML report cannot
+/// run scripts as the gateway.
+#[utoipa::path(
+    get,
+    path = "/api/v1/artifacts/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Artifact IRI, or the id after `http://swarm.os/artifact/`")),
+    responses((status = 200, description = "The content, with the type it was uploaded with")),
+)]
+pub async fn get_artifact(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    require_role(role, Role::Operator)?;
+    let artifact_id = artifacts::artifact_iri(&id);
+    let (artifact, content) = state
+        .artifacts
+        .open(&state.synapse, &artifact_id)
+        .await
+        .map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::not_found(format!("No artifact {}", artifact_id)))?;
+    let disposition = format!("inline; filename=\"{}\"", artifact.name.replace(['"', '\\'], "_"));
+    let headers = [
+        (header::CONTENT_TYPE, artifact.content_type),
+        (header::CONTENT_DISPOSITION, disposition),
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        (header::CONTENT_SECURITY_POLICY, "sandbox".to_string()),
+    ];
+    Ok((headers, content).into_response())
+}
+
+async fn require_task(state: &AppState, task_id: &str) -> Result<(), ApiError> {
+    let known = tasks::all_tasks(&state.synapse).await.map_err(ApiError::synapse)?;
+    if known.iter().any(|t| t.id == task_id) {
+        Ok(())
+    } else {
+        Err(ApiError::not_found(format!("No task {}", task_id)))
+    }
+}
+
+/// Emergency halt, like the chat `/stop_all` command.
+#[utoipa::path(
+    post,
+    path = "/api/v1/control/halt",
+    tag = "control",
+    responses((status = 200, body = SystemStatusAck)),
+)]
+pub async fn post_halt(State(state): State<AppState>, Extension(role): Extension<Role>) -> ApiResult<SystemStatusAck> {
+    set_operational_status(&state, role, SystemStatus::Halted).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/control/resume",
+    tag = "control",
+    responses((status = 200, body = SystemStatusAck)),
+)]
+pub async fn post_resume(State(state): State<AppState>, Extension(role): Extension<Role>) -> ApiResult<SystemStatusAck> {
+    set_operational_status(&state, role, SystemStatus::Operational).await
+}
+
+async fn set_operational_status(state: &AppState, role: Role, status: SystemStatus) -> ApiResult<SystemStatusAck> {
+    crate::workers::ops::set_operational_status(status.as_str(), Source::Gateway, &gateway_actor(role), &state.synapse)
+        .await
+        .map_err(ApiError::synapse)?;
+    info!("🚦 System status set to {} through the gateway", status.as_str());
+    Ok(Json(SystemStatusAck { system_status: status }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/workers",
+    tag = "admin",
+    responses((status = 200, body = Vec<WorkerStatus>)),
+)]
+pub async fn get_workers() -> ApiResult<Vec<WorkerStatus>> {
+    Ok(Json(crate::workers::supervisor::shared().statuses()))
+}
+
+/// `stop`, `start` or `restart` one background worker without touching the others.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/workers/{name}/{action}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Worker name"), ("action" = String, Path, description = "`start`, `stop` or `restart`")),
+    responses((status = 200, body = WorkerStatus)),
+)]
+pub async fn post_worker_action(
+    State(state): State<AppState>,
+    Path((name, action)): Path<(String, String)>,
+) -> ApiResult<WorkerStatus> {
+    let workers = crate::workers::supervisor::shared();
+    let result = match action.as_str() {
+        "stop" => workers.stop(&name),
+        "start" => workers.start(&name),
+        "restart" => workers.restart(&name),
+        other => return Err(ApiError::not_found(format!("Unknown worker action '{}'; use stop, start or restart", other))),
+    };
+    let status = result.map_err(|e| match e {
+        SupervisorError::UnknownWorker(_) => ApiError::not_found(e.to_string()),
+        SupervisorError::AlreadyRunning(_) | SupervisorError::NotRunning(_) => ApiError::new(ErrorCode::Conflict, e.to_string()),
+    })?;
+    // A stopped worker stops beating on purpose; it should not raise a WorkerDown alert.
+    if action == "stop" {
+        state.health.forget(&name);
+    }
+    Ok(Json(status))
+}
+
+/// Enters or leaves maintenance mode. Intake keeps running either way.
+#[utoipa::path(
+    put,
+    path = "/api/v1/maintenance",
+    tag = "maintenance",
+    request_body = MaintenanceRequest,
+    responses((status = 200, body = MaintenanceStatus)),
+)]
+pub async fn put_maintenance(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(request): Valid<MaintenanceRequest>,
+) -> ApiResult<MaintenanceStatus> {
+    require_role(role, Role::Operator)?;
+    let banner = crate::maintenance::set(&state.synapse, request.enabled, request.reason.as_deref(), Source::Gateway)
+        .await
+        .map_err(ApiError::synapse)?;
+    info!("🚧 Maintenance mode {}", if request.enabled { "entered" } else { "left" });
+    maintenance_status(&state, banner).await
+}
+
+async fn maintenance_status(state: &AppState, banner: Option<MaintenanceBanner>) -> ApiResult<MaintenanceStatus> {
+    let queued_tasks = crate::tasks::active_tasks(&state.synapse)
+        .await
+        .map_err(ApiError::synapse)?
+        .iter()
+        .filter(|t| t.state == "REQUIREMENTS")
+        .count();
+    Ok(Json(MaintenanceStatus { enabled: banner.is_some(), banner, queued_tasks }))
+}
+
+/// Registers a visualizer callback URL for signed event webhooks. The secret is only
+/// returned here.
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/subscriptions",
+    tag = "webhooks",
+    request_body = WebhookSubscriptionRequest,
+    responses((status = 200, body = WebhookSubscription)),
+)]
+pub async fn post_webhook_subscription(
+    Extension(role): Extension<Role>,
+    Valid(request): Valid<WebhookSubscriptionRequest>,
+) -> ApiResult<WebhookSubscription> {
+    require_role(role, Role::Operator)?;
+    let url = request.url.trim();
+    let subscription = crate::outbox::shared().subscribe(url).map_err(ApiError::storage)?;
+    Ok(Json(subscription))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/subscriptions",
+    tag = "webhooks",
+    responses((status = 200, body = Vec<WebhookSubscription>)),
+)]
+pub async fn get_webhook_subscriptions(Extension(role): Extension<Role>) -> ApiResult<Vec<WebhookSubscription>> {
+    require_role(role, Role::Operator)?;
+    Ok(Json(crate::outbox::shared().subscriptions()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/subscriptions/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Subscription id")),
+    responses((status = 200, body = WebhookSubscription)),
+)]
+pub async fn delete_webhook_subscription(
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+) -> ApiResult<WebhookSubscription> {
+    require_role(role, Role::Operator)?;
+    crate::outbox::shared()
+        .unsubscribe(&id)
+        .map_err(ApiError::storage)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("No webhook subscription {}", id)))
+}
+
+/// Firing built-in health rules, in the same envelope as Prometheus' `/api/v1/alerts`.
+/// The budget rule is skipped when today's spend cannot be read; the Synapse error-rate
+/// rule covers that case.
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts",
+    tag = "metrics",
+    responses((status = 200, body = HealthAlertsResponse)),
+)]
+pub async fn get_health_alerts(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+) -> ApiResult<HealthAlertsResponse> {
+    require_role(role, Role::Operator)?;
+    let budget = crate::budget::spent_today(&state.synapse)
+        .await
+        .ok()
+        .map(|spent| (spent, state.budget.get()));
+
+    Ok(Json(HealthAlertsResponse {
+        status: "success".to_string(),
+        data: HealthAlertsData { alerts: state.health.firing_alerts(budget) },
+    }))
+}
+
+/// Telegram update push (webhook mode). Telegram authenticates with the secret registered
+/// through `setWebhook`, not with a gateway token.
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/telegram",
+    tag = "chat",
+    request_body = serde_json::Value,
+    responses((status = 200, description = "Accepted")),
+)]
+pub async fn post_telegram_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(update): Json<serde_json::Value>,
+) -> Result<StatusCode, ApiError> {
+    let webhook = state
+        .webhooks
+        .telegram
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("Telegram webhook mode is not enabled"))?;
+
+    let secret = headers
+        .get("x-telegram-bot-api-secret-token")
+        .and_then(|v| v.to_str().ok());
+    if secret != Some(webhook.secret.as_str()) {
+        return Err(ApiError::new(ErrorCode::Unauthorized, "Invalid Telegram webhook secret"));
+    }
+
+    // A non-2xx makes Telegram redeliver, which is what we want if the worker is gone.
+    webhook
+        .updates
+        .send(update)
+        .await
+        .map_err(|_| ApiError::internal("Telegram worker is not running"))?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/slack/commands",
+    tag = "chat",
+    request_body(content = String, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, description = "Accepted")),
+)]
+pub async fn post_slack_command(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<StatusCode, ApiError> {
+    let slack = state
+        .webhooks
+        .slack
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("Slack commands are not enabled"))?;
+
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let signed = slack.verify(
+        header("x-slack-request-timestamp"),
+        &body,
+        header("x-slack-signature"),
+        Utc::now().timestamp(),
+    );
+    if !signed {
+        return Err(ApiError::new(ErrorCode::Unauthorized, "Invalid Slack request signature"));
+    }
+
+    let command = serde_urlencoded::from_bytes(&body)
+        .map_err(|e| ApiError::invalid(format!("Malformed slash command: {}", e)))?;
+    // The reply goes to the command's response_url, so the HTTP response stays empty.
+    slack
+        .commands
+        .send(command)
+        .await
+        .map_err(|_| ApiError::internal("Slack worker is not running"))?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/initiatives",
+    tag = "initiatives",
+    responses((status = 200, body = Vec<InitiativeStatus>)),
+)]
+pub async fn get_initiatives(State(state): State<AppState>) -> ApiResult<Vec<InitiativeStatus>> {
+    let statuses = crate::initiatives::statuses(&state.synapse).await.map_err(ApiError::synapse)?;
+    Ok(Json(statuses))
+}
+
+/// Plans an initiative from its blueprint and queues the per-repository tasks.
+#[utoipa::path(
+    post,
+    path = "/api/v1/initiatives",
+    tag = "initiatives",
+    request_body = InitiativeRequest,
+    responses((status = 200, body = InitiativeStatus)),
+)]
+pub async fn post_initiative(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(request): Valid<InitiativeRequest>,
+) -> ApiResult<InitiativeStatus> {
+    require_role(role, Role::Operator)?;
+    let id = crate::initiatives::new_id();
+    let planned = crate::initiatives::plan(&id, &request).map_err(ApiError::invalid)?;
+    crate::initiatives::record(&state.synapse, &id, &request.title, &planned)
+        .await
+        .map_err(ApiError::synapse)?;
+    info!("🗺️ Initiative '{}' planned into {} tasks", request.title, planned.len());
+
+    let statuses = crate::initiatives::statuses(&state.synapse).await.map_err(ApiError::synapse)?;
+    statuses
+        .into_iter()
+        .find(|s| s.id == id)
+        .map(Json)
+        .ok_or_else(|| ApiError::internal("Initiative was written but could not be read back"))
+}
+
+/// Every scheduled task, retired ones left out.
+#[utoipa::path(
+    get,
+    path = "/api/v1/schedules",
+    tag = "schedules",
+    responses((status = 200, body = Vec<ScheduledTaskStatus>)),
+)]
+pub async fn get_schedules(State(state): State<AppState>, Extension(role): Extension<Role>) -> ApiResult<Vec<ScheduledTaskStatus>> {
+    require_role(role, Role::Operator)?;
+    let schedules = crate::schedules::load(&state.synapse).await.map_err(ApiError::synapse)?;
+    let tz = crate::clock::tz();
+    Ok(Json(schedules.iter().filter(|s| !s.retired).map(|s| s.status(tz)).collect()))
+}
+
+/// Queues a copy of the template on every occurrence of `cron`, starting with the next one.
+#[utoipa::path(
+    post,
+    path = "/api/v1/schedules",
+    tag = "schedules",
+    request_body = ScheduledTaskRequest,
+    responses((status = 200, body = ScheduledTaskStatus)),
+)]
+pub async fn post_schedule(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Valid(request): Valid<ScheduledTaskRequest>,
+) -> ApiResult<ScheduledTaskStatus> {
+    require_role(role, Role::Operator)?;
+    let id = crate::schedules::new_id();
+    let schedule = crate::schedules::record(&state.synapse, &id, &request, Utc::now())
+        .await
+        .map_err(ApiError::synapse)?;
+    info!("⏰ Task '{}' scheduled at '{}'", schedule.title, schedule.cron);
+    Ok(Json(schedule.status(crate::clock::tz())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/schedules/{id}",
+    tag = "schedules",
+    params(("id" = String, Path, description = "Schedule id")),
+    responses((status = 200, body = ScheduledTaskStatus)),
+)]
+pub async fn delete_schedule(
+    State(state): State<AppState>,
+    Extension(role): Extension<Role>,
+    Path(id): Path<String>,
+) -> ApiResult<ScheduledTaskStatus> {
+    require_role(role, Role::Operator)?;
+    let id = crate::schedules::schedule_iri(&id);
+    let schedules = crate::schedules::load(&state.synapse).await.map_err(ApiError::synapse)?;
+    let Some(schedule) = schedules.into_iter().find(|s| s.id == id && !s.retired) else {
+        return Err(ApiError::not_found(format!("No schedule {}", id)));
+    };
+    crate::schedules::retire(&state.synapse, &id, Utc::now()).await.map_err(ApiError::synapse)?;
+    info!("⏰ Schedule '{}' retired", schedule.title);
+    Ok(Json(schedule.status(crate::clock::tz())))
+}
+
+/// Streams every notification as a [`NotificationEnvelope`], whatever chat sinks are configured.
+#[utoipa::path(
+    get,
+    path = "/api/v1/notifications/stream",
+    tag = "notifications",
+    responses((status = 101, description = "WebSocket upgrade")),
+)]
+pub async fn ws_notifications(ws: WebSocketUpgrade, Extension(role): Extension<Role>) -> Result<Response, ApiError> {
+    require_role(role, Role::Operator)?;
+    Ok(ws.on_upgrade(stream_notifications))
+}
+
+async fn stream_notifications(mut socket: WebSocket) {
+    let mut rx = crate::notifications::subscribe();
+    loop {
+        let notification = match rx.recv().await {
+            Ok(notification) => notification,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                Notification::trace(format!("{} notifications were missed", missed))
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        };
+        let envelope = envelope(notification.history_entry());
+        let Ok(msg) = serde_json::to_string(&envelope) else { continue };
+        if socket.send(Message::Text(msg)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// The latest notifications, oldest first: 50 unless `limit` says otherwise, sent at or after
+/// `since` and of `severity` or above when those are given.
+#[utoipa::path(
+    get,
+    path = "/api/v1/notifications",
+    tag = "notifications",
+    params(NotificationQuery),
+    responses((status = 200, body = Vec<NotificationEnvelope>)),
+)]
+pub async fn get_notifications(
+    Extension(role): Extension<Role>,
+    Query(query): Query<NotificationQuery>,
+) -> ApiResult<Vec<NotificationEnvelope>> {
+    require_role(role, Role::Operator)?;
+    let limit = query.limit.unwrap_or(50).min(1000);
+    let since = query
+        .since
+        .as_deref()
+        .map(|raw| {
+            chrono::DateTime::parse_from_rfc3339(raw)
+                .map(|at| at.with_timezone(&Utc))
+                .map_err(|_| ApiError::invalid(format!("'since' must be an RFC3339 time, not '{}'", raw)))
+        })
+        .transpose()?;
+    let severities: Vec<&str> = match query.severity.as_deref() {
+        Some(raw) => {
+            let min = Severity::parse(raw).ok_or_else(|| {
+                ApiError::invalid(format!("Unknown severity '{}'; expected debug, info, warn or critical", raw))
+            })?;
+            Severity::ALL.iter().filter(|s| **s >= min).map(Severity::as_str).collect()
+        }
+        None => Vec::new(),
+    };
+    let history = crate::state::shared()
+        .recent_notifications(limit, since, &severities)
+        .map_err(|e| ApiError::internal(format!("Could not read notification history: {}", e)))?;
+    Ok(Json(history.into_iter().map(envelope).collect()))
+}
+
+fn envelope(n: crate::state::StoredNotification) -> NotificationEnvelope {
+    NotificationEnvelope { kind: n.kind, text: n.text, at: n.at, severity: n.severity, source: n.source, task: n.task }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/combat/stream",
+    tag = "events",
+    responses((status = 101, description = "WebSocket upgrade")),
+)]
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    Extension(role): Extension<Role>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, role))
 }
 
-async fn handle_socket(mut socket: WebSocket, state: AppState) {
+async fn handle_socket(mut socket: WebSocket, state: AppState, role: Role) {
     let mut rx = state.event_tx.subscribe();
 
-    while let Ok(event) = rx.recv().await {
-        // Wrap in the same envelope format as Python gateway
-        let envelope = serde_json::json!({
-            "type": event.r#type,
-            "payload": event
-        });
-        
+    while let Some(delivery) = state.bus.recv(&mut rx, "combat-stream").await {
+        let envelope = match delivery {
+            // Wrap in the same envelope format as Python gateway
+            Delivery::Event(event) => serde_json::json!({
+                "type": event.r#type,
+                "payload": event
+            }),
+            // Events were dropped; send a fresh snapshot so the client can rebuild its view.
+            Delivery::Resync { missed } => {
+                let Ok(snapshot) = build_game_state(&state).await else {
+                    continue;
+                };
+                let snapshot = redact_game_state(snapshot, role);
+                state.bus.record_resync();
+                serde_json::json!({
+                    "type": EventType::GameStateUpdate,
+                    "payload": snapshot,
+                    "resync": true,
+                    "missed": missed
+                })
+            }
+        };
+
         if let Ok(msg) = serde_json::to_string(&envelope) {
             if socket.send(Message::Text(msg.into())).await.is_err() {
                 break;
@@ -515,12 +1734,9 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
     }
 }
 
-fn _clean_numeric(val: &str) -> String {
-    if let Some(pos) = val.find("^^") {
-        val[..pos].trim_matches('"').to_string()
-    } else {
-        val.trim_matches('"').to_string()
-    }
+/// Who a gateway caller is in the audit log: tokens carry no name, only a role.
+fn gateway_actor(role: Role) -> String {
+    format!("gateway:{}", role.as_str())
 }
 
 fn execute_command(command: &ControlCommand) -> String {
@@ -561,14 +1777,15 @@ fn parse_system_status(raw: &str) -> SystemStatus {
         "DEGRADED" => SystemStatus::Degraded,
         "OUTAGE" => SystemStatus::Outage,
         "HALTED" => SystemStatus::Halted,
+        "THROTTLED" => SystemStatus::Throttled,
         _ => SystemStatus::Unknown,
     }
 }
 
-fn build_countries(status: &SystemStatus) -> Vec<CountryState> {
+pub(super) fn build_countries(status: &SystemStatus) -> Vec<CountryState> {
     let health = match status {
         SystemStatus::Operational => ServiceHealth::Healthy,
-        SystemStatus::Degraded => ServiceHealth::Degraded,
+        SystemStatus::Degraded | SystemStatus::Throttled => ServiceHealth::Degraded,
         SystemStatus::Halted => ServiceHealth::Halted,
         _ => ServiceHealth::Healthy,
     };
@@ -649,7 +1866,7 @@ fn build_countries(status: &SystemStatus) -> Vec<CountryState> {
     ]
 }
 
-fn build_knowledge_tree() -> Vec<KnowledgeNode> {
+pub(super) fn build_knowledge_tree() -> Vec<KnowledgeNode> {
     vec![
         KnowledgeNode {
             id: "tdd-level-1".to_string(),
@@ -800,7 +2017,7 @@ fn knowledge_node_to_triples(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::server::contracts::{ControlCommandType, LlmProfile};
+    use crate::server::contracts::{ControlCommandType, EventType, LlmProfile};
 
     fn sample_command(command: ControlCommandType) -> ControlCommand {
         ControlCommand {
@@ -923,6 +2140,7 @@ mod tests {
     #[test]
     fn parse_halted_status() {
         assert_eq!(parse_system_status("HALTED"), SystemStatus::Halted);
+        assert_eq!(parse_system_status("THROTTLED"), SystemStatus::Throttled);
     }
 
     #[test]
@@ -966,4 +2184,15 @@ mod tests {
         assert!(triples.iter().any(|(_, p, o)| p.ends_with("documentation") && o == "docs"));
         assert!(triples.iter().any(|(_, p, o)| p.ends_with("prerequisite") && o.ends_with("/n0")));
     }
+
+    #[tokio::test]
+    async fn assigned_missions_pass_the_ingest_checks() {
+        let (store, synapse) = crate::store::MemoryStore::shared();
+        let title = sparql::literal("Fix \"login\"");
+        let triples = mission_triples("http://swarm.os/tasks/1", &title, "http://swarm.os/agents/Coder");
+        synapse.ingest_from(Source::Gateway, triples).await.unwrap();
+        assert!(store.triples().iter().any(|(s, p, o)| s == "http://swarm.os/tasks/1"
+            && p == "http://swarm.os/ontology/assignedTo"
+            && o == "http://swarm.os/agents/Coder"));
+    }
 }
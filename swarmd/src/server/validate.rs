@@ -0,0 +1,432 @@
+//! Validation of request bodies and queries before they reach Synapse. Ids end up inside
+//! `<…>` and titles inside quoted literals, so a stray `>` or newline would otherwise surface
+//! as a failed query and a 502 instead of telling the client which field is wrong.
+//!
+//! Handlers take [`Valid<T>`] instead of `Json<T>`; a rejected body comes back as a 400
+//! `INVALID_REQUEST` problem whose `errors` list every invalid field.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::server::contracts::{
+    ApprovalDecisionRequest, BlueprintStep, BudgetUpdateRequest, FieldError, InitiativeRequest, KnowledgeNodeIngestRequest, MaintenanceRequest,
+    MissionAssignment, ScheduledTaskRequest, SpendReport, TaskCancelRequest, TaskRequest, TaskResetRequest, WebhookSubscriptionRequest,
+};
+use crate::server::problem::{ApiError, ErrorCode};
+use crate::server::routes::{ArtifactUpload, TaskFilter};
+use crate::tasks;
+
+pub const MAX_TITLE_CHARS: usize = 200;
+const MAX_TEXT_CHARS: usize = 500;
+const MAX_KEY_CHARS: usize = 64;
+const MAX_IRI_CHARS: usize = 2048;
+
+/// A request type that knows its own rules.
+pub trait Validate {
+    fn validate(&self, v: &mut Violations);
+}
+
+/// Collects every broken rule of one request, so clients can fix them all at once.
+#[derive(Debug, Default)]
+pub struct Violations(Vec<FieldError>);
+
+impl Violations {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError { field: field.to_string(), message: message.into() });
+    }
+
+    /// Non-blank text of at most `max` characters, on one line. `false` when it was rejected.
+    pub fn text(&mut self, field: &str, value: &str, max: usize) -> bool {
+        if value.trim().is_empty() {
+            self.add(field, "must not be empty");
+            false
+        } else {
+            self.optional_text(field, value, max)
+        }
+    }
+
+    /// Like [`Violations::text`], but blank is fine.
+    pub fn optional_text(&mut self, field: &str, value: &str, max: usize) -> bool {
+        let chars = value.trim().chars().count();
+        if chars > max {
+            self.add(field, format!("must be at most {} characters, got {}", max, chars));
+            false
+        } else if value.chars().any(char::is_control) {
+            self.add(field, "must not contain control characters");
+            false
+        } else {
+            true
+        }
+    }
+
+    /// An id that becomes an IRI path segment: ASCII letters, digits, `-`, `_` and `.`.
+    pub fn key(&mut self, field: &str, value: &str) -> bool {
+        if value.is_empty() {
+            self.add(field, "must not be empty");
+            false
+        } else if value.chars().count() > MAX_KEY_CHARS {
+            self.add(field, format!("must be at most {} characters", MAX_KEY_CHARS));
+            false
+        } else if !is_key(value) {
+            self.add(field, "may only contain ASCII letters, digits, '-', '_' and '.'");
+            false
+        } else {
+            true
+        }
+    }
+
+    /// An absolute IRI such as `http://swarm.os/task/…`.
+    pub fn iri(&mut self, field: &str, value: &str) -> bool {
+        let value = value.trim();
+        let scheme_ok = value.split_once("://").is_some_and(|(scheme, rest)| {
+            !rest.is_empty()
+                && scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        });
+        if !scheme_ok {
+            self.add(field, "must be an absolute IRI such as http://swarm.os/task/…");
+            false
+        } else if value.len() > MAX_IRI_CHARS {
+            self.add(field, format!("must be at most {} bytes", MAX_IRI_CHARS));
+            false
+        } else if let Some(c) = value.chars().find(|c| c.is_whitespace() || c.is_control() || "<>\"{}|\\^`".contains(*c)) {
+            self.add(field, format!("must not contain {:?}", c));
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Either a full IRI or a key the gateway expands under its own namespace.
+    pub fn reference(&mut self, field: &str, value: &str) -> bool {
+        if value.contains("://") {
+            self.iri(field, value)
+        } else {
+            self.key(field, value.trim())
+        }
+    }
+
+    pub fn http_url(&mut self, field: &str, value: &str) -> bool {
+        let value = value.trim();
+        if !(value.starts_with("http://") || value.starts_with("https://")) {
+            self.add(field, "must be an http(s) URL");
+            false
+        } else {
+            self.iri(field, value)
+        }
+    }
+
+    pub fn non_negative(&mut self, field: &str, value: f64) -> bool {
+        if !value.is_finite() || value < 0.0 {
+            self.add(field, "must be a non-negative number");
+            false
+        } else {
+            true
+        }
+    }
+
+    /// One of `allowed`, ignoring case.
+    pub fn one_of(&mut self, field: &str, value: &str, allowed: &[&str]) -> bool {
+        if allowed.iter().any(|a| a.eq_ignore_ascii_case(value.trim())) {
+            true
+        } else {
+            self.add(field, format!("must be one of {}", allowed.join(", ")));
+            false
+        }
+    }
+
+    pub fn into_result(self) -> Result<(), ApiError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::fields(self.0))
+        }
+    }
+}
+
+/// Repository ids and blueprint step keys become IRI path segments.
+pub fn is_key(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Runs the rules of `value`; for extractors other than [`Valid`], such as queries.
+pub fn check<T: Validate>(value: &T) -> Result<(), ApiError> {
+    let mut violations = Violations::default();
+    value.validate(&mut violations);
+    violations.into_result()
+}
+
+/// A JSON body that deserialized and passed its [`Validate`] rules.
+#[derive(Debug, Clone)]
+pub struct Valid<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Valid<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, ApiError> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| ApiError::new(ErrorCode::from_status(rejection.status()), rejection.body_text()))?;
+        check(&value)?;
+        Ok(Valid(value))
+    }
+}
+
+impl Validate for TaskRequest {
+    fn validate(&self, v: &mut Violations) {
+        v.text("title", &self.title, MAX_TITLE_CHARS);
+        if let Some(repo) = self.repo_id.as_deref().filter(|r| !r.trim().is_empty()) {
+            v.reference("repo_id", repo);
+        }
+        for (i, dependency) in self.depends_on.iter().enumerate() {
+            v.iri(&format!("depends_on[{}]", i), dependency);
+        }
+    }
+}
+
+impl Validate for ScheduledTaskRequest {
+    fn validate(&self, v: &mut Violations) {
+        if let Err(message) = crate::schedules::parse_cron(&self.cron) {
+            v.add("cron", message);
+        }
+        v.text("title", &self.title, MAX_TITLE_CHARS);
+        if let Some(repo) = self.repo_id.as_deref().filter(|r| !r.trim().is_empty()) {
+            v.reference("repo_id", repo);
+        }
+        for (i, class) in self.requires_classes.iter().enumerate() {
+            v.text(&format!("requires_classes[{i}]"), class, MAX_KEY_CHARS);
+        }
+        for (i, skill) in self.requires_skills.iter().enumerate() {
+            v.text(&format!("requires_skills[{i}]"), skill, MAX_KEY_CHARS);
+        }
+    }
+}
+
+impl Validate for TaskResetRequest {
+    fn validate(&self, v: &mut Violations) {
+        if self.task_id.trim().is_empty() {
+            v.add("task_id", "must not be empty");
+        } else {
+            v.iri("task_id", &self.task_id);
+        }
+        if let Some(by) = &self.reset_by {
+            v.optional_text("reset_by", by, MAX_KEY_CHARS * 2);
+        }
+    }
+}
+
+impl Validate for ApprovalDecisionRequest {
+    fn validate(&self, v: &mut Violations) {
+        v.text("decided_by", &self.decided_by, MAX_KEY_CHARS * 2);
+    }
+}
+
+impl Validate for TaskCancelRequest {
+    fn validate(&self, v: &mut Violations) {
+        if let Some(by) = &self.cancelled_by {
+            v.optional_text("cancelled_by", by, MAX_KEY_CHARS * 2);
+        }
+    }
+}
+
+impl Validate for SpendReport {
+    fn validate(&self, v: &mut Violations) {
+        v.non_negative("amount", self.amount);
+        if !self.currency.eq_ignore_ascii_case("USD") {
+            v.add("currency", format!("unsupported currency '{}'; spend must be reported in USD", self.currency));
+        }
+        if let Some(agent) = self.agent_id.as_deref().filter(|a| !a.trim().is_empty()) {
+            v.reference("agent_id", agent);
+        }
+        if let Some(task) = self.task_id.as_deref().filter(|t| !t.trim().is_empty()) {
+            v.reference("task_id", task);
+        }
+        if let Some(repo) = self.repo_id.as_deref().filter(|r| !r.trim().is_empty()) {
+            v.reference("repo_id", repo);
+        }
+        if let Some(model) = self.model.as_deref() {
+            v.optional_text("model", model, MAX_KEY_CHARS * 2);
+        }
+    }
+}
+
+impl Validate for BudgetUpdateRequest {
+    fn validate(&self, v: &mut Violations) {
+        v.non_negative("max", self.max);
+    }
+}
+
+impl Validate for MissionAssignment {
+    fn validate(&self, v: &mut Violations) {
+        v.text("task", &self.task, MAX_TITLE_CHARS);
+        // The id is written as the task's assignee as is, so it must not need trimming either.
+        if !self.agent_id.is_empty() && v.iri("agent_id", &self.agent_id) && !crate::sparql::is_iri(&self.agent_id) {
+            v.add("agent_id", "must not start or end with whitespace");
+        }
+        if !self.repo_id.trim().is_empty() {
+            v.reference("repo_id", &self.repo_id);
+        }
+    }
+}
+
+impl Validate for KnowledgeNodeIngestRequest {
+    fn validate(&self, v: &mut Violations) {
+        v.key("node_id", self.node_id.trim());
+        v.text("domain", &self.domain, MAX_KEY_CHARS);
+        v.text("name", &self.name, MAX_TITLE_CHARS);
+        v.text("capability", &self.capability, MAX_TEXT_CHARS);
+        if !(0..=100).contains(&self.level) {
+            v.add("level", "must be between 0 and 100");
+        }
+        v.non_negative("budget_cost", self.budget_cost);
+        if self.time_cost_hours < 0 {
+            v.add("time_cost_hours", "must not be negative");
+        }
+        for (i, prerequisite) in self.prerequisites.iter().enumerate() {
+            v.key(&format!("prerequisites[{i}]"), prerequisite);
+        }
+        v.optional_text("source_type", &self.source_type, MAX_KEY_CHARS);
+        v.optional_text("source_ref", &self.source_ref, MAX_TEXT_CHARS);
+    }
+}
+
+impl Validate for WebhookSubscriptionRequest {
+    fn validate(&self, v: &mut Violations) {
+        v.http_url("url", &self.url);
+    }
+}
+
+impl Validate for MaintenanceRequest {
+    fn validate(&self, v: &mut Violations) {
+        if let Some(reason) = &self.reason {
+            v.optional_text("reason", reason, MAX_TEXT_CHARS);
+        }
+    }
+}
+
+impl Validate for InitiativeRequest {
+    fn validate(&self, v: &mut Violations) {
+        v.text("title", &self.title, MAX_TITLE_CHARS);
+        if self.repositories.is_empty() {
+            v.add("repositories", "at least one repository is required");
+        }
+        for (i, repo) in self.repositories.iter().enumerate() {
+            v.key(&format!("repositories[{i}]"), repo);
+        }
+        if let Some(lead) = &self.lead_repository {
+            if !self.repositories.contains(lead) {
+                v.add("lead_repository", format!("'{}' is not one of the repositories", lead));
+            }
+        }
+        for (i, BlueprintStep { key, title, .. }) in self.steps.iter().enumerate() {
+            v.key(&format!("steps[{i}].key"), key);
+            v.text(&format!("steps[{i}].title"), title, MAX_TITLE_CHARS);
+        }
+    }
+}
+
+impl Validate for TaskFilter {
+    fn validate(&self, v: &mut Violations) {
+        if let Some(state) = self.state.as_deref().filter(|s| !s.is_empty()) {
+            v.one_of("state", state, tasks::KNOWN_STATES);
+        }
+    }
+}
+
+impl Validate for ArtifactUpload {
+    fn validate(&self, v: &mut Violations) {
+        if v.text("name", &self.name, MAX_TITLE_CHARS) && self.name.contains(['/', '\\']) {
+            v.add("name", "must be a file name, not a path");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors<T: Validate>(value: &T) -> Vec<FieldError> {
+        let mut violations = Violations::default();
+        value.validate(&mut violations);
+        violations.0
+    }
+
+    fn fields<T: Validate>(value: &T) -> Vec<String> {
+        errors(value).into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn every_invalid_field_is_reported() {
+        let task = TaskRequest {
+            title: "x".repeat(MAX_TITLE_CHARS + 1),
+            priority: None,
+            repo_id: Some("api gateway".into()),
+            depends_on: vec!["http://swarm.os/task/1".into(), "task 2".into()],
+            requires_approval: false,
+        };
+        assert_eq!(fields(&task), ["title", "repo_id", "depends_on[1]"]);
+        let task = TaskRequest {
+            title: "Fix login".into(),
+            priority: Some(3),
+            repo_id: Some("http://swarm.os/repository/api".into()),
+            depends_on: Vec::new(),
+            requires_approval: true,
+        };
+        assert!(errors(&task).is_empty());
+
+        let reset = TaskResetRequest { task_id: "http://swarm.os/task/<1>".into(), reset_by: None };
+        assert_eq!(errors(&reset)[0].message, "must not contain '<'");
+        let spend = SpendReport {
+            amount: f64::NAN,
+            currency: "EUR".into(),
+            agent_id: Some("Coder".into()),
+            task_id: Some("a/b".into()),
+            repo_id: Some("api gateway".into()),
+            prompt_tokens: Some(1200),
+            completion_tokens: None,
+            model: Some("m".repeat(200)),
+        };
+        assert_eq!(fields(&spend), ["amount", "currency", "task_id", "repo_id", "model"]);
+
+        let initiative = InitiativeRequest {
+            title: "SSO\neverywhere".into(),
+            repositories: vec!["web".into(), "".into()],
+            steps: vec![BlueprintStep { key: "de sign".into(), title: "Design".into(), depends_on: vec![] }],
+            lead_repository: Some("api".into()),
+        };
+        assert_eq!(fields(&initiative), ["title", "repositories[1]", "lead_repository", "steps[0].key"]);
+
+        let mission = |agent_id: &str| MissionAssignment { agent_id: agent_id.into(), repo_id: "api".into(), task: "Fix login".into() };
+        assert!(errors(&mission("http://swarm.os/agents/Coder")).is_empty());
+        assert!(errors(&mission("")).is_empty());
+        assert_eq!(fields(&mission(" http://swarm.os/agents/Coder")), ["agent_id"]);
+        assert_eq!(fields(&mission("Coder")), ["agent_id"]);
+
+        assert!(check(&TaskFilter { state: Some("awaiting_merge".into()) }).is_ok());
+        let err = check(&TaskFilter { state: Some("SLEEPING".into()) }).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidRequest);
+        assert_eq!(err.errors.len(), 1);
+        assert!(err.detail.starts_with("state: must be one of REQUIREMENTS"));
+    }
+
+    #[test]
+    fn urls_and_iris_reject_what_would_break_a_query() {
+        let mut v = Violations::default();
+        assert!(v.http_url("url", " https://viz.example.com/hooks?team=a "));
+        assert!(!v.http_url("url", "ftp://viz.example.com"));
+        assert!(!v.iri("id", "http://swarm.os/task/a b"));
+        assert!(!v.iri("id", "://nothing"));
+        assert!(v.reference("id", "Coder"));
+        assert!(!v.reference("id", "Coder\"}"));
+        assert_eq!(v.0.len(), 4);
+    }
+}
@@ -0,0 +1,351 @@
+//! Safe construction of SPARQL and triples from outside text. Card names, chat messages and
+//! API fields end up inside quoted literals and `<…>` IRIs; written as they come, a quote,
+//! newline or `}` in them would end the literal early and change the query or the data.
+//!
+//! Literals go through [`literal`], which escapes them the way SPARQL and N-Triples read them,
+//! and come back through [`lexical`] (via [`crate::tasks::literal_value`]). Queries that take
+//! values use [`Query`] with `$name` placeholders instead of `format!`. Every write through
+//! `ingest_from` is checked with [`check_triple`] first, so a malformed term is refused rather
+//! than stored.
+//!
+//! Results are read with [`Synapse::query_as`], which deserializes each row into a struct
+//! whose fields are named after the query's variables.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use anyhow::Context;
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::store::Synapse;
+
+/// Quoted string literal for `text`, with `\`, `"` and line breaks escaped.
+pub fn literal(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The text an escaped literal body stands for; the input itself when it has no escapes.
+pub fn unescape(body: &str) -> Cow<'_, str> {
+    if !body.contains('\\') {
+        return Cow::Borrowed(body);
+    }
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Lexical value of a literal as Synapse returns it: the text between the quotes, unescaped,
+/// without datatype or language tag. Anything not starting with a quote is taken as is.
+pub fn lexical(raw: &str) -> Cow<'_, str> {
+    let Some(body) = raw.strip_prefix('"') else {
+        return Cow::Borrowed(raw.split("^^").next().unwrap_or(raw).trim_end_matches('"'));
+    };
+    let mut escaped = false;
+    let end = body
+        .char_indices()
+        .find(|&(_, c)| {
+            let closes = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            closes
+        })
+        .map_or(body.len(), |(i, _)| i);
+    unescape(&body[..end])
+}
+
+/// Whether `value` can sit between `<` and `>` as is: absolute, with none of the characters
+/// the IRI grammar excludes.
+pub fn is_iri(value: &str) -> bool {
+    value.contains(':')
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\'))
+}
+
+/// Whether `term` is a literal that cannot run past its closing quote: escapes are complete,
+/// there is no raw line break, and only a datatype or language tag follows.
+fn is_literal(term: &str) -> bool {
+    let Some(body) = term.strip_prefix('"') else { return false };
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if !matches!(chars.next(), Some((_, 't' | 'b' | 'n' | 'r' | 'f' | '"' | '\'' | '\\'))) => return false,
+            '\n' | '\r' => return false,
+            '"' => {
+                let rest = &body[i + 1..];
+                return rest.is_empty()
+                    || rest.strip_prefix("^^<").and_then(|r| r.strip_suffix('>')).is_some_and(is_iri)
+                    || rest.strip_prefix('@').is_some_and(|tag| !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Refuses a triple whose subject or predicate is not a clean IRI, or whose object is neither
+/// a clean IRI nor a well-formed literal.
+pub fn check_triple(subject: &str, predicate: &str, object: &str) -> anyhow::Result<()> {
+    for (position, term) in [("subject", subject), ("predicate", predicate)] {
+        if !is_iri(term) {
+            anyhow::bail!("refusing to write a triple whose {} is not an IRI: {:?}", position, term);
+        }
+    }
+    if !(is_iri(object) || is_literal(object)) {
+        anyhow::bail!("refusing to write a malformed object for <{}> <{}>: {:?}", subject, predicate, object);
+    }
+    Ok(())
+}
+
+/// SPARQL Update removing every triple that matches one of `patterns`, `(subject, predicate,
+/// object)` with no object standing for any. Fails on a term [`check_triple`] would refuse.
+pub fn retraction(patterns: &[(&str, &str, Option<&str>)]) -> anyhow::Result<String> {
+    let mut updates = Vec::with_capacity(patterns.len());
+    for (s, p, o) in patterns {
+        check_triple(s, p, o.unwrap_or("\"\""))?;
+        updates.push(match o {
+            Some(o) if is_iri(o) => format!("DELETE DATA {{ <{s}> <{p}> <{o}> }}"),
+            Some(o) => format!("DELETE DATA {{ <{s}> <{p}> {o} }}"),
+            None => format!("DELETE WHERE {{ <{s}> <{p}> ?o }}"),
+        });
+    }
+    Ok(updates.join(" ;\n"))
+}
+
+/// A query with `$name` placeholders, each bound to an IRI or a text literal. Placeholders
+/// that are not bound stay as they are, which SPARQL reads as variables.
+#[derive(Debug, Clone)]
+pub struct Query {
+    template: String,
+    bindings: Vec<(String, Term)>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Iri(String),
+    Literal(String),
+}
+
+impl Query {
+    pub fn new(template: impl Into<String>) -> Self {
+        Query { template: template.into(), bindings: Vec::new() }
+    }
+
+    /// Binds `$name` to `<value>`; [`Query::build`] fails if it is not a clean IRI.
+    pub fn iri(mut self, name: &str, value: &str) -> Self {
+        self.bindings.push((name.to_string(), Term::Iri(value.to_string())));
+        self
+    }
+
+    /// Binds `$name` to a string literal holding `text`.
+    pub fn text(mut self, name: &str, text: &str) -> Self {
+        self.bindings.push((name.to_string(), Term::Literal(text.to_string())));
+        self
+    }
+
+    pub fn build(&self) -> anyhow::Result<String> {
+        for (name, term) in &self.bindings {
+            if let Term::Iri(value) = term {
+                if !is_iri(value) {
+                    anyhow::bail!("${} is not a valid IRI: {:?}", name, value);
+                }
+            }
+        }
+
+        let mut out = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(at) = rest.find('$') {
+            out.push_str(&rest[..at]);
+            let after = &rest[at + 1..];
+            let len = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            let name = &after[..len];
+            match self.bindings.iter().rev().find(|(bound, _)| bound == name) {
+                Some((_, Term::Iri(value))) => {
+                    out.push('<');
+                    out.push_str(value);
+                    out.push('>');
+                }
+                Some((_, Term::Literal(text))) => out.push_str(&literal(text)),
+                None => {
+                    out.push('$');
+                    out.push_str(name);
+                }
+            }
+            rest = &after[len..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+/// The rows of a SELECT result as `T`. Variable names lose their `?`, IRIs their `<…>` and
+/// literals their quotes, escapes and datatype, so a field named after a variable gets plain
+/// text. Rows that do not fit `T`, such as one missing a required variable, are skipped.
+pub fn rows<T: DeserializeOwned>(results_json: &str) -> anyhow::Result<Vec<T>> {
+    let raw: Vec<Map<String, Value>> = serde_json::from_str(results_json).context("malformed SPARQL results")?;
+    Ok(raw
+        .into_iter()
+        .filter_map(|row| {
+            serde_json::from_value(Value::Object(normalize(row)))
+                .map_err(|e| debug!("Skipping a SPARQL result row: {}", e))
+                .ok()
+        })
+        .collect())
+}
+
+fn normalize(row: Map<String, Value>) -> Map<String, Value> {
+    row.into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(raw) => Value::String(term_value(&raw)),
+                other => other,
+            };
+            (name.trim_start_matches('?').to_string(), value)
+        })
+        .collect()
+}
+
+/// The IRI of `<…>`, or the lexical value of a literal.
+fn term_value(raw: &str) -> String {
+    match raw.strip_prefix('<').and_then(|r| r.strip_suffix('>')) {
+        Some(iri) => iri.to_string(),
+        None => lexical(raw).into_owned(),
+    }
+}
+
+/// `deserialize_with` for numbers, dates and other values parsed from a literal's text: `None`
+/// when the variable is unbound or the text does not parse. Use with `#[serde(default)]`.
+pub fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(text)) => text.trim().parse().ok(),
+        Some(Value::Number(n)) => n.to_string().parse().ok(),
+        _ => None,
+    })
+}
+
+impl Synapse {
+    /// Runs `query` with its values bound.
+    #[tracing::instrument(name = "synapse.query", skip_all)]
+    pub async fn select(&self, query: &Query) -> anyhow::Result<String> {
+        self.query(&query.build()?).await
+    }
+
+    /// Runs `query` and deserializes its rows; see [`rows`].
+    #[tracing::instrument(name = "synapse.query", skip_all)]
+    pub async fn query_as<T: DeserializeOwned>(&self, query: &str) -> anyhow::Result<Vec<T>> {
+        rows(&self.query(query).await?)
+    }
+
+    /// [`Synapse::query_as`] for a query with bound values.
+    pub async fn select_as<T: DeserializeOwned>(&self, query: &Query) -> anyhow::Result<Vec<T>> {
+        rows(&self.select(query).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retractions_delete_one_value_or_every_value() {
+        let update = retraction(&[
+            ("http://swarm.os/agent/A", "http://swarm.os/ontology/status", None),
+            ("http://swarm.os/task/1", "http://swarm.os/ontology/assignedTo", Some("http://swarm.os/agent/A")),
+            ("http://swarm.os/task/1", "http://swarm.os/ontology/title", Some("\"Old\"")),
+        ])
+        .unwrap();
+        assert_eq!(
+            update,
+            "DELETE WHERE { <http://swarm.os/agent/A> <http://swarm.os/ontology/status> ?o } ;\n\
+             DELETE DATA { <http://swarm.os/task/1> <http://swarm.os/ontology/assignedTo> <http://swarm.os/agent/A> } ;\n\
+             DELETE DATA { <http://swarm.os/task/1> <http://swarm.os/ontology/title> \"Old\" }"
+        );
+        assert!(retraction(&[("http://swarm.os/agent/A> } ; DROP ALL ; {", "http://swarm.os/ontology/status", None)]).is_err());
+    }
+
+    #[test]
+    fn literals_escape_and_round_trip_hostile_text() {
+        let hostile = "Fix \"login\" }\n DROP ALL ; \\ tab\there";
+        let lit = literal(hostile);
+        assert_eq!(lit, r#""Fix \"login\" }\n DROP ALL ; \\ tab\there""#);
+        assert!(is_literal(&lit));
+        assert_eq!(unescape(&lit[1..lit.len() - 1]), hostile);
+        assert!(matches!(unescape("plain"), Cow::Borrowed("plain")));
+        assert_eq!(lexical(&lit), hostile);
+        assert_eq!(lexical("\"say \\\"hi\\\"\"@en"), "say \"hi\"");
+        assert_eq!(lexical("\"2\"^^<http://www.w3.org/2001/XMLSchema#integer>"), "2");
+        assert_eq!(lexical("PROCESSING"), "PROCESSING");
+
+        assert!(is_literal("\"3\"^^<http://www.w3.org/2001/XMLSchema#integer>"));
+        assert!(is_literal("\"hola\"@es"));
+        assert!(!is_literal("\"Working on: \"x\"\""));
+        assert!(!is_literal("\"line\nbreak\""));
+        assert!(!is_literal("\"unterminated"));
+        assert!(check_triple("http://swarm.os/task/1", "http://swarm.os/ontology/title", "\"a\" . <x> <y> <z>").is_err());
+        assert!(check_triple("http://swarm.os/task/1> <p", "http://swarm.os/ontology/title", "\"a\"").is_err());
+        assert!(check_triple("http://swarm.os/task/1", "http://swarm.os/ontology/title", &literal(hostile)).is_ok());
+    }
+
+    #[test]
+    fn rows_deserialize_whatever_the_binding_style() {
+        #[derive(Debug, serde::Deserialize, PartialEq)]
+        struct Row {
+            task: String,
+            title: String,
+            #[serde(default, deserialize_with = "parsed")]
+            priority: Option<i64>,
+        }
+        let json = serde_json::json!([
+            {"?task": "<http://swarm.os/task/1>", "?title": "\"Fix \\\"login\\\"\"", "?priority": "\"2\"^^<http://www.w3.org/2001/XMLSchema#integer>"},
+            {"task": "<http://swarm.os/task/2>", "title": "\"Docs\"", "priority": "\"soon\""},
+            {"title": "\"No task\""},
+        ]);
+        let rows: Vec<Row> = rows(&json.to_string()).unwrap();
+        assert_eq!(rows, [
+            Row { task: "http://swarm.os/task/1".into(), title: "Fix \"login\"".into(), priority: Some(2) },
+            Row { task: "http://swarm.os/task/2".into(), title: "Docs".into(), priority: None },
+        ]);
+        assert!(super::rows::<Row>("not json").is_err());
+    }
+
+    #[test]
+    fn queries_bind_values_instead_of_splicing_them() {
+        let query = Query::new("SELECT ?t WHERE { $task swarm:title ?t . FILTER(?t != $title) } # $tasks $other")
+            .iri("task", "http://swarm.os/task/1")
+            .text("title", "x\" } DELETE { ?s ?p ?o");
+        assert_eq!(
+            query.build().unwrap(),
+            r#"SELECT ?t WHERE { <http://swarm.os/task/1> swarm:title ?t . FILTER(?t != "x\" } DELETE { ?s ?p ?o") } # $tasks $other"#
+        );
+        assert!(Query::new("ASK { $task ?p ?o }").iri("task", "http://x/1> } DROP ALL { <a").build().is_err());
+    }
+}
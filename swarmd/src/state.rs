@@ -0,0 +1,585 @@
+//! Worker bookkeeping in a local SQLite file (`SWARM_STATE_PATH`): Telegram update offsets,
+//! Trello cards and GitHub issues already ingested, leases on the tasks being run, escalated
+//! tasks reset but not run yet, the notification history,
+//! task drafts waiting for confirmation in Telegram, the writes waiting for Synapse to come back
+//! and snapshots of the game state. None of it is knowledge about the swarm, so it stays out of Synapse, whose
+//! append-only store would otherwise keep every offset the Telegram poller ever saw.
+//!
+//! Losing the file is harmless: offsets, ingested cards and issues are seeded again from what older
+//! versions wrote to Synapse, and in-flight runs are the only thing a lease protects.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Notifications kept for `/api/v1/notifications`, enough for a busy night; older ones are
+/// dropped.
+const MAX_NOTIFICATIONS: i64 = 10_000;
+/// Task drafts nobody confirmed within a day are dropped.
+const DRAFT_TTL_HOURS: i64 = 24;
+/// Game-state snapshots older than two weeks are dropped.
+const GAME_STATE_RETENTION_DAYS: i64 = 14;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS offsets (
+        name  TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS seen (
+        scope TEXT NOT NULL,
+        key   TEXT NOT NULL,
+        at    TEXT NOT NULL,
+        PRIMARY KEY (scope, key)
+    );
+    CREATE TABLE IF NOT EXISTS leases (
+        task       TEXT PRIMARY KEY,
+        title      TEXT NOT NULL,
+        agent      TEXT NOT NULL,
+        attempt    INTEGER NOT NULL,
+        owner      TEXT NOT NULL,
+        expires_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS resets (
+        task         TEXT PRIMARY KEY,
+        title        TEXT NOT NULL,
+        timeout_secs INTEGER,
+        owner        TEXT NOT NULL DEFAULT '',
+        at           TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS notifications (
+        id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        at       TEXT NOT NULL,
+        kind     TEXT NOT NULL,
+        text     TEXT NOT NULL,
+        severity TEXT NOT NULL DEFAULT 'info',
+        source   TEXT,
+        task     TEXT
+    );
+    CREATE TABLE IF NOT EXISTS task_drafts (
+        id          TEXT PRIMARY KEY,
+        chat        TEXT NOT NULL,
+        author      TEXT NOT NULL,
+        title       TEXT NOT NULL,
+        description TEXT,
+        at          TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS pending_ingests (
+        id      INTEGER PRIMARY KEY AUTOINCREMENT,
+        at      TEXT NOT NULL,
+        triples TEXT NOT NULL,
+        kind    TEXT NOT NULL DEFAULT 'ingest'
+    );
+    CREATE TABLE IF NOT EXISTS game_states (
+        id    INTEGER PRIMARY KEY AUTOINCREMENT,
+        at    TEXT NOT NULL,
+        state TEXT NOT NULL
+    );
+";
+
+/// Columns added to tables after their first release, for files created before them.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("notifications", "severity", "TEXT NOT NULL DEFAULT 'info'"),
+    ("notifications", "source", "TEXT"),
+    ("notifications", "task", "TEXT"),
+    ("pending_ingests", "kind", "TEXT NOT NULL DEFAULT 'ingest'"),
+];
+
+/// A task run held by an agent until it finishes or its timeout passes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    pub task: String,
+    pub title: String,
+    pub agent: String,
+    pub attempt: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// An escalated task a human reset, until the agency runs it again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedReset {
+    pub task: String,
+    pub title: String,
+    /// The task's own `swarm:timeoutSeconds`, if it has one.
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredNotification {
+    /// RFC3339, in UTC.
+    pub at: String,
+    pub kind: String,
+    pub severity: String,
+    pub source: Option<String>,
+    pub task: Option<String>,
+    pub text: String,
+}
+
+/// A task proposed from a chat message, until someone confirms or discards it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskDraft {
+    pub id: String,
+    pub chat: String,
+    pub author: String,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/// The game state as JSON, and when it was taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredGameState {
+    /// RFC3339, in UTC, to the second.
+    pub at: String,
+    pub state: String,
+}
+
+/// A write Synapse could not take, as it was attempted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingWrite {
+    pub id: i64,
+    pub op: WriteOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteOp {
+    Ingest(Vec<(String, String, String)>),
+    /// `(subject, predicate, object)` patterns, no object standing for any.
+    Retract(Vec<(String, String, Option<String>)>),
+}
+
+pub struct StateStore {
+    conn: Mutex<Connection>,
+    /// Written into every lease this process takes, so leases left by an earlier process can
+    /// be told apart from runs still going after a worker restart.
+    owner: String,
+}
+
+static STORE: OnceLock<StateStore> = OnceLock::new();
+
+/// Opens the state file once at startup, creating it and its directory if needed.
+pub fn init(path: &str) -> anyhow::Result<&'static StateStore> {
+    if let Some(parent) = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let store = StateStore::new(Connection::open(path)?)?;
+    Ok(STORE.get_or_init(|| store))
+}
+
+/// The process's state store; an in-memory one until [`init`] runs.
+pub fn shared() -> &'static StateStore {
+    STORE.get_or_init(|| {
+        StateStore::new(Connection::open_in_memory().expect("in-memory SQLite"))
+            .expect("in-memory SQLite schema")
+    })
+}
+
+/// A store of its own, for tests that must not share [`shared`].
+#[cfg(test)]
+pub fn scratch() -> &'static StateStore {
+    Box::leak(Box::new(StateStore::new(Connection::open_in_memory().unwrap()).unwrap()))
+}
+
+impl StateStore {
+    fn new(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        for (table, column, definition) in ADDED_COLUMNS {
+            let exists = conn
+                .prepare(&format!("SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?1"))?
+                .exists([column])?;
+            if !exists {
+                conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"))?;
+            }
+        }
+        Ok(Self { conn: Mutex::new(conn), owner: uuid::Uuid::new_v4().to_string() })
+    }
+
+    pub fn offset(&self, name: &str) -> rusqlite::Result<Option<i64>> {
+        self.lock()
+            .query_row("SELECT value FROM offsets WHERE name = ?1", [name], |row| row.get(0))
+            .optional()
+    }
+
+    pub fn set_offset(&self, name: &str, value: i64) -> rusqlite::Result<()> {
+        self.lock().execute(
+            "INSERT INTO offsets (name, value) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET value = excluded.value",
+            params![name, value],
+        )?;
+        Ok(())
+    }
+
+    /// Every key marked in `scope`.
+    pub fn seen(&self, scope: &str) -> rusqlite::Result<HashSet<String>> {
+        let conn = self.lock();
+        let mut stmt = conn.prepare("SELECT key FROM seen WHERE scope = ?1")?;
+        let keys = stmt.query_map([scope], |row| row.get(0))?.collect();
+        keys
+    }
+
+    pub fn mark_seen<'a>(&self, scope: &str, keys: impl IntoIterator<Item = &'a str>) -> rusqlite::Result<()> {
+        let mut conn = self.lock();
+        let tx = conn.transaction()?;
+        let at = Utc::now().to_rfc3339();
+        for key in keys {
+            tx.execute("INSERT OR IGNORE INTO seen (scope, key, at) VALUES (?1, ?2, ?3)", params![scope, key, at])?;
+        }
+        tx.commit()
+    }
+
+    /// Takes the lease on `task` for `ttl`. Fails when another live lease holds it.
+    pub fn acquire_lease(&self, task: &str, title: &str, agent: &str, attempt: u32, ttl: Duration) -> rusqlite::Result<bool> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or_default();
+        let changed = self.lock().execute(
+            "INSERT INTO leases (task, title, agent, attempt, owner, expires_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(task) DO UPDATE SET title = excluded.title, agent = excluded.agent, attempt = excluded.attempt,
+                 owner = excluded.owner, expires_at = excluded.expires_at
+             WHERE leases.expires_at <= ?7",
+            params![task, title, agent, attempt, self.owner, expires_at.to_rfc3339(), now.to_rfc3339()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn release_lease(&self, task: &str) -> rusqlite::Result<()> {
+        self.lock().execute("DELETE FROM leases WHERE task = ?1", [task])?;
+        Ok(())
+    }
+
+    /// Removes and returns the leases taken by earlier processes: runs a crash or restart
+    /// cut short, whatever their expiry.
+    pub fn take_orphaned_leases(&self) -> rusqlite::Result<Vec<Lease>> {
+        let mut conn = self.lock();
+        let tx = conn.transaction()?;
+        let leases = {
+            let mut stmt = tx.prepare("SELECT task, title, agent, attempt, expires_at FROM leases WHERE owner != ?1")?;
+            let rows = stmt.query_map([&self.owner], |row| {
+                let expires_at: String = row.get(4)?;
+                Ok(Lease {
+                    task: row.get(0)?,
+                    title: row.get(1)?,
+                    agent: row.get(2)?,
+                    attempt: row.get(3)?,
+                    expires_at: DateTime::parse_from_rfc3339(&expires_at).map(|at| at.with_timezone(&Utc)).unwrap_or_default(),
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        tx.execute("DELETE FROM leases WHERE owner != ?1", [&self.owner])?;
+        tx.commit()?;
+        Ok(leases)
+    }
+
+    /// Keeps `reset` until the agency launches its run; a newer reset of the same task replaces it.
+    pub fn queue_reset(&self, reset: &QueuedReset) -> rusqlite::Result<()> {
+        self.lock().execute(
+            "INSERT OR REPLACE INTO resets (task, title, timeout_secs, owner, at) VALUES (?1, ?2, ?3, '', ?4)",
+            params![reset.task, reset.title, reset.timeout_secs, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// The queued resets this process has not picked up yet, oldest first: new ones, and those
+    /// an earlier process picked up but never launched. They stay queued until [`finish_reset`].
+    ///
+    /// [`finish_reset`]: StateStore::finish_reset
+    pub fn claim_resets(&self) -> rusqlite::Result<Vec<QueuedReset>> {
+        let mut conn = self.lock();
+        let tx = conn.transaction()?;
+        let resets = {
+            let mut stmt = tx.prepare("SELECT task, title, timeout_secs FROM resets WHERE owner != ?1 ORDER BY at")?;
+            let rows = stmt.query_map([&self.owner], |row| {
+                Ok(QueuedReset { task: row.get(0)?, title: row.get(1)?, timeout_secs: row.get(2)? })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        tx.execute("UPDATE resets SET owner = ?1", [&self.owner])?;
+        tx.commit()?;
+        Ok(resets)
+    }
+
+    /// Drops the queued reset of `task` once its run is launched.
+    pub fn finish_reset(&self, task: &str) -> rusqlite::Result<()> {
+        self.lock().execute("DELETE FROM resets WHERE task = ?1", [task])?;
+        Ok(())
+    }
+
+    /// Keeps `triples` for a later replay, behind any write already waiting.
+    pub fn queue_ingest(&self, triples: &[(&str, &str, &str)]) -> anyhow::Result<()> {
+        self.queue_write("ingest", serde_json::to_string(triples)?)
+    }
+
+    /// Keeps a retraction of `patterns` for a later replay, behind any write already waiting.
+    pub fn queue_retraction(&self, patterns: &[(&str, &str, Option<&str>)]) -> anyhow::Result<()> {
+        self.queue_write("retract", serde_json::to_string(patterns)?)
+    }
+
+    fn queue_write(&self, kind: &str, triples: String) -> anyhow::Result<()> {
+        self.lock().execute(
+            "INSERT INTO pending_ingests (at, triples, kind) VALUES (?1, ?2, ?3)",
+            params![Utc::now().to_rfc3339(), triples, kind],
+        )?;
+        Ok(())
+    }
+
+    /// The oldest write waiting, if any.
+    pub fn next_pending_write(&self) -> anyhow::Result<Option<PendingWrite>> {
+        let row: Option<(i64, String, String)> = self
+            .lock()
+            .query_row("SELECT id, triples, kind FROM pending_ingests ORDER BY id LIMIT 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .optional()?;
+        let Some((id, triples, kind)) = row else { return Ok(None) };
+        let op = match kind.as_str() {
+            "retract" => WriteOp::Retract(serde_json::from_str(&triples)?),
+            _ => WriteOp::Ingest(serde_json::from_str(&triples)?),
+        };
+        Ok(Some(PendingWrite { id, op }))
+    }
+
+    pub fn pending_write_count(&self) -> rusqlite::Result<u64> {
+        self.lock().query_row("SELECT COUNT(*) FROM pending_ingests", [], |row| row.get(0))
+    }
+
+    pub fn remove_pending_write(&self, id: i64) -> rusqlite::Result<()> {
+        self.lock().execute("DELETE FROM pending_ingests WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn record_notification(&self, n: &StoredNotification) -> rusqlite::Result<()> {
+        let conn = self.lock();
+        conn.execute(
+            "INSERT INTO notifications (at, kind, severity, source, task, text) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![n.at, n.kind, n.severity, n.source, n.task, n.text],
+        )?;
+        conn.execute(
+            "DELETE FROM notifications WHERE id <= (SELECT MAX(id) FROM notifications) - ?1",
+            [MAX_NOTIFICATIONS],
+        )?;
+        Ok(())
+    }
+
+    /// The latest `limit` notifications sent at or after `since` with one of `severities`
+    /// (any, when empty), oldest first.
+    pub fn recent_notifications(
+        &self,
+        limit: usize,
+        since: Option<DateTime<Utc>>,
+        severities: &[&str],
+    ) -> rusqlite::Result<Vec<StoredNotification>> {
+        let conn = self.lock();
+        let mut stmt = conn.prepare(
+            "SELECT at, kind, severity, source, task, text FROM (
+                 SELECT * FROM notifications
+                 WHERE at >= ?1 AND (?2 = '' OR instr(?2, ',' || severity || ',') > 0)
+                 ORDER BY id DESC LIMIT ?3
+             ) ORDER BY id",
+        )?;
+        let since = since.map(|at| at.to_rfc3339()).unwrap_or_default();
+        let severities = if severities.is_empty() { String::new() } else { format!(",{},", severities.join(",")) };
+        let rows = stmt.query_map(params![since, severities, limit as i64], |row| {
+            Ok(StoredNotification {
+                at: row.get(0)?,
+                kind: row.get(1)?,
+                severity: row.get(2)?,
+                source: row.get(3)?,
+                task: row.get(4)?,
+                text: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Keeps the game state `state` (JSON) taken `at`, unless it is the same as the last one kept,
+    /// and drops snapshots past their retention. True when it was kept.
+    pub fn record_game_state(&self, at: DateTime<Utc>, state: &str) -> rusqlite::Result<bool> {
+        let conn = self.lock();
+        let last: Option<String> = conn
+            .query_row("SELECT state FROM game_states ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .optional()?;
+        if last.as_deref() == Some(state) {
+            return Ok(false);
+        }
+        conn.execute("INSERT INTO game_states (at, state) VALUES (?1, ?2)", params![snapshot_time(at), state])?;
+        let expired = at - chrono::Duration::days(GAME_STATE_RETENTION_DAYS);
+        conn.execute("DELETE FROM game_states WHERE at < ?1", [snapshot_time(expired)])?;
+        Ok(true)
+    }
+
+    /// The first `limit` game states taken between `from` and `to` (both included), oldest first.
+    pub fn game_states(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<StoredGameState>> {
+        let conn = self.lock();
+        let mut stmt = conn.prepare(
+            "SELECT at, state FROM game_states WHERE at >= ?1 AND (?2 = '' OR at <= ?2) ORDER BY id LIMIT ?3",
+        )?;
+        let from = from.map(snapshot_time).unwrap_or_default();
+        let to = to.map(snapshot_time).unwrap_or_default();
+        let rows = stmt.query_map(params![from, to, limit as i64], |row| {
+            Ok(StoredGameState { at: row.get(0)?, state: row.get(1)? })
+        })?;
+        rows.collect()
+    }
+
+    /// Keeps `draft` until [`StateStore::take_draft`], dropping drafts that expired.
+    pub fn save_draft(&self, draft: &TaskDraft) -> rusqlite::Result<()> {
+        let conn = self.lock();
+        let now = Utc::now();
+        conn.execute(
+            "INSERT OR REPLACE INTO task_drafts (id, chat, author, title, description, at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![draft.id, draft.chat, draft.author, draft.title, draft.description, now.to_rfc3339()],
+        )?;
+        let expired = now - chrono::Duration::hours(DRAFT_TTL_HOURS);
+        conn.execute("DELETE FROM task_drafts WHERE at < ?1", [expired.to_rfc3339()])?;
+        Ok(())
+    }
+
+    /// Removes and returns draft `id` of `chat`; `None` when it was taken already or expired.
+    pub fn take_draft(&self, id: &str, chat: &str) -> rusqlite::Result<Option<TaskDraft>> {
+        let conn = self.lock();
+        let expired = Utc::now() - chrono::Duration::hours(DRAFT_TTL_HOURS);
+        let draft = conn
+            .query_row(
+                "SELECT id, chat, author, title, description FROM task_drafts WHERE id = ?1 AND chat = ?2 AND at >= ?3",
+                params![id, chat, expired.to_rfc3339()],
+                |row| {
+                    Ok(TaskDraft {
+                        id: row.get(0)?,
+                        chat: row.get(1)?,
+                        author: row.get(2)?,
+                        title: row.get(3)?,
+                        description: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+        conn.execute("DELETE FROM task_drafts WHERE id = ?1 AND chat = ?2", params![id, chat])?;
+        Ok(draft)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Snapshot times compare as text, so they all have the same shape.
+fn snapshot_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> StateStore {
+        StateStore::new(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn offsets_seen_keys_and_notifications_round_trip() {
+        let store = store();
+        assert_eq!(store.offset("telegram").unwrap(), None);
+        store.set_offset("telegram", 41).unwrap();
+        store.set_offset("telegram", 57).unwrap();
+        assert_eq!(store.offset("telegram").unwrap(), Some(57));
+
+        store.mark_seen("trello", ["a:TODO", "b:INBOX"]).unwrap();
+        store.mark_seen("trello", ["a:TODO"]).unwrap();
+        assert_eq!(store.seen("trello").unwrap(), HashSet::from(["a:TODO".to_string(), "b:INBOX".to_string()]));
+        assert!(store.seen("github").unwrap().is_empty());
+
+        for i in 0..5 {
+            let note = StoredNotification {
+                at: format!("2026-10-17T0{}:00:00+00:00", i),
+                kind: "trace".into(),
+                severity: if i % 2 == 0 { "info" } else { "critical" }.into(),
+                source: Some("agency".into()),
+                task: None,
+                text: format!("note {}", i),
+            };
+            store.record_notification(&note).unwrap();
+        }
+        let texts = |notes: Vec<StoredNotification>| notes.into_iter().map(|n| n.text).collect::<Vec<_>>();
+        assert_eq!(texts(store.recent_notifications(2, None, &[]).unwrap()), ["note 3", "note 4"]);
+        assert_eq!(texts(store.recent_notifications(10, None, &["warn", "critical"]).unwrap()), ["note 1", "note 3"]);
+        let since = "2026-10-17T02:00:00Z".parse().ok();
+        assert_eq!(texts(store.recent_notifications(10, since, &["info"]).unwrap()), ["note 2", "note 4"]);
+
+        let draft = TaskDraft { id: "d1".into(), chat: "-100".into(), author: "ana".into(), title: "Fix login".into(), description: None };
+        store.save_draft(&draft).unwrap();
+        assert_eq!(store.take_draft("d1", "-200").unwrap(), None);
+        assert_eq!(store.take_draft("d1", "-100").unwrap(), Some(draft));
+        assert_eq!(store.take_draft("d1", "-100").unwrap(), None);
+    }
+
+    #[test]
+    fn game_states_are_kept_when_they_change_and_read_by_range() {
+        let store = store();
+        let at = |hour: u32| chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 10, 17, hour, 0, 0).unwrap();
+        assert!(store.record_game_state(at(1), "{\"party\":1}").unwrap());
+        assert!(!store.record_game_state(at(2), "{\"party\":1}").unwrap());
+        assert!(store.record_game_state(at(3), "{\"party\":2}").unwrap());
+        assert!(store.record_game_state(at(4), "{\"party\":3}").unwrap());
+
+        let times = |states: Vec<StoredGameState>| states.into_iter().map(|s| s.at).collect::<Vec<_>>();
+        assert_eq!(times(store.game_states(None, None, 10).unwrap()), ["2026-10-17T01:00:00Z", "2026-10-17T03:00:00Z", "2026-10-17T04:00:00Z"]);
+        assert_eq!(times(store.game_states(Some(at(2)), Some(at(3)), 10).unwrap()), ["2026-10-17T03:00:00Z"]);
+        assert_eq!(store.game_states(Some(at(2)), None, 1).unwrap()[0].state, "{\"party\":2}");
+
+        store.record_game_state(at(4) + chrono::Duration::days(GAME_STATE_RETENTION_DAYS), "{}").unwrap();
+        assert_eq!(times(store.game_states(None, None, 10).unwrap()), ["2026-10-17T04:00:00Z", "2026-10-31T04:00:00Z"]);
+    }
+
+    #[test]
+    fn columns_added_later_are_added_to_older_files() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE notifications (id INTEGER PRIMARY KEY AUTOINCREMENT, at TEXT NOT NULL, kind TEXT NOT NULL, text TEXT NOT NULL);
+                            INSERT INTO notifications (at, kind, text) VALUES ('2026-01-01T00:00:00+00:00', 'alert', 'old');")
+            .unwrap();
+        let store = StateStore::new(conn).unwrap();
+        let old = store.recent_notifications(1, None, &[]).unwrap();
+        assert_eq!((old[0].severity.as_str(), old[0].source.as_deref()), ("info", None));
+    }
+
+    #[test]
+    fn leases_are_exclusive_until_released_and_orphans_belong_to_other_processes() {
+        let store = store();
+        let hour = Duration::from_secs(3600);
+        assert!(store.acquire_lease("task/1", "Fix login", "agent/a", 1, hour).unwrap());
+        assert!(!store.acquire_lease("task/1", "Fix login", "agent/b", 1, hour).unwrap());
+        store.release_lease("task/1").unwrap();
+        assert!(store.acquire_lease("task/1", "Fix login", "agent/b", 2, hour).unwrap());
+        // An expired lease can be taken over.
+        assert!(store.acquire_lease("task/2", "Docs", "agent/c", 1, Duration::ZERO).unwrap());
+        assert!(store.acquire_lease("task/2", "Docs", "agent/a", 2, hour).unwrap());
+        assert!(store.take_orphaned_leases().unwrap().is_empty());
+
+        // Leases written by a previous process are orphans.
+        store.lock().execute("UPDATE leases SET owner = 'previous' WHERE task = 'task/1'", []).unwrap();
+        let orphans = store.take_orphaned_leases().unwrap();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!((orphans[0].agent.as_str(), orphans[0].attempt), ("agent/b", 2));
+        assert!(store.take_orphaned_leases().unwrap().is_empty());
+    }
+
+    #[test]
+    fn queued_resets_outlive_a_restart_until_launched() {
+        let store = store();
+        let reset = |task: &str| QueuedReset { task: task.into(), title: "Fix login".into(), timeout_secs: Some(600) };
+        store.queue_reset(&reset("task/1")).unwrap();
+        store.queue_reset(&reset("task/2")).unwrap();
+        assert_eq!(store.claim_resets().unwrap(), [reset("task/1"), reset("task/2")]);
+        assert!(store.claim_resets().unwrap().is_empty());
+        store.finish_reset("task/1").unwrap();
+
+        // A restart before task/2 was launched hands it to the next process.
+        store.lock().execute("UPDATE resets SET owner = 'previous'", []).unwrap();
+        assert_eq!(store.claim_resets().unwrap(), [reset("task/2")]);
+    }
+}
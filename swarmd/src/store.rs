@@ -0,0 +1,107 @@
+//! The semantic store behind every read and write of the swarm's graph. Handlers and workers
+//! hold a [`Synapse`], which runs SPARQL and ingests triples through a [`SemanticStore`]: the
+//! [`GrpcClient`] of synapse-engine in production, or [`MemoryStore`] in unit tests, so those
+//! need no live engine. A [`CircuitBreaker`] can sit in front of either to stop hammering an overloaded
+//! engine, and a [`WriteBuffer`] in front of that to keep writes through an outage.
+//! Everything built on top (`query_as`, `ingest_from`, the query cache) is an inherent method
+//! of `Synapse` and works the same over any of them.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::notifications::Notification;
+use crate::provenance::Source;
+use crate::sparql;
+
+mod batch;
+mod breaker;
+mod buffer;
+mod grpc;
+#[cfg(test)]
+mod memory;
+
+pub use breaker::CircuitBreaker;
+pub use buffer::WriteBuffer;
+pub use grpc::{Endpoint, GrpcClient};
+
+#[cfg(test)]
+pub use memory::MemoryStore;
+
+/// `(subject, predicate, object)` to retract; no object stands for every object.
+pub type Pattern<'a> = (&'a str, &'a str, Option<&'a str>);
+
+/// The primitives the rest of the daemon needs from a triple store.
+pub trait SemanticStore: Send + Sync {
+    /// Runs a SPARQL query; the result is a JSON array with one object per row.
+    fn query<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<String>>;
+
+    fn ingest<'a>(&'a self, triples: Vec<(&'a str, &'a str, &'a str)>) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// Removes every triple matching one of `patterns`.
+    fn retract<'a>(&'a self, patterns: Vec<Pattern<'a>>) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Cheap to clone handle on the store; clones share it.
+#[derive(Clone)]
+pub struct Synapse(Arc<dyn SemanticStore>);
+
+impl Synapse {
+    pub fn new(store: Arc<dyn SemanticStore>) -> Self {
+        Self(store)
+    }
+
+    /// Connects to synapse-engine over gRPC.
+    pub async fn connect(endpoint: &Endpoint) -> anyhow::Result<Self> {
+        Ok(Self::new(Arc::new(GrpcClient::connect(endpoint).await?)))
+    }
+
+    /// Fails fast for `cooldown` after `failures` calls in a row could not reach Synapse, and
+    /// says so on `tx`; see [`CircuitBreaker`].
+    pub fn with_circuit_breaker(self, failures: u32, cooldown: Duration, tx: mpsc::Sender<Notification>) -> Self {
+        Self(Arc::new(CircuitBreaker::new(self.0, failures, cooldown, tx)))
+    }
+
+    /// Buffers writes in the state store while Synapse is unreachable; see [`WriteBuffer`].
+    pub fn with_write_buffer(self) -> Self {
+        let buffer = Arc::new(WriteBuffer::new(self.0, crate::state::shared()));
+        tokio::spawn(buffer.clone().replay());
+        Self(buffer)
+    }
+
+    pub async fn query(&self, query: &str) -> anyhow::Result<String> {
+        self.0.query(query).await
+    }
+
+    /// Writes `triples` as they are. Callers go through [`Synapse::ingest_from`], which also
+    /// records where they came from.
+    pub async fn ingest(&self, triples: Vec<(&str, &str, &str)>) -> anyhow::Result<()> {
+        self.0.ingest(triples).await
+    }
+
+    /// Removes every triple matching one of `patterns`. Nothing is removed if any term is
+    /// malformed.
+    pub async fn retract(&self, patterns: Vec<Pattern<'_>>) -> anyhow::Result<()> {
+        for (s, p, o) in &patterns {
+            sparql::check_triple(s, p, o.unwrap_or("\"\""))?;
+        }
+        self.0.retract(patterns.clone()).await?;
+        crate::query_cache::shared().invalidate(patterns.iter().map(|(_, p, _)| *p));
+        Ok(())
+    }
+
+    /// Makes `value` the only object of `subject`'s `predicate`, attributed to `source`, so a
+    /// status keeps its current value rather than every value it ever had. The old values go
+    /// first, so a reader in between sees none; if they cannot go, for example on an engine
+    /// without `UpdateSparql`, the new value is still written next to them.
+    pub async fn replace_object(&self, source: Source, subject: &str, predicate: &str, value: &str) -> anyhow::Result<()> {
+        sparql::check_triple(subject, predicate, value)?;
+        if let Err(e) = self.retract(vec![(subject, predicate, None)]).await {
+            warn!("Could not retract the old <{}> of <{}>; writing the new value anyway: {}", predicate, subject, e);
+        }
+        self.ingest_from(source, vec![(subject, predicate, value)]).await
+    }
+}
@@ -0,0 +1,77 @@
+//! Bulk writes. [`Synapse::ingest_batch`] splits a large write into chunks of [`CHUNK_TRIPLES`],
+//! sends up to [`PARALLEL_CHUNKS`] of them at once, tries each failed chunk once more, and
+//! reports every chunk that still failed in one error, so a startup sync makes a handful of
+//! round trips instead of one per repository or agent.
+
+use std::time::Duration;
+
+use futures_util::stream::{self, StreamExt};
+
+use super::Synapse;
+use crate::provenance::Source;
+
+/// Triples per ingest call.
+pub const CHUNK_TRIPLES: usize = 500;
+/// Chunks in flight at once.
+const PARALLEL_CHUNKS: usize = 4;
+/// Pause before a failed chunk is tried again.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+impl Synapse {
+    /// [`Synapse::ingest_from`] for many triples, in chunks. Nothing is written if any triple
+    /// is malformed; otherwise the chunks that went in stay in when others fail, and the error
+    /// says how many triples were not written.
+    pub async fn ingest_batch(&self, source: Source, triples: Vec<(&str, &str, &str)>) -> anyhow::Result<()> {
+        for (s, p, o) in &triples {
+            crate::sparql::check_triple(s, p, o)?;
+        }
+        let failures: Vec<(usize, anyhow::Error)> = stream::iter(triples.chunks(CHUNK_TRIPLES))
+            .map(|chunk| async move {
+                if self.ingest_from(source, chunk.to_vec()).await.is_ok() {
+                    return None;
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+                self.ingest_from(source, chunk.to_vec()).await.err().map(|e| (chunk.len(), e))
+            })
+            .buffer_unordered(PARALLEL_CHUNKS)
+            .filter_map(|failure| async move { failure })
+            .collect()
+            .await;
+
+        let Some((_, first)) = failures.first() else { return Ok(()) };
+        let lost: usize = failures.iter().map(|(len, _)| len).sum();
+        anyhow::bail!(
+            "{} of {} triples were not ingested ({} of {} chunks failed twice): {}",
+            lost,
+            triples.len(),
+            failures.len(),
+            triples.len().div_ceil(CHUNK_TRIPLES),
+            first
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[tokio::test]
+    async fn large_writes_go_in_chunks_and_failures_are_reported_once() {
+        let subjects: Vec<String> = (0..CHUNK_TRIPLES * 2 + 1).map(|i| format!("urn:repo:{}", i)).collect();
+        let triples = || subjects.iter().map(|s| (s.as_str(), "urn:p", "\"x\"")).collect::<Vec<_>>();
+
+        let (store, synapse) = MemoryStore::shared();
+        synapse.ingest_batch(Source::Discovery, triples()).await.unwrap();
+        // Each subject also gets its attribution, and each chunk its two batch triples.
+        let written = subjects.len() * 2 + 3 * 2;
+        assert_eq!(store.triples().len(), written);
+
+        assert!(synapse.ingest_batch(Source::Discovery, vec![("not an iri", "urn:p", "\"x\"")]).await.is_err());
+        assert_eq!(store.triples().len(), written);
+
+        store.set_down(true);
+        let e = synapse.ingest_batch(Source::Discovery, triples()).await.unwrap_err();
+        assert!(e.to_string().starts_with("1001 of 1001 triples were not ingested (3 of 3 chunks failed twice)"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Circuit breaker for an overloaded Synapse (`SYNAPSE_BREAKER_FAILURES`). After that many
+//! calls in a row fail because the engine is unreachable or out of capacity, the circuit opens
+//! and every call fails fast for `SYNAPSE_BREAKER_COOLDOWN_SECS` instead of piling onto it.
+//! The first call after the cooldown goes through as a trial: if it succeeds the circuit
+//! closes, otherwise it stays open for another cooldown; a trial dropped before it answers
+//! leaves the next call to try. Opening and closing are announced as notifications.
+//!
+//! A call the engine refuses, such as a malformed query, says nothing about its load and
+//! neither trips nor resets the count, though as the trial it does close the circuit. The
+//! fast failure is `Unavailable`, so a [`super::WriteBuffer`] in front keeps the writes.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tonic::Code;
+use tracing::{info, warn};
+
+use super::buffer::unreachable;
+use super::{Pattern, SemanticStore};
+use crate::notifications::{Message, Notification};
+
+#[derive(Clone, Copy)]
+enum Circuit {
+    /// Calls go through; counts the failures in a row.
+    Closed(u32),
+    /// Calls fail fast until the instant.
+    Open(Instant),
+    /// One trial call is in flight; the others fail fast.
+    HalfOpen,
+}
+
+/// Held while an admitted call is in flight. Should a trial be dropped before its outcome is
+/// recorded, the circuit goes back to open with the cooldown over, so the next call is a trial.
+struct Admission<'a> {
+    circuit: &'a Mutex<Circuit>,
+    trial: bool,
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        let mut circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+        if self.trial && matches!(*circuit, Circuit::HalfOpen) {
+            *circuit = Circuit::Open(Instant::now());
+        }
+    }
+}
+
+pub struct CircuitBreaker {
+    inner: Arc<dyn SemanticStore>,
+    threshold: u32,
+    cooldown: Duration,
+    circuit: Mutex<Circuit>,
+    tx: mpsc::Sender<Notification>,
+}
+
+impl CircuitBreaker {
+    pub fn new(inner: Arc<dyn SemanticStore>, threshold: u32, cooldown: Duration, tx: mpsc::Sender<Notification>) -> Self {
+        Self { inner, threshold, cooldown, circuit: Mutex::new(Circuit::Closed(0)), tx }
+    }
+
+    /// Lets a call through, or says why it fails fast. Keep the admission until the outcome
+    /// is recorded.
+    fn admit(&self) -> anyhow::Result<Admission<'_>> {
+        let mut circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+        match *circuit {
+            Circuit::Closed(_) => Ok(Admission { circuit: &self.circuit, trial: false }),
+            Circuit::Open(until) if Instant::now() >= until => {
+                *circuit = Circuit::HalfOpen;
+                Ok(Admission { circuit: &self.circuit, trial: true })
+            }
+            Circuit::Open(until) => {
+                let secs = until.saturating_duration_since(Instant::now()).as_secs().max(1);
+                Err(tonic::Status::unavailable(format!("Synapse circuit is open; calls resume in {}s", secs)).into())
+            }
+            Circuit::HalfOpen => Err(tonic::Status::unavailable("Synapse circuit is open; a trial call is in flight").into()),
+        }
+    }
+
+    async fn record<T>(&self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        let failed = result.as_ref().err().is_some_and(overloaded);
+        let notification = {
+            let mut circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+            match (*circuit, failed) {
+                (Circuit::Closed(failures), true) if failures + 1 >= self.threshold => {
+                    *circuit = Circuit::Open(Instant::now() + self.cooldown);
+                    Some(Notification::alert(
+                        Message::new()
+                            .strong("Synapse circuit open")
+                            .text(format!(": {} calls in a row failed; failing fast for {}s", failures + 1, self.cooldown.as_secs())),
+                    ))
+                }
+                (Circuit::Closed(failures), true) => {
+                    *circuit = Circuit::Closed(failures + 1);
+                    None
+                }
+                (Circuit::Closed(_), false) if result.is_err() => None,
+                (Circuit::Closed(_), false) => {
+                    *circuit = Circuit::Closed(0);
+                    None
+                }
+                (_, true) => {
+                    warn!("🧯 Synapse trial call failed; circuit stays open for {}s", self.cooldown.as_secs());
+                    *circuit = Circuit::Open(Instant::now() + self.cooldown);
+                    None
+                }
+                // Even a refusal shows the engine is answering again.
+                (_, false) => {
+                    info!("🧯 Synapse answered again; circuit closed");
+                    *circuit = Circuit::Closed(0);
+                    Some(Notification::trace(Message::new().strong("Synapse circuit closed").text(": calls go through again")))
+                }
+            }
+        };
+        if let Some(notification) = notification {
+            let _ = self.tx.send(notification).await;
+        }
+        result
+    }
+}
+
+impl SemanticStore for CircuitBreaker {
+    fn query<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<String>> {
+        Box::pin(async move {
+            let _admission = self.admit()?;
+            self.record(self.inner.query(query).await).await
+        })
+    }
+
+    fn ingest<'a>(&'a self, triples: Vec<(&'a str, &'a str, &'a str)>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let _admission = self.admit()?;
+            self.record(self.inner.ingest(triples).await).await
+        })
+    }
+
+    fn retract<'a>(&'a self, patterns: Vec<Pattern<'a>>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let _admission = self.admit()?;
+            self.record(self.inner.retract(patterns).await).await
+        })
+    }
+}
+
+/// Whether `e` says Synapse could not take the call, as opposed to refusing it.
+fn overloaded(e: &anyhow::Error) -> bool {
+    unreachable(e)
+        || e.chain().any(|cause| cause.downcast_ref::<tonic::Status>().is_some_and(|status| status.code() == Code::ResourceExhausted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::notifications::Severity;
+    use crate::store::MemoryStore;
+
+    const QUERY: &str = "SELECT ?s WHERE { ?s ?p ?o }";
+    const COOLDOWN: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn opens_after_failures_in_a_row_and_closes_after_a_good_trial() {
+        let (store, _) = MemoryStore::shared();
+        let (tx, mut rx) = mpsc::channel(10);
+        let breaker = CircuitBreaker::new(store.clone(), 2, COOLDOWN, tx);
+
+        store.set_down(true);
+        assert!(breaker.query(QUERY).await.is_err());
+        assert!(rx.try_recv().is_err());
+        assert!(breaker.query(QUERY).await.is_err());
+        assert_eq!(rx.try_recv().unwrap().severity, Severity::Warn);
+
+        // Fails fast: the store is not asked, even though it is back.
+        store.set_down(false);
+        let e = breaker.query(QUERY).await.unwrap_err();
+        assert!(e.to_string().contains("calls resume in 1s"));
+        assert!(store.queries().is_empty());
+
+        tokio::time::sleep(COOLDOWN).await;
+        breaker.query(QUERY).await.unwrap();
+        assert_eq!(rx.try_recv().unwrap().severity, Severity::Info);
+        breaker.ingest(vec![("urn:a", "urn:p", "\"1\"")]).await.unwrap();
+        assert_eq!(store.triples().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failed_trial_keeps_it_open() {
+        let (store, _) = MemoryStore::shared();
+        let (tx, mut rx) = mpsc::channel(10);
+        let breaker = CircuitBreaker::new(store.clone(), 1, COOLDOWN, tx);
+
+        store.set_down(true);
+        assert!(breaker.query(QUERY).await.is_err());
+        assert!(rx.try_recv().is_ok());
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(breaker.query(QUERY).await.is_err());
+        store.set_down(false);
+        assert!(breaker.query(QUERY).await.unwrap_err().to_string().contains("circuit is open"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn a_dropped_trial_lets_the_next_call_try() {
+        let (store, _) = MemoryStore::shared();
+        let stalling = Arc::new(Stalling { inner: store.clone(), stalled: AtomicBool::new(false) });
+        let (tx, mut rx) = mpsc::channel(10);
+        let breaker = CircuitBreaker::new(stalling.clone(), 1, COOLDOWN, tx);
+
+        store.set_down(true);
+        assert!(breaker.query(QUERY).await.is_err());
+        assert!(rx.try_recv().is_ok());
+        store.set_down(false);
+        tokio::time::sleep(COOLDOWN).await;
+
+        stalling.stalled.store(true, Ordering::SeqCst);
+        assert!(tokio::time::timeout(COOLDOWN, breaker.query(QUERY)).await.is_err());
+        stalling.stalled.store(false, Ordering::SeqCst);
+        breaker.query(QUERY).await.unwrap();
+        assert_eq!(rx.try_recv().unwrap().severity, Severity::Info);
+    }
+
+    /// Never answers while `stalled`, like an engine that hangs.
+    struct Stalling {
+        inner: Arc<MemoryStore>,
+        stalled: AtomicBool,
+    }
+
+    impl Stalling {
+        async fn stall(&self) {
+            if self.stalled.load(Ordering::SeqCst) {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    impl SemanticStore for Stalling {
+        fn query<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<String>> {
+            Box::pin(async move {
+                self.stall().await;
+                self.inner.query(query).await
+            })
+        }
+
+        fn ingest<'a>(&'a self, triples: Vec<(&'a str, &'a str, &'a str)>) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async move {
+                self.stall().await;
+                self.inner.ingest(triples).await
+            })
+        }
+
+        fn retract<'a>(&'a self, patterns: Vec<Pattern<'a>>) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async move {
+                self.stall().await;
+                self.inner.retract(patterns).await
+            })
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Write-ahead buffer for Synapse outages (`SYNAPSE_WRITE_BUFFER`). An ingest or retraction
+//! that cannot reach synapse-engine is kept in the state store and reported as done, and every
+//! write after it queues behind it until the buffer is replayed, oldest first, once Synapse
+//! answers again.
+//! Trello cards, GitHub issues and chat commands that arrive during an outage therefore land
+//! late rather than never. Reads are not buffered: a query during an outage fails as before.
+//!
+//! Only connection failures are buffered. A write Synapse refuses is returned to its caller as
+//! usual, and one refused on replay is dropped with an error, so it cannot hold up the rest.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use tokio::sync::Mutex;
+use tonic::Code;
+use tracing::{error, info, warn};
+
+use super::{Pattern, SemanticStore};
+use crate::state::{StateStore, WriteOp};
+
+/// How often the buffer tries Synapse again while it holds writes.
+const REPLAY_INTERVAL: Duration = Duration::from_secs(15);
+
+pub struct WriteBuffer {
+    inner: Arc<dyn SemanticStore>,
+    state: &'static StateStore,
+    /// Held by each write and replay, so writes reach Synapse in the order they were made.
+    turn: Mutex<()>,
+}
+
+impl WriteBuffer {
+    pub fn new(inner: Arc<dyn SemanticStore>, state: &'static StateStore) -> Self {
+        Self { inner, state, turn: Mutex::new(()) }
+    }
+
+    /// Replays buffered writes every [`REPLAY_INTERVAL`]; runs for the life of the process.
+    pub async fn replay(self: Arc<Self>) {
+        let mut tick = tokio::time::interval(REPLAY_INTERVAL);
+        loop {
+            tick.tick().await;
+            self.drain().await;
+        }
+    }
+
+    /// Sends buffered writes until none is left or Synapse is still out of reach.
+    async fn drain(&self) {
+        let _turn = self.turn.lock().await;
+        let mut replayed = 0;
+        loop {
+            let pending = match self.state.next_pending_write() {
+                Ok(Some(pending)) => pending,
+                Ok(None) => break,
+                Err(e) => {
+                    error!("Could not read the Synapse write buffer: {}", e);
+                    break;
+                }
+            };
+            let sent = match &pending.op {
+                WriteOp::Ingest(triples) => {
+                    self.inner.ingest(triples.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())).collect()).await
+                }
+                WriteOp::Retract(patterns) => {
+                    self.inner.retract(patterns.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_deref())).collect()).await
+                }
+            };
+            match sent {
+                Ok(()) => replayed += 1,
+                Err(e) if unreachable(&e) => break,
+                Err(e) => error!("Dropping a buffered write Synapse refused: {}", e),
+            }
+            if let Err(e) = self.state.remove_pending_write(pending.id) {
+                error!("Could not remove a replayed write from the Synapse write buffer: {}", e);
+                break;
+            }
+        }
+        if replayed > 0 {
+            info!("🔌 Synapse is back; replayed {} buffered writes", replayed);
+        }
+    }
+}
+
+impl SemanticStore for WriteBuffer {
+    fn query<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<String>> {
+        self.inner.query(query)
+    }
+
+    fn ingest<'a>(&'a self, triples: Vec<(&'a str, &'a str, &'a str)>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let _turn = self.turn.lock().await;
+            if self.state.pending_write_count()? == 0 {
+                match self.inner.ingest(triples.clone()).await {
+                    Ok(()) => return Ok(()),
+                    Err(e) if !unreachable(&e) => return Err(e),
+                    Err(e) => warn!("🔌 Synapse unreachable; buffering writes until it is back: {}", e),
+                }
+            }
+            self.state.queue_ingest(&triples)
+        })
+    }
+
+    fn retract<'a>(&'a self, patterns: Vec<Pattern<'a>>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let _turn = self.turn.lock().await;
+            if self.state.pending_write_count()? == 0 {
+                match self.inner.retract(patterns.clone()).await {
+                    Ok(()) => return Ok(()),
+                    Err(e) if !unreachable(&e) => return Err(e),
+                    Err(e) => warn!("🔌 Synapse unreachable; buffering writes until it is back: {}", e),
+                }
+            }
+            self.state.queue_retraction(&patterns)
+        })
+    }
+}
+
+/// Whether `e` says Synapse could not be reached, rather than that it refused the request.
+pub(super) fn unreachable(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause.is::<tonic::transport::Error>()
+            || cause
+                .downcast_ref::<tonic::Status>()
+                .is_some_and(|status| matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MemoryStore, Synapse};
+
+    #[tokio::test]
+    async fn writes_made_during_an_outage_are_replayed_in_order() {
+        let (store, _) = MemoryStore::shared();
+        let buffer = Arc::new(WriteBuffer::new(store.clone(), crate::state::scratch()));
+        let synapse = Synapse::new(buffer.clone());
+
+        store.set_down(true);
+        synapse.ingest(vec![("urn:a", "urn:p", "\"1\"")]).await.unwrap();
+        store.set_down(false);
+        // Queued behind the first, although Synapse would take it now.
+        synapse.ingest(vec![("urn:b", "urn:p", "\"2\"")]).await.unwrap();
+        assert!(store.triples().is_empty());
+        assert_eq!(buffer.state.pending_write_count().unwrap(), 2);
+
+        buffer.drain().await;
+        let subjects: Vec<String> = store.triples().into_iter().map(|(s, _, _)| s).collect();
+        assert_eq!(subjects, ["urn:a", "urn:b"]);
+        assert_eq!(buffer.state.pending_write_count().unwrap(), 0);
+
+        synapse.ingest(vec![("urn:c", "urn:p", "\"3\"")]).await.unwrap();
+        assert_eq!(store.triples().len(), 3);
+
+        store.set_down(true);
+        synapse.retract(vec![("urn:a", "urn:p", None)]).await.unwrap();
+        synapse.ingest(vec![("urn:a", "urn:p", "\"4\"")]).await.unwrap();
+        store.set_down(false);
+        buffer.drain().await;
+        assert!(store.triples().contains(&("urn:a".into(), "urn:p".into(), "\"4\"".into())));
+        assert_eq!(store.triples().len(), 3);
+    }
+
+    #[test]
+    fn only_connection_failures_count_as_unreachable() {
+        assert!(unreachable(&tonic::Status::unavailable("connection refused").into()));
+        assert!(unreachable(&anyhow::Error::from(tonic::Status::deadline_exceeded("slow")).context("ingest")));
+        assert!(!unreachable(&tonic::Status::invalid_argument("bad IRI").into()));
+        assert!(!unreachable(&anyhow::anyhow!("malformed triple")));
+    }
+}
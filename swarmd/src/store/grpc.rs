@@ -0,0 +1,212 @@
+//! gRPC client of synapse-engine.
+//!
+//! Named graphs (`SYNAPSE_GRAPH`): synapse-engine keeps each namespace's triples apart, so a
+//! swarmd given a graph such as `urn:swarm:staging` sends it as the namespace of every query,
+//! ingest and retraction, and never sees or touches the triples of an instance on another
+//! graph of the same engine. Without a graph, swarmd uses the engine's default namespace.
+//!
+//! Remote engines (`SYNAPSE_TLS*`, `SYNAPSE_TOKEN`): the channel can run over TLS, checked
+//! against a private CA or the bundled web PKI roots and optionally presenting a client
+//! certificate, and every call can carry `authorization: Bearer <token>`.
+
+use anyhow::Context;
+use futures_util::future::BoxFuture;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::{InterceptedService, Interceptor};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+use tonic::{Request, Status};
+
+use super::{Pattern, SemanticStore};
+use crate::config::AppConfig;
+use crate::sparql;
+
+mod proto {
+    tonic::include_proto!("semantic_engine");
+}
+
+use proto::semantic_engine_client::SemanticEngineClient;
+
+/// Where synapse-engine listens and how to talk to it.
+#[derive(Debug, Clone, Default)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: String,
+    /// Named graph every call is scoped to; the engine's default namespace when `None`.
+    pub graph: Option<String>,
+    pub tls: Option<Tls>,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tls {
+    /// PEM file with the CA that signed the engine's certificate, instead of the web PKI roots.
+    pub ca: Option<String>,
+    /// PEM certificate and key files presented to the engine, for mutual TLS.
+    pub identity: Option<(String, String)>,
+    /// Name the engine's certificate is checked against, when it is not the host.
+    pub domain: Option<String>,
+}
+
+impl Endpoint {
+    pub fn new(host: &str, port: &str) -> Self {
+        Self { host: host.to_string(), port: port.to_string(), ..Self::default() }
+    }
+
+    pub fn from_config(cfg: &AppConfig) -> anyhow::Result<Self> {
+        let identity = match (&cfg.synapse_tls_cert, &cfg.synapse_tls_key) {
+            (Some(cert), Some(key)) => Some((cert.clone(), key.clone())),
+            (None, None) => None,
+            _ => anyhow::bail!("SYNAPSE_TLS_CERT and SYNAPSE_TLS_KEY must be set together"),
+        };
+        let tls = (cfg.synapse_tls || cfg.synapse_tls_ca.is_some() || identity.is_some()).then(|| Tls {
+            ca: cfg.synapse_tls_ca.clone(),
+            identity,
+            domain: cfg.synapse_tls_domain.clone(),
+        });
+        Ok(Self {
+            graph: cfg.synapse_graph.clone(),
+            tls,
+            token: cfg.synapse_token.clone(),
+            ..Self::new(&cfg.synapse_grpc_host, &cfg.synapse_grpc_port)
+        })
+    }
+
+    async fn channel(&self) -> anyhow::Result<Channel> {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        let mut endpoint = Channel::from_shared(format!("{}://{}:{}", scheme, self.host, self.port))?;
+        if let Some(tls) = &self.tls {
+            endpoint = endpoint.tls_config(tls.client_config()?)?;
+        }
+        Ok(endpoint.connect().await?)
+    }
+}
+
+impl Tls {
+    fn client_config(&self) -> anyhow::Result<ClientTlsConfig> {
+        let mut config = ClientTlsConfig::new();
+        if let Some(ca) = &self.ca {
+            config = config.ca_certificate(Certificate::from_pem(read_pem("SYNAPSE_TLS_CA", ca)?));
+        }
+        if let Some((cert, key)) = &self.identity {
+            config = config.identity(Identity::from_pem(read_pem("SYNAPSE_TLS_CERT", cert)?, read_pem("SYNAPSE_TLS_KEY", key)?));
+        }
+        if let Some(domain) = &self.domain {
+            config = config.domain_name(domain);
+        }
+        Ok(config)
+    }
+}
+
+fn read_pem(setting: &str, path: &str) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("reading {} from {}", setting, path))
+}
+
+/// Adds the bearer token, when there is one, to every call.
+#[derive(Clone)]
+struct Bearer(Option<MetadataValue<Ascii>>);
+
+impl Bearer {
+    fn new(token: Option<&str>) -> anyhow::Result<Self> {
+        let value = token
+            .map(|token| format!("Bearer {}", token).parse())
+            .transpose()
+            .map_err(|_| anyhow::anyhow!("SYNAPSE_TOKEN must be printable ASCII"))?;
+        Ok(Self(value))
+    }
+}
+
+impl Interceptor for Bearer {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(value) = &self.0 {
+            request.metadata_mut().insert("authorization", value.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[derive(Clone)]
+pub struct GrpcClient {
+    client: SemanticEngineClient<InterceptedService<Channel, Bearer>>,
+    /// Sent as the namespace of every call; empty for the default one.
+    graph: String,
+}
+
+impl GrpcClient {
+    pub async fn connect(endpoint: &Endpoint) -> anyhow::Result<Self> {
+        if let Some(graph) = endpoint.graph.as_deref().filter(|graph| !sparql::is_iri(graph)) {
+            anyhow::bail!("SYNAPSE_GRAPH must be an IRI such as urn:swarm:prod, not {:?}", graph);
+        }
+        let bearer = Bearer::new(endpoint.token.as_deref())?;
+        let client = SemanticEngineClient::with_interceptor(endpoint.channel().await?, bearer);
+        Ok(Self { client, graph: endpoint.graph.clone().unwrap_or_default() })
+    }
+
+    async fn sparql(&self, query: String) -> anyhow::Result<String> {
+        let request = proto::SparqlRequest { query, namespace: self.graph.clone() };
+        Ok(self.client.clone().query_sparql(request).await?.into_inner().results_json)
+    }
+}
+
+impl SemanticStore for GrpcClient {
+    fn query<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<String>> {
+        Box::pin(self.sparql(query.to_string()))
+    }
+
+    fn ingest<'a>(&'a self, triples: Vec<(&'a str, &'a str, &'a str)>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let triples = triples
+                .into_iter()
+                .map(|(s, p, o)| proto::Triple {
+                    subject: s.to_string(),
+                    predicate: p.to_string(),
+                    object: o.to_string(),
+                    provenance: None,
+                    embedding: Vec::new(),
+                })
+                .collect();
+            let request = proto::IngestRequest { triples, namespace: self.graph.clone() };
+            self.client.clone().ingest_triples(request).await?;
+            Ok(())
+        })
+    }
+
+    /// `QuerySparql` is read-only, so the retraction goes through `UpdateSparql`.
+    fn retract<'a>(&'a self, patterns: Vec<Pattern<'a>>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let request = proto::SparqlRequest { query: sparql::retraction(&patterns)?, namespace: self.graph.clone() };
+            self.client.clone().update_sparql(request).await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn bad_settings_fail_before_connecting() {
+        let endpoint = Endpoint { graph: Some("staging env".into()), ..Endpoint::new("127.0.0.1", "1") };
+        let e = GrpcClient::connect(&endpoint).await.err().unwrap();
+        assert!(e.to_string().contains("SYNAPSE_GRAPH must be an IRI"));
+
+        let endpoint = Endpoint { token: Some("line\nbreak".into()), ..Endpoint::new("127.0.0.1", "1") };
+        let e = GrpcClient::connect(&endpoint).await.err().unwrap();
+        assert!(e.to_string().contains("SYNAPSE_TOKEN must be printable ASCII"));
+
+        let tls = Tls { ca: Some("/nonexistent/ca.pem".into()), ..Tls::default() };
+        let endpoint = Endpoint { tls: Some(tls), ..Endpoint::new("127.0.0.1", "1") };
+        let e = GrpcClient::connect(&endpoint).await.err().unwrap();
+        assert!(e.to_string().contains("reading SYNAPSE_TLS_CA from /nonexistent/ca.pem"));
+    }
+
+    #[test]
+    fn every_call_carries_the_token() {
+        let mut bearer = Bearer::new(Some("s3cret")).unwrap();
+        let request = bearer.call(Request::new(())).unwrap();
+        assert_eq!(request.metadata().get("authorization").unwrap(), "Bearer s3cret");
+
+        let request = Bearer::new(None).unwrap().call(Request::new(())).unwrap();
+        assert!(request.metadata().get("authorization").is_none());
+    }
+}
@@ -0,0 +1,127 @@
+//! In-memory [`SemanticStore`] for unit tests. It keeps what is ingested and not retracted,
+//! answers each query with the rows of the first canned answer whose fragment the query
+//! contains (no rows when none does), and can be taken down to exercise the paths that handle
+//! a Synapse outage.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+
+use super::{Pattern, SemanticStore, Synapse};
+
+#[derive(Default)]
+pub struct MemoryStore {
+    answers: Mutex<Vec<(String, Value)>>,
+    triples: Mutex<Vec<(String, String, String)>>,
+    queries: Mutex<Vec<String>>,
+    down: AtomicBool,
+}
+
+impl MemoryStore {
+    /// A store and a [`Synapse`] on it, for the code under test.
+    pub fn shared() -> (Arc<Self>, Synapse) {
+        let store = Arc::new(Self::default());
+        (store.clone(), Synapse::new(store))
+    }
+
+    /// Answers queries containing `fragment` with `rows`, a JSON array of SPARQL rows such as
+    /// `[{"?title": "\"Add SSO\""}]`.
+    pub fn answer(&self, fragment: &str, rows: Value) {
+        self.answers.lock().unwrap().push((fragment.to_string(), rows));
+    }
+
+    /// Fails every query and ingest while `down`.
+    pub fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+
+    pub fn triples(&self) -> Vec<(String, String, String)> {
+        self.triples.lock().unwrap().clone()
+    }
+
+    pub fn queries(&self) -> Vec<String> {
+        self.queries.lock().unwrap().clone()
+    }
+
+    fn check_up(&self) -> anyhow::Result<()> {
+        if self.down.load(Ordering::SeqCst) {
+            return Err(tonic::Status::unavailable("synapse-engine unavailable").into());
+        }
+        Ok(())
+    }
+}
+
+impl SemanticStore for MemoryStore {
+    fn query<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<String>> {
+        Box::pin(async move {
+            self.check_up()?;
+            self.queries.lock().unwrap().push(query.to_string());
+            let answers = self.answers.lock().unwrap();
+            let rows = answers.iter().find(|(fragment, _)| query.contains(fragment.as_str())).map(|(_, rows)| rows.clone());
+            Ok(rows.unwrap_or_else(|| Value::Array(Vec::new())).to_string())
+        })
+    }
+
+    fn ingest<'a>(&'a self, triples: Vec<(&'a str, &'a str, &'a str)>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            self.check_up()?;
+            let mut stored = self.triples.lock().unwrap();
+            stored.extend(triples.into_iter().map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string())));
+            Ok(())
+        })
+    }
+
+    fn retract<'a>(&'a self, patterns: Vec<Pattern<'a>>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            self.check_up()?;
+            self.triples
+                .lock()
+                .unwrap()
+                .retain(|(s, p, o)| !patterns.iter().any(|(ps, pp, po)| s == ps && p == pp && po.is_none_or(|po| o == po)));
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provenance::{Source, PROV_ATTRIBUTED_TO};
+    use crate::tasks::AGENT_STATUS;
+
+    #[derive(Debug, serde::Deserialize, PartialEq)]
+    struct Row {
+        title: String,
+    }
+
+    #[tokio::test]
+    async fn queries_get_canned_rows_and_ingests_are_kept() {
+        let (store, synapse) = MemoryStore::shared();
+        store.answer("ontology/title", serde_json::json!([{"?title": "\"Add SSO\""}]));
+
+        let rows: Vec<Row> = synapse.query_as("SELECT ?title WHERE { ?t <http://swarm.os/ontology/title> ?title }").await.unwrap();
+        assert_eq!(rows, [Row { title: "Add SSO".into() }]);
+        assert!(synapse.query_as::<Row>("SELECT ?s WHERE { ?s ?p ?o }").await.unwrap().is_empty());
+        assert_eq!(store.queries().len(), 2);
+
+        synapse
+            .ingest_from(Source::Trello, vec![("http://swarm.os/tasks/1", "http://swarm.os/ontology/title", "\"A\"")])
+            .await
+            .unwrap();
+        let triples = store.triples();
+        assert_eq!(triples.len(), 4);
+        assert!(triples.iter().any(|(s, p, _)| s == "http://swarm.os/tasks/1" && p == PROV_ATTRIBUTED_TO));
+
+        let agent = "http://swarm.os/agent/Coder_1";
+        for status in ["\"Standby\"", "\"Working on: A\"", "\"Standby\""] {
+            synapse.replace_object(Source::Agency, agent, AGENT_STATUS, status).await.unwrap();
+        }
+        let statuses: Vec<String> = store.triples().into_iter().filter(|(s, p, _)| s == agent && p == AGENT_STATUS).map(|(_, _, o)| o).collect();
+        assert_eq!(statuses, ["\"Standby\""]);
+
+        store.set_down(true);
+        assert!(synapse.query("SELECT ?s WHERE { ?s ?p ?o }").await.is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! Task lifecycle vocabulary shared by the agency, the gateway and the chat workers.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+use tokio::sync::Notify;
+
+use crate::store::Synapse;
+
+pub const XSD_DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
+pub const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+pub const XSD_DATETIME: &str = "http://www.w3.org/2001/XMLSchema#dateTime";
+
+pub const INTERNAL_STATE: &str = "http://swarm.os/ontology/internalState";
+pub const AGENT_STATUS: &str = "http://swarm.os/ontology/status";
+pub const ASSIGNED_TO: &str = "http://swarm.os/ontology/assignedTo";
+/// The task's agent was retired; the assignment no longer counts.
+pub const UNASSIGNED_FROM: &str = "http://swarm.os/ontology/unassignedFrom";
+/// Integer; higher runs first. Tasks without one count as [`NORMAL_PRIORITY`].
+pub const PRIORITY: &str = "http://swarm.os/ontology/priority";
+pub const NORMAL_PRIORITY: i64 = 1;
+/// What the card or issue a task came from says beyond its title.
+pub const DESCRIPTION: &str = "http://swarm.os/ontology/description";
+/// One label of the card or issue a task came from.
+pub const LABEL: &str = "http://swarm.os/ontology/label";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Processing,
+    Done,
+    Failed,
+    RetryScheduled,
+    DeadLetter,
+    TimedOut,
+    /// The orchestrator stopped sending heartbeats and was stopped.
+    Stalled,
+    /// The run opened a pull request; the task is done once it merges.
+    AwaitingMerge,
+    /// Withdrawn at the source (e.g. the Trello card was deleted) before any work started.
+    Cancelled,
+    /// Agents gave up; handed to the human owners until one of them resets it.
+    Escalated,
+    /// Needs a human's approval before any agent starts it.
+    AwaitingApproval,
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskState::Processing => "PROCESSING",
+            TaskState::Done => "DONE",
+            TaskState::Failed => "FAILED",
+            TaskState::RetryScheduled => "RETRY_SCHEDULED",
+            TaskState::DeadLetter => "DEAD_LETTER",
+            TaskState::TimedOut => "TIMED_OUT",
+            TaskState::Stalled => "STALLED",
+            TaskState::AwaitingMerge => "AWAITING_MERGE",
+            TaskState::Cancelled => "CANCELLED",
+            TaskState::Escalated => "ESCALATED",
+            TaskState::AwaitingApproval => "AWAITING_APPROVAL",
+        }
+    }
+
+    /// Quoted literal form, as stored in `swarm:internalState`.
+    pub fn literal(&self) -> String {
+        format!("\"{}\"", self.as_str())
+    }
+}
+
+pub fn decimal_literal(value: f64) -> String {
+    format!("\"{value:.3}\"^^<{XSD_DECIMAL}>")
+}
+
+pub fn integer_literal(value: i64) -> String {
+    format!("\"{value}\"^^<{XSD_INTEGER}>")
+}
+
+pub fn datetime_literal(value: &chrono::DateTime<chrono::Utc>) -> String {
+    format!("\"{}\"^^<{XSD_DATETIME}>", value.to_rfc3339())
+}
+
+/// Priority named by a card or issue label such as `urgent`, `priority: high` or `P3`.
+pub fn priority_from_labels<'a>(labels: impl IntoIterator<Item = &'a str>) -> Option<i64> {
+    labels
+        .into_iter()
+        .filter_map(|label| {
+            let label = label.trim().to_lowercase();
+            let name = label.strip_prefix("priority").map(|rest| rest.trim_start_matches([':', '/', '-', ' '])).unwrap_or(&label);
+            match name {
+                "urgent" | "critical" | "p0" => Some(3),
+                "high" | "p1" => Some(2),
+                "normal" | "medium" | "p2" => Some(NORMAL_PRIORITY),
+                "low" | "p3" => Some(0),
+                _ => None,
+            }
+        })
+        .max()
+}
+
+/// Every state a task can be in: the intake states it arrives with (`REQUIREMENTS`, or the
+/// Trello list it came from), then the lifecycle ones.
+pub const KNOWN_STATES: &[&str] = &[
+    "REQUIREMENTS", "DESIGN", "TODO", "INBOX", "PROCESSING", "DONE", "FAILED", "RETRY_SCHEDULED", "DEAD_LETTER", "TIMED_OUT",
+    "STALLED", "AWAITING_MERGE", "CANCELLED", "ESCALATED", "REJECTED", "AWAITING_APPROVAL", "APPROVED",
+];
+
+/// States after which a task never runs again.
+pub const TERMINAL_STATES: &[&str] = &["DONE", "DEAD_LETTER", "REJECTED", "CANCELLED"];
+
+/// `internalState` is append-only, so a task accumulates every state it went through.
+/// Picks the one that best describes where it is now: terminal states win, then the
+/// later lifecycle stages, then whatever intake state (Trello list) it arrived with.
+pub fn current_state(states: &[String]) -> Option<&str> {
+    state_by_precedence(states, false)
+}
+
+/// [`current_state`] of a task with these `swarm:escalatedAt` and `swarm:resetAt` values. A
+/// reset newer than the newest escalation hands the task back to the agents, so `ESCALATED`
+/// no longer counts.
+pub fn current_state_after_resets<'a>(states: &'a [String], escalated: &[String], resets: &[String]) -> Option<&'a str> {
+    state_by_precedence(states, reset_since_escalation(escalated, resets))
+}
+
+/// Whether the newest of `resets` comes after the newest of `escalated`; both are RFC3339
+/// times, and any that do not parse are ignored.
+pub fn reset_since_escalation(escalated: &[String], resets: &[String]) -> bool {
+    let newest = |times: &[String]| times.iter().filter_map(|t| chrono::DateTime::parse_from_rfc3339(t).ok()).max();
+    match (newest(escalated), newest(resets)) {
+        (Some(escalated), Some(reset)) => reset > escalated,
+        _ => false,
+    }
+}
+
+fn state_by_precedence(states: &[String], reset: bool) -> Option<&str> {
+    const PRECEDENCE: &[&str] = &[
+        "DONE", "DEAD_LETTER", "REJECTED", "CANCELLED", "ESCALATED", "AWAITING_MERGE", "RETRY_SCHEDULED", "TIMED_OUT", "STALLED", "FAILED", "PROCESSING",
+        "APPROVED", "AWAITING_APPROVAL",
+    ];
+    PRECEDENCE
+        .iter()
+        .filter(|p| !(reset && **p == "ESCALATED"))
+        .find(|p| states.iter().any(|s| s == *p))
+        .copied()
+        .or_else(|| states.iter().map(String::as_str).find(|s| !(reset && *s == "ESCALATED")))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSummary {
+    pub id: String,
+    pub title: String,
+    pub state: String,
+    pub agent: Option<String>,
+}
+
+/// Tasks that have not reached a terminal state, ordered by title.
+pub async fn active_tasks(synapse: &Synapse) -> anyhow::Result<Vec<TaskSummary>> {
+    Ok(all_tasks(synapse)
+        .await?
+        .into_iter()
+        .filter(|t| !TERMINAL_STATES.contains(&t.state.as_str()))
+        .collect())
+}
+
+/// Every task with its current state, ordered by title.
+pub async fn all_tasks(synapse: &Synapse) -> anyhow::Result<Vec<TaskSummary>> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?task ?title ?state ?agent ?dropped ?escalated ?reset
+        WHERE {
+            ?task a swarm:Task ;
+                  swarm:title ?title ;
+                  swarm:internalState ?state .
+            OPTIONAL { ?task swarm:assignedTo ?agent }
+            OPTIONAL { ?task swarm:unassignedFrom ?dropped }
+            OPTIONAL { ?task swarm:escalatedAt ?escalated }
+            OPTIONAL { ?task swarm:resetAt ?reset }
+        }
+    "#;
+    let res_json = synapse.query_cached(query, crate::query_cache::DEFAULT_TTL).await?;
+    let rows = serde_json::from_str::<Vec<serde_json::Value>>(&res_json).unwrap_or_default();
+    Ok(summarize(&rows))
+}
+
+fn summarize(rows: &[serde_json::Value]) -> Vec<TaskSummary> {
+    let field = |row: &serde_json::Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(|v| v.as_str())
+            .map(|v| literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+
+    let mut by_task: BTreeMap<String, (String, Vec<String>, Option<String>)> = BTreeMap::new();
+    let mut unassigned: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    // task -> (escalation times, reset times)
+    let mut escalations: BTreeMap<String, (Vec<String>, Vec<String>)> = BTreeMap::new();
+    for row in rows {
+        let Some(id) = field(row, "task") else { continue };
+        if let Some(dropped) = field(row, "dropped") {
+            unassigned.entry(id.clone()).or_default().insert(dropped);
+        }
+        if let Some(escalated) = field(row, "escalated") {
+            escalations.entry(id.clone()).or_default().0.push(escalated);
+        }
+        if let Some(reset) = field(row, "reset") {
+            escalations.entry(id.clone()).or_default().1.push(reset);
+        }
+        let entry = by_task.entry(id).or_default();
+        if let Some(title) = field(row, "title") {
+            entry.0 = title;
+        }
+        if let Some(state) = field(row, "state") {
+            if !entry.1.contains(&state) {
+                entry.1.push(state);
+            }
+        }
+        if let Some(agent) = field(row, "agent") {
+            entry.2 = Some(agent);
+        }
+    }
+
+    let mut tasks: Vec<TaskSummary> = by_task
+        .into_iter()
+        .map(|(id, (title, states, agent))| TaskSummary {
+            state: match escalations.get(&id) {
+                Some((escalated, resets)) => current_state_after_resets(&states, escalated, resets),
+                None => current_state(&states),
+            }
+            .unwrap_or("UNKNOWN")
+            .to_string(),
+            agent: agent.filter(|a| !unassigned.get(&id).is_some_and(|d| d.contains(a))),
+            id,
+            title,
+        })
+        .collect();
+    tasks.sort_by(|a, b| a.title.cmp(&b.title));
+    tasks
+}
+
+/// Lexical value of a literal as returned by Synapse, without quotes or datatype.
+pub fn literal_value(raw: &str) -> Cow<'_, str> {
+    crate::sparql::lexical(raw)
+}
+
+/// Signalled by writes that can give the agency something to do: a task entering
+/// `REQUIREMENTS` or getting `APPROVED`, or an agent going back on `Standby`. Lets the agency skip the rest of its
+/// backoff instead of finding the work on its next poll.
+pub fn arrivals() -> &'static Notify {
+    static ARRIVALS: OnceLock<Notify> = OnceLock::new();
+    ARRIVALS.get_or_init(Notify::new)
+}
+
+/// Signals [`arrivals`] when `triples` hold such a write.
+pub fn announce_arrivals(triples: &[(&str, &str, &str)]) {
+    if triples.iter().any(|(_, p, o)| is_arrival(p, o)) {
+        arrivals().notify_one();
+    }
+}
+
+fn is_arrival(predicate: &str, object: &str) -> bool {
+    match predicate {
+        INTERNAL_STATE => matches!(literal_value(object).as_ref(), "REQUIREMENTS" | "APPROVED"),
+        AGENT_STATUS => literal_value(object) == "Standby",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_value_strips_quotes_and_datatype() {
+        assert_eq!(literal_value(&integer_literal(600)), "600");
+        assert_eq!(literal_value("\"DONE\""), "DONE");
+        assert_eq!(literal_value("42"), "42");
+        assert!(is_arrival(INTERNAL_STATE, "\"REQUIREMENTS\"") && is_arrival(AGENT_STATUS, "\"Standby\""));
+        assert!(is_arrival(INTERNAL_STATE, "\"APPROVED\"") && !is_arrival(INTERNAL_STATE, &TaskState::AwaitingApproval.literal()));
+        assert!(!is_arrival(INTERNAL_STATE, &TaskState::Processing.literal()) && !is_arrival(PRIORITY, "\"REQUIREMENTS\""));
+    }
+
+    #[test]
+    fn tasks_are_summarized_by_their_most_advanced_state() {
+        let rows = vec![
+            serde_json::json!({"task": "<http://swarm.os/task/a>", "title": "\"Fix login\"", "state": "\"TODO\""}),
+            serde_json::json!({"task": "<http://swarm.os/task/a>", "title": "\"Fix login\"", "state": "\"PROCESSING\"", "agent": "<http://swarm.os/agent/PM_1>"}),
+            serde_json::json!({"task": "<http://swarm.os/task/b>", "title": "\"Add docs\"", "state": "\"DONE\""}),
+            serde_json::json!({"task": "<http://swarm.os/task/c>", "title": "\"Retry sync\"", "state": "\"TODO\"", "agent": "<http://swarm.os/agent/Old>", "dropped": "<http://swarm.os/agent/Old>"}),
+        ];
+        let tasks = summarize(&rows);
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[2].agent, None);
+        assert_eq!(tasks[1].state, "PROCESSING");
+        assert_eq!(tasks[1].agent.as_deref(), Some("http://swarm.os/agent/PM_1"));
+        assert_eq!(tasks[0].state, "DONE");
+    }
+
+    #[test]
+    fn a_reset_after_the_newest_escalation_hands_the_task_back() {
+        let row = |state: &str, escalated: &str, reset: Option<&str>| {
+            let mut row = serde_json::json!({
+                "task": "<http://swarm.os/task/a>",
+                "title": "\"Fix login\"",
+                "state": format!("\"{state}\""),
+                "escalated": format!("\"{escalated}\"^^<{XSD_DATETIME}>"),
+            });
+            if let Some(reset) = reset {
+                row["reset"] = serde_json::json!(format!("\"{reset}\"^^<{XSD_DATETIME}>"));
+            }
+            row
+        };
+        let escalated = [row("FAILED", "2026-10-17T09:00:00+00:00", None), row("ESCALATED", "2026-10-17T09:00:00+00:00", None)];
+        assert_eq!(summarize(&escalated)[0].state, "ESCALATED");
+
+        let reset = Some("2026-10-17T10:00:00+00:00");
+        let rows = [
+            row("FAILED", "2026-10-17T09:00:00+00:00", reset),
+            row("ESCALATED", "2026-10-17T09:00:00+00:00", reset),
+            row("RETRY_SCHEDULED", "2026-10-17T09:00:00+00:00", reset),
+        ];
+        assert_eq!(summarize(&rows)[0].state, "RETRY_SCHEDULED");
+
+        // Escalated again after the reset.
+        let mut rows = rows.to_vec();
+        rows.push(row("ESCALATED", "2026-10-17T11:00:00+00:00", reset));
+        assert_eq!(summarize(&rows)[0].state, "ESCALATED");
+    }
+
+    #[test]
+    fn priority_labels_take_the_most_urgent_match() {
+        assert_eq!(priority_from_labels(["frontend", "Priority: High"]), Some(2));
+        assert_eq!(priority_from_labels(["P3", "urgent"]), Some(3));
+        assert_eq!(priority_from_labels(["priority/low"]), Some(0));
+        assert_eq!(priority_from_labels(["highway"]), None);
+    }
+}
@@ -0,0 +1,220 @@
+//! Tracing output. Logs go to stdout, as plain text or, with `LOG_FORMAT=json`, one JSON object
+//! per line carrying the fields of the spans around it (`worker`, `task_id`, `agent_id`).
+//! With `OTEL_EXPORTER_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) set, spans are
+//! also exported over OTLP/gRPC: one per gateway request, Synapse read and write, Trello and
+//! Telegram poll cycle, and orchestrator run.
+//! A task ingested from a Trello card keeps the W3C `traceparent` of its card, so every run of
+//! the task joins the card's trace, and the Python orchestrator gets its run's context as
+//! `TRACEPARENT` to carry it on.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use axum::extract::MatchedPath;
+use axum::http::{HeaderMap, Request};
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::Resource;
+use serde::Deserialize;
+use tracing::{info, info_span, warn, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::{filter_fn, LevelFilter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::sparql::Query;
+use crate::store::Synapse;
+
+/// W3C `traceparent` of the span that ingested a task.
+pub const TRACE_PARENT: &str = "http://swarm.os/ontology/traceParent";
+/// Span around everything a background worker does. Workers run as long as the process, so
+/// this span is kept out of the export, where it would never end; each poll cycle starts its
+/// own trace instead.
+pub const WORKER_SPAN: &str = "worker";
+const DEFAULT_SERVICE_NAME: &str = "swarmd";
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+static EXPORTING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, for Loki or Elasticsearch.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown LOG_FORMAT '{}'; expected text or json", other)),
+        }
+    }
+}
+
+/// Flushes the spans still waiting to be exported when dropped.
+pub struct Telemetry;
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if exporting() {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Installs the log output and, when an OTLP endpoint is set, the span exporter. Logging starts
+/// before the configuration loads, so the `OTEL_*` variables are read from `.env` here too.
+pub fn init() -> anyhow::Result<Telemetry> {
+    let format: LogFormat = dotenvy::var("LOG_FORMAT").unwrap_or_default().parse().map_err(anyhow::Error::msg)?;
+    let endpoint = ["OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "OTEL_EXPORTER_OTLP_ENDPOINT"]
+        .into_iter()
+        .find_map(|name| dotenvy::var(name).ok().filter(|value| !value.trim().is_empty()));
+    let otel = match &endpoint {
+        Some(_) => {
+            let service = dotenvy::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+                .with_trace_config(
+                    opentelemetry_sdk::trace::config().with_resource(Resource::new([KeyValue::new("service.name", service)])),
+                )
+                .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+            opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+            EXPORTING.store(true, Ordering::Relaxed);
+            let spans = tracing_opentelemetry::layer().with_tracer(tracer);
+            Some(spans.with_filter(filter_fn(|metadata| metadata.name() != WORKER_SPAN)))
+        }
+        None => None,
+    };
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with((format == LogFormat::Text).then(tracing_subscriber::fmt::layer))
+        .with((format == LogFormat::Json).then(|| tracing_subscriber::fmt::layer().json().flatten_event(true)))
+        .with(otel)
+        .try_init()?;
+    if let Some(endpoint) = endpoint {
+        info!("🔭 Exporting traces over OTLP to {}", endpoint);
+    }
+    Ok(Telemetry)
+}
+
+/// Whether spans leave the process; without an exporter there is no trace to carry on.
+fn exporting() -> bool {
+    EXPORTING.load(Ordering::Relaxed)
+}
+
+/// The W3C `traceparent` of `span`, when spans are exported.
+pub fn traceparent(span: &Span) -> Option<String> {
+    if !exporting() {
+        return None;
+    }
+    traceparent_of(&span.context())
+}
+
+/// Continues the trace `traceparent` names in `span`. Call it before `span` is entered.
+pub fn set_parent(span: &Span, traceparent: &str) {
+    span.set_parent(context_of(traceparent));
+}
+
+/// Span of a gateway request, named after its route, under the caller's trace when the
+/// request carries a `traceparent` header.
+pub fn http_span<B>(request: &Request<B>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path(), MatchedPath::as_str);
+    let span = info_span!(
+        "http.request",
+        otel.name = %format!("{} {}", request.method(), route),
+        otel.kind = "server",
+        http.method = %request.method(),
+        http.route = route,
+        http.status_code = tracing::field::Empty,
+    );
+    if exporting() && request.headers().contains_key(TRACEPARENT_HEADER) {
+        span.set_parent(TraceContextPropagator::new().extract(&Headers(request.headers())));
+    }
+    span
+}
+
+#[derive(Deserialize)]
+struct ParentRow {
+    parent: String,
+}
+
+/// The `traceparent` recorded on `task_id` when it was ingested, if spans were exported then
+/// and are now.
+pub async fn stored_parent(synapse: &Synapse, task_id: &str) -> Option<String> {
+    if !exporting() {
+        return None;
+    }
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?parent WHERE { $task swarm:traceParent ?parent }
+    "#,
+    )
+    .iri("task", task_id);
+    match synapse.select_as::<ParentRow>(&query).await {
+        Ok(rows) => rows.into_iter().next().map(|row| row.parent),
+        Err(e) => {
+            warn!("Could not read the trace of task {}; its run starts a new one: {}", task_id, e);
+            None
+        }
+    }
+}
+
+fn traceparent_of(cx: &Context) -> Option<String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(cx, &mut carrier);
+    carrier.remove(TRACEPARENT_HEADER)
+}
+
+fn context_of(traceparent: &str) -> Context {
+    let carrier = HashMap::from([(TRACEPARENT_HEADER.to_string(), traceparent.to_string())]);
+    TraceContextPropagator::new().extract(&carrier)
+}
+
+struct Headers<'a>(&'a HeaderMap);
+
+impl Extractor for Headers<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn a_stored_traceparent_round_trips() {
+        let stored = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let cx = context_of(stored);
+        assert_eq!(cx.span().span_context().trace_id().to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(traceparent_of(&cx).as_deref(), Some(stored));
+
+        assert_eq!(traceparent_of(&context_of("not a traceparent")), None);
+    }
+
+    #[test]
+    fn log_format_defaults_to_text() {
+        assert_eq!("".parse(), Ok(LogFormat::Text));
+        assert_eq!(" JSON ".parse(), Ok(LogFormat::Json));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! Triage of new Trello cards and GitHub issues by the native LLM client. With `SWARM_TRIAGE`
+//! on, the agency shows each new one to the model before it first queues it. The model picks
+//! the agent class it needs (`swarm:requiresClass`, unless a label already did), estimates what
+//! it will cost (`swarm:estimatedCostUsd`), and may split a large one into subtasks.
+//!
+//! Subtasks are gateway tasks (`http://swarm.os/task/…`) linked to the card with
+//! `swarm:subtaskOf`, in the same repository and chained with `swarm:dependsOn` as the model
+//! ordered them. The card depends on all of them, so its own run comes last and finds their work
+//! done. A card still waiting for a human's approval is not split, and one that could not be
+//! triaged is queued as it is. A failed triage is recorded too (`swarm:triageFailed`), so the
+//! model is not asked, and paid, again on every tick the card waits for an agent.
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::capabilities::REQUIRES_CLASS;
+use crate::dependencies::DEPENDS_ON;
+use crate::initiatives::REPOSITORY;
+use crate::llm::{Caller, Completion, Llm};
+use crate::provenance::Source;
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks::{self, DESCRIPTION, INTERNAL_STATE, LABEL, PRIORITY};
+use crate::workers::github::ISSUE_PREFIX;
+use crate::workers::trello::CARD_PREFIX;
+
+pub const TRIAGED_AT: &str = "http://swarm.os/ontology/triagedAt";
+/// Why the triage of a task failed; it is queued as it is.
+pub const TRIAGE_FAILED: &str = "http://swarm.os/ontology/triageFailed";
+/// USD the model expects the task to cost.
+pub const ESTIMATED_COST: &str = "http://swarm.os/ontology/estimatedCostUsd";
+/// Links a subtask to the task it was split from.
+pub const SUBTASK_OF: &str = "http://swarm.os/ontology/subtaskOf";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const TASK: &str = "http://swarm.os/ontology/Task";
+const TITLE: &str = "http://swarm.os/ontology/title";
+
+/// Tasks triaged per agency tick, so a burst of new cards does not hold up launches.
+pub const MAX_PER_TICK: usize = 3;
+/// A larger split is cut to this many subtasks.
+const MAX_SUBTASKS: usize = 8;
+
+const SYSTEM_PROMPT: &str = r#"You triage tasks for a swarm of software agents. Answer with one JSON object and nothing else:
+{"class": "<agent class best suited to the task>", "estimated_cost_usd": <LLM spend the task will take>,
+ "subtasks": [{"title": "<imperative title>", "class": "<agent class>", "depends_on": [<indexes of earlier subtasks>]}]}
+Pick classes from the list given. Leave "subtasks" empty unless the task is too large for a single run;
+then list the steps in order, each small enough for one agent."#;
+
+/// A new task as intake wrote it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Arrival {
+    pub task_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub labels: Vec<String>,
+    pub repository: Option<String>,
+    pub priority: Option<i64>,
+    /// Class a label already requires; the model's pick is not recorded over it.
+    pub class: Option<String>,
+    /// Waits for a human's approval, so it is not split: its subtasks would not.
+    pub awaiting_approval: bool,
+}
+
+/// What the model made of a task.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Triage {
+    pub class: Option<String>,
+    pub estimated_cost_usd: Option<f64>,
+    pub subtasks: Vec<Subtask>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Subtask {
+    pub title: String,
+    pub class: Option<String>,
+    /// Indexes of the earlier subtasks this one waits for.
+    pub depends_on: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArrivalRow {
+    task: String,
+    title: String,
+    description: Option<String>,
+    label: Option<String>,
+    repo: Option<String>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    priority: Option<i64>,
+    requires_class: Option<String>,
+    approval: Option<String>,
+}
+
+/// Cards and issues in the intake state that were neither triaged nor queued yet, oldest
+/// IRI first.
+pub async fn arrivals(synapse: &Synapse) -> anyhow::Result<Vec<Arrival>> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?task ?title ?description ?label ?repo ?priority ?requiresClass ?approval WHERE {
+            ?task a swarm:Task ;
+                  swarm:internalState "REQUIREMENTS" ;
+                  swarm:title ?title .
+            FILTER (STRSTARTS(STR(?task), $cards) || STRSTARTS(STR(?task), $issues))
+            FILTER NOT EXISTS { ?task swarm:triagedAt ?triaged }
+            FILTER NOT EXISTS { ?task swarm:queuedAt ?queued }
+            FILTER NOT EXISTS { ?task swarm:internalState "PROCESSING" }
+            FILTER NOT EXISTS { ?task swarm:internalState "CANCELLED" }
+            OPTIONAL { ?task swarm:description ?description }
+            OPTIONAL { ?task swarm:label ?label }
+            OPTIONAL { ?task swarm:repository ?repo }
+            OPTIONAL { ?task swarm:priority ?priority }
+            OPTIONAL { ?task swarm:requiresClass ?requiresClass }
+            OPTIONAL {
+                { ?task swarm:requiresApproval "true" } UNION { ?task swarm:riskLevel "HIGH" }
+                FILTER NOT EXISTS { ?task swarm:approvalState "APPROVED" }
+                BIND ("pending" AS ?approval)
+            }
+        }
+    "#,
+    )
+    .text("cards", CARD_PREFIX)
+    .text("issues", ISSUE_PREFIX);
+    Ok(collect(synapse.select_as(&query).await?))
+}
+
+fn collect(rows: Vec<ArrivalRow>) -> Vec<Arrival> {
+    let mut arrivals: Vec<Arrival> = Vec::new();
+    for row in rows {
+        let index = match arrivals.iter().position(|a| a.task_id == row.task) {
+            Some(index) => index,
+            None => {
+                arrivals.push(Arrival { task_id: row.task.clone(), title: row.title.clone(), ..Arrival::default() });
+                arrivals.len() - 1
+            }
+        };
+        let arrival = &mut arrivals[index];
+        arrival.description = arrival.description.take().or(row.description);
+        arrival.repository = arrival.repository.take().or(row.repo);
+        arrival.priority = arrival.priority.or(row.priority);
+        arrival.class = arrival.class.take().or(row.requires_class);
+        arrival.awaiting_approval |= row.approval.is_some();
+        if let Some(label) = row.label.filter(|l| !arrival.labels.contains(l)) {
+            arrival.labels.push(label);
+        }
+    }
+    arrivals.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+    arrivals
+}
+
+/// Agent classes of the roster, to choose from.
+pub async fn classes(synapse: &Synapse) -> anyhow::Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct ClassRow {
+        class: String,
+    }
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT DISTINCT ?class WHERE {
+            ?agent a swarm:Agent ;
+                   swarm:class ?class .
+            FILTER NOT EXISTS { ?agent swarm:status "Retired" }
+        }
+    "#;
+    let mut classes: Vec<String> = synapse.query_as::<ClassRow>(query).await?.into_iter().map(|row| row.class).collect();
+    classes.sort();
+    classes.dedup();
+    Ok(classes)
+}
+
+/// Asks the model about `arrival`, charging the call to the task.
+pub async fn assess(llm: &Llm, arrival: &Arrival, classes: &[String]) -> anyhow::Result<(Triage, Completion)> {
+    let caller = Caller { agent_id: Some("swarmd".into()), task_id: Some(arrival.task_id.clone()) };
+    let completion = llm.complete(&caller, SYSTEM_PROMPT, &prompt(arrival, classes)).await?;
+    let mut triage = parse(&completion.text, classes)?;
+    if arrival.awaiting_approval {
+        triage.subtasks.clear();
+    }
+    Ok((triage, completion))
+}
+
+fn prompt(arrival: &Arrival, classes: &[String]) -> String {
+    let task = json!({
+        "title": arrival.title,
+        "description": arrival.description,
+        "labels": arrival.labels,
+    });
+    format!("Agent classes: {}\n\nTask:\n{}", classes.join(", "), task)
+}
+
+/// The triage in a reply, keeping only classes from `classes` and dependencies on earlier
+/// subtasks.
+fn parse(reply: &str, classes: &[String]) -> anyhow::Result<Triage> {
+    // Models like to wrap JSON in a code fence or a sentence.
+    let start = reply.find('{').context("the triage reply holds no JSON object")?;
+    let end = reply.rfind('}').filter(|end| *end > start).context("the triage reply holds no JSON object")?;
+    let mut triage: Triage = serde_json::from_str::<Value>(&reply[start..=end])
+        .and_then(serde_json::from_value)
+        .context("the triage reply is not a triage")?;
+
+    let known = |class: Option<String>| class.and_then(|c| classes.iter().find(|k| k.eq_ignore_ascii_case(c.trim())).cloned());
+    triage.class = known(triage.class.take());
+    triage.estimated_cost_usd = triage.estimated_cost_usd.filter(|usd| usd.is_finite() && *usd >= 0.0);
+    triage.subtasks.retain(|s| !s.title.trim().is_empty());
+    triage.subtasks.truncate(MAX_SUBTASKS);
+    for (index, subtask) in triage.subtasks.iter_mut().enumerate() {
+        subtask.title = subtask.title.trim().chars().take(200).collect();
+        subtask.class = known(subtask.class.take());
+        subtask.depends_on.retain(|d| *d < index);
+        subtask.depends_on.sort_unstable();
+        subtask.depends_on.dedup();
+    }
+    // One step is the task itself.
+    if triage.subtasks.len() < 2 {
+        triage.subtasks.clear();
+    }
+    Ok(triage)
+}
+
+/// Records `triage` on `arrival` and creates its subtasks; returns their IRIs.
+pub async fn record(synapse: &Synapse, arrival: &Arrival, triage: &Triage) -> anyhow::Result<Vec<String>> {
+    let subtask_ids: Vec<String> = triage.subtasks.iter().map(|_| format!("http://swarm.os/task/{}", uuid::Uuid::new_v4())).collect();
+    let triples = triples(arrival, triage, &subtask_ids, &chrono::Utc::now());
+    synapse
+        .ingest_from(Source::Agency, triples.iter().map(|(s, p, o)| (s.as_str(), *p, o.as_str())).collect())
+        .await?;
+    Ok(subtask_ids)
+}
+
+/// Records that `arrival` could not be triaged, so later ticks queue it without asking again.
+pub async fn record_failure(synapse: &Synapse, arrival: &Arrival, reason: &str) -> anyhow::Result<()> {
+    let triaged_at = tasks::datetime_literal(&chrono::Utc::now());
+    let reason = sparql::literal(reason);
+    let task = arrival.task_id.as_str();
+    synapse.ingest_from(Source::Agency, vec![(task, TRIAGED_AT, triaged_at.as_str()), (task, TRIAGE_FAILED, reason.as_str())]).await
+}
+
+fn triples(arrival: &Arrival, triage: &Triage, subtask_ids: &[String], now: &chrono::DateTime<chrono::Utc>) -> Vec<(String, &'static str, String)> {
+    let task = arrival.task_id.as_str();
+    let triaged_at = tasks::datetime_literal(now);
+    let description = match &arrival.description {
+        Some(description) => format!("Part of \"{}\":\n\n{}", arrival.title, description),
+        None => format!("Part of \"{}\"", arrival.title),
+    };
+    let mut triples = vec![(task.to_string(), TRIAGED_AT, triaged_at.clone())];
+    if let Some(usd) = triage.estimated_cost_usd {
+        triples.push((task.to_string(), ESTIMATED_COST, tasks::decimal_literal(usd)));
+    }
+    if let (None, Some(class)) = (&arrival.class, &triage.class) {
+        triples.push((task.to_string(), REQUIRES_CLASS, sparql::literal(class)));
+    }
+
+    for (subtask, id) in triage.subtasks.iter().zip(subtask_ids) {
+        triples.push((id.clone(), RDF_TYPE, TASK.to_string()));
+        triples.push((id.clone(), TITLE, sparql::literal(&subtask.title)));
+        triples.push((id.clone(), INTERNAL_STATE, "\"REQUIREMENTS\"".to_string()));
+        triples.push((id.clone(), SUBTASK_OF, task.to_string()));
+        triples.push((id.clone(), TRIAGED_AT, triaged_at.clone()));
+        triples.push((id.clone(), DESCRIPTION, sparql::literal(&description)));
+        if let Some(repo) = &arrival.repository {
+            triples.push((id.clone(), REPOSITORY, repo.clone()));
+        }
+        if let Some(priority) = arrival.priority {
+            triples.push((id.clone(), PRIORITY, tasks::integer_literal(priority)));
+        }
+        for label in &arrival.labels {
+            triples.push((id.clone(), LABEL, sparql::literal(label)));
+        }
+        if let Some(class) = &subtask.class {
+            triples.push((id.clone(), REQUIRES_CLASS, sparql::literal(class)));
+        }
+        triples.extend(subtask.depends_on.iter().map(|d| (id.clone(), DEPENDS_ON, subtask_ids[*d].clone())));
+        triples.push((task.to_string(), DEPENDS_ON, id.clone()));
+    }
+    triples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_are_kept_to_known_classes_and_earlier_steps() {
+        let classes = ["Coder".to_string(), "Reviewer".to_string()];
+        let reply = r#"Here you go:
+```json
+{"class": "coder", "estimated_cost_usd": 1.5, "subtasks": [
+  {"title": " Add the SSO settings ", "class": "Coder"},
+  {"title": "Wire the login flow", "class": "Designer", "depends_on": [0, 0, 1, 5]},
+  {"title": "", "class": "Reviewer"}]}
+```"#;
+        let triage = parse(reply, &classes).unwrap();
+        assert_eq!(triage.class.as_deref(), Some("Coder"));
+        assert_eq!(triage.estimated_cost_usd, Some(1.5));
+        assert_eq!(
+            triage.subtasks,
+            vec![
+                Subtask { title: "Add the SSO settings".into(), class: Some("Coder".into()), depends_on: vec![] },
+                Subtask { title: "Wire the login flow".into(), class: None, depends_on: vec![0] },
+            ]
+        );
+
+        let single = parse(r#"{"class": "Reviewer", "estimated_cost_usd": -1, "subtasks": [{"title": "All of it"}]}"#, &classes).unwrap();
+        assert_eq!(single, Triage { class: Some("Reviewer".into()), estimated_cost_usd: None, subtasks: vec![] });
+        assert!(parse("I cannot help with that.", &classes).is_err());
+    }
+
+    #[test]
+    fn the_card_waits_for_its_subtasks_and_keeps_a_labelled_class() {
+        let arrival = Arrival {
+            task_id: format!("{}abc", CARD_PREFIX),
+            title: "Add SSO".into(),
+            repository: Some("http://swarm.os/repository/api".into()),
+            labels: vec!["backend".into()],
+            class: Some("Reviewer".into()),
+            ..Arrival::default()
+        };
+        let triage = Triage {
+            class: Some("Coder".into()),
+            estimated_cost_usd: Some(2.0),
+            subtasks: vec![
+                Subtask { title: "Settings".into(), class: Some("Coder".into()), depends_on: vec![] },
+                Subtask { title: "Login".into(), class: None, depends_on: vec![0] },
+            ],
+        };
+        let ids = ["http://swarm.os/task/1".to_string(), "http://swarm.os/task/2".to_string()];
+        let triples = triples(&arrival, &triage, &ids, &chrono::Utc::now());
+        let has = |s: &str, p: &str, o: &str| triples.iter().any(|t| t.0 == s && t.1 == p && t.2 == o);
+
+        assert!(!triples.iter().any(|t| t.0 == arrival.task_id && t.1 == REQUIRES_CLASS));
+        assert!(has(&arrival.task_id, ESTIMATED_COST, &tasks::decimal_literal(2.0)));
+        assert!(has(&arrival.task_id, DEPENDS_ON, &ids[0]) && has(&arrival.task_id, DEPENDS_ON, &ids[1]));
+        assert!(has(&ids[1], DEPENDS_ON, &ids[0]) && !has(&ids[0], DEPENDS_ON, &ids[1]));
+        assert!(has(&ids[0], SUBTASK_OF, &arrival.task_id) && has(&ids[0], REQUIRES_CLASS, "\"Coder\""));
+        assert!(has(&ids[1], REPOSITORY, "http://swarm.os/repository/api") && has(&ids[1], LABEL, "\"backend\""));
+        assert!(has(&ids[1], INTERNAL_STATE, "\"REQUIREMENTS\""));
+    }
+
+    #[tokio::test]
+    async fn a_failed_triage_is_not_asked_again() {
+        let (store, synapse) = crate::store::MemoryStore::shared();
+        let arrival = Arrival { task_id: format!("{}abc", CARD_PREFIX), title: "Add SSO".into(), ..Arrival::default() };
+        record_failure(&synapse, &arrival, "reply was not JSON").await.unwrap();
+        let triples = store.triples();
+        assert!(triples.iter().any(|(s, p, _)| s == &arrival.task_id && p == TRIAGED_AT));
+        assert!(triples.iter().any(|(s, p, o)| s == &arrival.task_id && p == TRIAGE_FAILED && o == "\"reply was not JSON\""));
+    }
+}
@@ -0,0 +1,68 @@
+//! Evaluates the achievement rules over Synapse every few minutes and announces each new one;
+//! see [`crate::achievements`].
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+use crate::achievements;
+use crate::config::AppConfig;
+use crate::health::Health;
+use crate::notifications::{Message, Notification};
+use crate::provenance::Source;
+use crate::store::Synapse;
+
+const POLL: Duration = Duration::from_secs(300);
+
+pub async fn run_achievements(
+    synapse: Synapse,
+    health: Arc<Health>,
+    config: watch::Receiver<AppConfig>,
+    tx: mpsc::Sender<Notification>,
+) {
+    info!("🏆 Achievements worker started");
+    loop {
+        health.beat("achievements");
+        let (enabled, thresholds) = {
+            let cfg = config.borrow();
+            (cfg.worker_enabled("achievements"), crate::experience::level_thresholds(cfg.level_thresholds.as_deref()))
+        };
+        if enabled {
+            if let Err(e) = tick(&synapse, &tx, &thresholds).await {
+                warn!("⚠️ Achievements could not be evaluated: {}", e);
+            }
+        }
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+async fn tick(synapse: &Synapse, tx: &mpsc::Sender<Notification>, thresholds: &[i64]) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let facts = achievements::facts(synapse).await?;
+    let known: HashSet<(String, String)> = achievements::all(synapse).await?.into_iter().map(|a| (a.agent, a.kind)).collect();
+    for (agent, kind) in achievements::earned(&facts, now) {
+        if known.contains(&(agent.clone(), kind.as_str().to_string())) {
+            continue;
+        }
+        achievements::record(synapse, &agent, kind, now).await?;
+        let name = agent.rsplit('/').next().unwrap_or(&agent).to_string();
+        info!("🏆 {} earned {}", name, kind.title());
+        let mut message = Message::new()
+            .text("🏆 ")
+            .strong(&name)
+            .text(" earned ")
+            .strong(kind.title())
+            .text(format!(" (+{} XP)", kind.reward()));
+        match crate::experience::grant(synapse, Source::Scheduler, &agent, kind.reward(), thresholds).await {
+            Ok(award) if award.leveled_up() => message = message.text(format!(" and reached level {}", award.level)),
+            Ok(_) => {}
+            Err(e) => warn!("Could not grant the reward of {} to {}: {}", kind.as_str(), agent, e),
+        }
+        let _ = tx.send(Notification::trace(message).with_source(Source::Scheduler)).await;
+    }
+    Ok(())
+}
@@ -1,94 +1,1386 @@
-use std::time::Duration;
+mod cancel;
+mod chores;
+mod context;
+mod handoff;
+mod pacing;
+mod queue;
+mod result;
+mod retry;
+mod runtime;
+mod scoring;
+mod stalls;
+mod strategy;
+mod trace;
+mod worktree;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+use tokio::sync::{mpsc, watch};
 use tokio::time::sleep;
-use tracing::{info, error};
-use crate::synapse::SynapseClient;
-use serde_json::Value;
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+use crate::approvals;
+use crate::budget::BudgetLimit;
+use crate::capabilities::Requirements;
+use crate::escalation;
+use crate::config::AppConfig;
+use crate::health::Health;
+use crate::llm::Llm;
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::hooks::{HookEvent, Hooks, TaskHookPayload};
+use crate::notifications::{Message, Notification};
+use crate::provenance::Source;
+use crate::server::contracts::TokenUsage;
+use crate::state;
+use crate::resource_usage::{self, ResourceUsage, Sampler};
+use crate::workers::github::pulls::{self, PullRef};
+use crate::workers::supervisor;
+use crate::tasks::{self, TaskState, AGENT_STATUS, ASSIGNED_TO, INTERNAL_STATE};
+use crate::telemetry;
+use crate::triage;
+use serde::Deserialize;
+
+pub use cancel::{cancel, CancelOutcome};
+pub use retry::RetryPolicy;
+pub use stalls::watch_stalls;
+use chores::Chores;
+use context::Contexts;
+use handoff::Handoffs;
+use pacing::{Outcome, Pacing};
+use queue::Aging;
+use result::{ResultStatus, RunResult};
+use retry::RetryQueue;
+use scoring::{AgentCandidate, ScriptChange, ScriptSlot, TaskCandidate};
+use strategy::{Assignments, Strategies, Strategy, SuccessCounts};
+use trace::Stream;
+use worktree::{Workdir, Worktrees};
+
+/// One attempt at running a task on a specific agent.
+#[derive(Debug, Clone)]
+pub struct TaskRun {
+    pub task_id: String,
+    pub title: String,
+    pub agent_id: String,
+    pub attempt: u32,
+    /// Wall-clock budget for the orchestrator process before it is killed.
+    pub timeout: Duration,
+    /// Failure reports of the earlier attempts, handed to humans on escalation.
+    pub failures: Vec<String>,
+    /// Set for a background chore: what it may still spend today. See [`chores`].
+    pub chore_budget_usd: Option<f64>,
+}
+
+/// How the agency runs tasks; follows config reloads.
+#[derive(Debug, Clone)]
+pub struct AgencySettings {
+    pub policy: RetryPolicy,
+    pub default_timeout: Duration,
+    /// Hold tasks whose run opened a pull request until it merges (GitHub tracking is on).
+    pub await_merge: bool,
+    /// Exhausted tasks go to these humans instead of the dead letter queue.
+    pub escalation_owners: Vec<String>,
+    /// Rhai script that scores (task, agent) pairs; see [`scoring`].
+    pub scheduler_script: Option<String>,
+    /// Which free agent gets a task when no script decides; see [`strategy`].
+    pub strategies: Strategies,
+    /// Forward what running orchestrators print as traces; see [`trace`].
+    pub stream_output: bool,
+    /// What idle agents do when no task is runnable; see [`chores`].
+    pub chores: Chores,
+    /// How waiting raises a task's priority; see [`queue`].
+    pub aging: Aging,
+    /// Percentages of the daily budget that raise an alert.
+    pub budget_thresholds: Vec<u32>,
+    /// Experience needed for each level past the first; see [`crate::experience`].
+    pub level_thresholds: Vec<i64>,
+}
+
+impl AgencySettings {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            policy: RetryPolicy::from_config(cfg),
+            default_timeout: Duration::from_secs(cfg.agency_task_timeout_secs),
+            await_merge: cfg.github_token.is_some(),
+            escalation_owners: escalation::owners_from_config(cfg.escalation_owners.as_deref()),
+            scheduler_script: cfg.scheduler_script.clone(),
+            strategies: assignment_strategies(cfg).unwrap_or_else(|e| {
+                warn!("Invalid assignment strategy, using first-available: {}", e);
+                Strategies::default()
+            }),
+            stream_output: cfg.agency_stream_output,
+            chores: agency_chores(cfg).unwrap_or_else(|e| {
+                warn!("Invalid chore settings, idle agents get no chores: {}", e);
+                Chores::default()
+            }),
+            aging: Aging::from_hours(cfg.priority_aging_hours),
+            budget_thresholds: crate::budget::alert_thresholds(cfg.budget_alert_thresholds.as_deref()),
+            level_thresholds: crate::experience::level_thresholds(cfg.level_thresholds.as_deref()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Agency {
+    synapse: Synapse,
+    hooks: Hooks,
+    policy: RetryPolicy,
+    retries: RetryQueue,
+    default_timeout: Duration,
+    await_merge: bool,
+    escalation_owners: Vec<String>,
+    scheduler_script: Option<String>,
+    scripts: ScriptSlot,
+    strategies: Strategies,
+    stream_output: bool,
+    chores: Chores,
+    aging: Aging,
+    assignments: Assignments,
+    handoffs: Handoffs,
+    worktrees: Worktrees,
+    contexts: Contexts,
+    /// Where runs find Synapse; see [`context`].
+    synapse_env: Vec<(&'static str, String)>,
+    budget: BudgetLimit,
+    budget_thresholds: Vec<u32>,
+    level_thresholds: Vec<i64>,
+    /// Triages new cards and issues; see [`triage`].
+    triage: Option<Llm>,
+    tx: mpsc::Sender<Notification>,
+}
+
+/// The LLM client that triages new tasks when `SWARM_TRIAGE` is on.
+fn triage_client(cfg: &AppConfig, synapse: &Synapse, budget: &BudgetLimit) -> Option<Llm> {
+    if !cfg.triage {
+        return None;
+    }
+    match Llm::from_config(cfg, synapse.clone(), budget.clone()) {
+        Ok(Some(llm)) => Some(llm),
+        Ok(None) => {
+            warn!("SWARM_TRIAGE is on but no LLM API key is set; new tasks are queued untriaged");
+            None
+        }
+        Err(e) => {
+            warn!("Invalid LLM settings, new tasks are queued untriaged: {}", e);
+            None
+        }
+    }
+}
 
-pub async fn start_agency(synapse: SynapseClient) {
+/// The `name` directory under `artifacts_dir` that runs share files through. It is made
+/// absolute, so its paths stay valid for runs in another working directory or a container.
+fn run_dir(artifacts_dir: &str, name: &str) -> std::path::PathBuf {
+    let dir = std::path::Path::new(artifacts_dir).join(name);
+    std::path::absolute(&dir).unwrap_or(dir)
+}
+
+/// Compiles the scheduler script without running it, for `swarmd check-config`.
+pub fn check_scheduler_script(path: &str) -> anyhow::Result<()> {
+    scoring::ScoringScript::load(std::path::Path::new(path)).map(|_| ())
+}
+
+/// `ASSIGNMENT_STRATEGY` and `ASSIGNMENT_STRATEGIES`, parsed.
+pub fn assignment_strategies(cfg: &AppConfig) -> Result<Strategies, String> {
+    Strategies::parse(cfg.assignment_strategy.as_deref(), cfg.assignment_strategies.as_deref())
+}
+
+/// `AGENCY_CHORES`, `CHORE_DAILY_BUDGET_USD` and `CHORE_INTERVAL_HOURS`, parsed.
+pub fn agency_chores(cfg: &AppConfig) -> Result<Chores, String> {
+    Chores::parse(cfg.agency_chores.as_deref(), cfg.chore_daily_budget_usd, cfg.chore_interval_hours)
+}
+
+pub async fn start_agency(
+    synapse: Synapse,
+    mut config: watch::Receiver<AppConfig>,
+    budget: BudgetLimit,
+    health: Arc<Health>,
+    tx: mpsc::Sender<Notification>,
+) {
     info!("🤖 Agent Agency system initialized. Monitoring for new tasks...");
 
+    let cfg = config.borrow_and_update().clone();
+    let settings = AgencySettings::from_config(&cfg);
+    let mut agency = Agency {
+        synapse: synapse.clone(),
+        hooks: Hooks::from_config(&cfg),
+        policy: settings.policy,
+        retries: RetryQueue::default(),
+        default_timeout: settings.default_timeout,
+        await_merge: settings.await_merge,
+        escalation_owners: settings.escalation_owners,
+        scheduler_script: settings.scheduler_script,
+        scripts: ScriptSlot::default(),
+        strategies: settings.strategies,
+        stream_output: settings.stream_output,
+        chores: settings.chores,
+        aging: settings.aging,
+        budget_thresholds: settings.budget_thresholds,
+        level_thresholds: settings.level_thresholds,
+        assignments: Assignments::default(),
+        handoffs: Handoffs::new(&cfg.artifacts_dir),
+        worktrees: Worktrees::new(&cfg.artifacts_dir),
+        contexts: Contexts::new(&cfg.artifacts_dir),
+        synapse_env: context::synapse_env(&cfg),
+        triage: triage_client(&cfg, &synapse, &budget),
+        budget,
+        tx,
+    };
+    agency.recover_interrupted_runs().await;
+    let mut in_maintenance = false;
+    let mut paused = false;
+    let mut pacing = Pacing::default();
+
     loop {
+        health.beat("agency");
+
+        // Runs already in flight finish with the settings they started with.
+        if config.has_changed().unwrap_or(false) {
+            let cfg = config.borrow_and_update().clone();
+            agency.reconfigure(&cfg);
+        }
+        agency.refresh_scheduler_script().await;
+        if !config.borrow().worker_enabled("agency") {
+            if !paused {
+                info!("⏸️ Agency disabled by DISABLED_WORKERS; no tasks will be launched");
+                paused = true;
+            }
+            sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+        if paused {
+            info!("▶️ Agency enabled again");
+            paused = false;
+        }
+        agency.hold_for_approval().await;
+
         // Simple logic:
-        // 1. Fetch active tasks (REQUIREMENTS)
+        // 0. Hold all new work during maintenance or while today's spend is over budget
+        // 1. Relaunch failed tasks whose backoff has elapsed, and tasks a human reset
         // 2. Fetch available agents (Standby)
-        // 3. Assign task to agent by updating agent's status
-        
+        // 3. Pick the most urgent active task (REQUIREMENTS)
+        // 4. Assign task to agent by updating agent's status
+        if crate::maintenance::is_active() {
+            if !in_maintenance {
+                info!("🚧 Maintenance mode: intake continues, no tasks will be launched");
+                in_maintenance = true;
+            }
+            sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+        if in_maintenance {
+            info!("🚧 Maintenance over; working through the queued backlog by priority");
+            in_maintenance = false;
+        }
+
+        if agency.over_budget().await {
+            sleep(Duration::from_secs(60)).await;
+            continue;
+        }
+
+        agency.launch_due_retries().await;
+        let untriaged = agency.triage_arrivals().await;
+
+        let outcome = match agency.next_assignment(&untriaged).await {
+            Ok(Some(run)) => {
+                agency.launch(run).await;
+                Outcome::Launched
+            }
+            Ok(None) => agency.fill_idle_time().await,
+            Err(e) => {
+                error!("Agency query failed: {}", e);
+                Outcome::Failed
+            }
+        };
+        pacing.record(outcome);
+        supervisor::shared().report_poll_interval("agency", pacing.interval());
+        agency.pause(&mut pacing).await;
+    }
+}
+
+impl Agency {
+    /// Waits out the pacing interval, or less when a retry falls due sooner or new work
+    /// arrives in the meantime.
+    async fn pause(&self, pacing: &mut Pacing) {
+        let mut pause = pacing.interval();
+        if let Some(due) = self.retries.next_due().await {
+            pause = pause.min(due.saturating_duration_since(Instant::now()).max(pacing::BASE));
+        }
+        tokio::select! {
+            _ = sleep(pause) => {}
+            _ = tasks::arrivals().notified() => pacing.reset(),
+        }
+    }
+
+    fn reconfigure(&mut self, cfg: &AppConfig) {
+        let settings = AgencySettings::from_config(cfg);
+        self.policy = settings.policy;
+        self.default_timeout = settings.default_timeout;
+        self.await_merge = settings.await_merge;
+        self.escalation_owners = settings.escalation_owners;
+        self.scheduler_script = settings.scheduler_script;
+        self.strategies = settings.strategies;
+        self.stream_output = settings.stream_output;
+        self.chores = settings.chores;
+        self.aging = settings.aging;
+        self.budget_thresholds = settings.budget_thresholds;
+        self.level_thresholds = settings.level_thresholds;
+        self.hooks = Hooks::from_config(cfg);
+        self.triage = triage_client(cfg, &self.synapse, &self.budget);
+    }
+
+    /// Picks up edits to the scheduler script; a broken one is reported once per change.
+    async fn refresh_scheduler_script(&mut self) {
+        match self.scripts.refresh(self.scheduler_script.as_deref()) {
+            Some(ScriptChange::Loaded(path)) => info!("📜 Scheduling with the policy script {}", path),
+            Some(ScriptChange::Removed) => info!("📜 Scheduler script removed; back to the built-in policy"),
+            Some(ScriptChange::Failed(e)) => {
+                warn!("Scheduler script unusable, using the built-in policy: {}", e);
+                let _ = self.tx.send(Notification::alert(
+                    Message::new().text("Scheduler script unusable, using the built-in policy: ").code(e),
+                ).with_source(Source::Agency)).await;
+            }
+            None => {}
+        }
+    }
+
+    /// Alerts on each budget threshold today's spend passes, once per day, and throttles the
+    /// swarm while the whole budget is spent. True while new work must wait.
+    async fn over_budget(&self) -> bool {
+        let spent = match crate::budget::spent_today(&self.synapse).await {
+            Ok(spent) => spent,
+            Err(e) => {
+                // Fail open: the assignment query below will surface a Synapse outage anyway.
+                warn!("Could not read today's spend, skipping budget gate: {}", e);
+                return false;
+            }
+        };
+        let tokens = crate::budget::tokens_today(&self.synapse, &self.budget).await.unwrap_or_else(|e| {
+            warn!("Could not read today's tokens, skipping the token cap: {}", e);
+            TokenUsage::default()
+        });
+        let max = self.budget.get();
+        self.alert_budget_thresholds("budget", spent, max, format!("${:.2} spent of ${:.2}", spent, max)).await;
+        if let Some(max_tokens) = tokens.max {
+            let usage = format!("{} of {} tokens", tokens.total(), max_tokens);
+            self.alert_budget_thresholds("token budget", tokens.total() as f64, max_tokens as f64, usage).await;
+        }
+
+        let status = match super::ops::operational_status(&self.synapse).await {
+            Ok(status) => status,
+            Err(e) => {
+                // Without the current status, leave it alone rather than overwrite a halt.
+                warn!("Could not read the operational status: {}", e);
+                return self.budget.exhausted(spent, &tokens);
+            }
+        };
+        if self.budget.exhausted(spent, &tokens) {
+            // A halted or degraded system keeps the status a human or a probe gave it.
+            if matches!(status.as_deref(), None | Some("OPERATIONAL" | "UNKNOWN")) {
+                warn!("💸 Daily budget exhausted (${:.2} of ${:.2}, {} tokens); throttling new work", spent, max, tokens.total());
+                if let Err(e) = super::ops::set_operational_status("THROTTLED", Source::Agency, "budget", &self.synapse).await {
+                    warn!("Could not set the THROTTLED status: {}", e);
+                }
+            }
+            return true;
+        }
+        if status.as_deref() == Some("THROTTLED") {
+            match super::ops::set_operational_status("OPERATIONAL", Source::Agency, "budget", &self.synapse).await {
+                Ok(()) => {
+                    info!("💰 Spend is under the daily budget again; throttle lifted");
+                    let _ = self.tx.send(Notification::trace(format!(
+                        "Budget throttle lifted: ${:.2} spent of ${:.2}, {} tokens. New tasks start again.",
+                        spent, max, tokens.total()
+                    )).with_source(Source::Agency)).await;
+                }
+                Err(e) => warn!("Could not lift the THROTTLED status: {}", e),
+            }
+        }
+        false
+    }
+
+    /// Alerts once a day for each threshold `used` of `max` has reached; `budget` names the
+    /// meter and `usage` reads it out.
+    async fn alert_budget_thresholds(&self, budget: &str, used: f64, max: f64, usage: String) {
+        let today = crate::clock::today();
+        let state = crate::state::shared();
+        let sent = match state.seen("budget-alerts") {
+            Ok(sent) => sent,
+            Err(e) => {
+                warn!("Could not read the budget alerts already sent: {}", e);
+                return;
+            }
+        };
+        for threshold in crate::budget::crossed(&self.budget_thresholds, used, max) {
+            let key = format!("{}@{}@{}", today, budget, threshold);
+            if sent.contains(&key) {
+                continue;
+            }
+            if let Err(e) = state.mark_seen("budget-alerts", [key.as_str()]) {
+                warn!("Could not record the {}% {} alert: {}", threshold, budget, e);
+                continue;
+            }
+            let text = format!("Daily {} {}% used: {}.", budget, threshold, usage);
+            let notification = if threshold >= 100 {
+                Notification::critical(format!("{} The swarm is THROTTLED; new tasks wait until tomorrow or a higher budget.", text))
+            } else {
+                Notification::alert(text)
+            };
+            let _ = self.tx.send(notification.with_source(Source::Agency)).await;
+        }
+    }
+
+    /// Marks the directories a finished run changed files in as explored; see [`crate::exploration`].
+    async fn record_exploration(&self, workdir: &Workdir) {
+        let recorded = async {
+            let files = workdir.changed_files().await?;
+            let directories = crate::exploration::directories_of(files.iter().map(String::as_str));
+            crate::exploration::record(&self.synapse, Source::Agency, &workdir.repository, &directories).await
+        };
+        if let Err(e) = recorded.await {
+            warn!("Could not record what the run explored in {}: {:#}", workdir.repository, e);
+        }
+    }
+
+    /// Credits the run's agent with its finished task and announces a level-up; see
+    /// [`crate::experience`].
+    async fn award_experience(&self, run: &TaskRun, wall_seconds: f64) {
+        let award = match crate::experience::award(&self.synapse, &run.task_id, &run.agent_id, wall_seconds, &self.level_thresholds).await {
+            Ok(Some(award)) => award,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Could not award experience for task {} to {}: {:#}", run.task_id, run.agent_id, e);
+                return;
+            }
+        };
+        if !award.leveled_up() {
+            return;
+        }
+        info!("⭐ {} reached level {} ({} XP)", short_id(&run.agent_id), award.level, award.experience);
+        let message = crate::experience::level_up_message(&run.agent_id, &award, &run.title);
+        let _ = self.tx.send(Notification::trace(message).with_source(Source::Agency).with_task(&run.task_id)).await;
+    }
+
+    /// A runnable task paired with a free agent: the best pair according to the scheduler
+    /// script, or else the most urgent task and the agent its repository's strategy picks.
+    async fn next_assignment(&self, untriaged: &[String]) -> anyhow::Result<Option<TaskRun>> {
+        let agents = self.standby_agents().await?;
+        if agents.is_empty() {
+            return Ok(None);
+        }
+
+        let mut runnable = self.runnable_tasks().await?;
+        runnable.retain(|row| !untriaged.contains(&row.task));
+        let now = chrono::Utc::now();
+        self.stamp_queued(&runnable, now).await;
+        let requirements = requirements_by_task(&runnable);
+        let rows = self.aging.order(&runnable, now);
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        let candidates: Vec<TaskCandidate> = rows
+            .iter()
+            .map(|row| task_candidate(row, self.aging.priority(row, now), &requirements))
+            .collect();
+
+        let (task_index, agent_index) = match self.scripts.script() {
+            None => match self.pick_pair(&candidates, &agents).await {
+                Some(pair) => pair,
+                None => return Ok(None),
+            },
+            Some(script) => {
+                let tasks: Vec<TaskCandidate> = candidates.iter().take(scoring::MAX_CANDIDATES).cloned().collect();
+                let free = agents.clone();
+                let scored = tokio::task::spawn_blocking({
+                    let script = script.clone();
+                    move || script.best(&tasks, &free)
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+                match scored {
+                    Ok(Some(pair)) => pair,
+                    // The script ruled out every pair; nothing runs this tick.
+                    Ok(None) => return Ok(None),
+                    Err(e) => {
+                        if script.first_report(&e) {
+                            warn!("Scheduler script failed, using the built-in policy: {}", e);
+                            let _ = self.tx.send(Notification::alert(
+                                Message::new().text("Scheduler script failed, using the built-in policy: ").code(&e),
+                            ).with_source(Source::Agency)).await;
+                        }
+                        match self.pick_pair(&candidates, &agents).await {
+                            Some(pair) => pair,
+                            None => return Ok(None),
+                        }
+                    }
+                }
+            }
+        };
+        let item = rows[task_index];
+        let agent_id = agents[agent_index].id.clone();
+        self.assignments.record(&candidates[task_index].repo, &agent_id);
+
+        let timeout = item
+            .timeout
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(self.default_timeout);
+
+        Ok(Some(TaskRun {
+            task_id: item.task.clone(),
+            title: item.title.clone(),
+            agent_id,
+            attempt: 1,
+            timeout,
+            failures: Vec::new(),
+            chore_budget_usd: None,
+        }))
+    }
+
+    /// Parks new tasks that need a human's approval in `AWAITING_APPROVAL` and asks for it.
+    /// Runs during maintenance too, so decisions can be gathered before work resumes.
+    async fn hold_for_approval(&self) {
+        let requests = match approvals::unheld(&self.synapse).await {
+            Ok(requests) => requests,
+            Err(e) => {
+                warn!("Could not look for tasks awaiting approval: {}", e);
+                return;
+            }
+        };
+        for request in requests {
+            if let Err(e) = approvals::hold(&self.synapse, &request.task_id).await {
+                warn!("Could not hold {} for approval: {}", request.task_id, e);
+                continue;
+            }
+            info!("🛡️ Task {} held for approval: {}", request.task_id, request.reason);
+            let _ = self.tx.send(Notification::approval(request).with_source(Source::Agency)).await;
+        }
+    }
+
+    /// Triages up to [`triage::MAX_PER_TICK`] new cards and issues. Returns the ones left for
+    /// later ticks, which must not be queued before then.
+    async fn triage_arrivals(&self) -> Vec<String> {
+        let Some(llm) = &self.triage else { return Vec::new() };
+        let arrivals = match triage::arrivals(&self.synapse).await {
+            Ok(arrivals) => arrivals,
+            Err(e) => {
+                warn!("Could not look for tasks to triage: {}", e);
+                return Vec::new();
+            }
+        };
+        if arrivals.is_empty() {
+            return Vec::new();
+        }
+        let classes = triage::classes(&self.synapse).await.unwrap_or_else(|e| {
+            warn!("Could not read the agent classes for triage: {}", e);
+            Vec::new()
+        });
+
+        for arrival in arrivals.iter().take(triage::MAX_PER_TICK) {
+            let recorded = match triage::assess(llm, arrival, &classes).await {
+                Ok((assessment, completion)) => triage::record(&self.synapse, arrival, &assessment)
+                    .await
+                    .map(|subtasks| (assessment, completion, subtasks)),
+                Err(e) => Err(e),
+            };
+            match recorded {
+                Ok((assessment, completion, subtasks)) => info!(
+                    "🧭 Triaged {}: {}, ~${:.2}, {} subtasks (triage cost ${:.4})",
+                    arrival.task_id,
+                    arrival.class.as_ref().or(assessment.class.as_ref()).map_or("any class", |c| c.as_str()),
+                    assessment.estimated_cost_usd.unwrap_or_default(),
+                    subtasks.len(),
+                    completion.cost_usd
+                ),
+                Err(e) => {
+                    warn!("Could not triage {}, queueing it as it is: {:#}", arrival.task_id, e);
+                    if let Err(e) = triage::record_failure(&self.synapse, arrival, &format!("{e:#}")).await {
+                        warn!("Could not record the failed triage of {}: {}", arrival.task_id, e);
+                    }
+                }
+            }
+        }
+        arrivals.into_iter().skip(triage::MAX_PER_TICK).map(|arrival| arrival.task_id).collect()
+    }
+
+    /// Starts the wait of tasks seen runnable for the first time.
+    async fn stamp_queued(&self, rows: &[RunnableRow], now: chrono::DateTime<chrono::Utc>) {
+        let tasks = queue::unstamped(rows);
+        if tasks.is_empty() {
+            return;
+        }
+        let stamp = tasks::datetime_literal(&now);
+        let triples = tasks.iter().map(|task| (task.as_str(), queue::QUEUED_AT, stamp.as_str())).collect();
+        if let Err(e) = self.synapse.ingest_from(Source::Agency, triples).await {
+            warn!("Could not record when {} tasks were queued: {}", tasks.len(), e);
+        }
+    }
+
+    /// Tasks that could start now, in no particular order.
+    async fn runnable_tasks(&self) -> anyhow::Result<Vec<RunnableRow>> {
         let query = r#"
             PREFIX swarm: <http://swarm.os/ontology/>
-            SELECT ?task ?title ?agent
+            SELECT ?task ?title ?timeout ?priority ?repo ?queuedAt ?requiresClass ?requiresSkill
             WHERE {
                 ?task a swarm:Task ;
                       swarm:internalState "REQUIREMENTS" ;
                       swarm:title ?title .
                 FILTER NOT EXISTS { ?task swarm:internalState "PROCESSING" }
-                ?agent a swarm:Agent ;
-                       swarm:status "Standby" .
+                FILTER NOT EXISTS { ?task swarm:internalState "CANCELLED" }
+                FILTER NOT EXISTS {
+                    { ?task swarm:requiresApproval "true" } UNION { ?task swarm:riskLevel "HIGH" }
+                    FILTER NOT EXISTS { ?task swarm:approvalState "APPROVED" }
+                }
+                FILTER NOT EXISTS { ?task swarm:approvalState "REJECTED" }
+                FILTER NOT EXISTS {
+                    ?task swarm:dependsOn ?dependency .
+                    FILTER NOT EXISTS { ?dependency swarm:internalState "DONE" }
+                }
+                OPTIONAL { ?task swarm:timeoutSeconds ?timeout }
+                OPTIONAL { ?task swarm:priority ?priority }
+                OPTIONAL { ?task swarm:repository ?repo }
+                OPTIONAL { ?task swarm:queuedAt ?queuedAt }
+                OPTIONAL { ?task swarm:requiresClass ?requiresClass }
+                OPTIONAL { ?task swarm:requiresSkill ?requiresSkill }
             }
-            LIMIT 1
         "#;
+        self.synapse.query_as(query).await
+    }
 
-        match synapse.query(query).await {
-            Ok(res_json) => {
-                if let Ok(parsed) = serde_json::from_str::<Vec<Value>>(&res_json) {
-                    if let Some(item) = parsed.first() {
-                        let task_id = item.get("?task").or_else(|| item.get("task"));
-                        let title = item.get("?title").or_else(|| item.get("title"));
-                        let agent_id = item.get("?agent").or_else(|| item.get("agent"));
-                        
-                        if let (Some(tid), Some(t), Some(aid)) = (task_id, title, agent_id) {
-                            let tid_str = clean_val(tid);
-                            let title_str = clean_val(t);
-                            let aid_str = clean_val(aid);
-                            
-                            info!("🚀 LAUNCHING REAL AGENT: Orchestrating task '{}' via agent {}", title_str, aid_str);
-                            
-                            // 1. Transition Task to PROCESSING to avoid race conditions
-                            let _ = synapse.ingest(vec![
-                                (&tid_str, "http://swarm.os/ontology/internalState", "\"PROCESSING\""),
-                                (&aid_str, "http://swarm.os/ontology/status", &format!("\"Working on: {}\"", title_str))
-                            ]).await;
-
-                            // 2. Spawn Real Python Orchestrator
-                            let title_clone = title_str.clone();
-                            tokio::spawn(async move {
-                                info!("🐍 [Python] Spawning Orchestrator for: {}", title_clone);
-                                let output = tokio::process::Command::new("python3")
-                                    .arg("sdk/python/agents/orchestrator.py")
-                                    .arg(&title_clone)
-                                    .output()
-                                    .await;
-
-                                match output {
-                                    Ok(out) => {
-                                        if out.status.success() {
-                                            info!("✅ [Python] Task '{}' completed successfully.", title_clone);
-                                        } else {
-                                            let err_msg = String::from_utf8_lossy(&out.stderr);
-                                            error!("❌ [Python] Task '{}' failed: {}", title_clone, err_msg);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("❌ [Python] Failed to spawn process: {}", e);
-                                    }
-                                }
-                            });
-                        }
+    /// No task could start. A running chore gives way when work waits for an agent;
+    /// otherwise a free agent may take a chore.
+    async fn fill_idle_time(&self) -> Outcome {
+        if !chores::in_flight().is_empty() {
+            match self.wanted_chore_agents(&chores::in_flight().agents()).await {
+                Ok(wanted) => {
+                    for chore in chores::in_flight().preempt(|agent| wanted.iter().any(|w| w == agent)) {
+                        info!("🧹 Stopping chore {} to free its agent for queued work", chore);
                     }
                 }
+                Err(e) => warn!("Could not check for queued tasks; letting chores run: {}", e),
             }
+            return Outcome::Idle;
+        }
+        if !self.chores.enabled() {
+            return Outcome::Idle;
+        }
+        match self.next_chore().await {
+            Ok(Some(run)) => {
+                chores::in_flight().start(&run.task_id, &run.agent_id);
+                self.launch(run).await;
+                Outcome::Launched
+            }
+            Ok(None) => Outcome::Idle,
             Err(e) => {
-                error!("Agency query failed: {}", e);
+                warn!("Could not plan a chore for idle agents: {}", e);
+                Outcome::Idle
+            }
+        }
+    }
+
+    /// The agents among `chore_agents` that some runnable task or due retry could run on.
+    async fn wanted_chore_agents(&self, chore_agents: &[String]) -> anyhow::Result<Vec<String>> {
+        if chore_agents.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut waiting: Vec<Requirements> = requirements_by_task(&self.runnable_tasks().await?).into_values().collect();
+        for task_id in self.retries.due_tasks().await {
+            waiting.push(self.requirements_of(&task_id).await?);
+        }
+        if waiting.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .agents(false)
+            .await?
+            .into_iter()
+            .filter(|agent| chore_agents.contains(&agent.id))
+            .filter(|agent| waiting.iter().any(|r| r.met_by(&agent.class, &agent.skills)))
+            .map(|agent| agent.id)
+            .collect())
+    }
+
+    /// The chore due longest on the repository of a free agent, while today's chore budget
+    /// lasts.
+    async fn next_chore(&self) -> anyhow::Result<Option<TaskRun>> {
+        let agents = self.standby_agents().await?;
+        if agents.is_empty() {
+            return Ok(None);
+        }
+        let history = chores::history(&self.synapse).await?;
+        let left = self.chores.daily_budget_usd() - history.spent_today;
+        if left <= 0.0 {
+            return Ok(None);
+        }
+        let Some((chore, index)) = self.chores.pick(&agents, &history, chrono::Utc::now()) else {
+            return Ok(None);
+        };
+        let agent = &agents[index];
+        let task_id = chores::new_id();
+        let title = chore.title_for(&agent.repo);
+        chores::record_start(&self.synapse, &task_id, chore, &title, &agent.repo).await?;
+        info!("🧹 Agent {} is idle; starting chore '{}' (${:.2} of the chore budget left today)", agent.id, title, left);
+        Ok(Some(TaskRun {
+            task_id,
+            title,
+            agent_id: agent.id.clone(),
+            attempt: 1,
+            timeout: self.default_timeout,
+            failures: Vec::new(),
+            chore_budget_usd: Some(left),
+        }))
+    }
+
+    /// The free agent the strategy of `task`'s repository picks.
+    async fn pick_agent(&self, task: &TaskCandidate, agents: &[AgentCandidate]) -> usize {
+        let strategy = self.strategies.for_repo(&task.repo);
+        let successes = match strategy {
+            Strategy::Weighted => strategy::success_counts(&self.synapse).await.unwrap_or_else(|e| {
+                warn!("Could not read run outcomes; weighting every agent the same: {}", e);
+                SuccessCounts::new()
+            }),
+            _ => SuccessCounts::new(),
+        };
+        self.assignments.pick(strategy, &task.repo, agents, &successes)
+    }
+
+    /// The most urgent task some free agent qualifies for, with the agent its repository's
+    /// strategy picks among the qualified ones.
+    async fn pick_pair(&self, tasks: &[TaskCandidate], agents: &[AgentCandidate]) -> Option<(usize, usize)> {
+        for (t, task) in tasks.iter().enumerate() {
+            let qualified: Vec<usize> = (0..agents.len()).filter(|a| task.admits(&agents[*a])).collect();
+            if qualified.is_empty() {
+                debug!("No free agent has what task '{}' requires; trying the next one", task.title);
+                continue;
+            }
+            let pool: Vec<AgentCandidate> = qualified.iter().map(|a| agents[*a].clone()).collect();
+            return Some((t, qualified[self.pick_agent(task, &pool).await]));
+        }
+        None
+    }
+
+    /// Free agents in the order Synapse lists them, with what the scheduler script sees.
+    async fn standby_agents(&self) -> anyhow::Result<Vec<AgentCandidate>> {
+        self.agents(true).await
+    }
+
+    /// Agents that are not retired, only those on Standby with `standby_only`.
+    async fn agents(&self, standby_only: bool) -> anyhow::Result<Vec<AgentCandidate>> {
+        let status = if standby_only { r#"; swarm:status "Standby""# } else { "" };
+        let query = format!(
+            r#"
+            PREFIX swarm: <http://swarm.os/ontology/>
+            SELECT ?agent ?name ?class ?repo ?skill WHERE {{
+                ?agent a swarm:Agent {status} .
+                FILTER NOT EXISTS {{ ?agent swarm:status "Retired" }}
+                OPTIONAL {{ ?agent swarm:name ?name }}
+                OPTIONAL {{ ?agent swarm:class ?class }}
+                OPTIONAL {{ ?repo swarm:hasPopulation ?agent }}
+                OPTIONAL {{ ?agent swarm:skill ?skill }}
+            }}
+        "#
+        );
+        Ok(agent_candidates(self.synapse.query_as(&query).await?))
+    }
+
+    async fn requirements_of(&self, task_id: &str) -> anyhow::Result<Requirements> {
+        let query = Query::new(
+            r#"
+            PREFIX swarm: <http://swarm.os/ontology/>
+            SELECT ?requiresClass ?requiresSkill WHERE {
+                $task a swarm:Task .
+                OPTIONAL { $task swarm:requiresClass ?requiresClass }
+                OPTIONAL { $task swarm:requiresSkill ?requiresSkill }
+            }
+        "#,
+        )
+        .iri("task", task_id);
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RequirementRow {
+            requires_class: Option<String>,
+            requires_skill: Option<String>,
+        }
+        let mut requirements = Requirements::default();
+        for row in self.synapse.select_as::<RequirementRow>(&query).await? {
+            add_requirements(&mut requirements, row.requires_class.as_deref(), row.requires_skill.as_deref());
+        }
+        Ok(requirements)
+    }
+
+    /// The first free agent with what the task of `run` requires.
+    async fn find_qualified_agent(&self, run: &TaskRun) -> anyhow::Result<Option<String>> {
+        let requirements = self.requirements_of(&run.task_id).await?;
+        let agents = self.standby_agents().await?;
+        Ok(agents.into_iter().find(|a| requirements.met_by(&a.class, &a.skills)).map(|a| a.id))
+    }
+
+    async fn launch_due_retries(&self) {
+        for task in cancel::running().take_cancelled() {
+            self.retries.remove(&task).await;
+        }
+        // A human reset an escalated task: it starts over with a fresh set of attempts.
+        for reset in escalation::take_resets() {
+            info!("🙋 Task {} was reset; handing it back to the agents", reset.task_id);
+            let run = TaskRun {
+                task_id: reset.task_id,
+                title: reset.title,
+                agent_id: String::new(),
+                attempt: 1,
+                timeout: reset.timeout.unwrap_or(self.default_timeout),
+                failures: Vec::new(),
+                chore_budget_usd: None,
+            };
+            self.retries.schedule(run, Duration::ZERO).await;
+        }
+
+        for mut run in self.retries.take_due().await {
+            match self.find_qualified_agent(&run).await {
+                Ok(Some(agent_id)) => {
+                    run.agent_id = agent_id;
+                    self.launch(run).await;
+                }
+                Ok(None) => {
+                    // No free agent yet; try again on the next tick.
+                    self.retries.schedule(run, Duration::ZERO).await;
+                }
+                Err(e) => {
+                    error!("Agency query failed while retrying {}: {}", run.task_id, e);
+                    self.retries.schedule(run, Duration::ZERO).await;
+                }
+            }
+        }
+    }
+
+    async fn launch(&self, run: TaskRun) {
+        // The lease outlives the timeout a little, so the kill and write-back fit inside it.
+        let lease_ttl = run.timeout + Duration::from_secs(60);
+        match state::shared().acquire_lease(&run.task_id, &run.title, &run.agent_id, run.attempt, lease_ttl) {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!("Task {} is already being run; not launching it again", run.task_id);
+                return;
+            }
+            Err(e) => warn!("Could not record the lease on task {}: {}", run.task_id, e),
+        }
+        // A reset task no longer needs its queued reset once it runs.
+        if let Err(e) = state::shared().finish_reset(&run.task_id) {
+            warn!("Could not drop the queued reset of task {}: {}", run.task_id, e);
+        }
+        info!("🚀 LAUNCHING REAL AGENT: Orchestrating task '{}' via agent {} (attempt {})", run.title, run.agent_id, run.attempt);
+
+        self.hooks.fire(&hook_payload(HookEvent::PreAssign, &run)).await;
+
+        // 1. Transition Task to PROCESSING to avoid race conditions
+        let _ = self.synapse.ingest_from(Source::Agency, vec![
+            (&run.task_id, INTERNAL_STATE, &TaskState::Processing.literal()),
+            (&run.task_id, ASSIGNED_TO, &run.agent_id),
+        ]).await;
+        let status = sparql::literal(&format!("Working on: {}", run.title));
+        let _ = self.synapse.replace_object(Source::Agency, &run.agent_id, AGENT_STATUS, &status).await;
+
+        // 2. Spawn Real Python Orchestrator
+        tokio::spawn(self.clone().run_orchestrator(run));
+    }
+
+    /// [`Agency::orchestrate`] under an `agent.run` span, in the trace of the card the task came
+    /// from when it has one.
+    async fn run_orchestrator(self, run: TaskRun) {
+        let span = info_span!("agent.run", task_id = %run.task_id, agent_id = %run.agent_id, attempt = run.attempt);
+        if let Some(parent) = telemetry::stored_parent(&self.synapse, &run.task_id).await {
+            telemetry::set_parent(&span, &parent);
+        }
+        self.orchestrate(run).instrument(span).await
+    }
+
+    /// Runs the orchestrator for one task and writes the outcome back to Synapse,
+    /// releasing the agent so it can pick up new work.
+    async fn orchestrate(self, mut run: TaskRun) {
+        let runtime = runtime::for_agent(&run.agent_id);
+        info!("🐍 Running task '{}' as a {} (timeout {}s)", run.title, runtime.describe(), run.timeout.as_secs());
+        let started_at = Instant::now();
+        // Python dates its spend with the local date; keep it on the budget's day.
+        let mut env = vec![("TZ", crate::clock::tz().name().to_string())];
+        if let Some(limit) = run.chore_budget_usd {
+            env.push(("SWARM_SPEND_LIMIT_USD", format!("{:.4}", limit)));
+        }
+        if let Some(parent) = telemetry::traceparent(&Span::current()) {
+            env.push(("TRACEPARENT", parent));
+        }
+        let handoff = match self.handoffs.prepare(&self.synapse, &run.task_id).await {
+            Ok(files) => {
+                env.push(("SWARM_HANDOFF_OUT", files.outbound.to_string_lossy().into_owned()));
+                if let Some(inbound) = &files.inbound {
+                    info!("📎 Task '{}' starts from the handoffs of the stages before it", run.title);
+                    env.push(("SWARM_HANDOFF_IN", inbound.to_string_lossy().into_owned()));
+                }
+                Some(files)
+            }
+            Err(e) => {
+                warn!("Could not prepare the handoff files of task {}; running without them: {}", run.task_id, e);
+                None
+            }
+        };
+        let context = match self.contexts.write(&self.synapse, &self.budget, &run).await {
+            Ok(path) => {
+                env.push(("SWARM_TASK_CONTEXT", path.to_string_lossy().into_owned()));
+                Some(path)
+            }
+            Err(e) => {
+                warn!("Could not write the context of task {}; running with its title alone: {:#}", run.task_id, e);
+                None
+            }
+        };
+        env.extend(self.synapse_env.iter().cloned());
+        let workdir = match self.worktrees.prepare(&self.synapse, &run.task_id).await {
+            Ok(workdir) => workdir,
+            Err(e) => {
+                warn!("Could not check out the repository of task {}; running without a working copy: {:#}", run.task_id, e);
+                None
+            }
+        };
+        if let Some(workdir) = &workdir {
+            env.push(("SWARM_WORKDIR", workdir.path.to_string_lossy().into_owned()));
+        }
+        let mounts = workdir.as_ref().map(Workdir::mounts).unwrap_or_default();
+        let mut shared = if handoff.is_some() { vec![self.handoffs.dir()] } else { Vec::new() };
+        if context.is_some() {
+            shared.push(self.contexts.dir());
+        }
+        shared.extend(mounts.iter().map(|mount| mount.as_path()));
+        let job = runtime::Job { task_id: &run.task_id, title: &run.title, agent_id: &run.agent_id, env: &env, shared: &shared };
+
+        let stalled = stalls::shared().watch(&run.task_id);
+        let cancelled = cancel::running().start(&run.task_id);
+        let preempted = run.chore_budget_usd.and_then(|_| chores::in_flight().signal(&run.task_id));
+        let mut sampler = None;
+        let output = match runtime.start(&job).await {
+            Ok(mut execution) => {
+                sampler = execution.pid.map(Sampler::spawn);
+                let lines = self.stream_output.then(|| trace::start(self.tx.clone(), &run.task_id, &run.title));
+                let heartbeat = Some(run.task_id.clone());
+                let stdout = execution.stdout.take().map(|out| tokio::spawn(read_output(out, Stream::Stdout, heartbeat, lines.clone())));
+                let stderr = execution.stderr.take().map(|err| tokio::spawn(read_output(err, Stream::Stderr, None, lines)));
+                let end = tokio::select! {
+                    waited = tokio::time::timeout(run.timeout, &mut execution.exit) => match waited {
+                        Ok(exit) => exit.map(RunEnd::Exited),
+                        Err(_) => Ok(RunEnd::TimedOut),
+                    },
+                    _ = stalled.notified() => Ok(RunEnd::Stalled),
+                    _ = cancelled.notified() => Ok(RunEnd::Cancelled),
+                    _ = signalled(preempted.as_deref()) => Ok(RunEnd::Preempted),
+                };
+                if matches!(end, Ok(RunEnd::TimedOut | RunEnd::Stalled | RunEnd::Preempted | RunEnd::Cancelled)) {
+                    runtime.stop(&mut execution).await;
+                }
+                drop(execution);
+                let (stdout, stderr) = (collected(stdout).await, collected(stderr).await);
+                end.map(|end| (end, stdout, stderr))
+            }
+            Err(e) => Err(e),
+        };
+        stalls::shared().forget(&run.task_id);
+        cancel::running().finish(&run.task_id);
+        chores::in_flight().finish(&run.task_id);
+
+        let result = match &output {
+            Ok((RunEnd::Exited(_), stdout, _)) => match RunResult::last_in(stdout) {
+                Some(Ok(result)) => Some(result),
+                Some(Err(e)) => {
+                    warn!("Task '{}' printed a malformed result; judging it by its exit code: {}", run.title, e);
+                    None
+                }
+                None => None,
+            },
+            _ => None,
+        };
+        let reported_failure = result.as_ref().is_some_and(|r| r.status == ResultStatus::Failure);
+
+        let (state, exit_code, report) = match output {
+            Ok((RunEnd::TimedOut, ..)) => {
+                warn!("⏱️ Task '{}' timed out after {}s; its run was stopped", run.title, run.timeout.as_secs());
+                (TaskState::TimedOut, None, Some(format!("Timed out after {}s", run.timeout.as_secs())))
+            }
+            Ok((RunEnd::Preempted, ..)) => {
+                info!("🧹 Chore '{}' stopped after {}s; its agent is needed for queued work", run.title, started_at.elapsed().as_secs());
+                (TaskState::Cancelled, None, None)
+            }
+            Ok((RunEnd::Cancelled, ..)) => {
+                info!("🛑 Task '{}' cancelled after {}s; its run was stopped", run.title, started_at.elapsed().as_secs());
+                (TaskState::Cancelled, None, None)
+            }
+            Ok((RunEnd::Stalled, ..)) => {
+                let minutes = started_at.elapsed().as_secs() / 60;
+                warn!("🫀 Task '{}' stalled after {} min; its run was stopped", run.title, minutes);
+                let _ = self.tx.send(Notification::alert(
+                    Message::new()
+                        .text("Task ")
+                        .strong(&run.title)
+                        .text(format!(" stalled after {} min: its orchestrator stopped sending heartbeats. The agent was freed.", minutes)),
+                ).with_source(Source::Agency).with_task(&run.task_id)).await;
+                (TaskState::Stalled, None, Some(format!("Stalled: no heartbeat, stopped after {} min", minutes)))
+            }
+            Ok((RunEnd::Exited(status), stdout, stderr)) => {
+                if status.success && !reported_failure {
+                    info!("✅ Task '{}' completed successfully.", run.title);
+                    let pull = result.as_ref().and_then(RunResult::pull).or_else(|| PullRef::last_in(&stdout));
+                    let state = match pull {
+                        Some(pull) => {
+                            info!("🔀 Task '{}' opened {}", run.title, pull.url());
+                            if let Err(e) = pulls::record(&self.synapse, &run.task_id, &pull).await {
+                                error!("Failed to record pull request for task {}: {}", run.task_id, e);
+                            }
+                            if self.await_merge { TaskState::AwaitingMerge } else { TaskState::Done }
+                        }
+                        None => TaskState::Done,
+                    };
+                    (state, status.code, None)
+                } else {
+                    error!("❌ Task '{}' failed: {}", run.title, stderr);
+                    // The orchestrator's own account beats the tail of its stderr.
+                    let summary = result.as_ref().and_then(|r| r.summary.as_deref()).filter(|_| reported_failure);
+                    let report = match escalation::failure_report(summary.unwrap_or(&stderr)) {
+                        empty if empty.is_empty() && reported_failure => "Reported failure".to_string(),
+                        empty if empty.is_empty() => format!("Exited with {}", status),
+                        report => report,
+                    };
+                    (TaskState::Failed, status.code, Some(report))
+                }
+            }
+            Err(e) => {
+                error!("❌ Could not start task '{}' as a {}: {}", run.title, runtime.describe(), e);
+                (TaskState::Failed, None, Some(escalation::failure_report(&format!("Could not start {}: {}", runtime.describe(), e))))
+            }
+        };
+
+        if let Some(files) = &handoff {
+            if matches!(state, TaskState::Done | TaskState::AwaitingMerge) {
+                match self.handoffs.collect(&self.synapse, &run.task_id, files).await {
+                    Ok(true) => info!("📎 Task '{}' left a handoff for the next stage", run.title),
+                    Ok(false) => {}
+                    Err(e) => warn!("Dropped the handoff of task {}: {:#}", run.task_id, e),
+                }
+            } else {
+                self.handoffs.discard(files).await;
+            }
+        }
+        if let Some(workdir) = &workdir {
+            if matches!(state, TaskState::Done | TaskState::AwaitingMerge) {
+                self.record_exploration(workdir).await;
             }
+            self.worktrees.release(workdir).await;
+        }
+        if let Some(path) = &context {
+            self.contexts.remove(path).await;
+        }
+
+        let duration = started_at.elapsed();
+        let usage = sampler
+            .map(|s| s.finish(duration))
+            .unwrap_or(ResourceUsage { wall_seconds: duration.as_secs_f64(), ..Default::default() });
+        record_completion(&self.synapse, &run, state, &usage, exit_code, report.as_deref(), result.as_ref()).await;
+        // A task awaiting merge pays when the merge moves it to DONE, see `pulls::track`.
+        if state == TaskState::Done {
+            self.award_experience(&run, usage.wall_seconds).await;
+        }
+        if let Err(e) = state::shared().release_lease(&run.task_id) {
+            warn!("Could not release the lease on task {}: {}", run.task_id, e);
+        }
+
+        let event = match state {
+            TaskState::Done | TaskState::AwaitingMerge => HookEvent::PostComplete,
+            _ => HookEvent::OnFail,
+        };
+        let mut payload = hook_payload(event, &run);
+        payload.state = Some(state.as_str().to_string());
+        payload.exit_code = exit_code;
+        payload.duration_seconds = Some(duration.as_secs_f64());
+        self.hooks.fire(&payload).await;
+
+        if run.chore_budget_usd.is_none() && matches!(state, TaskState::Failed | TaskState::TimedOut | TaskState::Stalled) {
+            run.failures.extend(report);
+            self.handle_failure(run).await;
+        }
+    }
+
+    /// Leases left by an earlier process are runs a crash or restart cut short. Their tasks
+    /// would sit in PROCESSING forever, so they count as failed attempts and are retried.
+    async fn recover_interrupted_runs(&self) {
+        let leases = match state::shared().take_orphaned_leases() {
+            Ok(leases) => leases,
+            Err(e) => {
+                error!("Could not read task leases from the state store: {}", e);
+                return;
+            }
+        };
+        for lease in leases {
+            warn!("🔌 Task {} was interrupted on agent {} by a restart (attempt {})", lease.task, lease.agent, lease.attempt);
+            let mut run = TaskRun {
+                task_id: lease.task,
+                title: lease.title,
+                agent_id: lease.agent,
+                attempt: lease.attempt,
+                timeout: self.default_timeout,
+                failures: Vec::new(),
+                chore_budget_usd: None,
+            };
+            let report = escalation::failure_report("Interrupted by a swarmd restart");
+            record_completion(&self.synapse, &run, TaskState::Failed, &ResourceUsage::default(), None, Some(&report), None).await;
+            // Chores are not retried; the next one comes along when an agent is idle again.
+            if run.task_id.starts_with(chores::CHORE_PREFIX) {
+                continue;
+            }
+            run.failures.push(report);
+            self.handle_failure(run).await;
+        }
+    }
+
+    async fn handle_failure(&self, run: TaskRun) {
+        let attempts_lit = tasks::integer_literal(run.attempt as i64);
+
+        if self.policy.exhausted(run.attempt) && !self.escalation_owners.is_empty() {
+            warn!("🙋 Task {} failed {} times; escalating to {}", run.task_id, run.attempt, self.escalation_owners.join(", "));
+            if let Err(e) = escalation::escalate(&self.synapse, &run.task_id, &self.escalation_owners, run.attempt).await {
+                error!("Failed to record escalation of task {}: {}", run.task_id, e);
+            }
+            let _ = self.tx.send(Notification::critical(
+                escalation::escalation_message(&run.title, &self.escalation_owners, &run.failures),
+            ).with_source(Source::Agency).with_task(&run.task_id)).await;
+            return;
         }
 
-        sleep(Duration::from_secs(5)).await;
+        if self.policy.exhausted(run.attempt) {
+            warn!("☠️ Task {} failed {} times; moving to dead letter", run.task_id, run.attempt);
+            let _ = self.synapse.ingest_from(Source::Agency, vec![
+                (&run.task_id, INTERNAL_STATE, &TaskState::DeadLetter.literal()),
+                (&run.task_id, "http://swarm.os/ontology/attempts", &attempts_lit),
+            ]).await;
+            let _ = self.tx.send(Notification::critical(
+                Message::new()
+                    .text("Task ")
+                    .strong(&run.title)
+                    .text(format!(" moved to DEAD_LETTER after {} failed attempts", run.attempt)),
+            ).with_source(Source::Agency).with_task(&run.task_id)).await;
+            return;
+        }
+
+        let delay = self.policy.delay_for(run.attempt);
+        let next_at = chrono::Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+        info!("🔁 Task {} failed (attempt {}/{}); retrying in {}s", run.task_id, run.attempt, self.policy.max_attempts, delay.as_secs());
+        let _ = self.synapse.ingest_from(Source::Agency, vec![
+            (&run.task_id, INTERNAL_STATE, &TaskState::RetryScheduled.literal()),
+            (&run.task_id, "http://swarm.os/ontology/attempts", &attempts_lit),
+            (&run.task_id, "http://swarm.os/ontology/nextAttemptAt", &tasks::datetime_literal(&next_at)),
+        ]).await;
+
+        self.retries.schedule(TaskRun { attempt: run.attempt + 1, ..run }, delay).await;
     }
 }
 
-fn clean_val(val: &Value) -> String {
-    let s = match val {
-        Value::String(s) => s.as_str(),
-        _ => "",
-    };
-    s.trim_matches(|c| c == '"' || c == '<' || c == '>').to_string()
+/// How an orchestrator run ended, short of failing to start.
+enum RunEnd {
+    Exited(runtime::Exit),
+    TimedOut,
+    /// Stopped by the stall watch.
+    Stalled,
+    /// A chore stopped to free its agent for a task.
+    Preempted,
+    /// Cancelled by a human.
+    Cancelled,
+}
+
+/// Resolves once `signal` fires; never without one.
+async fn signalled(signal: Option<&tokio::sync::Notify>) {
+    match signal {
+        Some(signal) => signal.notified().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Collects one output stream of the orchestrator, passing each line on to its trace. Lines
+/// on the stream that carries the `heartbeat` of a task count as signs of life.
+async fn read_output(out: impl AsyncRead + Unpin, stream: Stream, heartbeat: Option<String>, lines: Option<trace::Lines>) -> String {
+    let mut reader = tokio::io::BufReader::new(out);
+    let mut text = Vec::new();
+    loop {
+        let start = text.len();
+        match reader.read_until(b'\n', &mut text).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if let Some(task_id) = &heartbeat {
+            stalls::shared().beat(task_id, chrono::Utc::now());
+        }
+        if let Some(lines) = &lines {
+            lines.push(stream, &String::from_utf8_lossy(&text[start..]));
+        }
+    }
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+async fn collected(reader: Option<tokio::task::JoinHandle<String>>) -> String {
+    match reader {
+        Some(reader) => reader.await.unwrap_or_default(),
+        None => String::new(),
+    }
+}
+
+fn hook_payload(event: HookEvent, run: &TaskRun) -> TaskHookPayload {
+    TaskHookPayload {
+        event,
+        task_id: run.task_id.clone(),
+        title: run.title.clone(),
+        agent_id: run.agent_id.clone(),
+        state: None,
+        exit_code: None,
+        duration_seconds: None,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+async fn record_completion(
+    synapse: &Synapse,
+    run: &TaskRun,
+    state: TaskState,
+    usage: &ResourceUsage,
+    exit_code: Option<i32>,
+    failure_report: Option<&str>,
+    result: Option<&RunResult>,
+) {
+    let state_lit = state.literal();
+    let duration_lit = tasks::decimal_literal(usage.wall_seconds);
+    let completed_lit = tasks::datetime_literal(&chrono::Utc::now());
+    // Killed by a signal means there is no exit code; record -1 so the triple is always present.
+    let exit_lit = tasks::integer_literal(exit_code.unwrap_or(-1) as i64);
+
+    // Per-attempt usage lives on its own run node; the task keeps one value per attempt otherwise.
+    let run_id = format!("http://swarm.os/run/{}", uuid::Uuid::new_v4());
+    let attempt_lit = tasks::integer_literal(run.attempt as i64);
+    let cpu_lit = tasks::decimal_literal(usage.cpu_seconds);
+    let memory_lit = tasks::integer_literal(usage.peak_memory_bytes as i64);
+
+    let report_lit = failure_report.map(sparql::literal);
+
+    let mut triples: Vec<(&str, &str, &str)> = vec![
+        (&run.task_id, INTERNAL_STATE, &state_lit),
+        (&run.task_id, "http://swarm.os/ontology/durationSeconds", &duration_lit),
+        (&run.task_id, "http://swarm.os/ontology/exitCode", &exit_lit),
+        (&run.task_id, resource_usage::COMPLETED_AT, &completed_lit),
+        (&run_id, "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", resource_usage::RUN),
+        (&run_id, resource_usage::RUN_OF, &run.task_id),
+        (&run_id, resource_usage::RUN_AGENT, &run.agent_id),
+        (&run_id, resource_usage::RUN_ATTEMPT, &attempt_lit),
+        (&run_id, resource_usage::CPU_SECONDS, &cpu_lit),
+        (&run_id, resource_usage::PEAK_MEMORY_BYTES, &memory_lit),
+        (&run_id, resource_usage::DURATION_SECONDS, &duration_lit),
+        (&run_id, resource_usage::OUTCOME, &state_lit),
+        (&run_id, resource_usage::COMPLETED_AT, &completed_lit),
+    ];
+    if let Some(report_lit) = &report_lit {
+        triples.push((&run_id, escalation::FAILURE_REPORT, report_lit));
+    }
+    let reported = result.map(|r| r.triples(&run_id)).unwrap_or_default();
+    triples.extend(reported.iter().map(|(s, p, o)| (s.as_str(), *p, o.as_str())));
+    let mut result = synapse.ingest_from(Source::Agency, triples).await;
+    if result.is_ok() {
+        result = synapse.replace_object(Source::Agency, &run.agent_id, AGENT_STATUS, "\"Standby\"").await;
+    }
+
+    match result {
+        Ok(_) => info!(
+            "📝 Task {} marked {} after {:.1}s ({:.1} CPU-s, {} MiB peak); agent {} back on Standby",
+            run.task_id, state.as_str(), usage.wall_seconds, usage.cpu_seconds, usage.peak_memory_bytes / (1024 * 1024), run.agent_id
+        ),
+        Err(e) => error!("Failed to write back completion for task {}: {}", run.task_id, e),
+    }
+}
+
+fn short_id(iri: &str) -> String {
+    iri.rsplit('/').next().unwrap_or(iri).to_string()
+}
+
+/// A row of [`Agency::runnable_tasks`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RunnableRow {
+    task: String,
+    title: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    timeout: Option<u64>,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    priority: Option<i64>,
+    #[serde(default)]
+    repo: String,
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    queued_at: Option<chrono::DateTime<chrono::Utc>>,
+    requires_class: Option<String>,
+    requires_skill: Option<String>,
+}
+
+fn task_candidate(row: &RunnableRow, priority: i64, requirements: &HashMap<String, Requirements>) -> TaskCandidate {
+    TaskCandidate {
+        id: row.task.clone(),
+        title: row.title.clone(),
+        priority,
+        repo: short_id(&row.repo),
+        requirements: requirements.get(&row.task).cloned().unwrap_or_default(),
+    }
+}
+
+/// What each task requires, empty for tasks that require nothing. The OPTIONALs repeat a task
+/// once per combination of values.
+fn requirements_by_task(rows: &[RunnableRow]) -> HashMap<String, Requirements> {
+    let mut by_task: HashMap<String, Requirements> = HashMap::new();
+    for row in rows {
+        let requirements = by_task.entry(row.task.clone()).or_default();
+        add_requirements(requirements, row.requires_class.as_deref(), row.requires_skill.as_deref());
+    }
+    by_task
+}
+
+fn add_requirements(requirements: &mut Requirements, class: Option<&str>, skill: Option<&str>) {
+    if let Some(class) = class {
+        requirements.add_class(class);
+    }
+    if let Some(skill) = skill {
+        requirements.add_skill(skill);
+    }
+}
+
+#[derive(Deserialize)]
+struct AgentRow {
+    agent: String,
+    name: Option<String>,
+    class: Option<String>,
+    repo: Option<String>,
+    skill: Option<String>,
+}
+
+/// Agents in first-seen order; the OPTIONALs repeat an agent once per value combination.
+fn agent_candidates(rows: Vec<AgentRow>) -> Vec<AgentCandidate> {
+    let mut agents: Vec<AgentCandidate> = Vec::new();
+    for row in rows {
+        let id = row.agent;
+        let index = match agents.iter().position(|a| a.id == id) {
+            Some(index) => index,
+            None => {
+                agents.push(AgentCandidate { id, ..Default::default() });
+                agents.len() - 1
+            }
+        };
+        let agent = &mut agents[index];
+        if let Some(name) = row.name {
+            agent.name = name;
+        }
+        if let Some(class) = row.class {
+            agent.class = class;
+        }
+        if let Some(repo) = row.repo {
+            agent.repo = short_id(&repo);
+        }
+        if let Some(skill) = row.skill.filter(|s| !agent.skills.iter().any(|known| known.eq_ignore_ascii_case(s))) {
+            agent.skills.push(skill);
+        }
+    }
+    agents
 }
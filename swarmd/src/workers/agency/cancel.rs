@@ -0,0 +1,195 @@
+//! Cancelling tasks on request, from `POST /api/v1/tasks/{id}/cancel` or the chat `/cancel`
+//! command. A task whose orchestrator is running has its run stopped (the process is killed)
+//! and is written back `CANCELLED`, which puts its agent back on `Standby`; a task still waiting
+//! is marked `CANCELLED` straight away and dropped from the retry queue.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use serde_json::Value;
+use tokio::sync::Notify;
+use tracing::info;
+
+use crate::provenance::Source;
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks::{self, TaskState, AGENT_STATUS, INTERNAL_STATE, TERMINAL_STATES};
+
+const CANCELLED_BY: &str = "http://swarm.os/ontology/cancelledBy";
+const CANCELLED_AT: &str = "http://swarm.os/ontology/cancelledAt";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// The orchestrator was running and has been told to stop.
+    Stopping { title: String },
+    /// The task was waiting and will not be started.
+    Cancelled { title: String },
+    UnknownTask,
+    /// The task already reached `state`.
+    Finished { state: String },
+}
+
+#[derive(Default)]
+struct Registry {
+    running: HashMap<String, Arc<Notify>>,
+    /// Cancelled while waiting, for the agency to drop from its retry queue.
+    waiting: HashSet<String>,
+}
+
+/// The orchestrator runs of this process, keyed by task.
+#[derive(Default)]
+pub struct Running {
+    registry: Mutex<Registry>,
+}
+
+pub fn running() -> &'static Running {
+    static RUNNING: OnceLock<Running> = OnceLock::new();
+    RUNNING.get_or_init(Running::default)
+}
+
+impl Running {
+    /// Registers the run of `task`; the returned signal fires when it is cancelled.
+    pub fn start(&self, task: &str) -> Arc<Notify> {
+        let cancel = Arc::new(Notify::new());
+        self.lock().running.insert(task.to_string(), cancel.clone());
+        cancel
+    }
+
+    pub fn finish(&self, task: &str) {
+        self.lock().running.remove(task);
+    }
+
+    /// Tells the run of `task` to stop; false when it is not running here.
+    fn stop(&self, task: &str) -> bool {
+        let registry = self.lock();
+        let Some(cancel) = registry.running.get(task) else { return false };
+        // notify_one keeps the permit, so a run that has not started waiting still stops.
+        cancel.notify_one();
+        true
+    }
+
+    fn cancel_waiting(&self, task: &str) {
+        self.lock().waiting.insert(task.to_string());
+    }
+
+    /// Tasks cancelled while waiting, taken off the list.
+    pub fn take_cancelled(&self) -> HashSet<String> {
+        std::mem::take(&mut self.lock().waiting)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Cancels `task_id` on behalf of `by`.
+pub async fn cancel(synapse: &Synapse, source: Source, task_id: &str, by: &str) -> anyhow::Result<CancelOutcome> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?title ?state ?agent ?escalated ?reset
+        WHERE {
+            $task a swarm:Task ;
+                  swarm:title ?title .
+            OPTIONAL { $task swarm:internalState ?state }
+            OPTIONAL { $task swarm:assignedTo ?agent }
+            OPTIONAL { $task swarm:escalatedAt ?escalated }
+            OPTIONAL { $task swarm:resetAt ?reset }
+        }
+    "#,
+    )
+    .iri("task", task_id);
+    let rows: Vec<Value> = serde_json::from_str(&synapse.select(&query).await?).unwrap_or_default();
+    let Some(found) = Found::from_rows(&rows) else {
+        return Ok(CancelOutcome::UnknownTask);
+    };
+    if let Some(state) = found.state.as_deref().filter(|s| TERMINAL_STATES.contains(s)) {
+        return Ok(CancelOutcome::Finished { state: state.to_string() });
+    }
+
+    let state = TaskState::Cancelled.literal();
+    let by_lit = sparql::literal(by);
+    let at = tasks::datetime_literal(&chrono::Utc::now());
+    let triples = vec![
+        (task_id, INTERNAL_STATE, state.as_str()),
+        (task_id, CANCELLED_BY, by_lit.as_str()),
+        (task_id, CANCELLED_AT, at.as_str()),
+    ];
+    let stopping = running().stop(task_id);
+    // A run this process does not know about was cut short by a restart; nothing writes the
+    // agent back, so it is freed here.
+    let orphaned = !stopping && found.state.as_deref() == Some(TaskState::Processing.as_str());
+    synapse.ingest_from(source, triples).await?;
+    if let Some(agent) = found.agent.as_deref().filter(|_| orphaned) {
+        synapse.replace_object(source, agent, AGENT_STATUS, "\"Standby\"").await?;
+    }
+
+    if stopping {
+        info!("🛑 Task {} cancelled by {}; stopping its run", task_id, by);
+        Ok(CancelOutcome::Stopping { title: found.title })
+    } else {
+        running().cancel_waiting(task_id);
+        info!("🛑 Task {} cancelled by {}", task_id, by);
+        Ok(CancelOutcome::Cancelled { title: found.title })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Found {
+    title: String,
+    state: Option<String>,
+    agent: Option<String>,
+}
+
+impl Found {
+    fn from_rows(rows: &[Value]) -> Option<Found> {
+        let field = |row: &Value, name: &str| {
+            row.get(format!("?{name}").as_str())
+                .or_else(|| row.get(name))
+                .and_then(Value::as_str)
+                .map(|v| tasks::literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+        };
+        let title = rows.iter().find_map(|row| field(row, "title"))?;
+        let states: Vec<String> = rows.iter().filter_map(|row| field(row, "state")).collect();
+        let escalated: Vec<String> = rows.iter().filter_map(|row| field(row, "escalated")).collect();
+        let resets: Vec<String> = rows.iter().filter_map(|row| field(row, "reset")).collect();
+        let state = tasks::current_state_after_resets(&states, &escalated, &resets).map(str::to_string);
+        let agent = rows.iter().find_map(|row| field(row, "agent"));
+        Some(Found { title, state, agent })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn a_cancelled_run_is_signalled_even_before_it_waits() {
+        let runs = Running::default();
+        let cancel = runs.start("http://swarm.os/task/1");
+        assert!(runs.stop("http://swarm.os/task/1"));
+        assert!(!runs.stop("http://swarm.os/task/2"));
+        // The stored permit makes the next wait return at once.
+        let waited = tokio::time::timeout(std::time::Duration::from_millis(50), cancel.notified()).await;
+        assert!(waited.is_ok());
+
+        runs.finish("http://swarm.os/task/1");
+        assert!(!runs.stop("http://swarm.os/task/1"));
+        runs.cancel_waiting("http://swarm.os/task/3");
+        assert_eq!(runs.take_cancelled(), HashSet::from(["http://swarm.os/task/3".to_string()]));
+        assert!(runs.take_cancelled().is_empty());
+    }
+
+    #[test]
+    fn the_current_state_decides_whether_a_task_can_be_cancelled() {
+        let rows = [
+            json!({"title": "\"Fix login\"", "state": "\"REQUIREMENTS\"", "agent": "<http://swarm.os/agent/a>"}),
+            json!({"title": "\"Fix login\"", "state": "\"PROCESSING\"", "agent": "<http://swarm.os/agent/a>"}),
+        ];
+        let found = Found::from_rows(&rows).unwrap();
+        assert_eq!(found.state.as_deref(), Some("PROCESSING"));
+        assert_eq!(found.agent.as_deref(), Some("http://swarm.os/agent/a"));
+        assert!(Found::from_rows(&[]).is_none());
+    }
+}
@@ -0,0 +1,315 @@
+//! Background chores for idle agents. When no task is runnable, a free agent may take a chore
+//! on its repository: a dependency audit, TODO triage, a scan for documentation gaps. Chores
+//! are `swarm:Chore` nodes rather than tasks, so they never show up in the queue.
+//!
+//! - `AGENCY_CHORES` lists the chores, built-in names or `name=title` pairs; `{repo}` in a
+//!   title is replaced by the repository id. Empty (the default) turns chores off.
+//! - Each chore runs at most once per repository every `CHORE_INTERVAL_HOURS`.
+//! - Chores share `CHORE_DAILY_BUDGET_USD`, counted from the `cost_usd` their runs report.
+//!   One chore runs at a time and is told what is left in `SWARM_SPEND_LIMIT_USD`.
+//! - A chore is stopped as soon as a task or a due retry waits for an agent. It ends
+//!   `CANCELLED` and is not retried; failed chores are not retried either.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use tokio::sync::Notify;
+
+use super::result::COST_USD;
+use super::scoring::AgentCandidate;
+use crate::initiatives::REPOSITORY;
+use crate::provenance::Source;
+use crate::resource_usage::RUN_OF;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, literal_value};
+
+pub const CHORE: &str = "http://swarm.os/ontology/Chore";
+pub const CHORE_KIND: &str = "http://swarm.os/ontology/choreKind";
+pub const CHORE_PREFIX: &str = "http://swarm.os/chore/";
+const STARTED_AT: &str = "http://swarm.os/ontology/startedAt";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const TITLE: &str = "http://swarm.os/ontology/title";
+
+const BUILT_IN: &[(&str, &str)] = &[
+    ("dependency-audit", "Audit the dependencies of {repo} for outdated or vulnerable packages and propose upgrades"),
+    ("todo-triage", "Triage the TODO and FIXME comments in {repo} into concrete follow-up tasks"),
+    ("doc-gaps", "Find the public modules and APIs of {repo} that lack documentation and draft what is missing"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chore {
+    pub kind: String,
+    title: String,
+}
+
+impl Chore {
+    pub fn title_for(&self, repo: &str) -> String {
+        self.title.replace("{repo}", repo)
+    }
+}
+
+/// Which chores run, how often and on what budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chores {
+    chores: Vec<Chore>,
+    daily_budget_usd: f64,
+    interval: chrono::Duration,
+}
+
+impl Default for Chores {
+    fn default() -> Self {
+        Self { chores: Vec::new(), daily_budget_usd: 0.0, interval: chrono::Duration::hours(24) }
+    }
+}
+
+impl Chores {
+    /// `list` is a comma-separated list of built-in chore names or `name=title` pairs.
+    pub fn parse(list: Option<&str>, daily_budget_usd: f64, interval_hours: u64) -> Result<Self, String> {
+        let mut chores: Vec<Chore> = Vec::new();
+        for entry in list.unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let chore = match entry.split_once('=') {
+                Some((kind, title)) if !kind.trim().is_empty() && !title.trim().is_empty() => {
+                    Chore { kind: kind.trim().to_string(), title: title.trim().to_string() }
+                }
+                Some(_) => return Err(format!("expected name=title, got {:?}", entry)),
+                None => match BUILT_IN.iter().find(|(kind, _)| *kind == entry) {
+                    Some((kind, title)) => Chore { kind: kind.to_string(), title: title.to_string() },
+                    None => {
+                        let known: Vec<&str> = BUILT_IN.iter().map(|(kind, _)| *kind).collect();
+                        return Err(format!("unknown chore {:?}; use {} or name=title", entry, known.join(", ")));
+                    }
+                },
+            };
+            if !chore.kind.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+                return Err(format!("chore name {:?} may only use letters, digits, '-', '_' or '.'", chore.kind));
+            }
+            if chores.iter().any(|c| c.kind == chore.kind) {
+                return Err(format!("chore {} is listed twice", chore.kind));
+            }
+            chores.push(chore);
+        }
+        if !daily_budget_usd.is_finite() || daily_budget_usd < 0.0 {
+            return Err("the chore budget must be a non-negative number of USD".to_string());
+        }
+        let interval = chrono::Duration::hours(interval_hours.max(1) as i64);
+        Ok(Self { chores, daily_budget_usd, interval })
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.chores.is_empty() && self.daily_budget_usd > 0.0
+    }
+
+    pub fn daily_budget_usd(&self) -> f64 {
+        self.daily_budget_usd
+    }
+
+    /// The chore that has waited longest among those due on the repository of a free agent,
+    /// and the index of that agent in `agents`. Never-run chores go first.
+    pub fn pick(&self, agents: &[AgentCandidate], history: &History, now: DateTime<Utc>) -> Option<(&Chore, usize)> {
+        let mut best: Option<(Option<DateTime<Utc>>, &Chore, usize)> = None;
+        for (agent_index, agent) in agents.iter().enumerate().filter(|(_, a)| !a.repo.is_empty()) {
+            for chore in &self.chores {
+                let last = history.last_runs.get(&(chore.kind.clone(), agent.repo.clone())).copied();
+                if last.is_some_and(|last| now - last < self.interval) {
+                    continue;
+                }
+                if best.as_ref().is_none_or(|(best_last, ..)| last < *best_last) {
+                    best = Some((last, chore, agent_index));
+                }
+            }
+        }
+        best.map(|(_, chore, agent)| (chore, agent))
+    }
+}
+
+/// What earlier chores tell about the next one.
+#[derive(Debug, Default, PartialEq)]
+pub struct History {
+    /// Latest start per (chore, repository id).
+    last_runs: HashMap<(String, String), DateTime<Utc>>,
+    /// Reported cost of the chores started `today`.
+    pub spent_today: f64,
+}
+
+pub async fn history(synapse: &Synapse) -> anyhow::Result<History> {
+    let query = format!(
+        r#"
+        SELECT ?chore ?kind ?repo ?at ?cost WHERE {{
+            ?chore a <{CHORE}> ;
+                   <{CHORE_KIND}> ?kind ;
+                   <{REPOSITORY}> ?repo ;
+                   <{STARTED_AT}> ?at .
+            OPTIONAL {{ ?run <{RUN_OF}> ?chore ; <{COST_USD}> ?cost }}
+        }}
+    "#
+    );
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(&query).await?).unwrap_or_default();
+    Ok(summarize(&rows, crate::clock::today()))
+}
+
+fn summarize(rows: &[Value], today: NaiveDate) -> History {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+    let mut history = History::default();
+    // A chore has one run, but the OPTIONAL may repeat it; count each cost once.
+    let mut costs: HashMap<String, f64> = HashMap::new();
+    for row in rows {
+        let (Some(chore), Some(kind), Some(repo), Some(at)) = (field(row, "chore"), field(row, "kind"), field(row, "repo"), field(row, "at")) else {
+            continue;
+        };
+        let Ok(at) = DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)) else { continue };
+        let repo = repo.rsplit('/').next().unwrap_or(&repo).to_string();
+        history.last_runs.entry((kind, repo)).and_modify(|last| *last = (*last).max(at)).or_insert(at);
+        if crate::clock::date_of(at) == today {
+            if let Some(cost) = field(row, "cost").and_then(|c| c.parse::<f64>().ok()) {
+                costs.insert(chore, cost);
+            }
+        }
+    }
+    history.spent_today = costs.values().sum();
+    history
+}
+
+/// Writes the chore `id` before its run starts, so it counts as done for the interval even if
+/// the run never reports back.
+pub async fn record_start(synapse: &Synapse, id: &str, chore: &Chore, title: &str, repo: &str) -> anyhow::Result<()> {
+    let kind = format!("\"{}\"", chore.kind);
+    let title = sparql::literal(title);
+    let repo = format!("http://swarm.os/repository/{}", repo);
+    let started = tasks::datetime_literal(&Utc::now());
+    synapse
+        .ingest_from(Source::Agency, vec![
+            (id, RDF_TYPE, CHORE),
+            (id, TITLE, &title),
+            (id, CHORE_KIND, &kind),
+            (id, REPOSITORY, &repo),
+            (id, STARTED_AT, &started),
+        ])
+        .await
+}
+
+pub fn new_id() -> String {
+    format!("{}{}", CHORE_PREFIX, uuid::Uuid::new_v4())
+}
+
+/// A running chore, the agent it runs on and whether it was already told to stop.
+struct Running {
+    agent: String,
+    preempt: Arc<Notify>,
+    preempted: bool,
+}
+
+/// The chores running in this process, each with the signal that preempts it. A chore stays
+/// listed until its run is over, so no other starts meanwhile.
+#[derive(Default)]
+pub struct InFlight {
+    runs: Mutex<HashMap<String, Running>>,
+}
+
+pub fn in_flight() -> &'static InFlight {
+    static IN_FLIGHT: OnceLock<InFlight> = OnceLock::new();
+    IN_FLIGHT.get_or_init(InFlight::default)
+}
+
+impl InFlight {
+    pub fn start(&self, chore: &str, agent: &str) {
+        let run = Running { agent: agent.to_string(), preempt: Arc::new(Notify::new()), preempted: false };
+        self.lock().insert(chore.to_string(), run);
+    }
+
+    /// Fires when the chore is preempted, even if that happened before anyone waited.
+    pub fn signal(&self, chore: &str) -> Option<Arc<Notify>> {
+        self.lock().get(chore).map(|run| run.preempt.clone())
+    }
+
+    pub fn finish(&self, chore: &str) {
+        self.lock().remove(chore);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Agents of the running chores that were not told to stop yet.
+    pub fn agents(&self) -> Vec<String> {
+        self.lock().values().filter(|run| !run.preempted).map(|run| run.agent.clone()).collect()
+    }
+
+    /// Signals the running chores on the agents `wanted` picks to stop; returns the ids of
+    /// those not signalled before.
+    pub fn preempt(&self, wanted: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut runs = self.lock();
+        let mut preempted = Vec::new();
+        for (chore, run) in runs.iter_mut().filter(|(_, run)| !run.preempted && wanted(&run.agent)) {
+            run.preempt.notify_one();
+            run.preempted = true;
+            preempted.push(chore.clone());
+        }
+        preempted
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Running>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn agent(id: &str, repo: &str) -> AgentCandidate {
+        AgentCandidate { id: id.to_string(), repo: repo.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn chores_parse_from_names_and_pairs() {
+        let chores = Chores::parse(Some("todo-triage, licenses=Check the licenses of {repo}"), 2.0, 12).unwrap();
+        assert!(chores.enabled());
+        assert_eq!(chores.chores[1].title_for("api"), "Check the licenses of api");
+        assert!(!Chores::parse(None, 2.0, 24).unwrap().enabled());
+        assert!(!Chores::parse(Some("doc-gaps"), 0.0, 24).unwrap().enabled());
+        assert!(Chores::parse(Some("vacuum"), 1.0, 24).is_err());
+        assert!(Chores::parse(Some("doc-gaps,doc-gaps"), 1.0, 24).is_err());
+        assert!(Chores::parse(Some("a b=Title"), 1.0, 24).is_err());
+    }
+
+    #[test]
+    fn the_longest_waiting_chore_goes_to_a_free_agent_of_its_repository() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let now = "2026-10-17T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let rows = vec![
+            json!({"chore": "<http://swarm.os/chore/1>", "kind": "\"doc-gaps\"", "repo": "<http://swarm.os/repository/api>", "at": "\"2026-10-17T08:00:00Z\"", "cost": "\"0.250\"^^<http://www.w3.org/2001/XMLSchema#decimal>"}),
+            json!({"chore": "<http://swarm.os/chore/1>", "kind": "\"doc-gaps\"", "repo": "<http://swarm.os/repository/api>", "at": "\"2026-10-17T08:00:00Z\"", "cost": "\"0.250\""}),
+            json!({"?chore": "<http://swarm.os/chore/2>", "?kind": "\"todo-triage\"", "?repo": "<http://swarm.os/repository/api>", "?at": "\"2026-10-16T08:00:00Z\"", "?cost": "\"1.0\""}),
+            json!({"chore": "<http://swarm.os/chore/3>", "kind": "\"todo-triage\"", "repo": "<http://swarm.os/repository/web>", "at": "\"2026-10-17T09:00:00Z\""}),
+        ];
+        let history = summarize(&rows, today);
+        assert_eq!(history.spent_today, 0.25);
+
+        let chores = Chores::parse(Some("doc-gaps,todo-triage"), 1.0, 24).unwrap();
+        let agents = [agent("agent/floating", ""), agent("agent/web", "web"), agent("agent/api", "api")];
+        // web: doc-gaps never ran. api: todo-triage last ran 28h ago, doc-gaps 4h ago.
+        let (chore, index) = chores.pick(&agents, &history, now).unwrap();
+        assert_eq!((chore.kind.as_str(), index), ("doc-gaps", 1));
+        assert_eq!(chores.pick(&agents[2..], &history, now).map(|(c, _)| c.kind.as_str()), Some("todo-triage"));
+        assert!(chores.pick(&agents[..1], &history, now).is_none());
+
+        let running = InFlight::default();
+        running.start("http://swarm.os/chore/4", "agent/web");
+        assert!(running.preempt(|agent| agent == "agent/api").is_empty());
+        assert_eq!(running.preempt(|agent| agent == "agent/web"), vec!["http://swarm.os/chore/4".to_string()]);
+        assert!(running.preempt(|_| true).is_empty());
+        assert!(running.agents().is_empty());
+        assert!(!running.is_empty());
+        running.finish("http://swarm.os/chore/4");
+        assert!(running.is_empty());
+    }
+}
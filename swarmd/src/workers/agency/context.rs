@@ -0,0 +1,173 @@
+//! What a run is told about its task beyond the title. Before the run starts, the agency
+//! writes a JSON document under `SWARM_ARTIFACTS_DIR/contexts` and names it in
+//! `SWARM_TASK_CONTEXT`:
+//!
+//! ```json
+//! {"task": "http://swarm.os/task/…", "title": "Add SSO", "description": "…", "repository": "…",
+//!  "labels": ["backend"], "attempt": 2, "prior_failures": ["…"],
+//!  "budget": {"remaining_today_usd": 4.2, "run_limit_usd": null}}
+//! ```
+//!
+//! The document is removed when the run ends. Runs also get the Synapse endpoint
+//! (`SYNAPSE_GRPC_HOST`, `SYNAPSE_GRPC_PORT` and `SYNAPSE_GRAPH` when set), so they can read
+//! whatever else they need from the graph.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::TaskRun;
+use crate::budget::{self, BudgetLimit};
+use crate::config::AppConfig;
+use crate::sparql::Query;
+use crate::store::Synapse;
+
+#[derive(Debug, Serialize)]
+pub struct TaskContext {
+    pub task: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// IRI of the repository the task is on.
+    pub repository: Option<String>,
+    pub labels: Vec<String>,
+    /// 1 for the first run of the task.
+    pub attempt: u32,
+    /// Failure reports of the earlier attempts, oldest first.
+    pub prior_failures: Vec<String>,
+    pub budget: Budget,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Budget {
+    /// What is left of today's budget; `None` when today's spend could not be read.
+    pub remaining_today_usd: Option<f64>,
+    /// What a background chore may still spend; `None` for tasks.
+    pub run_limit_usd: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct DetailRow {
+    description: Option<String>,
+    repo: Option<String>,
+    label: Option<String>,
+}
+
+/// The variables that point a run at the Synapse swarmd uses, and let it in: the graph, the
+/// TLS settings and the bearer token, each only when set.
+pub fn synapse_env(cfg: &AppConfig) -> Vec<(&'static str, String)> {
+    let mut env = vec![("SYNAPSE_GRPC_HOST", cfg.synapse_grpc_host.clone()), ("SYNAPSE_GRPC_PORT", cfg.synapse_grpc_port.clone())];
+    env.extend(cfg.synapse_tls.then(|| ("SYNAPSE_TLS", "true".to_string())));
+    let optional = [
+        ("SYNAPSE_GRAPH", &cfg.synapse_graph),
+        ("SYNAPSE_TLS_CA", &cfg.synapse_tls_ca),
+        ("SYNAPSE_TLS_CERT", &cfg.synapse_tls_cert),
+        ("SYNAPSE_TLS_KEY", &cfg.synapse_tls_key),
+        ("SYNAPSE_TLS_DOMAIN", &cfg.synapse_tls_domain),
+        ("SYNAPSE_TOKEN", &cfg.synapse_token),
+    ];
+    env.extend(optional.into_iter().filter_map(|(name, value)| value.clone().map(|value| (name, value))));
+    env
+}
+
+#[derive(Debug, Clone)]
+pub struct Contexts {
+    dir: PathBuf,
+}
+
+impl Contexts {
+    pub fn new(artifacts_dir: &str) -> Self {
+        Self { dir: super::run_dir(artifacts_dir, "contexts") }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Gathers the context of `run` and writes it where the run can read it.
+    pub async fn write(&self, synapse: &Synapse, budget: &BudgetLimit, run: &TaskRun) -> anyhow::Result<PathBuf> {
+        let context = gather(synapse, budget, run).await?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let name = format!("{}-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S"), uuid::Uuid::new_v4().simple());
+        let path = self.dir.join(format!("{name}.json"));
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&context)?).await?;
+        Ok(path)
+    }
+
+    pub async fn remove(&self, path: &Path) {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+}
+
+async fn gather(synapse: &Synapse, budget: &BudgetLimit, run: &TaskRun) -> anyhow::Result<TaskContext> {
+    let query = Query::new(
+        r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?description ?repo ?label WHERE {
+            OPTIONAL { $task swarm:description ?description }
+            OPTIONAL { $task swarm:repository ?repo }
+            OPTIONAL { $task swarm:label ?label }
+        }
+    "#,
+    )
+    .iri("task", &run.task_id);
+    let rows: Vec<DetailRow> = synapse.select_as(&query).await?;
+
+    let mut labels: Vec<String> = rows.iter().filter_map(|row| row.label.clone()).collect();
+    labels.sort();
+    labels.dedup();
+    let remaining_today_usd = budget::spent_today(synapse).await.ok().map(|spent| (budget.get() - spent).max(0.0));
+    Ok(TaskContext {
+        task: run.task_id.clone(),
+        title: run.title.clone(),
+        description: rows.iter().find_map(|row| row.description.clone()),
+        repository: rows.iter().find_map(|row| row.repo.clone()),
+        labels,
+        attempt: run.attempt,
+        prior_failures: run.failures.clone(),
+        budget: Budget { remaining_today_usd, run_limit_usd: run.chore_budget_usd },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn the_context_has_the_task_details_attempts_and_budget() {
+        let (store, synapse) = MemoryStore::shared();
+        store.answer(
+            "swarm:description",
+            json!([
+                {"?description": "\"Use the corporate IdP\"", "?repo": "<http://swarm.os/repository/api>", "?label": "\"backend\""},
+                {"?description": "\"Use the corporate IdP\"", "?repo": "<http://swarm.os/repository/api>", "?label": "\"auth\""},
+            ]),
+        );
+        let run = TaskRun {
+            task_id: "http://swarm.os/task/1".into(),
+            title: "Add SSO".into(),
+            agent_id: "http://swarm.os/agent/Coder_1".into(),
+            attempt: 2,
+            timeout: std::time::Duration::from_secs(60),
+            failures: vec!["Timed out after 60s".into()],
+            chore_budget_usd: None,
+        };
+        let dir = std::env::temp_dir().join(format!("swarm-context-{}", uuid::Uuid::new_v4()));
+        let contexts = Contexts::new(dir.to_str().unwrap());
+
+        let path = contexts.write(&synapse, &BudgetLimit::new(10.0), &run).await.unwrap();
+        assert!(path.starts_with(contexts.dir()));
+        let written: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written["description"], "Use the corporate IdP");
+        assert_eq!(written["repository"], "http://swarm.os/repository/api");
+        assert_eq!(written["labels"], json!(["auth", "backend"]));
+        assert_eq!(written["attempt"], 2);
+        assert_eq!(written["prior_failures"], json!(["Timed out after 60s"]));
+        assert_eq!(written["budget"], json!({"remaining_today_usd": 10.0, "run_limit_usd": null}));
+
+        contexts.remove(&path).await;
+        assert!(!path.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+//! Context handed from one stage to the next. An initiative's steps (design, implement,
+//! verify) are tasks chained with `swarm:dependsOn`, and each is run by a fresh orchestrator
+//! that would otherwise start from the title alone.
+//!
+//! A run may write a JSON object to the file named by `SWARM_HANDOFF_OUT`: decisions, files
+//! touched, open questions, whatever the next stage needs. When the run succeeds the file is kept
+//! under `SWARM_ARTIFACTS_DIR/handoffs` and linked to its task with `swarm:handoff`. The next run
+//! of a task whose dependencies left handoffs gets them bundled in the file named by
+//! `SWARM_HANDOFF_IN`:
+//!
+//! ```json
+//! {"task": "http://swarm.os/task/…", "handoffs": [{"task": "…", "title": "Design", "document": {}}]}
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::provenance::Source;
+use crate::sparql::{self, Query};
+use crate::store::Synapse;
+use crate::tasks::literal_value;
+
+/// Path of a handoff document a task's run left behind.
+pub const HANDOFF: &str = "http://swarm.os/ontology/handoff";
+/// Larger documents are dropped; they are meant to be a summary, not the work itself.
+const MAX_DOCUMENT_BYTES: u64 = 1024 * 1024;
+
+/// Where one run reads its inbound handoffs and writes its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunFiles {
+    /// Bundle of the dependencies' handoffs; `None` when they left none.
+    pub inbound: Option<PathBuf>,
+    pub outbound: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct Handoffs {
+    dir: PathBuf,
+}
+
+impl Handoffs {
+    pub fn new(artifacts_dir: &str) -> Self {
+        Self { dir: super::run_dir(artifacts_dir, "handoffs") }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Bundles what the dependencies of `task_id` handed off and picks where the run writes
+    /// its own handoff.
+    pub async fn prepare(&self, synapse: &Synapse, task_id: &str) -> anyhow::Result<RunFiles> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let name = format!("{}-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S"), uuid::Uuid::new_v4().simple());
+        let outbound = self.dir.join(format!("{name}.json"));
+
+        let query = Query::new(format!(
+            r#"
+            PREFIX swarm: <http://swarm.os/ontology/>
+            SELECT ?dependency ?title ?document WHERE {{
+                $task swarm:dependsOn ?dependency .
+                ?dependency <{HANDOFF}> ?document .
+                OPTIONAL {{ ?dependency swarm:title ?title }}
+            }}
+        "#
+        ))
+        .iri("task", task_id);
+        let rows: Vec<Value> = serde_json::from_str(&synapse.select(&query).await?).unwrap_or_default();
+        let mut handoffs = Vec::new();
+        for (dependency, (title, path)) in latest_by_dependency(&rows) {
+            match read_document(Path::new(&path)).await {
+                Ok(document) => handoffs.push(json!({"task": dependency, "title": title, "document": document})),
+                Err(e) => warn!("Skipping the handoff of {} to {}: {:#}", dependency, task_id, e),
+            }
+        }
+        if handoffs.is_empty() {
+            return Ok(RunFiles { inbound: None, outbound });
+        }
+
+        let inbound = self.dir.join(format!("{name}.in.json"));
+        let bundle = json!({"task": task_id, "handoffs": handoffs});
+        tokio::fs::write(&inbound, serde_json::to_vec_pretty(&bundle)?).await?;
+        Ok(RunFiles { inbound: Some(inbound), outbound })
+    }
+
+    /// Keeps the handoff a successful run wrote and links it to its task. `Ok(false)` when
+    /// the run wrote none.
+    pub async fn collect(&self, synapse: &Synapse, task_id: &str, files: &RunFiles) -> anyhow::Result<bool> {
+        remove(files.inbound.as_deref()).await;
+        if !tokio::fs::try_exists(&files.outbound).await.unwrap_or(false) {
+            return Ok(false);
+        }
+        if let Err(e) = read_document(&files.outbound).await {
+            remove(Some(&files.outbound)).await;
+            return Err(e);
+        }
+        let path = files.outbound.to_string_lossy();
+        synapse.ingest_from(Source::Agency, vec![(task_id, HANDOFF, &sparql::literal(&path))]).await?;
+        Ok(true)
+    }
+
+    /// Drops the files of a run that did not succeed; a failed stage hands nothing on.
+    pub async fn discard(&self, files: &RunFiles) {
+        remove(files.inbound.as_deref()).await;
+        remove(Some(&files.outbound)).await;
+    }
+}
+
+/// The newest handoff path and the title of each dependency. Paths start with their
+/// creation time, so the greatest is the latest.
+fn latest_by_dependency(rows: &[Value]) -> BTreeMap<String, (String, String)> {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+    let mut latest: BTreeMap<String, (String, String)> = BTreeMap::new();
+    for row in rows {
+        let (Some(dependency), Some(path)) = (field(row, "dependency"), field(row, "document")) else { continue };
+        let title = field(row, "title").unwrap_or_default();
+        let entry = latest.entry(dependency).or_insert_with(|| (title.clone(), path.clone()));
+        if path > entry.1 {
+            *entry = (title, path);
+        }
+    }
+    latest
+}
+
+async fn read_document(path: &Path) -> anyhow::Result<Value> {
+    let size = tokio::fs::metadata(path).await.with_context(|| format!("reading {}", path.display()))?.len();
+    if size > MAX_DOCUMENT_BYTES {
+        bail!("{} is {} bytes; handoffs are limited to {}", path.display(), size, MAX_DOCUMENT_BYTES);
+    }
+    let raw = tokio::fs::read(path).await.with_context(|| format!("reading {}", path.display()))?;
+    match serde_json::from_slice::<Value>(&raw).with_context(|| format!("{} is not JSON", path.display()))? {
+        document @ Value::Object(_) => Ok(document),
+        _ => bail!("{} is not a JSON object", path.display()),
+    }
+}
+
+async fn remove(path: Option<&Path>) {
+    if let Some(path) = path {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keeps_the_latest_handoff_per_dependency_and_only_json_objects() {
+        let rows = vec![
+            json!({"dependency": "<task/design>", "title": "\"Design\"", "document": "\"data/handoffs/20260101T000000-a.json\""}),
+            json!({"?dependency": "<task/design>", "?title": "\"Design\"", "?document": "\"data/handoffs/20260301T000000-b.json\""}),
+            json!({"dependency": "<task/spike>", "document": "\"data/handoffs/20260201T000000-c.json\""}),
+            json!({"dependency": "<task/broken>"}),
+        ];
+        let latest = latest_by_dependency(&rows);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest["task/design"], ("Design".to_string(), "data/handoffs/20260301T000000-b.json".to_string()));
+        assert_eq!(latest["task/spike"].0, "");
+
+        let dir = std::env::temp_dir().join(format!("swarm-handoff-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let document = dir.join("object.json");
+        std::fs::write(&document, r#"{"decisions": ["Use OAuth"]}"#).unwrap();
+        assert_eq!(read_document(&document).await.unwrap()["decisions"][0], "Use OAuth");
+        std::fs::write(&document, "[1, 2]").unwrap();
+        assert!(read_document(&document).await.is_err());
+        assert!(read_document(&dir.join("missing.json")).await.is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
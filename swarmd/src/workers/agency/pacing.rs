@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+/// Pause after a launch, when more runnable tasks may be waiting.
+const BUSY: Duration = Duration::from_secs(1);
+/// Pause while tasks keep being launched now and then; where backoff restarts from.
+pub const BASE: Duration = Duration::from_secs(5);
+/// Longest pause while there is nothing to run; new tasks and freed agents cut it short.
+const MAX_IDLE: Duration = Duration::from_secs(30);
+/// Longest pause while Synapse keeps failing.
+const MAX_FAILING: Duration = Duration::from_secs(120);
+
+/// What one pass of the agency loop came to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Launched,
+    /// No runnable task or no free agent.
+    Idle,
+    /// Synapse could not be queried.
+    Failed,
+}
+
+/// The agency loop's pause between passes: short while there is work, doubling while there
+/// is none or Synapse is failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    interval: Duration,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self { interval: BASE }
+    }
+}
+
+impl Pacing {
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Adjusts the pause to the outcome of the pass that just ran and returns it.
+    pub fn record(&mut self, outcome: Outcome) -> Duration {
+        self.interval = match outcome {
+            Outcome::Launched => BUSY,
+            Outcome::Idle => self.interval.max(BASE / 2).saturating_mul(2).min(MAX_IDLE),
+            Outcome::Failed => self.interval.max(BASE).saturating_mul(2).min(MAX_FAILING),
+        };
+        self.interval
+    }
+
+    /// New work arrived: back to the base pace.
+    pub fn reset(&mut self) {
+        self.interval = BASE;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_while_idle_or_failing_and_speeds_up_on_work() {
+        let mut pacing = Pacing::default();
+        let secs = |d: Duration| d.as_secs();
+        let idle: Vec<u64> = (0..4).map(|_| secs(pacing.record(Outcome::Idle))).collect();
+        assert_eq!(idle, [10, 20, 30, 30]);
+        assert_eq!(secs(pacing.record(Outcome::Launched)), 1);
+        assert_eq!(secs(pacing.record(Outcome::Idle)), 5);
+
+        let failing: Vec<u64> = (0..5).map(|_| secs(pacing.record(Outcome::Failed))).collect();
+        assert_eq!(failing, [10, 20, 40, 80, 120]);
+        // Synapse is back but there is nothing to do: the idle cap applies again.
+        assert_eq!(secs(pacing.record(Outcome::Idle)), 30);
+        pacing.reset();
+        assert_eq!(pacing.interval(), BASE);
+    }
+}
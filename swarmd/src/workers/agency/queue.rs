@@ -0,0 +1,89 @@
+//! The order runnable tasks are offered to agents in. Higher `swarm:priority` goes first, and a
+//! task gains one level of priority for every `PRIORITY_AGING_HOURS` it has waited, so old
+//! low-priority work is not starved by a steady stream of urgent tasks. The wait counts from
+//! `swarm:queuedAt`, which the agency stamps the first time it sees a task runnable. Ties go
+//! to the task that waited longest, then by title, so the order is stable between ticks.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use super::RunnableRow;
+use crate::tasks;
+
+/// When the agency first saw the task runnable.
+pub const QUEUED_AT: &str = "http://swarm.os/ontology/queuedAt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aging {
+    /// Wait that earns one level of priority; `None` turns aging off.
+    step: Option<Duration>,
+}
+
+impl Aging {
+    pub fn from_hours(hours: u64) -> Self {
+        Self { step: (hours > 0).then(|| Duration::from_secs(hours * 3600)) }
+    }
+
+    /// `swarm:priority` of the task in `row`, raised by its wait.
+    pub fn priority(&self, row: &RunnableRow, now: DateTime<Utc>) -> i64 {
+        let waited = row.queued_at.and_then(|at| (now - at).to_std().ok()).unwrap_or_default();
+        let bonus = match self.step {
+            Some(step) => (waited.as_secs() / step.as_secs().max(1)) as i64,
+            None => 0,
+        };
+        row.priority.unwrap_or(tasks::NORMAL_PRIORITY).saturating_add(bonus)
+    }
+
+    /// One row per task, most urgent first.
+    pub fn order<'a>(&self, rows: &'a [RunnableRow], now: DateTime<Utc>) -> Vec<&'a RunnableRow> {
+        let mut seen = HashSet::new();
+        let mut rows: Vec<&RunnableRow> = rows.iter().filter(|row| seen.insert(&row.task)).collect();
+        // Never stamped sorts as just arrived.
+        let since = |row: &RunnableRow| row.queued_at.unwrap_or(now);
+        rows.sort_by(|a, b| {
+            self.priority(b, now)
+                .cmp(&self.priority(a, now))
+                .then_with(|| since(a).cmp(&since(b)))
+                .then_with(|| a.title.cmp(&b.title))
+        });
+        rows
+    }
+}
+
+/// Tasks in `rows` that carry no `swarm:queuedAt` yet.
+pub fn unstamped(rows: &[RunnableRow]) -> Vec<String> {
+    let stamped: HashSet<&str> = rows.iter().filter(|row| row.queued_at.is_some()).map(|row| row.task.as_str()).collect();
+    let mut tasks: Vec<String> = rows.iter().map(|row| row.task.clone()).filter(|task| !stamped.contains(task.as_str())).collect();
+    tasks.sort();
+    tasks.dedup();
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sparql;
+    use serde_json::json;
+
+    #[test]
+    fn old_low_priority_tasks_climb_past_new_urgent_ones() {
+        let now = Utc::now();
+        let stamp = |hours: i64| tasks::datetime_literal(&(now - chrono::Duration::hours(hours)));
+        let rows: Vec<RunnableRow> = sparql::rows(&json!([
+            {"task": "<t/urgent>", "title": "\"Hotfix\"", "priority": tasks::integer_literal(3), "queuedAt": stamp(1)},
+            {"task": "<t/old>", "title": "\"Cleanup\"", "priority": tasks::integer_literal(0), "queuedAt": stamp(80)},
+            {"?task": "<t/old>", "?title": "\"Cleanup\""},
+            {"task": "<t/new>", "title": "\"Docs\""},
+            {"task": "<t/normal>", "title": "\"Bump\"", "queuedAt": stamp(2)},
+        ]).to_string())
+        .unwrap();
+        let order = |aging: Aging| aging.order(&rows, now).iter().map(|row| row.task.as_str()).collect::<Vec<_>>();
+
+        // 80h at one level per day lifts Cleanup from 0 to 3, and it waited longer than Hotfix.
+        assert_eq!(order(Aging::from_hours(24)), ["t/old", "t/urgent", "t/normal", "t/new"]);
+        assert_eq!(order(Aging::from_hours(0)), ["t/urgent", "t/normal", "t/new", "t/old"]);
+        assert_eq!(unstamped(&rows), ["t/new"]);
+    }
+}
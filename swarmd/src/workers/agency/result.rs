@@ -0,0 +1,123 @@
+//! What an orchestrator reports when it is done, on a line of its stdout of its own:
+//!
+//! ```text
+//! SWARM_RESULT {"status": "success", "summary": "Added SSO login", "cost_usd": 0.42,
+//!               "artifacts": [{"kind": "pull_request", "uri": "https://github.com/acme/api/pull/7"}]}
+//! ```
+//!
+//! The JSON stays on one line; `docs/orchestrator-result.schema.json` describes it. The last
+//! such line wins. A run that prints none is judged by its exit code alone, and a run that
+//! exits 0 but reports `failure` counts as failed.
+
+use serde::Deserialize;
+
+use crate::sparql::literal;
+use crate::tasks;
+use crate::workers::github::pulls::PullRef;
+
+pub const RESULT_PREFIX: &str = "SWARM_RESULT ";
+
+pub const SUMMARY: &str = "http://swarm.os/ontology/summary";
+pub const COST_USD: &str = "http://swarm.os/ontology/costUsd";
+pub const ARTIFACT: &str = "http://swarm.os/ontology/artifact";
+pub const ARTIFACT_KIND: &str = "http://swarm.os/ontology/artifactKind";
+pub const ARTIFACT_URI: &str = "http://swarm.os/ontology/artifactUri";
+pub const ARTIFACT_TITLE: &str = "http://swarm.os/ontology/artifactTitle";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const ARTIFACT_CLASS: &str = "http://swarm.os/ontology/Artifact";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultStatus {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Artifact {
+    /// `pull_request`, `file`, `branch`, `commit`, `report`…
+    pub kind: String,
+    pub uri: String,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RunResult {
+    pub status: ResultStatus,
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// LLM spend of the run. Informational: the orchestrator records its spend events itself.
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+}
+
+impl RunResult {
+    /// The result on the last `SWARM_RESULT` line of `output`, or why it could not be read.
+    pub fn last_in(output: &str) -> Option<Result<Self, String>> {
+        let line = output.lines().rev().find_map(|line| line.trim_start().strip_prefix(RESULT_PREFIX))?;
+        let parsed = serde_json::from_str::<Self>(line.trim()).map_err(|e| e.to_string()).and_then(|result| {
+            match result.cost_usd {
+                Some(cost) if !cost.is_finite() || cost < 0.0 => Err(format!("cost_usd must be a non-negative number, got {}", cost)),
+                _ => Ok(result),
+            }
+        });
+        Some(parsed)
+    }
+
+    /// The first pull request among the artifacts.
+    pub fn pull(&self) -> Option<PullRef> {
+        self.artifacts.iter().filter(|a| a.kind == "pull_request").find_map(|a| PullRef::last_in(&a.uri))
+    }
+
+    /// Facts about the result for the run node `run_id`.
+    pub fn triples(&self, run_id: &str) -> Vec<(String, &'static str, String)> {
+        let mut triples = Vec::new();
+        if let Some(summary) = self.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+            triples.push((run_id.to_string(), SUMMARY, literal(summary.trim())));
+        }
+        if let Some(cost) = self.cost_usd {
+            triples.push((run_id.to_string(), COST_USD, tasks::decimal_literal(cost)));
+        }
+        for (i, artifact) in self.artifacts.iter().enumerate() {
+            let node = format!("{}/artifact/{}", run_id, i);
+            triples.push((run_id.to_string(), ARTIFACT, node.clone()));
+            triples.push((node.clone(), RDF_TYPE, ARTIFACT_CLASS.to_string()));
+            triples.push((node.clone(), ARTIFACT_KIND, literal(&artifact.kind)));
+            triples.push((node.clone(), ARTIFACT_URI, literal(&artifact.uri)));
+            if let Some(title) = &artifact.title {
+                triples.push((node, ARTIFACT_TITLE, literal(title)));
+            }
+        }
+        triples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_last_result_line_and_describes_it() {
+        let stdout = "\
+🏛️  Mode: COUNCIL
+SWARM_RESULT {\"status\": \"failure\", \"summary\": \"first try\"}
+Opened https://github.com/acme/web/pull/3
+SWARM_RESULT {\"status\": \"success\", \"summary\": \"Added \\\"SSO\\\" login\", \"cost_usd\": 0.42, \"artifacts\": [{\"kind\": \"file\", \"uri\": \"src/sso.rs\"}, {\"kind\": \"pull_request\", \"uri\": \"https://github.com/acme/api/pull/7\", \"title\": \"SSO\"}]}
+";
+        let result = RunResult::last_in(stdout).unwrap().unwrap();
+        assert_eq!(result.status, ResultStatus::Success);
+        assert_eq!(result.pull().unwrap().url(), "https://github.com/acme/api/pull/7");
+
+        let triples = result.triples("http://swarm.os/run/1");
+        assert!(triples.contains(&("http://swarm.os/run/1".to_string(), SUMMARY, r#""Added \"SSO\" login""#.to_string())));
+        assert!(triples.contains(&("http://swarm.os/run/1/artifact/1".to_string(), ARTIFACT_TITLE, "\"SSO\"".to_string())));
+        assert_eq!(triples.iter().filter(|(_, p, _)| *p == ARTIFACT).count(), 2);
+
+        assert!(RunResult::last_in("no result here\n").is_none());
+        assert!(RunResult::last_in("SWARM_RESULT {\"summary\": \"no status\"}").unwrap().is_err());
+        assert!(RunResult::last_in("SWARM_RESULT {\"status\": \"success\", \"cost_usd\": -1}").unwrap().is_err());
+    }
+}
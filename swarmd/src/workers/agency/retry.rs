@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use super::TaskRun;
+use crate::config::AppConfig;
+
+/// Exponential backoff: attempt 1 waits `base_delay`, each further attempt doubles it,
+/// capped at `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            max_attempts: cfg.agency_max_attempts.max(1),
+            base_delay: Duration::from_secs(cfg.agency_retry_base_secs),
+            max_delay: Duration::from_secs(cfg.agency_retry_max_secs),
+        }
+    }
+
+    pub fn delay_for(&self, failed_attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failed_attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    pub fn exhausted(&self, failed_attempt: u32) -> bool {
+        failed_attempt >= self.max_attempts
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PendingRetry {
+    run: TaskRun,
+    due: Instant,
+}
+
+/// Failed runs waiting for their backoff to elapse. Shared between the agency loop
+/// and the spawned orchestrator tasks that report failures.
+#[derive(Debug, Clone, Default)]
+pub struct RetryQueue {
+    pending: Arc<Mutex<Vec<PendingRetry>>>,
+}
+
+impl RetryQueue {
+    pub async fn schedule(&self, run: TaskRun, delay: Duration) {
+        self.pending.lock().await.push(PendingRetry { run, due: Instant::now() + delay });
+    }
+
+    /// Removes and returns every run whose backoff has elapsed, oldest first.
+    pub async fn take_due(&self) -> Vec<TaskRun> {
+        let now = Instant::now();
+        let mut pending = self.pending.lock().await;
+        pending.sort_by_key(|p| p.due);
+        let split = pending.iter().position(|p| p.due > now).unwrap_or(pending.len());
+        pending.drain(..split).map(|p| p.run).collect()
+    }
+
+    /// Drops the pending retry of `task`, if any.
+    pub async fn remove(&self, task: &str) {
+        self.pending.lock().await.retain(|p| p.run.task_id != task);
+    }
+
+    /// Tasks whose retry is due, without taking them.
+    pub async fn due_tasks(&self) -> Vec<String> {
+        let now = Instant::now();
+        self.pending.lock().await.iter().filter(|p| p.due <= now).map(|p| p.run.task_id.clone()).collect()
+    }
+
+    /// When the earliest pending retry is due.
+    pub async fn next_due(&self) -> Option<Instant> {
+        self.pending.lock().await.iter().map(|p| p.due).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(100),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_is_capped() {
+        let p = policy();
+        assert_eq!(p.delay_for(1), Duration::from_secs(30));
+        assert_eq!(p.delay_for(2), Duration::from_secs(60));
+        assert_eq!(p.delay_for(3), Duration::from_secs(100));
+        assert_eq!(p.delay_for(40), Duration::from_secs(100));
+    }
+
+    #[test]
+    fn attempts_are_exhausted_at_the_limit() {
+        let p = policy();
+        assert!(!p.exhausted(3));
+        assert!(p.exhausted(4));
+    }
+}
@@ -0,0 +1,145 @@
+//! How an agent runs a task. Every run goes through an `AgentRuntime`, picked per agent from
+//! the roster and set up by the entry of its class:
+//!
+//! - `host`: the agent's command as a child process of swarmd.
+//! - `docker`: the same command in a throwaway container.
+//! - `http`: a POST of the task to a service, which answers with the run's output.
+//! - `native`: an agent built into swarmd, running on its own task.
+//!
+//! Whatever the runtime, a run looks the same to the agency: output streams read line by line
+//! (heartbeats, traces, the `SWARM_RESULT` line, pull request URLs), an exit and a way to stop
+//! it early.
+
+mod docker;
+mod http;
+mod native;
+mod process;
+
+use std::fmt;
+use std::path::Path;
+
+use futures_util::future::BoxFuture;
+use tokio::io::{AsyncRead, DuplexStream};
+use tokio::task::AbortHandle;
+
+use crate::roster::{self, RuntimeSpec};
+
+pub use docker::Docker;
+pub use http::Http;
+pub use native::Native;
+pub use process::Process;
+
+/// Bytes buffered between an in-process run and the reader of its output.
+const PIPE_BYTES: usize = 64 * 1024;
+
+pub type Output = Box<dyn AsyncRead + Send + Unpin>;
+
+/// One task for a runtime to run.
+pub struct Job<'a> {
+    pub task_id: &'a str,
+    pub title: &'a str,
+    pub agent_id: &'a str,
+    /// Variables for the run: the timezone, handoff files, spend limits.
+    pub env: &'a [(&'a str, String)],
+    /// Directories holding files named in `env`.
+    pub shared: &'a [&'a Path],
+}
+
+impl Job<'_> {
+    /// The agent's command with the title last.
+    fn argv(&self) -> Vec<String> {
+        let mut argv = roster::command_for(self.agent_id);
+        argv.push(self.title.to_string());
+        argv
+    }
+}
+
+/// How a run ended on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exit {
+    pub success: bool,
+    /// The exit code of a process; runs that are not processes have none.
+    pub code: Option<i32>,
+    description: String,
+}
+
+impl Exit {
+    pub fn new(success: bool, code: Option<i32>, description: impl Into<String>) -> Self {
+        Self { success, code, description: description.into() }
+    }
+}
+
+impl From<std::process::ExitStatus> for Exit {
+    fn from(status: std::process::ExitStatus) -> Self {
+        Self::new(status.success(), status.code(), status.to_string())
+    }
+}
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+/// A run in progress.
+pub struct Execution {
+    /// The process running the task, whose process group is killed to stop it early.
+    pub pid: Option<u32>,
+    pub stdout: Option<Output>,
+    pub stderr: Option<Output>,
+    pub exit: BoxFuture<'static, std::io::Result<Exit>>,
+    /// The swarmd task of an in-process run.
+    task: Option<AbortHandle>,
+}
+
+impl Execution {
+    /// A run on a task of swarmd's own that writes its output into a pipe.
+    fn in_process<F>(run: impl FnOnce(DuplexStream) -> F) -> Self
+    where
+        F: std::future::Future<Output = std::io::Result<Exit>> + Send + 'static,
+    {
+        let (writer, reader) = tokio::io::duplex(PIPE_BYTES);
+        let handle = tokio::spawn(run(writer));
+        let task = handle.abort_handle();
+        Self {
+            pid: None,
+            stdout: Some(Box::new(reader)),
+            stderr: None,
+            exit: Box::pin(async move { handle.await.map_err(std::io::Error::other)? }),
+            task: Some(task),
+        }
+    }
+}
+
+/// Runs tasks for the agents it was resolved for.
+pub trait AgentRuntime: Send + Sync {
+    /// What runs the task, for logs and failure reports.
+    fn describe(&self) -> String;
+
+    fn start<'a>(&'a self, job: &'a Job<'a>) -> BoxFuture<'a, std::io::Result<Execution>>;
+
+    /// Stops a run that timed out, stalled or was preempted. Its exit is dropped afterwards.
+    fn stop<'a>(&'a self, execution: &'a mut Execution) -> BoxFuture<'a, ()> {
+        Box::pin(async move { stop_locally(execution) })
+    }
+}
+
+/// Stops what swarmd itself runs for `execution`: its process group or its task.
+fn stop_locally(execution: &Execution) {
+    if let Some(pid) = execution.pid {
+        process::kill_process_group(pid);
+    }
+    if let Some(task) = &execution.task {
+        task.abort();
+    }
+}
+
+/// The runtime of `agent` (an agent IRI), fresh for one run.
+pub fn for_agent(agent: &str) -> Box<dyn AgentRuntime> {
+    match roster::runtime_for(agent) {
+        RuntimeSpec::Host => Box::new(Process),
+        RuntimeSpec::Docker(spec) => Box::new(Docker::new(spec)),
+        RuntimeSpec::Http(spec) => Box::new(Http::new(spec)),
+        RuntimeSpec::Native(spec) => Box::new(Native::new(&spec.agent)),
+    }
+}
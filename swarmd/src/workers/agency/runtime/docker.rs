@@ -0,0 +1,133 @@
+//! Docker runs: the agent's command in a throwaway container of its class's image, with its
+//! mounts, variables and CPU/memory limits, so agent code never touches the host beyond what
+//! is mounted.
+//!
+//! swarmd still spawns a process, the `docker run` client, and reads its output the same way.
+//! Killing that client does not stop the container, so a run cut short is also stopped with
+//! `docker kill`. Resource usage of docker runs only covers the client.
+
+use std::path::{Path, PathBuf};
+
+use futures_util::future::BoxFuture;
+use tokio::process::Command;
+use tracing::warn;
+
+use super::{process, stop_locally, AgentRuntime, Execution, Job};
+use crate::roster::DockerSpec;
+
+/// The `docker` CLI; the daemon it talks to is up to its own configuration (`DOCKER_HOST`).
+const DOCKER: &str = "docker";
+
+pub struct Docker {
+    spec: &'static DockerSpec,
+    container: String,
+}
+
+impl Docker {
+    pub fn new(spec: &'static DockerSpec) -> Self {
+        Self { spec, container: format!("swarm-run-{}", uuid::Uuid::new_v4().simple()) }
+    }
+}
+
+impl AgentRuntime for Docker {
+    fn describe(&self) -> String {
+        format!("docker container of {}", self.spec.image)
+    }
+
+    fn start<'a>(&'a self, job: &'a Job<'a>) -> BoxFuture<'a, std::io::Result<Execution>> {
+        Box::pin(async move {
+            let mut command = Command::new(DOCKER);
+            command.args(docker_args(self.spec, &self.container, &job.argv(), job.env, job.shared));
+            // `-e NAME` reads the value from the client's environment.
+            command.envs(job.env.iter().map(|(k, v)| (*k, v.as_str())));
+            process::spawn(command)
+        })
+    }
+
+    fn stop<'a>(&'a self, execution: &'a mut Execution) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            stop_locally(execution);
+            match Command::new(DOCKER).args(["kill", &self.container]).output().await {
+                Ok(out) if out.status.success() => {}
+                // Already gone when the client's own teardown got there first.
+                Ok(out) => warn!("docker kill {} failed: {}", self.container, String::from_utf8_lossy(&out.stderr).trim()),
+                Err(e) => warn!("Could not run docker kill {}: {}", self.container, e),
+            }
+        })
+    }
+}
+
+fn docker_args(spec: &DockerSpec, container: &str, argv: &[String], env: &[(&str, String)], shared: &[&Path]) -> Vec<String> {
+    let mut args: Vec<String> = vec!["run".into(), "--rm".into(), "--init".into(), "--name".into(), container.into()];
+    if let Some(cpus) = spec.cpus {
+        args.extend(["--cpus".into(), cpus.to_string()]);
+    }
+    if let Some(memory) = &spec.memory {
+        args.extend(["--memory".into(), memory.clone()]);
+    }
+    if let Some(network) = &spec.network {
+        args.extend(["--network".into(), network.clone()]);
+    }
+    if let Some(workdir) = &spec.workdir {
+        args.extend(["--workdir".into(), workdir.clone()]);
+    }
+    for mount in &spec.mounts {
+        args.extend(["--volume".into(), resolve_mount(mount)]);
+    }
+    for dir in shared {
+        let dir = absolute(dir).to_string_lossy().into_owned();
+        args.extend(["--volume".into(), format!("{dir}:{dir}")]);
+    }
+    for (name, value) in &spec.env {
+        args.extend(["--env".into(), format!("{name}={value}")]);
+    }
+    for name in spec.pass_env.iter().map(String::as_str).chain(env.iter().map(|(k, _)| *k)) {
+        args.extend(["--env".into(), name.to_string()]);
+    }
+    args.push(spec.image.clone());
+    args.extend(argv.iter().cloned());
+    args
+}
+
+/// `docker run` wants absolute host paths for bind mounts.
+fn resolve_mount(mount: &str) -> String {
+    match mount.split_once(':') {
+        Some((host, rest)) if host.starts_with('.') => format!("{}:{}", absolute(Path::new(host)).display(), rest),
+        _ => mount.to_string(),
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docker_runs_get_the_class_limits_mounts_and_variables() {
+        let spec = DockerSpec {
+            image: "ghcr.io/acme/orchestrator:1".into(),
+            mounts: vec!["./workspace:/workspace:ro".into(), "/srv/cache:/cache".into()],
+            env: [("LOG_LEVEL".to_string(), "debug".to_string())].into(),
+            pass_env: vec!["OPENAI_API_KEY".into()],
+            cpus: Some(1.5),
+            memory: Some("2g".into()),
+            workdir: Some("/workspace".into()),
+            network: None,
+        };
+        let argv = vec!["python3".to_string(), "orchestrator.py".into(), "Add SSO".into()];
+        let env = [("SWARM_HANDOFF_OUT", "/data/handoffs/1.json".to_string())];
+        let args = docker_args(&spec, "swarm-run-1", &argv, &env, &[Path::new("/data/handoffs")]);
+        let line = args.join(" ");
+
+        assert!(line.starts_with("run --rm --init --name swarm-run-1 --cpus 1.5 --memory 2g --workdir /workspace "));
+        let cwd = std::env::current_dir().unwrap();
+        assert!(line.contains(&format!("--volume {}:/workspace:ro ", cwd.join("workspace").display())));
+        assert!(line.contains("--volume /srv/cache:/cache --volume /data/handoffs:/data/handoffs "));
+        assert!(line.contains("--env LOG_LEVEL=debug --env OPENAI_API_KEY --env SWARM_HANDOFF_OUT "));
+        assert!(line.ends_with("ghcr.io/acme/orchestrator:1 python3 orchestrator.py Add SSO"));
+        assert!(!line.contains("/data/handoffs/1.json"));
+    }
+}
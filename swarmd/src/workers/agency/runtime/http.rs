@@ -0,0 +1,85 @@
+//! HTTP runs: the task is POSTed to the service of the agent's class, and the response body
+//! is the run's output, read as it streams in. A 2xx status is a successful exit, anything else
+//! a failed one. The service gets the task, the agent and the run's variables as JSON:
+//!
+//! ```json
+//! {"task_id": "http://swarm.os/task/…", "title": "Add SSO", "agent_id": "http://swarm.os/agent/Reviewer_api",
+//!  "env": {"TZ": "Europe/Madrid"}}
+//! ```
+//!
+//! Handoff files live on swarmd's disk, so a remote service only sees their paths.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use super::{AgentRuntime, Execution, Exit, Job};
+use crate::roster::HttpSpec;
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+#[derive(Debug, Serialize)]
+struct RunRequest<'a> {
+    task_id: &'a str,
+    title: &'a str,
+    agent_id: &'a str,
+    env: BTreeMap<&'a str, &'a str>,
+}
+
+pub struct Http {
+    spec: &'static HttpSpec,
+}
+
+impl Http {
+    pub fn new(spec: &'static HttpSpec) -> Self {
+        Self { spec }
+    }
+}
+
+impl AgentRuntime for Http {
+    fn describe(&self) -> String {
+        format!("http callout to {}", self.spec.url)
+    }
+
+    fn start<'a>(&'a self, job: &'a Job<'a>) -> BoxFuture<'a, std::io::Result<Execution>> {
+        let body = RunRequest {
+            task_id: job.task_id,
+            title: job.title,
+            agent_id: job.agent_id,
+            env: job.env.iter().map(|(k, v)| (*k, v.as_str())).collect(),
+        };
+        let mut request = client().post(&self.spec.url).json(&body);
+        for (name, value) in &self.spec.headers {
+            request = request.header(name, value);
+        }
+        if let Some(var) = &self.spec.token_env {
+            match std::env::var(var) {
+                Ok(token) => request = request.bearer_auth(token),
+                Err(_) => tracing::warn!("{} is not set; calling {} without a token", var, self.spec.url),
+            }
+        }
+        Box::pin(async move {
+            // Fails here when the service cannot be reached, like a command that cannot start.
+            let response = request.send().await.map_err(std::io::Error::other)?;
+            Ok(Execution::in_process(|out| relay(response, out)))
+        })
+    }
+}
+
+/// Copies the response body into the run's output.
+async fn relay(mut response: reqwest::Response, mut out: tokio::io::DuplexStream) -> std::io::Result<Exit> {
+    let status = response.status();
+    while let Some(chunk) = response.chunk().await.map_err(std::io::Error::other)? {
+        if out.write_all(&chunk).await.is_err() {
+            // Nobody reads the output any more; the exit still counts.
+            break;
+        }
+    }
+    Ok(Exit::new(status.is_success(), None, format!("HTTP {}", status)))
+}
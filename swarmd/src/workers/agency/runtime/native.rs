@@ -0,0 +1,111 @@
+//! Native runs: agents written in Rust and built into swarmd. They run on a task of their own
+//! and report like any orchestrator, by printing lines and a final `SWARM_RESULT` line. A new
+//! one implements `NativeAgent` and is added to `NATIVE_AGENTS` under the name rosters use.
+
+use futures_util::future::BoxFuture;
+use serde_json::json;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+
+use super::super::result::RESULT_PREFIX;
+use super::{AgentRuntime, Execution, Exit, Job};
+
+/// The task a native agent gets, owned so it can outlive the call that started it.
+#[derive(Debug, Clone)]
+pub struct NativeTask {
+    pub task_id: String,
+    pub title: String,
+    pub agent_id: String,
+    pub env: Vec<(String, String)>,
+}
+
+pub trait NativeAgent: Send + Sync {
+    /// Runs `task`, writing its output to `out`; `Ok(false)` is a failed run.
+    fn run(&self, task: NativeTask, out: DuplexStream) -> BoxFuture<'static, std::io::Result<bool>>;
+}
+
+/// The agents rosters can name in a `native` entry.
+const NATIVE_AGENTS: &[(&str, &dyn NativeAgent)] = &[("echo", &Echo)];
+
+fn agent(name: &str) -> Option<&'static dyn NativeAgent> {
+    NATIVE_AGENTS.iter().find(|(n, _)| *n == name).map(|(_, agent)| *agent)
+}
+
+pub struct Native {
+    name: String,
+}
+
+impl Native {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string() }
+    }
+}
+
+impl AgentRuntime for Native {
+    fn describe(&self) -> String {
+        format!("native agent {}", self.name)
+    }
+
+    fn start<'a>(&'a self, job: &'a Job<'a>) -> BoxFuture<'a, std::io::Result<Execution>> {
+        Box::pin(async move {
+            let Some(agent) = agent(&self.name) else {
+                let known: Vec<&str> = NATIVE_AGENTS.iter().map(|(name, _)| *name).collect();
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no native agent named {:?}; known: {}", self.name, known.join(", ")),
+                ));
+            };
+            let task = NativeTask {
+                task_id: job.task_id.to_string(),
+                title: job.title.to_string(),
+                agent_id: job.agent_id.to_string(),
+                env: job.env.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+            };
+            let name = self.name.clone();
+            Ok(Execution::in_process(move |out| async move {
+                let success = agent.run(task, out).await?;
+                Ok(Exit::new(success, None, format!("native agent {} {}", name, if success { "succeeded" } else { "failed" })))
+            }))
+        })
+    }
+}
+
+/// Reports the task back as done without doing anything, to try out a swarm end to end
+/// without an LLM.
+struct Echo;
+
+impl NativeAgent for Echo {
+    fn run(&self, task: NativeTask, mut out: DuplexStream) -> BoxFuture<'static, std::io::Result<bool>> {
+        Box::pin(async move {
+            let result = json!({"status": "success", "summary": format!("Echoed: {}", task.title), "cost_usd": 0.0});
+            let vars: Vec<&str> = task.env.iter().map(|(k, _)| k.as_str()).collect();
+            let line = format!("echo: {} ({}) on {} with {}\n", task.title, task.task_id, task.agent_id, vars.join(", "));
+            out.write_all(format!("{}{}{}\n", line, RESULT_PREFIX, result).as_bytes()).await?;
+            Ok(true)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workers::agency::result::{ResultStatus, RunResult};
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn native_agents_report_through_their_output() {
+        let job = Job { task_id: "http://swarm.os/task/1", title: "Add SSO", agent_id: "http://swarm.os/agent/Smoke", env: &[], shared: &[] };
+        let mut execution = Native::new("echo").start(&job).await.unwrap();
+        let mut output = String::new();
+        execution.stdout.take().unwrap().read_to_string(&mut output).await.unwrap();
+        let exit = execution.exit.await.unwrap();
+
+        assert!(exit.success);
+        assert_eq!(exit.code, None);
+        let result = RunResult::last_in(&output).unwrap().unwrap();
+        assert_eq!(result.status, ResultStatus::Success);
+        assert_eq!(result.summary.as_deref(), Some("Echoed: Add SSO"));
+
+        let unknown = Native::new("nope").start(&job).await.err().unwrap();
+        assert!(unknown.to_string().contains("known: echo"));
+    }
+}
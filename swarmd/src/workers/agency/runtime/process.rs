@@ -0,0 +1,57 @@
+//! Host runs: the agent's command as a child process of swarmd, in a process group of its own
+//! so stopping it also takes down whatever it spawned.
+
+use std::process::Stdio;
+
+use futures_util::future::BoxFuture;
+use tokio::process::Command;
+
+use super::{AgentRuntime, Execution, Exit, Job};
+
+pub struct Process;
+
+impl AgentRuntime for Process {
+    fn describe(&self) -> String {
+        "host process".to_string()
+    }
+
+    fn start<'a>(&'a self, job: &'a Job<'a>) -> BoxFuture<'a, std::io::Result<Execution>> {
+        Box::pin(async move {
+            let argv = job.argv();
+            let mut command = Command::new(&argv[0]);
+            command.args(&argv[1..]).envs(job.env.iter().map(|(k, v)| (*k, v.as_str())));
+            spawn(command).map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", argv[0], e)))
+        })
+    }
+}
+
+/// Spawns `command` with piped output. The child is killed when its exit is dropped.
+pub(super) fn spawn(mut command: Command) -> std::io::Result<Execution> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+    // Own process group, so a timeout also takes down whatever the orchestrator spawned.
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command.spawn()?;
+    Ok(Execution {
+        // The child leads its process group, so the group id is its pid.
+        pid: child.id(),
+        stdout: child.stdout.take().map(|out| Box::new(out) as _),
+        stderr: child.stderr.take().map(|err| Box::new(err) as _),
+        exit: Box::pin(async move { child.wait().await.map(Exit::from) }),
+        task: None,
+    })
+}
+
+#[cfg(unix)]
+pub(super) fn kill_process_group(pid: u32) {
+    // SAFETY: plain syscall; a negative pid addresses the whole process group.
+    let rc = unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
+    if rc != 0 {
+        tracing::warn!("Failed to kill process group {}: {}", pid, std::io::Error::last_os_error());
+    }
+}
+
+#[cfg(not(unix))]
+pub(super) fn kill_process_group(_pid: u32) {
+    // The child itself is killed when its handle is dropped (`kill_on_drop`).
+}
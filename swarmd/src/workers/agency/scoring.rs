@@ -0,0 +1,257 @@
+//! Custom matchmaking (`SCHEDULER_SCRIPT`). A Rhai script defining `score(task, agent)`
+//! ranks every pair of runnable task and free agent, and the agency launches the best pair.
+//! `task` has `id`, `title`, `priority` (aged by waiting, see [`super::queue`]), `repo`,
+//! `requires_class` and `requires_skills`.
+//! `agent` has `id`, `name`, `class`, `repo` and `skills`. Repositories are ids, or `""` when
+//! unknown. A number is a score, where higher wins. `()` or a negative number means the agent
+//! must not take the task. Pairs whose agent lacks the class or skills the task requires are
+//! never offered to the script.
+//!
+//! Scripts are sandboxed: they cannot reach files or the network, their operations, call
+//! depth and data sizes are bounded, and every call starts from an empty scope. A script that
+//! fails to compile or to run is reported, and the built-in policy (most urgent task, first
+//! free agent) is used instead.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::Context;
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use tracing::debug;
+
+use crate::capabilities::Requirements;
+
+/// Operations one `score` call may take before it is aborted.
+const MAX_OPERATIONS: u64 = 20_000;
+/// Most urgent tasks and first free agents offered to the script, bounding the calls per tick.
+pub const MAX_CANDIDATES: usize = 25;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskCandidate {
+    pub id: String,
+    pub title: String,
+    pub priority: i64,
+    pub repo: String,
+    pub requirements: Requirements,
+}
+
+impl TaskCandidate {
+    /// Whether `agent` has the class and skills the task requires.
+    pub fn admits(&self, agent: &AgentCandidate) -> bool {
+        self.requirements.met_by(&agent.class, &agent.skills)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentCandidate {
+    pub id: String,
+    pub name: String,
+    pub class: String,
+    pub repo: String,
+    pub skills: Vec<String>,
+}
+
+pub struct ScoringScript {
+    engine: Engine,
+    ast: AST,
+    /// Last runtime error reported, so a broken script alerts once rather than every tick.
+    reported: Mutex<Option<String>>,
+}
+
+impl ScoringScript {
+    pub fn compile(source: &str) -> anyhow::Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(16);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(4_096);
+        engine.set_max_array_size(1_000);
+        engine.set_max_map_size(1_000);
+        engine.disable_symbol("eval");
+        engine.on_print(|text| debug!("scheduler script: {}", text));
+        engine.on_debug(|text, _, pos| debug!("scheduler script at {}: {}", pos, text));
+
+        let ast = engine.compile(source).map_err(|e| anyhow::anyhow!("{}", e))?;
+        if !ast.iter_functions().any(|f| f.name == "score" && f.params.len() == 2) {
+            anyhow::bail!("the script must define `fn score(task, agent)`");
+        }
+        Ok(Self { engine, ast, reported: Mutex::new(None) })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::compile(&source).with_context(|| format!("invalid scheduler script {}", path.display()))
+    }
+
+    /// `None` when the script rules the pair out.
+    pub fn score(&self, task: &TaskCandidate, agent: &AgentCandidate) -> Result<Option<f64>, String> {
+        let options = CallFnOptions::new().eval_ast(false);
+        let result: Dynamic = self
+            .engine
+            .call_fn_with_options(options, &mut Scope::new(), &self.ast, "score", (task_map(task), agent_map(agent)))
+            .map_err(|e| format!("score({}, {}): {}", task.id, agent.id, e))?;
+        let score = if result.is_unit() {
+            None
+        } else if let Ok(n) = result.as_int() {
+            Some(n as f64)
+        } else if let Ok(n) = result.as_float() {
+            Some(n)
+        } else {
+            return Err(format!("score({}, {}) returned a {}, expected a number or ()", task.id, agent.id, result.type_name()));
+        };
+        Ok(score.filter(|s| *s >= 0.0))
+    }
+
+    /// Indexes of the best-scoring pair. Ties go to the more urgent task, then the agent
+    /// listed first.
+    pub fn best(&self, tasks: &[TaskCandidate], agents: &[AgentCandidate]) -> Result<Option<(usize, usize)>, String> {
+        let mut best: Option<(f64, usize, usize)> = None;
+        for (t, task) in tasks.iter().enumerate().take(MAX_CANDIDATES) {
+            for (a, agent) in agents.iter().enumerate().take(MAX_CANDIDATES) {
+                if !task.admits(agent) {
+                    continue;
+                }
+                if let Some(score) = self.score(task, agent)? {
+                    if best.is_none_or(|(top, _, _)| score > top) {
+                        best = Some((score, t, a));
+                    }
+                }
+            }
+        }
+        Ok(best.map(|(_, t, a)| (t, a)))
+    }
+
+    /// Whether `error` differs from the last one reported.
+    pub fn first_report(&self, error: &str) -> bool {
+        let mut reported = self.reported.lock().unwrap_or_else(|e| e.into_inner());
+        if reported.as_deref() == Some(error) {
+            return false;
+        }
+        *reported = Some(error.to_string());
+        true
+    }
+}
+
+fn task_map(task: &TaskCandidate) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), task.id.clone().into());
+    map.insert("title".into(), task.title.clone().into());
+    map.insert("priority".into(), task.priority.into());
+    map.insert("repo".into(), task.repo.clone().into());
+    map.insert("requires_class".into(), task.requirements.classes.clone().into());
+    map.insert("requires_skills".into(), task.requirements.skills.clone().into());
+    map
+}
+
+fn agent_map(agent: &AgentCandidate) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), agent.id.clone().into());
+    map.insert("name".into(), agent.name.clone().into());
+    map.insert("class".into(), agent.class.clone().into());
+    map.insert("repo".into(), agent.repo.clone().into());
+    map.insert("skills".into(), agent.skills.clone().into());
+    map
+}
+
+/// What changed when the script file was looked at again.
+pub enum ScriptChange {
+    Loaded(String),
+    Failed(String),
+    Removed,
+}
+
+/// The script at `SCHEDULER_SCRIPT`, compiled again whenever the file or the setting changes.
+#[derive(Clone, Default)]
+pub struct ScriptSlot {
+    path: Option<String>,
+    modified: Option<SystemTime>,
+    script: Option<Arc<ScoringScript>>,
+}
+
+impl ScriptSlot {
+    pub fn script(&self) -> Option<Arc<ScoringScript>> {
+        self.script.clone()
+    }
+
+    pub fn refresh(&mut self, path: Option<&str>) -> Option<ScriptChange> {
+        let Some(path) = path else {
+            self.path = None;
+            self.modified = None;
+            return self.script.take().map(|_| ScriptChange::Removed);
+        };
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if self.path.as_deref() == Some(path) && self.modified == modified {
+            return None;
+        }
+        self.path = Some(path.to_string());
+        self.modified = modified;
+        match ScoringScript::load(Path::new(path)) {
+            Ok(script) => {
+                self.script = Some(Arc::new(script));
+                Some(ScriptChange::Loaded(path.to_string()))
+            }
+            Err(e) => {
+                self.script = None;
+                Some(ScriptChange::Failed(format!("{:#}", e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, priority: i64, repo: &str) -> TaskCandidate {
+        TaskCandidate { id: id.into(), title: id.into(), priority, repo: repo.into(), ..Default::default() }
+    }
+
+    fn agent(id: &str, class: &str, repo: &str) -> AgentCandidate {
+        AgentCandidate { id: id.into(), name: id.into(), class: class.into(), repo: repo.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn the_best_scoring_pair_wins() {
+        let script = ScoringScript::compile(
+            r#"
+            fn score(task, agent) {
+                if task.repo != "" && task.repo != agent.repo { return (); }
+                let s = task.priority * 10;
+                if agent.class == "Reviewer" && task.title.contains("review") { s += 25; }
+                s
+            }
+            "#,
+        )
+        .unwrap();
+        let tasks = [task("fix-api", 2, "api"), task("review-web", 1, "web"), task("docs", 0, "")];
+        let agents = [agent("Coder_api", "Coder", "api"), agent("Reviewer_web", "Reviewer", "web")];
+
+        assert_eq!(script.best(&tasks, &agents), Ok(Some((1, 1))));
+        assert_eq!(script.score(&tasks[0], &agents[1]), Ok(None));
+        assert_eq!(script.best(&tasks[..1], &agents[1..]), Ok(None));
+
+        // The Reviewer would score higher, but the task needs a Coder that knows rust.
+        let mut fix = task("fix-api", 2, "");
+        fix.requirements = Requirements { classes: vec!["Coder".into()], skills: vec!["rust".into()] };
+        let mut rustacean = agent("Coder_web", "Coder", "web");
+        rustacean.skills = vec!["Rust".into()];
+        let any = ScoringScript::compile(r#"fn score(task, agent) { if agent.class == "Reviewer" { 9 } else { task.requires_skills.len() } }"#).unwrap();
+        assert_eq!(any.best(std::slice::from_ref(&fix), &[agents[1].clone(), agents[0].clone(), rustacean]), Ok(Some((0, 2))));
+    }
+
+    #[test]
+    fn broken_scripts_are_contained() {
+        assert!(ScoringScript::compile("fn rank(task) { 1 }").is_err());
+        assert!(ScoringScript::compile("fn score(task, agent) { ").is_err());
+
+        let runaway = ScoringScript::compile("fn score(task, agent) { loop { } }").unwrap();
+        let err = runaway.best(&[task("t", 1, "")], &[agent("a", "Coder", "")]).unwrap_err();
+        assert!(err.starts_with("score(t, a)"), "{}", err);
+        assert!(runaway.first_report(&err));
+        assert!(!runaway.first_report(&err));
+
+        let wrong_type = ScoringScript::compile(r#"fn score(task, agent) { "high" }"#).unwrap();
+        assert!(wrong_type.score(&task("t", 1, ""), &agent("a", "Coder", "")).unwrap_err().contains("expected a number"));
+    }
+}
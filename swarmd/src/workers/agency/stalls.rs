@@ -0,0 +1,153 @@
+//! Stuck-run detection. A running orchestrator shows it is alive by printing to stdout or by
+//! writing `swarm:heartbeatAt` on its task. The `stall-watch` worker checks the runs every
+//! half minute; one silent for `AGENCY_STALL_MINUTES` is stopped and its task marked
+//! `STALLED`, which frees the agent and counts as a failed attempt.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::sync::{watch, Notify};
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::health::Health;
+use crate::store::Synapse;
+use crate::tasks::literal_value;
+
+/// xsd:dateTime an orchestrator may write on its task while it works.
+pub const HEARTBEAT_AT: &str = "http://swarm.os/ontology/heartbeatAt";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+struct Watched {
+    last_seen: DateTime<Utc>,
+    stall: Arc<Notify>,
+}
+
+/// The runs in progress and when each last showed signs of life.
+#[derive(Default)]
+pub struct Runs {
+    runs: Mutex<HashMap<String, Watched>>,
+}
+
+/// The runs of this process.
+pub fn shared() -> &'static Runs {
+    static RUNS: OnceLock<Runs> = OnceLock::new();
+    RUNS.get_or_init(Runs::default)
+}
+
+impl Runs {
+    /// Starts watching the run of `task`; the returned signal fires if it stalls.
+    pub fn watch(&self, task: &str) -> Arc<Notify> {
+        let stall = Arc::new(Notify::new());
+        self.lock().insert(task.to_string(), Watched { last_seen: Utc::now(), stall: stall.clone() });
+        stall
+    }
+
+    pub fn beat(&self, task: &str, at: DateTime<Utc>) {
+        if let Some(run) = self.lock().get_mut(task) {
+            run.last_seen = run.last_seen.max(at);
+        }
+    }
+
+    pub fn forget(&self, task: &str) {
+        self.lock().remove(task);
+    }
+
+    /// Signals and stops watching every run silent since before `cutoff`.
+    fn stop_silent(&self, cutoff: DateTime<Utc>) -> Vec<String> {
+        let mut runs = self.lock();
+        let silent: Vec<String> = runs.iter().filter(|(_, run)| run.last_seen < cutoff).map(|(task, _)| task.clone()).collect();
+        for task in &silent {
+            if let Some(run) = runs.remove(task) {
+                run.stall.notify_one();
+            }
+        }
+        silent
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Watched>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The `stall-watch` worker. `AGENCY_STALL_MINUTES` is re-read on every check; 0 turns
+/// detection off.
+pub async fn watch_stalls(synapse: Synapse, config: watch::Receiver<AppConfig>, health: Arc<Health>) {
+    info!("🫀 Watching orchestrator runs for missing heartbeats");
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        health.beat("stall-watch");
+        let minutes = config.borrow().agency_stall_minutes;
+        if minutes == 0 {
+            continue;
+        }
+
+        match synapse.query(&format!("SELECT ?task ?at WHERE {{ ?task <{HEARTBEAT_AT}> ?at }}")).await {
+            Ok(res_json) => {
+                let rows = serde_json::from_str::<Vec<Value>>(&res_json).unwrap_or_default();
+                for (task, at) in heartbeats(&rows) {
+                    shared().beat(&task, at);
+                }
+            }
+            Err(e) => warn!("Could not read orchestrator heartbeats; judging runs by their output only: {}", e),
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::minutes(minutes as i64);
+        for task in shared().stop_silent(cutoff) {
+            warn!("🫀 Task {} sent no heartbeat for {} minutes; stopping its run", task, minutes);
+        }
+    }
+}
+
+/// Latest heartbeat per task.
+fn heartbeats(rows: &[Value]) -> HashMap<String, DateTime<Utc>> {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+    let mut latest: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for row in rows {
+        let Some(task) = field(row, "task") else { continue };
+        let Some(at) = field(row, "at").and_then(|at| DateTime::parse_from_rfc3339(&at).ok()) else { continue };
+        let at = at.with_timezone(&Utc);
+        latest.entry(task).and_modify(|t| *t = (*t).max(at)).or_insert(at);
+    }
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn silent_runs_are_stopped_and_heartbeats_keep_them_alive() {
+        let runs = Runs::default();
+        let stalled = runs.watch("task/quiet");
+        runs.watch("task/chatty");
+        runs.watch("task/done");
+        runs.forget("task/done");
+
+        let rows = vec![
+            json!({"task": "<task/chatty>", "at": "\"2099-01-01T00:00:00Z\"^^<http://www.w3.org/2001/XMLSchema#dateTime>"}),
+            json!({"?task": "<task/chatty>", "?at": "\"2020-01-01T00:00:00Z\""}),
+            json!({"task": "<task/other>", "at": "\"not a date\""}),
+        ];
+        let beats = heartbeats(&rows);
+        assert_eq!(beats.len(), 1);
+        for (task, at) in beats {
+            runs.beat(&task, at);
+        }
+
+        let cutoff = Utc::now() + chrono::Duration::minutes(1);
+        assert_eq!(runs.stop_silent(cutoff), vec!["task/quiet".to_string()]);
+        // The permit is stored, so the run sees it even if it starts waiting later.
+        tokio::time::timeout(Duration::from_secs(1), stalled.notified()).await.unwrap();
+        assert!(runs.stop_silent(cutoff).is_empty());
+    }
+}
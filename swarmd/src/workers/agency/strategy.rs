@@ -0,0 +1,247 @@
+//! Built-in assignment strategies: which free agent takes the most urgent task when no
+//! scheduler script decides. `ASSIGNMENT_STRATEGY` sets the default and
+//! `ASSIGNMENT_STRATEGIES` (`repo=strategy` pairs) overrides it for the tasks of a repository.
+//!
+//! - `first-available`: the first free agent Synapse lists.
+//! - `round-robin`: the free agent that comes next, by id, after the one last given a task
+//!   of the same repository.
+//! - `weighted`: a random free agent, weighted by its share of successful runs.
+//! - `least-recently-used`: the free agent idle the longest; ones that have not run anything
+//!   since swarmd started go first.
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use rand::Rng;
+use serde_json::Value;
+
+use super::scoring::AgentCandidate;
+use crate::store::Synapse;
+use crate::tasks::literal_value;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    #[default]
+    FirstAvailable,
+    RoundRobin,
+    Weighted,
+    LeastRecentlyUsed,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, String> {
+        match raw.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "first-available" => Ok(Strategy::FirstAvailable),
+            "round-robin" => Ok(Strategy::RoundRobin),
+            "weighted" => Ok(Strategy::Weighted),
+            "least-recently-used" | "lru" => Ok(Strategy::LeastRecentlyUsed),
+            other => Err(format!(
+                "unknown assignment strategy {:?}; use first-available, round-robin, weighted or least-recently-used",
+                other
+            )),
+        }
+    }
+}
+
+/// The default strategy and the per-repository overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Strategies {
+    default: Strategy,
+    by_repo: BTreeMap<String, Strategy>,
+}
+
+impl Strategies {
+    /// `overrides` is a comma-separated list of `repo=strategy` pairs.
+    pub fn parse(default: Option<&str>, overrides: Option<&str>) -> Result<Self, String> {
+        let default = default.filter(|d| !d.trim().is_empty()).map(str::parse).transpose()?.unwrap_or_default();
+        let mut by_repo = BTreeMap::new();
+        for pair in overrides.unwrap_or_default().split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (repo, strategy) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected repo=strategy, got {:?}", pair))?;
+            by_repo.insert(repo.trim().to_string(), strategy.parse()?);
+        }
+        Ok(Self { default, by_repo })
+    }
+
+    /// The strategy for a task of `repo` (a repository id, `""` when unknown).
+    pub fn for_repo(&self, repo: &str) -> Strategy {
+        self.by_repo.get(repo).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Default)]
+struct History {
+    /// Agent last given a task, per repository.
+    last_by_repo: HashMap<String, String>,
+    last_assigned: HashMap<String, Instant>,
+}
+
+/// Past assignments, for the strategies that depend on them. Kept in memory, so round-robin
+/// and least-recently-used start afresh when swarmd restarts.
+#[derive(Debug, Clone, Default)]
+pub struct Assignments {
+    history: Arc<Mutex<History>>,
+}
+
+/// Runs and successful runs per agent IRI.
+pub type SuccessCounts = HashMap<String, (u32, u32)>;
+
+impl Assignments {
+    pub fn record(&self, repo: &str, agent: &str) {
+        let mut history = self.lock();
+        history.last_by_repo.insert(repo.to_string(), agent.to_string());
+        history.last_assigned.insert(agent.to_string(), Instant::now());
+    }
+
+    /// Index into `agents` of the one `strategy` picks for a task of `repo`. `agents` must
+    /// not be empty; `successes` is only read by `weighted`.
+    pub fn pick(&self, strategy: Strategy, repo: &str, agents: &[AgentCandidate], successes: &SuccessCounts) -> usize {
+        match strategy {
+            Strategy::FirstAvailable => 0,
+            Strategy::RoundRobin => {
+                let history = self.lock();
+                let last = history.last_by_repo.get(repo).map(String::as_str);
+                next_in_rotation(agents, last)
+            }
+            Strategy::LeastRecentlyUsed => {
+                let history = self.lock();
+                // Never used sorts first; ties keep Synapse's order.
+                (0..agents.len()).min_by_key(|i| history.last_assigned.get(&agents[*i].id)).unwrap_or(0)
+            }
+            Strategy::Weighted => weighted_pick(agents, successes, rand::thread_rng().gen::<f64>()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, History> {
+        self.history.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The agent whose id comes after `last`, wrapping around; the lowest id without a `last`.
+fn next_in_rotation(agents: &[AgentCandidate], last: Option<&str>) -> usize {
+    let mut order: Vec<usize> = (0..agents.len()).collect();
+    order.sort_by(|a, b| agents[*a].id.cmp(&agents[*b].id));
+    last.and_then(|last| order.iter().copied().find(|i| agents[*i].id.as_str() > last))
+        .or_else(|| order.first().copied())
+        .unwrap_or(0)
+}
+
+/// Laplace-smoothed success rate, so agents without runs get a fair chance.
+fn weight(successes: &SuccessCounts, agent: &str) -> f64 {
+    let (runs, ok) = successes.get(agent).copied().unwrap_or((0, 0));
+    (ok as f64 + 1.0) / (runs as f64 + 2.0)
+}
+
+/// The agent `roll` (in `[0, 1)`) lands on when each one gets a slice as wide as its weight.
+fn weighted_pick(agents: &[AgentCandidate], successes: &SuccessCounts, roll: f64) -> usize {
+    let weights: Vec<f64> = agents.iter().map(|a| weight(successes, &a.id)).collect();
+    let mut target = roll * weights.iter().sum::<f64>();
+    for (i, w) in weights.iter().enumerate() {
+        if target < *w {
+            return i;
+        }
+        target -= w;
+    }
+    agents.len().saturating_sub(1)
+}
+
+/// Runs per agent from Synapse; a run with a failure report failed.
+pub async fn success_counts(synapse: &Synapse) -> anyhow::Result<SuccessCounts> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?run ?agent ?report WHERE {
+            ?run a swarm:Run ; swarm:agent ?agent .
+            OPTIONAL { ?run swarm:failureReport ?report }
+        }
+    "#;
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(query).await?).unwrap_or_default();
+    Ok(count_successes(&rows))
+}
+
+fn count_successes(rows: &[Value]) -> SuccessCounts {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+    // Agent and outcome per run; the OPTIONAL repeats a run once per report.
+    let mut runs: HashMap<String, (String, bool)> = HashMap::new();
+    for row in rows {
+        let (Some(run), Some(agent)) = (field(row, "run"), field(row, "agent")) else { continue };
+        let entry = runs.entry(run).or_insert((agent, false));
+        entry.1 |= field(row, "report").is_some();
+    }
+    let mut counts = SuccessCounts::new();
+    for (agent, failed) in runs.into_values() {
+        let entry = counts.entry(agent).or_default();
+        entry.0 += 1;
+        entry.1 += u32::from(!failed);
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn agents(ids: &[&str]) -> Vec<AgentCandidate> {
+        ids.iter().map(|id| AgentCandidate { id: id.to_string(), ..Default::default() }).collect()
+    }
+
+    #[test]
+    fn strategies_parse_per_repository() {
+        let strategies = Strategies::parse(Some("least_recently_used"), Some("api=round-robin, web = weighted")).unwrap();
+        assert_eq!(strategies.for_repo("api"), Strategy::RoundRobin);
+        assert_eq!(strategies.for_repo("web"), Strategy::Weighted);
+        assert_eq!(strategies.for_repo(""), Strategy::LeastRecentlyUsed);
+        assert_eq!(Strategies::parse(None, None).unwrap().for_repo("api"), Strategy::FirstAvailable);
+        assert!(Strategies::parse(Some("fastest"), None).is_err());
+        assert!(Strategies::parse(None, Some("api")).is_err());
+    }
+
+    #[test]
+    fn strategies_spread_tasks_across_agents() {
+        let log = Assignments::default();
+        let free = agents(&["agent/c", "agent/a", "agent/b"]);
+        let none = SuccessCounts::new();
+        let rotation: Vec<&str> = (0..4)
+            .map(|_| {
+                let agent = &free[log.pick(Strategy::RoundRobin, "api", &free, &none)].id;
+                log.record("api", agent);
+                agent.as_str()
+            })
+            .collect();
+        assert_eq!(rotation, ["agent/a", "agent/b", "agent/c", "agent/a"]);
+        // Another repository keeps its own rotation.
+        assert_eq!(log.pick(Strategy::RoundRobin, "web", &free, &none), 1);
+
+        let log = Assignments::default();
+        log.record("api", "agent/c");
+        log.record("api", "agent/a");
+        assert_eq!(log.pick(Strategy::LeastRecentlyUsed, "api", &free, &none), 2);
+        log.record("api", "agent/b");
+        assert_eq!(free[log.pick(Strategy::LeastRecentlyUsed, "api", &free, &none)].id, "agent/c");
+        assert_eq!(log.pick(Strategy::FirstAvailable, "api", &free, &none), 0);
+
+        let rows = vec![
+            json!({"run": "<http://swarm.os/run/1>", "agent": "<agent/a>"}),
+            json!({"run": "<http://swarm.os/run/2>", "agent": "<agent/a>"}),
+            json!({"run": "<http://swarm.os/run/3>", "agent": "<agent/b>", "report": "\"boom\""}),
+            json!({"run": "<http://swarm.os/run/4>", "agent": "<agent/b>", "report": "\"again\""}),
+        ];
+        let successes = count_successes(&rows);
+        assert_eq!(successes["agent/a"], (2, 2));
+        assert_eq!(successes["agent/b"], (2, 0));
+        // Weights: c 1/2, a 3/4, b 1/4 of a total 3/2.
+        assert_eq!(weighted_pick(&free, &successes, 0.0), 0);
+        assert_eq!(weighted_pick(&free, &successes, 0.5), 1);
+        assert_eq!(weighted_pick(&free, &successes, 0.9), 2);
+    }
+}
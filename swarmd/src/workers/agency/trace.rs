@@ -0,0 +1,147 @@
+//! Live output of orchestrator runs. Every line a run prints, on stdout or stderr, goes out as
+//! a `Debug` trace notification tagged with its task, so operators can follow the run in chat or on
+//! the notification stream while it works. Lines are batched per run, one notification every
+//! few seconds at most, so a chatty orchestrator stays within chat rate limits.
+//! `AGENCY_STREAM_OUTPUT=false` turns it off.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+
+use super::result::RESULT_PREFIX;
+use crate::notifications::{Message, Notification, Severity};
+use crate::provenance::Source;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(3);
+/// Lines per notification; older ones are summarized as skipped.
+const MAX_BATCH_LINES: usize = 20;
+const MAX_LINE_CHARS: usize = 300;
+/// Lines waiting to be batched before the readers start dropping them.
+const BUFFERED_LINES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Where the output readers of one run hand their lines. The trace ends when every clone is
+/// dropped, after a last notification with whatever was left.
+#[derive(Debug, Clone)]
+pub struct Lines(mpsc::Sender<(Stream, String)>);
+
+impl Lines {
+    /// Never waits: a run must not slow down because notifications do.
+    pub fn push(&self, stream: Stream, line: &str) {
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.trim_start().starts_with(RESULT_PREFIX) {
+            return;
+        }
+        let line = match line.char_indices().nth(MAX_LINE_CHARS) {
+            Some((cut, _)) => format!("{}…", &line[..cut]),
+            None => line.to_string(),
+        };
+        let _ = self.0.try_send((stream, line));
+    }
+}
+
+/// Starts forwarding the output of the run of `task_id` to `tx`.
+pub fn start(tx: mpsc::Sender<Notification>, task_id: &str, title: &str) -> Lines {
+    let (lines, rx) = mpsc::channel(BUFFERED_LINES);
+    tokio::spawn(forward(rx, tx, task_id.to_string(), title.to_string()));
+    Lines(lines)
+}
+
+async fn forward(mut rx: mpsc::Receiver<(Stream, String)>, tx: mpsc::Sender<Notification>, task_id: String, title: String) {
+    let mut batch = Batch::default();
+    let mut flush = tokio::time::interval_at(tokio::time::Instant::now() + FLUSH_INTERVAL, FLUSH_INTERVAL);
+    flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            line = rx.recv() => match line {
+                Some((stream, line)) => batch.push(stream, line),
+                None => break,
+            },
+            _ = flush.tick() => {
+                if let Some(message) = batch.take(&task_id, &title) {
+                    let _ = tx.send(output(&task_id, message)).await;
+                }
+            }
+        }
+    }
+    if let Some(message) = batch.take(&task_id, &title) {
+        let _ = tx.send(output(&task_id, message)).await;
+    }
+}
+
+fn output(task_id: &str, message: Message) -> Notification {
+    Notification::trace(message).with_severity(Severity::Debug).with_source(Source::Agency).with_task(task_id)
+}
+
+/// The latest lines since the last notification.
+#[derive(Debug, Default)]
+struct Batch {
+    lines: VecDeque<(Stream, String)>,
+    skipped: usize,
+}
+
+impl Batch {
+    fn push(&mut self, stream: Stream, line: String) {
+        if self.lines.len() == MAX_BATCH_LINES {
+            self.lines.pop_front();
+            self.skipped += 1;
+        }
+        self.lines.push_back((stream, line));
+    }
+
+    fn take(&mut self, task_id: &str, title: &str) -> Option<Message> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        let mut message = Message::new().strong(title).text(" ").code(task_id);
+        if self.skipped > 0 {
+            message = message.text(format!("\n… {} earlier lines skipped", self.skipped));
+        }
+        for (stream, line) in self.lines.drain(..) {
+            message = message.text(match stream {
+                Stream::Stdout => "\n",
+                Stream::Stderr => "\n[stderr] ",
+            });
+            message = message.text(line);
+        }
+        self.skipped = 0;
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::{ChannelRenderer, PlainText};
+
+    #[tokio::test]
+    async fn output_lines_reach_the_channel_tagged_with_their_task() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let lines = start(tx, "http://swarm.os/task/1", "Add SSO");
+        for i in 0..MAX_BATCH_LINES + 2 {
+            lines.push(Stream::Stdout, &format!("step {i}\n"));
+        }
+        lines.push(Stream::Stdout, "   ");
+        lines.push(Stream::Stdout, "SWARM_RESULT {\"status\": \"success\"}");
+        lines.push(Stream::Stderr, &"x".repeat(MAX_LINE_CHARS + 10));
+        drop(lines);
+
+        let Some(notification) = rx.recv().await else { panic!("expected a trace") };
+        assert_eq!((notification.kind(), notification.severity), ("trace", Severity::Debug));
+        assert_eq!(notification.task.as_deref(), Some("http://swarm.os/task/1"));
+        let text = PlainText.render(&notification);
+        assert!(text.contains("Add SSO"));
+        assert!(text.contains("http://swarm.os/task/1"));
+        assert!(text.contains("… 3 earlier lines skipped\nstep 3\n"));
+        assert!(!text.contains("SWARM_RESULT"));
+        assert!(text.ends_with(&format!("[stderr] {}…", "x".repeat(MAX_LINE_CHARS))));
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,183 @@
+//! A working copy per run. Agents on the same repository would otherwise share its checkout and
+//! trip over each other's branches and uncommitted files, so every run of a task on a
+//! repository gets a checkout of its own under `SWARM_ARTIFACTS_DIR/worktrees`, named by
+//! `SWARM_WORKDIR`: a detached `git worktree` of the checkout discovery found (`swarm:path`),
+//! or else a shallow clone of the repository's `swarm:remoteUrl`. The copy is removed when the
+//! run ends, so whatever the agent means to keep it pushes or opens as a pull request. Before
+//! that, the files it changed tell which directories of the repository it explored.
+//!
+//! Tasks on no repository, or on one with neither a checkout nor a remote, run without one.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::sparql::Query;
+use crate::store::Synapse;
+
+/// A run's own working copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workdir {
+    pub path: PathBuf,
+    /// IRI of the repository checked out.
+    pub repository: String,
+    /// The checkout it is a worktree of; `None` for a clone.
+    origin: Option<PathBuf>,
+    /// The commit the run started from.
+    base: Option<String>,
+}
+
+impl Workdir {
+    /// What a container needs mounted to use it: a worktree's git metadata stays in its origin.
+    pub fn mounts(&self) -> Vec<PathBuf> {
+        let mut mounts = vec![self.path.clone()];
+        mounts.extend(self.origin.as_ref().map(|origin| origin.join(".git")));
+        mounts
+    }
+
+    /// Files the run changed, committed or not, relative to the checkout root.
+    pub async fn changed_files(&self) -> anyhow::Result<Vec<String>> {
+        let base = self.base.as_deref().unwrap_or("HEAD");
+        let mut files = output(git_in(&self.path).args(["diff", "--name-only", base, "--"])).await?;
+        files.push_str(&output(git_in(&self.path).args(["ls-files", "--others", "--exclude-standard"])).await?);
+        Ok(files.lines().map(str::to_string).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct RepoRow {
+    repo: String,
+    path: Option<String>,
+    remote: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Worktrees {
+    dir: PathBuf,
+}
+
+impl Worktrees {
+    pub fn new(artifacts_dir: &str) -> Self {
+        Self { dir: super::run_dir(artifacts_dir, "worktrees") }
+    }
+
+    /// Checks out the repository of `task_id` for one run; `Ok(None)` when it has none to
+    /// check out.
+    pub async fn prepare(&self, synapse: &Synapse, task_id: &str) -> anyhow::Result<Option<Workdir>> {
+        let query = Query::new(
+            r#"
+            PREFIX swarm: <http://swarm.os/ontology/>
+            SELECT ?repo ?path ?remote WHERE {
+                $task swarm:repository ?repo .
+                OPTIONAL { ?repo swarm:path ?path }
+                OPTIONAL { ?repo swarm:remoteUrl ?remote }
+            } LIMIT 1
+        "#,
+        )
+        .iri("task", task_id);
+        let Some(repo) = synapse.select_as::<RepoRow>(&query).await?.into_iter().next() else { return Ok(None) };
+
+        let name: String = repo.repo.rsplit('/').next().unwrap_or_default().chars().filter(|c| c.is_ascii_alphanumeric() || "-_".contains(*c)).collect();
+        let path = self.dir.join(format!("{}-{}", name, uuid::Uuid::new_v4().simple()));
+        let checkout = repo.path.map(PathBuf::from).filter(|checkout| checkout.join(".git").is_dir());
+        let origin = match (checkout, repo.remote) {
+            (Some(checkout), _) => {
+                let origin = std::path::absolute(&checkout).unwrap_or(checkout);
+                tokio::fs::create_dir_all(&self.dir).await?;
+                run(git_in(&origin).args(["worktree", "add", "--detach"]).arg(&path)).await?;
+                Some(origin)
+            }
+            (None, Some(remote)) => {
+                tokio::fs::create_dir_all(&self.dir).await?;
+                run(Command::new("git").args(["clone", "--quiet", "--depth=1", "--", &remote]).arg(&path)).await?;
+                None
+            }
+            (None, None) => return Ok(None),
+        };
+        let base = output(git_in(&path).args(["rev-parse", "HEAD"])).await.ok().map(|head| head.trim().to_string());
+        Ok(Some(Workdir { path, repository: repo.repo, origin, base }))
+    }
+
+    /// Removes a run's working copy, and a worktree's entry in its origin.
+    pub async fn release(&self, workdir: &Workdir) {
+        if let Some(origin) = &workdir.origin {
+            match run(git_in(origin).args(["worktree", "remove", "--force"]).arg(&workdir.path)).await {
+                Ok(()) => return,
+                Err(e) => warn!("Could not remove the worktree {}; deleting it: {:#}", workdir.path.display(), e),
+            }
+        }
+        if let Err(e) = tokio::fs::remove_dir_all(&workdir.path).await {
+            warn!("Could not delete the working copy {}: {}", workdir.path.display(), e);
+        }
+        if let Some(origin) = &workdir.origin {
+            let _ = run(git_in(origin).args(["worktree", "prune"])).await;
+        }
+    }
+}
+
+/// `git` run on the repository at `dir`.
+fn git_in(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir);
+    command
+}
+
+async fn run(command: &mut Command) -> anyhow::Result<()> {
+    output(command).await.map(|_| ())
+}
+
+/// What `command` prints, once it succeeds.
+async fn output(command: &mut Command) -> anyhow::Result<String> {
+    let out = command.output().await.context("running git")?;
+    if !out.status.success() {
+        bail!("{:?} failed: {}", command.as_std(), String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[tokio::test]
+    async fn each_run_gets_a_worktree_of_the_checkout() {
+        let root = std::env::temp_dir().join(format!("swarm-worktree-{}", uuid::Uuid::new_v4()));
+        let checkout = root.join("api");
+        std::fs::create_dir_all(&checkout).unwrap();
+        run(git_in(&checkout).args(["init", "--quiet"])).await.unwrap();
+        std::fs::write(checkout.join("README.md"), "api").unwrap();
+        run(git_in(&checkout).args(["add", "."])).await.unwrap();
+        let commit = ["-c", "user.name=swarm", "-c", "user.email=swarm@localhost", "commit", "--quiet", "-m", "init"];
+        run(git_in(&checkout).args(commit)).await.unwrap();
+
+        let (store, synapse) = MemoryStore::shared();
+        let worktrees = Worktrees::new(root.join("artifacts").to_str().unwrap());
+        assert_eq!(worktrees.prepare(&synapse, "http://swarm.os/task/1").await.unwrap(), None);
+
+        let path = checkout.to_str().unwrap();
+        store.answer("swarm:repository", serde_json::json!([{"?repo": "<http://swarm.os/repository/api>", "?path": format!("\"{path}\"")}]));
+        let first = worktrees.prepare(&synapse, "http://swarm.os/task/1").await.unwrap().unwrap();
+        let second = worktrees.prepare(&synapse, "http://swarm.os/task/2").await.unwrap().unwrap();
+        assert_ne!(first.path, second.path);
+        assert!(first.path.file_name().unwrap().to_str().unwrap().starts_with("api-"));
+        assert_eq!(std::fs::read_to_string(first.path.join("README.md")).unwrap(), "api");
+        assert_eq!(first.mounts(), [first.path.clone(), checkout.join(".git")]);
+        assert_eq!(first.repository, "http://swarm.os/repository/api");
+
+        std::fs::create_dir_all(first.path.join("src/auth")).unwrap();
+        std::fs::write(first.path.join("src/auth/sso.rs"), "").unwrap();
+        std::fs::write(first.path.join("README.md"), "api, with SSO").unwrap();
+        let mut changed = first.changed_files().await.unwrap();
+        changed.sort();
+        assert_eq!(changed, ["README.md", "src/auth/sso.rs"]);
+
+        worktrees.release(&first).await;
+        worktrees.release(&second).await;
+        assert!(!first.path.exists() && !second.path.exists());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
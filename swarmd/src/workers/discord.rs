@@ -0,0 +1,226 @@
+//! Discord bot: posts notifications to one channel and answers `!` commands typed there.
+//! Commands are read by polling the channel over REST, so no gateway connection is needed;
+//! the bot needs the Message Content intent to see what was typed.
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+use crate::health::Health;
+use crate::notifications::{ChannelRenderer, DiscordMarkdown, Message, Notification, NotificationSink};
+use crate::provenance::Source;
+use crate::roles::Role;
+use crate::store::Synapse;
+use crate::tasks::active_tasks;
+
+use super::ops::{self, state_emoji};
+
+const API_BASE: &str = "https://discord.com/api/v10";
+const TASKS_SHOWN: usize = 10;
+
+/// A `!command` typed in the bot's channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Help,
+    Status { verbose: bool },
+    Tasks { state: Option<String> },
+    Halt,
+    Resume,
+    Unknown(String),
+}
+
+impl Action {
+    /// `None` for ordinary chat, which the bot ignores.
+    fn parse(content: &str) -> Option<Self> {
+        let mut words = content.trim().strip_prefix('!')?.split_whitespace();
+        let name = words.next()?.to_lowercase();
+        Some(match name.as_str() {
+            "help" => Action::Help,
+            "status" => Action::Status { verbose: words.any(|w| w.eq_ignore_ascii_case("verbose")) },
+            "tasks" => Action::Tasks { state: words.next().map(str::to_uppercase) },
+            "halt" | "stop_all" => Action::Halt,
+            "resume" => Action::Resume,
+            _ => Action::Unknown(name),
+        })
+    }
+}
+
+fn help() -> Message {
+    Message::new()
+        .text("🤖 ")
+        .strong("Commands")
+        .text("\n")
+        .code("!status [verbose]")
+        .text(" — System status; `verbose` adds spend and queue size\n")
+        .code("!tasks [STATE]")
+        .text(" — Active tasks, optionally filtered by state\n")
+        .code("!halt")
+        .text(" — Emergency halt of the swarm\n")
+        .code("!resume")
+        .text(" — Resume operational status after a halt")
+}
+
+fn auth_header(token: &str) -> String {
+    format!("Bot {}", token)
+}
+
+pub async fn poll_discord(token: String, channel_id: String, synapse: Synapse, client: Client, health: Arc<Health>) {
+    info!("🎮 Discord Poller Started (channel {})...", channel_id);
+    let messages_url = format!("{}/channels/{}/messages", API_BASE, channel_id);
+
+    // Start after the newest message, so commands sent while swarmd was down are not replayed.
+    let mut cursor: Option<u64> = None;
+
+    loop {
+        health.beat("discord");
+        sleep(Duration::from_secs(3)).await;
+
+        let Some(last_seen) = cursor else {
+            match fetch_messages(&messages_url, &token, &client, "limit=1").await {
+                Ok(messages) => cursor = Some(messages.first().and_then(message_id).unwrap_or(0)),
+                Err(e) => warn!("⚠️ Discord API error reading channel: {}", e),
+            }
+            continue;
+        };
+
+        let query = format!("after={}&limit=50", last_seen);
+        let mut messages = match fetch_messages(&messages_url, &token, &client, &query).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                warn!("⚠️ Discord API error during polling: {}", e);
+                continue;
+            }
+        };
+        // Discord returns newest first; snowflake ids sort chronologically.
+        messages.sort_by_key(|m| message_id(m).unwrap_or(0));
+
+        for message in &messages {
+            cursor = cursor.max(message_id(message));
+            let from_bot = message.pointer("/author/bot").and_then(Value::as_bool).unwrap_or(false);
+            let content = message.get("content").and_then(Value::as_str).unwrap_or("");
+            let Some(action) = Action::parse(content).filter(|_| !from_bot) else { continue };
+
+            let user = message.pointer("/author/username").and_then(Value::as_str).unwrap_or("unknown");
+            let reply = run_action(action, user, &synapse).await;
+            if let Err(e) = post_message(&messages_url, &token, &client, &reply).await {
+                warn!("⚠️ Could not answer Discord command: {}", e);
+            }
+        }
+    }
+}
+
+async fn run_action(action: Action, user: &str, synapse: &Synapse) -> Message {
+    match action {
+        Action::Help => help(),
+        // Anyone in the configured channel may halt the swarm, so they also see spend.
+        Action::Status { verbose } => ops::status_message(synapse, verbose, Role::Admin).await,
+        Action::Tasks { state } => match active_tasks(synapse).await {
+            Ok(mut tasks) => {
+                if let Some(state) = &state {
+                    tasks.retain(|t| t.state.eq_ignore_ascii_case(state));
+                }
+                if tasks.is_empty() {
+                    return Message::new().text("📭 ").strong("No active tasks");
+                }
+                let mut msg = Message::new().text("📋 ").strong(format!("Active tasks ({})", tasks.len()));
+                for task in tasks.iter().take(TASKS_SHOWN) {
+                    let agent = task.agent.as_deref().map(|a| a.rsplit('/').next().unwrap_or(a)).unwrap_or("unassigned");
+                    msg = msg
+                        .text(format!("\n{} ", state_emoji(&task.state)))
+                        .strong(&task.title)
+                        .text(format!(" · {} · {}", task.state, agent));
+                }
+                if tasks.len() > TASKS_SHOWN {
+                    msg = msg.text(format!("\n…and {} more", tasks.len() - TASKS_SHOWN));
+                }
+                msg
+            }
+            Err(e) => Message::new().text(format!("❌ Error querying Synapse: {}", e)),
+        },
+        Action::Halt => match ops::set_operational_status("HALTED", Source::Discord, &format!("discord:{}", user), synapse).await {
+            Ok(()) => {
+                info!("🛑 System halted via Discord by {}", user);
+                Message::new().text("🛑 ").strong("SYSTEM HALTED").text(format!(" by {}.", user))
+            }
+            Err(e) => Message::new().text(format!("❌ Failed to halt: {}", e)),
+        },
+        Action::Resume => match ops::set_operational_status("OPERATIONAL", Source::Discord, &format!("discord:{}", user), synapse).await {
+            Ok(()) => Message::new().text("✅ ").strong("SYSTEM RESUMED").text(" to Operational status."),
+            Err(e) => Message::new().text(format!("❌ Failed to resume: {}", e)),
+        },
+        Action::Unknown(name) => Message::new()
+            .text("❓ Unknown command ")
+            .code(format!("!{}", name))
+            .text(". Send ")
+            .code("!help")
+            .text(" for the list of commands."),
+    }
+}
+
+fn message_id(message: &Value) -> Option<u64> {
+    message.get("id")?.as_str()?.parse().ok()
+}
+
+async fn fetch_messages(url: &str, token: &str, client: &Client, query: &str) -> anyhow::Result<Vec<Value>> {
+    Ok(client
+        .get(format!("{}?{}", url, query))
+        .header("Authorization", auth_header(token))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+async fn post_message(url: &str, token: &str, client: &Client, msg: &Message) -> anyhow::Result<()> {
+    client
+        .post(url)
+        .header("Authorization", auth_header(token))
+        // Never let a task title ping @everyone or a role.
+        .json(&json!({ "content": DiscordMarkdown.render_message(msg), "allowed_mentions": { "parse": [] } }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+pub struct DiscordSink {
+    token: String,
+    messages_url: String,
+    client: Client,
+}
+
+impl DiscordSink {
+    pub fn new(token: String, channel_id: &str, client: Client) -> Self {
+        Self { token, messages_url: format!("{}/channels/{}/messages", API_BASE, channel_id), client }
+    }
+}
+
+impl NotificationSink for DiscordSink {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move { post_message(&self.messages_url, &self.token, &self.client, &notification.decorated()).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bang_commands_and_ignores_chat() {
+        assert_eq!(Action::parse("!Status verbose"), Some(Action::Status { verbose: true }));
+        assert_eq!(Action::parse("!tasks processing"), Some(Action::Tasks { state: Some("PROCESSING".into()) }));
+        assert_eq!(Action::parse(" !halt"), Some(Action::Halt));
+        assert_eq!(Action::parse("!deploy"), Some(Action::Unknown("deploy".into())));
+        assert_eq!(Action::parse("status please"), None);
+        assert_eq!(Action::parse("!"), None);
+    }
+}
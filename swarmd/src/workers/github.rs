@@ -0,0 +1,314 @@
+//! GitHub Issues as a task source: open issues labelled `swarm` in the configured
+//! repositories become `swarm:Task`s, the same way Trello cards do, and the issue gets a
+//! comment whenever its task reaches a new lifecycle state. Pull requests opened by agents are
+//! tracked in [`pulls`].
+
+mod api;
+pub mod pulls;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+use crate::approvals;
+use crate::capabilities::Requirements;
+use crate::config::AppConfig;
+use crate::health::Health;
+use crate::notifications::{Message, Notification};
+use crate::provenance::Source;
+use crate::sparql;
+use crate::state::{self, StateStore};
+use crate::store::Synapse;
+use crate::tasks::{self, literal_value, TaskSummary};
+use api::GitHubApi;
+
+pub const ISSUE_PREFIX: &str = "http://swarm.os/github/issue/";
+/// States already announced on the issue; append-only, so restarts do not repeat comments.
+pub const REPORTED_STATE: &str = "http://swarm.os/ontology/reportedState";
+pub const SWARM_LABEL: &str = "swarm";
+/// State store scope of the ingested issues.
+const SEEN_SCOPE: &str = "github";
+
+/// Issue `number` of `owner/repo`; its task subject is `{ISSUE_PREFIX}{owner}/{repo}/{number}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IssueRef {
+    pub repo: String,
+    pub number: u64,
+}
+
+impl IssueRef {
+    pub fn subject(&self) -> String {
+        format!("{}{}/{}", ISSUE_PREFIX, self.repo, self.number)
+    }
+
+    pub fn from_subject(subject: &str) -> Option<Self> {
+        let (repo, number) = subject.strip_prefix(ISSUE_PREFIX)?.rsplit_once('/')?;
+        Some(Self { repo: repo.to_string(), number: number.parse().ok()? })
+    }
+}
+
+/// The comment posted when a task reaches `state`; intake states stay silent.
+fn progress_comment(task: &TaskSummary) -> Option<String> {
+    let agent = task.agent.as_deref().map(|a| a.rsplit('/').next().unwrap_or(a)).unwrap_or("an agent");
+    let body = match task.state.as_str() {
+        "PROCESSING" => format!("⚙️ Picked up by **{}**.", agent),
+        "RETRY_SCHEDULED" => "🔁 The last attempt failed; a retry is scheduled.".to_string(),
+        "TIMED_OUT" => "⏱️ The run timed out.".to_string(),
+        "STALLED" => "🫀 The run stopped responding and was stopped.".to_string(),
+        "FAILED" => "⚠️ The run failed.".to_string(),
+        "DEAD_LETTER" => "🪦 Gave up after repeated failures; a human needs to take a look.".to_string(),
+        "ESCALATED" => "🙋 Handed to a human after repeated failures; agents will try again once it is reset.".to_string(),
+        "AWAITING_APPROVAL" => "🛡️ Waiting for a human to approve it before any agent starts.".to_string(),
+        "APPROVED" => "✅ Approved; an agent will pick it up.".to_string(),
+        "AWAITING_MERGE" => "🔀 Pull request opened; the task completes once it is merged.".to_string(),
+        "REJECTED" => "⛔ Rejected; the swarm will not work on this issue.".to_string(),
+        "CANCELLED" => "🚫 Cancelled before work started.".to_string(),
+        "DONE" => format!("✅ Completed by **{}**.", agent),
+        _ => return None,
+    };
+    Some(format!("{}\n\n<sub>swarmd task `{}`</sub>", body, task.id))
+}
+
+/// The `owner/repo`s of `GITHUB_REPOS`. Without any, the worker only tracks pull requests
+/// opened by agents.
+pub fn repos_from_config(cfg: &AppConfig) -> Vec<String> {
+    cfg.github_repos
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(|r| r.trim().to_string())
+        .filter(|r| r.contains('/'))
+        .collect()
+}
+
+pub async fn poll_github(
+    token: String,
+    synapse: Synapse,
+    client: Client,
+    health: Arc<Health>,
+    config: watch::Receiver<AppConfig>,
+    tx: mpsc::Sender<Notification>,
+) {
+    let repos = repos_from_config(&config.borrow());
+    info!("🐙 GitHub Poller Started (issues from: {})...", if repos.is_empty() { "none".to_string() } else { repos.join(", ") });
+    let api = GitHubApi::new(token, client);
+    let mut ingested = load_ingested(&synapse, state::shared()).await;
+    let mut last_ci = HashMap::new();
+
+    loop {
+        health.beat("github");
+        // Read every round, so reloads take effect on the next poll.
+        let (enabled, repos, interval, thresholds) = {
+            let cfg = config.borrow();
+            (
+                cfg.worker_enabled("github"),
+                repos_from_config(&cfg),
+                Duration::from_secs(cfg.github_poll_secs.max(1)),
+                crate::experience::level_thresholds(cfg.level_thresholds.as_deref()),
+            )
+        };
+        if !enabled {
+            tokio::time::sleep(interval).await;
+            continue;
+        }
+
+        for repo in &repos {
+            match api.labelled_issues(repo, SWARM_LABEL).await {
+                Ok(issues) => {
+                    for issue in issues {
+                        ingest_issue(repo, &issue, &synapse, &mut ingested, &tx).await;
+                    }
+                }
+                Err(e) => warn!("⚠️ GitHub API error listing issues of {}: {}", repo, e),
+            }
+        }
+
+        if let Err(e) = report_progress(&api, &synapse).await {
+            warn!("⚠️ Could not report task progress to GitHub: {}", e);
+        }
+
+        if let Err(e) = pulls::track(&api, &synapse, &mut last_ci, &thresholds, &tx).await {
+            warn!("⚠️ Could not check pull requests on GitHub: {}", e);
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn ingest_issue(
+    repo: &str,
+    issue: &Value,
+    synapse: &Synapse,
+    ingested: &mut HashSet<IssueRef>,
+    tx: &mpsc::Sender<Notification>,
+) {
+    // The issues endpoint also lists pull requests.
+    if issue.get("pull_request").is_some() {
+        return;
+    }
+    let Some(number) = issue.get("number").and_then(Value::as_u64) else { return };
+    let issue_ref = IssueRef { repo: repo.to_string(), number };
+    if ingested.contains(&issue_ref) {
+        return;
+    }
+
+    let title = issue.get("title").and_then(Value::as_str).unwrap_or("");
+    let body = issue.get("body").and_then(Value::as_str).map(str::trim).filter(|body| !body.is_empty());
+    let labels: Vec<&str> = issue
+        .get("labels")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|l| l.get("name").and_then(Value::as_str))
+        .collect();
+    let risk_label = labels.iter().find(|name| approvals::is_high_risk_label(name));
+    let priority_lit = tasks::priority_from_labels(labels.iter().copied()).map(tasks::integer_literal);
+    let requirements = Requirements::from_labels(labels.iter().copied()).triples();
+    info!("🔎 Found NEW issue {}#{} '{}'", repo, number, title);
+
+    let _ = tx
+        .send(Notification::trace(
+            Message::new().text("New issue in ").strong(repo).text(format!(": #{} {}", number, title)),
+        ).with_source(Source::GitHub))
+        .await;
+
+    let subject = issue_ref.subject();
+    let title_lit = sparql::literal(title);
+    let body_lit = body.map(sparql::literal);
+    let label_lits: Vec<String> = labels.iter().map(|label| sparql::literal(label)).collect();
+    let repo_iri = format!("http://swarm.os/repository/{}", repo);
+    let mut triples = vec![
+        (subject.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task"),
+        // The agency's intake state, as for initiative and mission tasks.
+        (subject.as_str(), tasks::INTERNAL_STATE, "\"REQUIREMENTS\""),
+        (subject.as_str(), "http://swarm.os/ontology/title", title_lit.as_str()),
+        (subject.as_str(), crate::initiatives::REPOSITORY, repo_iri.as_str()),
+    ];
+    if let Some(priority_lit) = &priority_lit {
+        triples.push((subject.as_str(), tasks::PRIORITY, priority_lit.as_str()));
+    }
+    if let Some(body_lit) = &body_lit {
+        triples.push((subject.as_str(), tasks::DESCRIPTION, body_lit.as_str()));
+    }
+    triples.extend(label_lits.iter().map(|label| (subject.as_str(), tasks::LABEL, label.as_str())));
+    triples.extend(requirements.iter().map(|(predicate, value)| (subject.as_str(), *predicate, value.as_str())));
+    let reason_lit = risk_label.map(|label| sparql::literal(&format!("labelled '{}' in {}", label, repo)));
+    if let Some(reason_lit) = &reason_lit {
+        triples.extend(approvals::flag_triples(&subject, reason_lit));
+    }
+
+    match synapse.ingest_from(Source::GitHub, triples).await {
+        Ok(()) => {
+            if let Err(e) = state::shared().mark_seen(SEEN_SCOPE, [subject.as_str()]) {
+                warn!("⚠️ Could not record issue {}#{} in the state store: {}", repo, number, e);
+            }
+            ingested.insert(issue_ref);
+        }
+        Err(e) => warn!("⚠️ Could not ingest {}#{}: {}", repo, number, e),
+    }
+}
+
+/// Issues already ingested, so restarts do not announce them and reset their tasks again.
+/// They live in the state store; on first use it is seeded with the issue tasks in Synapse.
+async fn load_ingested(synapse: &Synapse, store: &StateStore) -> HashSet<IssueRef> {
+    let subjects = match store.seen(SEEN_SCOPE) {
+        Ok(subjects) if !subjects.is_empty() => subjects,
+        stored => {
+            if let Err(e) = stored {
+                warn!("⚠️ Could not read ingested GitHub issues from the state store: {}", e);
+            }
+            let query = format!(
+                "SELECT ?task WHERE {{ ?task a <http://swarm.os/ontology/Task> . FILTER(STRSTARTS(STR(?task), \"{ISSUE_PREFIX}\")) }}"
+            );
+            let rows: Vec<Value> = match synapse.query(&query).await {
+                Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+                Err(e) => {
+                    warn!("⚠️ Could not read ingested GitHub issues from Synapse: {}", e);
+                    Vec::new()
+                }
+            };
+            let subjects: HashSet<String> = rows
+                .iter()
+                .filter_map(|row| row.get("task").or_else(|| row.get("?task")).and_then(Value::as_str))
+                .map(|task| task.trim_matches(|c| c == '<' || c == '>').to_string())
+                .collect();
+            if let Err(e) = store.mark_seen(SEEN_SCOPE, subjects.iter().map(String::as_str)) {
+                warn!("⚠️ Could not save ingested GitHub issues to the state store: {}", e);
+            }
+            subjects
+        }
+    };
+    let ingested: HashSet<IssueRef> = subjects.iter().filter_map(|subject| IssueRef::from_subject(subject)).collect();
+    if !ingested.is_empty() {
+        info!("🐙 {} GitHub issues already ingested", ingested.len());
+    }
+    ingested
+}
+
+/// Comments on every issue whose task reached a state not yet announced there.
+async fn report_progress(api: &GitHubApi, synapse: &Synapse) -> anyhow::Result<()> {
+    let query = format!("SELECT ?task ?state WHERE {{ ?task <{REPORTED_STATE}> ?state }}");
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(&query).await?).unwrap_or_default();
+    let mut reported: HashMap<String, HashSet<String>> = HashMap::new();
+    for row in &rows {
+        let field = |name: &str| row.get(name).or_else(|| row.get(format!("?{name}").as_str())).and_then(Value::as_str);
+        if let (Some(task), Some(state)) = (field("task"), field("state")) {
+            reported.entry(task.to_string()).or_default().insert(literal_value(state).to_string());
+        }
+    }
+
+    for task in tasks::all_tasks(synapse).await? {
+        let Some(issue) = IssueRef::from_subject(&task.id) else { continue };
+        if reported.get(&task.id).is_some_and(|states| states.contains(&task.state)) {
+            continue;
+        }
+        let Some(body) = progress_comment(&task) else { continue };
+
+        api.comment(&issue, &body).await?;
+        let state_lit = sparql::literal(&task.state);
+        synapse
+            .ingest_from(Source::GitHub, vec![(task.id.as_str(), REPORTED_STATE, state_lit.as_str())])
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issue_subjects_round_trip_and_drive_comments() {
+        let issue = IssueRef { repo: "pmaojo/agent-swarm-dev".into(), number: 42 };
+        assert_eq!(issue.subject(), "http://swarm.os/github/issue/pmaojo/agent-swarm-dev/42");
+        assert_eq!(IssueRef::from_subject(&issue.subject()), Some(issue.clone()));
+        assert_eq!(IssueRef::from_subject("http://swarm.os/trello/card/42"), None);
+
+        let task = |state: &str| TaskSummary {
+            id: issue.subject(),
+            title: "Fix login".into(),
+            state: state.into(),
+            agent: Some("http://swarm.os/agent/Coder_1".into()),
+        };
+        assert_eq!(progress_comment(&task("REQUIREMENTS")), None);
+        assert!(progress_comment(&task("DONE")).unwrap().starts_with("✅ Completed by **Coder_1**."));
+    }
+
+    #[tokio::test]
+    async fn ingested_issues_are_seeded_from_synapse_once() {
+        let (memory, synapse) = crate::store::MemoryStore::shared();
+        memory.answer("STRSTARTS", serde_json::json!([{"?task": "<http://swarm.os/github/issue/pmaojo/agent-swarm-dev/42>"}]));
+        let store = state::scratch();
+        let issue = IssueRef { repo: "pmaojo/agent-swarm-dev".into(), number: 42 };
+        assert_eq!(load_ingested(&synapse, store).await, HashSet::from([issue.clone()]));
+
+        // After that the state store answers, even with Synapse down.
+        memory.set_down(true);
+        assert_eq!(load_ingested(&synapse, store).await, HashSet::from([issue]));
+    }
+}
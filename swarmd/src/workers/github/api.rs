@@ -0,0 +1,59 @@
+//! Thin GitHub REST client shared by the issue poller and the pull request tracker.
+
+use reqwest::{Client, Method, RequestBuilder};
+use serde_json::{json, Value};
+
+use super::IssueRef;
+
+const API_BASE: &str = "https://api.github.com";
+
+pub(super) struct GitHubApi {
+    token: String,
+    client: Client,
+}
+
+impl GitHubApi {
+    pub(super) fn new(token: String, client: Client) -> Self {
+        Self { token, client }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", API_BASE, path))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "swarmd")
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        Ok(self.request(Method::GET, path).send().await?.error_for_status()?.json().await?)
+    }
+
+    pub(super) async fn labelled_issues(&self, repo: &str, label: &str) -> anyhow::Result<Vec<Value>> {
+        self.get(&format!("/repos/{}/issues?labels={}&state=open&per_page=100", repo, label)).await
+    }
+
+    pub(super) async fn comment(&self, issue: &IssueRef, body: &str) -> anyhow::Result<()> {
+        let path = format!("/repos/{}/issues/{}/comments", issue.repo, issue.number);
+        self.request(Method::POST, &path)
+            .json(&json!({ "body": body }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub(super) async fn pull_request(&self, repo: &str, number: u64) -> anyhow::Result<Value> {
+        self.get(&format!("/repos/{}/pulls/{}", repo, number)).await
+    }
+
+    /// Legacy commit statuses, combined into one `state`.
+    pub(super) async fn combined_status(&self, repo: &str, sha: &str) -> anyhow::Result<Value> {
+        self.get(&format!("/repos/{}/commits/{}/status", repo, sha)).await
+    }
+
+    /// Check runs (GitHub Actions and other apps) for a commit.
+    pub(super) async fn check_runs(&self, repo: &str, sha: &str) -> anyhow::Result<Value> {
+        self.get(&format!("/repos/{}/commits/{}/check-runs?per_page=100", repo, sha)).await
+    }
+}
@@ -0,0 +1,281 @@
+//! Pull requests opened by agents. When an orchestrator run prints a PR URL, the agency records
+//! a `swarm:PullRequest` for the task and parks it in `AWAITING_MERGE`; this tracker then polls
+//! the PR and its CI, and only a merge moves the task to `DONE`. A PR closed without merging
+//! rejects the task.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::experience;
+use crate::notifications::{Message, Notification};
+use crate::provenance::Source;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, TaskState, INTERNAL_STATE};
+
+use super::api::GitHubApi;
+
+pub const PULL_PREFIX: &str = "http://swarm.os/github/pull/";
+pub const PULL_REQUEST: &str = "http://swarm.os/ontology/PullRequest";
+/// Task → the pull request carrying its output.
+pub const HAS_PULL_REQUEST: &str = "http://swarm.os/ontology/pullRequest";
+pub const PULL_REQUEST_URL: &str = "http://swarm.os/ontology/url";
+/// `OPEN`, `MERGED` or `CLOSED`; the latter two are final.
+pub const PULL_REQUEST_STATE: &str = "http://swarm.os/ontology/pullRequestState";
+/// Every CI status observed on the PR's head commit, see [`CiStatus`].
+pub const CI_STATUS: &str = "http://swarm.os/ontology/ciStatus";
+
+/// Pull request `number` of `owner/repo`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PullRef {
+    pub repo: String,
+    pub number: u64,
+}
+
+impl PullRef {
+    pub fn subject(&self) -> String {
+        format!("{}{}/{}", PULL_PREFIX, self.repo, self.number)
+    }
+
+    pub fn from_subject(subject: &str) -> Option<Self> {
+        let (repo, number) = subject.strip_prefix(PULL_PREFIX)?.rsplit_once('/')?;
+        Some(Self { repo: repo.to_string(), number: number.parse().ok()? })
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://github.com/{}/pull/{}", self.repo, self.number)
+    }
+
+    /// The last `https://github.com/<owner>/<repo>/pull/<n>` in an orchestrator's output,
+    /// which is what `gh pr create` prints.
+    pub fn last_in(output: &str) -> Option<Self> {
+        output
+            .split(|c: char| c.is_whitespace() || "()<>[]\"'`".contains(c))
+            .rev()
+            .find_map(|word| {
+                let path = word.strip_prefix("https://github.com/")?;
+                let mut parts = path.split('/');
+                let (owner, repo, kind, number) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+                let number = number.trim_end_matches(|c: char| !c.is_ascii_digit()).parse().ok()?;
+                (kind == "pull" && !owner.is_empty() && !repo.is_empty())
+                    .then(|| Self { repo: format!("{}/{}", owner, repo), number })
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiStatus {
+    /// No statuses or check runs reported for the head commit.
+    None,
+    Pending,
+    Success,
+    Failure,
+}
+
+impl CiStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CiStatus::None => "NONE",
+            CiStatus::Pending => "PENDING",
+            CiStatus::Success => "SUCCESS",
+            CiStatus::Failure => "FAILURE",
+        }
+    }
+
+    /// Folds the combined commit status and the check runs of a commit into one verdict:
+    /// any failure fails, otherwise anything unfinished is pending.
+    fn from_github(combined: &Value, checks: &Value) -> Self {
+        let mut seen = false;
+        let mut pending = false;
+
+        if combined.get("total_count").and_then(Value::as_u64).unwrap_or(0) > 0 {
+            seen = true;
+            match combined.get("state").and_then(Value::as_str) {
+                Some("failure" | "error") => return CiStatus::Failure,
+                Some("success") => {}
+                _ => pending = true,
+            }
+        }
+
+        for run in checks.get("check_runs").and_then(Value::as_array).into_iter().flatten() {
+            seen = true;
+            if run.get("status").and_then(Value::as_str) != Some("completed") {
+                pending = true;
+                continue;
+            }
+            let conclusion = run.get("conclusion").and_then(Value::as_str).unwrap_or("");
+            if matches!(conclusion, "failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure") {
+                return CiStatus::Failure;
+            }
+        }
+
+        match (seen, pending) {
+            (false, _) => CiStatus::None,
+            (true, true) => CiStatus::Pending,
+            (true, false) => CiStatus::Success,
+        }
+    }
+}
+
+/// Links `pull` to the task whose run produced it. Called by the agency.
+pub async fn record(synapse: &Synapse, task_id: &str, pull: &PullRef) -> anyhow::Result<()> {
+    let subject = pull.subject();
+    let url_lit = sparql::literal(&pull.url());
+    let repo_iri = format!("http://swarm.os/repository/{}", pull.repo);
+    synapse
+        .ingest_from(Source::Agency, vec![
+            (subject.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", PULL_REQUEST),
+            (subject.as_str(), PULL_REQUEST_URL, url_lit.as_str()),
+            (subject.as_str(), PULL_REQUEST_STATE, "\"OPEN\""),
+            (subject.as_str(), crate::initiatives::REPOSITORY, repo_iri.as_str()),
+            (task_id, HAS_PULL_REQUEST, subject.as_str()),
+        ])
+        .await
+}
+
+/// One pass over the PRs of tasks still awaiting a merge. `last_ci` remembers the CI status
+/// reported per PR, so a status is written and a failure alerted only when it changes. A merge
+/// pays the task's experience against `thresholds`.
+pub(super) async fn track(
+    api: &GitHubApi,
+    synapse: &Synapse,
+    last_ci: &mut HashMap<PullRef, CiStatus>,
+    thresholds: &[i64],
+    tx: &mpsc::Sender<Notification>,
+) -> anyhow::Result<()> {
+    let awaiting: HashMap<String, String> = tasks::active_tasks(synapse)
+        .await?
+        .into_iter()
+        .filter(|t| t.state == "AWAITING_MERGE")
+        .map(|t| (t.id, t.title))
+        .collect();
+    if awaiting.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!("SELECT ?task ?pr WHERE {{ ?task <{HAS_PULL_REQUEST}> ?pr }}");
+    let rows: Vec<Value> = serde_json::from_str(&synapse.query(&query).await?).unwrap_or_default();
+    let mut pulls: BTreeMap<PullRef, String> = BTreeMap::new();
+    for row in &rows {
+        let field = |name: &str| row.get(name).or_else(|| row.get(format!("?{name}").as_str())).and_then(Value::as_str);
+        if let (Some(task), Some(pr)) = (field("task"), field("pr").and_then(PullRef::from_subject)) {
+            if awaiting.contains_key(task) {
+                pulls.insert(pr, task.to_string());
+            }
+        }
+    }
+
+    for (pull, task_id) in pulls {
+        let title = awaiting.get(&task_id).map(String::as_str).unwrap_or(&task_id);
+        let pr = api.pull_request(&pull.repo, pull.number).await?;
+        let subject = pull.subject();
+
+        if pr.get("merged").and_then(Value::as_bool).unwrap_or(false) {
+            info!("🔀 {} merged; task {} is DONE", pull.url(), task_id);
+            let completed_lit = tasks::datetime_literal(&chrono::Utc::now());
+            synapse
+                .ingest_from(Source::GitHub, vec![
+                    (subject.as_str(), PULL_REQUEST_STATE, "\"MERGED\""),
+                    (task_id.as_str(), INTERNAL_STATE, TaskState::Done.literal().as_str()),
+                    (task_id.as_str(), "http://swarm.os/ontology/completedAt", completed_lit.as_str()),
+                ])
+                .await?;
+            let _ = tx
+                .send(Notification::trace(
+                    Message::new().text("Pull request for ").strong(title).text(format!(" merged: {}", pull.url())),
+                ).with_source(Source::GitHub))
+                .await;
+            award_merged(synapse, &task_id, title, thresholds, tx).await;
+            last_ci.remove(&pull);
+            continue;
+        }
+
+        if pr.get("state").and_then(Value::as_str) == Some("closed") {
+            warn!("🔀 {} closed without merging; task {} rejected", pull.url(), task_id);
+            synapse
+                .ingest_from(Source::GitHub, vec![
+                    (subject.as_str(), PULL_REQUEST_STATE, "\"CLOSED\""),
+                    (task_id.as_str(), INTERNAL_STATE, "\"REJECTED\""),
+                ])
+                .await?;
+            let _ = tx
+                .send(Notification::alert(
+                    Message::new().text("Pull request for ").strong(title).text(format!(" was closed without merging: {}", pull.url())),
+                ).with_source(Source::GitHub))
+                .await;
+            last_ci.remove(&pull);
+            continue;
+        }
+
+        let Some(sha) = pr.pointer("/head/sha").and_then(Value::as_str) else { continue };
+        let ci = CiStatus::from_github(
+            &api.combined_status(&pull.repo, sha).await?,
+            &api.check_runs(&pull.repo, sha).await?,
+        );
+        if last_ci.get(&pull) == Some(&ci) {
+            continue;
+        }
+        let ci_lit = format!("\"{}\"", ci.as_str());
+        synapse.ingest_from(Source::GitHub, vec![(subject.as_str(), CI_STATUS, ci_lit.as_str())]).await?;
+        if ci == CiStatus::Failure {
+            let _ = tx
+                .send(Notification::alert(
+                    Message::new().text("CI is failing on the pull request for ").strong(title).text(format!(": {}", pull.url())),
+                ).with_source(Source::GitHub))
+                .await;
+        }
+        last_ci.insert(pull, ci);
+    }
+    Ok(())
+}
+
+/// Pays the agent whose run opened the merged PR, and announces a level-up.
+async fn award_merged(synapse: &Synapse, task_id: &str, title: &str, thresholds: &[i64], tx: &mpsc::Sender<Notification>) {
+    let (agent, award) = match experience::award_merged(synapse, task_id, thresholds).await {
+        Ok(Some(paid)) => paid,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Could not award experience for merged task {}: {:#}", task_id, e);
+            return;
+        }
+    };
+    if award.leveled_up() {
+        info!("⭐ {} reached level {} ({} XP)", agent, award.level, award.experience);
+        let message = experience::level_up_message(&agent, &award, title);
+        let _ = tx.send(Notification::trace(message).with_source(Source::GitHub).with_task(task_id)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn finds_the_pull_request_url_in_orchestrator_output() {
+        let output = "Pushed branch swarm/fix-login\nCreating pull request for swarm/fix-login into main\n\nhttps://github.com/pmaojo/agent-swarm-dev/pull/57\n";
+        let pull = PullRef::last_in(output).unwrap();
+        assert_eq!(pull, PullRef { repo: "pmaojo/agent-swarm-dev".into(), number: 57 });
+        assert_eq!(PullRef::from_subject(&pull.subject()), Some(pull.clone()));
+        assert_eq!(PullRef::last_in("see (https://github.com/o/r/pull/3)."), Some(PullRef { repo: "o/r".into(), number: 3 }));
+        assert_eq!(PullRef::last_in("https://github.com/o/r/issues/3 https://github.com/o/r"), None);
+    }
+
+    #[test]
+    fn folds_statuses_and_check_runs_into_one_ci_status() {
+        let no_statuses = json!({ "state": "pending", "total_count": 0 });
+        let check = |status: &str, conclusion: Option<&str>| json!({ "status": status, "conclusion": conclusion });
+
+        assert_eq!(CiStatus::from_github(&no_statuses, &json!({ "check_runs": [] })), CiStatus::None);
+        let running = json!({ "check_runs": [check("completed", Some("success")), check("in_progress", None)] });
+        assert_eq!(CiStatus::from_github(&no_statuses, &running), CiStatus::Pending);
+        let failed = json!({ "check_runs": [check("completed", Some("failure")), check("queued", None)] });
+        assert_eq!(CiStatus::from_github(&no_statuses, &failed), CiStatus::Failure);
+        let green = json!({ "check_runs": [check("completed", Some("success")), check("completed", Some("skipped"))] });
+        assert_eq!(CiStatus::from_github(&json!({ "state": "success", "total_count": 2 }), &green), CiStatus::Success);
+        assert_eq!(CiStatus::from_github(&json!({ "state": "error", "total_count": 1 }), &green), CiStatus::Failure);
+    }
+}
@@ -1,38 +1,234 @@
 use reqwest::Client;
 pub mod telegram;
+pub mod slack;
+pub mod discord;
 pub mod trello;
+pub mod github;
 pub mod agency;
+pub mod ops;
+pub mod schedules;
+pub mod standup;
+pub mod achievements;
+pub mod supervisor;
 
+use std::sync::Arc;
 use std::time::Duration;
-use tracing::info;
-use tokio::sync::mpsc;
-use crate::notifications::Notification;
+use tracing::{info, warn};
+use tokio::sync::{mpsc, watch};
+use crate::budget::BudgetLimit;
+use crate::config::AppConfig;
+use crate::health::Health;
+use crate::llm::Llm;
+use crate::notifications::{Notification, NotificationRouter, NotificationSink, Routes, SlackSink, StdoutSink, WebhookSink};
+use crate::roles::TelegramRoles;
+
+/// Gateway endpoints chat platforms push to; each hands what it receives to its worker.
+#[derive(Clone, Default)]
+pub struct ChatWebhooks {
+    pub telegram: Option<telegram::TelegramWebhook>,
+    pub slack: Option<slack::SlackCommands>,
+}
+
+/// Worker halves of the chat integrations, matching `ChatWebhooks`.
+pub struct ChatInbox {
+    pub telegram: telegram::UpdateSource,
+    pub slack: Option<supervisor::SharedReceiver<slack::SlashCommand>>,
+}
+
+pub fn chat_webhooks(cfg: &AppConfig) -> (ChatWebhooks, ChatInbox) {
+    // Telegram either polls getUpdates or receives pushes through the gateway
+    let (telegram_webhook, telegram_updates) = match (&cfg.telegram_bot_token, &cfg.telegram_webhook_url) {
+        (Some(_), Some(url)) => {
+            let (webhook, source) = telegram::webhook(url, cfg.telegram_webhook_secret.clone());
+            (Some(webhook), source)
+        }
+        _ => (None, telegram::UpdateSource::Polling),
+    };
+    // Slash commands are only accepted when their signatures can be checked
+    let (slack_commands, slack_inbox) = match &cfg.slack_signing_secret {
+        Some(secret) => {
+            let (commands, rx) = slack::commands(secret.clone());
+            (Some(commands), Some(rx))
+        }
+        None => (None, None),
+    };
+    (
+        ChatWebhooks { telegram: telegram_webhook, slack: slack_commands },
+        ChatInbox { telegram: telegram_updates, slack: slack_inbox },
+    )
+}
 
 pub async fn start_background_workers(
-    telegram_token: Option<String>,
-    telegram_chat_id: Option<String>,
-    trello_api_key: Option<String>,
-    trello_token: Option<String>,
-    trello_board_id: Option<String>,
-    synapse: crate::synapse::SynapseClient,
+    config: watch::Receiver<AppConfig>,
+    synapse: crate::store::Synapse,
+    budget: BudgetLimit,
+    health: Arc<Health>,
+    inbox: ChatInbox,
     tx: mpsc::Sender<Notification>,
     rx: mpsc::Receiver<Notification>,
 ) {
+    // Workers are chosen once at startup; the ones that support it follow `config` after that.
+    let startup = config.borrow().clone();
+    let cfg = &startup;
     let client = Client::builder()
         .timeout(Duration::from_secs(15))
         .build()
         .unwrap();
 
-    if let Some(token) = telegram_token {
-        info!("📱 Spawning Telegram Background Poller & Notifier...");
-        tokio::spawn(telegram::poll_telegram(token, synapse.clone(), client.clone(), telegram_chat_id, rx));
+    let routes = notification_routes(cfg);
+    let mut router = NotificationRouter::new(notification_sinks(cfg, routes.as_ref(), &client))
+        .with_dedupe_window(Duration::from_secs(cfg.notification_dedupe_secs));
+    if let Some(routes) = routes {
+        router = router.with_routes(routes);
+    }
+    tokio::spawn(router.run(rx));
+
+    let workers = supervisor::shared();
+
+    if let Some(token) = cfg.telegram_bot_token.clone() {
+        info!("📱 Spawning Telegram Background Poller...");
+        let roles = TelegramRoles::new(
+            cfg.telegram_chat_id.as_deref(),
+            cfg.telegram_chats.as_deref().unwrap_or_default(),
+            cfg.telegram_user_roles.as_deref().unwrap_or_default(),
+        );
+        if roles.is_empty() {
+            warn!("⚠️ No Telegram chat or user is authorized; the bot only answers read commands");
+        }
+        // Drafts tasks from free text; without it the message's own lines are used.
+        let llm = Llm::from_config(cfg, synapse.clone(), budget.clone()).unwrap_or_else(|e| {
+            warn!("Invalid LLM settings, Telegram drafts tasks without it: {}", e);
+            None
+        });
+        let (synapse, client, health, source) = (synapse.clone(), client.clone(), health.clone(), inbox.telegram);
+        workers.spawn("telegram", move || {
+            telegram::poll_telegram(token.clone(), synapse.clone(), client.clone(), roles.clone(), source.clone(), llm.clone(), health.clone())
+        });
+    }
+
+    if let (Some(token), Some(channel_id)) = (cfg.discord_bot_token.clone(), cfg.discord_channel_id.clone()) {
+        info!("🎮 Spawning Discord Background Poller...");
+        let (synapse, client, health) = (synapse.clone(), client.clone(), health.clone());
+        workers.spawn("discord", move || {
+            discord::poll_discord(token.clone(), channel_id.clone(), synapse.clone(), client.clone(), health.clone())
+        });
     }
 
-    if let (Some(api_key), Some(token), Some(board_id)) = (trello_api_key, trello_token, trello_board_id) {
+    if let Some(commands) = inbox.slack {
+        info!("💬 Spawning Slack command worker...");
+        let (synapse, client, health, channel_id) = (synapse.clone(), client.clone(), health.clone(), cfg.slack_channel_id.clone());
+        workers.spawn("slack", move || {
+            slack::run_slack(synapse.clone(), client.clone(), channel_id.clone(), commands.clone(), health.clone())
+        });
+    }
+
+    if let (Some(api_key), Some(token), Some(board_id)) = (cfg.trello_api_key.clone(), cfg.trello_token.clone(), cfg.trello_board_id.clone()) {
         info!("📱 Spawning Trello Background Poller...");
-        tokio::spawn(trello::poll_trello(api_key, token, board_id, synapse.clone(), client.clone(), tx.clone()));
+        let (hour, synapse_r, client_r, tx_r) = (cfg.trello_reconcile_hour, synapse.clone(), client.clone(), tx.clone());
+        workers.spawn("trello-reconcile", move || {
+            trello::reconcile_nightly(hour, api_key.clone(), token.clone(), board_id.clone(), synapse_r.clone(), client_r.clone(), tx_r.clone())
+        });
+        let (synapse, client, health, config, tx) = (synapse.clone(), client.clone(), health.clone(), config.clone(), tx.clone());
+        workers.spawn("trello", move || {
+            trello::poll_trello(synapse.clone(), client.clone(), health.clone(), config.clone(), tx.clone())
+        });
     }
 
+    if let Some(token) = cfg.github_token.clone() {
+        if cfg.github_repos.is_some() && github::repos_from_config(cfg).is_empty() {
+            warn!("GITHUB_REPOS has no `owner/repo` entries; no GitHub issues will be ingested");
+        }
+        info!("🐙 Spawning GitHub Poller...");
+        let (synapse, client, health, config, tx) = (synapse.clone(), client.clone(), health.clone(), config.clone(), tx.clone());
+        workers.spawn("github", move || {
+            github::poll_github(token.clone(), synapse.clone(), client.clone(), health.clone(), config.clone(), tx.clone())
+        });
+    }
+
+    let (synapse_c, health_c, config_c, tx_c) = (synapse.clone(), health.clone(), config.clone(), tx.clone());
+    workers.spawn("schedules", move || {
+        schedules::run_schedules(synapse_c.clone(), health_c.clone(), config_c.clone(), tx_c.clone())
+    });
+
+    let (synapse_a, health_a, config_a, tx_a) = (synapse.clone(), health.clone(), config.clone(), tx.clone());
+    workers.spawn("achievements", move || {
+        achievements::run_achievements(synapse_a.clone(), health_a.clone(), config_a.clone(), tx_a.clone())
+    });
+
+    let (synapse_d, config_d, budget_d, tx_d) = (synapse.clone(), config.clone(), budget.clone(), tx.clone());
+    workers.spawn("standup", move || {
+        standup::run_daily(synapse_d.clone(), config_d.clone(), budget_d.clone(), tx_d.clone())
+    });
+
     info!("🤖 Spawning Agent Agency worker...");
-    tokio::spawn(agency::start_agency(synapse.clone()));
+    tokio::spawn(budget.clone().follow_config(synapse.clone(), config.clone()));
+    let (synapse_s, config_s, health_s) = (synapse.clone(), config.clone(), health.clone());
+    workers.spawn("stall-watch", move || agency::watch_stalls(synapse_s.clone(), config_s.clone(), health_s.clone()));
+    workers.spawn("agency", move || {
+        agency::start_agency(synapse.clone(), config.clone(), budget.clone(), health.clone(), tx.clone())
+    });
+}
+
+/// `NOTIFICATION_ROUTES`, when set and valid. Invalid routes are ignored with a warning, so
+/// every sink keeps getting every notification rather than alerts going nowhere.
+fn notification_routes(cfg: &AppConfig) -> Option<Routes> {
+    match Routes::parse(cfg.notification_routes.as_deref()?) {
+        Ok(routes) => Some(routes),
+        Err(e) => {
+            warn!("Ignoring NOTIFICATION_ROUTES: {}", e);
+            None
+        }
+    }
+}
+
+/// Builds the sinks named in `NOTIFICATION_SINKS`, or else in the routes. Without either
+/// notifications go to whichever of Telegram, the Slack bot channel and Discord are fully
+/// configured, and to the log otherwise.
+fn notification_sinks(cfg: &AppConfig, routes: Option<&Routes>, client: &Client) -> Vec<Box<dyn NotificationSink>> {
+    let telegram = cfg.telegram_bot_token.as_ref().zip(cfg.telegram_chat_id.as_ref());
+    let slack_bot = cfg.slack_bot_token.as_ref().zip(cfg.slack_channel_id.as_ref());
+    let discord = cfg.discord_bot_token.as_ref().zip(cfg.discord_channel_id.as_ref());
+    let names: Vec<String> = match (&cfg.notification_sinks, routes) {
+        (Some(list), _) => list.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect(),
+        (None, Some(routes)) => routes.sink_names().into_iter().map(String::from).collect(),
+        (None, None) => {
+            let configured: Vec<String> = [("telegram", telegram.is_some()), ("slack", slack_bot.is_some()), ("discord", discord.is_some())]
+                .into_iter()
+                .filter(|(_, ok)| *ok)
+                .map(|(name, _)| name.to_string())
+                .collect();
+            if configured.is_empty() { vec!["stdout".to_string()] } else { configured }
+        }
+    };
+
+    let mut sinks: Vec<Box<dyn NotificationSink>> = Vec::new();
+    for name in names {
+        match name.as_str() {
+            "telegram" => match telegram {
+                Some((token, chat_id)) => sinks.push(Box::new(telegram::TelegramSink::new(token, chat_id.clone(), client.clone()))),
+                None => warn!("Notification sink 'telegram' needs TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID; skipping"),
+            },
+            "slack" => match (slack_bot, &cfg.slack_webhook_url) {
+                (Some((token, channel)), _) => sinks.push(Box::new(slack::SlackBotSink::new(token.clone(), channel.clone(), client.clone()))),
+                (None, Some(url)) => sinks.push(Box::new(SlackSink::new(url.clone(), client.clone()))),
+                (None, None) => warn!("Notification sink 'slack' needs SLACK_BOT_TOKEN and SLACK_CHANNEL_ID, or SLACK_WEBHOOK_URL; skipping"),
+            },
+            "discord" => match discord {
+                Some((token, channel_id)) => sinks.push(Box::new(discord::DiscordSink::new(token.clone(), channel_id, client.clone()))),
+                None => warn!("Notification sink 'discord' needs DISCORD_BOT_TOKEN and DISCORD_CHANNEL_ID; skipping"),
+            },
+            "webhook" => match &cfg.notify_webhook_url {
+                Some(url) => sinks.push(Box::new(WebhookSink::new(url.clone(), client.clone()))),
+                None => warn!("Notification sink 'webhook' needs NOTIFY_WEBHOOK_URL; skipping"),
+            },
+            "stdout" => sinks.push(Box::new(StdoutSink)),
+            other => warn!("Unknown notification sink '{}'; expected telegram, slack, discord, webhook or stdout", other),
+        }
+    }
+
+    if sinks.is_empty() {
+        warn!("No usable notification sinks configured; falling back to stdout");
+        sinks.push(Box::new(StdoutSink));
+    }
+    sinks
 }
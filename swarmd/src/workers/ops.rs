@@ -0,0 +1,155 @@
+//! Operator actions shared by the chat workers, so every channel reports and changes
+//! system status the same way.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+
+use crate::audit;
+use crate::notifications::Message;
+use crate::provenance::Source;
+use crate::roles::Role;
+use crate::server::contracts::QuestStatus;
+use crate::store::Synapse;
+use crate::sparql;
+use crate::tasks::active_tasks;
+
+pub fn state_emoji(state: &str) -> &'static str {
+    match state {
+        "PROCESSING" => "⚙️",
+        "RETRY_SCHEDULED" => "🔁",
+        "AWAITING_MERGE" => "🔀",
+        "ESCALATED" => "🙋",
+        "AWAITING_APPROVAL" => "🛡️",
+        "FAILED" | "TIMED_OUT" | "STALLED" => "⚠️",
+        "DESIGN" => "📐",
+        "REQUIREMENTS" => "📝",
+        _ => "📋",
+    }
+}
+
+/// Status set by the newest `StatusChangeEvent` of the system control node, if one was ever
+/// recorded. `nist:operationalStatus` keeps every status ever set, so it cannot say which is
+/// current.
+pub async fn operational_status(synapse: &Synapse) -> anyhow::Result<Option<String>> {
+    let query = r#"
+        PREFIX nist: <http://nist.gov/caisi/>
+        PREFIX prov: <http://www.w3.org/ns/prov#>
+        SELECT ?status ?at WHERE {
+            nist:SystemControl nist:hasStatusHistory ?event .
+            ?event nist:newStatus ?status ;
+                   prov:generatedAtTime ?at .
+        }
+    "#;
+    #[derive(Deserialize)]
+    struct StatusRow {
+        status: String,
+        at: String,
+    }
+    let res = synapse.query_cached(query, crate::query_cache::DEFAULT_TTL).await?;
+    let rows = sparql::rows::<StatusRow>(&res).unwrap_or_default();
+    Ok(rows.into_iter().max_by_key(|row| changed_at(&row.at)).map(|row| row.status))
+}
+
+/// When a status change happened. The Python monitor writes local times without an offset,
+/// read here as UTC; anything unreadable sorts first.
+fn changed_at(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f").map(|at| at.and_utc()))
+        .ok()
+}
+
+/// System status for a chat command; `verbose` adds queue details, and the day's
+/// spend when `role` may see spend data.
+pub async fn status_message(synapse: &Synapse, verbose: bool, role: Role) -> Message {
+    let status = match operational_status(synapse).await {
+        Ok(status) => status.unwrap_or_else(|| "UNKNOWN".to_string()),
+        Err(_) => "Error querying Synapse".to_string(),
+    };
+
+    let mut msg = Message::new().text("📊 ").strong("System Status").text("\n").code(status);
+    if let Some(banner) = crate::maintenance::current() {
+        msg = msg.text(format!("\n🚧 Maintenance mode since {}", crate::clock::display_rfc3339(&banner.since)));
+        if let Some(reason) = banner.reason {
+            msg = msg.text(format!(" — {}", reason));
+        }
+    }
+    if verbose && role >= Role::Admin {
+        match crate::budget::spent_today(synapse).await {
+            Ok(spent) => msg = msg.text(format!("\n💸 Spent today: ${:.2}", spent)),
+            Err(e) => msg = msg.text(format!("\n💸 Spend unavailable: {}", e)),
+        }
+    }
+    if verbose {
+        if let Ok(tasks) = active_tasks(synapse).await {
+            let running = tasks.iter().filter(|t| t.state == "PROCESSING").count();
+            msg = msg.text(format!("\n📋 Active tasks: {} ({} processing)", tasks.len(), running));
+        }
+        if let Ok(initiatives) = crate::initiatives::statuses(synapse).await {
+            for initiative in initiatives.iter().filter(|i| i.status != QuestStatus::Done) {
+                msg = msg
+                    .text("\n🗺️ ")
+                    .strong(&initiative.title)
+                    .text(format!(" — {}/{} tasks done", initiative.tasks_done, initiative.tasks_total));
+                if initiative.tasks_blocked > 0 {
+                    msg = msg.text(format!(", {} blocked", initiative.tasks_blocked));
+                }
+            }
+        }
+    }
+    msg
+}
+
+/// Records a `StatusChangeEvent` and sets `operationalStatus` (`HALTED`, `THROTTLED`,
+/// `OPERATIONAL`), auditing it as `actor`'s halt, throttle or resume.
+pub async fn set_operational_status(status: &str, source: Source, actor: &str, synapse: &Synapse) -> anyhow::Result<()> {
+    let event_id = format!("http://nist.gov/caisi/event/status/{}", uuid::Uuid::new_v4());
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let action = match status {
+        "HALTED" => audit::Action::Halt,
+        "THROTTLED" => audit::Action::Throttle,
+        _ => audit::Action::Resume,
+    };
+    let audit = audit::triples(action, actor, None, None);
+
+    let status_lit = format!("\"{}\"", status);
+    let timestamp_lit = format!("\"{}\"", timestamp);
+    let mut triples = vec![
+        (event_id.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://nist.gov/caisi/StatusChangeEvent"),
+        (event_id.as_str(), "http://nist.gov/caisi/newStatus", status_lit.as_str()),
+        (event_id.as_str(), "http://www.w3.org/ns/prov#generatedAtTime", timestamp_lit.as_str()),
+        ("http://nist.gov/caisi/SystemControl", "http://nist.gov/caisi/hasStatusHistory", event_id.as_str()),
+        ("http://nist.gov/caisi/SystemControl", "http://nist.gov/caisi/operationalStatus", status_lit.as_str()),
+    ];
+    triples.extend(audit.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())));
+    synapse.ingest_from(source, triples).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[tokio::test]
+    async fn the_newest_status_change_wins() {
+        let (store, synapse) = MemoryStore::shared();
+        store.answer(
+            "hasStatusHistory",
+            serde_json::json!([
+                {"?status": "\"OPERATIONAL\"", "?at": "\"2026-10-17T08:00:00+00:00\""},
+                {"?status": "\"HALTED\"", "?at": "\"2026-10-17T09:30:00.25+00:00\""},
+                {"?status": "\"THROTTLED\"", "?at": "\"2026-10-17T09:30:00+00:00\""},
+                {"?status": "\"OPERATIONAL\"", "?at": "\"2026-10-17T07:00:00\""},
+            ]),
+        );
+        assert_eq!(operational_status(&synapse).await.unwrap().as_deref(), Some("HALTED"));
+    }
+
+    #[test]
+    fn change_times_without_an_offset_are_utc() {
+        assert_eq!(changed_at("2026-10-17T09:30:00.123456"), changed_at("2026-10-17T09:30:00.123456+00:00"));
+        assert!(changed_at("yesterday").is_none());
+    }
+}
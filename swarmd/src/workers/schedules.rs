@@ -0,0 +1,65 @@
+//! Queues scheduled tasks when they come due; see [`crate::schedules`]. Runs missed while
+//! swarmd was down are caught up on the first tick after it starts.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::health::Health;
+use crate::notifications::{Message, Notification};
+use crate::provenance::Source;
+use crate::schedules;
+use crate::store::Synapse;
+
+pub async fn run_schedules(
+    synapse: Synapse,
+    health: Arc<Health>,
+    config: watch::Receiver<AppConfig>,
+    tx: mpsc::Sender<Notification>,
+) {
+    info!("⏰ Schedules worker started");
+    loop {
+        health.beat("schedules");
+        let (enabled, interval) = {
+            let cfg = config.borrow();
+            (cfg.worker_enabled("schedules"), Duration::from_secs(cfg.schedules_poll_secs.max(1)))
+        };
+        if enabled {
+            if let Err(e) = tick(&synapse, &tx, Utc::now()).await {
+                warn!("⚠️ Scheduled tasks could not be checked: {}", e);
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn tick(synapse: &Synapse, tx: &mpsc::Sender<Notification>, now: DateTime<Utc>) -> anyhow::Result<()> {
+    let tz = crate::clock::tz();
+    for schedule in schedules::load(synapse).await?.into_iter().filter(|s| !s.retired) {
+        let cron = match schedules::parse_cron(&schedule.cron) {
+            Ok(cron) => cron,
+            Err(e) => {
+                warn!("Schedule {} is skipped: {}", schedule.id, e);
+                continue;
+            }
+        };
+        if schedule.since.is_none() && schedule.last_run.is_none() {
+            schedules::start(synapse, &schedule.id, now).await?;
+            continue;
+        }
+        let Some(due) = schedule.due(&cron, tz, now) else { continue };
+
+        let task = schedules::run(synapse, &schedule, due.at, tz).await?;
+        info!("⏰ Queued {} for schedule {} ({} due)", task, schedule.id, due.occurrences);
+        let mut message = Message::new().text("Scheduled task ").strong(schedule.title.as_str()).text(" queued");
+        if due.occurrences > 1 {
+            message = message.text(format!(", catching up {} runs missed while swarmd was down", due.occurrences));
+        }
+        let _ = tx.send(Notification::trace(message).with_source(Source::Scheduler)).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Slack counterpart of the Telegram worker: notifications are posted to a channel with the
+//! bot token, and `/swarm` slash commands arrive through the gateway.
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+use crate::health::Health;
+use crate::notifications::{ChannelRenderer, Message, Notification, NotificationSink, SlackBlocks};
+use crate::provenance::Source;
+use crate::roles::Role;
+use crate::store::Synapse;
+
+use super::ops;
+use super::supervisor::{shared_receiver, SharedReceiver};
+
+pub const COMMANDS_PATH: &str = "/api/v1/webhooks/slack/commands";
+
+/// Slack recommends rejecting requests signed more than five minutes ago, to stop replays.
+const MAX_SIGNATURE_AGE_SECS: i64 = 300;
+
+/// The form fields Slack posts for a slash command that swarmd uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlashCommand {
+    pub command: String,
+    #[serde(default)]
+    pub text: String,
+    pub channel_id: String,
+    #[serde(default)]
+    pub user_name: String,
+    pub response_url: String,
+}
+
+/// Gateway half of the command integration: verifies Slack's request signature and hands
+/// commands to the worker.
+#[derive(Clone)]
+pub struct SlackCommands {
+    signing_secret: String,
+    pub commands: mpsc::Sender<SlashCommand>,
+}
+
+impl SlackCommands {
+    /// Checks `X-Slack-Signature`, which is `v0=` + hex HMAC-SHA256 of `v0:{timestamp}:{body}`.
+    pub fn verify(&self, timestamp: &str, body: &[u8], signature: &str, now: i64) -> bool {
+        let Ok(ts) = timestamp.parse::<i64>() else { return false };
+        if (now - ts).abs() > MAX_SIGNATURE_AGE_SECS {
+            return false;
+        }
+        let Some(expected) = signature.strip_prefix("v0=").and_then(|hex| hex::decode(hex).ok()) else {
+            return false;
+        };
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(self.signing_secret.as_bytes()) else {
+            return false;
+        };
+        mac.update(format!("v0:{}:", timestamp).as_bytes());
+        mac.update(body);
+        mac.verify_slice(&expected).is_ok()
+    }
+}
+
+pub fn commands(signing_secret: String) -> (SlackCommands, SharedReceiver<SlashCommand>) {
+    let (tx, rx) = mpsc::channel(100);
+    (SlackCommands { signing_secret, commands: tx }, shared_receiver(rx))
+}
+
+/// What `/swarm <text>` asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Help,
+    Status { verbose: bool },
+    Halt,
+    Resume,
+    Unknown(String),
+}
+
+impl Action {
+    fn parse(text: &str) -> Self {
+        let mut words = text.split_whitespace().map(str::to_lowercase);
+        match words.next().as_deref() {
+            None | Some("help") => Action::Help,
+            Some("status") => Action::Status { verbose: words.any(|w| w == "verbose" || w == "--verbose") },
+            Some("halt") | Some("stop_all") => Action::Halt,
+            Some("resume") => Action::Resume,
+            Some(other) => Action::Unknown(other.to_string()),
+        }
+    }
+
+    /// Changes system state, so only the configured channel may run it.
+    fn privileged(&self) -> bool {
+        matches!(self, Action::Halt | Action::Resume)
+    }
+}
+
+fn help() -> Message {
+    Message::new()
+        .text("🤖 ")
+        .strong("Commands")
+        .text("\n")
+        .code("/swarm status [verbose]")
+        .text(" — System status; `verbose` adds spend and queue size\n")
+        .code("/swarm halt")
+        .text(" — Emergency halt of the swarm\n")
+        .code("/swarm resume")
+        .text(" — Resume operational status after a halt")
+}
+
+pub async fn run_slack(
+    synapse: Synapse,
+    client: Client,
+    channel_id: Option<String>,
+    commands: SharedReceiver<SlashCommand>,
+    health: Arc<Health>,
+) {
+    info!("💬 Slack command worker started...");
+    let mut commands = commands.lock().await;
+    loop {
+        health.beat("slack");
+        tokio::select! {
+            Some(cmd) = commands.recv() => handle_command(&cmd, &synapse, &client, &channel_id).await,
+            _ = sleep(Duration::from_secs(30)) => {}
+        }
+    }
+}
+
+async fn handle_command(cmd: &SlashCommand, synapse: &Synapse, client: &Client, authorized_channel: &Option<String>) {
+    let action = Action::parse(&cmd.text);
+    let is_authorized = authorized_channel.as_ref().map(|c| c == &cmd.channel_id).unwrap_or(true);
+    // The authorized channel may pull the emergency switch, so it also sees spend.
+    let role = if is_authorized { Role::Admin } else { Role::Viewer };
+
+    let reply = if action.privileged() && !is_authorized {
+        Message::new().text("⛔ Unauthorized.")
+    } else {
+        match action {
+            Action::Help => help(),
+            Action::Status { verbose } => ops::status_message(synapse, verbose, role).await,
+            Action::Halt => match ops::set_operational_status("HALTED", Source::Slack, &format!("slack:{}", cmd.user_name), synapse).await {
+                Ok(()) => {
+                    info!("🛑 System halted via Slack by {}", cmd.user_name);
+                    Message::new().text("🛑 ").strong("SYSTEM HALTED").text(format!(" by {}.", cmd.user_name))
+                }
+                Err(e) => Message::new().text(format!("❌ Failed to halt: {}", e)),
+            },
+            Action::Resume => match ops::set_operational_status("OPERATIONAL", Source::Slack, &format!("slack:{}", cmd.user_name), synapse).await {
+                Ok(()) => Message::new().text("✅ ").strong("SYSTEM RESUMED").text(" to Operational status."),
+                Err(e) => Message::new().text(format!("❌ Failed to resume: {}", e)),
+            },
+            Action::Unknown(name) => Message::new()
+                .text("❓ Unknown command ")
+                .code(format!("{} {}", cmd.command, name))
+                .text(". Try ")
+                .code(format!("{} help", cmd.command)),
+        }
+    };
+
+    let mut body = SlackBlocks.render_message(&reply);
+    body["response_type"] = json!("in_channel");
+    if let Err(e) = client.post(&cmd.response_url).json(&body).send().await {
+        warn!("⚠️ Could not answer Slack command: {}", e);
+    }
+}
+
+/// Posts notifications to a channel through `chat.postMessage`.
+pub struct SlackBotSink {
+    token: String,
+    channel: String,
+    client: Client,
+}
+
+impl SlackBotSink {
+    pub fn new(token: String, channel: String, client: Client) -> Self {
+        Self { token, channel, client }
+    }
+}
+
+impl NotificationSink for SlackBotSink {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut body = SlackBlocks.render(notification);
+            body["channel"] = json!(self.channel);
+            let res: Value = self
+                .client
+                .post("https://slack.com/api/chat.postMessage")
+                .bearer_auth(&self.token)
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            // The Web API reports failures with 200 and `ok: false`.
+            if res.get("ok").and_then(Value::as_bool) != Some(true) {
+                anyhow::bail!("chat.postMessage failed: {}", res.get("error").and_then(Value::as_str).unwrap_or("unknown error"));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_slack_signatures() {
+        // Example request from Slack's "Verifying requests" guide.
+        let (slack, _rx) = commands("8f742231b10e8888abcd99yyyzzz85a5".to_string());
+        let body = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+        let signature = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+
+        assert!(slack.verify("1531420618", body.as_bytes(), signature, 1531420618 + 10));
+        assert!(!slack.verify("1531420618", body.as_bytes(), signature, 1531420618 + 3600));
+        assert!(!slack.verify("1531420618", b"text=halt", signature, 1531420618));
+
+        let cmd: SlashCommand = serde_urlencoded::from_str(body).unwrap();
+        assert_eq!((cmd.command.as_str(), cmd.channel_id.as_str()), ("/webhook-collect", "G8PSS9T3V"));
+    }
+
+    #[test]
+    fn parses_swarm_subcommands() {
+        assert_eq!(Action::parse(""), Action::Help);
+        assert_eq!(Action::parse("Status verbose"), Action::Status { verbose: true });
+        assert_eq!(Action::parse("halt now"), Action::Halt);
+        assert!(Action::parse("resume").privileged());
+        assert_eq!(Action::parse("deploy"), Action::Unknown("deploy".into()));
+    }
+}
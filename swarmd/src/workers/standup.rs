@@ -0,0 +1,270 @@
+//! Morning standup. Once a day at `STANDUP_HOUR` the swarm sums up yesterday: tasks completed
+//! and failed, what is still in flight, spend against the daily budget and the agents left
+//! idle. The digest goes out through the notification channels and is kept as a
+//! `swarm:Report` so the dashboard and later digests can look back.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+use crate::budget::{self, BudgetLimit};
+use crate::clock;
+use crate::config::AppConfig;
+use crate::notifications::{ChannelRenderer, Message, Notification, PlainText};
+use crate::provenance::Source;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, TaskSummary};
+
+pub const REPORT: &str = "http://swarm.os/ontology/Report";
+pub const REPORT_KIND: &str = "http://swarm.os/ontology/reportKind";
+pub const REPORT_DATE: &str = "http://swarm.os/ontology/reportDate";
+pub const COMPLETED_COUNT: &str = "http://swarm.os/ontology/completedCount";
+pub const FAILED_COUNT: &str = "http://swarm.os/ontology/failedCount";
+pub const IN_FLIGHT_COUNT: &str = "http://swarm.os/ontology/inFlightCount";
+pub const IDLE_AGENT_COUNT: &str = "http://swarm.os/ontology/idleAgentCount";
+pub const SPENT_USD: &str = "http://swarm.os/ontology/spentUsd";
+pub const BUDGET_USD: &str = "http://swarm.os/ontology/budgetUsd";
+pub const SUMMARY: &str = "http://swarm.os/ontology/summary";
+
+/// Where a task that ended yesterday counts as a failure.
+const FAILED_STATES: &[&str] = &["FAILED", "RETRY_SCHEDULED", "DEAD_LETTER", "ESCALATED", "TIMED_OUT", "STALLED"];
+/// Where a task counts as in flight.
+const IN_FLIGHT_STATES: &[&str] = &["PROCESSING", "AWAITING_MERGE"];
+/// Tasks listed per section; the rest are only counted.
+const MAX_LISTED: usize = 10;
+
+/// Yesterday in numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Digest {
+    pub date: NaiveDate,
+    pub completed: Vec<TaskSummary>,
+    pub failed: Vec<TaskSummary>,
+    pub in_flight: Vec<TaskSummary>,
+    pub spent_usd: f64,
+    pub budget_usd: f64,
+    pub idle_agents: Vec<String>,
+}
+
+impl Digest {
+    /// Sorts `tasks` into the sections for `date`, given when each task finished a run.
+    pub fn compose(
+        date: NaiveDate,
+        tasks: &[TaskSummary],
+        finished: &BTreeMap<String, Vec<DateTime<Utc>>>,
+        idle_agents: Vec<String>,
+        spent_usd: f64,
+        budget_usd: f64,
+    ) -> Self {
+        let ended_on_date = |task: &TaskSummary| finished.get(&task.id).is_some_and(|runs| runs.iter().any(|at| clock::date_of(*at) == date));
+        let pick = |keep: &dyn Fn(&TaskSummary) -> bool| tasks.iter().filter(|t| keep(t)).cloned().collect::<Vec<_>>();
+        Self {
+            date,
+            completed: pick(&|t| t.state == "DONE" && ended_on_date(t)),
+            failed: pick(&|t| FAILED_STATES.contains(&t.state.as_str()) && ended_on_date(t)),
+            in_flight: pick(&|t| IN_FLIGHT_STATES.contains(&t.state.as_str())),
+            spent_usd,
+            budget_usd,
+            idle_agents,
+        }
+    }
+
+    pub fn message(&self) -> Message {
+        let mut msg = Message::new().text("☀️ ").strong(format!("Standup for {}", self.date.format("%a %-d %b")));
+        msg = section(msg, "\n\n✅ Completed", &self.completed, |t| t.title.clone());
+        msg = section(msg, "\n\n❌ Failed", &self.failed, |t| format!("{} ({})", t.title, t.state));
+        msg = section(msg, "\n\n🔄 In flight", &self.in_flight, |t| match &t.agent {
+            Some(agent) => format!("{} ({}, {})", t.title, t.state, agent.rsplit('/').next().unwrap_or(agent)),
+            None => format!("{} ({})", t.title, t.state),
+        });
+        msg = msg.text("\n\n💰 Spend: ").strong(format!("${:.2}", self.spent_usd)).text(format!(" of ${:.2}", self.budget_usd));
+        if self.budget_usd > 0.0 {
+            msg = msg.text(format!(" ({:.0}%)", self.spent_usd / self.budget_usd * 100.0));
+        }
+        msg = msg.text("\n😴 Idle agents: ");
+        if self.idle_agents.is_empty() {
+            msg.text("none")
+        } else {
+            msg.text(self.idle_agents.join(", "))
+        }
+    }
+}
+
+fn section(msg: Message, heading: &str, tasks: &[TaskSummary], line: impl Fn(&TaskSummary) -> String) -> Message {
+    let mut msg = msg.text(format!("{}: ", heading)).strong(tasks.len().to_string());
+    for task in tasks.iter().take(MAX_LISTED) {
+        msg = msg.text(format!("\n• {}", line(task)));
+    }
+    if tasks.len() > MAX_LISTED {
+        msg = msg.text(format!("\n• … and {} more", tasks.len() - MAX_LISTED));
+    }
+    msg
+}
+
+pub async fn run_daily(synapse: Synapse, config: watch::Receiver<AppConfig>, budget: BudgetLimit, tx: mpsc::Sender<Notification>) {
+    let mut hour = config.borrow().standup_hour;
+    info!("☀️ Standup digest scheduled daily at {:02}:00 {}", hour, clock::tz());
+    loop {
+        let now = Utc::now();
+        tokio::time::sleep((clock::next_local_hour(clock::tz(), hour, now) - now).to_std().unwrap_or(Duration::from_secs(1))).await;
+        // A reloaded hour applies from the next run on
+        let (enabled, next_hour) = {
+            let cfg = config.borrow();
+            (cfg.worker_enabled("standup"), cfg.standup_hour)
+        };
+        if enabled {
+            if let Err(e) = run_once(&synapse, &budget, &tx).await {
+                warn!("⚠️ Standup digest failed: {}", e);
+            }
+        }
+        hour = next_hour;
+    }
+}
+
+async fn run_once(synapse: &Synapse, budget: &BudgetLimit, tx: &mpsc::Sender<Notification>) -> anyhow::Result<()> {
+    let Some(date) = clock::today().pred_opt() else { return Ok(()) };
+    let tasks = tasks::all_tasks(synapse).await?;
+    let digest = Digest::compose(
+        date,
+        &tasks,
+        &finished_runs(synapse).await?,
+        idle_agents(synapse).await?,
+        budget::spent_on(synapse, date).await?,
+        budget.get(),
+    );
+    let message = digest.message();
+    store_report(synapse, &digest, &PlainText.render_message(&message)).await?;
+    info!(
+        "☀️ Standup for {}: {} completed, {} failed, {} in flight",
+        date,
+        digest.completed.len(),
+        digest.failed.len(),
+        digest.in_flight.len()
+    );
+    let _ = tx.send(Notification::trace(message).with_source(Source::Scheduler)).await;
+    Ok(())
+}
+
+/// When each task finished its runs, keyed by task IRI.
+async fn finished_runs(synapse: &Synapse) -> anyhow::Result<BTreeMap<String, Vec<DateTime<Utc>>>> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct FinishedRow {
+        task: String,
+        #[serde(default, deserialize_with = "sparql::parsed")]
+        completed_at: Option<DateTime<Utc>>,
+    }
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?task ?completedAt WHERE {
+            ?task a swarm:Task ;
+                  swarm:completedAt ?completedAt .
+        }
+    "#;
+    let mut finished: BTreeMap<String, Vec<DateTime<Utc>>> = BTreeMap::new();
+    for row in synapse.query_as::<FinishedRow>(query).await? {
+        if let Some(at) = row.completed_at {
+            finished.entry(row.task).or_default().push(at);
+        }
+    }
+    Ok(finished)
+}
+
+/// Names of the agents on Standby.
+async fn idle_agents(synapse: &Synapse) -> anyhow::Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct AgentRow {
+        agent: String,
+        name: Option<String>,
+    }
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?agent ?name WHERE {
+            ?agent a swarm:Agent ;
+                   swarm:status "Standby" .
+            FILTER NOT EXISTS { ?agent swarm:status "Retired" }
+            OPTIONAL { ?agent swarm:name ?name }
+        }
+    "#;
+    let names: BTreeSet<String> = synapse
+        .query_as::<AgentRow>(query)
+        .await?
+        .into_iter()
+        .map(|row| row.name.unwrap_or_else(|| row.agent.rsplit('/').next().unwrap_or(&row.agent).to_string()))
+        .collect();
+    Ok(names.into_iter().collect())
+}
+
+async fn store_report(synapse: &Synapse, digest: &Digest, summary: &str) -> anyhow::Result<()> {
+    let date = digest.date.format("%Y-%m-%d").to_string();
+    let subject = format!("http://swarm.os/report/standup/{}", date);
+    let date_lit = format!("\"{}\"", date);
+    let completed_lit = tasks::integer_literal(digest.completed.len() as i64);
+    let failed_lit = tasks::integer_literal(digest.failed.len() as i64);
+    let in_flight_lit = tasks::integer_literal(digest.in_flight.len() as i64);
+    let idle_lit = tasks::integer_literal(digest.idle_agents.len() as i64);
+    let spent_lit = tasks::decimal_literal(digest.spent_usd);
+    let budget_lit = tasks::decimal_literal(digest.budget_usd);
+    let summary_lit = sparql::literal(summary);
+
+    synapse
+        .ingest_from(
+            Source::Scheduler,
+            vec![
+                (subject.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", REPORT),
+                (subject.as_str(), REPORT_KIND, "\"standup\""),
+                (subject.as_str(), REPORT_DATE, date_lit.as_str()),
+                (subject.as_str(), COMPLETED_COUNT, completed_lit.as_str()),
+                (subject.as_str(), FAILED_COUNT, failed_lit.as_str()),
+                (subject.as_str(), IN_FLIGHT_COUNT, in_flight_lit.as_str()),
+                (subject.as_str(), IDLE_AGENT_COUNT, idle_lit.as_str()),
+                (subject.as_str(), SPENT_USD, spent_lit.as_str()),
+                (subject.as_str(), BUDGET_USD, budget_lit.as_str()),
+                (subject.as_str(), SUMMARY, summary_lit.as_str()),
+            ],
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, state: &str, agent: Option<&str>) -> TaskSummary {
+        TaskSummary { id: id.into(), title: format!("Task {id}"), state: state.into(), agent: agent.map(str::to_string) }
+    }
+
+    #[test]
+    fn digest_sorts_yesterdays_tasks_into_sections() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let at = |raw: &str| raw.parse::<DateTime<Utc>>().unwrap();
+        let tasks = vec![
+            task("done", "DONE", Some("http://swarm.os/agent/Coder_1")),
+            task("done-earlier", "DONE", None),
+            task("failed", "DEAD_LETTER", None),
+            task("running", "PROCESSING", Some("http://swarm.os/agent/Coder_2")),
+            task("waiting", "TODO", None),
+        ];
+        let finished = BTreeMap::from([
+            ("done".to_string(), vec![at("2026-10-15T22:00:00Z"), at("2026-10-16T09:00:00Z")]),
+            ("done-earlier".to_string(), vec![at("2026-10-15T09:00:00Z")]),
+            ("failed".to_string(), vec![at("2026-10-16T23:59:00Z")]),
+        ]);
+
+        let digest = Digest::compose(date, &tasks, &finished, vec!["Reviewer_1".into()], 2.5, 10.0);
+        let ids = |tasks: &[TaskSummary]| tasks.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&digest.completed), ["done"]);
+        assert_eq!(ids(&digest.failed), ["failed"]);
+        assert_eq!(ids(&digest.in_flight), ["running"]);
+
+        let text = PlainText.render_message(&digest.message());
+        assert!(text.starts_with("☀️ Standup for Fri 16 Oct"), "{text}");
+        assert!(text.contains("❌ Failed: 1\n• Task failed (DEAD_LETTER)"), "{text}");
+        assert!(text.contains("• Task running (PROCESSING, Coder_2)"), "{text}");
+        assert!(text.contains("💰 Spend: $2.50 of $10.00 (25%)\n😴 Idle agents: Reviewer_1"), "{text}");
+    }
+}
@@ -0,0 +1,213 @@
+//! Runtime control of the background workers (`/api/v1/admin/workers`). Each worker is
+//! spawned from a factory, so a misbehaving one can be stopped, by aborting its task, and
+//! started again without restarting swarmd. Whatever a worker keeps in memory starts afresh on
+//! every start; what it keeps in Synapse or the state store (poll offsets, cards already seen)
+//! does not.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, info_span, Instrument};
+
+use crate::server::contracts::{WorkerState, WorkerStatus};
+use crate::telemetry;
+
+/// A receiver a worker borrows for as long as it runs, so a restarted worker picks up where
+/// the stopped one left off instead of losing the channel with the aborted task.
+pub type SharedReceiver<T> = Arc<tokio::sync::Mutex<mpsc::Receiver<T>>>;
+
+pub fn shared_receiver<T>(rx: mpsc::Receiver<T>) -> SharedReceiver<T> {
+    Arc::new(tokio::sync::Mutex::new(rx))
+}
+
+type Factory = Box<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+struct Worker {
+    factory: Factory,
+    handle: Option<JoinHandle<()>>,
+    since: DateTime<Utc>,
+    starts: u32,
+    /// Pause between passes, for workers whose pace changes while they run.
+    poll_interval: Option<Duration>,
+}
+
+impl Worker {
+    fn launch(&mut self) {
+        self.handle = Some(tokio::spawn((self.factory)()));
+        self.since = Utc::now();
+        self.starts += 1;
+        self.poll_interval = None;
+    }
+
+    fn status(&self, name: &str) -> WorkerStatus {
+        let state = match &self.handle {
+            None => WorkerState::Stopped,
+            Some(handle) if handle.is_finished() => WorkerState::Exited,
+            Some(_) => WorkerState::Running,
+        };
+        WorkerStatus {
+            name: name.to_string(),
+            state,
+            since: self.since.to_rfc3339(),
+            starts: self.starts,
+            poll_interval_secs: self.poll_interval.filter(|_| state == WorkerState::Running).map(|d| d.as_secs()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupervisorError {
+    UnknownWorker(String),
+    AlreadyRunning(String),
+    NotRunning(String),
+}
+
+impl std::fmt::Display for SupervisorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SupervisorError::UnknownWorker(name) => write!(f, "No worker named '{}'", name),
+            SupervisorError::AlreadyRunning(name) => write!(f, "Worker '{}' is already running", name),
+            SupervisorError::NotRunning(name) => write!(f, "Worker '{}' is not running", name),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Supervisor {
+    workers: Mutex<BTreeMap<String, Worker>>,
+}
+
+/// The supervisor of this process's workers.
+pub fn shared() -> &'static Supervisor {
+    static SUPERVISOR: OnceLock<Supervisor> = OnceLock::new();
+    SUPERVISOR.get_or_init(Supervisor::default)
+}
+
+impl Supervisor {
+    /// Starts `name` now and keeps `factory` to start it again later.
+    pub fn spawn<F, Fut>(&self, name: &str, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        // Every log line of the worker carries its name.
+        let span = info_span!(telemetry::WORKER_SPAN, worker = name);
+        let mut worker = Worker {
+            factory: Box::new(move || Box::pin(factory().instrument(span.clone()))),
+            handle: None,
+            since: Utc::now(),
+            starts: 0,
+            poll_interval: None,
+        };
+        worker.launch();
+        if let Some(mut previous) = self.lock().insert(name.to_string(), worker) {
+            if let Some(handle) = previous.handle.take() {
+                handle.abort();
+            }
+        }
+    }
+
+    /// Records the pause `name` now takes between passes; ignored for unknown workers.
+    pub fn report_poll_interval(&self, name: &str, interval: Duration) {
+        if let Some(worker) = self.lock().get_mut(name) {
+            worker.poll_interval = Some(interval);
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        self.lock().iter().map(|(name, worker)| worker.status(name)).collect()
+    }
+
+    pub fn stop(&self, name: &str) -> Result<WorkerStatus, SupervisorError> {
+        self.with_worker(name, |worker| {
+            match worker.handle.take() {
+                Some(handle) if !handle.is_finished() => handle.abort(),
+                _ => return Err(SupervisorError::NotRunning(name.to_string())),
+            }
+            worker.since = Utc::now();
+            info!("⏹️ Worker '{}' stopped", name);
+            Ok(())
+        })
+    }
+
+    /// Starts a stopped worker; one whose task ended on its own counts as stopped.
+    pub fn start(&self, name: &str) -> Result<WorkerStatus, SupervisorError> {
+        self.with_worker(name, |worker| {
+            if worker.handle.as_ref().is_some_and(|h| !h.is_finished()) {
+                return Err(SupervisorError::AlreadyRunning(name.to_string()));
+            }
+            worker.launch();
+            info!("▶️ Worker '{}' started", name);
+            Ok(())
+        })
+    }
+
+    pub fn restart(&self, name: &str) -> Result<WorkerStatus, SupervisorError> {
+        self.with_worker(name, |worker| {
+            if let Some(handle) = worker.handle.take() {
+                handle.abort();
+            }
+            worker.launch();
+            info!("🔄 Worker '{}' restarted", name);
+            Ok(())
+        })
+    }
+
+    fn with_worker(
+        &self,
+        name: &str,
+        action: impl FnOnce(&mut Worker) -> Result<(), SupervisorError>,
+    ) -> Result<WorkerStatus, SupervisorError> {
+        let mut workers = self.lock();
+        let worker = workers.get_mut(name).ok_or_else(|| SupervisorError::UnknownWorker(name.to_string()))?;
+        action(worker)?;
+        Ok(worker.status(name))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Worker>> {
+        self.workers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn workers_stop_start_and_restart() {
+        let supervisor = Supervisor::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        supervisor.spawn("poller", move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                std::future::pending::<()>().await;
+            }
+        });
+        tokio::task::yield_now().await;
+
+        let state = |s: Result<WorkerStatus, SupervisorError>| s.map(|s| (s.state, s.starts));
+        assert_eq!(state(supervisor.start("poller")), Err(SupervisorError::AlreadyRunning("poller".into())));
+        assert_eq!(state(supervisor.stop("poller")), Ok((WorkerState::Stopped, 1)));
+        assert_eq!(state(supervisor.stop("poller")), Err(SupervisorError::NotRunning("poller".into())));
+        assert_eq!(state(supervisor.start("poller")), Ok((WorkerState::Running, 2)));
+        tokio::task::yield_now().await;
+        assert_eq!(state(supervisor.restart("poller")), Ok((WorkerState::Running, 3)));
+        assert_eq!(state(supervisor.restart("nope")), Err(SupervisorError::UnknownWorker("nope".into())));
+
+        supervisor.report_poll_interval("poller", Duration::from_secs(20));
+        assert_eq!(supervisor.statuses()[0].poll_interval_secs, Some(20));
+
+        tokio::task::yield_now().await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(supervisor.statuses().len(), 1);
+    }
+}
@@ -1,139 +1,679 @@
+mod commands;
+mod intake;
+mod outbound;
+
 use reqwest::Client;
+use serde::Deserialize;
+use std::sync::Arc;
 use serde_json::{json, Value};
 use tokio::time::{sleep, Duration};
-use tracing::{info, warn, error};
-use tokio::sync::mpsc;
-use crate::notifications::Notification;
+use tracing::{error, info, info_span, warn, Instrument};
+use tokio::sync::{mpsc, OwnedMutexGuard};
+use futures_util::future::BoxFuture;
+use crate::notifications::{ChannelRenderer, Message, Notification, NotificationBody, NotificationSink, TelegramMarkdownV2};
+
+use crate::approvals::{self, Decision, DecisionOutcome};
+use crate::dependencies;
+use crate::health::Health;
+use crate::llm::Llm;
+use crate::provenance::Source;
+use crate::roles::{Role, TelegramRoles};
+use crate::sparql;
+use crate::state::{self, TaskDraft};
+use crate::store::Synapse;
+use crate::tasks::{active_tasks, TaskSummary};
+use super::agency::{self, CancelOutcome};
+use super::ops::{self, state_emoji, status_message};
+use super::supervisor::{shared_receiver, SharedReceiver};
+
+use commands::Command;
+
+/// How bot updates reach swarmd.
+#[derive(Clone)]
+pub enum UpdateSource {
+    /// `getUpdates` polling every few seconds.
+    Polling,
+    /// Telegram pushes updates to `/api/v1/webhooks/telegram`; the gateway forwards them here.
+    Webhook { url: String, secret: String, updates: SharedReceiver<Value> },
+}
 
-use crate::synapse::SynapseClient;
+/// Gateway half of webhook mode: checks Telegram's secret header and hands updates to the worker.
+#[derive(Clone)]
+pub struct TelegramWebhook {
+    pub secret: String,
+    pub updates: mpsc::Sender<Value>,
+}
+
+pub const WEBHOOK_PATH: &str = "/api/v1/webhooks/telegram";
+
+/// Pairs the gateway endpoint with the worker for webhook mode. `public_url` is the
+/// externally reachable base URL of the gateway. Without an explicit secret a random one
+/// is used; it is registered with Telegram on every start anyway.
+pub fn webhook(public_url: &str, secret: Option<String>) -> (TelegramWebhook, UpdateSource) {
+    let secret = secret.unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    let (tx, rx) = mpsc::channel(100);
+    let url = format!("{}{}", public_url.trim_end_matches('/'), WEBHOOK_PATH);
+    (
+        TelegramWebhook { secret: secret.clone(), updates: tx },
+        UpdateSource::Webhook { url, secret, updates: shared_receiver(rx) },
+    )
+}
 
 pub async fn poll_telegram(
     token: String,
-    synapse: SynapseClient,
+    synapse: Synapse,
     client: Client,
-    auth_chat_id: Option<String>,
-    mut rx: mpsc::Receiver<Notification>
+    roles: TelegramRoles,
+    source: UpdateSource,
+    llm: Option<Llm>,
+    health: Arc<Health>,
 ) {
-    info!("🤖 Telegram Poller & Notifier Started...");
-    let mut last_update_id = 0;
+    let mut last_update_id = load_offset(&synapse).await;
     let base_url = format!("https://api.telegram.org/bot{}", token);
 
+    let mut pushed = match source {
+        UpdateSource::Polling => {
+            info!("🤖 Telegram Poller Started...");
+            // getUpdates is refused while a webhook is registered, e.g. after switching modes.
+            if let Err(e) = client.post(format!("{}/deleteWebhook", base_url)).send().await {
+                warn!("⚠️ Could not clear Telegram webhook: {}", e);
+            }
+            None
+        }
+        UpdateSource::Webhook { url, secret, updates } => {
+            info!("🤖 Telegram Webhook Started ({})...", url);
+            if let Err(e) = set_webhook(&base_url, &url, &secret, &client).await {
+                error!("Failed to register Telegram webhook: {}", e);
+            }
+            Some(updates.lock_owned().await)
+        }
+    };
+
     loop {
         tokio::select! {
-            // Priority 1: Handle incoming notifications to broadcast
-            Some(notification) = rx.recv() => {
-                if let Some(target_chat) = &auth_chat_id {
-                    let text = match notification {
-                        Notification::Trace(msg) => format!("👁️ [TRACE] {}", msg),
-                        Notification::Alert(msg) => format!("🚨 [ALERT] {}", msg),
-                    };
-                    if let Err(e) = send_message(&base_url, target_chat, &text, &client).await {
-                        error!("Failed to send Telegram notification: {}", e);
-                    }
-                } else {
-                    warn!("Received notification but no Telegram auth_chat_id configured.");
-                }
+            // Priority 1: Updates pushed through the gateway webhook
+            Some(update) = next_pushed(&mut pushed) => {
+                handle_update(&update, &base_url, &synapse, &client, &roles, llm.as_ref())
+                    .instrument(info_span!("telegram.update"))
+                    .await;
             }
 
-            // Priority 2: Poll for user commands
+            // Priority 2: Poll for user commands (heartbeat only in webhook mode)
             _ = sleep(Duration::from_secs(3)) => {
-                let url = format!("{}/getUpdates?offset={}&timeout=10", base_url, last_update_id + 1);
-                match client.get(&url).send().await {
-                    Ok(res) => {
-                        if let Ok(val) = res.json::<Value>().await {
-                            if let Some(updates) = val.get("result").and_then(|r| r.as_array()) {
-                                for update in updates {
-                                    let update_id = update.get("update_id").and_then(|id| id.as_i64()).unwrap_or(0);
-                                    if update_id > last_update_id {
-                                        last_update_id = update_id;
+                health.beat("telegram");
+                if pushed.is_some() {
+                    continue;
+                }
+                async {
+                    let url = format!("{}/getUpdates?offset={}&timeout=10", base_url, last_update_id + 1);
+                    match client.get(&url).send().await {
+                        Ok(res) => {
+                            if let Ok(val) = res.json::<Value>().await {
+                                if let Some(updates) = val.get("result").and_then(|r| r.as_array()) {
+                                    // Persist the new offset before acting, so a crash mid-batch can never
+                                    // replay a command such as /stop_all after a restart.
+                                    let newest = updates.iter().filter_map(|u| u.get("update_id").and_then(|id| id.as_i64())).max();
+                                    if let Some(newest) = newest.filter(|id| *id > last_update_id) {
+                                        last_update_id = newest;
+                                        if let Err(e) = save_offset(newest) {
+                                            warn!("⚠️ Could not persist Telegram update offset: {}", e);
+                                        }
                                     }
-
-                                    if let Some(message) = update.get("message") {
-                                        let msg_chat_id = message.get("chat").and_then(|c| c.get("id")).and_then(|id| id.as_i64()).unwrap_or(0);
-                                        let text = message.get("text").and_then(|t| t.as_str()).unwrap_or("");
-
-                                        handle_command(msg_chat_id, text, &base_url, &synapse, &client, &auth_chat_id).await;
+                                    for update in updates {
+                                        handle_update(update, &base_url, &synapse, &client, &roles, llm.as_ref()).await;
                                     }
                                 }
                             }
                         }
-                    }
-                    Err(e) => {
-                        warn!("⚠️ Telegram API error during polling: {}", e);
+                        Err(e) => {
+                            warn!("⚠️ Telegram API error during polling: {}", e);
+                        }
                     }
                 }
+                .instrument(info_span!("telegram.poll"))
+                .await;
             }
         }
     }
 }
 
-async fn send_message(base_url: &str, chat_id: &str, text: &str, client: &Client) -> Result<(), reqwest::Error> {
-    let url = format!("{}/sendMessage", base_url);
-    client.post(&url)
+/// Where releases before the state store kept the offset; read once to carry it over.
+const UPDATE_OFFSET: &str = "http://swarm.os/ontology/telegramUpdateOffset";
+const OFFSET_NAME: &str = "telegram";
+
+/// Highest update id already handled, so a restart does not replay old commands.
+async fn load_offset(synapse: &Synapse) -> i64 {
+    let offset = match state::shared().offset(OFFSET_NAME) {
+        Ok(Some(offset)) => offset,
+        Ok(None) => load_legacy_offset(synapse).await,
+        Err(e) => {
+            warn!("⚠️ Could not read the Telegram update offset from the state store: {}", e);
+            load_legacy_offset(synapse).await
+        }
+    };
+    if offset > 0 {
+        info!("📨 Resuming Telegram updates after id {}", offset);
+    }
+    offset
+}
+
+async fn load_legacy_offset(synapse: &Synapse) -> i64 {
+    let query = format!("SELECT ?offset WHERE {{ <http://nist.gov/caisi/SystemControl> <{UPDATE_OFFSET}> ?offset }}");
+    match synapse.query_as::<OffsetRow>(&query).await {
+        Ok(rows) => max_offset(&rows),
+        Err(e) => {
+            warn!("⚠️ Could not restore Telegram update offset: {}", e);
+            0
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OffsetRow {
+    #[serde(default, deserialize_with = "sparql::parsed")]
+    offset: Option<i64>,
+}
+
+/// Offsets were appended, never replaced, so the current one is the largest.
+fn max_offset(rows: &[OffsetRow]) -> i64 {
+    rows.iter().filter_map(|row| row.offset).max().unwrap_or(0)
+}
+
+fn save_offset(offset: i64) -> rusqlite::Result<()> {
+    state::shared().set_offset(OFFSET_NAME, offset)
+}
+
+/// Delivers notifications to the authorized chat, with Approve / Reject buttons on
+/// approval requests. Messages leave through an [`outbound::OutboundQueue`], so delivery only
+/// fails here when the queue is full; send failures are logged by the queue.
+pub struct TelegramSink {
+    chat_id: String,
+    queue: outbound::OutboundQueue,
+}
+
+impl TelegramSink {
+    /// Starts the sink's outbound queue, so call it inside the runtime.
+    pub fn new(token: &str, chat_id: String, client: Client) -> Self {
+        let queue = outbound::OutboundQueue::spawn(format!("https://api.telegram.org/bot{}", token), client);
+        Self { chat_id, queue }
+    }
+}
+
+impl NotificationSink for TelegramSink {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn deliver<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let keyboard = match &notification.body {
+                NotificationBody::Approval(req) => Some(approval_keyboard(&req.task_id)),
+                _ => None,
+            };
+            self.queue.push(&self.chat_id, notification, keyboard)
+        })
+    }
+}
+
+/// Never resolves in polling mode, so the webhook branch of the select stays idle.
+async fn next_pushed(pushed: &mut Option<OwnedMutexGuard<mpsc::Receiver<Value>>>) -> Option<Value> {
+    match pushed {
+        Some(updates) => updates.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn set_webhook(base_url: &str, url: &str, secret: &str, client: &Client) -> anyhow::Result<()> {
+    let res: Value = client
+        .post(format!("{}/setWebhook", base_url))
         .json(&json!({
-            "chat_id": chat_id,
-            "text": text,
-            "parse_mode": "Markdown"
+            "url": url,
+            "secret_token": secret,
+            "allowed_updates": ["message", "callback_query"]
         }))
         .send()
+        .await?
+        .json()
         .await?;
+    if res.get("ok").and_then(|ok| ok.as_bool()) != Some(true) {
+        anyhow::bail!("setWebhook rejected: {}", res);
+    }
+    Ok(())
+}
+
+async fn handle_update(update: &Value, base_url: &str, synapse: &Synapse, client: &Client, roles: &TelegramRoles, llm: Option<&Llm>) {
+    if let Some(message) = update.get("message") {
+        let msg_chat_id = message.get("chat").and_then(|c| c.get("id")).and_then(|id| id.as_i64()).unwrap_or(0);
+        let text = message.get("text").and_then(|t| t.as_str()).unwrap_or("");
+        let role = roles.resolve(&msg_chat_id.to_string(), sender_id(message));
+
+        let is_task = role >= Role::Operator && !text.trim().is_empty() && !intake::is_greeting(text);
+        if is_task && commands::parse_batch(text).is_empty() {
+            propose_task(msg_chat_id, &sender(message), text, base_url, client, llm).await;
+        } else {
+            handle_command(msg_chat_id, &sender(message), role, text, base_url, synapse, client).await;
+        }
+    }
+
+    if let Some(callback) = update.get("callback_query") {
+        handle_callback(callback, base_url, synapse, client, roles).await;
+    }
+}
+
+/// Username of whoever sent a message or pressed a button, or their id when they have none.
+fn sender(update: &Value) -> String {
+    update
+        .get("from")
+        .and_then(|f| f.get("username").or_else(|| f.get("id")))
+        .map(|v| v.as_str().map(String::from).unwrap_or_else(|| v.to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Numeric id of whoever sent a message or pressed a button, which `TELEGRAM_USER_ROLES` maps.
+fn sender_id(update: &Value) -> Option<i64> {
+    update.get("from").and_then(|f| f.get("id")).and_then(|id| id.as_i64())
+}
+
+fn forbidden(required: Role) -> String {
+    format!("⛔ Requires the {} role.", required.as_str())
+}
+
+async fn send_message(base_url: &str, chat_id: &str, msg: &Message, client: &Client) -> Result<(), reqwest::Error> {
+    send(base_url, chat_id, msg, None, client).await
+}
+
+async fn send(base_url: &str, chat_id: &str, msg: &Message, keyboard: Option<Value>, client: &Client) -> Result<(), reqwest::Error> {
+    let url = format!("{}/sendMessage", base_url);
+    client.post(&url).json(&message_body(chat_id, msg, keyboard)).send().await?;
+    Ok(())
+}
+
+/// `sendMessage` body for `msg`, rendered as MarkdownV2.
+fn message_body(chat_id: &str, msg: &Message, keyboard: Option<Value>) -> Value {
+    let mut body = json!({
+        "chat_id": chat_id,
+        "text": TelegramMarkdownV2.render_message(msg),
+        "parse_mode": TelegramMarkdownV2::PARSE_MODE
+    });
+    if let Some(keyboard) = keyboard {
+        body["reply_markup"] = keyboard;
+    }
+    body
+}
+
+async fn edit_message(base_url: &str, chat_id: &str, message_id: i64, msg: &Message, keyboard: Option<Value>, client: &Client) -> Result<(), reqwest::Error> {
+    let mut body = json!({
+        "chat_id": chat_id,
+        "message_id": message_id,
+        "text": TelegramMarkdownV2.render_message(msg),
+        "parse_mode": TelegramMarkdownV2::PARSE_MODE
+    });
+    if let Some(keyboard) = keyboard {
+        body["reply_markup"] = keyboard;
+    }
+    client.post(format!("{}/editMessageText", base_url)).json(&body).send().await?;
     Ok(())
 }
 
-async fn handle_command(chat_id: i64, text: &str, base_url: &str, synapse: &SynapseClient, client: &Client, authorized_chat_id: &Option<String>) {
+const TASKS_PAGE_SIZE: usize = 5;
+const TASKS_CALLBACK: &str = "tasks|";
+
+/// One page of the `/tasks` listing plus Prev/Next buttons when there is more to show.
+fn tasks_page(tasks: &[TaskSummary], page: usize, state: Option<&str>) -> (Message, Option<Value>) {
+    if tasks.is_empty() {
+        return (Message::new().text("📭 ").strong("No active tasks"), None);
+    }
+    // The state filter rides along in the callback so paging keeps it.
+    let callback = |page: usize| match state {
+        Some(state) => format!("{}{}|{}", TASKS_CALLBACK, page, state),
+        None => format!("{}{}", TASKS_CALLBACK, page),
+    };
+
+    let pages = tasks.len().div_ceil(TASKS_PAGE_SIZE);
+    let page = page.min(pages - 1);
+    let mut msg = Message::new()
+        .text("📋 ")
+        .strong(format!("Active tasks ({})", tasks.len()))
+        .text(format!(" — page {}/{}\n", page + 1, pages));
+
+    for task in tasks.iter().skip(page * TASKS_PAGE_SIZE).take(TASKS_PAGE_SIZE) {
+        let agent = task
+            .agent
+            .as_deref()
+            .map(|a| a.rsplit('/').next().unwrap_or(a))
+            .unwrap_or("unassigned");
+        msg = msg
+            .text(format!("\n{} ", state_emoji(&task.state)))
+            .strong(&task.title)
+            .text(format!(" · {} · {}", task.state, agent));
+    }
+
+    let mut buttons = Vec::new();
+    if page > 0 {
+        buttons.push(json!({ "text": "◀️ Prev", "callback_data": callback(page - 1) }));
+    }
+    if page + 1 < pages {
+        buttons.push(json!({ "text": "Next ▶️", "callback_data": callback(page + 1) }));
+    }
+    let keyboard = (!buttons.is_empty()).then(|| json!({ "inline_keyboard": [buttons] }));
+    (msg, keyboard)
+}
+
+fn parse_tasks_callback(data: &str) -> Option<(usize, Option<&str>)> {
+    let mut parts = data.splitn(2, '|');
+    let page = parts.next()?.parse().ok()?;
+    Some((page, parts.next().filter(|s| !s.is_empty())))
+}
+
+/// Telegram caps `callback_data` at 64 bytes, so the swarm IRI prefix is abbreviated.
+const IRI_PREFIX: &str = "http://swarm.os/";
+
+fn callback_data(decision: Decision, task_id: &str) -> String {
+    let verb = match decision {
+        Decision::Approve => "approve",
+        Decision::Reject => "reject",
+    };
+    match task_id.strip_prefix(IRI_PREFIX) {
+        Some(rest) => format!("{}|~{}", verb, rest),
+        None => format!("{}|{}", verb, task_id),
+    }
+}
+
+fn parse_callback_data(data: &str) -> Option<(Decision, String)> {
+    let (verb, task) = data.split_once('|')?;
+    let decision = match verb {
+        "approve" => Decision::Approve,
+        "reject" => Decision::Reject,
+        _ => return None,
+    };
+    let task_id = match task.strip_prefix('~') {
+        Some(rest) => format!("{}{}", IRI_PREFIX, rest),
+        None => task.to_string(),
+    };
+    Some((decision, task_id))
+}
+
+fn approval_keyboard(task_id: &str) -> Value {
+    json!({
+        "inline_keyboard": [[
+            { "text": "✅ Approve", "callback_data": callback_data(Decision::Approve, task_id) },
+            { "text": "❌ Reject", "callback_data": callback_data(Decision::Reject, task_id) }
+        ]]
+    })
+}
+
+async fn handle_callback(callback: &Value, base_url: &str, synapse: &Synapse, client: &Client, roles: &TelegramRoles) {
+    let callback_id = callback.get("id").and_then(|v| v.as_str()).unwrap_or("");
+    let data = callback.get("data").and_then(|v| v.as_str()).unwrap_or("");
+    let message = callback.get("message");
+    let chat_id = message
+        .and_then(|m| m.get("chat"))
+        .and_then(|c| c.get("id"))
+        .and_then(|id| id.as_i64())
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+    let message_id = message.and_then(|m| m.get("message_id")).and_then(|id| id.as_i64());
+    let user = sender(callback);
+
+    let role = roles.resolve(&chat_id, sender_id(callback));
+    let answer = if let Some((page, state)) = data.strip_prefix(TASKS_CALLBACK).and_then(parse_tasks_callback) {
+        match (load_tasks(synapse, state).await, message_id) {
+            (Ok(tasks), Some(message_id)) => {
+                let (msg, keyboard) = tasks_page(&tasks, page, state);
+                let _ = edit_message(base_url, &chat_id, message_id, &msg, keyboard, client).await;
+                format!("Page {}", page + 1)
+            }
+            (Err(e), _) => format!("❌ Error querying Synapse: {}", e),
+            (_, None) => String::new(),
+        }
+    } else if intake::parse_callback(data).is_some() && role < Role::Operator {
+        forbidden(Role::Operator)
+    } else if let Some((choice, draft_id)) = intake::parse_callback(data) {
+        decide_draft(choice, draft_id, &chat_id, message_id, &user, base_url, synapse, client).await
+    } else if parse_callback_data(data).is_some() && role < Role::Operator {
+        forbidden(Role::Operator)
+    } else if let Some((decision, task_id)) = parse_callback_data(data) {
+        match approvals::decide(synapse, Source::Telegram, &task_id, decision, &format!("telegram:{}", user)).await {
+            Ok(DecisionOutcome::Recorded { .. }) => {
+                info!("🛡️ Task {} {} via Telegram by {}", task_id, decision.state(), user);
+                if let Some(message_id) = message_id {
+                    let outcome = Message::new()
+                        .text("🛡️ ")
+                        .code(&task_id)
+                        .text(" ")
+                        .strong(decision.state())
+                        .text(format!(" by {}", user));
+                    let _ = edit_message(base_url, &chat_id, message_id, &outcome, None, client).await;
+                }
+                format!("Task {}", decision.state().to_lowercase())
+            }
+            Ok(DecisionOutcome::UnknownTask) => "❓ Unknown task".to_string(),
+            Ok(DecisionOutcome::NotAwaiting) => "Already decided".to_string(),
+            Err(e) => {
+                error!("Failed to record approval for {}: {}", task_id, e);
+                "❌ Could not record the decision".to_string()
+            }
+        }
+    } else {
+        "Unknown action".to_string()
+    };
+
+    // Always answer, otherwise the button keeps spinning on the client.
+    let _ = client.post(format!("{}/answerCallbackQuery", base_url))
+        .json(&json!({ "callback_query_id": callback_id, "text": answer }))
+        .send()
+        .await;
+}
+
+async fn handle_command(chat_id: i64, user: &str, role: Role, text: &str, base_url: &str, synapse: &Synapse, client: &Client) {
     let chat_id_str = chat_id.to_string();
-    let is_authorized = authorized_chat_id.as_ref().map(|id| id == &chat_id_str).unwrap_or(true);
 
-    match text {
-        "/start" => {
-            let _ = send_message(base_url, &chat_id_str, "🤖 *Swarm Orchestrator Online*\nI am monitoring Trello and Synapse.", client).await;
+    let batch = commands::parse_batch(text);
+    if batch.is_empty() {
+        if intake::is_greeting(text) {
+            let _ = send_message(base_url, &chat_id_str, &"👋 Hello! I am the Swarm Orchestrator. Use /help to see what I can do.".into(), client).await;
+        }
+        return;
+    }
+
+    for cmd in batch {
+        run_command(&cmd, &chat_id_str, user, role, base_url, synapse, client).await;
+    }
+}
+
+/// Drafts a task from a free-text message and asks for confirmation.
+async fn propose_task(chat_id: i64, user: &str, text: &str, base_url: &str, client: &Client, llm: Option<&Llm>) {
+    let chat_id = chat_id.to_string();
+    let (title, description) = intake::draft(llm, text).await;
+    let draft = TaskDraft { id: uuid::Uuid::new_v4().simple().to_string(), chat: chat_id.clone(), author: user.to_string(), title, description };
+    if let Err(e) = state::shared().save_draft(&draft) {
+        error!("Could not keep a task draft: {}", e);
+        let _ = send_message(base_url, &chat_id, &"❌ Could not draft the task.".into(), client).await;
+        return;
+    }
+    let _ = send(base_url, &chat_id, &intake::proposal(&draft), Some(intake::keyboard(&draft.id)), client).await;
+}
+
+/// Creates or discards a draft from its buttons; returns the answer to the button press.
+#[allow(clippy::too_many_arguments)]
+async fn decide_draft(
+    choice: intake::Choice,
+    draft_id: &str,
+    chat_id: &str,
+    message_id: Option<i64>,
+    user: &str,
+    base_url: &str,
+    synapse: &Synapse,
+    client: &Client,
+) -> String {
+    let draft = match state::shared().take_draft(draft_id, chat_id) {
+        Ok(Some(draft)) => draft,
+        Ok(None) => return "This draft expired or was already handled".to_string(),
+        Err(e) => {
+            error!("Could not read task draft {}: {}", draft_id, e);
+            return "❌ Could not read the draft".to_string();
+        }
+    };
+    let (outcome, answer) = match choice {
+        intake::Choice::Discard => (Message::new().text("✖️ ").strong(&draft.title).text(format!(" discarded by {}", user)), "Discarded".to_string()),
+        intake::Choice::Create => match intake::create(synapse, &draft).await {
+            Ok(task_id) => {
+                info!("📝 Task '{}' queued via Telegram by {} (drafted by {})", draft.title, user, draft.author);
+                (Message::new().text("📝 ").strong(&draft.title).text(" queued as ").code(&task_id), "Task created".to_string())
+            }
+            Err(e) => {
+                error!("Failed to queue drafted task '{}': {}", draft.title, e);
+                // Keep the draft, so the button can be pressed again.
+                let _ = state::shared().save_draft(&draft);
+                return "❌ Could not create the task".to_string();
+            }
+        },
+    };
+    if let Some(message_id) = message_id {
+        let _ = edit_message(base_url, chat_id, message_id, &outcome, None, client).await;
+    }
+    answer
+}
+
+async fn run_command(cmd: &Command, chat_id: &str, user: &str, role: Role, base_url: &str, synapse: &Synapse, client: &Client) {
+    if let Some(spec) = commands::spec(&cmd.name).filter(|spec| role < spec.role) {
+        let _ = send_message(base_url, chat_id, &forbidden(spec.role).into(), client).await;
+        return;
+    }
+
+    match cmd.name.as_str() {
+        "start" => {
+            let _ = send_message(base_url, chat_id, &Message::new().text("🤖 ").strong("Swarm Orchestrator Online").text("\nI am monitoring Trello and Synapse."), client).await;
         },
-        "/status" => {
-            let status = match synapse.query("SELECT ?s WHERE { <http://nist.gov/caisi/SystemControl> <http://nist.gov/caisi/operationalStatus> ?s }").await {
-                Ok(res) => res,
-                Err(_) => "Error querying Synapse".to_string(),
+        "help" => {
+            let _ = send_message(base_url, chat_id, &commands::help(cmd.args.first().map(String::as_str)), client).await;
+        },
+        "status" => {
+            let verbose = cmd.flag("verbose") || cmd.args.iter().any(|a| a.eq_ignore_ascii_case("verbose"));
+            let _ = send_message(base_url, chat_id, &status_message(synapse, verbose, role).await, client).await;
+        },
+        "tasks" => {
+            let filter = cmd.args.first().map(|s| s.to_uppercase());
+            let page = cmd.flag_value("page").and_then(|p| p.parse::<usize>().ok()).unwrap_or(1).saturating_sub(1);
+            let (msg, keyboard) = match load_tasks(synapse, filter.as_deref()).await {
+                Ok(tasks) => tasks_page(&tasks, page, filter.as_deref()),
+                Err(e) => (format!("❌ Error querying Synapse: {}", e).into(), None),
             };
-            let _ = send_message(base_url, &chat_id_str, &format!("📊 *System Status*\n{}", status), client).await;
+            let _ = send(base_url, chat_id, &msg, keyboard, client).await;
         },
-        "/stop_all" => {
-            if !is_authorized {
-                let _ = send_message(base_url, &chat_id_str, "⛔ Unauthorized.", client).await;
-                return;
-            }
-            match perform_status_change("HALTED", synapse).await {
-                Ok(_) => { let _ = send_message(base_url, &chat_id_str, "🛑 *SYSTEM HALTED* via Emergency Switch.", client).await; },
-                Err(e) => { let _ = send_message(base_url, &chat_id_str, &format!("❌ Failed to halt: {}", e), client).await; }
-            }
+        "cancel" => {
+            let msg = match cmd.args.first() {
+                Some(task) => cancel_task(synapse, &dependencies::task_iri(task), chat_id).await,
+                None => commands::help(Some("cancel")),
+            };
+            let _ = send_message(base_url, chat_id, &msg, client).await;
         },
-        "/resume" => {
-            if !is_authorized {
-                let _ = send_message(base_url, &chat_id_str, "⛔ Unauthorized.", client).await;
-                return;
-            }
-            match perform_status_change("OPERATIONAL", synapse).await {
-                Ok(_) => { let _ = send_message(base_url, &chat_id_str, "✅ *SYSTEM RESUMED* to Operational status.", client).await; },
-                Err(e) => { let _ = send_message(base_url, &chat_id_str, &format!("❌ Failed to resume: {}", e), client).await; }
+        "stop_all" => {
+            match ops::set_operational_status("HALTED", Source::Telegram, &format!("telegram:{}", user), synapse).await {
+                Ok(_) => { let _ = send_message(base_url, chat_id, &Message::new().text("🛑 ").strong("SYSTEM HALTED").text(" via Emergency Switch."), client).await; },
+                Err(e) => { let _ = send_message(base_url, chat_id, &format!("❌ Failed to halt: {}", e).into(), client).await; }
             }
         },
-        _ => {
-            if text.to_lowercase().contains("hi") || text.to_lowercase().contains("hola") {
-                let _ = send_message(base_url, &chat_id_str, "👋 Hello! I am the Swarm Orchestrator. Use /status to check on things.", client).await;
+        "resume" => {
+            match ops::set_operational_status("OPERATIONAL", Source::Telegram, &format!("telegram:{}", user), synapse).await {
+                Ok(_) => { let _ = send_message(base_url, chat_id, &Message::new().text("✅ ").strong("SYSTEM RESUMED").text(" to Operational status."), client).await; },
+                Err(e) => { let _ = send_message(base_url, chat_id, &format!("❌ Failed to resume: {}", e).into(), client).await; }
             }
+        },
+        other => {
+            let msg = Message::new().text("❓ Unknown command ").code(format!("/{}", other));
+            let msg = match commands::suggest(other) {
+                Some(name) => msg.text(". Did you mean ").code(format!("/{}", name)).text("?"),
+                None => msg.text(". Send /help for the list of commands."),
+            };
+            let _ = send_message(base_url, chat_id, &msg, client).await;
         }
     }
 }
 
-async fn perform_status_change(status: &str, synapse: &SynapseClient) -> anyhow::Result<()> {
-    let event_id = format!("http://nist.gov/caisi/event/status/{}", uuid::Uuid::new_v4());
-    let timestamp = chrono::Utc::now().to_rfc3339();
-    
-    synapse.ingest(vec![
-        (&event_id, "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://nist.gov/caisi/StatusChangeEvent"),
-        (&event_id, "http://nist.gov/caisi/newStatus", &format!("\"{}\"", status)),
-        (&event_id, "http://www.w3.org/ns/prov#generatedAtTime", &format!("\"{}\"", timestamp)),
-        ("http://nist.gov/caisi/SystemControl", "http://nist.gov/caisi/hasStatusHistory", &event_id),
-        ("http://nist.gov/caisi/SystemControl", "http://nist.gov/caisi/operationalStatus", &format!("\"{}\"", status)),
-    ]).await?;
-    
-    Ok(())
+async fn cancel_task(synapse: &Synapse, task_id: &str, chat_id: &str) -> Message {
+    match agency::cancel(synapse, Source::Telegram, task_id, &format!("telegram:{}", chat_id)).await {
+        Ok(CancelOutcome::Stopping { title }) => {
+            Message::new().text("🛑 ").strong(title).text(" cancelled; its run is being stopped and its agent freed.")
+        }
+        Ok(CancelOutcome::Cancelled { title }) => Message::new().text("🛑 ").strong(title).text(" cancelled."),
+        Ok(CancelOutcome::UnknownTask) => Message::new().text("❓ Unknown task ").code(task_id),
+        Ok(CancelOutcome::Finished { state }) => Message::new().text("Task ").code(task_id).text(format!(" already finished as {}.", state)),
+        Err(e) => format!("❌ Failed to cancel: {}", e).into(),
+    }
+}
+
+async fn load_tasks(synapse: &Synapse, state: Option<&str>) -> anyhow::Result<Vec<TaskSummary>> {
+    let mut tasks = active_tasks(synapse).await?;
+    if let Some(state) = state {
+        tasks.retain(|t| t.state.eq_ignore_ascii_case(state));
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::PlainText;
+    use crate::tasks;
+
+    #[test]
+    fn callback_data_round_trips_and_fits_telegram_limit() {
+        let task = "http://swarm.os/trello/card/65f1c2a9b3e4d5f6a7b8c9d0";
+        let data = callback_data(Decision::Reject, task);
+        assert!(data.len() <= 64);
+        assert_eq!(parse_callback_data(&data), Some((Decision::Reject, task.to_string())));
+        assert_eq!(parse_callback_data("delete|x"), None);
+    }
+
+    #[test]
+    fn tasks_are_paginated_with_navigation_buttons() {
+        let tasks: Vec<TaskSummary> = (0..12)
+            .map(|i| TaskSummary {
+                id: format!("http://swarm.os/task/{i}"),
+                title: format!("Task {i}"),
+                state: "PROCESSING".to_string(),
+                agent: Some("http://swarm.os/agent/PM_1".to_string()),
+            })
+            .collect();
+
+        let (first, keyboard) = tasks_page(&tasks, 0, None);
+        assert!(PlainText.render_message(&first).contains("page 1/3"));
+        assert_eq!(keyboard.unwrap()["inline_keyboard"][0][0]["callback_data"], "tasks|1");
+
+        let (last, keyboard) = tasks_page(&tasks, 7, Some("PROCESSING"));
+        let text = PlainText.render_message(&last);
+        assert!(text.contains("page 3/3") && text.contains("Task 11 · PROCESSING · PM_1"));
+        let buttons = keyboard.unwrap()["inline_keyboard"][0].clone();
+        assert_eq!(buttons.as_array().unwrap().len(), 1);
+        assert_eq!(buttons[0]["callback_data"], "tasks|1|PROCESSING");
+        assert_eq!(parse_tasks_callback("1|PROCESSING"), Some((1, Some("PROCESSING"))));
+    }
+
+    #[test]
+    fn webhook_mode_registers_gateway_path_with_secret() {
+        let (hook, source) = webhook("https://swarm.example.com/", Some("s3cret".into()));
+        assert_eq!(hook.secret, "s3cret");
+        match source {
+            UpdateSource::Webhook { url, secret, .. } => {
+                assert_eq!(url, "https://swarm.example.com/api/v1/webhooks/telegram");
+                assert_eq!(secret, "s3cret");
+            }
+            UpdateSource::Polling => panic!("expected webhook mode"),
+        }
+        let (generated, _) = webhook("https://swarm.example.com", None);
+        assert_eq!(generated.secret.len(), 32);
+    }
+
+    #[test]
+    fn restored_offset_is_the_largest_persisted_one() {
+        let rows: Vec<OffsetRow> = crate::sparql::rows(&json!([
+            {"offset": tasks::integer_literal(41)},
+            {"?offset": tasks::integer_literal(57)},
+            {"offset": "\"garbage\""},
+        ]).to_string())
+        .unwrap();
+        assert_eq!(max_offset(&rows), 57);
+        assert_eq!(max_offset(&[]), 0);
+    }
 }
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::notifications::Message;
+use crate::roles::Role;
+
+/// A bot command as typed by the user: `/tasks TODO --page=2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub name: String,
+    pub args: Vec<String>,
+    /// `--flag` maps to `None`, `--key=value` to `Some(value)`.
+    pub flags: HashMap<String, Option<String>>,
+}
+
+impl Command {
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    pub fn flag_value(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).and_then(|v| v.as_deref())
+    }
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub description: &'static str,
+    /// Least role allowed to run it.
+    pub role: Role,
+}
+
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec { name: "start", usage: "/start", description: "Check that the bot is online", role: Role::Viewer },
+    CommandSpec { name: "help", usage: "/help [command]", description: "List commands or show one command's usage", role: Role::Viewer },
+    CommandSpec { name: "status", usage: "/status [verbose]", description: "System status; `verbose` adds spend and queue size", role: Role::Viewer },
+    CommandSpec { name: "tasks", usage: "/tasks [STATE] [--page=N]", description: "Active tasks, optionally filtered by state", role: Role::Viewer },
+    CommandSpec { name: "cancel", usage: "/cancel <task>", description: "Cancel a task by id or IRI, stopping its run if it is running", role: Role::Operator },
+    CommandSpec { name: "stop_all", usage: "/stop_all", description: "Emergency halt of the swarm", role: Role::Admin },
+    CommandSpec { name: "resume", usage: "/resume", description: "Resume operational status after a halt", role: Role::Admin },
+];
+
+pub fn spec(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+/// Every line starting with `/` is a command, so several can be sent in one message.
+pub fn parse_batch(text: &str) -> Vec<Command> {
+    text.lines().filter_map(parse).collect()
+}
+
+pub fn parse(line: &str) -> Option<Command> {
+    let line = line.trim();
+    let rest = line.strip_prefix('/')?;
+    let mut tokens = tokenize(rest).into_iter();
+
+    // In groups Telegram appends the bot name: `/status@swarm_bot`.
+    let head = tokens.next()?;
+    let name = head.split('@').next().unwrap_or(&head).to_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut args = Vec::new();
+    let mut flags = HashMap::new();
+    for token in tokens {
+        if let Some(flag) = token.strip_prefix("--").filter(|f| !f.is_empty()) {
+            match flag.split_once('=') {
+                Some((k, v)) => flags.insert(k.to_lowercase(), Some(v.to_string())),
+                None => flags.insert(flag.to_lowercase(), None),
+            };
+        } else {
+            args.push(token);
+        }
+    }
+
+    Some(Command { name, args, flags })
+}
+
+/// Splits on whitespace, keeping `"quoted phrases"` together.
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+pub fn help(topic: Option<&str>) -> Message {
+    if let Some(spec) = topic.and_then(|t| spec(t.trim_start_matches('/'))) {
+        return Message::new().code(spec.usage).text(format!("\n{}", spec.description));
+    }
+
+    let mut msg = Message::new().text("🤖 ").strong("Commands").text("\n");
+    for spec in COMMANDS {
+        msg = msg.text("\n").code(spec.usage).text(format!(" — {}", spec.description));
+    }
+    msg.text("\n\nSeveral commands can be sent at once, one per line. Operators can also describe a task in plain words to queue it.")
+}
+
+/// Closest known command for a typo, if any is near enough to be a plausible match.
+pub fn suggest(name: &str) -> Option<&'static str> {
+    COMMANDS
+        .iter()
+        .map(|c| (c.name, edit_distance(name, c.name)))
+        .filter(|(candidate, d)| *d <= 2 || candidate.starts_with(name))
+        .min_by_key(|(_, d)| *d)
+        .map(|(candidate, _)| candidate)
+}
+
+/// Optimal string alignment distance: Levenshtein plus adjacent transpositions, which
+/// are the most common typo (`/stauts`).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_args_flags_and_bot_suffix() {
+        let cmd = parse("/Tasks@swarm_bot TODO \"needs review\" --page=2 --verbose").unwrap();
+        assert_eq!(cmd.name, "tasks");
+        assert_eq!(cmd.args, vec!["TODO", "needs review"]);
+        assert_eq!(cmd.flag_value("page"), Some("2"));
+        assert!(cmd.flag("verbose"));
+        assert_eq!(parse("hello there"), None);
+    }
+
+    #[test]
+    fn batches_and_suggestions() {
+        let batch = parse_batch("/status verbose\nignored line\n/tasks");
+        assert_eq!(batch.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["status", "tasks"]);
+        assert_eq!(suggest("stauts"), Some("status"));
+        assert_eq!(suggest("stop"), Some("stop_all"));
+        assert_eq!(suggest("deploy"), None);
+    }
+}
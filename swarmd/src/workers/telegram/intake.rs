@@ -0,0 +1,169 @@
+//! Tasks from plain messages. Free text an operator sends the bot becomes a draft: a title and a
+//! description, read by the native LLM client when one is configured and else taken from the
+//! first line and the rest. The bot shows the draft with Create and Discard buttons, and Create
+//! queues it as a `swarm:Task` in `REQUIREMENTS`. Drafts wait in the state store for a day.
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::llm::{Caller, Llm};
+use crate::notifications::Message;
+use crate::provenance::Source;
+use crate::sparql;
+use crate::state::TaskDraft;
+use crate::store::Synapse;
+use crate::tasks::{DESCRIPTION, INTERNAL_STATE};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const TASK: &str = "http://swarm.os/ontology/Task";
+const TITLE: &str = "http://swarm.os/ontology/title";
+
+/// Longer titles are cut at a word and keep the full text as description.
+const MAX_TITLE_CHARS: usize = 100;
+const CALLBACK: &str = "draft|";
+
+const SYSTEM_PROMPT: &str = r#"Turn the user's message into a task for a software agent. Answer with one JSON object and nothing else:
+{"title": "<short imperative title>", "description": "<what needs doing, with every detail from the message>"}"#;
+
+/// What a draft's buttons do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Create,
+    Discard,
+}
+
+/// Greetings, answered in kind rather than drafted as tasks.
+pub fn is_greeting(text: &str) -> bool {
+    let words: Vec<String> = text.split_whitespace().map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()).collect();
+    words.len() <= 3 && words.first().is_some_and(|w| matches!(w.as_str(), "hi" | "hello" | "hey" | "hola"))
+}
+
+/// The title and description in `text`, as the LLM reads it or else by its lines.
+pub async fn draft(llm: Option<&Llm>, text: &str) -> (String, Option<String>) {
+    if let Some(llm) = llm {
+        let caller = Caller { agent_id: Some("swarmd".into()), task_id: None };
+        let read = async {
+            let completion = llm.complete(&caller, SYSTEM_PROMPT, text).await?;
+            parse(&completion.text)
+        };
+        match read.await {
+            Ok(draft) => return draft,
+            Err(e) => warn!("Could not draft a task with the LLM, using the message as it is: {:#}", e),
+        }
+    }
+    split(text)
+}
+
+fn parse(reply: &str) -> anyhow::Result<(String, Option<String>)> {
+    #[derive(Deserialize)]
+    struct Reply {
+        title: String,
+        #[serde(default)]
+        description: Option<String>,
+    }
+    let start = reply.find('{').context("the reply holds no JSON object")?;
+    let end = reply.rfind('}').filter(|end| *end > start).context("the reply holds no JSON object")?;
+    let reply: Reply = serde_json::from_str(&reply[start..=end]).context("the reply is not a task")?;
+    let (title, _) = shorten(reply.title.trim());
+    if title.is_empty() {
+        anyhow::bail!("the reply has no title");
+    }
+    Ok((title, reply.description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty())))
+}
+
+/// First line as title, the rest as description.
+fn split(text: &str) -> (String, Option<String>) {
+    let text = text.trim();
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let (title, cut) = shorten(first.trim());
+    let rest = rest.trim();
+    let description = match (cut, rest.is_empty()) {
+        (false, true) => None,
+        (false, false) => Some(rest.to_string()),
+        (true, _) => Some(text.to_string()),
+    };
+    (title, description)
+}
+
+/// `line` cut to [`MAX_TITLE_CHARS`] at a word, and whether it was cut.
+fn shorten(line: &str) -> (String, bool) {
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return (line.trim_end_matches('.').to_string(), false);
+    }
+    let head: String = line.chars().take(MAX_TITLE_CHARS).collect();
+    let head = head.rsplit_once(' ').map_or(head.as_str(), |(words, _)| words).trim_end_matches([',', '.', ';', ':']);
+    (format!("{}…", head), true)
+}
+
+/// The draft as shown for confirmation.
+pub fn proposal(draft: &TaskDraft) -> Message {
+    let msg = Message::new().text("📝 ").strong("New task?").text("\n").strong(&draft.title);
+    match &draft.description {
+        Some(description) => msg.text(format!("\n\n{}", description)),
+        None => msg,
+    }
+}
+
+pub fn keyboard(draft_id: &str) -> Value {
+    json!({
+        "inline_keyboard": [[
+            { "text": "✅ Create", "callback_data": format!("{}create|{}", CALLBACK, draft_id) },
+            { "text": "✖️ Discard", "callback_data": format!("{}discard|{}", CALLBACK, draft_id) }
+        ]]
+    })
+}
+
+pub fn parse_callback(data: &str) -> Option<(Choice, &str)> {
+    let (choice, id) = data.strip_prefix(CALLBACK)?.split_once('|')?;
+    let choice = match choice {
+        "create" => Choice::Create,
+        "discard" => Choice::Discard,
+        _ => return None,
+    };
+    Some((choice, id))
+}
+
+/// Queues `draft` and returns the new task's IRI.
+pub async fn create(synapse: &Synapse, draft: &TaskDraft) -> anyhow::Result<String> {
+    let task_id = format!("http://swarm.os/task/{}", uuid::Uuid::new_v4());
+    let title = sparql::literal(&draft.title);
+    let description = draft.description.as_deref().map(sparql::literal);
+    let mut triples = vec![
+        (task_id.as_str(), RDF_TYPE, TASK),
+        (task_id.as_str(), INTERNAL_STATE, "\"REQUIREMENTS\""),
+        (task_id.as_str(), TITLE, title.as_str()),
+    ];
+    if let Some(description) = &description {
+        triples.push((task_id.as_str(), DESCRIPTION, description.as_str()));
+    }
+    synapse.ingest_from(Source::Telegram, triples).await?;
+    Ok(task_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_become_a_title_and_a_description() {
+        assert_eq!(split("Fix the login page.\n\nIt 500s on Safari."), ("Fix the login page".into(), Some("It 500s on Safari.".into())));
+        assert_eq!(split("  Bump serde  "), ("Bump serde".into(), None));
+        let long = format!("Please {} now", "migrate the billing service ".repeat(5));
+        let (title, description) = split(&long);
+        assert!(title.ends_with("service migrate…") && title.chars().count() <= MAX_TITLE_CHARS + 1, "{title}");
+        assert_eq!(description.as_deref(), Some(long.trim()));
+
+        let reply = "```json\n{\"title\": \"Fix Safari login\", \"description\": \"It 500s.\"}\n```";
+        assert_eq!(parse(reply).unwrap(), ("Fix Safari login".into(), Some("It 500s.".into())));
+        assert!(parse("{\"title\": \" \"}").is_err());
+
+        assert!(is_greeting("Hi there!") && is_greeting("hola") && !is_greeting("this login is broken"));
+        let keyboard = keyboard("0123456789abcdef0123456789abcdef");
+        let data = keyboard["inline_keyboard"][0][0]["callback_data"].as_str().unwrap();
+        assert!(data.len() <= 64);
+        assert_eq!(parse_callback(data), Some((Choice::Create, "0123456789abcdef0123456789abcdef")));
+        assert_eq!(parse_callback("draft|publish|x"), None);
+    }
+}
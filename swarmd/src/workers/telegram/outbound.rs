@@ -0,0 +1,250 @@
+//! Outbound queue of the notification sink. Telegram allows about one message a second per
+//! chat and twenty a minute per group, and answers `429` with a `retry_after` beyond that, so
+//! notifications are sent from one task that paces each chat, waits out every `429` it gets,
+//! and folds the traces raised within `DIGEST_WINDOW` into one digest message, capped to fit
+//! Telegram's message size. Alerts and
+//! approval requests go out as soon as the chat's pace allows, after any digest pending for
+//! that chat, so they never overtake the traces raised before them.
+
+use std::collections::HashMap;
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+use tracing::{error, warn};
+
+use crate::notifications::{ChannelRenderer, Message, Notification, NotificationBody, TelegramMarkdownV2};
+
+/// Traces raised within this long of the first one are sent as one message.
+const DIGEST_WINDOW: Duration = Duration::from_secs(10);
+/// Messages waiting to be sent before new ones are refused.
+const QUEUE_CAPACITY: usize = 256;
+/// Attempts at one message while Telegram keeps answering `429`.
+const MAX_ATTEMPTS: u32 = 5;
+/// Traces a digest lists before summing up the rest as "+N more".
+const MAX_DIGEST_LINES: usize = 30;
+/// Telegram refuses longer messages; measured on the rendered text, escapes included.
+const MAX_MESSAGE_CHARS: usize = 4096;
+/// Kept free in a digest for its "+N more" line.
+const MORE_LINE_CHARS: usize = 32;
+
+struct Outgoing {
+    chat_id: String,
+    message: Message,
+    keyboard: Option<Value>,
+}
+
+enum Queued {
+    Trace { chat_id: String, message: Message },
+    Now(Outgoing),
+}
+
+/// Handle the sink enqueues through; the queue runs until every handle is dropped.
+#[derive(Clone)]
+pub struct OutboundQueue {
+    tx: mpsc::Sender<Queued>,
+}
+
+impl OutboundQueue {
+    /// Starts the task sending through `base_url`.
+    pub fn spawn(base_url: String, client: Client) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run(base_url, client, rx));
+        Self { tx }
+    }
+
+    pub fn push(&self, chat_id: &str, notification: &Notification, keyboard: Option<Value>) -> anyhow::Result<()> {
+        let chat_id = chat_id.to_string();
+        let queued = match &notification.body {
+            NotificationBody::Trace(message) if keyboard.is_none() => Queued::Trace { chat_id, message: message.clone() },
+            _ => Queued::Now(Outgoing { chat_id, message: notification.decorated(), keyboard }),
+        };
+        self.tx.try_send(queued).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => anyhow::anyhow!("Telegram outbound queue is full"),
+            mpsc::error::TrySendError::Closed(_) => anyhow::anyhow!("Telegram outbound queue has stopped"),
+        })
+    }
+}
+
+/// Traces waiting for their chat's window to close.
+struct Digest {
+    due: Instant,
+    traces: Vec<Message>,
+}
+
+async fn run(base_url: String, client: Client, mut rx: mpsc::Receiver<Queued>) {
+    let mut digests: HashMap<String, Digest> = HashMap::new();
+    let mut pace = Pace::default();
+
+    loop {
+        let next_due = digests.values().map(|d| d.due).min();
+        tokio::select! {
+            queued = rx.recv() => match queued {
+                Some(Queued::Trace { chat_id, message }) => {
+                    digests
+                        .entry(chat_id)
+                        .or_insert_with(|| Digest { due: Instant::now() + DIGEST_WINDOW, traces: Vec::new() })
+                        .traces
+                        .push(message);
+                }
+                Some(Queued::Now(outgoing)) => {
+                    if let Some(digest) = digests.remove(&outgoing.chat_id) {
+                        deliver(&base_url, &client, &mut pace, digest_of(&outgoing.chat_id, &digest.traces)).await;
+                    }
+                    deliver(&base_url, &client, &mut pace, outgoing).await;
+                }
+                None => {
+                    for (chat_id, digest) in std::mem::take(&mut digests) {
+                        deliver(&base_url, &client, &mut pace, digest_of(&chat_id, &digest.traces)).await;
+                    }
+                    break;
+                }
+            },
+            _ = sleep_until(next_due.unwrap_or_else(Instant::now)), if next_due.is_some() => {
+                let now = Instant::now();
+                let due: Vec<String> = digests.iter().filter(|(_, d)| d.due <= now).map(|(chat, _)| chat.clone()).collect();
+                for chat_id in due {
+                    if let Some(digest) = digests.remove(&chat_id) {
+                        deliver(&base_url, &client, &mut pace, digest_of(&chat_id, &digest.traces)).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One trace as it is, several as a single message listing them: at most `MAX_DIGEST_LINES`,
+/// and only as many as fit in a Telegram message, with the count of the rest.
+fn digest_of(chat_id: &str, traces: &[Message]) -> Outgoing {
+    let message = match traces {
+        [single] => Notification::trace(single.clone()).decorated(),
+        _ => {
+            let mut digest = Message::new().text("👁️ [TRACE] ").strong(format!("{} updates", traces.len()));
+            let mut listed = 0;
+            for trace in traces.iter().take(MAX_DIGEST_LINES) {
+                let mut longer = digest.clone().text("\n• ");
+                longer.fragments.extend(trace.fragments.iter().cloned());
+                if TelegramMarkdownV2.render_message(&longer).chars().count() + MORE_LINE_CHARS > MAX_MESSAGE_CHARS {
+                    break;
+                }
+                digest = longer;
+                listed += 1;
+            }
+            if listed < traces.len() {
+                digest = digest.text(format!("\n+{} more", traces.len() - listed));
+            }
+            digest
+        }
+    };
+    Outgoing { chat_id: chat_id.to_string(), message, keyboard: None }
+}
+
+/// When each chat may be sent to next.
+#[derive(Default)]
+struct Pace {
+    next: HashMap<String, Instant>,
+}
+
+impl Pace {
+    /// Groups and channels (negative ids) take twenty messages a minute, private chats one a second.
+    fn interval(chat_id: &str) -> Duration {
+        if chat_id.starts_with('-') {
+            Duration::from_secs(3)
+        } else {
+            Duration::from_secs(1)
+        }
+    }
+
+    async fn wait(&mut self, chat_id: &str) {
+        if let Some(next) = self.next.get(chat_id) {
+            sleep_until(*next).await;
+        }
+        self.next.insert(chat_id.to_string(), Instant::now() + Self::interval(chat_id));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Reply {
+    ok: bool,
+    description: Option<String>,
+    parameters: Option<ReplyParameters>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyParameters {
+    retry_after: Option<u64>,
+}
+
+/// How long Telegram asks to wait before trying again, when it refused for flooding.
+fn retry_after(reply: &Reply) -> Option<Duration> {
+    reply.parameters.as_ref().and_then(|p| p.retry_after).map(Duration::from_secs)
+}
+
+async fn deliver(base_url: &str, client: &Client, pace: &mut Pace, outgoing: Outgoing) {
+    let body = super::message_body(&outgoing.chat_id, &outgoing.message, outgoing.keyboard);
+    for attempt in 1..=MAX_ATTEMPTS {
+        pace.wait(&outgoing.chat_id).await;
+        let reply = match client.post(format!("{}/sendMessage", base_url)).json(&body).send().await {
+            Ok(res) => res.json::<Reply>().await,
+            Err(e) => Err(e),
+        };
+        match reply {
+            Ok(reply) if reply.ok => return,
+            Ok(reply) => match retry_after(&reply) {
+                Some(delay) if attempt < MAX_ATTEMPTS => {
+                    warn!("⏳ Telegram rate limit hit for chat {}; retrying in {}s", outgoing.chat_id, delay.as_secs());
+                    sleep(delay).await;
+                }
+                _ => {
+                    let reason = reply.description.unwrap_or_else(|| "no description".to_string());
+                    error!("Failed to deliver notification via telegram: {}", reason);
+                    return;
+                }
+            },
+            Err(e) => {
+                error!("Failed to deliver notification via telegram: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::PlainText;
+
+    #[test]
+    fn traces_fold_into_one_digest() {
+        let one = digest_of("-100", &["Task A started".into()]);
+        assert_eq!(PlainText.render_message(&one.message), "👁️ [TRACE] Task A started");
+
+        let many = digest_of("-100", &["Task A started".into(), Message::new().text("Task ").code("B").text(" done")]);
+        assert_eq!(PlainText.render_message(&many.message), "👁️ [TRACE] 2 updates\n• Task A started\n• Task B done");
+    }
+
+    #[test]
+    fn long_digests_stay_within_a_telegram_message() {
+        let short: Vec<Message> = (0..100).map(|i| Message::from(format!("Task {i} started"))).collect();
+        let text = PlainText.render_message(&digest_of("-100", &short).message);
+        assert_eq!(text.matches("\n• ").count(), MAX_DIGEST_LINES);
+        assert!(text.ends_with("\n• Task 29 started\n+70 more"), "{text}");
+
+        let long: Vec<Message> = (0..10).map(|_| Message::from("x".repeat(1000))).collect();
+        let digest = digest_of("-100", &long);
+        assert!(TelegramMarkdownV2.render_message(&digest.message).chars().count() <= MAX_MESSAGE_CHARS);
+        assert!(PlainText.render_message(&digest.message).ends_with("x\n+6 more"));
+    }
+
+    #[test]
+    fn rate_limited_replies_carry_the_advised_delay() {
+        let reply: Reply = serde_json::from_str(
+            r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 7","parameters":{"retry_after":7}}"#,
+        )
+        .unwrap();
+        assert_eq!(retry_after(&reply), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after(&serde_json::from_str(r#"{"ok":true,"result":{}}"#).unwrap()), None);
+    }
+}
@@ -1,59 +1,174 @@
+mod history;
+mod reconcile;
+
 use reqwest::Client;
 use serde_json::Value;
 use std::time::Duration;
-use tracing::{info, warn};
+use tracing::{info, info_span, warn, Instrument};
 use std::collections::HashSet;
-use tokio::sync::mpsc;
-use crate::notifications::Notification;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
+use crate::config::AppConfig;
+use crate::notifications::{Message, Notification};
+
+use crate::approvals;
+use crate::capabilities::Requirements;
+use crate::health::Health;
+use crate::provenance::Source;
+use crate::state;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks;
+use crate::telemetry;
+
+pub use history::{import as import_history, ImportOptions};
+pub use reconcile::run_nightly as reconcile_nightly;
 
-use crate::synapse::SynapseClient;
+pub const CARD_PREFIX: &str = "http://swarm.os/trello/card/";
 
+/// Lists the poller ingests cards from.
+const INTAKE_LISTS: &[&str] = &["REQUIREMENTS", "DESIGN", "TODO", "INBOX"];
+/// Lists whose cards count as finished.
+const DONE_LISTS: &[&str] = &["DONE", "COMPLETED"];
+
+/// Credentials and board come from `config`; they need a restart to change, the poll
+/// interval and `DISABLED_WORKERS` do not.
 pub async fn poll_trello(
-    api_key: String, 
-    token: String, 
-    board_id: String, 
-    synapse: SynapseClient, 
+    synapse: Synapse, 
     client: Client,
+    health: Arc<Health>,
+    config: watch::Receiver<AppConfig>,
     tx: mpsc::Sender<Notification>
 ) {
+    let (api_key, token, board_id) = {
+        let cfg = config.borrow();
+        let field = |v: &Option<String>| v.clone().unwrap_or_default();
+        (field(&cfg.trello_api_key), field(&cfg.trello_token), field(&cfg.trello_board_id))
+    };
     info!("📋 Trello Poller Started (Board: {})...", board_id);
     let base_url = "https://api.trello.com/1";
-    let mut processed_cards = HashSet::new();
+    let mut processed_cards = load_processed(&synapse).await;
 
     loop {
-        // 1. Fetch Lists for the Board
-        let lists_url = format!("{}/boards/{}/lists?key={}&token={}", base_url, board_id, api_key, token);
-        
-        match client.get(&lists_url).send().await {
-            Ok(res) => {
-                if let Ok(lists) = res.json::<Vec<Value>>().await {
-                    for list in lists {
-                        let list_id = list.get("id").and_then(|id| id.as_str()).unwrap_or("");
-                        let list_name = list.get("name").and_then(|n| n.as_str()).unwrap_or("");
-
-                        // We care about REQUIREMENTS, DESIGN, TODO, INBOX
-                        if ["REQUIREMENTS", "DESIGN", "TODO", "INBOX"].contains(&list_name) {
-                            check_list_cards(list_id, list_name, &api_key, &token, &client, &synapse, &mut processed_cards, &tx).await;
-                        }
+        health.beat("trello");
+        let (enabled, interval) = {
+            let cfg = config.borrow();
+            (cfg.worker_enabled("trello"), Duration::from_secs(cfg.trello_poll_secs.max(1)))
+        };
+        if !enabled {
+            tokio::time::sleep(interval).await;
+            continue;
+        }
+
+        poll_board(base_url, &board_id, &api_key, &token, &client, &synapse, &mut processed_cards, &tx)
+            .instrument(info_span!("trello.poll", board = %board_id))
+            .await;
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// One poll cycle: ingests the new cards of the intake lists.
+#[allow(clippy::too_many_arguments)]
+async fn poll_board(
+    base_url: &str,
+    board_id: &str,
+    api_key: &str,
+    token: &str,
+    client: &Client,
+    synapse: &Synapse,
+    processed_cards: &mut HashSet<String>,
+    tx: &mpsc::Sender<Notification>,
+) {
+    // 1. Fetch Lists for the Board
+    let lists_url = format!("{}/boards/{}/lists?key={}&token={}", base_url, board_id, api_key, token);
+
+    match client.get(&lists_url).send().await {
+        Ok(res) => {
+            if let Ok(lists) = res.json::<Vec<Value>>().await {
+                for list in lists {
+                    let list_id = list.get("id").and_then(|id| id.as_str()).unwrap_or("");
+                    let list_name = list.get("name").and_then(|n| n.as_str()).unwrap_or("");
+
+                    // We care about REQUIREMENTS, DESIGN, TODO, INBOX
+                    if INTAKE_LISTS.contains(&list_name) {
+                        check_list_cards(list_id, list_name, api_key, token, client, synapse, processed_cards, tx).await;
                     }
                 }
             }
-            Err(e) => {
-                warn!("⚠️ Trello API error fetching lists: {}", e);
+        }
+        Err(e) => {
+            warn!("⚠️ Trello API error fetching lists: {}", e);
+        }
+    }
+}
+
+/// State store scope of the ingested cards.
+const SEEN_SCOPE: &str = "trello";
+
+/// Cards already ingested, as `card_id:list` keys, so restarts skip them instead of resetting
+/// tasks the agents already picked up. They live in the state store; on first use it is
+/// seeded from Synapse, where the poller writes the list a card was found in as its
+/// `internalState`: Synapse keeps every value, so the intake lists among a card's states are
+/// the lists it was ingested from.
+async fn load_processed(synapse: &Synapse) -> HashSet<String> {
+    let store = state::shared();
+    let processed = match store.seen(SEEN_SCOPE) {
+        Ok(processed) if !processed.is_empty() => processed,
+        stored => {
+            if let Err(e) = stored {
+                warn!("⚠️ Could not read processed Trello cards from the state store: {}", e);
+            }
+            let processed = load_processed_from_synapse(synapse).await;
+            if let Err(e) = store.mark_seen(SEEN_SCOPE, processed.iter().map(String::as_str)) {
+                warn!("⚠️ Could not save processed Trello cards to the state store: {}", e);
             }
+            processed
         }
+    };
+    if !processed.is_empty() {
+        info!("📋 {} Trello cards already ingested", processed.len());
+    }
+    processed
+}
 
-        tokio::time::sleep(Duration::from_secs(10)).await;
+async fn load_processed_from_synapse(synapse: &Synapse) -> HashSet<String> {
+    let query = r#"
+        PREFIX swarm: <http://swarm.os/ontology/>
+        SELECT ?card ?list WHERE { ?card a swarm:Task ; swarm:internalState ?list }
+    "#;
+    match synapse.query(query).await {
+        Ok(res_json) => processed_keys(&serde_json::from_str::<Vec<Value>>(&res_json).unwrap_or_default()),
+        Err(e) => {
+            warn!("⚠️ Could not restore processed Trello cards, every card will be ingested again: {}", e);
+            HashSet::new()
+        }
     }
 }
 
+fn processed_keys(rows: &[Value]) -> HashSet<String> {
+    let field = |row: &Value, name: &str| {
+        row.get(name)
+            .or_else(|| row.get(format!("?{name}").as_str()))
+            .and_then(Value::as_str)
+            .map(|v| tasks::literal_value(v.trim_matches(|c| c == '<' || c == '>')).to_string())
+    };
+    rows.iter()
+        .filter_map(|row| {
+            let card_id = field(row, "card")?.strip_prefix(CARD_PREFIX)?.to_string();
+            let list = field(row, "list").filter(|l| INTAKE_LISTS.contains(&l.as_str()))?;
+            Some(format!("{}:{}", card_id, list))
+        })
+        .collect()
+}
+
 async fn check_list_cards(
     list_id: &str, 
     list_name: &str, 
     api_key: &str, 
     token: &str, 
     client: &Client, 
-    synapse: &SynapseClient,
+    synapse: &Synapse,
     processed_cards: &mut HashSet<String>,
     tx: &mpsc::Sender<Notification>,
 ) {
@@ -64,6 +179,17 @@ async fn check_list_cards(
             for card in cards {
                 let card_id = card.get("id").and_then(|id| id.as_str()).unwrap_or("");
                 let card_name = card.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                let card_desc = card.get("desc").and_then(|d| d.as_str()).unwrap_or("");
+                let labels: Vec<&str> = card
+                    .get("labels")
+                    .and_then(|l| l.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|l| l.get("name").and_then(|n| n.as_str()))
+                    .collect();
+                let risk_label = labels.iter().find(|name| approvals::is_high_risk_label(name));
+                let priority = tasks::priority_from_labels(labels.iter().copied());
+                let requirements = Requirements::from_labels(labels.iter().copied()).triples();
                 
                 let state_key = format!("{}:{}", card_id, list_name);
                 
@@ -71,19 +197,66 @@ async fn check_list_cards(
                     info!("🔎 Found NEW card '{}' in '{}'", card_name, list_name);
                     
                     // Push to Telegram Live Trace
-                    let _ = tx.send(Notification::Trace(format!("New card in *{}*: {}", list_name, card_name))).await;
+                    let _ = tx.send(Notification::trace(
+                        Message::new().text("New card in ").strong(list_name).text(format!(": {}", card_name)),
+                    ).with_source(Source::Trello)).await;
 
-                    // Ingest to Synapse
-                    let subject = format!("http://swarm.os/trello/card/{}", card_id);
-                    let _ = synapse.ingest(vec![
-                        (&subject, "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task"),
-                        (&subject, "http://swarm.os/ontology/internalState", &format!("\"{}\"", list_name)),
-                        (&subject, "http://swarm.os/ontology/title", &format!("\"{}\"", card_name))
-                    ]).await;
+                    // Ingest to Synapse, under a span the runs of the task will continue
+                    let span = info_span!("trello.card", card = card_id, list = list_name);
+                    let subject = format!("{}{}", CARD_PREFIX, card_id);
+                    let state_lit = sparql::literal(list_name);
+                    let title_lit = sparql::literal(card_name);
+                    let priority_lit = priority.map(tasks::integer_literal);
+                    let desc_lit = Some(card_desc.trim()).filter(|d| !d.is_empty()).map(sparql::literal);
+                    let label_lits: Vec<String> = labels.iter().map(|label| sparql::literal(label)).collect();
+                    let mut triples = vec![
+                        (subject.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task"),
+                        (subject.as_str(), "http://swarm.os/ontology/internalState", state_lit.as_str()),
+                        (subject.as_str(), "http://swarm.os/ontology/title", title_lit.as_str())
+                    ];
+                    if let Some(priority_lit) = &priority_lit {
+                        triples.push((subject.as_str(), tasks::PRIORITY, priority_lit.as_str()));
+                    }
+                    if let Some(desc_lit) = &desc_lit {
+                        triples.push((subject.as_str(), tasks::DESCRIPTION, desc_lit.as_str()));
+                    }
+                    triples.extend(label_lits.iter().map(|label| (subject.as_str(), tasks::LABEL, label.as_str())));
+                    triples.extend(requirements.iter().map(|(predicate, value)| (subject.as_str(), *predicate, value.as_str())));
+                    // High-risk cards wait for a human decision before any agent touches them.
+                    let reason_lit = risk_label.map(|label| sparql::literal(&format!("labelled '{}' in {}", label, list_name)));
+                    if let Some(reason_lit) = &reason_lit {
+                        triples.extend(approvals::flag_triples(&subject, reason_lit));
+                    }
+                    let traceparent = telemetry::traceparent(&span).map(|parent| sparql::literal(&parent));
+                    if let Some(traceparent) = &traceparent {
+                        triples.push((subject.as_str(), telemetry::TRACE_PARENT, traceparent.as_str()));
+                    }
+                    let _ = synapse.ingest_from(Source::Trello, triples).instrument(span).await;
 
+                    if let Err(e) = state::shared().mark_seen(SEEN_SCOPE, [state_key.as_str()]) {
+                        warn!("⚠️ Could not record Trello card {} as ingested: {}", card_id, e);
+                    }
                     processed_cards.insert(state_key);
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn processed_cards_come_from_intake_states() {
+        let rows = vec![
+            json!({"card": format!("<{CARD_PREFIX}abc>"), "list": "\"TODO\""}),
+            json!({"?card": format!("<{CARD_PREFIX}abc>"), "?list": "\"PROCESSING\""}),
+            json!({"card": format!("<{CARD_PREFIX}def>"), "list": "\"DESIGN\""}),
+            json!({"card": "<http://swarm.os/task/local>", "list": "\"TODO\""}),
+        ];
+        let keys = processed_keys(&rows);
+        assert_eq!(keys, HashSet::from(["abc:TODO".to_string(), "def:DESIGN".to_string()]));
+    }
+}
@@ -0,0 +1,302 @@
+//! One-off backfill of finished work from the Trello board (`swarmd import-trello`).
+//!
+//! Every card on the board, archived ones included, is matched with the board's list moves.
+//! Cards that ended up in a done list become `DONE` tasks with their completion time, the
+//! time from leaving the intake lists to reaching done, and an estimated `swarm:SpendEvent`
+//! dated on the completion day. Cards Synapse already knows are left alone, so the import can
+//! be run again safely.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use reqwest::Client;
+use serde_json::Value;
+use tracing::info;
+
+use crate::budget::{self, Spend};
+use crate::clock;
+use crate::provenance::Source;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, INTERNAL_STATE, PRIORITY};
+
+use super::{CARD_PREFIX, DONE_LISTS, INTAKE_LISTS};
+
+/// When the task was written by the importer rather than by the poller.
+pub const IMPORTED_AT: &str = "http://swarm.os/ontology/importedAt";
+/// `"true"` on spend events that are estimates rather than reported costs.
+pub const ESTIMATED: &str = "http://swarm.os/ontology/estimated";
+
+/// Actions per page, Trello's maximum.
+const ACTION_PAGE: usize = 1000;
+/// Pages of board actions read at most; older moves fall back to card dates.
+const MAX_ACTION_PAGES: usize = 50;
+/// Cards written per Synapse ingest.
+const BATCH: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Only cards completed on or after this local date.
+    pub since: Option<NaiveDate>,
+    /// Spend recorded for each imported task; zero records none.
+    pub usd_per_task: f64,
+    /// Report what would be imported without writing anything.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Completed cards Synapse already had.
+    pub known: usize,
+    /// Cards not in a done list, or completed before `since`.
+    pub skipped: usize,
+    pub estimated_usd: f64,
+}
+
+/// What the board says about one finished card.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletedCard {
+    pub id: String,
+    pub title: String,
+    pub priority: Option<i64>,
+    pub started: DateTime<Utc>,
+    pub completed: DateTime<Utc>,
+}
+
+impl CompletedCard {
+    pub fn duration_seconds(&self) -> f64 {
+        (self.completed - self.started).num_milliseconds().max(0) as f64 / 1000.0
+    }
+}
+
+struct ListMove<'a> {
+    at: DateTime<Utc>,
+    from: Option<&'a str>,
+    to: &'a str,
+}
+
+/// Finished cards among `cards`, given the board's `lists` and its `createCard` and
+/// `updateCard:idList` actions. Work starts on the first move out of an intake list (or at
+/// creation) and ends on the last move into a done list (or the card's last activity).
+pub fn completed_cards(cards: &[Value], lists: &[Value], actions: &[Value]) -> Vec<CompletedCard> {
+    let list_names: HashMap<&str, &str> = lists
+        .iter()
+        .filter_map(|l| Some((l.get("id")?.as_str()?, l.get("name")?.as_str()?)))
+        .collect();
+
+    let mut created: HashMap<&str, DateTime<Utc>> = HashMap::new();
+    let mut moves: HashMap<&str, Vec<ListMove>> = HashMap::new();
+    for action in actions {
+        let Some(card) = action.pointer("/data/card/id").and_then(Value::as_str) else { continue };
+        let Some(at) = action.get("date").and_then(Value::as_str).and_then(parse_date) else { continue };
+        match action.get("type").and_then(Value::as_str) {
+            Some("createCard") => {
+                created.insert(card, at);
+            }
+            Some("updateCard") => {
+                let Some(to) = action.pointer("/data/listAfter/name").and_then(Value::as_str) else { continue };
+                let from = action.pointer("/data/listBefore/name").and_then(Value::as_str);
+                moves.entry(card).or_default().push(ListMove { at, from, to });
+            }
+            _ => {}
+        }
+    }
+
+    let mut completed = Vec::new();
+    for card in cards {
+        let Some(id) = card.get("id").and_then(Value::as_str) else { continue };
+        let list = card.get("idList").and_then(Value::as_str).and_then(|l| list_names.get(l));
+        if !list.is_some_and(|l| DONE_LISTS.contains(l)) {
+            continue;
+        }
+        let mut card_moves = moves.remove(id).unwrap_or_default();
+        card_moves.sort_by_key(|m| m.at);
+
+        let last_activity = card.get("dateLastActivity").and_then(Value::as_str).and_then(parse_date);
+        let Some(done_at) = card_moves.iter().rev().find(|m| DONE_LISTS.contains(&m.to)).map(|m| m.at).or(last_activity) else {
+            continue;
+        };
+        let started = card_moves
+            .iter()
+            .find(|m| m.from.is_some_and(|from| INTAKE_LISTS.contains(&from)))
+            .map(|m| m.at)
+            .or_else(|| created.get(id).copied())
+            .or_else(|| created_from_id(id))
+            .unwrap_or(done_at)
+            .min(done_at);
+
+        let labels = card
+            .get("labels")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|l| l.get("name").and_then(Value::as_str));
+        completed.push(CompletedCard {
+            id: id.to_string(),
+            title: card.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+            priority: tasks::priority_from_labels(labels),
+            started,
+            completed: done_at,
+        });
+    }
+    completed
+}
+
+fn parse_date(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw).ok().map(|at| at.with_timezone(&Utc))
+}
+
+/// Trello ids are Mongo object ids: the first eight hex digits are the creation time.
+fn created_from_id(id: &str) -> Option<DateTime<Utc>> {
+    let seconds = i64::from_str_radix(id.get(..8)?, 16).ok()?;
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+pub async fn import(
+    api_key: &str,
+    token: &str,
+    board_id: &str,
+    synapse: &Synapse,
+    client: &Client,
+    options: &ImportOptions,
+) -> anyhow::Result<ImportSummary> {
+    let base_url = "https://api.trello.com/1";
+    let auth = format!("key={}&token={}", api_key, token);
+    let lists = get(client, format!("{}/boards/{}/lists?filter=all&fields=id,name&{}", base_url, board_id, auth)).await?;
+    let cards = get(
+        client,
+        format!("{}/boards/{}/cards/all?fields=id,name,idList,labels,dateLastActivity&{}", base_url, board_id, auth),
+    )
+    .await?;
+
+    let mut actions = Vec::new();
+    for _ in 0..MAX_ACTION_PAGES {
+        let before = actions.last().and_then(|a: &Value| a.get("id")).and_then(Value::as_str);
+        let url = format!(
+            "{}/boards/{}/actions?filter=createCard,updateCard:idList&limit={}{}&{}",
+            base_url,
+            board_id,
+            ACTION_PAGE,
+            before.map(|id| format!("&before={}", id)).unwrap_or_default(),
+            auth
+        );
+        let page = get(client, url).await?;
+        let full = page.len() == ACTION_PAGE;
+        actions.extend(page);
+        if !full {
+            break;
+        }
+    }
+    info!("📥 Trello history: {} cards, {} lists, {} actions", cards.len(), lists.len(), actions.len());
+
+    let finished = completed_cards(&cards, &lists, &actions);
+    let known: HashSet<String> = tasks::all_tasks(synapse).await?.into_iter().map(|t| t.id).collect();
+
+    let mut summary = ImportSummary { skipped: cards.len() - finished.len(), ..Default::default() };
+    let mut pending = Vec::new();
+    for card in finished {
+        if options.since.is_some_and(|since| clock::date_of(card.completed) < since) {
+            summary.skipped += 1;
+        } else if known.contains(&format!("{}{}", CARD_PREFIX, card.id)) {
+            summary.known += 1;
+        } else {
+            pending.push(card);
+        }
+    }
+    summary.imported = pending.len();
+    summary.estimated_usd = options.usd_per_task.max(0.0) * pending.len() as f64;
+    if options.dry_run {
+        return Ok(summary);
+    }
+
+    let now = tasks::datetime_literal(&Utc::now());
+    for batch in pending.chunks(BATCH) {
+        let statements: Vec<(String, &str, String)> =
+            batch.iter().flat_map(|card| card_triples(card, options.usd_per_task, &now)).collect();
+        synapse
+            .ingest_from(Source::Trello, statements.iter().map(|(s, p, o)| (s.as_str(), *p, o.as_str())).collect())
+            .await?;
+        info!("📥 Imported {} completed cards", batch.len());
+    }
+    Ok(summary)
+}
+
+async fn get(client: &Client, url: String) -> anyhow::Result<Vec<Value>> {
+    Ok(client.get(url).send().await?.error_for_status()?.json().await?)
+}
+
+fn card_triples(card: &CompletedCard, usd_per_task: f64, imported_at: &str) -> Vec<(String, &'static str, String)> {
+    let subject = format!("{}{}", CARD_PREFIX, card.id);
+    let mut triples = vec![
+        (subject.clone(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://swarm.os/ontology/Task".to_string()),
+        (subject.clone(), "http://swarm.os/ontology/title", sparql::literal(&card.title)),
+        (subject.clone(), INTERNAL_STATE, "\"DONE\"".to_string()),
+        (subject.clone(), "http://swarm.os/ontology/completedAt", tasks::datetime_literal(&card.completed)),
+        (subject.clone(), "http://swarm.os/ontology/durationSeconds", tasks::decimal_literal(card.duration_seconds())),
+        (subject.clone(), IMPORTED_AT, imported_at.to_string()),
+    ];
+    if let Some(priority) = card.priority {
+        triples.push((subject.clone(), PRIORITY, tasks::integer_literal(priority)));
+    }
+    if usd_per_task > 0.0 {
+        let spend = Spend {
+            amount_usd: usd_per_task,
+            agent_id: None,
+            task_id: Some(subject),
+            repository: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            model: None,
+        };
+        let (event_id, properties) = budget::spend_event(&spend, card.completed);
+        triples.extend(properties.into_iter().map(|(p, o)| (event_id.clone(), p, o)));
+        triples.push((event_id, ESTIMATED, "\"true\"".to_string()));
+    }
+    triples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn completed_cards_take_their_dates_from_list_moves() {
+        let lists = vec![
+            json!({"id": "l-todo", "name": "TODO"}),
+            json!({"id": "l-doing", "name": "IN PROGRESS"}),
+            json!({"id": "l-done", "name": "DONE"}),
+        ];
+        let cards = vec![
+            json!({"id": "moved", "name": "Fix login", "idList": "l-done", "labels": [{"name": "urgent"}]}),
+            // No actions left for it: created from the id, completed at the last activity.
+            json!({"id": "6500000000000000000000aa", "name": "Old card", "idList": "l-done",
+                   "dateLastActivity": "2023-09-13T00:00:00.000Z"}),
+            json!({"id": "open", "name": "Still going", "idList": "l-doing"}),
+        ];
+        let actions = vec![
+            json!({"type": "updateCard", "date": "2026-10-03T12:00:00.000Z", "data": {"card": {"id": "moved"},
+                   "listBefore": {"name": "IN PROGRESS"}, "listAfter": {"name": "DONE"}}}),
+            json!({"type": "updateCard", "date": "2026-10-02T09:00:00.000Z", "data": {"card": {"id": "moved"},
+                   "listBefore": {"name": "TODO"}, "listAfter": {"name": "IN PROGRESS"}}}),
+            json!({"type": "createCard", "date": "2026-10-01T08:00:00.000Z", "data": {"card": {"id": "moved"}}}),
+            json!({"type": "updateCard", "date": "2026-10-04T08:00:00.000Z", "data": {"card": {"id": "open"},
+                   "listBefore": {"name": "TODO"}, "listAfter": {"name": "IN PROGRESS"}}}),
+        ];
+
+        let done = completed_cards(&cards, &lists, &actions);
+        assert_eq!(done.len(), 2);
+        assert_eq!(done[0].id, "moved");
+        assert_eq!(done[0].started, parse_date("2026-10-02T09:00:00Z").unwrap());
+        assert_eq!(done[0].duration_seconds(), 27.0 * 3600.0);
+        assert_eq!(done[0].priority, tasks::priority_from_labels(["urgent"]));
+        assert_eq!(done[1].started, Utc.timestamp_opt(0x65000000, 0).unwrap());
+        assert_eq!(done[1].completed, parse_date("2023-09-13T00:00:00Z").unwrap());
+
+        let triples = card_triples(&done[0], 0.5, "\"now\"");
+        assert!(triples.iter().any(|(_, p, o)| *p == ESTIMATED && o == "\"true\""));
+        assert!(triples.iter().any(|(_, p, o)| *p == "http://swarm.os/ontology/date" && o == "\"2026-10-03\""));
+        assert_eq!(card_triples(&done[1], 0.0, "\"now\"").len(), 6);
+    }
+}
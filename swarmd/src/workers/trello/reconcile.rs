@@ -0,0 +1,258 @@
+//! Nightly consistency check between the Trello board and the tasks Synapse derived from it.
+//!
+//! Cards deleted, archived or moved in Trello never reach the poller again, so their tasks
+//! would otherwise sit in Synapse forever. Safe cases are fixed in place; anything that
+//! might interrupt running work is only flagged, and every run leaves a
+//! `swarm:ReconciliationReport` behind for the daily digest.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::clock;
+use crate::notifications::{Message, Notification};
+use crate::provenance::Source;
+use crate::sparql;
+use crate::store::Synapse;
+use crate::tasks::{self, TaskState, TaskSummary, INTERNAL_STATE, TERMINAL_STATES};
+
+use super::{CARD_PREFIX, DONE_LISTS, INTAKE_LISTS};
+
+pub const REPORT: &str = "http://swarm.os/ontology/ReconciliationReport";
+pub const REPORT_DATE: &str = "http://swarm.os/ontology/reportDate";
+pub const FIXED_COUNT: &str = "http://swarm.os/ontology/fixedCount";
+pub const FLAGGED_COUNT: &str = "http://swarm.os/ontology/flaggedCount";
+pub const FLAGGED: &str = "http://swarm.os/ontology/flagged";
+
+/// States that mean an agent has picked the task up at least once.
+const STARTED_STATES: &[&str] = &["PROCESSING", "ESCALATED", "AWAITING_MERGE", "RETRY_SCHEDULED", "TIMED_OUT", "STALLED", "FAILED"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// Safe to correct without a human: the task is moved to `state`.
+    Fix { task: String, state: TaskState, reason: String },
+    /// Needs a human decision; reported but left untouched.
+    Flag { task: String, reason: String },
+}
+
+/// Compares the open cards on the board (card id → list name) with the Trello-sourced tasks.
+pub fn reconcile(board: &HashMap<String, String>, tasks: &[TaskSummary]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for task in tasks {
+        let Some(card_id) = task.id.strip_prefix(CARD_PREFIX) else { continue };
+        let state = task.state.as_str();
+        let terminal = TERMINAL_STATES.contains(&state);
+        let started = task.agent.is_some() || STARTED_STATES.contains(&state);
+
+        match board.get(card_id).map(String::as_str) {
+            None if terminal => {}
+            None if !started => findings.push(Finding::Fix {
+                task: task.id.clone(),
+                state: TaskState::Cancelled,
+                reason: "card deleted or archived before work started".to_string(),
+            }),
+            None => findings.push(Finding::Flag {
+                task: task.id.clone(),
+                reason: format!("card deleted or archived while the task is {}", state),
+            }),
+            Some(list) if DONE_LISTS.contains(&list) && !terminal => {
+                if state == "PROCESSING" {
+                    findings.push(Finding::Flag {
+                        task: task.id.clone(),
+                        reason: format!("card moved to {} while an agent is still working on it", list),
+                    });
+                } else {
+                    findings.push(Finding::Fix {
+                        task: task.id.clone(),
+                        state: TaskState::Done,
+                        reason: format!("card moved to {}", list),
+                    });
+                }
+            }
+            Some(list) if state == "DONE" && INTAKE_LISTS.contains(&list) => findings.push(Finding::Flag {
+                task: task.id.clone(),
+                reason: format!("task is DONE but the card is back in {}", list),
+            }),
+            Some(_) => {}
+        }
+    }
+    findings
+}
+
+/// Time left until the next `hour`:00 in `tz`, never zero so a run cannot repeat immediately.
+pub fn until_next(hour: u32, tz: Tz, now: DateTime<Utc>) -> Duration {
+    let next = clock::next_local_hour(tz, hour, now);
+    (next - now).to_std().unwrap_or(Duration::from_secs(1))
+}
+
+pub async fn run_nightly(
+    hour: u32,
+    api_key: String,
+    token: String,
+    board_id: String,
+    synapse: Synapse,
+    client: Client,
+    tx: mpsc::Sender<Notification>,
+) {
+    info!("🧮 Trello reconciliation scheduled daily at {:02}:00 {}", hour, clock::tz());
+    loop {
+        tokio::time::sleep(until_next(hour, clock::tz(), Utc::now())).await;
+        if let Err(e) = run_once(&api_key, &token, &board_id, &synapse, &client, &tx).await {
+            warn!("⚠️ Trello reconciliation failed: {}", e);
+        }
+    }
+}
+
+async fn run_once(
+    api_key: &str,
+    token: &str,
+    board_id: &str,
+    synapse: &Synapse,
+    client: &Client,
+    tx: &mpsc::Sender<Notification>,
+) -> anyhow::Result<()> {
+    let board = fetch_board(api_key, token, board_id, client).await?;
+    let tasks = tasks::all_tasks(synapse).await?;
+    let findings = reconcile(&board, &tasks);
+
+    let mut fixed = 0;
+    let mut flagged = Vec::new();
+    for finding in &findings {
+        match finding {
+            Finding::Fix { task, state, reason } => {
+                info!("🧮 {} → {} ({})", task, state.as_str(), reason);
+                synapse
+                    .ingest_from(Source::Trello, vec![(task.as_str(), INTERNAL_STATE, state.literal().as_str())])
+                    .await?;
+                fixed += 1;
+            }
+            Finding::Flag { task, reason } => flagged.push(format!("{}: {}", task, reason)),
+        }
+    }
+
+    store_report(synapse, fixed, &flagged).await?;
+    info!("🧮 Trello reconciliation: {} fixed, {} flagged", fixed, flagged.len());
+
+    if !flagged.is_empty() {
+        let mut msg = Message::new()
+            .text("Trello reconciliation: ")
+            .strong(format!("{} fixed, {} need attention", fixed, flagged.len()));
+        for line in &flagged {
+            msg = msg.text("\n• ").text(line.clone());
+        }
+        let _ = tx.send(Notification::alert(msg).with_source(Source::Trello)).await;
+    }
+    Ok(())
+}
+
+/// Open cards on the board, keyed by card id, with the name of the list they sit in.
+async fn fetch_board(api_key: &str, token: &str, board_id: &str, client: &Client) -> anyhow::Result<HashMap<String, String>> {
+    let base_url = "https://api.trello.com/1";
+    let lists: Vec<Value> = client
+        .get(format!("{}/boards/{}/lists?fields=id,name&key={}&token={}", base_url, board_id, api_key, token))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let cards: Vec<Value> = client
+        .get(format!("{}/boards/{}/cards?fields=id,idList&key={}&token={}", base_url, board_id, api_key, token))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let list_names: HashMap<&str, &str> = lists
+        .iter()
+        .filter_map(|l| Some((l.get("id")?.as_str()?, l.get("name")?.as_str()?)))
+        .collect();
+    Ok(cards
+        .iter()
+        .filter_map(|c| {
+            let id = c.get("id")?.as_str()?;
+            let list = list_names.get(c.get("idList")?.as_str()?)?;
+            Some((id.to_string(), list.to_string()))
+        })
+        .collect())
+}
+
+async fn store_report(synapse: &Synapse, fixed: usize, flagged: &[String]) -> anyhow::Result<()> {
+    let date = clock::today().format("%Y-%m-%d").to_string();
+    let subject = format!("http://swarm.os/reconciliation/{}", date);
+    let date_lit = format!("\"{}\"", date);
+    let fixed_lit = tasks::integer_literal(fixed as i64);
+    let flagged_lit = tasks::integer_literal(flagged.len() as i64);
+    let flagged_lits: Vec<String> = flagged.iter().map(|f| sparql::literal(f)).collect();
+
+    let mut triples = vec![
+        (subject.as_str(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", REPORT),
+        (subject.as_str(), REPORT_DATE, date_lit.as_str()),
+        (subject.as_str(), FIXED_COUNT, fixed_lit.as_str()),
+        (subject.as_str(), FLAGGED_COUNT, flagged_lit.as_str()),
+    ];
+    triples.extend(flagged_lits.iter().map(|f| (subject.as_str(), FLAGGED, f.as_str())));
+    synapse.ingest_from(Source::Trello, triples).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(card: &str, state: &str, agent: Option<&str>) -> TaskSummary {
+        TaskSummary {
+            id: format!("{CARD_PREFIX}{card}"),
+            title: card.to_string(),
+            state: state.to_string(),
+            agent: agent.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn fixes_safe_cases_and_flags_the_rest() {
+        let board = HashMap::from([
+            ("moved".to_string(), "DONE".to_string()),
+            ("busy".to_string(), "DONE".to_string()),
+            ("reopened".to_string(), "TODO".to_string()),
+            ("fine".to_string(), "TODO".to_string()),
+        ]);
+        let tasks = vec![
+            task("gone", "TODO", None),
+            task("gone-running", "PROCESSING", Some("coder")),
+            task("gone-done", "DONE", Some("coder")),
+            task("moved", "TODO", None),
+            task("busy", "PROCESSING", Some("coder")),
+            task("reopened", "DONE", Some("coder")),
+            task("fine", "TODO", None),
+            TaskSummary { id: "http://swarm.os/task/local".into(), title: "x".into(), state: "TODO".into(), agent: None },
+        ];
+
+        let summary: Vec<_> = reconcile(&board, &tasks)
+            .into_iter()
+            .map(|f| match f {
+                Finding::Fix { task, state, .. } => format!("fix {} {}", task.trim_start_matches(CARD_PREFIX), state.as_str()),
+                Finding::Flag { task, .. } => format!("flag {}", task.trim_start_matches(CARD_PREFIX)),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec!["fix gone CANCELLED", "flag gone-running", "fix moved DONE", "flag busy", "flag reopened"]
+        );
+    }
+
+    #[test]
+    fn next_run_is_always_in_the_future() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        assert_eq!(until_next(3, Tz::UTC, at("2026-10-17T01:30:00Z")), Duration::from_secs(90 * 60));
+        assert_eq!(until_next(3, Tz::UTC, at("2026-10-17T03:00:00Z")), Duration::from_secs(24 * 3600));
+        assert_eq!(until_next(3, Tz::UTC, at("2026-10-17T22:00:00Z")), Duration::from_secs(5 * 3600));
+        assert_eq!(until_next(3, Tz::Europe__Madrid, at("2026-10-17T00:30:00Z")), Duration::from_secs(30 * 60));
+    }
+}